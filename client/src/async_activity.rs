@@ -0,0 +1,197 @@
+use crate::{Result, WorkflowClientTrait};
+use temporal_sdk_core_protos::{
+    temporal::api::{common::v1::Payloads, failure::v1::Failure},
+    TaskToken,
+};
+
+/// Identifies an activity which is being completed asynchronously, meaning by something other than
+/// the worker which received the activity task (ex: an external system the activity handed its
+/// work off to).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityIdentifier {
+    /// The task token the worker received when it was given the activity task
+    TaskToken(TaskToken),
+    /// The ids of the activity and the workflow which scheduled it
+    Id {
+        /// Id of the workflow which scheduled the activity
+        workflow_id: String,
+        /// Run id of the workflow which scheduled the activity. May be empty, in which case the
+        /// latest run of the workflow is targeted.
+        run_id: String,
+        /// Id of the activity
+        activity_id: String,
+    },
+}
+
+impl ActivityIdentifier {
+    /// Identify an activity by the ids of the workflow which scheduled it and the activity itself
+    pub fn by_id(
+        workflow_id: impl Into<String>,
+        run_id: impl Into<String>,
+        activity_id: impl Into<String>,
+    ) -> Self {
+        Self::Id {
+            workflow_id: workflow_id.into(),
+            run_id: run_id.into(),
+            activity_id: activity_id.into(),
+        }
+    }
+}
+
+impl From<TaskToken> for ActivityIdentifier {
+    fn from(tt: TaskToken) -> Self {
+        Self::TaskToken(tt)
+    }
+}
+
+/// What the server said in response to a heartbeat of an asynchronously completed activity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsyncActivityHeartbeat {
+    /// Cancellation of the activity has been requested, so it should report itself cancelled with
+    /// [AsyncActivityHandle::report_cancelled] once it has stopped
+    pub cancel_requested: bool,
+    /// The activity has been paused, so it should stop and fail the attempt, to be retried once
+    /// it's unpaused
+    pub activity_paused: bool,
+}
+
+/// A handle to an activity which is being completed asynchronously, through which its progress and
+/// outcome are reported
+#[derive(Debug, Clone)]
+pub struct AsyncActivityHandle<CT> {
+    client: CT,
+    identifier: ActivityIdentifier,
+}
+
+impl<CT> AsyncActivityHandle<CT>
+where
+    CT: WorkflowClientTrait + Sync,
+{
+    /// Create a handle to the identified activity, which reports to the server with `client`
+    pub fn new(client: CT, identifier: impl Into<ActivityIdentifier>) -> Self {
+        Self {
+            client,
+            identifier: identifier.into(),
+        }
+    }
+
+    /// The activity this handle reports on
+    pub fn identifier(&self) -> &ActivityIdentifier {
+        &self.identifier
+    }
+
+    /// Complete the activity with the provided result
+    pub async fn complete(&self, result: Option<Payloads>) -> Result<()> {
+        self.client
+            .complete_async_activity(self.identifier.clone(), result)
+            .await
+    }
+
+    /// Fail the activity. It's retried according to its retry policy, unless the failure is
+    /// non-retryable.
+    pub async fn fail(
+        &self,
+        failure: Failure,
+        last_heartbeat_details: Option<Payloads>,
+    ) -> Result<()> {
+        self.client
+            .fail_async_activity(self.identifier.clone(), failure, last_heartbeat_details)
+            .await
+    }
+
+    /// Report the activity cancelled. Should only be called after a heartbeat said cancellation
+    /// was requested.
+    pub async fn report_cancelled(&self, details: Option<Payloads>) -> Result<()> {
+        self.client
+            .cancel_async_activity(self.identifier.clone(), details)
+            .await
+    }
+
+    /// Record a heartbeat for the activity
+    pub async fn heartbeat(&self, details: Option<Payloads>) -> Result<AsyncActivityHeartbeat> {
+        self.client
+            .heartbeat_async_activity(self.identifier.clone(), details)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockWorkflowClientTrait;
+    use temporal_sdk_core_protos::temporal::api::common::v1::Payload;
+
+    fn details() -> Option<Payloads> {
+        Some(Payloads {
+            payloads: vec![Payload::from(b"progress")],
+        })
+    }
+
+    #[tokio::test]
+    async fn handle_reports_for_its_activity() {
+        let by_token = ActivityIdentifier::from(TaskToken(vec![1, 2, 3]));
+        let by_id = ActivityIdentifier::by_id("wid", "", "act");
+        let mut token_client = MockWorkflowClientTrait::new();
+        token_client
+            .expect_complete_async_activity()
+            .withf(|a, r| {
+                *a == ActivityIdentifier::TaskToken(TaskToken(vec![1, 2, 3])) && *r == details()
+            })
+            .returning(|_, _| Ok(()))
+            .times(1);
+        let mut client = MockWorkflowClientTrait::new();
+        client
+            .expect_fail_async_activity()
+            .withf(|a, f, d| {
+                *a == ActivityIdentifier::by_id("wid", "", "act")
+                    && f.message == "boom"
+                    && d.is_none()
+            })
+            .returning(|_, _, _| Ok(()))
+            .times(1);
+        client
+            .expect_cancel_async_activity()
+            .withf(|a, d| *a == ActivityIdentifier::by_id("wid", "", "act") && *d == details())
+            .returning(|_, _| Ok(()))
+            .times(1);
+
+        AsyncActivityHandle::new(token_client, by_token)
+            .complete(details())
+            .await
+            .unwrap();
+        let handle = AsyncActivityHandle::new(client, by_id.clone());
+        assert_eq!(handle.identifier(), &by_id);
+        handle
+            .fail(
+                Failure {
+                    message: "boom".to_string(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        handle.report_cancelled(details()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn heartbeat_says_whether_activity_should_stop() {
+        let mut client = MockWorkflowClientTrait::new();
+        client
+            .expect_heartbeat_async_activity()
+            .withf(|a, d| *a == ActivityIdentifier::by_id("wid", "rid", "act") && *d == details())
+            .returning(|_, _| {
+                Ok(AsyncActivityHeartbeat {
+                    cancel_requested: false,
+                    activity_paused: true,
+                })
+            })
+            .times(1);
+
+        let handle =
+            AsyncActivityHandle::new(client, ActivityIdentifier::by_id("wid", "rid", "act"));
+        let hb = handle.heartbeat(details()).await.unwrap();
+        assert!(hb.activity_paused);
+        assert!(!hb.cancel_requested);
+    }
+}
@@ -0,0 +1,369 @@
+//! Client side load balancing across every address the server's host name resolves to, for
+//! frontends behind something like a Kubernetes headless service. Without it, all calls (long
+//! polls included) share the one connection made to whichever address was resolved first.
+
+use crate::{ClientInitError, ClientOptions, ClientTlsConfig, TlsConfig};
+use parking_lot::RwLock;
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::mpsc::Sender, task::JoinHandle};
+use tonic::{
+    body::BoxBody,
+    transport::{channel::ResponseFuture, Channel, Endpoint},
+};
+use tower::{discover::Change, Service};
+use url::Url;
+
+/// How calls are spread across the addresses the server's host name resolves to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadBalancingPolicy {
+    /// Each call goes to whichever of two randomly picked addresses has fewer calls in flight
+    LeastLoaded,
+    /// Calls go to each address in turn
+    RoundRobin,
+}
+
+/// Configures client side load balancing. Connections to each address are only made once calls
+/// are sent to it. Not supported through a proxy, where it is ignored.
+#[derive(Clone, Debug)]
+pub struct LoadBalancingOptions {
+    /// How calls are spread across addresses
+    pub policy: LoadBalancingPolicy,
+    /// How often to resolve the host name again, to pick up replicas which were added or removed.
+    /// Must be nonzero.
+    pub dns_refresh_interval: Duration,
+}
+
+/// The channel calls are sent over, which may spread them across several connections
+#[derive(Clone, Debug)]
+pub(crate) enum FrontendChannel {
+    /// A single channel, which may itself be balanced
+    Single(Channel),
+    RoundRobin(RoundRobinChannel),
+}
+
+impl From<Channel> for FrontendChannel {
+    fn from(c: Channel) -> Self {
+        FrontendChannel::Single(c)
+    }
+}
+
+impl Service<http::Request<BoxBody>> for FrontendChannel {
+    type Response = <Channel as Service<http::Request<BoxBody>>>::Response;
+    type Error = <Channel as Service<http::Request<BoxBody>>>::Error;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self {
+            FrontendChannel::Single(c) => c.poll_ready(cx),
+            FrontendChannel::RoundRobin(c) => c.poll_ready(cx),
+        }
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        match self {
+            FrontendChannel::Single(c) => c.call(req),
+            FrontendChannel::RoundRobin(c) => c.call(req),
+        }
+    }
+}
+
+/// Sends each call to the next of a set of channels, one per address, which is kept up to date by
+/// a background task
+#[derive(Clone, Debug)]
+pub(crate) struct RoundRobinChannel {
+    channels: Arc<RwLock<Vec<Channel>>>,
+    next: Arc<AtomicUsize>,
+    /// The channel picked for the next call, once it has been polled ready
+    picked: Option<Channel>,
+}
+
+impl Service<http::Request<BoxBody>> for RoundRobinChannel {
+    type Response = <Channel as Service<http::Request<BoxBody>>>::Response;
+    type Error = <Channel as Service<http::Request<BoxBody>>>::Error;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let picked = self.picked.get_or_insert_with(|| {
+            let channels = self.channels.read();
+            // Never empty, since resolutions which find no addresses are ignored
+            let idx = self.next.fetch_add(1, Ordering::Relaxed) % channels.len();
+            channels[idx].clone()
+        });
+        picked.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        self.picked
+            .take()
+            .expect("poll_ready must be called before call")
+            .call(req)
+    }
+}
+
+/// Connects a channel which balances calls across every address the target's host name resolves
+/// to, as configured
+pub(crate) async fn connect_balanced(
+    opts: &ClientOptions,
+    lb: &LoadBalancingOptions,
+    client_tls: Option<&ClientTlsConfig>,
+) -> Result<FrontendChannel, ClientInitError> {
+    let addrs = resolve(&opts.target_url).await?;
+    if addrs.is_empty() {
+        return Err(ClientInitError::DnsResolutionError(format!(
+            "{} resolved to no addresses",
+            opts.target_url
+        )));
+    }
+    let mut endpoints = Vec::with_capacity(addrs.len());
+    for addr in addrs.iter() {
+        endpoints.push((*addr, address_endpoint(opts, *addr, client_tls).await?));
+    }
+    let resolver = Resolver {
+        opts: opts.clone(),
+        client_tls: client_tls.cloned(),
+        interval: lb.dns_refresh_interval,
+        addrs,
+    };
+    Ok(match lb.policy {
+        LoadBalancingPolicy::LeastLoaded => {
+            let (channel, tx) = Channel::balance_channel(endpoints.len().max(16));
+            for (addr, endpoint) in endpoints {
+                let _ = tx.send(Change::Insert(addr, endpoint)).await;
+            }
+            resolver.spawn_least_loaded(tx);
+            FrontendChannel::Single(channel)
+        }
+        LoadBalancingPolicy::RoundRobin => {
+            let channels = Arc::new(RwLock::new(
+                endpoints
+                    .into_iter()
+                    .map(|(_, e)| e.connect_lazy())
+                    .collect::<Vec<_>>(),
+            ));
+            resolver.spawn_round_robin(Arc::downgrade(&channels));
+            FrontendChannel::RoundRobin(RoundRobinChannel {
+                channels,
+                next: Arc::new(AtomicUsize::new(0)),
+                picked: None,
+            })
+        }
+    })
+}
+
+async fn resolve(url: &Url) -> Result<HashSet<SocketAddr>, ClientInitError> {
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(7233);
+    tokio::net::lookup_host((host, port))
+        .await
+        .map(Iterator::collect)
+        .map_err(|e| ClientInitError::DnsResolutionError(format!("Couldn't resolve {host}: {e}")))
+}
+
+/// An endpoint connecting to one address of the target. TLS still verifies, and the
+/// `:authority` header still carries, the target's host name.
+async fn address_endpoint(
+    opts: &ClientOptions,
+    addr: SocketAddr,
+    client_tls: Option<&ClientTlsConfig>,
+) -> Result<Endpoint, ClientInitError> {
+    let host = opts.target_url.host_str().unwrap_or_default().to_string();
+    let mut addr_opts = opts.clone();
+    addr_opts
+        .target_url
+        .set_ip_host(addr.ip())
+        .expect("URLs with a host can have an IP host");
+    addr_opts.target_url.set_port(Some(addr.port())).ok();
+    if addr_opts.override_origin.is_none() {
+        addr_opts.override_origin = Some(opts.target_url.as_str().parse()?);
+    }
+    let uses_tls =
+        opts.tls_cfg.is_some() || client_tls.is_some() || opts.target_url.scheme() == "https";
+    if uses_tls {
+        let tls_cfg = addr_opts.tls_cfg.get_or_insert_with(TlsConfig::default);
+        tls_cfg.domain.get_or_insert(host);
+    }
+    addr_opts.build_endpoint(client_tls).await
+}
+
+/// Resolves the target's host name periodically, applying changes to the addresses
+struct Resolver {
+    opts: ClientOptions,
+    client_tls: Option<ClientTlsConfig>,
+    interval: Duration,
+    addrs: HashSet<SocketAddr>,
+}
+
+impl Resolver {
+    /// Keeps the addresses of a balanced channel up to date, until the channel is dropped (which
+    /// closes `tx`)
+    fn spawn_least_loaded(self, tx: Sender<Change<SocketAddr, Endpoint>>) -> JoinHandle<()> {
+        let closed = tx.clone();
+        tokio::spawn(
+            self.run(async move { closed.closed().await }, move |changes| {
+                let tx = tx.clone();
+                async move {
+                    for change in changes {
+                        if tx.send(change).await.is_err() {
+                            return false;
+                        }
+                    }
+                    true
+                }
+            }),
+        )
+    }
+
+    /// Keeps the set of round robin channels up to date, until it is dropped
+    fn spawn_round_robin(self, channels: Weak<RwLock<Vec<Channel>>>) -> JoinHandle<()> {
+        tokio::spawn(self.run_round_robin(channels))
+    }
+
+    /// Resolves and passes changes to `apply` until it returns false, or `closed` resolves
+    async fn run<F, Fut>(mut self, closed: impl Future<Output = ()>, apply: F)
+    where
+        F: Fn(Vec<Change<SocketAddr, Endpoint>>) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        tokio::pin!(closed);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = &mut closed => return,
+            }
+            let Some((added, removed)) = self.refresh().await else {
+                continue;
+            };
+            let mut changes: Vec<_> = removed.into_iter().map(Change::Remove).collect();
+            for addr in added {
+                match address_endpoint(&self.opts, addr, self.client_tls.as_ref()).await {
+                    Ok(e) => changes.push(Change::Insert(addr, e)),
+                    Err(e) => warn!(error=%e, "Couldn't build endpoint for resolved address"),
+                }
+            }
+            if !apply(changes).await {
+                return;
+            }
+        }
+    }
+
+    async fn run_round_robin(mut self, channels: Weak<RwLock<Vec<Channel>>>) {
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if channels.strong_count() == 0 {
+                return;
+            }
+            if self.refresh().await.is_none() {
+                continue;
+            }
+            let mut fresh = Vec::with_capacity(self.addrs.len());
+            for addr in self.addrs.iter() {
+                match address_endpoint(&self.opts, *addr, self.client_tls.as_ref()).await {
+                    Ok(e) => fresh.push(e.connect_lazy()),
+                    Err(e) => warn!(error=%e, "Couldn't build endpoint for resolved address"),
+                }
+            }
+            match channels.upgrade() {
+                Some(c) if !fresh.is_empty() => *c.write() = fresh,
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
+    /// Resolves again, returning the addresses added and removed if any changed. Failed
+    /// resolutions, and ones which find no addresses, leave the addresses as they were.
+    async fn refresh(&mut self) -> Option<(Vec<SocketAddr>, Vec<SocketAddr>)> {
+        let fresh = match resolve(&self.opts.target_url).await {
+            Ok(a) if !a.is_empty() => a,
+            Ok(_) => {
+                warn!("DNS resolution found no addresses, keeping previous ones");
+                return None;
+            }
+            Err(e) => {
+                warn!(error=%e, "DNS resolution failed, keeping previous addresses");
+                return None;
+            }
+        };
+        let added: Vec<_> = fresh.difference(&self.addrs).copied().collect();
+        let removed: Vec<_> = self.addrs.difference(&fresh).copied().collect();
+        if added.is_empty() && removed.is_empty() {
+            return None;
+        }
+        debug!(?added, ?removed, "Frontend addresses changed");
+        self.addrs = fresh;
+        Some((added, removed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+
+    #[tokio::test]
+    async fn round_robin_takes_turns() {
+        let lazy = |port| Endpoint::from_shared(format!("http://127.0.0.1:{port}")).unwrap();
+        let channels = vec![lazy(1).connect_lazy(), lazy(2).connect_lazy()];
+        let mut rr = RoundRobinChannel {
+            channels: Arc::new(RwLock::new(channels)),
+            next: Arc::new(AtomicUsize::new(0)),
+            picked: None,
+        };
+        let mut clone = rr.clone();
+        poll_fn(|cx| rr.poll_ready(cx)).await.unwrap();
+        poll_fn(|cx| clone.poll_ready(cx)).await.unwrap();
+        // Clones share the turn counter, and polling again keeps the already picked channel
+        poll_fn(|cx| rr.poll_ready(cx)).await.unwrap();
+        assert_eq!(rr.next.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn address_endpoints_keep_host_name() {
+        let opts = crate::ClientOptionsBuilder::default()
+            .target_url(Url::parse("https://frontend.temporal.svc:7233").unwrap())
+            .client_name("lb-test")
+            .client_version("0.1.0")
+            .build()
+            .unwrap();
+        let endpoint = address_endpoint(&opts, "10.0.0.7:7233".parse().unwrap(), None)
+            .await
+            .unwrap();
+        assert_eq!(endpoint.uri().host(), Some("10.0.0.7"));
+    }
+
+    #[tokio::test]
+    async fn resolvers_stop_once_channel_dropped() {
+        let resolver = || Resolver {
+            opts: crate::ClientOptionsBuilder::default()
+                .target_url(Url::parse("http://127.0.0.1:7233").unwrap())
+                .client_name("lb-test")
+                .client_version("0.1.0")
+                .build()
+                .unwrap(),
+            client_tls: None,
+            interval: Duration::from_millis(10),
+            addrs: HashSet::new(),
+        };
+        let wait = |handle| tokio::time::timeout(Duration::from_secs(5), handle);
+
+        let (channel, tx) = Channel::balance_channel::<SocketAddr>(16);
+        let handle = resolver().spawn_least_loaded(tx);
+        drop(channel);
+        wait(handle).await.unwrap().unwrap();
+
+        let channels = Arc::new(RwLock::new(vec![]));
+        let handle = resolver().spawn_round_robin(Arc::downgrade(&channels));
+        drop(channels);
+        wait(handle).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{Result, WorkflowClientTrait};
+use std::time::{Duration, SystemTime};
+use temporal_sdk_core_protos::temporal::api::{
+    batch::v1::{
+        BatchOperationCancellation, BatchOperationDeletion, BatchOperationReset,
+        BatchOperationSignal, BatchOperationTermination,
+    },
+    common::v1::{Header, Payloads, ResetOptions, WorkflowExecution},
+    enums::v1::{BatchOperationState, BatchOperationType},
+    workflowservice::v1::{
+        start_batch_operation_request::Operation, DescribeBatchOperationResponse,
+        StartBatchOperationRequest,
+    },
+};
+use uuid::Uuid;
+
+/// Options for starting a server side batch operation, which applies an operation to many
+/// workflows at once. See [crate::WorkflowClientTrait::start_batch_operation].
+#[derive(Clone, Debug, derive_builder::Builder)]
+pub struct BatchOperationOptions {
+    /// Unique id of the batch job, used to describe or stop it. Defaults to a random UUID.
+    #[builder(setter(into), default = "Uuid::new_v4().to_string()")]
+    pub job_id: String,
+    /// The workflows to apply the operation to
+    pub target: BatchTarget,
+    /// What to do to each workflow
+    pub operation: BatchOperation,
+    /// Why the operation is being performed, recorded with the batch job
+    #[builder(setter(into))]
+    pub reason: String,
+    /// If set, caps how many workflows are operated on per second. The server's own limit applies
+    /// if this is unset or above it.
+    #[builder(setter(strip_option), default)]
+    pub max_operations_per_second: Option<f32>,
+}
+
+/// The workflows a batch operation applies to
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchTarget {
+    /// Every workflow matching a visibility query
+    Query(String),
+    /// Specific workflow executions. Executions with an empty run id target the latest run.
+    Executions(Vec<WorkflowExecution>),
+}
+
+/// What a batch operation does to each workflow it applies to
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchOperation {
+    /// Signal each workflow
+    Signal {
+        /// Name of the signal
+        signal_name: String,
+        /// Input to the signal
+        input: Option<Payloads>,
+        /// Headers passed to the workflow with the signal
+        header: Option<Header>,
+    },
+    /// Request cancellation of each workflow
+    Cancel,
+    /// Terminate each workflow
+    Terminate {
+        /// Details recorded with each termination
+        details: Option<Payloads>,
+    },
+    /// Delete each workflow, including its history
+    Delete,
+    /// Reset each workflow
+    Reset(ResetOptions),
+}
+
+impl BatchOperationOptions {
+    pub(crate) fn into_request(
+        self,
+        namespace: String,
+        identity: String,
+    ) -> StartBatchOperationRequest {
+        let (visibility_query, executions) = match self.target {
+            BatchTarget::Query(q) => (q, vec![]),
+            BatchTarget::Executions(e) => (String::new(), e),
+        };
+        let operation = match self.operation {
+            BatchOperation::Signal {
+                signal_name,
+                input,
+                header,
+            } => Operation::SignalOperation(BatchOperationSignal {
+                signal: signal_name,
+                input,
+                header,
+                identity,
+            }),
+            BatchOperation::Cancel => {
+                Operation::CancellationOperation(BatchOperationCancellation { identity })
+            }
+            BatchOperation::Terminate { details } => {
+                Operation::TerminationOperation(BatchOperationTermination { details, identity })
+            }
+            BatchOperation::Delete => {
+                Operation::DeletionOperation(BatchOperationDeletion { identity })
+            }
+            BatchOperation::Reset(options) => Operation::ResetOperation(BatchOperationReset {
+                identity,
+                options: Some(options),
+                ..Default::default()
+            }),
+        };
+        StartBatchOperationRequest {
+            namespace,
+            visibility_query,
+            job_id: self.job_id,
+            reason: self.reason,
+            executions,
+            max_operations_per_second: self.max_operations_per_second.unwrap_or_default(),
+            operation: Some(operation),
+        }
+    }
+}
+
+/// The progress of a batch operation, as described by the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchOperationProgress {
+    /// Id of the batch job
+    pub job_id: String,
+    /// What the operation does to each workflow
+    pub operation_type: BatchOperationType,
+    /// Whether the operation is still running
+    pub state: BatchOperationState,
+    /// When the operation started
+    pub start_time: Option<SystemTime>,
+    /// When the operation finished, if it has
+    pub close_time: Option<SystemTime>,
+    /// How many workflows the operation applies to
+    pub total_operation_count: i64,
+    /// How many workflows have been operated on successfully
+    pub complete_operation_count: i64,
+    /// How many workflows couldn't be operated on
+    pub failure_operation_count: i64,
+    /// The reason the operation was started or stopped with
+    pub reason: String,
+}
+
+impl BatchOperationProgress {
+    /// Whether the operation has finished, successfully or not
+    pub fn is_finished(&self) -> bool {
+        !matches!(
+            self.state,
+            BatchOperationState::Running | BatchOperationState::Unspecified
+        )
+    }
+}
+
+impl From<DescribeBatchOperationResponse> for BatchOperationProgress {
+    fn from(resp: DescribeBatchOperationResponse) -> Self {
+        Self {
+            operation_type: resp.operation_type(),
+            state: resp.state(),
+            job_id: resp.job_id,
+            start_time: resp.start_time.and_then(|t| t.try_into().ok()),
+            close_time: resp.close_time.and_then(|t| t.try_into().ok()),
+            total_operation_count: resp.total_operation_count,
+            complete_operation_count: resp.complete_operation_count,
+            failure_operation_count: resp.failure_operation_count,
+            reason: resp.reason,
+        }
+    }
+}
+
+/// Wait for a batch operation to finish, describing it every `poll_interval` and passing its
+/// progress to `on_progress` each time. Returns its final progress.
+pub async fn wait_for_batch_operation(
+    client: &impl WorkflowClientTrait,
+    job_id: impl Into<String>,
+    poll_interval: Duration,
+    mut on_progress: impl FnMut(&BatchOperationProgress),
+) -> Result<BatchOperationProgress> {
+    let job_id = job_id.into();
+    loop {
+        let progress = client.describe_batch_operation(job_id.clone()).await?;
+        on_progress(&progress);
+        if progress.is_finished() {
+            return Ok(progress);
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_requests_by_target_and_operation() {
+        let opts = BatchOperationOptionsBuilder::default()
+            .target(BatchTarget::Query("WorkflowType = 'Foo'".to_string()))
+            .operation(BatchOperation::Terminate { details: None })
+            .reason("cleanup")
+            .build()
+            .unwrap();
+        assert!(!opts.job_id.is_empty());
+        let req = opts.into_request("ns".to_string(), "me".to_string());
+        assert_eq!(req.visibility_query, "WorkflowType = 'Foo'");
+        assert!(req.executions.is_empty());
+        assert_eq!(
+            req.operation,
+            Some(Operation::TerminationOperation(BatchOperationTermination {
+                details: None,
+                identity: "me".to_string(),
+            }))
+        );
+
+        let req = BatchOperationOptionsBuilder::default()
+            .job_id("job")
+            .target(BatchTarget::Executions(vec![WorkflowExecution {
+                workflow_id: "wf".to_string(),
+                run_id: "".to_string(),
+            }]))
+            .operation(BatchOperation::Cancel)
+            .reason("cancel")
+            .max_operations_per_second(5.0)
+            .build()
+            .unwrap()
+            .into_request("ns".to_string(), "me".to_string());
+        assert_eq!(req.job_id, "job");
+        assert!(req.visibility_query.is_empty());
+        assert_eq!(req.executions.len(), 1);
+        assert_eq!(req.max_operations_per_second, 5.0);
+    }
+
+    #[tokio::test]
+    async fn waits_until_finished() {
+        let mut client = crate::MockWorkflowClientTrait::new();
+        let mut polls = 0;
+        client
+            .expect_describe_batch_operation()
+            .times(3)
+            .returning(move |job_id| {
+                polls += 1;
+                let state = if polls < 3 {
+                    BatchOperationState::Running
+                } else {
+                    BatchOperationState::Completed
+                };
+                Ok(BatchOperationProgress::from(
+                    DescribeBatchOperationResponse {
+                        job_id,
+                        state: state as i32,
+                        complete_operation_count: polls,
+                        ..Default::default()
+                    },
+                ))
+            });
+        let mut seen = vec![];
+        let done = wait_for_batch_operation(&client, "job", Duration::from_millis(1), |p| {
+            seen.push(p.complete_operation_count)
+        })
+        .await
+        .unwrap();
+        assert_eq!(done.state, BatchOperationState::Completed);
+        assert_eq!(seen, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn progress_knows_when_finished() {
+        let progress = |state| {
+            BatchOperationProgress::from(DescribeBatchOperationResponse {
+                state: state as i32,
+                total_operation_count: 10,
+                ..Default::default()
+            })
+        };
+        assert!(!progress(BatchOperationState::Running).is_finished());
+        assert!(progress(BatchOperationState::Completed).is_finished());
+        assert!(progress(BatchOperationState::Failed).is_finished());
+        assert_eq!(
+            progress(BatchOperationState::Running).total_operation_count,
+            10
+        );
+    }
+}
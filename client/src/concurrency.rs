@@ -0,0 +1,193 @@
+//! Limits on how many calls a client has in flight at once
+
+use crate::{metrics::RpcLimiterMetrics, ClientInitError};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use temporal_sdk_core_api::telemetry::metrics::TemporalMeter;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::Status;
+
+/// Caps how many calls, other than polls for tasks, a client (and all of its clones) has in flight
+/// at once, so that a burst of calls like activity completions can't exhaust file descriptors or
+/// overwhelm a small frontend. Calls over the cap wait for one in flight to finish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcConcurrencyLimit {
+    /// Most calls which may be in flight at once. Must be at least 1.
+    pub max_outstanding: usize,
+    /// Most calls which may wait for one in flight to finish. Calls beyond that fail with
+    /// `ResourceExhausted`, which is retried with a longer backoff. If unset, any number may wait.
+    pub max_queued: Option<usize>,
+}
+
+/// Enforces an [RpcConcurrencyLimit], shared by all clones of a client
+#[derive(Debug)]
+pub struct RpcLimiter {
+    limit: RpcConcurrencyLimit,
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    metrics: Option<RpcLimiterMetrics>,
+}
+
+/// Held while a call is in flight
+pub(crate) struct RpcPermit {
+    _permit: OwnedSemaphorePermit,
+    limiter: Arc<RpcLimiter>,
+}
+
+impl Drop for RpcPermit {
+    fn drop(&mut self) {
+        // The permit is only returned after this runs
+        self.limiter.record_in_use(1);
+    }
+}
+
+/// Counts a call as waiting for as long as it lives, so calls whose futures are dropped while
+/// waiting stop being counted
+struct QueuedCall<'a>(&'a RpcLimiter);
+
+impl Drop for QueuedCall<'_> {
+    fn drop(&mut self) {
+        let queued = self.0.queued.fetch_sub(1, Ordering::SeqCst) - 1;
+        self.0.record_queued(queued);
+    }
+}
+
+impl RpcLimiter {
+    pub(crate) fn new(
+        limit: RpcConcurrencyLimit,
+        metrics_meter: Option<TemporalMeter>,
+    ) -> Result<Self, ClientInitError> {
+        if limit.max_outstanding == 0 {
+            return Err(ClientInitError::InvalidRpcConcurrencyLimit(
+                "`max_outstanding` must be at least 1".to_string(),
+            ));
+        }
+        Ok(Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit.max_outstanding)),
+            queued: AtomicUsize::new(0),
+            metrics: metrics_meter.map(RpcLimiterMetrics::new),
+        })
+    }
+
+    /// Waits for room for another call, unless too many are waiting already
+    pub(crate) async fn acquire(self: Arc<Self>) -> Result<RpcPermit, Status> {
+        let permit = match self.semaphore.clone().try_acquire_owned() {
+            Ok(p) => p,
+            Err(_) => {
+                let queued = self.queued.fetch_add(1, Ordering::SeqCst) + 1;
+                let queued_call = QueuedCall(&self);
+                if self.limit.max_queued.is_some_and(|max| queued > max) {
+                    drop(queued_call);
+                    if let Some(m) = self.metrics.as_ref() {
+                        m.rejected();
+                    }
+                    return Err(Status::resource_exhausted(
+                        "Too many calls are waiting for the client's concurrency limit",
+                    ));
+                }
+                self.record_queued(queued);
+                let started = Instant::now();
+                let permit = self.semaphore.clone().acquire_owned().await;
+                drop(queued_call);
+                if let Some(m) = self.metrics.as_ref() {
+                    m.waited(started.elapsed());
+                }
+                permit.expect("The semaphore is never closed")
+            }
+        };
+        self.record_in_use(0);
+        Ok(RpcPermit {
+            _permit: permit,
+            limiter: self,
+        })
+    }
+
+    /// Records how many calls are in flight, less the given number which are about to finish
+    fn record_in_use(&self, finishing: usize) {
+        if let Some(m) = self.metrics.as_ref() {
+            let in_use = self.limit.max_outstanding - self.semaphore.available_permits();
+            m.in_use(in_use.saturating_sub(finishing) as u64);
+        }
+    }
+
+    fn record_queued(&self, queued: usize) {
+        if let Some(m) = self.metrics.as_ref() {
+            m.queued(queued as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn queues_then_rejects() {
+        let limiter = Arc::new(
+            RpcLimiter::new(
+                RpcConcurrencyLimit {
+                    max_outstanding: 1,
+                    max_queued: Some(1),
+                },
+                None,
+            )
+            .unwrap(),
+        );
+        let first = limiter.clone().acquire().await.unwrap();
+        let queued = tokio::spawn(limiter.clone().acquire());
+        while limiter.queued.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let rejected = limiter.clone().acquire().await.err().unwrap();
+        assert_eq!(rejected.code(), tonic::Code::ResourceExhausted);
+        drop(first);
+        let _second = queued.await.unwrap().unwrap();
+        assert_eq!(limiter.queued.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn dropped_waiting_calls_stop_being_queued() {
+        let limiter = Arc::new(
+            RpcLimiter::new(
+                RpcConcurrencyLimit {
+                    max_outstanding: 1,
+                    max_queued: Some(1),
+                },
+                None,
+            )
+            .unwrap(),
+        );
+        let first = limiter.clone().acquire().await.unwrap();
+        // Give up waiting, as a call whose deadline passes does
+        let timed_out =
+            tokio::time::timeout(Duration::from_millis(10), limiter.clone().acquire()).await;
+        assert!(timed_out.is_err());
+        assert_eq!(limiter.queued.load(Ordering::SeqCst), 0);
+        // The queue still has room, so this waits rather than being rejected
+        let queued = tokio::spawn(limiter.clone().acquire());
+        drop(first);
+        queued.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn rejects_zero_outstanding() {
+        let res = RpcLimiter::new(
+            RpcConcurrencyLimit {
+                max_outstanding: 0,
+                max_queued: None,
+            },
+            None,
+        );
+        assert!(matches!(
+            res,
+            Err(ClientInitError::InvalidRpcConcurrencyLimit(_))
+        ));
+    }
+}
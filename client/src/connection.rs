@@ -0,0 +1,150 @@
+//! Visibility into, and control over, the client's connection to the server
+
+use crate::{RetryConfig, RETRYABLE_ERROR_CODES};
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// The state of the client's connection to the server, as observed by the calls it has made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No call has completed yet, so nothing is known about the connection
+    Unknown,
+    /// The most recent call reached the server, whether or not it succeeded
+    Connected,
+    /// Calls have been failing to reach the server
+    Disconnected {
+        /// When calls started failing
+        since: SystemTime,
+        /// The transport error the most recent call failed with
+        last_error: String,
+    },
+}
+
+/// Tracks the [ConnectionState] of a channel, shared by all clones of a client
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectionMonitor(Arc<Mutex<ConnectionState>>);
+
+impl Default for ConnectionMonitor {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(ConnectionState::Unknown)))
+    }
+}
+
+impl ConnectionMonitor {
+    pub(crate) fn state(&self) -> ConnectionState {
+        self.0.lock().clone()
+    }
+
+    /// Forgets what has been observed, for when the channel is replaced
+    pub(crate) fn reset(&self) {
+        *self.0.lock() = ConnectionState::Unknown;
+    }
+
+    pub(crate) fn record_reached_server(&self) {
+        let mut state = self.0.lock();
+        if let ConnectionState::Disconnected { since, .. } = &*state {
+            info!(
+                outage = ?since.elapsed().unwrap_or_default(),
+                "Reconnected to server"
+            );
+        }
+        *state = ConnectionState::Connected;
+    }
+
+    pub(crate) fn record_transport_error(&self, error: &tonic::transport::Error) {
+        let mut state = self.0.lock();
+        let since = match &*state {
+            ConnectionState::Disconnected { since, .. } => *since,
+            _ => {
+                warn!(error = %error, "Lost connection to server");
+                SystemTime::now()
+            }
+        };
+        *state = ConnectionState::Disconnected {
+            since,
+            last_error: format!("{error:?}"),
+        };
+    }
+}
+
+/// How polls for tasks behave while the server can't be reached or keeps failing them. Polls are
+/// retried with an exponential backoff until the server can be reached again, or until the outage
+/// has lasted long enough that the error is surfaced to the worker, which treats it as fatal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Wait before the first retry of a failed poll
+    pub initial_interval: Duration,
+    /// Rate at which the wait increases, until it reaches `max_interval`
+    pub multiplier: f64,
+    /// Longest wait between retries
+    pub max_interval: Duration,
+    /// How long a poll may keep failing with retryable errors, as it does while the server can't
+    /// be reached, before the error is surfaced. If unset, polls are retried forever.
+    pub max_outage: Option<Duration>,
+    /// How long a poll keeps retrying errors which otherwise aren't retryable before surfacing
+    /// them, since some proxies return such errors while starting up
+    pub fatal_error_grace: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl ReconnectPolicy {
+    pub(crate) const DEFAULT: Self = Self {
+        initial_interval: Duration::from_millis(200),
+        multiplier: 2.0,
+        max_interval: Duration::from_secs(10),
+        max_outage: None,
+        fatal_error_grace: Duration::from_secs(60),
+    };
+
+    /// The retry configuration polls are made with under this policy
+    pub(crate) fn poll_retry_config(&self) -> RetryConfig {
+        RetryConfig {
+            initial_interval: self.initial_interval,
+            randomization_factor: 0.2,
+            multiplier: self.multiplier,
+            max_interval: self.max_interval,
+            max_elapsed_time: self.max_outage,
+            max_retries: 0,
+            retryable_codes: Cow::Borrowed(&RETRYABLE_ERROR_CODES),
+            retry_budget: None,
+            poll_reconnect: *self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::transport::Endpoint;
+
+    #[tokio::test]
+    async fn tracks_outages() {
+        let monitor = ConnectionMonitor::default();
+        assert_eq!(monitor.state(), ConnectionState::Unknown);
+        // Nothing listens on port 1
+        let err = Endpoint::from_static("http://127.0.0.1:1")
+            .connect()
+            .await
+            .unwrap_err();
+        monitor.record_transport_error(&err);
+        let ConnectionState::Disconnected { since, .. } = monitor.state() else {
+            panic!("Should be disconnected");
+        };
+        monitor.record_transport_error(&err);
+        assert_matches::assert_matches!(
+            monitor.state(),
+            ConnectionState::Disconnected { since: s, .. } if s == since
+        );
+        monitor.record_reached_server();
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+    }
+}
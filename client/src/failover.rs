@@ -0,0 +1,249 @@
+//! Failover between several frontend endpoints behind a single logical client. The channel all
+//! clones of a client share is swapped for one connected to another endpoint once calls have been
+//! failing to reach the current one for long enough.
+
+use crate::{
+    balance::FrontendChannel,
+    connection::{ConnectionMonitor, ConnectionState},
+    metrics::FailoverMetrics,
+    tls_reload::{Reconnected, WeakReloadableChannel},
+    ClientInitError, ClientOptions, ClientTlsConfig,
+};
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use temporal_sdk_core_api::telemetry::metrics::TemporalMeter;
+use url::Url;
+
+/// How often the connection is checked for an outage, and whether it's time to fail back
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configures failover from [ClientOptions::target_url] to other endpoints, for example the
+/// frontends of several regions of a Temporal Cloud namespace. All endpoints use the same TLS
+/// configuration, so [crate::TlsConfig::domain] should be left unset if they have different
+/// names.
+///
+/// Certificates loaded with [ClientOptions::tls_reload] or [ClientOptions::spiffe] are carried
+/// over to whichever endpoint is in use, and reloading them reconnects to that endpoint.
+#[derive(Clone, Debug)]
+pub struct FailoverOptions {
+    /// Endpoints to fail over to, in order of preference, after [ClientOptions::target_url]
+    pub fallback_urls: Vec<Url>,
+    /// How long calls must keep failing to reach the current endpoint before failing over to the
+    /// most preferred endpoint which can be reached
+    pub failover_after: Duration,
+    /// If set, how often to check whether a more preferred endpoint can be reached again while
+    /// failed over, and fail back to it if so
+    pub fail_back_interval: Option<Duration>,
+    /// If set, told about every failover and fail back
+    pub listener: Option<Arc<dyn FailoverListener>>,
+}
+
+/// Told whenever a client switches endpoints. See [FailoverOptions::listener].
+pub trait FailoverListener: Debug + Send + Sync {
+    /// Called after the client has switched endpoints. Calls started from now on go to the new
+    /// one.
+    fn on_failover(&self, event: &FailoverEvent);
+}
+
+/// A switch between endpoints
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailoverEvent {
+    /// The endpoint switched away from
+    pub from: Url,
+    /// The endpoint switched to
+    pub to: Url,
+    /// Why the switch happened
+    pub reason: FailoverReason,
+}
+
+/// Why a client switched endpoints
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FailoverReason {
+    /// Calls had been failing to reach the previous endpoint
+    Outage {
+        /// When calls started failing
+        since: SystemTime,
+        /// The transport error the most recent call failed with
+        last_error: String,
+    },
+    /// A more preferred endpoint could be reached again
+    FailBack,
+}
+
+impl FailoverReason {
+    fn metric_label(&self) -> &'static str {
+        match self {
+            FailoverReason::Outage { .. } => "outage",
+            FailoverReason::FailBack => "fail_back",
+        }
+    }
+}
+
+impl FailoverOptions {
+    /// All endpoints, most preferred first
+    pub(crate) fn endpoints(&self, opts: &ClientOptions) -> Vec<Url> {
+        std::iter::once(opts.target_url.clone())
+            .chain(self.fallback_urls.iter().cloned())
+            .collect()
+    }
+
+    /// Connects to the most preferred endpoint which can be reached, returning its index in
+    /// [Self::endpoints], or the error connecting to the first if none can be
+    pub(crate) async fn connect_preferred(
+        &self,
+        opts: &ClientOptions,
+        client_tls: Option<&ClientTlsConfig>,
+    ) -> Result<(FrontendChannel, usize), ClientInitError> {
+        let mut first_err = None;
+        for (idx, endpoint) in self.endpoints(opts).into_iter().enumerate() {
+            let mut endpoint_opts = opts.clone();
+            endpoint_opts.target_url = endpoint;
+            match endpoint_opts.connect_channel(client_tls).await {
+                Ok(ch) => return Ok((ch, idx)),
+                Err(e) => {
+                    warn!(endpoint=%endpoint_opts.target_url, error=%e, "Couldn't connect to endpoint");
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_err.expect("There is always at least one endpoint"))
+    }
+}
+
+impl WeakReloadableChannel {
+    /// Fails over between the configured endpoints for as long as any clone of the channel exists,
+    /// starting from the one at index `current`
+    pub(crate) fn spawn_failover(
+        self,
+        opts: ClientOptions,
+        cfg: FailoverOptions,
+        current: usize,
+        connection: ConnectionMonitor,
+        metrics_meter: Option<TemporalMeter>,
+    ) {
+        let metrics = metrics_meter.map(FailoverMetrics::new);
+        tokio::spawn(failover_loop(self, opts, cfg, current, connection, metrics));
+    }
+}
+
+async fn failover_loop(
+    channel: WeakReloadableChannel,
+    opts: ClientOptions,
+    cfg: FailoverOptions,
+    mut current: usize,
+    connection: ConnectionMonitor,
+    metrics: Option<FailoverMetrics>,
+) {
+    let endpoints = cfg.endpoints(&opts);
+    let mut last_fail_back_check = Instant::now();
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        if !channel.is_alive() {
+            return;
+        }
+        let (candidates, reason) = match connection.state() {
+            ConnectionState::Disconnected { since, last_error }
+                if since.elapsed().unwrap_or_default() >= cfg.failover_after =>
+            {
+                (
+                    failover_candidates(current, endpoints.len()),
+                    FailoverReason::Outage { since, last_error },
+                )
+            }
+            _ if current > 0
+                && cfg
+                    .fail_back_interval
+                    .is_some_and(|i| last_fail_back_check.elapsed() >= i) =>
+            {
+                last_fail_back_check = Instant::now();
+                ((0..current).collect(), FailoverReason::FailBack)
+            }
+            _ => continue,
+        };
+        for idx in candidates {
+            let reconnected = channel
+                .reconnect(&opts, |target| {
+                    target.url = endpoints[idx].clone();
+                    true
+                })
+                .await;
+            match reconnected {
+                Ok(Reconnected::Replaced | Reconnected::Unchanged) => {}
+                Ok(Reconnected::Dropped) => return,
+                Err(e) => {
+                    debug!(endpoint=%endpoints[idx], error=%e, "Couldn't connect to endpoint");
+                    continue;
+                }
+            }
+            connection.reset();
+            let event = FailoverEvent {
+                from: endpoints[current].clone(),
+                to: endpoints[idx].clone(),
+                reason: reason.clone(),
+            };
+            warn!(from=%event.from, to=%event.to, reason=?event.reason, "Client switched endpoints");
+            if let Some(m) = metrics.as_ref() {
+                m.failed_over(event.to.to_string(), event.reason.metric_label());
+            }
+            if let Some(l) = cfg.listener.as_ref() {
+                l.on_failover(&event);
+            }
+            current = idx;
+            last_fail_back_check = Instant::now();
+            break;
+        }
+    }
+}
+
+/// The endpoints to try, in order, when the one at `current` is out
+fn failover_candidates(current: usize, num_endpoints: usize) -> Vec<usize> {
+    (0..num_endpoints).filter(|i| *i != current).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_endpoints_in_order() {
+        assert_eq!(failover_candidates(0, 3), vec![1, 2]);
+        assert_eq!(failover_candidates(1, 3), vec![0, 2]);
+        assert!(failover_candidates(0, 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn connects_to_first_reachable_endpoint() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            let mut conns = vec![];
+            while let Ok((conn, _)) = listener.accept().await {
+                conns.push(conn);
+            }
+        });
+        // Nothing listens on port 1
+        let opts = crate::ClientOptionsBuilder::default()
+            .target_url(Url::parse("http://127.0.0.1:1").unwrap())
+            .client_name("failover-test")
+            .client_version("0.1.0")
+            .build()
+            .unwrap();
+        let failover = FailoverOptions {
+            fallback_urls: vec![Url::parse("http://127.0.0.1:2").unwrap(), reachable],
+            failover_after: Duration::from_secs(10),
+            fail_back_interval: None,
+            listener: None,
+        };
+        let (_, idx) = failover.connect_preferred(&opts, None).await.unwrap();
+        assert_eq!(idx, 2);
+
+        let failover = FailoverOptions {
+            fallback_urls: vec![],
+            ..failover
+        };
+        assert!(failover.connect_preferred(&opts, None).await.is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! A gRPC-Web transport, for networks where HTTP/2 egress is blocked. Calls are still made by
+//! tonic, over an in-process HTTP/2 connection. The other end of that connection translates each
+//! call into a gRPC-Web request to the server over HTTP/1.1, and the response back.
+
+use crate::{ClientInitError, ClientOptions, ClientTlsConfig, TlsConfig};
+use bytes::{Bytes, BytesMut};
+use futures::{future::BoxFuture, FutureExt};
+use http::{header, HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, Uri};
+use hyper::{
+    body::{HttpBody, Sender},
+    client::connect::{Connected, Connection},
+    service::service_fn,
+    Body,
+};
+use std::{
+    convert::Infallible,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
+};
+use tonic::{
+    transport::{Channel, Endpoint},
+    Code, Status,
+};
+use tower::Service;
+use url::Url;
+
+const GRPC_WEB_CONTENT_TYPE: &str = "application/grpc-web+proto";
+/// Set on the frame of a gRPC-Web response which carries its trailers
+const TRAILERS_FRAME_FLAG: u8 = 0x80;
+/// Every frame starts with a flag byte and a four byte length
+const FRAME_HEADER_LEN: usize = 5;
+const PIPE_BUFFER_BYTES: usize = 64 * 1024;
+
+/// The protocol calls to the server are made with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransportProtocol {
+    /// gRPC over HTTP/2
+    #[default]
+    Grpc,
+    /// gRPC-Web over HTTP/1.1, for networks which only let HTTP/1.1 through. The server, or a
+    /// proxy in front of it, must accept gRPC-Web. Each call in flight, polls for tasks included,
+    /// holds a connection of its own, so a worker needs as many connections as it has outstanding
+    /// polls. HTTP/2 keep alive, load balancing, and proxies don't apply, and are ignored.
+    GrpcWeb,
+}
+
+/// Connects a channel which makes calls with gRPC-Web, using `client_tls` in place of any
+/// configured mTLS client certificate if it is provided
+pub(crate) async fn connect_grpc_web(
+    opts: &ClientOptions,
+    client_tls: Option<&ClientTlsConfig>,
+    lazy: bool,
+) -> Result<Channel, ClientInitError> {
+    let translator = Translator {
+        client: hyper::Client::builder().build(UpstreamConnector {
+            tls: tls_connector(opts, client_tls).map_err(ClientInitError::InvalidTlsConfig)?,
+        }),
+        target: opts.target_url.clone(),
+        host: opts
+            .override_origin
+            .as_ref()
+            .and_then(Uri::authority)
+            .and_then(|a| HeaderValue::from_str(a.as_str()).ok()),
+    };
+    // Only names the in-process connection. Calls are sent on to the target.
+    let endpoint = Endpoint::from_static("http://grpc-web.local");
+    let connector = tower::service_fn(move |_: Uri| {
+        let translator = translator.clone();
+        async move { Ok::<_, io::Error>(translator.serve_pipe()) }
+    });
+    Ok(if lazy {
+        endpoint.connect_with_connector_lazy(connector)
+    } else {
+        endpoint.connect_with_connector(connector).await?
+    })
+}
+
+fn tls_connector(
+    opts: &ClientOptions,
+    client_tls: Option<&ClientTlsConfig>,
+) -> Result<Option<(TlsConnector, ServerName<'static>)>, String> {
+    let tls_cfg = match (&opts.tls_cfg, client_tls) {
+        (Some(cfg), _) => cfg.clone(),
+        (None, Some(_)) => TlsConfig::default(),
+        (None, None) if opts.target_url.scheme() == "https" => TlsConfig::default(),
+        (None, None) => return Ok(None),
+    };
+    let invalid = |e: &dyn std::fmt::Display| e.to_string();
+    let mut roots = RootCertStore::empty();
+    match &tls_cfg.server_root_ca_cert {
+        Some(pem) => {
+            for cert in rustls_pemfile::certs(&mut pem.as_slice()) {
+                roots
+                    .add(cert.map_err(|e| invalid(&e))?)
+                    .map_err(|e| invalid(&e))?;
+            }
+        }
+        None => {
+            let native = rustls_native_certs::load_native_certs().map_err(|e| invalid(&e))?;
+            roots.add_parsable_certificates(native);
+        }
+    }
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+    let mut config = match client_tls.or(tls_cfg.client_tls_config.as_ref()) {
+        Some(identity) => {
+            let certs = rustls_pemfile::certs(&mut identity.client_cert.as_slice())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| invalid(&e))?;
+            let key = rustls_pemfile::private_key(&mut identity.client_private_key.as_slice())
+                .map_err(|e| invalid(&e))?
+                .ok_or_else(|| invalid(&"no private key found in the client key PEM"))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| invalid(&e))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    let domain = tls_cfg
+        .domain
+        .clone()
+        .or_else(|| opts.target_url.host_str().map(ToString::to_string))
+        .unwrap_or_default();
+    let domain = ServerName::try_from(domain).map_err(|e| invalid(&e))?;
+    Ok(Some((TlsConnector::from(Arc::new(config)), domain)))
+}
+
+/// Serves calls made over the in-process connection by sending them on as gRPC-Web
+#[derive(Clone)]
+struct Translator {
+    client: hyper::Client<UpstreamConnector, Body>,
+    target: Url,
+    /// Overrides the `Host` header, if set
+    host: Option<HeaderValue>,
+}
+
+impl Translator {
+    /// Returns one end of an in-process connection, the other end of which is served
+    fn serve_pipe(self) -> DuplexStream {
+        let (ours, theirs) = tokio::io::duplex(PIPE_BUFFER_BYTES);
+        tokio::spawn(async move {
+            let svc = service_fn(move |req| {
+                let translator = self.clone();
+                async move { Ok::<_, Infallible>(translator.call(req).await) }
+            });
+            if let Err(e) = hyper::server::conn::Http::new()
+                .http2_only(true)
+                .serve_connection(theirs, svc)
+                .await
+            {
+                debug!(error=%e, "gRPC-Web translating connection closed");
+            }
+        });
+        ours
+    }
+
+    async fn call(&self, req: Request<Body>) -> Response<Body> {
+        let (mut parts, body) = req.into_parts();
+        let mut url = self.target.clone();
+        url.set_path(parts.uri.path());
+        url.set_query(parts.uri.query());
+        parts.uri = match url.as_str().parse() {
+            Ok(uri) => uri,
+            Err(e) => return status_response(Code::Internal, &format!("Invalid call URI: {e}")),
+        };
+        parts.version = http::Version::HTTP_11;
+        to_grpc_web_request_headers(&mut parts.headers, self.host.clone());
+        match self.client.request(Request::from_parts(parts, body)).await {
+            Ok(resp) => from_grpc_web_response(resp),
+            // Calls which never reached the server fail as they would over gRPC
+            Err(e) => status_response(Code::Unavailable, &format!("gRPC-Web call failed: {e}")),
+        }
+    }
+}
+
+fn to_grpc_web_request_headers(headers: &mut HeaderMap, host: Option<HeaderValue>) {
+    headers.remove(header::TE);
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(GRPC_WEB_CONTENT_TYPE),
+    );
+    headers.insert(
+        header::ACCEPT,
+        HeaderValue::from_static(GRPC_WEB_CONTENT_TYPE),
+    );
+    headers.insert("x-grpc-web", HeaderValue::from_static("1"));
+    if let Some(host) = host {
+        headers.insert(header::HOST, host);
+    }
+}
+
+fn from_grpc_web_response(resp: Response<Body>) -> Response<Body> {
+    let (mut parts, body) = resp.into_parts();
+    parts.version = http::Version::HTTP_2;
+    for h in [
+        header::CONNECTION,
+        header::TRANSFER_ENCODING,
+        header::CONTENT_LENGTH,
+    ] {
+        parts.headers.remove(h);
+    }
+    // tonic works out a status from the HTTP status of responses which don't carry one
+    if parts.status != StatusCode::OK {
+        return Response::from_parts(parts, Body::empty());
+    }
+    let is_grpc_web = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/grpc-web"));
+    if !is_grpc_web {
+        return status_response(Code::Internal, "Server didn't answer with gRPC-Web");
+    }
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    let (tx, rx) = Body::channel();
+    tokio::spawn(forward_frames(body, tx));
+    Response::from_parts(parts, rx)
+}
+
+/// A gRPC response carrying only a status
+fn status_response(code: Code, message: &str) -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    let headers = resp.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    let _ = Status::new(code, message).add_header(headers);
+    resp
+}
+
+/// Forwards the messages of a gRPC-Web response body as they arrive, and its trailers frame as
+/// trailers
+async fn forward_frames(mut body: Body, mut tx: Sender) {
+    let mut buf = BytesMut::new();
+    loop {
+        while let Some(frame) = next_frame(&mut buf) {
+            match frame {
+                Frame::Message(m) => {
+                    if tx.send_data(m).await.is_err() {
+                        return;
+                    }
+                }
+                Frame::Trailers(t) => {
+                    let _ = tx.send_trailers(t).await;
+                    return;
+                }
+            }
+        }
+        match body.data().await {
+            Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+            Some(Err(e)) => {
+                debug!(error=%e, "gRPC-Web response body failed");
+                tx.abort();
+                return;
+            }
+            None => {
+                // The server may have put the status in the headers instead, but a partial
+                // frame means the response was cut short
+                if !buf.is_empty() {
+                    tx.abort();
+                }
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Frame {
+    /// A message frame, as is, since gRPC frames them the same way
+    Message(Bytes),
+    Trailers(HeaderMap),
+}
+
+/// Takes the next complete frame off the buffer, if it holds one
+fn next_frame(buf: &mut BytesMut) -> Option<Frame> {
+    let header = buf.get(..FRAME_HEADER_LEN)?;
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if buf.len() < FRAME_HEADER_LEN + len {
+        return None;
+    }
+    let frame = buf.split_to(FRAME_HEADER_LEN + len).freeze();
+    Some(if frame[0] & TRAILERS_FRAME_FLAG != 0 {
+        Frame::Trailers(parse_trailers(&frame[FRAME_HEADER_LEN..]))
+    } else {
+        Frame::Message(frame)
+    })
+}
+
+/// Trailers are sent as HTTP/1 style header lines. Malformed lines are skipped.
+fn parse_trailers(block: &[u8]) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    for line in String::from_utf8_lossy(block).split("\r\n") {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.trim().as_bytes()),
+            HeaderValue::from_str(value.trim()),
+        ) {
+            trailers.append(name, value);
+        }
+    }
+    trailers
+}
+
+/// Opens connections to the server, with TLS if it's configured
+#[derive(Clone)]
+struct UpstreamConnector {
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+impl Service<Uri> for UpstreamConnector {
+    type Response = UpstreamStream;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<UpstreamStream>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let tls = self.tls.clone();
+        async move {
+            let host = uri
+                .host()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URI has no host"))?
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string();
+            let port = uri
+                .port_u16()
+                .unwrap_or(if tls.is_some() { 443 } else { 80 });
+            let tcp = TcpStream::connect((host, port)).await?;
+            tcp.set_nodelay(true)?;
+            Ok(match tls {
+                Some((connector, domain)) => {
+                    UpstreamStream::Tls(Box::new(connector.connect(domain, tcp).await?))
+                }
+                None => UpstreamStream::Plain(tcp),
+            })
+        }
+        .boxed()
+    }
+}
+
+enum UpstreamStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Connection for UpstreamStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for UpstreamStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            UpstreamStream::Tls(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for UpstreamStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            UpstreamStream::Tls(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            UpstreamStream::Tls(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            UpstreamStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            UpstreamStream::Tls(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use temporal_sdk_core_protos::grpc::health::v1::{
+        health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+        HealthCheckResponse,
+    };
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn splits_messages_from_trailers() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&[0, 0, 0, 0, 2, 7, 7]);
+        let trailers = b"grpc-status:0\r\nGrpc-Message: ok\r\nbogus\r\n";
+        buf.extend_from_slice(&[TRAILERS_FRAME_FLAG, 0, 0, 0, trailers.len() as u8]);
+        buf.extend_from_slice(&trailers[..10]);
+        assert_eq!(
+            next_frame(&mut buf),
+            Some(Frame::Message(Bytes::from_static(&[0, 0, 0, 0, 2, 7, 7])))
+        );
+        // The trailers frame isn't complete yet
+        assert_eq!(next_frame(&mut buf), None);
+        buf.extend_from_slice(&trailers[10..]);
+        let Some(Frame::Trailers(t)) = next_frame(&mut buf) else {
+            panic!("expected trailers");
+        };
+        assert_eq!(t["grpc-status"], "0");
+        assert_eq!(t["grpc-message"], "ok");
+        assert_eq!(t.len(), 2);
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn calls_are_made_with_grpc_web() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let svc = service_fn(|req: Request<Body>| async move {
+                assert_eq!(req.version(), http::Version::HTTP_11);
+                assert_eq!(req.uri().path(), "/grpc.health.v1.Health/Check");
+                assert_eq!(req.headers()[header::CONTENT_TYPE], GRPC_WEB_CONTENT_TYPE);
+                let msg = HealthCheckResponse {
+                    status: ServingStatus::Serving as i32,
+                }
+                .encode_to_vec();
+                let trailers = b"grpc-status: 0\r\n";
+                let mut body = vec![0];
+                body.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+                body.extend_from_slice(&msg);
+                body.push(TRAILERS_FRAME_FLAG);
+                body.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+                body.extend_from_slice(trailers);
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header(header::CONTENT_TYPE, GRPC_WEB_CONTENT_TYPE)
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+            });
+            hyper::server::conn::Http::new()
+                .http1_only(true)
+                .serve_connection(stream, svc)
+                .await
+                .unwrap();
+        });
+
+        let opts = crate::ClientOptionsBuilder::default()
+            .target_url(Url::parse(&format!("http://{addr}")).unwrap())
+            .client_name("grpc-web-test")
+            .client_version("0.1.0")
+            .build()
+            .unwrap();
+        let channel = connect_grpc_web(&opts, None, false).await.unwrap();
+        let resp = HealthClient::new(channel)
+            .check(HealthCheckRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(resp.into_inner().status(), ServingStatus::Serving);
+    }
+
+    #[tokio::test]
+    async fn unreachable_servers_are_unavailable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let opts = crate::ClientOptionsBuilder::default()
+            .target_url(Url::parse(&format!("http://{addr}")).unwrap())
+            .client_name("grpc-web-test")
+            .client_version("0.1.0")
+            .build()
+            .unwrap();
+        let channel = connect_grpc_web(&opts, None, true).await.unwrap();
+        let err = HealthClient::new(channel)
+            .check(HealthCheckRequest::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unavailable);
+    }
+}
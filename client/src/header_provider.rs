@@ -0,0 +1,244 @@
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tonic::{
+    metadata::{MetadataKey, MetadataMap},
+    Request, Status,
+};
+
+/// Headers are refreshed this long before they expire, so that calls don't go out with
+/// credentials which expire in flight
+const REFRESH_BEFORE_EXPIRY: Duration = Duration::from_secs(30);
+/// A fetch from the provider taking longer than this is treated as a failure
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Supplies headers which are attached to every call the client makes, for example to provide
+/// OAuth or JWT credentials which must be refreshed periodically. Headers are fetched on the first
+/// call and cached until shortly before they expire, at which point the next call fetches them
+/// again.
+///
+/// Provided headers take precedence over [crate::ClientOptions::headers] and
+/// [crate::ClientOptions::api_key], but not over headers set on an individual request.
+#[async_trait::async_trait]
+pub trait HeaderProvider: Debug + Send + Sync {
+    /// Returns the current headers. If this fails, or takes longer than ten seconds, calls keep
+    /// using previously provided headers until they expire, and otherwise fail with an
+    /// `Unavailable` status, which is retried.
+    async fn get_headers(&self) -> Result<ProvidedHeaders, anyhow::Error>;
+}
+
+/// Headers returned by a [HeaderProvider]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProvidedHeaders {
+    /// Headers to attach to each call
+    pub headers: HashMap<String, String>,
+    /// When the headers stop being valid. If unset, they are used for the life of the client.
+    pub expires_at: Option<SystemTime>,
+}
+
+impl ProvidedHeaders {
+    fn needs_refresh(&self) -> bool {
+        self.expires_at
+            .is_some_and(|exp| SystemTime::now() + REFRESH_BEFORE_EXPIRY >= exp)
+    }
+
+    fn expired(&self) -> bool {
+        self.expires_at.is_some_and(|exp| SystemTime::now() >= exp)
+    }
+
+    fn apply_to_metadata(&self, metadata: &mut MetadataMap) {
+        for (key, val) in self.headers.iter() {
+            // Only if not already present. Headers from the client options are added after these
+            // by the interceptor, which won't overwrite them.
+            if !metadata.contains_key(key) {
+                // Ignore invalid keys/values
+                if let (Ok(key), Ok(val)) = (MetadataKey::from_str(key), val.parse()) {
+                    metadata.insert(key, val);
+                }
+            }
+        }
+    }
+}
+
+/// Caches the headers from a [HeaderProvider], shared by all clones of a client
+#[derive(Debug)]
+pub struct CachedHeaderProvider {
+    provider: Arc<dyn HeaderProvider>,
+    cached: Mutex<Option<ProvidedHeaders>>,
+    fetch_timeout: Duration,
+}
+
+impl CachedHeaderProvider {
+    pub(crate) fn new(provider: Arc<dyn HeaderProvider>) -> Self {
+        Self {
+            provider,
+            cached: Mutex::new(None),
+            fetch_timeout: FETCH_TIMEOUT,
+        }
+    }
+
+    /// Attaches the current headers to the request, fetching them first if needed
+    pub(crate) async fn apply_to_request<T>(&self, req: &mut Request<T>) -> Result<(), Status> {
+        let headers = self.current_headers().await?;
+        headers.apply_to_metadata(req.metadata_mut());
+        Ok(())
+    }
+
+    async fn current_headers(&self) -> Result<ProvidedHeaders, Status> {
+        // The lock is never held across the fetch, so a slow provider can't block calls which
+        // could use unexpired headers
+        let cached = self.cached.lock().clone();
+        if let Some(c) = cached.as_ref().filter(|c| !c.needs_refresh()) {
+            return Ok(c.clone());
+        }
+        let fetched = tokio::time::timeout(self.fetch_timeout, self.provider.get_headers())
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out fetching headers")));
+        match fetched {
+            Ok(fresh) => {
+                *self.cached.lock() = Some(fresh.clone());
+                Ok(fresh)
+            }
+            Err(e) => match cached.as_ref().filter(|c| !c.expired()) {
+                Some(c) => {
+                    warn!(error=?e, "Header provider failed, using previous headers until they expire");
+                    Ok(c.clone())
+                }
+                None => Err(Status::unavailable(format!(
+                    "Header provider failed to provide headers: {e:#}"
+                ))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+        valid_for: Duration,
+        fail_after_first: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl HeaderProvider for CountingProvider {
+        async fn get_headers(&self) -> Result<ProvidedHeaders, anyhow::Error> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.fail_after_first && call > 1 {
+                anyhow::bail!("token endpoint down");
+            }
+            Ok(ProvidedHeaders {
+                headers: HashMap::from([
+                    ("authorization".to_string(), format!("Bearer token-{call}")),
+                    ("x-other".to_string(), "provided".to_string()),
+                ]),
+                expires_at: Some(SystemTime::now() + self.valid_for),
+            })
+        }
+    }
+
+    fn provider(valid_for: Duration, fail_after_first: bool) -> Arc<CountingProvider> {
+        Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            valid_for,
+            fail_after_first,
+        })
+    }
+
+    fn auth_header(req: &Request<()>) -> &str {
+        req.metadata()
+            .get("authorization")
+            .unwrap()
+            .to_str()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn caches_until_near_expiry() {
+        let long_lived = provider(Duration::from_secs(600), false);
+        let cache = CachedHeaderProvider::new(long_lived.clone());
+        for _ in 0..3 {
+            let mut req = Request::new(());
+            cache.apply_to_request(&mut req).await.unwrap();
+            assert_eq!(auth_header(&req), "Bearer token-1");
+        }
+        assert_eq!(long_lived.calls.load(Ordering::SeqCst), 1);
+
+        // Headers which expire within the refresh window are fetched again on every call
+        let short_lived = provider(Duration::from_secs(5), false);
+        let cache = CachedHeaderProvider::new(short_lived.clone());
+        for i in 1..=2 {
+            let mut req = Request::new(());
+            cache.apply_to_request(&mut req).await.unwrap();
+            assert_eq!(auth_header(&req), format!("Bearer token-{i}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn request_headers_take_precedence() {
+        let cache = CachedHeaderProvider::new(provider(Duration::from_secs(600), false));
+        let mut req = Request::new(());
+        req.metadata_mut()
+            .insert("x-other", "from-request".parse().unwrap());
+        cache.apply_to_request(&mut req).await.unwrap();
+        assert_eq!(req.metadata().get("x-other").unwrap(), "from-request");
+        assert_eq!(auth_header(&req), "Bearer token-1");
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_uses_unexpired_headers() {
+        let cache = CachedHeaderProvider::new(provider(Duration::from_secs(5), true));
+        let mut req = Request::new(());
+        cache.apply_to_request(&mut req).await.unwrap();
+        let mut req = Request::new(());
+        cache.apply_to_request(&mut req).await.unwrap();
+        assert_eq!(auth_header(&req), "Bearer token-1");
+
+        let cache = CachedHeaderProvider::new(provider(Duration::ZERO, true));
+        cache.apply_to_request(&mut Request::new(())).await.unwrap();
+        let err = cache
+            .apply_to_request(&mut Request::new(()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+    }
+
+    #[derive(Debug)]
+    struct HangingProvider;
+
+    #[async_trait::async_trait]
+    impl HeaderProvider for HangingProvider {
+        async fn get_headers(&self) -> Result<ProvidedHeaders, anyhow::Error> {
+            futures::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_refresh_times_out_and_uses_unexpired_headers() {
+        let mut cache = CachedHeaderProvider::new(Arc::new(HangingProvider));
+        cache.fetch_timeout = Duration::from_millis(10);
+        *cache.cached.lock() = Some(ProvidedHeaders {
+            headers: HashMap::from([("authorization".to_string(), "Bearer old".to_string())]),
+            expires_at: Some(SystemTime::now() + Duration::from_secs(5)),
+        });
+        let mut req = Request::new(());
+        cache.apply_to_request(&mut req).await.unwrap();
+        assert_eq!(auth_header(&req), "Bearer old");
+
+        *cache.cached.lock() = None;
+        let err = cache
+            .apply_to_request(&mut Request::new(()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+    }
+}
@@ -0,0 +1,122 @@
+//! Fetching whole workflow histories, for replay and export
+
+use crate::{Result, WorkflowClientTrait};
+use futures::{stream::BoxStream, Future, StreamExt};
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::WorkflowExecution, enums::v1::HistoryEventFilterType, history::v1::History,
+};
+
+/// Fetches whole workflow histories, following page tokens. Fetched histories can be turned into
+/// a `HistoryInfo` with `HistoryInfo::new_from_history`, for replay.
+pub trait HistoryFetchExt: WorkflowClientTrait + Clone + Send + Sync + 'static {
+    /// Fetch every page of a workflow run's history. If `run_id` is unset, the latest run's
+    /// history is fetched. With [HistoryEventFilterType::CloseEvent], only the close event is
+    /// fetched, so the history is empty if the run hasn't closed.
+    fn fetch_workflow_history(
+        &self,
+        workflow_id: impl Into<String>,
+        run_id: Option<String>,
+        filter: HistoryEventFilterType,
+    ) -> impl Future<Output = Result<History>> + Send {
+        let client = self.clone();
+        let execution = WorkflowExecution {
+            workflow_id: workflow_id.into(),
+            run_id: run_id.unwrap_or_default(),
+        };
+        async move { fetch_history(&client, execution, filter).await }
+    }
+
+    /// Fetch the histories of many workflow runs, at most `max_concurrent` at a time. Histories
+    /// are streamed as each finishes downloading, so not necessarily in the order given, and
+    /// failing to fetch one doesn't stop the rest from being fetched.
+    fn fetch_workflow_histories<I>(
+        &self,
+        executions: I,
+        filter: HistoryEventFilterType,
+        max_concurrent: usize,
+    ) -> BoxStream<'static, (WorkflowExecution, Result<History>)>
+    where
+        I: IntoIterator<Item = WorkflowExecution>,
+        I::IntoIter: Send + 'static,
+    {
+        let client = self.clone();
+        futures::stream::iter(executions)
+            .map(move |execution| {
+                let client = client.clone();
+                async move {
+                    let res = fetch_history(&client, execution.clone(), filter).await;
+                    (execution, res)
+                }
+            })
+            .buffer_unordered(max_concurrent.max(1))
+            .boxed()
+    }
+}
+
+impl<T> HistoryFetchExt for T where T: WorkflowClientTrait + Clone + Send + Sync + 'static {}
+
+pub(crate) async fn fetch_history(
+    client: &(impl WorkflowClientTrait + Sync),
+    execution: WorkflowExecution,
+    filter: HistoryEventFilterType,
+) -> Result<History> {
+    let mut events = vec![];
+    let mut page_token = vec![];
+    loop {
+        let resp = client
+            .get_filtered_workflow_execution_history(execution.clone(), page_token, filter)
+            .await?;
+        events.extend(resp.history.unwrap_or_default().events);
+        if resp.next_page_token.is_empty() {
+            return Ok(History { events });
+        }
+        page_token = resp.next_page_token;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockWorkflowClientTrait;
+    use temporal_sdk_core_protos::temporal::api::{
+        history::v1::HistoryEvent, workflowservice::v1::GetWorkflowExecutionHistoryResponse,
+    };
+
+    #[tokio::test]
+    async fn fetches_every_page() {
+        let mut client = MockWorkflowClientTrait::new();
+        client
+            .expect_get_filtered_workflow_execution_history()
+            .times(3)
+            .returning(|_, token, _| {
+                let (event_id, next) = match token.as_slice() {
+                    [] => (Some(1), vec![1]),
+                    // Pages may be empty while the server has more to return
+                    [1] => (None, vec![2]),
+                    _ => (Some(2), vec![]),
+                };
+                Ok(GetWorkflowExecutionHistoryResponse {
+                    history: Some(History {
+                        events: event_id
+                            .map(|event_id| HistoryEvent {
+                                event_id,
+                                ..Default::default()
+                            })
+                            .into_iter()
+                            .collect(),
+                    }),
+                    next_page_token: next,
+                    ..Default::default()
+                })
+            });
+        let history = fetch_history(
+            &client,
+            WorkflowExecution::default(),
+            HistoryEventFilterType::AllEvent,
+        )
+        .await
+        .unwrap();
+        let ids: Vec<_> = history.events.iter().map(|e| e.event_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+}
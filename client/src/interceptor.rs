@@ -0,0 +1,209 @@
+use std::{
+    any::Any,
+    fmt::Debug,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tonic::{metadata::MetadataMap, Request, Response, Status};
+
+/// Hooks run around every attempt of every call a client makes, for things like audit logging,
+/// custom metrics, modifying requests, or injecting failures. Register them with
+/// [crate::ClientOptions::interceptors].
+///
+/// Interceptors see each attempt separately, so a call which is retried is seen once per attempt.
+pub trait ClientInterceptor: Debug + Send + Sync {
+    /// Called before each attempt with the (snake case) name of the method being called, the
+    /// request's metadata, and the request itself, which can be downcast to the specific request
+    /// type. Either may be modified. Returning an error fails the attempt with it without
+    /// contacting the server, and it is retried if the error is retryable.
+    fn before_call(
+        &self,
+        _method: &str,
+        _metadata: &mut MetadataMap,
+        _request: &mut dyn Any,
+    ) -> Result<(), Status> {
+        Ok(())
+    }
+
+    /// Called after each attempt with the response's metadata, or the error the attempt failed
+    /// with, and how long it took. Called whenever this interceptor's `before_call` ran, even if
+    /// the attempt was failed by it or an interceptor after it, but not if an interceptor before it
+    /// failed the attempt.
+    fn after_call(
+        &self,
+        _method: &str,
+        _result: Result<&MetadataMap, &Status>,
+        _elapsed: Duration,
+    ) {
+    }
+}
+
+/// Runs `call` wrapped by the provided interceptors. `before_call` runs in registration order, and
+/// `after_call` in reverse, only for the interceptors whose `before_call` ran.
+pub(crate) async fn intercepted_call<Req, Resp, F, Fut>(
+    interceptors: &[Arc<dyn ClientInterceptor>],
+    method: &'static str,
+    req: Request<Req>,
+    call: F,
+) -> Result<Response<Resp>, Status>
+where
+    Req: 'static,
+    F: FnOnce(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<Resp>, Status>>,
+{
+    if interceptors.is_empty() {
+        return call(req).await;
+    }
+    let started = Instant::now();
+    let (mut metadata, extensions, mut message) = req.into_parts();
+    let mut ran = 0;
+    let mut rejection = None;
+    for i in interceptors {
+        ran += 1;
+        if let Err(status) = i.before_call(method, &mut metadata, &mut message) {
+            rejection = Some(status);
+            break;
+        }
+    }
+    let res = match rejection {
+        Some(status) => Err(status),
+        None => call(Request::from_parts(metadata, extensions, message)).await,
+    };
+    let elapsed = started.elapsed();
+    for i in interceptors[..ran].iter().rev() {
+        i.after_call(method, res.as_ref().map(Response::metadata), elapsed);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use temporal_sdk_core_protos::temporal::api::workflowservice::v1::{
+        DescribeNamespaceRequest, DescribeNamespaceResponse,
+    };
+    use tonic::Code;
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        name: &'static str,
+        reject: bool,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ClientInterceptor for Recorder {
+        fn before_call(
+            &self,
+            method: &str,
+            metadata: &mut MetadataMap,
+            request: &mut dyn Any,
+        ) -> Result<(), Status> {
+            self.log
+                .lock()
+                .push(format!("{} before {method}", self.name));
+            metadata.insert("x-intercepted", self.name.parse().unwrap());
+            if let Some(r) = request.downcast_mut::<DescribeNamespaceRequest>() {
+                r.namespace = "rewritten".to_string();
+            }
+            if self.reject {
+                return Err(Status::unavailable("chaos"));
+            }
+            Ok(())
+        }
+
+        fn after_call(
+            &self,
+            method: &str,
+            result: Result<&MetadataMap, &Status>,
+            _elapsed: Duration,
+        ) {
+            let outcome = match result {
+                Ok(_) => "ok".to_string(),
+                Err(s) => format!("{:?}", s.code()),
+            };
+            self.log
+                .lock()
+                .push(format!("{} after {method} {outcome}", self.name));
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_interceptors_in_order_and_can_modify_requests() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let interceptors: Vec<Arc<dyn ClientInterceptor>> = vec![
+            Arc::new(Recorder {
+                name: "a",
+                log: log.clone(),
+                ..Default::default()
+            }),
+            Arc::new(Recorder {
+                name: "b",
+                log: log.clone(),
+                ..Default::default()
+            }),
+        ];
+        let req = Request::new(DescribeNamespaceRequest {
+            namespace: "orig".to_string(),
+            ..Default::default()
+        });
+        let res = intercepted_call(&interceptors, "describe_namespace", req, |r| async move {
+            assert_eq!(r.get_ref().namespace, "rewritten");
+            assert_eq!(r.metadata().get("x-intercepted").unwrap(), "b");
+            Ok(Response::new(DescribeNamespaceResponse::default()))
+        })
+        .await;
+        assert!(res.is_ok());
+        assert_eq!(
+            *log.lock(),
+            vec![
+                "a before describe_namespace",
+                "b before describe_namespace",
+                "b after describe_namespace ok",
+                "a after describe_namespace ok",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejecting_interceptor_skips_call_and_later_interceptors() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let interceptors: Vec<Arc<dyn ClientInterceptor>> = vec![
+            Arc::new(Recorder {
+                name: "first",
+                log: log.clone(),
+                ..Default::default()
+            }),
+            Arc::new(Recorder {
+                name: "chaos",
+                reject: true,
+                log: log.clone(),
+            }),
+            Arc::new(Recorder {
+                name: "never",
+                log: log.clone(),
+                ..Default::default()
+            }),
+        ];
+        let res = intercepted_call(
+            &interceptors,
+            "describe_namespace",
+            Request::new(DescribeNamespaceRequest::default()),
+            |_| async {
+                Err::<Response<DescribeNamespaceResponse>, _>(Status::internal("call was made"))
+            },
+        )
+        .await;
+        assert_eq!(res.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(
+            *log.lock(),
+            vec![
+                "first before describe_namespace",
+                "chaos before describe_namespace",
+                "chaos after describe_namespace Unavailable",
+                "first after describe_namespace Unavailable",
+            ]
+        );
+    }
+}
@@ -7,14 +7,66 @@
 #[macro_use]
 extern crate tracing;
 
+mod async_activity;
+mod balance;
+mod batch;
+mod concurrency;
+mod connection;
+mod failover;
+mod grpc_web;
+mod header_provider;
+mod history_fetch;
+mod interceptor;
+mod list_streams;
+mod local_socket;
 mod metrics;
+mod namespace_admin;
+mod proxy;
 mod raw;
+mod reset;
 mod retry;
+mod schedule;
+mod spiffe;
+mod tls_reload;
+mod trace_propagation;
 mod worker_registry;
+mod worker_versioning;
+mod workflow_count;
 mod workflow_handle;
+mod workflow_update;
 
-pub use crate::retry::{CallType, RetryClient, RETRYABLE_ERROR_CODES};
-pub use raw::{HealthService, OperatorService, TestService, WorkflowService};
+pub use crate::{
+    balance::{LoadBalancingOptions, LoadBalancingPolicy},
+    batch::{
+        wait_for_batch_operation, BatchOperation, BatchOperationOptions,
+        BatchOperationOptionsBuilder, BatchOperationProgress, BatchTarget,
+    },
+    concurrency::RpcConcurrencyLimit,
+    connection::{ConnectionState, ReconnectPolicy},
+    failover::{FailoverEvent, FailoverListener, FailoverOptions, FailoverReason},
+    grpc_web::TransportProtocol,
+    header_provider::{HeaderProvider, ProvidedHeaders},
+    history_fetch::HistoryFetchExt,
+    interceptor::ClientInterceptor,
+    list_streams::{ListStreamOptions, ListStreamsExt},
+    namespace_admin::NamespaceAdminExt,
+    proxy::{HttpConnectProxyOptions, Socks5ProxyOptions},
+    reset::{
+        ResetReapply, ResetTarget, ResetWorkflowExt, ResetWorkflowOptions,
+        ResetWorkflowOptionsBuilder,
+    },
+    retry::{CallType, RetryBudget, RetryClient, RETRYABLE_ERROR_CODES},
+    schedule::{
+        CalendarSpec, CalendarSpecBuilder, CreateScheduleOptions, IntervalSpec, Schedule,
+        ScheduleAction, ScheduleBackfill, ScheduleBuilder, SchedulePatch, SchedulePolicies,
+        ScheduleRange, ScheduleSpec, ScheduleSpecBuilder,
+    },
+    spiffe::SpiffeWorkloadOptions,
+    tls_reload::TlsReloadConfig,
+    trace_propagation::TracePropagator,
+};
+pub use async_activity::{ActivityIdentifier, AsyncActivityHandle, AsyncActivityHeartbeat};
+pub use raw::{CloudService, HealthService, OperatorService, TestService, WorkflowService};
 pub use temporal_sdk_core_protos::temporal::api::{
     enums::v1::ArchivalState,
     filter::v1::{StartTimeFilter, StatusFilter, WorkflowExecutionFilter, WorkflowTypeFilter},
@@ -24,22 +76,45 @@ pub use temporal_sdk_core_protos::temporal::api::{
     },
 };
 pub use tonic;
+#[cfg(feature = "telemetry")]
+pub use trace_propagation::OtelTracePropagator;
 pub use worker_registry::{Slot, SlotManager, SlotProvider, WorkerKey};
-pub use workflow_handle::{WorkflowExecutionInfo, WorkflowExecutionResult};
+pub use worker_versioning::{
+    BuildIdOperation, BuildIdReachability, CompatibleVersionSet, CompatibleVersionSets,
+};
+pub use workflow_count::{WorkflowExecutionCount, WorkflowExecutionCountGroup};
+pub use workflow_handle::{
+    GetWorkflowResultOpts, WorkflowExecutionInfo, WorkflowExecutionResult, WorkflowHandle,
+    WorkflowStartOutcome,
+};
+pub use workflow_update::{
+    execute_update_with_start, execute_workflow_update, UpdateWaitStage, UpdateWithStartOutcome,
+    UpdateWithStartWorkflow, WorkflowUpdate, WorkflowUpdateOutcome,
+};
 
 use crate::{
+    balance::FrontendChannel,
+    concurrency::RpcLimiter,
+    connection::ConnectionMonitor,
+    header_provider::CachedHeaderProvider,
+    local_socket::{LocalSocket, LOCAL_SOCKET_AUTHORITY},
     metrics::{GrpcMetricSvc, MetricsContext},
-    raw::{sealed::RawClientLike, AttachMetricLabels},
+    proxy::ProxyConfig,
+    raw::{sealed::RawClientLike, AttachMetricLabels, CallAttempt},
     sealed::WfHandleClient,
+    tls_reload::{ChannelTarget, ReloadableChannel},
     workflow_handle::UntypedWorkflowHandle,
 };
 use backoff::{exponential, ExponentialBackoff, SystemClock};
 use http::{uri::InvalidUri, Uri};
 use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
+use prost::Message;
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fmt::{Debug, Formatter},
+    future::Future,
     ops::{Deref, DerefMut},
     str::FromStr,
     sync::Arc,
@@ -47,13 +122,35 @@ use std::{
 };
 use temporal_sdk_core_api::telemetry::metrics::TemporalMeter;
 use temporal_sdk_core_protos::{
-    coresdk::{workflow_commands::QueryResult, IntoPayloadsExt},
-    grpc::health::v1::health_client::HealthClient,
+    coresdk::{workflow_commands::QueryResult, FromPayloadsExt, IntoPayloadsExt},
+    google::rpc::Status as RpcStatus,
+    grpc::health::v1::{
+        health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+    },
     temporal::api::{
-        common::v1::{Header, Payload, Payloads, RetryPolicy, WorkflowExecution, WorkflowType},
-        enums::v1::{TaskQueueKind, WorkflowIdReusePolicy},
+        cloud::cloudservice::v1::cloud_service_client::CloudServiceClient,
+        common::v1::{
+            Header, Memo, Payload, Payloads, RetryPolicy, WorkflowExecution, WorkflowType,
+        },
+        enums::v1::{
+            HistoryEventFilterType, IndexedValueType, TaskQueueKind, TaskReachability,
+            UpdateWorkflowExecutionLifecycleStage, WorkflowExecutionStatus,
+            WorkflowIdConflictPolicy, WorkflowIdReusePolicy,
+        },
+        errordetails::v1::MultiOperationExecutionFailure,
         failure::v1::Failure,
-        operatorservice::v1::operator_service_client::OperatorServiceClient,
+        namespace::v1::{NamespaceConfig, UpdateNamespaceInfo},
+        nexus::v1::EndpointSpec,
+        operatorservice::v1::{
+            operator_service_client::OperatorServiceClient, AddSearchAttributesRequest,
+            AddSearchAttributesResponse, CreateNexusEndpointRequest, CreateNexusEndpointResponse,
+            DeleteNamespaceRequest, DeleteNamespaceResponse, DeleteNexusEndpointRequest,
+            DeleteNexusEndpointResponse, GetNexusEndpointRequest, GetNexusEndpointResponse,
+            ListNexusEndpointsRequest, ListNexusEndpointsResponse, ListSearchAttributesRequest,
+            ListSearchAttributesResponse, RemoveSearchAttributesRequest,
+            RemoveSearchAttributesResponse, UpdateNexusEndpointRequest,
+            UpdateNexusEndpointResponse,
+        },
         query::v1::WorkflowQuery,
         replication::v1::ClusterReplicationConfig,
         taskqueue::v1::TaskQueue,
@@ -66,6 +163,7 @@ use temporal_sdk_core_protos::{
 use tonic::{
     body::BoxBody,
     client::GrpcService,
+    codec::CompressionEncoding,
     codegen::InterceptedService,
     metadata::{MetadataKey, MetadataMap, MetadataValue},
     service::Interceptor,
@@ -78,23 +176,31 @@ use uuid::Uuid;
 
 static CLIENT_NAME_HEADER_KEY: &str = "client-name";
 static CLIENT_VERSION_HEADER_KEY: &str = "client-version";
+/// Carries the namespace of each call, which Temporal Cloud uses to route calls authenticated
+/// with an API key
+pub(crate) static TEMPORAL_NAMESPACE_HEADER_KEY: &str = "temporal-namespace";
+/// The gRPC service name health checks ask about
+static WORKFLOW_SERVICE_NAME: &str = "temporal.api.workflowservice.v1.WorkflowService";
+static CLOUD_API_VERSION_HEADER_KEY: &str = "temporal-cloud-api-version";
+/// The version of the Cloud Operations API protos the [CloudOpsClient] is generated from
+pub static CLOUD_OPS_API_VERSION: &str = "v0.7.1";
 /// These must match the gRPC method names, not the snake case versions that exist in the Rust code.
 static LONG_POLL_METHOD_NAMES: [&str; 3] = [
     "PollWorkflowTaskQueue",
     "PollActivityTaskQueue",
     "PollWorkflowExecutionUpdateRequest",
 ];
-/// The server times out polls after 60 seconds. Set our timeout to be slightly beyond that.
-const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(70);
-const OTHER_CALL_TIMEOUT: Duration = Duration::from_secs(30);
 
 type Result<T, E = tonic::Status> = std::result::Result<T, E>;
 
 /// Options for the connection to the temporal server. Construct with [ClientOptionsBuilder]
 #[derive(Clone, Debug, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
 #[non_exhaustive]
 pub struct ClientOptions {
-    /// The URL of the Temporal server to connect to
+    /// The URL of the Temporal server to connect to. Servers on this machine may also be reached
+    /// through a Unix domain socket, as `unix:///path/to/socket`, or on Windows a named pipe, as
+    /// `npipe://./pipe/name`. Load balancing, proxies, and gRPC-Web don't apply to those.
     #[builder(setter(into))]
     pub target_url: Url,
 
@@ -118,10 +224,44 @@ pub struct ClientOptions {
     #[builder(setter(strip_option), default)]
     pub tls_cfg: Option<TlsConfig>,
 
+    /// If set, the mTLS client certificate and key are read from these files, and the client
+    /// reconnects using the new ones whenever they change. Implies TLS even if
+    /// [ClientOptions::tls_cfg] is unset.
+    #[builder(setter(strip_option), default)]
+    pub tls_reload: Option<TlsReloadConfig>,
+
+    /// If set, the mTLS client identity is the X.509 SVID issued by this SPIFFE Workload API, and
+    /// the client reconnects whenever it is rotated. Takes precedence over
+    /// [ClientOptions::tls_reload], and implies TLS even if [ClientOptions::tls_cfg] is unset.
+    #[builder(setter(strip_option), default)]
+    pub spiffe: Option<SpiffeWorkloadOptions>,
+
+    /// If set, connecting neither contacts the server nor fails if it can't be reached. The
+    /// connection is instead made by the first call, or by [ConfiguredClient::ensure_connected],
+    /// which also learns the server's capabilities. Until then [ConfiguredClient::capabilities] is
+    /// unset. Useful for short-lived processes which may not make any calls at all.
+    #[builder(default)]
+    pub lazy_connect: bool,
+
+    /// If set, calls are spread across every address the host name of
+    /// [ClientOptions::target_url] resolves to, rather than all sharing one connection. See
+    /// [LoadBalancingOptions].
+    #[builder(setter(strip_option), default)]
+    pub load_balancing: Option<LoadBalancingOptions>,
+
+    /// If set, the client fails over to other endpoints when [ClientOptions::target_url] can't be
+    /// reached. See [FailoverOptions].
+    #[builder(setter(strip_option), default)]
+    pub failover: Option<FailoverOptions>,
+
     /// Retry configuration for the server client. Default is [RetryConfig::default]
     #[builder(default)]
     pub retry_config: RetryConfig,
 
+    /// Deadlines for calls to the server, by kind of call. Default is [RpcTimeouts::default]
+    #[builder(default)]
+    pub rpc_timeouts: RpcTimeouts,
+
     /// If set, override the origin used when connecting. May be useful in rare situations where tls
     /// verification needs to use a different name from what should be set as the `:authority`
     /// header. If [TlsConfig::domain] is set, and this is not, this will be set to
@@ -130,7 +270,9 @@ pub struct ClientOptions {
     #[builder(default)]
     pub override_origin: Option<Uri>,
 
-    /// If set (which it is by default), HTTP2 gRPC keep alive will be enabled.
+    /// If set (which it is by default), HTTP2 gRPC keep alive will be enabled. Lowering the
+    /// interval may be needed for connections to survive NATs or load balancers which drop idle
+    /// connections, since long polls can leave a connection quiet for up to a minute.
     #[builder(default = "Some(ClientKeepAliveConfig::default())")]
     pub keep_alive: Option<ClientKeepAliveConfig>,
 
@@ -139,9 +281,69 @@ pub struct ClientOptions {
     pub headers: Option<HashMap<String, String>>,
 
     /// API key which is set as the "Authorization" header with "Bearer " prepended. This will only
-    /// be applied if the headers don't already have an "Authorization" header.
+    /// be applied if the headers don't already have an "Authorization" header. Every call which
+    /// targets a namespace also carries it in the "temporal-namespace" header, as Temporal Cloud
+    /// requires alongside API keys. The key can be changed without reconnecting using
+    /// [ConfiguredClient::set_api_key].
     #[builder(default)]
     pub api_key: Option<String>,
+
+    /// If set, headers from this provider are attached to every call, and refreshed as they
+    /// expire. Use this for credentials which must be rotated without reconnecting the client.
+    /// See [HeaderProvider].
+    #[builder(setter(strip_option), default)]
+    pub header_provider: Option<Arc<dyn HeaderProvider>>,
+
+    /// Interceptors run around every call attempt, in order. See [ClientInterceptor].
+    #[builder(default)]
+    pub interceptors: Vec<Arc<dyn ClientInterceptor>>,
+
+    /// Writes the trace context of the code making each call into the call's metadata, so the
+    /// server's traces link up with the caller's. With the `telemetry` feature, defaults to
+    /// propagating the current OpenTelemetry context as W3C Trace Context headers, see
+    /// `OtelTracePropagator`.
+    #[builder(setter(strip_option), default = "default_trace_propagator()")]
+    pub trace_propagator: Option<Arc<dyn TracePropagator>>,
+
+    /// If set, caps how many calls, other than polls for tasks, may be in flight at once across
+    /// all clones of the client. See [RpcConcurrencyLimit].
+    #[builder(setter(strip_option), default)]
+    pub rpc_concurrency_limit: Option<RpcConcurrencyLimit>,
+
+    /// If set, connect to the server through this HTTP CONNECT proxy. If not set, the proxy is
+    /// read from the `HTTPS_PROXY` (when using TLS) or `HTTP_PROXY` environment variables,
+    /// respecting `NO_PROXY`. See [HttpConnectProxyOptions::from_env].
+    #[builder(setter(strip_option), default)]
+    pub http_connect_proxy: Option<HttpConnectProxyOptions>,
+
+    /// If set, connect to the server through this SOCKS5 proxy. Takes precedence over
+    /// [ClientOptions::http_connect_proxy] and the proxy environment variables.
+    #[builder(setter(strip_option), default)]
+    pub socks5_proxy: Option<Socks5ProxyOptions>,
+
+    /// If set, compress outgoing calls with this encoding. The server must support it.
+    #[builder(setter(strip_option), default)]
+    pub send_compression: Option<CompressionEncoding>,
+
+    /// Encodings the server is told it may use to compress responses. May greatly shrink large
+    /// responses like workflow histories over slow links, at some CPU cost. Empty by default.
+    #[builder(default)]
+    pub accept_compression: Vec<CompressionEncoding>,
+
+    /// The protocol to call the server with. See [TransportProtocol::GrpcWeb] for what changes
+    /// when calling with gRPC-Web.
+    #[builder(default)]
+    pub transport_protocol: TransportProtocol,
+}
+
+#[cfg(feature = "telemetry")]
+fn default_trace_propagator() -> Option<Arc<dyn TracePropagator>> {
+    Some(Arc::new(OtelTracePropagator::default()))
+}
+
+#[cfg(not(feature = "telemetry"))]
+fn default_trace_propagator() -> Option<Arc<dyn TracePropagator>> {
+    None
 }
 
 /// Configuration options for TLS
@@ -174,6 +376,10 @@ pub struct ClientKeepAliveConfig {
     pub interval: Duration,
     /// Timeout that the keep alive must be responded to within or the connection will be closed.
     pub timeout: Duration,
+    /// If true, pings are sent even while no calls are in flight, keeping idle connections from
+    /// being dropped by NATs or load balancers with aggressive idle timeouts. Servers may close
+    /// connections which ping more often than they permit while idle.
+    pub permit_without_calls: bool,
 }
 
 impl Default for ClientKeepAliveConfig {
@@ -181,6 +387,7 @@ impl Default for ClientKeepAliveConfig {
         Self {
             interval: Duration::from_secs(30),
             timeout: Duration::from_secs(15),
+            permit_without_calls: true,
         }
     }
 }
@@ -191,17 +398,26 @@ pub struct RetryConfig {
     /// initial wait time before the first retry.
     pub initial_interval: Duration,
     /// randomization jitter that is used as a multiplier for the current retry interval
-    /// and is added or subtracted from the interval length.
+    /// and is added or subtracted from the interval length. Must be between 0 and 1.
     pub randomization_factor: f64,
     /// rate at which retry time should be increased, until it reaches max_interval.
     pub multiplier: f64,
-    /// maximum amount of time to wait between retries.
+    /// maximum amount of time to wait between retries. Must be at least `initial_interval`.
     pub max_interval: Duration,
     /// maximum total amount of time requests should be retried for, if None is set then no limit
     /// will be used.
     pub max_elapsed_time: Option<Duration>,
     /// maximum number of retry attempts.
     pub max_retries: usize,
+    /// gRPC status codes which are retried. Defaults to [RETRYABLE_ERROR_CODES]. Long polls always
+    /// use the default codes, since they are retried indefinitely no matter what.
+    pub retryable_codes: Cow<'static, [Code]>,
+    /// If set, retries performed with this config draw from the provided budget, and stop once it
+    /// is depleted. Long polls are never limited by the budget. See [RetryBudget].
+    pub retry_budget: Option<RetryBudget>,
+    /// How polls for tasks, which aren't retried according to the rest of this config, behave
+    /// while the server can't be reached. See [ReconnectPolicy].
+    pub poll_reconnect: ReconnectPolicy,
 }
 
 impl Default for RetryConfig {
@@ -213,22 +429,81 @@ impl Default for RetryConfig {
             max_interval: Duration::from_secs(5), // until it reaches 5 seconds.
             max_elapsed_time: Some(Duration::from_secs(10)), // 10 seconds total allocated time for all retries.
             max_retries: 10,
+            retryable_codes: Cow::Borrowed(&RETRYABLE_ERROR_CODES),
+            retry_budget: None,
+            poll_reconnect: ReconnectPolicy::default(),
         }
     }
 }
 
-impl RetryConfig {
-    pub(crate) const fn poll_retry_policy() -> Self {
+/// Deadlines applied to each attempt of a call to the server, depending on what kind of call it
+/// is. Calls which already carry a deadline (a `grpc-timeout` header) keep it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcTimeouts {
+    /// Polls for workflow and activity tasks. The server holds these open until shortly before
+    /// their deadline, for at most a minute, and answers with an empty response if there's no work
+    /// by then. Shorten this when something between the client and the server, like a load
+    /// balancer, drops connections idle for less than that. Lengthening it past a minute only
+    /// helps against servers configured to hold polls for longer. Values under
+    /// [MIN_LONG_POLL_TIMEOUT] are raised to it, since the server rejects such polls.
+    pub long_poll: Duration,
+    /// Fetches of workflow history
+    pub history: Duration,
+    /// Responses to workflow, activity, and query tasks, and activity heartbeats
+    pub task_completion: Duration,
+    /// Workflow queries and updates, including polls for update outcomes
+    pub query_and_update: Duration,
+    /// All other calls
+    pub other: Duration,
+}
+
+/// The shortest deadline polls for tasks are made with. See [RpcTimeouts::long_poll].
+pub const MIN_LONG_POLL_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl Default for RpcTimeouts {
+    fn default() -> Self {
         Self {
-            initial_interval: Duration::from_millis(200),
-            randomization_factor: 0.2,
-            multiplier: 2.0,
-            max_interval: Duration::from_secs(10),
-            max_elapsed_time: None,
-            max_retries: 0,
+            // The server times out polls after 60 seconds. Set our timeout to be slightly beyond
+            // that.
+            long_poll: Duration::from_secs(70),
+            history: Duration::from_secs(30),
+            task_completion: Duration::from_secs(30),
+            query_and_update: Duration::from_secs(30),
+            other: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RpcTimeouts {
+    /// Returns the timeout for the call with the provided (snake case) method name
+    pub(crate) fn for_call(&self, call_name: &str) -> Duration {
+        match call_name {
+            "poll_workflow_task_queue" | "poll_activity_task_queue" => {
+                self.long_poll.max(MIN_LONG_POLL_TIMEOUT)
+            }
+            "get_workflow_execution_history" | "get_workflow_execution_history_reverse" => {
+                self.history
+            }
+            "respond_workflow_task_completed"
+            | "respond_workflow_task_failed"
+            | "respond_query_task_completed"
+            | "record_activity_task_heartbeat"
+            | "record_activity_task_heartbeat_by_id"
+            | "respond_activity_task_completed"
+            | "respond_activity_task_completed_by_id"
+            | "respond_activity_task_failed"
+            | "respond_activity_task_failed_by_id"
+            | "respond_activity_task_canceled"
+            | "respond_activity_task_canceled_by_id" => self.task_completion,
+            "query_workflow" | "update_workflow_execution" | "poll_workflow_execution_update" => {
+                self.query_and_update
+            }
+            _ => self.other,
         }
     }
+}
 
+impl RetryConfig {
     pub(crate) const fn throttle_retry_policy() -> Self {
         Self {
             initial_interval: Duration::from_secs(1),
@@ -237,6 +512,9 @@ impl RetryConfig {
             max_interval: Duration::from_secs(10),
             max_elapsed_time: None,
             max_retries: 0,
+            retryable_codes: Cow::Borrowed(&RETRYABLE_ERROR_CODES),
+            retry_budget: None,
+            poll_reconnect: ReconnectPolicy::DEFAULT,
         }
     }
 
@@ -280,6 +558,24 @@ pub enum ClientInitError {
     /// server capabilities / verify server is responding.
     #[error("`get_system_info` call error after connection: {0:?}")]
     SystemInfoCallError(tonic::Status),
+    /// A TLS certificate or key file couldn't be read
+    #[error("Couldn't read TLS file {0:?}: {1}")]
+    TlsFileReadError(std::path::PathBuf, std::io::Error),
+    /// The SPIFFE Workload API couldn't provide an identity
+    #[error("SPIFFE Workload API error: {0}")]
+    WorkloadApiError(String),
+    /// The server's host name couldn't be resolved for load balancing
+    #[error("DNS resolution error: {0}")]
+    DnsResolutionError(String),
+    /// The target URL can't be connected to. Configuration error, fatal.
+    #[error("Invalid target: {0}")]
+    InvalidTarget(String),
+    /// TLS certificates or keys couldn't be parsed. Configuration error, fatal.
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidTlsConfig(String),
+    /// The RPC concurrency limit can never admit a call. Configuration error, fatal.
+    #[error("Invalid RPC concurrency limit: {0}")]
+    InvalidRpcConcurrencyLimit(String),
 }
 
 /// A client with [ClientOptions] attached, which can be passed to initialize workers,
@@ -289,8 +585,14 @@ pub struct ConfiguredClient<C> {
     client: C,
     options: Arc<ClientOptions>,
     headers: Arc<RwLock<ClientHeaders>>,
-    /// Capabilities as read from the `get_system_info` RPC call made on client connection
-    capabilities: Option<get_system_info_response::Capabilities>,
+    header_provider: Option<Arc<CachedHeaderProvider>>,
+    interceptors: Arc<[Arc<dyn ClientInterceptor>]>,
+    rpc_limiter: Option<Arc<RpcLimiter>>,
+    channel: ReloadableChannel,
+    connection: ConnectionMonitor,
+    /// Capabilities as read from the `get_system_info` RPC call made on client connection. Set
+    /// once the call has been made, even if the server didn't report any.
+    capabilities: Arc<OnceCell<Option<get_system_info_response::Capabilities>>>,
     workers: Arc<SlotManager>,
 }
 
@@ -310,10 +612,33 @@ impl<C> ConfiguredClient<C> {
         &self.options
     }
 
+    /// Reconnect using the provided mTLS client certificate and key, without disturbing calls in
+    /// progress. All clones of this client (and workers using them) switch to the new connection,
+    /// which is made to whichever endpoint they are currently using.
+    pub async fn reload_client_tls(
+        &self,
+        client_tls: ClientTlsConfig,
+    ) -> Result<(), ClientInitError> {
+        self.channel
+            .downgrade()
+            .reconnect(&self.options, |target| {
+                target.client_tls = Some(client_tls);
+                true
+            })
+            .await?;
+        Ok(())
+    }
+
     /// Returns the server capabilities we (may have) learned about when establishing an initial
     /// connection
     pub fn capabilities(&self) -> Option<&get_system_info_response::Capabilities> {
-        self.capabilities.as_ref()
+        self.capabilities.get().and_then(Option::as_ref)
+    }
+
+    /// Returns the state of the connection to the server, as observed by the calls made with this
+    /// client and its clones
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection.state()
     }
 
     /// Returns a cloned reference to a registry with workers using this client instance
@@ -365,6 +690,76 @@ impl<C> DerefMut for ConfiguredClient<C> {
     }
 }
 
+impl ConfiguredClient<TemporalServiceClientWithMetrics> {
+    /// Connects to the server, if that hasn't happened yet, and learns its capabilities. Only
+    /// needed for clients connected with [ClientOptions::lazy_connect], to surface connection
+    /// problems at a time of the caller's choosing rather than on the first call.
+    pub async fn ensure_connected(&self) -> Result<(), ClientInitError> {
+        if self.capabilities.get().is_some() {
+            return Ok(());
+        }
+        let capabilities = match WorkflowService::get_system_info(
+            &mut self.clone(),
+            GetSystemInfoRequest::default(),
+        )
+        .await
+        {
+            Ok(sysinfo) => sysinfo.into_inner().capabilities,
+            Err(status) => match status.code() {
+                Code::Unimplemented => None,
+                _ => return Err(ClientInitError::SystemInfoCallError(status)),
+            },
+        };
+        // Concurrent callers learn the same thing, so it doesn't matter whose result is kept
+        let _ = self.capabilities.set(capabilities);
+        Ok(())
+    }
+
+    /// Asks the server, with the standard gRPC health check, whether its workflow service is
+    /// serving. Fails if the server can't be reached, in which case [Self::connection_state]
+    /// explains why.
+    pub async fn check_health(&self) -> Result<ServingStatus> {
+        let resp = HealthService::check(
+            &mut self.clone(),
+            HealthCheckRequest {
+                service: WORKFLOW_SERVICE_NAME.to_string(),
+            },
+        )
+        .await?;
+        Ok(resp.into_inner().status())
+    }
+}
+
+impl ClientOptionsBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(Some(ref lb)) = self.load_balancing {
+            if lb.dns_refresh_interval.is_zero() {
+                return Err("`load_balancing.dns_refresh_interval` must be nonzero".to_owned());
+            }
+        }
+        if let Some(ref rc) = self.retry_config {
+            if !(0.0..=1.0).contains(&rc.randomization_factor) {
+                return Err(
+                    "`retry_config.randomization_factor` must be between 0 and 1".to_owned(),
+                );
+            }
+            if rc.max_interval < rc.initial_interval {
+                return Err(
+                    "`retry_config.max_interval` must be at least `initial_interval`".to_owned(),
+                );
+            }
+            if rc.poll_reconnect.max_interval < rc.poll_reconnect.initial_interval {
+                return Err(
+                    "`retry_config.poll_reconnect.max_interval` must be at least \
+                     `initial_interval`"
+                        .to_owned(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 impl ClientOptions {
     /// Attempt to establish a connection to the Temporal server in a specific namespace. The
     /// returned client is bound to that namespace.
@@ -388,28 +783,102 @@ impl ClientOptions {
         metrics_meter: Option<TemporalMeter>,
     ) -> Result<RetryClient<ConfiguredClient<TemporalServiceClientWithMetrics>>, ClientInitError>
     {
-        let channel = Channel::from_shared(self.target_url.to_string())?;
-        let channel = self.add_tls_to_channel(channel).await?;
-        let channel = if let Some(keep_alive) = self.keep_alive.as_ref() {
-            channel
-                .keep_alive_while_idle(true)
-                .http2_keep_alive_interval(keep_alive.interval)
-                .keep_alive_timeout(keep_alive.timeout)
+        let client = self.connect_configured(metrics_meter).await?;
+        if !self.lazy_connect {
+            client.ensure_connected().await?;
+        }
+        Ok(RetryClient::new(client, self.retry_config.clone()))
+    }
+
+    /// Attempt to establish a connection to the Temporal Cloud Operations API, which manages
+    /// namespaces, users, certificates and the like on Temporal Cloud. The target URL should be
+    /// the API's (`https://saas-api.tmprl.cloud:443`), and the options should carry an API key.
+    ///
+    /// Calls are made with [CloudService], and are retried, timed out, and recorded in metrics the
+    /// same way as calls to the workflow service.
+    pub async fn connect_cloud_ops(
+        &self,
+        metrics_meter: Option<TemporalMeter>,
+    ) -> Result<CloudOpsClient, ClientInitError> {
+        let opts = self.cloud_ops_options();
+        // The Cloud Operations API doesn't implement `get_system_info`, so there's nothing to ask
+        let client = opts.connect_configured(metrics_meter).await?;
+        Ok(RetryClient::new(client, opts.retry_config))
+    }
+
+    /// These options, with the Cloud Operations API version header added unless it is already set
+    fn cloud_ops_options(&self) -> ClientOptions {
+        let mut opts = self.clone();
+        opts.headers
+            .get_or_insert_with(HashMap::new)
+            .entry(CLOUD_API_VERSION_HEADER_KEY.to_string())
+            .or_insert_with(|| CLOUD_OPS_API_VERSION.to_string());
+        opts
+    }
+
+    async fn connect_configured(
+        &self,
+        metrics_meter: Option<TemporalMeter>,
+    ) -> Result<ConfiguredClient<TemporalServiceClientWithMetrics>, ClientInitError> {
+        let rpc_limiter = self
+            .rpc_concurrency_limit
+            .map(|l| RpcLimiter::new(l, metrics_meter.clone()).map(Arc::new))
+            .transpose()?;
+        let (client_tls, svid_stream) = if let Some(spiffe) = self.spiffe.as_ref() {
+            let (tls, stream) = spiffe.connect().await?;
+            (Some(tls), Some(stream))
+        } else if let Some(cfg) = self.tls_reload.as_ref() {
+            (Some(cfg.load().await?), None)
         } else {
-            channel
+            (None, None)
         };
-        let channel = if let Some(origin) = self.override_origin.clone() {
-            channel.origin(origin)
-        } else {
-            channel
+        let (channel, endpoint) = match self.failover.as_ref() {
+            Some(failover) if !self.lazy_connect => {
+                failover
+                    .connect_preferred(self, client_tls.as_ref())
+                    .await?
+            }
+            _ => (
+                self.open_channel(client_tls.as_ref(), self.lazy_connect)
+                    .await?,
+                0,
+            ),
+        };
+        let target_url = match self.failover.as_ref() {
+            Some(failover) => failover.endpoints(self).swap_remove(endpoint),
+            None => self.target_url.clone(),
         };
-        let channel = channel.connect().await?;
+        let channel = ReloadableChannel::new(
+            channel,
+            ChannelTarget {
+                url: target_url,
+                client_tls,
+            },
+        );
+        let connection = ConnectionMonitor::default();
+        if let (Some(spiffe), Some(stream)) = (self.spiffe.clone(), svid_stream) {
+            channel
+                .downgrade()
+                .spawn_svid_watcher(self.clone(), spiffe, stream);
+        } else if let Some(cfg) = self.tls_reload.clone() {
+            channel.spawn_reloader(self.clone(), cfg);
+        }
+        if let Some(failover) = self.failover.clone() {
+            channel.downgrade().spawn_failover(
+                self.clone(),
+                failover,
+                endpoint,
+                connection.clone(),
+                metrics_meter.clone(),
+            );
+        }
         let service = ServiceBuilder::new()
-            .layer_fn(move |channel| GrpcMetricSvc {
+            .layer_fn(|channel| GrpcMetricSvc {
                 inner: channel,
+                connection: connection.clone(),
                 metrics: metrics_meter.clone().map(MetricsContext::new),
             })
-            .service(channel);
+            .service(channel.clone());
         let headers = Arc::new(RwLock::new(ClientHeaders {
             user_headers: self.headers.clone().unwrap_or_default(),
             api_key: self.api_key.clone(),
@@ -420,32 +889,142 @@ impl ClientOptions {
         };
         let svc = InterceptedService::new(service, interceptor);
 
-        let mut client = ConfiguredClient {
+        Ok(ConfiguredClient {
             headers,
-            client: TemporalServiceClient::new(svc),
+            header_provider: self
+                .header_provider
+                .clone()
+                .map(|p| Arc::new(CachedHeaderProvider::new(p))),
+            interceptors: self.interceptors.clone().into(),
+            rpc_limiter,
+            channel,
+            connection,
+            client: TemporalServiceClient::new(
+                svc,
+                self.send_compression,
+                self.accept_compression.clone(),
+            ),
             options: Arc::new(self.clone()),
-            capabilities: None,
+            capabilities: Arc::new(OnceCell::new()),
             workers: Arc::new(SlotManager::new()),
-        };
-        match client
-            .get_system_info(GetSystemInfoRequest::default())
-            .await
+        })
+    }
+
+    /// Connects a channel to the server as configured, using `client_tls` in place of any
+    /// configured mTLS client certificate if it is provided
+    async fn connect_channel(
+        &self,
+        client_tls: Option<&ClientTlsConfig>,
+    ) -> Result<FrontendChannel, ClientInitError> {
+        self.open_channel(client_tls, false).await
+    }
+
+    /// Like [Self::connect_channel], but if `lazy` is set the returned channel only connects when
+    /// first used
+    async fn open_channel(
+        &self,
+        client_tls: Option<&ClientTlsConfig>,
+        lazy: bool,
+    ) -> Result<FrontendChannel, ClientInitError> {
+        if let Some(socket) =
+            LocalSocket::from_url(&self.target_url).map_err(ClientInitError::InvalidTarget)?
         {
-            Ok(sysinfo) => {
-                client.capabilities = sysinfo.into_inner().capabilities;
+            if self.load_balancing.is_some()
+                || self.socks5_proxy.is_some()
+                || self.http_connect_proxy.is_some()
+                || self.transport_protocol != TransportProtocol::Grpc
+            {
+                warn!("Load balancing, proxies, and gRPC-Web don't apply to local socket targets, ignoring them");
             }
-            Err(status) => match status.code() {
-                Code::Unimplemented => {}
-                _ => return Err(ClientInitError::SystemInfoCallError(status)),
-            },
+            let endpoint = self.build_endpoint(client_tls).await?;
+            return Ok(socket.connect_endpoint(&endpoint, lazy).await?.into());
+        }
+        if self.transport_protocol == TransportProtocol::GrpcWeb {
+            if self.load_balancing.is_some()
+                || self.socks5_proxy.is_some()
+                || self.http_connect_proxy.is_some()
+            {
+                warn!("Load balancing and proxies aren't supported with gRPC-Web, ignoring them");
+            }
+            return Ok(grpc_web::connect_grpc_web(self, client_tls, lazy)
+                .await?
+                .into());
+        }
+        let use_tls = self.tls_cfg.is_some()
+            || self.tls_reload.is_some()
+            || self.spiffe.is_some()
+            || self.target_url.scheme() == "https";
+        let proxy = if let Some(socks) = self.socks5_proxy.clone() {
+            Some(ProxyConfig::Socks5(socks))
+        } else {
+            self.http_connect_proxy
+                .clone()
+                .or_else(|| HttpConnectProxyOptions::from_env(&self.target_url, use_tls))
+                .map(ProxyConfig::HttpConnect)
         };
-        Ok(RetryClient::new(client, self.retry_config.clone()))
+        if let Some(lb) = self.load_balancing.as_ref() {
+            if proxy.is_none() {
+                return balance::connect_balanced(self, lb, client_tls).await;
+            }
+            warn!("Load balancing isn't supported through a proxy, ignoring it");
+        }
+        let channel = self.build_endpoint(client_tls).await?;
+        Ok(match (proxy, lazy) {
+            (Some(proxy), false) => proxy.connect_endpoint(&channel).await?,
+            (Some(proxy), true) => proxy.connect_endpoint_lazy(&channel),
+            (None, false) => channel.connect().await?,
+            (None, true) => channel.connect_lazy(),
+        }
+        .into())
+    }
+
+    /// Builds the endpoint to connect to, configured with TLS, keep alive, and origin settings
+    async fn build_endpoint(
+        &self,
+        client_tls: Option<&ClientTlsConfig>,
+    ) -> Result<Endpoint, ClientInitError> {
+        let channel = if matches!(self.target_url.scheme(), "unix" | "npipe") {
+            // The connector dials the socket, but calls still need an authority. TLS is only
+            // applied to connections made with an https URI.
+            let scheme = if self.tls_cfg.is_some() || client_tls.is_some() {
+                "https"
+            } else {
+                "http"
+            };
+            Channel::from_shared(format!("{scheme}://{LOCAL_SOCKET_AUTHORITY}"))?
+        } else {
+            Channel::from_shared(self.target_url.to_string())?
+        };
+        let channel = self.add_tls_to_channel(channel, client_tls).await?;
+        let channel = if let Some(keep_alive) = self.keep_alive.as_ref() {
+            channel
+                .keep_alive_while_idle(keep_alive.permit_without_calls)
+                .http2_keep_alive_interval(keep_alive.interval)
+                .keep_alive_timeout(keep_alive.timeout)
+        } else {
+            channel
+        };
+        Ok(if let Some(origin) = self.override_origin.clone() {
+            channel.origin(origin)
+        } else {
+            channel
+        })
     }
 
     /// If TLS is configured, set the appropriate options on the provided channel and return it.
-    /// Passes it through if TLS options not set.
-    async fn add_tls_to_channel(&self, mut channel: Endpoint) -> Result<Endpoint, ClientInitError> {
-        if let Some(tls_cfg) = &self.tls_cfg {
+    /// Passes it through if TLS options not set. A provided `client_tls` replaces the configured
+    /// client certificate, and implies TLS.
+    async fn add_tls_to_channel(
+        &self,
+        mut channel: Endpoint,
+        client_tls: Option<&ClientTlsConfig>,
+    ) -> Result<Endpoint, ClientInitError> {
+        let tls_cfg = match (&self.tls_cfg, client_tls) {
+            (Some(cfg), _) => Some(cfg.clone()),
+            (None, Some(_)) => Some(TlsConfig::default()),
+            (None, None) => None,
+        };
+        if let Some(tls_cfg) = &tls_cfg {
             let mut tls = tonic::transport::ClientTlsConfig::new();
 
             if let Some(root_cert) = &tls_cfg.server_root_ca_cert {
@@ -464,7 +1043,7 @@ impl ClientOptions {
                 channel = channel.origin(uri);
             }
 
-            if let Some(client_opts) = &tls_cfg.client_tls_config {
+            if let Some(client_opts) = client_tls.or(tls_cfg.client_tls_config.as_ref()) {
                 let client_identity =
                     Identity::from_pem(&client_opts.client_cert, &client_opts.client_private_key);
                 tls = tls.identity(client_identity);
@@ -508,8 +1087,11 @@ impl Interceptor for ServiceCallInterceptor {
             );
         }
         self.headers.read().apply_to_metadata(metadata);
+        if let Some(propagator) = self.opts.trace_propagator.as_ref() {
+            propagator.inject(metadata);
+        }
         if !metadata.contains_key("grpc-timeout") {
-            request.set_timeout(OTHER_CALL_TIMEOUT);
+            request.set_timeout(self.opts.rpc_timeouts.other);
         }
 
         Ok(request)
@@ -522,8 +1104,25 @@ pub struct TemporalServiceClient<T> {
     svc: T,
     workflow_svc_client: OnceCell<WorkflowServiceClient<T>>,
     operator_svc_client: OnceCell<OperatorServiceClient<T>>,
+    cloud_svc_client: OnceCell<CloudServiceClient<T>>,
     test_svc_client: OnceCell<TestServiceClient<T>>,
     health_svc_client: OnceCell<HealthClient<T>>,
+    send_compression: Option<CompressionEncoding>,
+    accept_compression: Vec<CompressionEncoding>,
+}
+
+/// Applies the compression settings of a [TemporalServiceClient] to one of its service clients
+macro_rules! with_compression {
+    ($self:ident, $client:expr) => {{
+        let mut client = $client;
+        if let Some(enc) = $self.send_compression {
+            client = client.send_compressed(enc);
+        }
+        for enc in &$self.accept_compression {
+            client = client.accept_compressed(*enc);
+        }
+        client
+    }};
 }
 
 /// We up the limit on incoming messages from server from the 4Mb default to 128Mb. If for
@@ -546,40 +1145,70 @@ where
     T::Error: Into<tonic::codegen::StdError>,
     <T::ResponseBody as tonic::codegen::Body>::Error: Into<tonic::codegen::StdError> + Send,
 {
-    fn new(svc: T) -> Self {
+    fn new(
+        svc: T,
+        send_compression: Option<CompressionEncoding>,
+        accept_compression: Vec<CompressionEncoding>,
+    ) -> Self {
         Self {
             svc,
             workflow_svc_client: OnceCell::new(),
             operator_svc_client: OnceCell::new(),
+            cloud_svc_client: OnceCell::new(),
             test_svc_client: OnceCell::new(),
             health_svc_client: OnceCell::new(),
+            send_compression,
+            accept_compression,
         }
     }
     /// Get the underlying workflow service client
     pub fn workflow_svc(&self) -> &WorkflowServiceClient<T> {
         self.workflow_svc_client.get_or_init(|| {
-            WorkflowServiceClient::new(self.svc.clone())
-                .max_decoding_message_size(get_decode_max_size())
+            with_compression!(
+                self,
+                WorkflowServiceClient::new(self.svc.clone())
+                    .max_decoding_message_size(get_decode_max_size())
+            )
         })
     }
     /// Get the underlying operator service client
     pub fn operator_svc(&self) -> &OperatorServiceClient<T> {
         self.operator_svc_client.get_or_init(|| {
-            OperatorServiceClient::new(self.svc.clone())
-                .max_decoding_message_size(get_decode_max_size())
+            with_compression!(
+                self,
+                OperatorServiceClient::new(self.svc.clone())
+                    .max_decoding_message_size(get_decode_max_size())
+            )
+        })
+    }
+    /// Get the underlying cloud operations service client
+    pub fn cloud_svc(&self) -> &CloudServiceClient<T> {
+        self.cloud_svc_client.get_or_init(|| {
+            with_compression!(
+                self,
+                CloudServiceClient::new(self.svc.clone())
+                    .max_decoding_message_size(get_decode_max_size())
+            )
         })
     }
     /// Get the underlying test service client
     pub fn test_svc(&self) -> &TestServiceClient<T> {
         self.test_svc_client.get_or_init(|| {
-            TestServiceClient::new(self.svc.clone())
-                .max_decoding_message_size(get_decode_max_size())
+            with_compression!(
+                self,
+                TestServiceClient::new(self.svc.clone())
+                    .max_decoding_message_size(get_decode_max_size())
+            )
         })
     }
     /// Get the underlying health service client
     pub fn health_svc(&self) -> &HealthClient<T> {
         self.health_svc_client.get_or_init(|| {
-            HealthClient::new(self.svc.clone()).max_decoding_message_size(get_decode_max_size())
+            with_compression!(
+                self,
+                HealthClient::new(self.svc.clone())
+                    .max_decoding_message_size(get_decode_max_size())
+            )
         })
     }
     /// Get the underlying workflow service client mutably
@@ -592,6 +1221,11 @@ where
         let _ = self.operator_svc();
         self.operator_svc_client.get_mut().unwrap()
     }
+    /// Get the underlying cloud operations service client mutably
+    pub fn cloud_svc_mut(&mut self) -> &mut CloudServiceClient<T> {
+        let _ = self.cloud_svc();
+        self.cloud_svc_client.get_mut().unwrap()
+    }
     /// Get the underlying test service client mutably
     pub fn test_svc_mut(&mut self) -> &mut TestServiceClient<T> {
         let _ = self.test_svc();
@@ -612,6 +1246,8 @@ pub type OperatorServiceClientWithMetrics = OperatorServiceClient<InterceptedMet
 pub type TestServiceClientWithMetrics = TestServiceClient<InterceptedMetricsSvc>;
 /// A [TemporalServiceClient] with the default interceptors attached.
 pub type TemporalServiceClientWithMetrics = TemporalServiceClient<InterceptedMetricsSvc>;
+/// A client for the Temporal Cloud Operations API. See [ClientOptions::connect_cloud_ops].
+pub type CloudOpsClient = RetryClient<ConfiguredClient<TemporalServiceClientWithMetrics>>;
 type InterceptedMetricsSvc = InterceptedService<GrpcMetricSvc, ServiceCallInterceptor>;
 
 /// Contains an instance of a namespace-bound client for interacting with the Temporal server
@@ -674,6 +1310,101 @@ impl Client {
     pub fn into_inner(self) -> ConfiguredClient<TemporalServiceClientWithMetrics> {
         self.inner
     }
+
+    fn start_workflow_request(
+        &self,
+        input: Vec<Payload>,
+        task_queue: String,
+        workflow_id: String,
+        workflow_type: String,
+        request_id: Option<String>,
+        options: WorkflowOptions,
+    ) -> Result<StartWorkflowExecutionRequest> {
+        let workflow_start_delay = options.proto_start_delay()?;
+        Ok(StartWorkflowExecutionRequest {
+            namespace: self.namespace.clone(),
+            input: input.into_payloads(),
+            workflow_id,
+            workflow_type: Some(WorkflowType {
+                name: workflow_type,
+            }),
+            task_queue: Some(TaskQueue {
+                name: task_queue,
+                kind: TaskQueueKind::Unspecified as i32,
+                normal_name: "".to_string(),
+            }),
+            request_id: request_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            workflow_id_reuse_policy: options.id_reuse_policy as i32,
+            workflow_id_conflict_policy: options.id_conflict_policy as i32,
+            workflow_execution_timeout: options.execution_timeout.and_then(|d| d.try_into().ok()),
+            workflow_run_timeout: options.run_timeout.and_then(|d| d.try_into().ok()),
+            workflow_task_timeout: options.task_timeout.and_then(|d| d.try_into().ok()),
+            search_attributes: options.search_attributes.map(|d| d.into()),
+            cron_schedule: options.cron_schedule.unwrap_or_default(),
+            request_eager_execution: options.enable_eager_workflow_start,
+            retry_policy: options.retry_policy,
+            workflow_start_delay,
+            ..Default::default()
+        })
+    }
+
+    fn update_request(
+        &self,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> UpdateWorkflowExecutionRequest {
+        UpdateWorkflowExecutionRequest {
+            namespace: self.namespace.clone(),
+            workflow_execution: Some(update.execution()),
+            wait_policy: Some(update::v1::WaitPolicy {
+                lifecycle_stage: UpdateWorkflowExecutionLifecycleStage::from(wait_for) as i32,
+            }),
+            request: Some(update::v1::Request {
+                meta: Some(update::v1::Meta {
+                    update_id: update.update_id,
+                    identity: self.inner.options.identity.clone(),
+                }),
+                input: Some(update::v1::Input {
+                    header: update.header,
+                    name: update.name,
+                    args: update.args,
+                }),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Multi-operation calls fail with a status listing the status of each operation. Returns the
+/// status of the operation which failed, so callers see the same error as if it had been called
+/// on its own.
+fn failed_operation_status(status: Status) -> Status {
+    let Ok(details) = RpcStatus::decode(status.details()) else {
+        return status;
+    };
+    let failure = details.details.iter().find_map(|d| {
+        d.type_url
+            .ends_with("MultiOperationExecutionFailure")
+            .then(|| MultiOperationExecutionFailure::decode(d.value.as_slice()).ok())
+            .flatten()
+    });
+    let Some(failed) = failure.and_then(|f| {
+        f.statuses
+            .into_iter()
+            .find(|s| s.code != Code::Aborted as i32)
+    }) else {
+        return status;
+    };
+    let details = RpcStatus {
+        code: failed.code,
+        message: failed.message.clone(),
+        details: failed.details,
+    };
+    Status::with_details(
+        Code::from(failed.code),
+        failed.message,
+        details.encode_to_vec().into(),
+    )
 }
 
 /// Enum to help reference a namespace by either the namespace name or the namespace id
@@ -693,6 +1424,17 @@ impl Namespace {
         };
         DescribeNamespaceRequest { namespace, id }
     }
+
+    fn into_delete_namespace_request(self) -> DeleteNamespaceRequest {
+        let (namespace, namespace_id) = match self {
+            Namespace::Name(n) => (n, "".to_owned()),
+            Namespace::Id(n) => ("".to_owned(), n),
+        };
+        DeleteNamespaceRequest {
+            namespace,
+            namespace_id,
+        }
+    }
 }
 
 /// Default workflow execution retention for a Namespace is 3 days
@@ -786,6 +1528,75 @@ impl RegisterNamespaceOptionsBuilder {
     }
 }
 
+/// Helper struct for `update_namespace`. Settings which are left unset are left as they are.
+#[derive(Clone, Debug, Default, derive_builder::Builder)]
+pub struct UpdateNamespaceOptions {
+    /// Name (required)
+    #[builder(setter(into))]
+    pub namespace: String,
+    /// Description
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<String>,
+    /// Owner's email
+    #[builder(setter(into, strip_option), default)]
+    pub owner_email: Option<String>,
+    /// Custom data, merged into what the namespace already has
+    #[builder(default)]
+    pub data: HashMap<String, String>,
+    /// Workflow execution retention period
+    #[builder(setter(strip_option), default)]
+    pub workflow_execution_retention_period: Option<Duration>,
+    /// History Archival setting
+    #[builder(setter(into, strip_option), default)]
+    pub history_archival_state: Option<ArchivalState>,
+    /// History Archival uri
+    #[builder(setter(into, strip_option), default)]
+    pub history_archival_uri: Option<String>,
+    /// Visibility Archival setting
+    #[builder(setter(into, strip_option), default)]
+    pub visibility_archival_state: Option<ArchivalState>,
+    /// Visibility Archival uri
+    #[builder(setter(into, strip_option), default)]
+    pub visibility_archival_uri: Option<String>,
+    /// Security Token
+    #[builder(setter(into), default)]
+    pub security_token: String,
+}
+
+impl UpdateNamespaceOptions {
+    /// Builder convenience.  Less `use` imports
+    pub fn builder() -> UpdateNamespaceOptionsBuilder {
+        Default::default()
+    }
+}
+
+impl From<UpdateNamespaceOptions> for UpdateNamespaceRequest {
+    fn from(val: UpdateNamespaceOptions) -> Self {
+        // The server leaves empty and unspecified settings unchanged
+        UpdateNamespaceRequest {
+            namespace: val.namespace,
+            update_info: Some(UpdateNamespaceInfo {
+                description: val.description.unwrap_or_default(),
+                owner_email: val.owner_email.unwrap_or_default(),
+                data: val.data,
+                state: 0,
+            }),
+            config: Some(NamespaceConfig {
+                workflow_execution_retention_ttl: val
+                    .workflow_execution_retention_period
+                    .and_then(|d| d.try_into().ok()),
+                history_archival_state: val.history_archival_state.unwrap_or_default() as i32,
+                history_archival_uri: val.history_archival_uri.unwrap_or_default(),
+                visibility_archival_state: val.visibility_archival_state.unwrap_or_default() as i32,
+                visibility_archival_uri: val.visibility_archival_uri.unwrap_or_default(),
+                ..Default::default()
+            }),
+            security_token: val.security_token,
+            ..Default::default()
+        }
+    }
+}
+
 /// Helper struct for `signal_with_start_workflow_execution`.
 #[derive(Clone, derive_builder::Builder)]
 pub struct SignalWithStartOptions {
@@ -883,6 +1694,56 @@ pub trait WorkflowClientTrait {
         failure: Option<Failure>,
     ) -> Result<RespondActivityTaskFailedResponse>;
 
+    /// Pause an activity of a workflow execution, or all of its activities of some type. A paused
+    /// activity isn't retried until unpaused, and its running attempt learns of the pause the next
+    /// time it heartbeats.
+    async fn pause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: pause_activity_request::Activity,
+    ) -> Result<PauseActivityResponse>;
+
+    /// Unpause activities of a workflow execution previously paused with [Self::pause_activity]
+    async fn unpause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: unpause_activity_request::Activity,
+        options: UnpauseActivityOptions,
+    ) -> Result<UnpauseActivityResponse>;
+
+    /// Complete an activity asynchronously, from outside the worker which is running it
+    async fn complete_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        result: Option<Payloads>,
+    ) -> Result<()>;
+
+    /// Fail an activity asynchronously, from outside the worker which is running it
+    async fn fail_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        failure: Failure,
+        last_heartbeat_details: Option<Payloads>,
+    ) -> Result<()>;
+
+    /// Report an asynchronously completed activity as cancelled, from outside the worker which is
+    /// running it. Should only be called after cancellation was requested.
+    async fn cancel_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<()>;
+
+    /// Heartbeat an activity asynchronously, from outside the worker which is running it. The
+    /// response says whether the activity has been asked to stop.
+    async fn heartbeat_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<AsyncActivityHeartbeat>;
+
     /// Send a signal to a certain workflow instance
     async fn signal_workflow_execution(
         &self,
@@ -925,6 +1786,15 @@ pub trait WorkflowClientTrait {
         page_token: Vec<u8>,
     ) -> Result<GetWorkflowExecutionHistoryResponse>;
 
+    /// Get a page of a workflow execution's history, only including events matching the filter.
+    /// See [HistoryFetchExt] for fetching all of it.
+    async fn get_filtered_workflow_execution_history(
+        &self,
+        execution: WorkflowExecution,
+        page_token: Vec<u8>,
+        filter_type: HistoryEventFilterType,
+    ) -> Result<GetWorkflowExecutionHistoryResponse>;
+
     /// Respond to a legacy query-only workflow task
     async fn respond_legacy_query(
         &self,
@@ -948,22 +1818,92 @@ pub trait WorkflowClientTrait {
         run_id: Option<String>,
     ) -> Result<TerminateWorkflowExecutionResponse>;
 
+    /// Reset a workflow to the workflow task which finished with the given event, starting a new
+    /// run from there. [ResetWorkflowExt::reset_workflow] can find that event in history.
+    async fn reset_workflow_execution(
+        &self,
+        execution: WorkflowExecution,
+        workflow_task_finish_event_id: i64,
+        options: ResetWorkflowOptions,
+    ) -> Result<ResetWorkflowExecutionResponse>;
+
     /// Register a new namespace
     async fn register_namespace(
         &self,
         options: RegisterNamespaceOptions,
     ) -> Result<RegisterNamespaceResponse>;
 
+    /// Change the settings of a namespace
+    async fn update_namespace(
+        &self,
+        options: UpdateNamespaceOptions,
+    ) -> Result<UpdateNamespaceResponse>;
+
     /// Lists all available namespaces
     async fn list_namespaces(&self) -> Result<ListNamespacesResponse>;
 
     /// Query namespace details
     async fn describe_namespace(&self, namespace: Namespace) -> Result<DescribeNamespaceResponse>;
 
-    /// List open workflow executions with Standard Visibility filtering
-    async fn list_open_workflow_executions(
+    /// Delete a namespace and all of its data
+    async fn delete_namespace(&self, namespace: Namespace) -> Result<DeleteNamespaceResponse>;
+
+    /// Register custom search attributes, with the type each is indexed as, on this client's
+    /// namespace
+    async fn add_search_attributes(
         &self,
-        max_page_size: i32,
+        search_attributes: HashMap<String, IndexedValueType>,
+    ) -> Result<AddSearchAttributesResponse>;
+
+    /// Remove custom search attributes from this client's namespace
+    async fn remove_search_attributes(
+        &self,
+        search_attributes: Vec<String>,
+    ) -> Result<RemoveSearchAttributesResponse>;
+
+    /// List the custom and system search attributes of this client's namespace
+    async fn list_search_attributes(&self) -> Result<ListSearchAttributesResponse>;
+
+    /// Register a Nexus endpoint with the cluster. The response carries the endpoint's id and
+    /// initial version, which later updates and deletes must refer to.
+    async fn create_nexus_endpoint(
+        &self,
+        spec: EndpointSpec,
+    ) -> Result<CreateNexusEndpointResponse>;
+
+    /// Get a Nexus endpoint registered with the cluster by its id
+    async fn get_nexus_endpoint(&self, id: String) -> Result<GetNexusEndpointResponse>;
+
+    /// Replace the spec of a Nexus endpoint. Fails with `FailedPrecondition` if `version` isn't
+    /// the endpoint's current version.
+    async fn update_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+        spec: EndpointSpec,
+    ) -> Result<UpdateNexusEndpointResponse>;
+
+    /// Delete a Nexus endpoint. Fails with `FailedPrecondition` if `version` isn't the endpoint's
+    /// current version.
+    async fn delete_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+    ) -> Result<DeleteNexusEndpointResponse>;
+
+    /// List the Nexus endpoints registered with the cluster, optionally only the one with the
+    /// given `name`
+    async fn list_nexus_endpoints(
+        &self,
+        page_size: i32,
+        next_page_token: Vec<u8>,
+        name: Option<String>,
+    ) -> Result<ListNexusEndpointsResponse>;
+
+    /// List open workflow executions with Standard Visibility filtering
+    async fn list_open_workflow_executions(
+        &self,
+        max_page_size: i32,
         next_page_token: Vec<u8>,
         start_time_filter: Option<StartTimeFilter>,
         filters: Option<ListOpenFilters>,
@@ -994,9 +1934,77 @@ pub trait WorkflowClientTrait {
         query: String,
     ) -> Result<ListArchivedWorkflowExecutionsResponse>;
 
+    /// Count workflow executions matching a visibility query, which may group them by a field
+    /// with `GROUP BY`
+    async fn count_workflow_executions(&self, query: String) -> Result<WorkflowExecutionCount>;
+
+    /// List schedules
+    async fn list_schedules(
+        &self,
+        maximum_page_size: i32,
+        next_page_token: Vec<u8>,
+    ) -> Result<ListSchedulesResponse>;
+
+    /// List the partitions of a task queue, and the hosts which own them
+    async fn list_task_queue_partitions(
+        &self,
+        task_queue: String,
+    ) -> Result<ListTaskQueuePartitionsResponse>;
+
     /// Get Cluster Search Attributes
     async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse>;
 
+    /// Start a batch operation, which the server applies to many workflows in the background
+    async fn start_batch_operation(
+        &self,
+        options: BatchOperationOptions,
+    ) -> Result<StartBatchOperationResponse>;
+
+    /// Describe the progress of a batch operation
+    async fn describe_batch_operation(&self, job_id: String) -> Result<BatchOperationProgress>;
+
+    /// Stop a batch operation. Workflows it has already been applied to aren't affected.
+    async fn stop_batch_operation(
+        &self,
+        job_id: String,
+        reason: String,
+    ) -> Result<StopBatchOperationResponse>;
+
+    /// Create a schedule
+    async fn create_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        options: CreateScheduleOptions,
+    ) -> Result<CreateScheduleResponse>;
+
+    /// Describe a schedule, including its recent and upcoming actions. The returned conflict
+    /// token may be passed to [WorkflowClientTrait::update_schedule].
+    async fn describe_schedule(&self, schedule_id: String) -> Result<DescribeScheduleResponse>;
+
+    /// Replace a schedule. If `conflict_token` is not empty, the update fails if the schedule was
+    /// changed since it was described with that token. `request_id` deduplicates the request, and
+    /// is generated if not set.
+    async fn update_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        conflict_token: Vec<u8>,
+        request_id: Option<String>,
+    ) -> Result<UpdateScheduleResponse>;
+
+    /// Make one-off changes to a schedule, like triggering, backfilling, pausing, or unpausing it.
+    /// See [SchedulePatch]. `request_id` deduplicates the request, and is generated if not set.
+    async fn patch_schedule(
+        &self,
+        schedule_id: String,
+        patch: SchedulePatch,
+        request_id: Option<String>,
+    ) -> Result<PatchScheduleResponse>;
+
+    /// Delete a schedule. Workflows it started aren't affected.
+    async fn delete_schedule(&self, schedule_id: String) -> Result<DeleteScheduleResponse>;
+
     /// Send an Update to a workflow execution
     async fn update_workflow_execution(
         &self,
@@ -1007,6 +2015,58 @@ pub trait WorkflowClientTrait {
         args: Option<Payloads>,
     ) -> Result<UpdateWorkflowExecutionResponse>;
 
+    /// Send an update to a workflow execution, waiting for it to reach the given stage or for the
+    /// server's maximum wait, whichever comes first. See [execute_workflow_update], which handles
+    /// the rest of the update's lifecycle.
+    async fn start_workflow_update(
+        &self,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<UpdateWorkflowExecutionResponse>;
+
+    /// Start a workflow if it isn't already running and send it an update, atomically, waiting
+    /// for the update to reach the given stage or for the server's maximum wait, whichever comes
+    /// first. The update's workflow id and run id are used for the workflow. See
+    /// [execute_update_with_start], which handles the rest of the update's lifecycle.
+    async fn start_update_with_start(
+        &self,
+        start: UpdateWithStartWorkflow,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<ExecuteMultiOperationResponse>;
+
+    /// Wait for a previously sent update to reach the given stage, or for the server's maximum
+    /// wait, whichever comes first
+    async fn poll_workflow_execution_update(
+        &self,
+        update_ref: update::v1::UpdateRef,
+        wait_for: UpdateWaitStage,
+    ) -> Result<PollWorkflowExecutionUpdateResponse>;
+
+    /// Modify the Build ID compatibility sets of a task queue, for worker versioning
+    async fn update_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        operation: BuildIdOperation,
+    ) -> Result<()>;
+
+    /// Fetch the Build ID compatibility sets of a task queue. At most `max_sets` of the most
+    /// recent sets are returned, or all of them if it is zero.
+    async fn get_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        max_sets: i32,
+    ) -> Result<CompatibleVersionSets>;
+
+    /// Determine which kinds of tasks may reach workers with the given Build IDs on the given
+    /// task queues. If `task_queues` is empty, all queues the Build IDs belong to are considered.
+    async fn get_worker_task_reachability(
+        &self,
+        build_ids: Vec<String>,
+        task_queues: Vec<String>,
+        reachability: TaskReachability,
+    ) -> Result<BuildIdReachability>;
+
     /// Returns options that were used to initialize the client
     fn get_options(&self) -> &ClientOptions;
 
@@ -1014,12 +2074,27 @@ pub trait WorkflowClientTrait {
     fn namespace(&self) -> &str;
 }
 
+/// Optional settings for [WorkflowClientTrait::unpause_activity]
+#[derive(Debug, Clone, Default)]
+pub struct UnpauseActivityOptions {
+    /// Also reset the number of attempts the activities have made
+    pub reset_attempts: bool,
+    /// Also discard the details the activities last heartbeated
+    pub reset_heartbeat: bool,
+    /// If set, the activities are scheduled again at a random point within this duration
+    pub jitter: Option<Duration>,
+}
+
 /// Optional fields supplied at the start of workflow execution
 #[derive(Debug, Clone, Default)]
 pub struct WorkflowOptions {
     /// Set the policy for reusing the workflow id
     pub id_reuse_policy: WorkflowIdReusePolicy,
 
+    /// Set what happens if a workflow with the same id is already running. When unspecified,
+    /// starting it fails, while signal-with-start signals the running workflow.
+    pub id_conflict_policy: WorkflowIdConflictPolicy,
+
     /// Optionally set the execution timeout for the workflow
     /// <https://docs.temporal.io/workflows/#workflow-execution-timeout>
     pub execution_timeout: Option<Duration>,
@@ -1033,7 +2108,9 @@ pub struct WorkflowOptions {
     /// Optionally set a cron schedule for the workflow
     pub cron_schedule: Option<String>,
 
-    /// Optionally associate extra search attributes with a workflow
+    /// Optionally associate extra search attributes with a workflow. See
+    /// [temporal_sdk_core_protos::search_attributes::TypedSearchAttributes::to_payloads] to encode
+    /// them from typed values.
     pub search_attributes: Option<HashMap<String, Payload>>,
 
     /// Optionally enable Eager Workflow Start, a latency optimization using local workers
@@ -1042,6 +2119,33 @@ pub struct WorkflowOptions {
 
     /// Optionally set a retry policy for the workflow
     pub retry_policy: Option<RetryPolicy>,
+
+    /// Optionally delay the first workflow task by this long. A signal sent with signal-with-start
+    /// doesn't cut the delay short, but any other signal does. Can't be combined with a cron
+    /// schedule or eager workflow start; starts which do so fail with `InvalidArgument`.
+    pub start_delay: Option<Duration>,
+}
+
+impl WorkflowOptions {
+    /// Converts the start delay to be sent to the server, failing if it's out of range or combined
+    /// with options it can't be used with
+    fn proto_start_delay(&self) -> Result<Option<prost_types::Duration>> {
+        let Some(delay) = self.start_delay else {
+            return Ok(None);
+        };
+        let invalid = if self.cron_schedule.is_some() {
+            "it can't be combined with a cron schedule"
+        } else if self.enable_eager_workflow_start {
+            "it can't be combined with eager workflow start"
+        } else {
+            return delay.try_into().map(Some).map_err(|_| {
+                Status::invalid_argument(format!("Invalid start delay: {delay:?} is out of range"))
+            });
+        };
+        Err(Status::invalid_argument(format!(
+            "Invalid start delay: {invalid}"
+        )))
+    }
 }
 
 #[async_trait::async_trait]
@@ -1057,31 +2161,14 @@ impl WorkflowClientTrait for Client {
     ) -> Result<StartWorkflowExecutionResponse> {
         Ok(WorkflowService::start_workflow_execution(
             &mut self.inner.clone(),
-            StartWorkflowExecutionRequest {
-                namespace: self.namespace.clone(),
-                input: input.into_payloads(),
+            self.start_workflow_request(
+                input,
+                task_queue,
                 workflow_id,
-                workflow_type: Some(WorkflowType {
-                    name: workflow_type,
-                }),
-                task_queue: Some(TaskQueue {
-                    name: task_queue,
-                    kind: TaskQueueKind::Unspecified as i32,
-                    normal_name: "".to_string(),
-                }),
-                request_id: request_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
-                workflow_id_reuse_policy: options.id_reuse_policy as i32,
-                workflow_execution_timeout: options
-                    .execution_timeout
-                    .and_then(|d| d.try_into().ok()),
-                workflow_run_timeout: options.run_timeout.and_then(|d| d.try_into().ok()),
-                workflow_task_timeout: options.task_timeout.and_then(|d| d.try_into().ok()),
-                search_attributes: options.search_attributes.map(|d| d.into()),
-                cron_schedule: options.cron_schedule.unwrap_or_default(),
-                request_eager_execution: options.enable_eager_workflow_start,
-                retry_policy: options.retry_policy,
-                ..Default::default()
-            },
+                workflow_type,
+                request_id,
+                options,
+            )?,
         )
         .await?
         .into_inner())
@@ -1183,6 +2270,241 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
+    async fn pause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: pause_activity_request::Activity,
+    ) -> Result<PauseActivityResponse> {
+        Ok(WorkflowService::pause_activity(
+            &mut self.inner.client.clone(),
+            PauseActivityRequest {
+                namespace: self.namespace.clone(),
+                execution: Some(WorkflowExecution {
+                    workflow_id,
+                    run_id: run_id.unwrap_or_default(),
+                }),
+                identity: self.inner.options.identity.clone(),
+                activity: Some(activity),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn unpause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: unpause_activity_request::Activity,
+        options: UnpauseActivityOptions,
+    ) -> Result<UnpauseActivityResponse> {
+        Ok(WorkflowService::unpause_activity(
+            &mut self.inner.client.clone(),
+            UnpauseActivityRequest {
+                namespace: self.namespace.clone(),
+                execution: Some(WorkflowExecution {
+                    workflow_id,
+                    run_id: run_id.unwrap_or_default(),
+                }),
+                identity: self.inner.options.identity.clone(),
+                activity: Some(activity),
+                reset_attempts: options.reset_attempts,
+                reset_heartbeat: options.reset_heartbeat,
+                jitter: options.jitter.and_then(|d| d.try_into().ok()),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn complete_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        result: Option<Payloads>,
+    ) -> Result<()> {
+        let mut client = self.inner.client.clone();
+        let identity = self.inner.options.identity.clone();
+        let namespace = self.namespace.clone();
+        match activity {
+            ActivityIdentifier::TaskToken(tt) => {
+                WorkflowService::respond_activity_task_completed(
+                    &mut client,
+                    RespondActivityTaskCompletedRequest {
+                        task_token: tt.0,
+                        result,
+                        identity,
+                        namespace,
+                        worker_version: None,
+                    },
+                )
+                .await?;
+            }
+            ActivityIdentifier::Id {
+                workflow_id,
+                run_id,
+                activity_id,
+            } => {
+                WorkflowService::respond_activity_task_completed_by_id(
+                    &mut client,
+                    RespondActivityTaskCompletedByIdRequest {
+                        namespace,
+                        workflow_id,
+                        run_id,
+                        activity_id,
+                        result,
+                        identity,
+                    },
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fail_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        failure: Failure,
+        last_heartbeat_details: Option<Payloads>,
+    ) -> Result<()> {
+        let mut client = self.inner.client.clone();
+        let identity = self.inner.options.identity.clone();
+        let namespace = self.namespace.clone();
+        match activity {
+            ActivityIdentifier::TaskToken(tt) => {
+                WorkflowService::respond_activity_task_failed(
+                    &mut client,
+                    RespondActivityTaskFailedRequest {
+                        task_token: tt.0,
+                        failure: Some(failure),
+                        identity,
+                        namespace,
+                        last_heartbeat_details,
+                        worker_version: None,
+                    },
+                )
+                .await?;
+            }
+            ActivityIdentifier::Id {
+                workflow_id,
+                run_id,
+                activity_id,
+            } => {
+                WorkflowService::respond_activity_task_failed_by_id(
+                    &mut client,
+                    RespondActivityTaskFailedByIdRequest {
+                        namespace,
+                        workflow_id,
+                        run_id,
+                        activity_id,
+                        failure: Some(failure),
+                        identity,
+                        last_heartbeat_details,
+                    },
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn cancel_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<()> {
+        let mut client = self.inner.client.clone();
+        let identity = self.inner.options.identity.clone();
+        let namespace = self.namespace.clone();
+        match activity {
+            ActivityIdentifier::TaskToken(tt) => {
+                WorkflowService::respond_activity_task_canceled(
+                    &mut client,
+                    RespondActivityTaskCanceledRequest {
+                        task_token: tt.0,
+                        details,
+                        identity,
+                        namespace,
+                        worker_version: None,
+                    },
+                )
+                .await?;
+            }
+            ActivityIdentifier::Id {
+                workflow_id,
+                run_id,
+                activity_id,
+            } => {
+                WorkflowService::respond_activity_task_canceled_by_id(
+                    &mut client,
+                    RespondActivityTaskCanceledByIdRequest {
+                        namespace,
+                        workflow_id,
+                        run_id,
+                        activity_id,
+                        details,
+                        identity,
+                    },
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn heartbeat_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<AsyncActivityHeartbeat> {
+        let mut client = self.inner.client.clone();
+        let identity = self.inner.options.identity.clone();
+        let namespace = self.namespace.clone();
+        Ok(match activity {
+            ActivityIdentifier::TaskToken(tt) => {
+                let resp = WorkflowService::record_activity_task_heartbeat(
+                    &mut client,
+                    RecordActivityTaskHeartbeatRequest {
+                        task_token: tt.0,
+                        details,
+                        identity,
+                        namespace,
+                    },
+                )
+                .await?
+                .into_inner();
+                AsyncActivityHeartbeat {
+                    cancel_requested: resp.cancel_requested,
+                    activity_paused: resp.activity_paused,
+                }
+            }
+            ActivityIdentifier::Id {
+                workflow_id,
+                run_id,
+                activity_id,
+            } => {
+                let resp = WorkflowService::record_activity_task_heartbeat_by_id(
+                    &mut client,
+                    RecordActivityTaskHeartbeatByIdRequest {
+                        namespace,
+                        workflow_id,
+                        run_id,
+                        activity_id,
+                        details,
+                        identity,
+                    },
+                )
+                .await?
+                .into_inner();
+                AsyncActivityHeartbeat {
+                    cancel_requested: resp.cancel_requested,
+                    activity_paused: resp.activity_paused,
+                }
+            }
+        })
+    }
+
     async fn signal_workflow_execution(
         &self,
         workflow_id: String,
@@ -1215,6 +2537,7 @@ impl WorkflowClientTrait for Client {
         options: SignalWithStartOptions,
         workflow_options: WorkflowOptions,
     ) -> Result<SignalWithStartWorkflowExecutionResponse> {
+        let workflow_start_delay = workflow_options.proto_start_delay()?;
         Ok(WorkflowService::signal_with_start_workflow_execution(
             &mut self.inner.client.clone(),
             SignalWithStartWorkflowExecutionRequest {
@@ -1236,6 +2559,7 @@ impl WorkflowClientTrait for Client {
                     .request_id
                     .unwrap_or_else(|| Uuid::new_v4().to_string()),
                 workflow_id_reuse_policy: workflow_options.id_reuse_policy as i32,
+                workflow_id_conflict_policy: workflow_options.id_conflict_policy as i32,
                 workflow_execution_timeout: workflow_options
                     .execution_timeout
                     .and_then(|d| d.try_into().ok()),
@@ -1245,7 +2569,9 @@ impl WorkflowClientTrait for Client {
                     .and_then(|d| d.try_into().ok()),
                 search_attributes: workflow_options.search_attributes.map(|d| d.into()),
                 cron_schedule: workflow_options.cron_schedule.unwrap_or_default(),
+                retry_policy: workflow_options.retry_policy,
                 header: options.signal_header,
+                workflow_start_delay,
                 ..Default::default()
             },
         )
@@ -1316,6 +2642,26 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
+    async fn get_filtered_workflow_execution_history(
+        &self,
+        execution: WorkflowExecution,
+        page_token: Vec<u8>,
+        filter_type: HistoryEventFilterType,
+    ) -> Result<GetWorkflowExecutionHistoryResponse> {
+        Ok(WorkflowService::get_workflow_execution_history(
+            &mut self.inner.client.clone(),
+            GetWorkflowExecutionHistoryRequest {
+                namespace: self.namespace.clone(),
+                execution: Some(execution),
+                next_page_token: page_token,
+                history_event_filter_type: filter_type as i32,
+                ..Default::default()
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
     async fn respond_legacy_query(
         &self,
         task_token: TaskToken,
@@ -1384,6 +2730,24 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
+    async fn reset_workflow_execution(
+        &self,
+        execution: WorkflowExecution,
+        workflow_task_finish_event_id: i64,
+        options: ResetWorkflowOptions,
+    ) -> Result<ResetWorkflowExecutionResponse> {
+        Ok(WorkflowService::reset_workflow_execution(
+            &mut self.inner.client.clone(),
+            options.into_request(
+                self.namespace.clone(),
+                execution,
+                workflow_task_finish_event_id,
+            ),
+        )
+        .await?
+        .into_inner())
+    }
+
     async fn register_namespace(
         &self,
         options: RegisterNamespaceOptions,
@@ -1396,6 +2760,18 @@ impl WorkflowClientTrait for Client {
         )
     }
 
+    async fn update_namespace(
+        &self,
+        options: UpdateNamespaceOptions,
+    ) -> Result<UpdateNamespaceResponse> {
+        let req = Into::<UpdateNamespaceRequest>::into(options);
+        Ok(
+            WorkflowService::update_namespace(&mut self.inner.client.clone(), req)
+                .await?
+                .into_inner(),
+        )
+    }
+
     async fn list_namespaces(&self) -> Result<ListNamespacesResponse> {
         Ok(WorkflowService::list_namespaces(
             &mut self.inner.client.clone(),
@@ -1414,6 +2790,129 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
+    async fn delete_namespace(&self, namespace: Namespace) -> Result<DeleteNamespaceResponse> {
+        Ok(OperatorService::delete_namespace(
+            &mut self.inner.clone(),
+            namespace.into_delete_namespace_request(),
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn add_search_attributes(
+        &self,
+        search_attributes: HashMap<String, IndexedValueType>,
+    ) -> Result<AddSearchAttributesResponse> {
+        Ok(OperatorService::add_search_attributes(
+            &mut self.inner.clone(),
+            AddSearchAttributesRequest {
+                search_attributes: search_attributes
+                    .into_iter()
+                    .map(|(name, ty)| (name, ty as i32))
+                    .collect(),
+                namespace: self.namespace.clone(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn remove_search_attributes(
+        &self,
+        search_attributes: Vec<String>,
+    ) -> Result<RemoveSearchAttributesResponse> {
+        Ok(OperatorService::remove_search_attributes(
+            &mut self.inner.clone(),
+            RemoveSearchAttributesRequest {
+                search_attributes,
+                namespace: self.namespace.clone(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn list_search_attributes(&self) -> Result<ListSearchAttributesResponse> {
+        Ok(OperatorService::list_search_attributes(
+            &mut self.inner.clone(),
+            ListSearchAttributesRequest {
+                namespace: self.namespace.clone(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn create_nexus_endpoint(
+        &self,
+        spec: EndpointSpec,
+    ) -> Result<CreateNexusEndpointResponse> {
+        Ok(OperatorService::create_nexus_endpoint(
+            &mut self.inner.clone(),
+            CreateNexusEndpointRequest { spec: Some(spec) },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn get_nexus_endpoint(&self, id: String) -> Result<GetNexusEndpointResponse> {
+        Ok(OperatorService::get_nexus_endpoint(
+            &mut self.inner.clone(),
+            GetNexusEndpointRequest { id },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn update_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+        spec: EndpointSpec,
+    ) -> Result<UpdateNexusEndpointResponse> {
+        Ok(OperatorService::update_nexus_endpoint(
+            &mut self.inner.clone(),
+            UpdateNexusEndpointRequest {
+                id,
+                version,
+                spec: Some(spec),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn delete_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+    ) -> Result<DeleteNexusEndpointResponse> {
+        Ok(OperatorService::delete_nexus_endpoint(
+            &mut self.inner.clone(),
+            DeleteNexusEndpointRequest { id, version },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn list_nexus_endpoints(
+        &self,
+        page_size: i32,
+        next_page_token: Vec<u8>,
+        name: Option<String>,
+    ) -> Result<ListNexusEndpointsResponse> {
+        Ok(OperatorService::list_nexus_endpoints(
+            &mut self.inner.clone(),
+            ListNexusEndpointsRequest {
+                page_size,
+                next_page_token,
+                name: name.unwrap_or_default(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
     async fn list_open_workflow_executions(
         &self,
         maximum_page_size: i32,
@@ -1494,13 +2993,197 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
-    async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse> {
-        Ok(WorkflowService::get_search_attributes(
+    async fn count_workflow_executions(&self, query: String) -> Result<WorkflowExecutionCount> {
+        Ok(WorkflowService::count_workflow_executions(
             &mut self.inner.client.clone(),
-            GetSearchAttributesRequest {},
+            CountWorkflowExecutionsRequest {
+                namespace: self.namespace.clone(),
+                query,
+            },
         )
         .await?
-        .into_inner())
+        .into_inner()
+        .into())
+    }
+
+    async fn list_schedules(
+        &self,
+        maximum_page_size: i32,
+        next_page_token: Vec<u8>,
+    ) -> Result<ListSchedulesResponse> {
+        Ok(WorkflowService::list_schedules(
+            &mut self.inner.client.clone(),
+            ListSchedulesRequest {
+                namespace: self.namespace.clone(),
+                maximum_page_size,
+                next_page_token,
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn list_task_queue_partitions(
+        &self,
+        task_queue: String,
+    ) -> Result<ListTaskQueuePartitionsResponse> {
+        Ok(WorkflowService::list_task_queue_partitions(
+            &mut self.inner.client.clone(),
+            ListTaskQueuePartitionsRequest {
+                namespace: self.namespace.clone(),
+                task_queue: Some(TaskQueue {
+                    name: task_queue,
+                    kind: TaskQueueKind::Normal as i32,
+                    normal_name: "".to_string(),
+                }),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn start_batch_operation(
+        &self,
+        options: BatchOperationOptions,
+    ) -> Result<StartBatchOperationResponse> {
+        Ok(WorkflowService::start_batch_operation(
+            &mut self.inner.client.clone(),
+            options.into_request(self.namespace.clone(), self.inner.options.identity.clone()),
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn describe_batch_operation(&self, job_id: String) -> Result<BatchOperationProgress> {
+        Ok(WorkflowService::describe_batch_operation(
+            &mut self.inner.client.clone(),
+            DescribeBatchOperationRequest {
+                namespace: self.namespace.clone(),
+                job_id,
+            },
+        )
+        .await?
+        .into_inner()
+        .into())
+    }
+
+    async fn stop_batch_operation(
+        &self,
+        job_id: String,
+        reason: String,
+    ) -> Result<StopBatchOperationResponse> {
+        Ok(WorkflowService::stop_batch_operation(
+            &mut self.inner.client.clone(),
+            StopBatchOperationRequest {
+                namespace: self.namespace.clone(),
+                job_id,
+                reason,
+                identity: self.inner.options.identity.clone(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn create_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        options: CreateScheduleOptions,
+    ) -> Result<CreateScheduleResponse> {
+        Ok(WorkflowService::create_schedule(
+            &mut self.inner.client.clone(),
+            CreateScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id,
+                schedule: Some(schedule.into()),
+                initial_patch: options.initial_patch.map(Into::into),
+                identity: self.inner.options.identity.clone(),
+                request_id: options
+                    .request_id
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                memo: options.memo.map(|fields| Memo { fields }),
+                search_attributes: options.search_attributes.map(Into::into),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn describe_schedule(&self, schedule_id: String) -> Result<DescribeScheduleResponse> {
+        Ok(WorkflowService::describe_schedule(
+            &mut self.inner.client.clone(),
+            DescribeScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id,
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn update_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        conflict_token: Vec<u8>,
+        request_id: Option<String>,
+    ) -> Result<UpdateScheduleResponse> {
+        Ok(WorkflowService::update_schedule(
+            &mut self.inner.client.clone(),
+            UpdateScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id,
+                schedule: Some(schedule.into()),
+                conflict_token,
+                identity: self.inner.options.identity.clone(),
+                request_id: request_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn patch_schedule(
+        &self,
+        schedule_id: String,
+        patch: SchedulePatch,
+        request_id: Option<String>,
+    ) -> Result<PatchScheduleResponse> {
+        Ok(WorkflowService::patch_schedule(
+            &mut self.inner.client.clone(),
+            PatchScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id,
+                patch: Some(patch.into()),
+                identity: self.inner.options.identity.clone(),
+                request_id: request_id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn delete_schedule(&self, schedule_id: String) -> Result<DeleteScheduleResponse> {
+        Ok(WorkflowService::delete_schedule(
+            &mut self.inner.client.clone(),
+            DeleteScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id,
+                identity: self.inner.options.identity.clone(),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse> {
+        Ok(WorkflowService::get_search_attributes(
+            &mut self.inner.client.clone(),
+            GetSearchAttributesRequest {},
+        )
+        .await?
+        .into_inner())
     }
 
     async fn update_workflow_execution(
@@ -1538,6 +3221,133 @@ impl WorkflowClientTrait for Client {
         .into_inner())
     }
 
+    async fn start_workflow_update(
+        &self,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<UpdateWorkflowExecutionResponse> {
+        Ok(WorkflowService::update_workflow_execution(
+            &mut self.inner.client.clone(),
+            self.update_request(update, wait_for),
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn start_update_with_start(
+        &self,
+        start: UpdateWithStartWorkflow,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<ExecuteMultiOperationResponse> {
+        start.validate()?;
+        let start = self.start_workflow_request(
+            start.input,
+            start.task_queue,
+            update.workflow_id.clone(),
+            start.workflow_type,
+            start.request_id,
+            start.options,
+        )?;
+        let update = self.update_request(update, wait_for);
+        Ok(WorkflowService::execute_multi_operation(
+            &mut self.inner.client.clone(),
+            ExecuteMultiOperationRequest {
+                namespace: self.namespace.clone(),
+                operations: vec![
+                    execute_multi_operation_request::Operation {
+                        operation: Some(
+                            execute_multi_operation_request::operation::Operation::StartWorkflow(
+                                start,
+                            ),
+                        ),
+                    },
+                    execute_multi_operation_request::Operation {
+                        operation: Some(
+                            execute_multi_operation_request::operation::Operation::UpdateWorkflow(
+                                update,
+                            ),
+                        ),
+                    },
+                ],
+            },
+        )
+        .await
+        .map_err(failed_operation_status)?
+        .into_inner())
+    }
+
+    async fn poll_workflow_execution_update(
+        &self,
+        update_ref: update::v1::UpdateRef,
+        wait_for: UpdateWaitStage,
+    ) -> Result<PollWorkflowExecutionUpdateResponse> {
+        Ok(WorkflowService::poll_workflow_execution_update(
+            &mut self.inner.client.clone(),
+            PollWorkflowExecutionUpdateRequest {
+                namespace: self.namespace.clone(),
+                update_ref: Some(update_ref),
+                identity: self.inner.options.identity.clone(),
+                wait_policy: Some(update::v1::WaitPolicy {
+                    lifecycle_stage: UpdateWorkflowExecutionLifecycleStage::from(wait_for) as i32,
+                }),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    async fn update_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        operation: BuildIdOperation,
+    ) -> Result<()> {
+        WorkflowService::update_worker_build_id_compatibility(
+            &mut self.inner.client.clone(),
+            operation.into_request(self.namespace.clone(), task_queue),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        max_sets: i32,
+    ) -> Result<CompatibleVersionSets> {
+        Ok(WorkflowService::get_worker_build_id_compatibility(
+            &mut self.inner.client.clone(),
+            GetWorkerBuildIdCompatibilityRequest {
+                namespace: self.namespace.clone(),
+                task_queue,
+                max_sets,
+            },
+        )
+        .await?
+        .into_inner()
+        .into())
+    }
+
+    async fn get_worker_task_reachability(
+        &self,
+        build_ids: Vec<String>,
+        task_queues: Vec<String>,
+        reachability: TaskReachability,
+    ) -> Result<BuildIdReachability> {
+        Ok(WorkflowService::get_worker_task_reachability(
+            &mut self.inner.client.clone(),
+            GetWorkerTaskReachabilityRequest {
+                namespace: self.namespace.clone(),
+                build_ids,
+                task_queues,
+                reachability: reachability as i32,
+            },
+        )
+        .await?
+        .into_inner()
+        .into())
+    }
+
     fn get_options(&self) -> &ClientOptions {
         &self.inner.options
     }
@@ -1580,13 +3390,249 @@ pub trait WfClientExt: WfHandleClient + Sized + Clone {
             },
         )
     }
+
+    /// Start a workflow and return a handle to the run which was started. If a workflow with the
+    /// same id is already running and `options.id_conflict_policy` is
+    /// [WorkflowIdConflictPolicy::UseExisting], the outcome is instead a handle to that run.
+    /// Otherwise, conflicts fail with `AlreadyExists`, as they do from
+    /// [WorkflowClientTrait::start_workflow].
+    fn start_workflow_or_attach(
+        &self,
+        input: Vec<Payload>,
+        task_queue: String,
+        workflow_id: String,
+        workflow_type: String,
+        request_id: Option<String>,
+        options: WorkflowOptions,
+    ) -> impl Future<Output = Result<WorkflowStartOutcome<Self>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let previously_running =
+                if options.id_conflict_policy == WorkflowIdConflictPolicy::UseExisting {
+                    running_run_id(self, &workflow_id).await?
+                } else {
+                    None
+                };
+            let res = self
+                .start_workflow(
+                    input,
+                    task_queue,
+                    workflow_id.clone(),
+                    workflow_type,
+                    request_id,
+                    options,
+                )
+                .await?;
+            let handle = self.get_untyped_workflow_handle(workflow_id, res.run_id);
+            Ok(start_outcome(handle, res.started, previously_running))
+        }
+    }
+
+    /// Signal a workflow, starting it first if no workflow with the given id is running, and
+    /// return a handle to the run which received the signal, which says whether it was started. A
+    /// running workflow is signalled rather than started again, unless
+    /// `workflow_options.id_conflict_policy` is [WorkflowIdConflictPolicy::TerminateExisting].
+    /// Whether a closed workflow's id may be reused for the new run is controlled by
+    /// `workflow_options.id_reuse_policy`.
+    ///
+    /// [WorkflowIdConflictPolicy::Fail] is rejected with `InvalidArgument`, since signal-with-start
+    /// never fails because the workflow is running.
+    fn signal_with_start_workflow(
+        &self,
+        options: SignalWithStartOptions,
+        workflow_options: WorkflowOptions,
+    ) -> impl Future<Output = Result<WorkflowStartOutcome<Self>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            if workflow_options.id_conflict_policy == WorkflowIdConflictPolicy::Fail {
+                return Err(tonic::Status::invalid_argument(
+                    "Signal-with-start can't use the Fail workflow id conflict policy",
+                ));
+            }
+            let workflow_id = options.workflow_id.clone();
+            let previously_running = if workflow_options.id_conflict_policy
+                == WorkflowIdConflictPolicy::TerminateExisting
+            {
+                None
+            } else {
+                running_run_id(self, &workflow_id).await?
+            };
+            let res = self
+                .signal_with_start_workflow_execution(options, workflow_options)
+                .await?;
+            let handle = self.get_untyped_workflow_handle(workflow_id, res.run_id);
+            Ok(start_outcome(handle, res.started, previously_running))
+        }
+    }
+
+    /// Wait for a workflow to close and return its result, following it across continue-as-new
+    /// and retries unless `opts` says otherwise. `run_id` may be left blank to start from the
+    /// latest run.
+    fn get_workflow_result<RT>(
+        &self,
+        workflow_id: impl Into<String>,
+        run_id: impl Into<String>,
+        opts: GetWorkflowResultOpts,
+    ) -> impl Future<Output = Result<WorkflowExecutionResult<RT>, anyhow::Error>> + Send
+    where
+        Self: Send + Sync,
+        RT: FromPayloadsExt + Send + Sync,
+    {
+        let rid = run_id.into();
+        let handle = WorkflowHandle::<Self, RT>::new(
+            self.clone(),
+            WorkflowExecutionInfo {
+                namespace: self.namespace().to_string(),
+                workflow_id: workflow_id.into(),
+                run_id: if rid.is_empty() { None } else { Some(rid) },
+            },
+        );
+        async move { handle.get_workflow_result(opts).await }
+    }
 }
 
 impl<T> WfClientExt for T where T: WfHandleClient + Clone + Sized {}
 
+/// The id of the run of `workflow_id` which is running, if any
+async fn running_run_id(
+    client: &impl WorkflowClientTrait,
+    workflow_id: &str,
+) -> Result<Option<String>> {
+    match client
+        .describe_workflow_execution(workflow_id.to_string(), None)
+        .await
+    {
+        Ok(desc) => Ok(desc
+            .workflow_execution_info
+            .filter(|info| info.status() == WorkflowExecutionStatus::Running)
+            .and_then(|info| info.execution)
+            .map(|exec| exec.run_id)),
+        Err(e) if e.code() == Code::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Servers which predate workflow id conflict policies never set `started`, and start a new run
+/// where newer ones would use the running one. So a run is only taken to be one which was already
+/// running if it's the one which was running before the call. A run someone else started in the
+/// meantime is reported as started.
+fn start_outcome<CT>(
+    handle: UntypedWorkflowHandle<CT>,
+    started: bool,
+    previously_running: Option<String>,
+) -> WorkflowStartOutcome<CT> {
+    if !started && previously_running.is_some() && handle.info().run_id == previously_running {
+        WorkflowStartOutcome::AttachedToRunning(handle)
+    } else {
+        WorkflowStartOutcome::Started(handle)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert_matches::assert_matches;
+    use temporal_sdk_core_protos::temporal::api::errordetails::v1::multi_operation_execution_failure::OperationStatus;
+
+    #[test]
+    fn multi_operation_failures_surface_the_failed_operation() {
+        let aborted = OperationStatus {
+            code: Code::Aborted as i32,
+            message: "aborted".to_string(),
+            details: vec![],
+        };
+        let failed = OperationStatus {
+            code: Code::InvalidArgument as i32,
+            message: "bad update".to_string(),
+            details: vec![],
+        };
+        let failure = MultiOperationExecutionFailure {
+            statuses: vec![aborted, failed],
+        };
+        let details = RpcStatus {
+            code: Code::InvalidArgument as i32,
+            message: "multi op failed".to_string(),
+            details: vec![prost_wkt_types::Any {
+                type_url: "type.googleapis.com/temporal.api.errordetails.v1.MultiOperationExecutionFailure".to_string(),
+                value: failure.encode_to_vec(),
+            }],
+        };
+        let status = Status::with_details(
+            Code::InvalidArgument,
+            "multi op failed",
+            details.encode_to_vec().into(),
+        );
+        let status = failed_operation_status(status);
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "bad update");
+
+        // Anything else is left alone
+        let status = failed_operation_status(Status::unavailable("down"));
+        assert_eq!(status.message(), "down");
+    }
+
+    #[tokio::test]
+    async fn lazy_connect_defers_failure_to_ensure_connected() {
+        // Nothing listens on port 1
+        let opts = ClientOptionsBuilder::default()
+            .target_url(Url::parse("http://127.0.0.1:1").unwrap())
+            .client_name("cli-tool")
+            .client_version("0.1.0")
+            .lazy_connect(true)
+            .build()
+            .unwrap();
+        let client = opts.connect_no_namespace(None).await.unwrap();
+        assert!(client.get_client().capabilities().is_none());
+        assert_eq!(
+            client.get_client().connection_state(),
+            ConnectionState::Unknown
+        );
+        assert_matches!(
+            client.get_client().ensure_connected().await,
+            Err(ClientInitError::SystemInfoCallError(s)) if s.code() == Code::Unavailable
+        );
+        assert_matches!(
+            client.get_client().connection_state(),
+            ConnectionState::Disconnected { .. }
+        );
+
+        let opts = ClientOptions {
+            lazy_connect: false,
+            ..opts
+        };
+        assert!(opts.connect_no_namespace(None).await.is_err());
+    }
+
+    #[test]
+    fn cloud_ops_options_add_api_version() {
+        let mut builder = ClientOptionsBuilder::default();
+        builder
+            .target_url(Url::parse("https://saas-api.tmprl.cloud:443").unwrap())
+            .client_name("cloud-tool")
+            .client_version("0.1.0");
+        let opts = builder.build().unwrap().cloud_ops_options();
+        assert_eq!(
+            opts.headers.unwrap()[CLOUD_API_VERSION_HEADER_KEY],
+            CLOUD_OPS_API_VERSION
+        );
+        // A version the user asked for is kept
+        let opts = builder
+            .headers(Some(HashMap::from([(
+                CLOUD_API_VERSION_HEADER_KEY.to_string(),
+                "v0.3.0".to_string(),
+            )])))
+            .build()
+            .unwrap()
+            .cloud_ops_options();
+        assert_eq!(
+            opts.headers.unwrap()[CLOUD_API_VERSION_HEADER_KEY],
+            "v0.3.0"
+        );
+    }
 
     #[test]
     fn applies_headers() {
@@ -1649,6 +3695,34 @@ mod tests {
         assert!(!req.metadata().contains_key("authorization"));
     }
 
+    #[test]
+    fn applies_trace_propagator() {
+        #[derive(Debug)]
+        struct FixedPropagator;
+        impl TracePropagator for FixedPropagator {
+            fn inject(&self, metadata: &mut tonic::metadata::MetadataMap) {
+                metadata.insert("traceparent", MetadataValue::from_static("00-abc-def-01"));
+            }
+        }
+
+        let opts = ClientOptionsBuilder::default()
+            .target_url(Url::parse("https://smolkitty").unwrap())
+            .client_name("cute-kitty".to_string())
+            .client_version("0.1.0".to_string())
+            .trace_propagator(Arc::new(FixedPropagator))
+            .build()
+            .unwrap();
+        let mut interceptor = ServiceCallInterceptor {
+            opts,
+            headers: Arc::new(RwLock::new(ClientHeaders {
+                user_headers: HashMap::new(),
+                api_key: None,
+            })),
+        };
+        let req = interceptor.call(tonic::Request::new(())).unwrap();
+        assert_eq!(req.metadata().get("traceparent").unwrap(), "00-abc-def-01");
+    }
+
     #[test]
     fn keep_alive_defaults() {
         let mut builder = ClientOptionsBuilder::default();
@@ -1667,8 +3741,136 @@ mod tests {
             opts.keep_alive.clone().unwrap().timeout,
             ClientKeepAliveConfig::default().timeout
         );
+        assert!(opts.keep_alive.clone().unwrap().permit_without_calls);
         // But can be set to none
         let opts = builder.keep_alive(None).build().unwrap();
         assert!(opts.keep_alive.is_none());
     }
+
+    #[test]
+    fn rejects_zero_dns_refresh_interval() {
+        let mut builder = ClientOptionsBuilder::default();
+        builder
+            .target_url(Url::parse("https://smolkitty").unwrap())
+            .client_name("cute-kitty".to_string())
+            .client_version("0.1.0".to_string())
+            .load_balancing(LoadBalancingOptions {
+                policy: LoadBalancingPolicy::RoundRobin,
+                dns_refresh_interval: Duration::ZERO,
+            });
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_retry_backoff() {
+        let mut builder = ClientOptionsBuilder::default();
+        builder
+            .target_url(Url::parse("https://smolkitty").unwrap())
+            .client_name("cute-kitty".to_string())
+            .client_version("0.1.0".to_string());
+        builder.retry_config(RetryConfig::default());
+        assert!(builder.build().is_ok());
+        builder.retry_config(RetryConfig {
+            randomization_factor: 1.5,
+            ..Default::default()
+        });
+        assert!(builder.build().is_err());
+        builder.retry_config(RetryConfig {
+            randomization_factor: -0.1,
+            ..Default::default()
+        });
+        assert!(builder.build().is_err());
+        builder.retry_config(RetryConfig {
+            initial_interval: Duration::from_secs(10),
+            max_interval: Duration::from_secs(1),
+            ..Default::default()
+        });
+        assert!(builder.build().is_err());
+        builder.retry_config(RetryConfig {
+            poll_reconnect: ReconnectPolicy {
+                initial_interval: Duration::from_secs(10),
+                max_interval: Duration::from_secs(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn rpc_timeouts_by_call() {
+        let timeouts = RpcTimeouts {
+            long_poll: Duration::from_secs(10),
+            history: Duration::from_secs(2),
+            task_completion: Duration::from_secs(3),
+            query_and_update: Duration::from_secs(4),
+            other: Duration::from_secs(5),
+        };
+        assert_eq!(
+            timeouts.for_call("poll_activity_task_queue"),
+            timeouts.long_poll
+        );
+        assert_eq!(
+            timeouts.for_call("get_workflow_execution_history_reverse"),
+            timeouts.history
+        );
+        assert_eq!(
+            timeouts.for_call("respond_workflow_task_completed"),
+            timeouts.task_completion
+        );
+        assert_eq!(
+            timeouts.for_call("query_workflow"),
+            timeouts.query_and_update
+        );
+        assert_eq!(
+            timeouts.for_call("start_workflow_execution"),
+            timeouts.other
+        );
+        assert_eq!(
+            RpcTimeouts::default().for_call("poll_workflow_task_queue"),
+            Duration::from_secs(70)
+        );
+        let too_short = RpcTimeouts {
+            long_poll: Duration::from_millis(500),
+            ..timeouts
+        };
+        assert_eq!(
+            too_short.for_call("poll_workflow_task_queue"),
+            MIN_LONG_POLL_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn invalid_start_delays_are_rejected() {
+        let delayed = WorkflowOptions {
+            start_delay: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        assert_eq!(
+            delayed.proto_start_delay().unwrap(),
+            Some(prost_types::Duration {
+                seconds: 10,
+                nanos: 0
+            })
+        );
+        for invalid in [
+            WorkflowOptions {
+                cron_schedule: Some("@hourly".to_string()),
+                ..delayed.clone()
+            },
+            WorkflowOptions {
+                enable_eager_workflow_start: true,
+                ..delayed.clone()
+            },
+            WorkflowOptions {
+                start_delay: Some(Duration::MAX),
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(
+                invalid.proto_start_delay().unwrap_err().code(),
+                Code::InvalidArgument
+            );
+        }
+    }
 }
@@ -0,0 +1,200 @@
+//! Streams over the results of list APIs, which fetch further pages as they're consumed
+
+use crate::{Result, WorkflowClientTrait};
+use futures::{
+    stream::{self, BoxStream},
+    Future, StreamExt, TryStreamExt,
+};
+use std::time::Duration;
+use temporal_sdk_core_protos::temporal::api::{
+    enums::v1::TaskQueueType, filter::v1::StartTimeFilter, schedule::v1::ScheduleListEntry,
+    taskqueue::v1::TaskQueuePartitionMetadata, workflow::v1::WorkflowExecutionInfo,
+    workflowservice::v1::list_open_workflow_executions_request::Filters as ListOpenFilters,
+};
+use tokio::time::Instant;
+
+/// Controls how the streams of [ListStreamsExt] fetch pages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ListStreamOptions {
+    /// How many results to ask for per page. Zero lets the server decide.
+    pub page_size: i32,
+    /// If set, fetches of successive pages are started at least this far apart, to keep large
+    /// listings from overwhelming visibility
+    pub min_page_interval: Option<Duration>,
+}
+
+/// Streams over the results of list APIs. Each stream fetches the next page once the previous one
+/// has been consumed, and ends after the first error.
+pub trait ListStreamsExt: WorkflowClientTrait + Clone + Send + Sync + 'static {
+    /// Stream workflow executions matching a visibility query, which may be empty to match all
+    fn stream_workflow_executions(
+        &self,
+        query: impl Into<String>,
+        opts: ListStreamOptions,
+    ) -> BoxStream<'static, Result<WorkflowExecutionInfo>> {
+        let client = self.clone();
+        let query = query.into();
+        paginate(opts, move |token| {
+            let client = client.clone();
+            let query = query.clone();
+            async move {
+                let resp = client
+                    .list_workflow_executions(opts.page_size, token, query)
+                    .await?;
+                Ok((resp.executions, resp.next_page_token))
+            }
+        })
+    }
+
+    /// Stream open workflow executions with Standard Visibility filtering
+    fn stream_open_workflow_executions(
+        &self,
+        start_time_filter: Option<StartTimeFilter>,
+        filters: Option<ListOpenFilters>,
+        opts: ListStreamOptions,
+    ) -> BoxStream<'static, Result<WorkflowExecutionInfo>> {
+        let client = self.clone();
+        paginate(opts, move |token| {
+            let client = client.clone();
+            let start_time_filter = start_time_filter.clone();
+            let filters = filters.clone();
+            async move {
+                let resp = client
+                    .list_open_workflow_executions(
+                        opts.page_size,
+                        token,
+                        start_time_filter,
+                        filters,
+                    )
+                    .await?;
+                Ok((resp.executions, resp.next_page_token))
+            }
+        })
+    }
+
+    /// Stream schedules
+    fn stream_schedules(
+        &self,
+        opts: ListStreamOptions,
+    ) -> BoxStream<'static, Result<ScheduleListEntry>> {
+        let client = self.clone();
+        paginate(opts, move |token| {
+            let client = client.clone();
+            async move {
+                let resp = client.list_schedules(opts.page_size, token).await?;
+                Ok((resp.schedules, resp.next_page_token))
+            }
+        })
+    }
+
+    /// Stream the partitions of a task queue, workflow task partitions first. The server returns
+    /// them all at once, so [ListStreamOptions] don't apply.
+    fn stream_task_queue_partitions(
+        &self,
+        task_queue: impl Into<String>,
+    ) -> BoxStream<'static, Result<(TaskQueueType, TaskQueuePartitionMetadata)>> {
+        let client = self.clone();
+        let task_queue = task_queue.into();
+        paginate(ListStreamOptions::default(), move |_| {
+            let client = client.clone();
+            let task_queue = task_queue.clone();
+            async move {
+                let resp = client.list_task_queue_partitions(task_queue).await?;
+                let partitions = resp
+                    .workflow_task_queue_partitions
+                    .into_iter()
+                    .map(|p| (TaskQueueType::Workflow, p))
+                    .chain(
+                        resp.activity_task_queue_partitions
+                            .into_iter()
+                            .map(|p| (TaskQueueType::Activity, p)),
+                    )
+                    .collect();
+                Ok((partitions, vec![]))
+            }
+        })
+    }
+}
+
+impl<T> ListStreamsExt for T where T: WorkflowClientTrait + Clone + Send + Sync + 'static {}
+
+/// Streams the items of the pages returned by `fetch`, which is given the token of the page to
+/// fetch and returns its items along with the token of the next page, empty if it was the last
+fn paginate<T, F, Fut>(opts: ListStreamOptions, fetch: F) -> BoxStream<'static, Result<T>>
+where
+    T: Send + 'static,
+    F: FnMut(Vec<u8>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(Vec<T>, Vec<u8>)>> + Send,
+{
+    stream::unfold(
+        (fetch, Some(vec![]), None::<Instant>),
+        move |(mut fetch, token, last_fetch)| async move {
+            let token = token?;
+            if let (Some(interval), Some(last)) = (opts.min_page_interval, last_fetch) {
+                tokio::time::sleep_until(last + interval).await;
+            }
+            let started = Instant::now();
+            Some(match fetch(token).await {
+                Ok((items, next)) => {
+                    let next = (!next.is_empty()).then_some(next);
+                    (Ok(items), (fetch, next, Some(started)))
+                }
+                Err(e) => (Err(e), (fetch, None, Some(started))),
+            })
+        },
+    )
+    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use tonic::Status;
+
+    #[tokio::test]
+    async fn follows_page_tokens_until_the_last_page() {
+        let tokens = Arc::new(Mutex::new(vec![]));
+        let seen = tokens.clone();
+        let items: Vec<_> = paginate(ListStreamOptions::default(), move |token| {
+            seen.lock().push(token.clone());
+            async move {
+                Ok(match token.as_slice() {
+                    [] => (vec![1, 2], vec![1]),
+                    [1] => (vec![], vec![2]),
+                    _ => (vec![3], vec![]),
+                })
+            }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(*tokens.lock(), vec![vec![], vec![1], vec![2]]);
+    }
+
+    #[tokio::test]
+    async fn spaces_out_pages_and_stops_after_errors() {
+        let started = Instant::now();
+        let results: Vec<_> = paginate(
+            ListStreamOptions {
+                page_size: 0,
+                min_page_interval: Some(Duration::from_millis(50)),
+            },
+            |token| async move {
+                match token.as_slice() {
+                    [] => Ok((vec![1], vec![1])),
+                    _ => Err(Status::unavailable("down")),
+                }
+            },
+        )
+        .collect()
+        .await;
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,208 @@
+//! Connecting to servers through Unix domain sockets or Windows named pipes, named in
+//! [crate::ClientOptions::target_url] as `unix:///path/to/socket` or `npipe://./pipe/name`
+
+use futures::future::BoxFuture;
+use http::Uri;
+use percent_encoding::percent_decode_str;
+use std::{
+    io,
+    path::PathBuf,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tonic::transport::{Channel, Endpoint};
+use tower::Service;
+use url::Url;
+
+#[cfg(unix)]
+type LocalStream = tokio::net::UnixStream;
+#[cfg(windows)]
+type LocalStream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+/// Calls made through a local socket still need an authority, which is all this provides unless
+/// an origin override or TLS domain replaces it
+pub(crate) const LOCAL_SOCKET_AUTHORITY: &str = "localhost";
+
+/// A socket on this machine the server is reached through, in place of TCP
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LocalSocket {
+    /// Path of the Unix domain socket, or name of the named pipe
+    path: PathBuf,
+}
+
+impl LocalSocket {
+    /// The local socket `url` names, if it names one. Fails if the URL is malformed, or names a
+    /// kind of socket this platform doesn't have.
+    pub(crate) fn from_url(url: &Url) -> Result<Option<Self>, String> {
+        let path = match url.scheme() {
+            "unix" if cfg!(unix) => unix_socket_path(url)?,
+            "npipe" if cfg!(windows) => pipe_name(url)?.into(),
+            "unix" | "npipe" => {
+                return Err(format!(
+                    "{}: targets aren't supported on this platform",
+                    url.scheme()
+                ))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Self { path }))
+    }
+
+    /// Connects the provided endpoint through the socket, when it's first used if `lazy`
+    pub(crate) async fn connect_endpoint(
+        &self,
+        endpoint: &Endpoint,
+        lazy: bool,
+    ) -> Result<Channel, tonic::transport::Error> {
+        let connector = LocalSocketConnector(Arc::new(self.path.clone()));
+        if lazy {
+            Ok(endpoint.connect_with_connector_lazy(connector))
+        } else {
+            endpoint.connect_with_connector(connector).await
+        }
+    }
+}
+
+/// `unix:///abs/path` and `unix:relative/path` both name a socket by path. A host is a mistake
+/// for `unix://abs/path`, which would otherwise quietly connect to `/path`.
+fn unix_socket_path(url: &Url) -> Result<PathBuf, String> {
+    if url.host_str().is_some_and(|h| !h.is_empty()) {
+        return Err(format!(
+            "Unix socket target {url} has a host, socket paths are written like unix:///path/to/socket"
+        ));
+    }
+    let path = percent_decode_str(url.path())
+        .decode_utf8()
+        .map_err(|e| format!("Unix socket target {url} isn't valid UTF-8: {e}"))?;
+    if path.is_empty() {
+        return Err(format!("Unix socket target {url} has no path"));
+    }
+    Ok(PathBuf::from(path.as_ref()))
+}
+
+/// `npipe://./pipe/name` names `\\.\pipe\name`, as does the `npipe:////./pipe/name` form Docker
+/// uses. A host other than `.` names a pipe on another machine.
+fn pipe_name(url: &Url) -> Result<String, String> {
+    let server = url.host_str().filter(|h| !h.is_empty()).unwrap_or(".");
+    let path = percent_decode_str(url.path())
+        .decode_utf8()
+        .map_err(|e| format!("Named pipe target {url} isn't valid UTF-8: {e}"))?;
+    let name = path.trim_start_matches('/');
+    let name = name.strip_prefix("./").unwrap_or(name);
+    let name = name.strip_prefix("pipe/").unwrap_or(name);
+    if name.is_empty() {
+        return Err(format!("Named pipe target {url} has no pipe name"));
+    }
+    Ok(format!(r"\\{server}\pipe\{}", name.replace('/', r"\")))
+}
+
+#[derive(Clone)]
+struct LocalSocketConnector(Arc<PathBuf>);
+
+impl Service<Uri> for LocalSocketConnector {
+    type Response = LocalStream;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<LocalStream>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let path = self.0.clone();
+        Box::pin(async move { open(&path).await })
+    }
+}
+
+#[cfg(unix)]
+async fn open(path: &std::path::Path) -> io::Result<LocalStream> {
+    tokio::net::UnixStream::connect(path).await
+}
+
+#[cfg(windows)]
+async fn open(path: &std::path::Path) -> io::Result<LocalStream> {
+    /// Returned while every instance of the pipe is serving another client
+    const ERROR_PIPE_BUSY: i32 = 231;
+    loop {
+        match tokio::net::windows::named_pipe::ClientOptions::new().open(path) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await
+            }
+            res => return res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unix_socket_paths() {
+        let path = |u: &str| unix_socket_path(&Url::parse(u).unwrap());
+        assert_eq!(
+            path("unix:///tmp/temporal.sock").unwrap(),
+            PathBuf::from("/tmp/temporal.sock")
+        );
+        assert_eq!(
+            path("unix:///tmp/my%20dir/t.sock").unwrap(),
+            PathBuf::from("/tmp/my dir/t.sock")
+        );
+        assert_eq!(
+            path("unix:run/temporal.sock").unwrap(),
+            PathBuf::from("run/temporal.sock")
+        );
+        assert!(path("unix://tmp/temporal.sock").is_err());
+        assert!(path("unix://").is_err());
+        assert_eq!(
+            LocalSocket::from_url(&Url::parse("http://localhost:7233").unwrap()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn parses_pipe_names() {
+        let name = |u: &str| pipe_name(&Url::parse(u).unwrap());
+        assert_eq!(
+            name("npipe://./pipe/temporal").unwrap(),
+            r"\\.\pipe\temporal"
+        );
+        assert_eq!(
+            name("npipe:////./pipe/temporal").unwrap(),
+            r"\\.\pipe\temporal"
+        );
+        assert_eq!(name("npipe:///temporal").unwrap(), r"\\.\pipe\temporal");
+        assert_eq!(
+            name("npipe://build-box/pipe/temporal").unwrap(),
+            r"\\build-box\pipe\temporal"
+        );
+        assert!(name("npipe://./pipe/").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn connects_through_unix_sockets() {
+        use temporal_sdk_core_protos::grpc::health::v1::{
+            health_client::HealthClient, HealthCheckRequest,
+        };
+
+        let path = std::env::temp_dir().join(format!("{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let socket =
+            LocalSocket::from_url(&Url::parse(&format!("unix://{}", path.display())).unwrap())
+                .unwrap()
+                .unwrap();
+        let endpoint = Endpoint::from_static("http://localhost");
+        let channel = socket.connect_endpoint(&endpoint, true).await.unwrap();
+        let call = tokio::spawn(async move {
+            HealthClient::new(channel)
+                .check(HealthCheckRequest::default())
+                .await
+        });
+        // Nothing serves gRPC on the socket, but accepting the connection shows it was dialed
+        let (stream, _) = listener.accept().await.unwrap();
+        drop(stream);
+        assert!(call.await.unwrap().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{AttachMetricLabels, LONG_POLL_METHOD_NAMES};
+use crate::{
+    connection::ConnectionMonitor, tls_reload::ReloadableChannel, AttachMetricLabels, CallAttempt,
+    LONG_POLL_METHOD_NAMES,
+};
 use futures::{future::BoxFuture, FutureExt};
 use std::{
     sync::Arc,
@@ -6,10 +9,10 @@ use std::{
     time::{Duration, Instant},
 };
 use temporal_sdk_core_api::telemetry::metrics::{
-    CoreMeter, Counter, HistogramDuration, MetricAttributes, MetricKeyValue, MetricParameters,
-    TemporalMeter,
+    CoreMeter, Counter, Gauge, HistogramDuration, MetricAttributes, MetricKeyValue,
+    MetricParameters, TemporalMeter,
 };
-use tonic::{body::BoxBody, transport::Channel};
+use tonic::{body::BoxBody, Code, Status};
 use tower::Service;
 
 /// Used to track context associated with metrics, and record/update them
@@ -29,6 +32,9 @@ pub struct MetricsContext {
 
     svc_request_latency: Arc<dyn HistogramDuration>,
     long_svc_request_latency: Arc<dyn HistogramDuration>,
+
+    rpc_request: Arc<dyn Counter>,
+    rpc_request_latency: Arc<dyn HistogramDuration>,
 }
 
 impl MetricsContext {
@@ -67,6 +73,20 @@ impl MetricsContext {
                 unit: "duration".into(),
                 description: "Histogram of client long-poll request latencies".into(),
             }),
+            rpc_request: meter.counter(MetricParameters {
+                name: "rpc_request".into(),
+                description: "Count of client request attempts by rpc name, status code, and \
+                              attempt number"
+                    .into(),
+                unit: "".into(),
+            }),
+            rpc_request_latency: meter.histogram_duration(MetricParameters {
+                name: "rpc_request_latency".into(),
+                unit: "duration".into(),
+                description: "Histogram of client request attempt latencies by rpc name, status \
+                              code, and attempt number"
+                    .into(),
+            }),
             meter,
         }
     }
@@ -108,9 +128,124 @@ impl MetricsContext {
             self.svc_request_latency.record(dur, &self.kvs);
         }
     }
+
+    /// Record one attempt of a request, long polls included, by the status code it completed with
+    pub(crate) fn record_rpc_attempt(&self, code: Code, attempt: usize, dur: Duration) {
+        let kvs = self.meter.extend_attributes(
+            self.kvs.clone(),
+            [status_code_kv(code), attempt_kv(attempt)].into(),
+        );
+        self.rpc_request.add(1, &kvs);
+        self.rpc_request_latency.record(dur, &kvs);
+    }
+}
+
+/// Records failovers between the endpoints a client is configured with
+#[derive(Clone)]
+pub(crate) struct FailoverMetrics {
+    meter: Arc<dyn CoreMeter>,
+    kvs: MetricAttributes,
+    failover: Arc<dyn Counter>,
+}
+
+impl FailoverMetrics {
+    pub(crate) fn new(tm: TemporalMeter) -> Self {
+        let meter = tm.inner;
+        Self {
+            kvs: meter.new_attributes(tm.default_attribs),
+            failover: meter.counter(MetricParameters {
+                name: "client_failover".into(),
+                description: "Count of client switches between endpoints by endpoint switched to"
+                    .into(),
+                unit: "".into(),
+            }),
+            meter,
+        }
+    }
+
+    pub(crate) fn failed_over(&self, endpoint: String, reason: &'static str) {
+        let kvs = self.meter.extend_attributes(
+            self.kvs.clone(),
+            [
+                MetricKeyValue::new(KEY_ENDPOINT, endpoint),
+                MetricKeyValue::new(KEY_FAILOVER_REASON, reason),
+            ]
+            .into(),
+        );
+        self.failover.add(1, &kvs);
+    }
+}
+
+/// Records how saturated a client's [crate::RpcConcurrencyLimit] is
+#[derive(Clone, derive_more::DebugCustom)]
+#[debug(fmt = "RpcLimiterMetrics {{ attribs: {kvs:?} }}")]
+pub(crate) struct RpcLimiterMetrics {
+    kvs: MetricAttributes,
+    in_use: Arc<dyn Gauge>,
+    queued: Arc<dyn Gauge>,
+    rejected: Arc<dyn Counter>,
+    wait_latency: Arc<dyn HistogramDuration>,
+}
+
+impl RpcLimiterMetrics {
+    pub(crate) fn new(tm: TemporalMeter) -> Self {
+        let meter = tm.inner;
+        Self {
+            kvs: meter.new_attributes(tm.default_attribs),
+            in_use: meter.gauge(MetricParameters {
+                name: "rpc_concurrency_in_use".into(),
+                description: "Number of client requests in flight under the concurrency limit"
+                    .into(),
+                unit: "".into(),
+            }),
+            queued: meter.gauge(MetricParameters {
+                name: "rpc_concurrency_queued".into(),
+                description: "Number of client requests waiting for the concurrency limit".into(),
+                unit: "".into(),
+            }),
+            rejected: meter.counter(MetricParameters {
+                name: "rpc_concurrency_rejected".into(),
+                description: "Count of client requests rejected because too many were waiting \
+                              for the concurrency limit"
+                    .into(),
+                unit: "".into(),
+            }),
+            wait_latency: meter.histogram_duration(MetricParameters {
+                name: "rpc_concurrency_wait_latency".into(),
+                unit: "duration".into(),
+                description: "Histogram of how long client requests waited for the concurrency \
+                              limit"
+                    .into(),
+            }),
+        }
+    }
+
+    pub(crate) fn in_use(&self, count: u64) {
+        self.in_use.record(count, &self.kvs);
+    }
+
+    pub(crate) fn queued(&self, count: u64) {
+        self.queued.record(count, &self.kvs);
+    }
+
+    pub(crate) fn rejected(&self) {
+        self.rejected.add(1, &self.kvs);
+    }
+
+    pub(crate) fn waited(&self, dur: Duration) {
+        self.wait_latency.record(dur, &self.kvs);
+    }
 }
 
+/// Attempts from this one on share an attempt label, to bound its cardinality for calls which are
+/// retried indefinitely, like long polls
+const MAX_ATTEMPT_LABEL: usize = 5;
+
+const KEY_ATTEMPT: &str = "attempt";
+const KEY_ENDPOINT: &str = "endpoint";
+const KEY_FAILOVER_REASON: &str = "reason";
 const KEY_NAMESPACE: &str = "namespace";
+const KEY_STATUS_CODE: &str = "status_code";
 const KEY_SVC_METHOD: &str = "operation";
 const KEY_TASK_QUEUE: &str = "task_queue";
 
@@ -126,10 +261,69 @@ pub(crate) fn svc_operation(op: String) -> MetricKeyValue {
     MetricKeyValue::new(KEY_SVC_METHOD, op)
 }
 
+fn status_code_kv(code: Code) -> MetricKeyValue {
+    let name = match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    };
+    MetricKeyValue::new(KEY_STATUS_CODE, name)
+}
+
+fn attempt_kv(attempt: usize) -> MetricKeyValue {
+    let label = if attempt >= MAX_ATTEMPT_LABEL {
+        format!("{MAX_ATTEMPT_LABEL}+")
+    } else {
+        attempt.to_string()
+    };
+    MetricKeyValue::new(KEY_ATTEMPT, label)
+}
+
+/// The gRPC status code a response was completed with, as far as can be told from its headers.
+/// Errors are almost always sent without a body, with the status in the headers, so responses with
+/// no status there are counted as successes. Transport errors are counted as `UNAVAILABLE`, which is
+/// what they're retried as.
+fn response_code(
+    res: &Result<http::Response<tonic::transport::Body>, tonic::transport::Error>,
+) -> Code {
+    let resp = match res {
+        Ok(r) => r,
+        Err(_) => return Code::Unavailable,
+    };
+    if let Some(status) = Status::from_header_map(resp.headers()) {
+        return status.code();
+    }
+    // Per the gRPC spec's mapping for responses from things like proxies which aren't gRPC aware
+    match resp.status().as_u16() {
+        200..=299 => Code::Ok,
+        400 => Code::Internal,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::Unimplemented,
+        429 | 502 | 503 | 504 => Code::Unavailable,
+        _ => Code::Unknown,
+    }
+}
+
 /// Implements metrics functionality for gRPC (really, any http) calls
 #[derive(Debug, Clone)]
 pub struct GrpcMetricSvc {
-    pub(crate) inner: Channel,
+    pub(crate) inner: ReloadableChannel,
+    pub(crate) connection: ConnectionMonitor,
     // If set to none, metrics are a no-op
     pub(crate) metrics: Option<MetricsContext>,
 }
@@ -166,12 +360,20 @@ impl Service<http::Request<BoxBody>> for GrpcMetricSvc {
                     metrics
                 })
             });
+        let attempt = req.extensions().get::<CallAttempt>().map_or(1, |a| a.0);
         let callfut = self.inner.call(req);
+        let connection = self.connection.clone();
         async move {
             let started = Instant::now();
             let res = callfut.await;
+            match &res {
+                Ok(_) => connection.record_reached_server(),
+                Err(e) => connection.record_transport_error(e),
+            }
             if let Some(metrics) = metrics {
-                metrics.record_svc_req_latency(started.elapsed());
+                let elapsed = started.elapsed();
+                metrics.record_svc_req_latency(elapsed);
+                metrics.record_rpc_attempt(response_code(&res), attempt, elapsed);
                 if res.is_err() {
                     metrics.svc_request_failed();
                 }
@@ -181,3 +383,32 @@ impl Service<http::Request<BoxBody>> for GrpcMetricSvc {
         .boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_api::telemetry::metrics::MetricValue;
+
+    #[test]
+    fn reads_status_codes_from_responses() {
+        let resp = |status: u16, grpc_status: Option<&str>| {
+            let mut b = http::Response::builder().status(status);
+            if let Some(s) = grpc_status {
+                b = b.header("grpc-status", s);
+            }
+            Ok(b.body(tonic::transport::Body::empty()).unwrap())
+        };
+        assert_eq!(response_code(&resp(200, None)), Code::Ok);
+        assert_eq!(response_code(&resp(200, Some("5"))), Code::NotFound);
+        assert_eq!(response_code(&resp(503, None)), Code::Unavailable);
+        let attempt = |n| format!("{:?}", attempt_kv(n).value);
+        assert_eq!(
+            attempt(2),
+            format!("{:?}", MetricValue::from("2".to_string()))
+        );
+        assert_eq!(
+            attempt(9),
+            format!("{:?}", MetricValue::from("5+".to_string()))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Setting up namespaces, for test harnesses and provisioning tools
+
+use crate::{Namespace, RegisterNamespaceOptions, Result, WorkflowClientTrait};
+use futures::Future;
+use std::time::Duration;
+use temporal_sdk_core_protos::temporal::api::{
+    enums::v1::NamespaceState, workflowservice::v1::DescribeNamespaceResponse,
+};
+use tonic::{Code, Status};
+
+const FIRST_DESCRIBE_DELAY: Duration = Duration::from_millis(100);
+const MAX_DESCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Registers namespaces and waits for them to be usable. A registered namespace may not be
+/// visible to every frontend of the server straight away, so describing it, or starting workflows
+/// in it, can fail for a little while.
+pub trait NamespaceAdminExt: WorkflowClientTrait + Clone + Send + Sync + 'static {
+    /// Register a namespace and wait, for up to `timeout`, until it's ready. A namespace which
+    /// already exists isn't an error, so this may be used to make sure one exists, but its
+    /// settings aren't changed to match `options` then.
+    fn register_namespace_and_wait(
+        &self,
+        options: RegisterNamespaceOptions,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<DescribeNamespaceResponse>> + Send {
+        let client = self.clone();
+        async move {
+            let namespace = Namespace::Name(options.namespace.clone());
+            match client.register_namespace(options).await {
+                Ok(_) => {}
+                Err(e) if e.code() == Code::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+            wait_until_ready(&client, namespace, timeout).await
+        }
+    }
+
+    /// Describe a namespace once it exists and is registered, retrying for up to `timeout`.
+    /// Fails with `DeadlineExceeded` if it isn't ready by then.
+    fn wait_for_namespace(
+        &self,
+        namespace: Namespace,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<DescribeNamespaceResponse>> + Send {
+        let client = self.clone();
+        async move { wait_until_ready(&client, namespace, timeout).await }
+    }
+}
+
+impl<T> NamespaceAdminExt for T where T: WorkflowClientTrait + Clone + Send + Sync + 'static {}
+
+async fn wait_until_ready(
+    client: &(impl WorkflowClientTrait + Sync),
+    namespace: Namespace,
+    timeout: Duration,
+) -> Result<DescribeNamespaceResponse> {
+    let poll = async {
+        let mut delay = FIRST_DESCRIBE_DELAY;
+        loop {
+            match client.describe_namespace(namespace.clone()).await {
+                Ok(resp)
+                    if resp.namespace_info.as_ref().map(|i| i.state())
+                        == Some(NamespaceState::Registered) =>
+                {
+                    return Ok(resp);
+                }
+                // Not visible here yet, or still being set up
+                Ok(_) => {}
+                Err(e) if e.code() == Code::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_DESCRIBE_DELAY);
+        }
+    };
+    tokio::time::timeout(timeout, poll)
+        .await
+        .map_err(|_| Status::deadline_exceeded("Namespace wasn't ready in time"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockWorkflowClientTrait;
+    use temporal_sdk_core_protos::temporal::api::namespace::v1::NamespaceInfo;
+
+    #[tokio::test]
+    async fn waits_until_registered() {
+        let mut client = MockWorkflowClientTrait::new();
+        let mut describes = 0;
+        client
+            .expect_describe_namespace()
+            .times(3)
+            .returning(move |_| {
+                describes += 1;
+                match describes {
+                    1 => Err(Status::not_found("no such namespace")),
+                    2 => Ok(DescribeNamespaceResponse::default()),
+                    _ => Ok(DescribeNamespaceResponse {
+                        namespace_info: Some(NamespaceInfo {
+                            name: "ns".to_string(),
+                            state: NamespaceState::Registered as i32,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                }
+            });
+        let resp = wait_until_ready(
+            &client,
+            Namespace::Name("ns".to_string()),
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.namespace_info.unwrap().name, "ns");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_timeout() {
+        let mut client = MockWorkflowClientTrait::new();
+        client
+            .expect_describe_namespace()
+            .returning(|_| Err(Status::not_found("no such namespace")));
+        let err = wait_until_ready(
+            &client,
+            Namespace::Name("ns".to_string()),
+            Duration::from_millis(300),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), Code::DeadlineExceeded);
+    }
+}
@@ -3,11 +3,15 @@
 //! happen.
 
 use crate::{
+    concurrency::{RpcLimiter, RpcPermit},
+    header_provider::CachedHeaderProvider,
+    interceptor::{intercepted_call, ClientInterceptor},
     metrics::{namespace_kv, task_queue_kv},
     raw::sealed::RawClientLike,
+    retry::{record_call_success, CallType},
     worker_registry::{Slot, SlotManager},
-    Client, ConfiguredClient, InterceptedMetricsSvc, RetryClient, TemporalServiceClient,
-    LONG_POLL_TIMEOUT,
+    Client, ConfiguredClient, InterceptedMetricsSvc, RetryClient, RpcTimeouts,
+    TemporalServiceClient, TEMPORAL_NAMESPACE_HEADER_KEY,
 };
 use futures::{future::BoxFuture, FutureExt, TryFutureExt};
 use std::sync::Arc;
@@ -15,6 +19,7 @@ use temporal_sdk_core_api::telemetry::metrics::MetricKeyValue;
 use temporal_sdk_core_protos::{
     grpc::health::v1::{health_client::HealthClient, *},
     temporal::api::{
+        cloud::cloudservice::{v1 as cloudreq, v1::cloud_service_client::CloudServiceClient},
         operatorservice::v1::{operator_service_client::OperatorServiceClient, *},
         taskqueue::v1::TaskQueue,
         testservice::v1::{test_service_client::TestServiceClient, *},
@@ -45,6 +50,12 @@ pub(super) mod sealed {
         /// Return a mutable ref to the operator service client instance
         fn operator_client_mut(&mut self) -> &mut OperatorServiceClient<Self::SvcType>;
 
+        /// Return a ref to the cloud operations service client instance
+        fn cloud_client(&self) -> &CloudServiceClient<Self::SvcType>;
+
+        /// Return a mutable ref to the cloud operations service client instance
+        fn cloud_client_mut(&mut self) -> &mut CloudServiceClient<Self::SvcType>;
+
         /// Return a ref to the test service client instance
         fn test_client(&self) -> &TestServiceClient<Self::SvcType>;
 
@@ -60,6 +71,26 @@ pub(super) mod sealed {
         /// Return a registry with workers using this client instance
         fn get_workers_info(&self) -> Option<Arc<SlotManager>>;
 
+        /// Return the deadlines applied to calls which don't set their own
+        fn rpc_timeouts(&self) -> RpcTimeouts {
+            RpcTimeouts::default()
+        }
+
+        /// Return the provider of headers to attach to each call, if there is one
+        fn header_provider(&self) -> Option<Arc<CachedHeaderProvider>> {
+            None
+        }
+
+        /// Return the interceptors to run around each call
+        fn interceptors(&self) -> Arc<[Arc<dyn ClientInterceptor>]> {
+            Arc::new([])
+        }
+
+        /// Return the limiter of concurrent calls, if there is one
+        fn rpc_limiter(&self) -> Option<Arc<RpcLimiter>> {
+            None
+        }
+
         async fn call<F, Req, Resp>(
             &mut self,
             _call_name: &'static str,
@@ -100,6 +131,14 @@ where
         self.get_client_mut().operator_client_mut()
     }
 
+    fn cloud_client(&self) -> &CloudServiceClient<Self::SvcType> {
+        self.get_client().cloud_client()
+    }
+
+    fn cloud_client_mut(&mut self) -> &mut CloudServiceClient<Self::SvcType> {
+        self.get_client_mut().cloud_client_mut()
+    }
+
     fn test_client(&self) -> &TestServiceClient<Self::SvcType> {
         self.get_client().test_client()
     }
@@ -120,6 +159,22 @@ where
         self.get_client().get_workers_info()
     }
 
+    fn rpc_timeouts(&self) -> RpcTimeouts {
+        self.get_client().rpc_timeouts()
+    }
+
+    fn header_provider(&self) -> Option<Arc<CachedHeaderProvider>> {
+        self.get_client().header_provider()
+    }
+
+    fn interceptors(&self) -> Arc<[Arc<dyn ClientInterceptor>]> {
+        self.get_client().interceptors()
+    }
+
+    fn rpc_limiter(&self) -> Option<Arc<RpcLimiter>> {
+        self.get_client().rpc_limiter()
+    }
+
     async fn call<F, Req, Resp>(
         &mut self,
         call_name: &'static str,
@@ -132,12 +187,20 @@ where
         F: Send + Sync + Unpin + 'static,
     {
         let rtc = self.get_retry_config(call_name);
+        let budget = rtc.retry_budget.clone();
+        let mut attempt = 0;
         let fact = || {
-            let req_clone = req_cloner(&req);
+            attempt += 1;
+            let mut req_clone = req_cloner(&req);
+            req_clone.extensions_mut().insert(CallAttempt(attempt));
             callfn(self, req_clone)
         };
         let res = Self::make_future_retry(rtc, fact, call_name);
-        res.map_err(|(e, _attempt)| e).map_ok(|x| x.0).await
+        let res = res.map_err(|(e, _attempt)| e).map_ok(|x| x.0).await;
+        if res.is_ok() {
+            record_call_success(budget.as_ref());
+        }
+        res
     }
 }
 
@@ -167,6 +230,14 @@ where
         self.operator_svc_mut()
     }
 
+    fn cloud_client(&self) -> &CloudServiceClient<Self::SvcType> {
+        self.cloud_svc()
+    }
+
+    fn cloud_client_mut(&mut self) -> &mut CloudServiceClient<Self::SvcType> {
+        self.cloud_svc_mut()
+    }
+
     fn test_client(&self) -> &TestServiceClient<Self::SvcType> {
         self.test_svc()
     }
@@ -214,6 +285,14 @@ where
         self.client.operator_client_mut()
     }
 
+    fn cloud_client(&self) -> &CloudServiceClient<Self::SvcType> {
+        self.client.cloud_client()
+    }
+
+    fn cloud_client_mut(&mut self) -> &mut CloudServiceClient<Self::SvcType> {
+        self.client.cloud_client_mut()
+    }
+
     fn test_client(&self) -> &TestServiceClient<Self::SvcType> {
         self.client.test_client()
     }
@@ -233,6 +312,22 @@ where
     fn get_workers_info(&self) -> Option<Arc<SlotManager>> {
         Some(self.workers())
     }
+
+    fn rpc_timeouts(&self) -> RpcTimeouts {
+        self.options().rpc_timeouts
+    }
+
+    fn header_provider(&self) -> Option<Arc<CachedHeaderProvider>> {
+        self.header_provider.clone()
+    }
+
+    fn interceptors(&self) -> Arc<[Arc<dyn ClientInterceptor>]> {
+        self.interceptors.clone()
+    }
+
+    fn rpc_limiter(&self) -> Option<Arc<RpcLimiter>> {
+        self.rpc_limiter.clone()
+    }
 }
 
 impl RawClientLike for Client {
@@ -254,6 +349,14 @@ impl RawClientLike for Client {
         self.inner.operator_client_mut()
     }
 
+    fn cloud_client(&self) -> &CloudServiceClient<Self::SvcType> {
+        self.inner.cloud_client()
+    }
+
+    fn cloud_client_mut(&mut self) -> &mut CloudServiceClient<Self::SvcType> {
+        self.inner.cloud_client_mut()
+    }
+
     fn test_client(&self) -> &TestServiceClient<Self::SvcType> {
         self.inner.test_client()
     }
@@ -273,6 +376,22 @@ impl RawClientLike for Client {
     fn get_workers_info(&self) -> Option<Arc<SlotManager>> {
         self.inner.get_workers_info()
     }
+
+    fn rpc_timeouts(&self) -> RpcTimeouts {
+        self.inner.rpc_timeouts()
+    }
+
+    fn header_provider(&self) -> Option<Arc<CachedHeaderProvider>> {
+        self.inner.header_provider()
+    }
+
+    fn interceptors(&self) -> Arc<[Arc<dyn ClientInterceptor>]> {
+        self.inner.interceptors()
+    }
+
+    fn rpc_limiter(&self) -> Option<Arc<RpcLimiter>> {
+        self.inner.rpc_limiter()
+    }
 }
 
 /// Helper for cloning a tonic request as long as the inner message may be cloned.
@@ -294,6 +413,10 @@ fn req_cloner<T: Clone>(cloneme: &Request<T>) -> Request<T> {
     new_req
 }
 
+/// Which attempt of a call a request is made for, counting from one
+#[derive(Clone, Copy, Debug)]
+pub(super) struct CallAttempt(pub(super) usize);
+
 #[derive(Debug)]
 pub(super) struct AttachMetricLabels {
     pub(super) labels: Vec<MetricKeyValue>,
@@ -360,6 +483,21 @@ where
 {
 }
 
+/// Attaches the namespace of the provided request as a header, and returns metric labels for it
+macro_rules! namespaced_request {
+    ($req:ident) => {{
+        let ns_str = $req.get_ref().namespace.clone();
+        if !$req.metadata().contains_key(TEMPORAL_NAMESPACE_HEADER_KEY) {
+            // Namespaces which aren't valid header values can't be routed by it anyway
+            if let Ok(val) = ns_str.parse() {
+                $req.metadata_mut()
+                    .insert(TEMPORAL_NAMESPACE_HEADER_KEY, val);
+            }
+        }
+        AttachMetricLabels::namespace(ns_str)
+    }};
+}
+
 /// Helps re-declare gRPC client methods
 macro_rules! proxy {
     ($client_type:tt, $client_meth:ident, $method:ident, $req:ty, $resp:ty $(, $closure:expr)?) => {
@@ -371,8 +509,17 @@ macro_rules! proxy {
             #[allow(unused_mut)]
             let fact = |c: &mut Self, mut req: tonic::Request<$req>| {
                 $( type_closure_arg(&mut req, $closure); )*
+                set_default_timeout(&mut req, c.rpc_timeouts(), stringify!($method));
+                let header_provider = c.header_provider();
+                let interceptors = c.interceptors();
+                let limiter = c.rpc_limiter();
                 let mut c = c.$client_meth().clone();
-                async move { c.$method(req).await }.boxed()
+                async move {
+                    apply_provided_headers(header_provider, &mut req).await?;
+                    let _permit = acquire_rpc_permit(limiter, stringify!($method)).await?;
+                    intercepted_call(&interceptors, stringify!($method), req,
+                                     |req| c.$method(req)).await
+                }.boxed()
             };
             self.call(stringify!($method), fact, request.into_request())
         }
@@ -388,9 +535,19 @@ macro_rules! proxy {
             let fact = |c: &mut Self, mut req: tonic::Request<$req>| {
                 let data = type_closure_two_arg(&mut req, c.get_workers_info().unwrap(),
                                                 $closure_before);
+                set_default_timeout(&mut req, c.rpc_timeouts(), stringify!($method));
+                let header_provider = c.header_provider();
+                let interceptors = c.interceptors();
+                let limiter = c.rpc_limiter();
                 let mut c = c.$client_meth().clone();
                 async move {
-                    type_closure_two_arg(c.$method(req).await, data, $closure_after)
+                    let res = async {
+                        apply_provided_headers(header_provider, &mut req).await?;
+                        let _permit = acquire_rpc_permit(limiter, stringify!($method)).await?;
+                        intercepted_call(&interceptors, stringify!($method), req,
+                                         |req| c.$method(req)).await
+                    }.await;
+                    type_closure_two_arg(res, data, $closure_after)
                 }.boxed()
             };
             self.call(stringify!($method), fact, request.into_request())
@@ -424,6 +581,37 @@ macro_rules! proxier {
     };
 }
 
+/// Sets the configured deadline for the call, unless the request already has one
+fn set_default_timeout<T>(req: &mut Request<T>, timeouts: RpcTimeouts, call_name: &str) {
+    if !req.metadata().contains_key("grpc-timeout") {
+        req.set_timeout(timeouts.for_call(call_name));
+    }
+}
+
+async fn apply_provided_headers<T>(
+    provider: Option<Arc<CachedHeaderProvider>>,
+    req: &mut Request<T>,
+) -> Result<(), Status> {
+    match provider {
+        Some(p) => p.apply_to_request(req).await,
+        None => Ok(()),
+    }
+}
+
+/// Waits for room under the concurrency limit, if there is one. Polls for tasks aren't limited,
+/// since they're already bounded by the pollers configured for each worker.
+async fn acquire_rpc_permit(
+    limiter: Option<Arc<RpcLimiter>>,
+    call_name: &str,
+) -> Result<Option<RpcPermit>, Status> {
+    match limiter {
+        Some(l) if CallType::from_call_name(call_name) == CallType::Normal => {
+            l.acquire().await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
 // Nice little trick to avoid the callsite asking to type the closure parameter
 fn type_closure_arg<T, R>(arg: T, f: impl FnOnce(T) -> R) -> R {
     f(arg)
@@ -440,7 +628,7 @@ proxier! {
         RegisterNamespaceRequest,
         RegisterNamespaceResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -449,7 +637,7 @@ proxier! {
         DescribeNamespaceRequest,
         DescribeNamespaceResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -463,7 +651,7 @@ proxier! {
         UpdateNamespaceRequest,
         UpdateNamespaceResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -472,7 +660,7 @@ proxier! {
         DeprecateNamespaceRequest,
         DeprecateNamespaceResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -482,7 +670,7 @@ proxier! {
         StartWorkflowExecutionResponse,
         |r, workers| {
             let mut slot: Option<Box<dyn Slot + Send>> = None;
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
             let req_mut = r.get_mut();
@@ -497,6 +685,8 @@ proxier! {
             slot
         },
         |resp, slot| {
+            // No task comes back if the start attached to an already running workflow, in which
+            // case dropping the slot frees it up again
             if let Some(mut s) = slot {
                 if let Ok(response) = resp.as_ref() {
                     if let Some(task) = response.get_ref().clone().eager_workflow_task {
@@ -516,7 +706,7 @@ proxier! {
         GetWorkflowExecutionHistoryRequest,
         GetWorkflowExecutionHistoryResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -525,7 +715,7 @@ proxier! {
         GetWorkflowExecutionHistoryReverseRequest,
         GetWorkflowExecutionHistoryReverseResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -534,10 +724,9 @@ proxier! {
         PollWorkflowTaskQueueRequest,
         PollWorkflowTaskQueueResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
-            r.set_timeout(LONG_POLL_TIMEOUT);
         }
     );
     (
@@ -545,7 +734,7 @@ proxier! {
         RespondWorkflowTaskCompletedRequest,
         RespondWorkflowTaskCompletedResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -554,7 +743,7 @@ proxier! {
         RespondWorkflowTaskFailedRequest,
         RespondWorkflowTaskFailedResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -563,10 +752,9 @@ proxier! {
         PollActivityTaskQueueRequest,
         PollActivityTaskQueueResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
-            r.set_timeout(LONG_POLL_TIMEOUT);
         }
     );
     (
@@ -574,7 +762,7 @@ proxier! {
         RecordActivityTaskHeartbeatRequest,
         RecordActivityTaskHeartbeatResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -583,7 +771,7 @@ proxier! {
         RecordActivityTaskHeartbeatByIdRequest,
         RecordActivityTaskHeartbeatByIdResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -592,7 +780,7 @@ proxier! {
         RespondActivityTaskCompletedRequest,
         RespondActivityTaskCompletedResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -601,7 +789,7 @@ proxier! {
         RespondActivityTaskCompletedByIdRequest,
         RespondActivityTaskCompletedByIdResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -611,7 +799,7 @@ proxier! {
         RespondActivityTaskFailedRequest,
         RespondActivityTaskFailedResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -620,7 +808,7 @@ proxier! {
         RespondActivityTaskFailedByIdRequest,
         RespondActivityTaskFailedByIdResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -629,7 +817,7 @@ proxier! {
         RespondActivityTaskCanceledRequest,
         RespondActivityTaskCanceledResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -638,7 +826,7 @@ proxier! {
         RespondActivityTaskCanceledByIdRequest,
         RespondActivityTaskCanceledByIdResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -647,7 +835,7 @@ proxier! {
         RequestCancelWorkflowExecutionRequest,
         RequestCancelWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -656,7 +844,7 @@ proxier! {
         SignalWorkflowExecutionRequest,
         SignalWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -665,7 +853,7 @@ proxier! {
         SignalWithStartWorkflowExecutionRequest,
         SignalWithStartWorkflowExecutionResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
         }
@@ -675,7 +863,7 @@ proxier! {
         ResetWorkflowExecutionRequest,
         ResetWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -684,7 +872,7 @@ proxier! {
         TerminateWorkflowExecutionRequest,
         TerminateWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -693,7 +881,7 @@ proxier! {
         DeleteWorkflowExecutionRequest,
         DeleteWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -702,7 +890,7 @@ proxier! {
         ListOpenWorkflowExecutionsRequest,
         ListOpenWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -711,7 +899,7 @@ proxier! {
         ListClosedWorkflowExecutionsRequest,
         ListClosedWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -720,7 +908,7 @@ proxier! {
         ListWorkflowExecutionsRequest,
         ListWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -729,7 +917,7 @@ proxier! {
         ListArchivedWorkflowExecutionsRequest,
         ListArchivedWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -738,7 +926,7 @@ proxier! {
         ScanWorkflowExecutionsRequest,
         ScanWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -747,7 +935,7 @@ proxier! {
         CountWorkflowExecutionsRequest,
         CountWorkflowExecutionsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -761,7 +949,7 @@ proxier! {
         RespondQueryTaskCompletedRequest,
         RespondQueryTaskCompletedResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -770,7 +958,7 @@ proxier! {
         ResetStickyTaskQueueRequest,
         ResetStickyTaskQueueResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -779,7 +967,7 @@ proxier! {
         QueryWorkflowRequest,
         QueryWorkflowResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -788,7 +976,7 @@ proxier! {
         DescribeWorkflowExecutionRequest,
         DescribeWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -797,7 +985,7 @@ proxier! {
         DescribeTaskQueueRequest,
         DescribeTaskQueueResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
         }
@@ -817,7 +1005,7 @@ proxier! {
         ListTaskQueuePartitionsRequest,
         ListTaskQueuePartitionsResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
         }
@@ -827,7 +1015,7 @@ proxier! {
         CreateScheduleRequest,
         CreateScheduleResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -836,7 +1024,7 @@ proxier! {
         DescribeScheduleRequest,
         DescribeScheduleResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -845,7 +1033,7 @@ proxier! {
         UpdateScheduleRequest,
         UpdateScheduleResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -854,7 +1042,7 @@ proxier! {
         PatchScheduleRequest,
         PatchScheduleResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -863,7 +1051,7 @@ proxier! {
         ListScheduleMatchingTimesRequest,
         ListScheduleMatchingTimesResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -872,7 +1060,7 @@ proxier! {
         DeleteScheduleRequest,
         DeleteScheduleResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -881,7 +1069,7 @@ proxier! {
         ListSchedulesRequest,
         ListSchedulesResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -890,7 +1078,7 @@ proxier! {
         UpdateWorkerBuildIdCompatibilityRequest,
         UpdateWorkerBuildIdCompatibilityResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q_str(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
         }
@@ -900,7 +1088,7 @@ proxier! {
         GetWorkerBuildIdCompatibilityRequest,
         GetWorkerBuildIdCompatibilityResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             labels.task_q_str(r.get_ref().task_queue.clone());
             r.extensions_mut().insert(labels);
         }
@@ -910,7 +1098,7 @@ proxier! {
         GetWorkerTaskReachabilityRequest,
         GetWorkerTaskReachabilityResponse,
         |r| {
-            let mut labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let mut labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -919,7 +1107,16 @@ proxier! {
         UpdateWorkflowExecutionRequest,
         UpdateWorkflowExecutionResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
+            r.extensions_mut().insert(labels);
+        }
+    );
+    (
+        execute_multi_operation,
+        ExecuteMultiOperationRequest,
+        ExecuteMultiOperationResponse,
+        |r| {
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -928,7 +1125,7 @@ proxier! {
         PollWorkflowExecutionUpdateRequest,
         PollWorkflowExecutionUpdateResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -937,7 +1134,7 @@ proxier! {
         StartBatchOperationRequest,
         StartBatchOperationResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -946,7 +1143,7 @@ proxier! {
         StopBatchOperationRequest,
         StopBatchOperationResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -955,7 +1152,7 @@ proxier! {
         DescribeBatchOperationRequest,
         DescribeBatchOperationResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -964,7 +1161,25 @@ proxier! {
         ListBatchOperationsRequest,
         ListBatchOperationsResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
+            r.extensions_mut().insert(labels);
+        }
+    );
+    (
+        pause_activity,
+        PauseActivityRequest,
+        PauseActivityResponse,
+        |r| {
+            let labels = namespaced_request!(r);
+            r.extensions_mut().insert(labels);
+        }
+    );
+    (
+        unpause_activity,
+        UnpauseActivityRequest,
+        UnpauseActivityResponse,
+        |r| {
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
@@ -977,13 +1192,78 @@ proxier! {
     (list_search_attributes, ListSearchAttributesRequest, ListSearchAttributesResponse);
     (delete_namespace, DeleteNamespaceRequest, DeleteNamespaceResponse,
         |r| {
-            let labels = AttachMetricLabels::namespace(r.get_ref().namespace.clone());
+            let labels = namespaced_request!(r);
             r.extensions_mut().insert(labels);
         }
     );
     (add_or_update_remote_cluster, AddOrUpdateRemoteClusterRequest, AddOrUpdateRemoteClusterResponse);
     (remove_remote_cluster, RemoveRemoteClusterRequest, RemoveRemoteClusterResponse);
     (list_clusters, ListClustersRequest, ListClustersResponse);
+    (get_nexus_endpoint, GetNexusEndpointRequest, GetNexusEndpointResponse);
+    (create_nexus_endpoint, CreateNexusEndpointRequest, CreateNexusEndpointResponse);
+    (update_nexus_endpoint, UpdateNexusEndpointRequest, UpdateNexusEndpointResponse);
+    (delete_nexus_endpoint, DeleteNexusEndpointRequest, DeleteNexusEndpointResponse);
+    (list_nexus_endpoints, ListNexusEndpointsRequest, ListNexusEndpointsResponse);
+}
+
+proxier! {
+    CloudService; ALL_IMPLEMENTED_CLOUD_SERVICE_RPCS; CloudServiceClient; cloud_client_mut;
+    (get_users, cloudreq::GetUsersRequest, cloudreq::GetUsersResponse);
+    (get_user, cloudreq::GetUserRequest, cloudreq::GetUserResponse);
+    (create_user, cloudreq::CreateUserRequest, cloudreq::CreateUserResponse);
+    (update_user, cloudreq::UpdateUserRequest, cloudreq::UpdateUserResponse);
+    (delete_user, cloudreq::DeleteUserRequest, cloudreq::DeleteUserResponse);
+    (set_user_namespace_access, cloudreq::SetUserNamespaceAccessRequest, cloudreq::SetUserNamespaceAccessResponse);
+    (get_async_operation, cloudreq::GetAsyncOperationRequest, cloudreq::GetAsyncOperationResponse);
+    (create_namespace, cloudreq::CreateNamespaceRequest, cloudreq::CreateNamespaceResponse);
+    (get_namespaces, cloudreq::GetNamespacesRequest, cloudreq::GetNamespacesResponse);
+    (get_namespace, cloudreq::GetNamespaceRequest, cloudreq::GetNamespaceResponse);
+    (update_namespace, cloudreq::UpdateNamespaceRequest, cloudreq::UpdateNamespaceResponse);
+    (rename_custom_search_attribute, cloudreq::RenameCustomSearchAttributeRequest, cloudreq::RenameCustomSearchAttributeResponse);
+    (delete_namespace, cloudreq::DeleteNamespaceRequest, cloudreq::DeleteNamespaceResponse);
+    (failover_namespace_region, cloudreq::FailoverNamespaceRegionRequest, cloudreq::FailoverNamespaceRegionResponse);
+    (add_namespace_region, cloudreq::AddNamespaceRegionRequest, cloudreq::AddNamespaceRegionResponse);
+    (delete_namespace_region, cloudreq::DeleteNamespaceRegionRequest, cloudreq::DeleteNamespaceRegionResponse);
+    (get_regions, cloudreq::GetRegionsRequest, cloudreq::GetRegionsResponse);
+    (get_region, cloudreq::GetRegionRequest, cloudreq::GetRegionResponse);
+    (get_api_keys, cloudreq::GetApiKeysRequest, cloudreq::GetApiKeysResponse);
+    (get_api_key, cloudreq::GetApiKeyRequest, cloudreq::GetApiKeyResponse);
+    (create_api_key, cloudreq::CreateApiKeyRequest, cloudreq::CreateApiKeyResponse);
+    (update_api_key, cloudreq::UpdateApiKeyRequest, cloudreq::UpdateApiKeyResponse);
+    (delete_api_key, cloudreq::DeleteApiKeyRequest, cloudreq::DeleteApiKeyResponse);
+    (get_nexus_endpoints, cloudreq::GetNexusEndpointsRequest, cloudreq::GetNexusEndpointsResponse);
+    (get_nexus_endpoint, cloudreq::GetNexusEndpointRequest, cloudreq::GetNexusEndpointResponse);
+    (create_nexus_endpoint, cloudreq::CreateNexusEndpointRequest, cloudreq::CreateNexusEndpointResponse);
+    (update_nexus_endpoint, cloudreq::UpdateNexusEndpointRequest, cloudreq::UpdateNexusEndpointResponse);
+    (delete_nexus_endpoint, cloudreq::DeleteNexusEndpointRequest, cloudreq::DeleteNexusEndpointResponse);
+    (get_user_groups, cloudreq::GetUserGroupsRequest, cloudreq::GetUserGroupsResponse);
+    (get_user_group, cloudreq::GetUserGroupRequest, cloudreq::GetUserGroupResponse);
+    (create_user_group, cloudreq::CreateUserGroupRequest, cloudreq::CreateUserGroupResponse);
+    (update_user_group, cloudreq::UpdateUserGroupRequest, cloudreq::UpdateUserGroupResponse);
+    (delete_user_group, cloudreq::DeleteUserGroupRequest, cloudreq::DeleteUserGroupResponse);
+    (set_user_group_namespace_access, cloudreq::SetUserGroupNamespaceAccessRequest, cloudreq::SetUserGroupNamespaceAccessResponse);
+    (add_user_group_member, cloudreq::AddUserGroupMemberRequest, cloudreq::AddUserGroupMemberResponse);
+    (remove_user_group_member, cloudreq::RemoveUserGroupMemberRequest, cloudreq::RemoveUserGroupMemberResponse);
+    (get_user_group_members, cloudreq::GetUserGroupMembersRequest, cloudreq::GetUserGroupMembersResponse);
+    (create_service_account, cloudreq::CreateServiceAccountRequest, cloudreq::CreateServiceAccountResponse);
+    (get_service_account, cloudreq::GetServiceAccountRequest, cloudreq::GetServiceAccountResponse);
+    (get_service_accounts, cloudreq::GetServiceAccountsRequest, cloudreq::GetServiceAccountsResponse);
+    (update_service_account, cloudreq::UpdateServiceAccountRequest, cloudreq::UpdateServiceAccountResponse);
+    (delete_service_account, cloudreq::DeleteServiceAccountRequest, cloudreq::DeleteServiceAccountResponse);
+    (get_usage, cloudreq::GetUsageRequest, cloudreq::GetUsageResponse);
+    (get_account, cloudreq::GetAccountRequest, cloudreq::GetAccountResponse);
+    (update_account, cloudreq::UpdateAccountRequest, cloudreq::UpdateAccountResponse);
+    (create_namespace_export_sink, cloudreq::CreateNamespaceExportSinkRequest, cloudreq::CreateNamespaceExportSinkResponse);
+    (get_namespace_export_sink, cloudreq::GetNamespaceExportSinkRequest, cloudreq::GetNamespaceExportSinkResponse);
+    (get_namespace_export_sinks, cloudreq::GetNamespaceExportSinksRequest, cloudreq::GetNamespaceExportSinksResponse);
+    (update_namespace_export_sink, cloudreq::UpdateNamespaceExportSinkRequest, cloudreq::UpdateNamespaceExportSinkResponse);
+    (delete_namespace_export_sink, cloudreq::DeleteNamespaceExportSinkRequest, cloudreq::DeleteNamespaceExportSinkResponse);
+    (validate_namespace_export_sink, cloudreq::ValidateNamespaceExportSinkRequest, cloudreq::ValidateNamespaceExportSinkResponse);
+    (update_namespace_tags, cloudreq::UpdateNamespaceTagsRequest, cloudreq::UpdateNamespaceTagsResponse);
+    (create_connectivity_rule, cloudreq::CreateConnectivityRuleRequest, cloudreq::CreateConnectivityRuleResponse);
+    (get_connectivity_rule, cloudreq::GetConnectivityRuleRequest, cloudreq::GetConnectivityRuleResponse);
+    (get_connectivity_rules, cloudreq::GetConnectivityRulesRequest, cloudreq::GetConnectivityRulesResponse);
+    (delete_connectivity_rule, cloudreq::DeleteConnectivityRuleRequest, cloudreq::DeleteConnectivityRuleResponse);
 }
 
 proxier! {
@@ -1011,6 +1291,20 @@ mod tests {
         operatorservice::v1::DeleteNamespaceRequest, workflowservice::v1::ListNamespacesRequest,
     };
 
+    #[test]
+    fn namespaced_request_attaches_header() {
+        let mut req = Request::new(DeleteNamespaceRequest {
+            namespace: "my-ns.acct".to_string(),
+            ..Default::default()
+        });
+        let r = &mut req;
+        namespaced_request!(r);
+        assert_eq!(
+            req.metadata().get(TEMPORAL_NAMESPACE_HEADER_KEY).unwrap(),
+            "my-ns.acct"
+        );
+    }
+
     // Just to help make sure some stuff compiles. Not run.
     #[allow(dead_code)]
     async fn raw_client_retry_compiles() {
@@ -1081,6 +1375,14 @@ mod tests {
         verify_methods(proto_def, ALL_IMPLEMENTED_OPERATOR_SERVICE_RPCS);
     }
 
+    #[test]
+    fn verify_all_cloud_service_methods_implemented() {
+        let proto_def = include_str!(
+            "../../sdk-core-protos/protos/api_cloud_upstream/temporal/api/cloud/cloudservice/v1/service.proto"
+        );
+        verify_methods(proto_def, ALL_IMPLEMENTED_CLOUD_SERVICE_RPCS);
+    }
+
     #[test]
     fn verify_all_test_service_methods_implemented() {
         let proto_def =
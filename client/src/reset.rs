@@ -0,0 +1,207 @@
+//! Resetting workflows to an earlier workflow task
+
+use crate::{history_fetch::fetch_history, Result, WorkflowClientTrait};
+use futures::Future;
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::WorkflowExecution,
+    enums::v1::{EventType, HistoryEventFilterType, ResetReapplyExcludeType, ResetReapplyType},
+    history::v1::History,
+    workflowservice::v1::{ResetWorkflowExecutionRequest, ResetWorkflowExecutionResponse},
+};
+use tonic::Status;
+use uuid::Uuid;
+
+/// Options for resetting a workflow. See [crate::WorkflowClientTrait::reset_workflow_execution].
+#[derive(Clone, Debug, derive_builder::Builder)]
+pub struct ResetWorkflowOptions {
+    /// Why the workflow is being reset, recorded in the reset run's history
+    #[builder(setter(into))]
+    pub reason: String,
+    /// Which events from after the reset point are reapplied to the new run
+    #[builder(default)]
+    pub reapply: ResetReapply,
+    /// De-duplicates reset requests. Defaults to a random UUID.
+    #[builder(setter(into), default = "Uuid::new_v4().to_string()")]
+    pub request_id: String,
+}
+
+/// Events which happened after the reset point, but weren't caused by the workflow itself, may be
+/// reapplied to the new run so they aren't lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResetReapply {
+    /// Reapply every kind of event the server can reapply
+    #[default]
+    All,
+    /// Only reapply signals
+    SignalsOnly,
+    /// Only reapply updates
+    UpdatesOnly,
+    /// Don't reapply anything
+    NoEvents,
+}
+
+/// Which workflow task a workflow is reset to. The new run starts from just after that task,
+/// which is then run again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetTarget {
+    /// The first workflow task the workflow completed
+    FirstWorkflowTask,
+    /// The most recent workflow task the workflow completed
+    LastWorkflowTask,
+    /// The last workflow task the workflow completed before the event with this id, so events
+    /// from that one onwards are undone
+    LastWorkflowTaskBefore(i64),
+    /// The workflow task that finished with this event, which must be a workflow task completed,
+    /// failed, timed out, or started event
+    WorkflowTaskFinishEvent(i64),
+}
+
+impl ResetWorkflowOptions {
+    pub(crate) fn into_request(
+        self,
+        namespace: String,
+        execution: WorkflowExecution,
+        workflow_task_finish_event_id: i64,
+    ) -> ResetWorkflowExecutionRequest {
+        let (reset_reapply_type, reset_reapply_exclude_types) = match self.reapply {
+            ResetReapply::All => (ResetReapplyType::AllEligible, vec![]),
+            ResetReapply::SignalsOnly => (
+                ResetReapplyType::AllEligible,
+                vec![ResetReapplyExcludeType::Update as i32],
+            ),
+            ResetReapply::UpdatesOnly => (
+                ResetReapplyType::AllEligible,
+                vec![ResetReapplyExcludeType::Signal as i32],
+            ),
+            ResetReapply::NoEvents => (ResetReapplyType::None, vec![]),
+        };
+        ResetWorkflowExecutionRequest {
+            namespace,
+            workflow_execution: Some(execution),
+            reason: self.reason,
+            workflow_task_finish_event_id,
+            request_id: self.request_id,
+            reset_reapply_type: reset_reapply_type as i32,
+            reset_reapply_exclude_types,
+        }
+    }
+}
+
+/// Resets workflows to a workflow task picked by a [ResetTarget], finding its event id in the
+/// workflow's history.
+pub trait ResetWorkflowExt: WorkflowClientTrait + Clone + Send + Sync + 'static {
+    /// Reset a workflow run. If `run_id` is unset, the latest run is reset. Fails with
+    /// `FailedPrecondition` if history has no workflow task matching `target`.
+    fn reset_workflow(
+        &self,
+        workflow_id: impl Into<String>,
+        run_id: Option<String>,
+        target: ResetTarget,
+        options: ResetWorkflowOptions,
+    ) -> impl Future<Output = Result<ResetWorkflowExecutionResponse>> + Send {
+        let client = self.clone();
+        let workflow_id = workflow_id.into();
+        async move {
+            let (run_id, event_id) = match target {
+                ResetTarget::WorkflowTaskFinishEvent(event_id) => {
+                    (run_id.unwrap_or_default(), event_id)
+                }
+                target => {
+                    // Pin the run, so the history searched is that of the run which is reset
+                    let run_id = match run_id {
+                        Some(run_id) => run_id,
+                        None => client
+                            .describe_workflow_execution(workflow_id.clone(), None)
+                            .await?
+                            .workflow_execution_info
+                            .and_then(|i| i.execution)
+                            .map(|e| e.run_id)
+                            .unwrap_or_default(),
+                    };
+                    let history = fetch_history(
+                        &client,
+                        WorkflowExecution {
+                            workflow_id: workflow_id.clone(),
+                            run_id: run_id.clone(),
+                        },
+                        HistoryEventFilterType::AllEvent,
+                    )
+                    .await?;
+                    let event_id = find_reset_point(&history, target).ok_or_else(|| {
+                        Status::failed_precondition(format!(
+                            "Workflow history has no completed workflow task to reset to for \
+                             {target:?}"
+                        ))
+                    })?;
+                    (run_id, event_id)
+                }
+            };
+            client
+                .reset_workflow_execution(
+                    WorkflowExecution {
+                        workflow_id,
+                        run_id,
+                    },
+                    event_id,
+                    options,
+                )
+                .await
+        }
+    }
+}
+
+impl<T> ResetWorkflowExt for T where T: WorkflowClientTrait + Clone + Send + Sync + 'static {}
+
+/// Find the id of the workflow task completed event `target` picks out of `history`
+fn find_reset_point(history: &History, target: ResetTarget) -> Option<i64> {
+    let mut completions = history
+        .events
+        .iter()
+        .filter(|e| e.event_type() == EventType::WorkflowTaskCompleted)
+        .map(|e| e.event_id);
+    match target {
+        ResetTarget::FirstWorkflowTask => completions.next(),
+        ResetTarget::LastWorkflowTask => completions.next_back(),
+        ResetTarget::LastWorkflowTaskBefore(event_id) => {
+            completions.take_while(|&id| id < event_id).last()
+        }
+        ResetTarget::WorkflowTaskFinishEvent(event_id) => Some(event_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::temporal::api::history::v1::HistoryEvent;
+
+    #[test]
+    fn finds_completed_tasks_to_reset_to() {
+        let history = History {
+            events: [
+                EventType::WorkflowExecutionStarted,
+                EventType::WorkflowTaskScheduled,
+                EventType::WorkflowTaskStarted,
+                EventType::WorkflowTaskCompleted,
+                EventType::WorkflowExecutionSignaled,
+                EventType::WorkflowTaskScheduled,
+                EventType::WorkflowTaskStarted,
+                EventType::WorkflowTaskCompleted,
+                EventType::WorkflowExecutionSignaled,
+            ]
+            .into_iter()
+            .zip(1..)
+            .map(|(et, event_id)| HistoryEvent {
+                event_id,
+                event_type: et as i32,
+                ..Default::default()
+            })
+            .collect(),
+        };
+        let point = |target| find_reset_point(&history, target);
+        assert_eq!(point(ResetTarget::FirstWorkflowTask), Some(4));
+        assert_eq!(point(ResetTarget::LastWorkflowTask), Some(8));
+        assert_eq!(point(ResetTarget::LastWorkflowTaskBefore(9)), Some(8));
+        assert_eq!(point(ResetTarget::LastWorkflowTaskBefore(8)), Some(4));
+        assert_eq!(point(ResetTarget::LastWorkflowTaskBefore(4)), None);
+    }
+}
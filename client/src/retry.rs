@@ -1,15 +1,31 @@
 use crate::{
-    ClientOptions, ListClosedFilters, ListOpenFilters, Namespace, RegisterNamespaceOptions, Result,
-    RetryConfig, SignalWithStartOptions, StartTimeFilter, WorkflowClientTrait, WorkflowOptions,
+    ActivityIdentifier, AsyncActivityHeartbeat, BatchOperationOptions, BatchOperationProgress,
+    BuildIdOperation, BuildIdReachability, ClientOptions, CompatibleVersionSets,
+    CreateScheduleOptions, ListClosedFilters, ListOpenFilters, Namespace, RegisterNamespaceOptions,
+    ResetWorkflowOptions, Result, RetryConfig, Schedule, SchedulePatch, SignalWithStartOptions,
+    StartTimeFilter, UnpauseActivityOptions, UpdateNamespaceOptions, UpdateWaitStage,
+    UpdateWithStartWorkflow, WorkflowClientTrait, WorkflowExecutionCount, WorkflowOptions,
+    WorkflowUpdate,
 };
 use backoff::{backoff::Backoff, exponential::ExponentialBackoff, Clock, SystemClock};
 use futures_retry::{ErrorHandler, FutureRetry, RetryPolicy};
-use std::{fmt::Debug, future::Future, sync::Arc, time::Duration};
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow, collections::HashMap, fmt::Debug, future::Future, sync::Arc, time::Duration,
+};
 use temporal_sdk_core_protos::{
     coresdk::workflow_commands::QueryResult,
     temporal::api::{
-        common::v1::{Payload, Payloads},
+        common::v1::{Payload, Payloads, WorkflowExecution},
+        enums::v1::{HistoryEventFilterType, IndexedValueType, TaskReachability},
         failure::v1::Failure,
+        nexus::v1::EndpointSpec,
+        operatorservice::v1::{
+            AddSearchAttributesResponse, CreateNexusEndpointResponse, DeleteNamespaceResponse,
+            DeleteNexusEndpointResponse, GetNexusEndpointResponse, ListNexusEndpointsResponse,
+            ListSearchAttributesResponse, RemoveSearchAttributesResponse,
+            UpdateNexusEndpointResponse,
+        },
         query::v1::WorkflowQuery,
         update,
         workflowservice::v1::*,
@@ -17,6 +33,7 @@ use temporal_sdk_core_protos::{
     TaskToken,
 };
 use tonic::Code;
+use uuid::Uuid;
 
 /// List of gRPC error codes that client will retry.
 pub const RETRYABLE_ERROR_CODES: [Code; 7] = [
@@ -28,12 +45,55 @@ pub const RETRYABLE_ERROR_CODES: [Code; 7] = [
     Code::OutOfRange,
     Code::Unavailable,
 ];
-const LONG_POLL_FATAL_GRACE: Duration = Duration::from_secs(60);
 /// Must match the method name in [crate::raw::WorkflowService]
 const POLL_WORKFLOW_METH_NAME: &str = "poll_workflow_task_queue";
 /// Must match the method name in [crate::raw::WorkflowService]
 const POLL_ACTIVITY_METH_NAME: &str = "poll_activity_task_queue";
 
+/// Limits how much calls sharing it may retry while the server is failing many of them, so that a
+/// struggling server isn't met with a multiple of its usual load from every client retrying at
+/// once.
+///
+/// Works like gRPC's retry throttling: the budget starts with `max_tokens` tokens, each failed
+/// attempt with a retryable code takes one away, and each successful call puts back
+/// `token_ratio`. Failed calls are only retried while more than half of `max_tokens` remain.
+///
+/// Clones share the same budget, so a single budget set in [crate::ClientOptions::retry_config]
+/// covers every client connected with those options.
+#[derive(Clone, Debug)]
+pub struct RetryBudget {
+    max_tokens: f64,
+    token_ratio: f64,
+    tokens: Arc<Mutex<f64>>,
+}
+
+impl RetryBudget {
+    /// Create a new, full budget. `max_tokens` and `token_ratio` must both be positive.
+    pub fn new(max_tokens: f64, token_ratio: f64) -> Self {
+        assert!(
+            max_tokens > 0.0 && token_ratio > 0.0,
+            "Retry budget max tokens and token ratio must be positive"
+        );
+        Self {
+            max_tokens,
+            token_ratio,
+            tokens: Arc::new(Mutex::new(max_tokens)),
+        }
+    }
+
+    /// Records a failed attempt, returning true if it may be retried
+    fn record_failure(&self) -> bool {
+        let mut tokens = self.tokens.lock();
+        *tokens = (*tokens - 1.0).max(0.0);
+        *tokens > self.max_tokens / 2.0
+    }
+
+    fn record_success(&self) {
+        let mut tokens = self.tokens.lock();
+        *tokens = (*tokens + self.token_ratio).min(self.max_tokens);
+    }
+}
+
 /// A wrapper for a [WorkflowClientTrait] or [crate::WorkflowService] implementor which performs
 /// auto-retries
 #[derive(Debug, Clone)]
@@ -81,14 +141,17 @@ impl<SG> RetryClient<SG> {
         Fut: Future<Output = Result<R>>,
     {
         let rtc = self.get_retry_config(call_name);
+        let budget = rtc.retry_budget.clone();
         let res = Self::make_future_retry(rtc, factory, call_name).await;
-        Ok(res.map_err(|(e, _attempt)| e)?.0)
+        let res = res.map_err(|(e, _attempt)| e)?.0;
+        record_call_success(budget.as_ref());
+        Ok(res)
     }
 
     pub(crate) fn get_retry_config(&self, call_name: &'static str) -> RetryConfig {
         match CallType::from_call_name(call_name) {
             CallType::Normal => (*self.retry_config).clone(),
-            CallType::LongPoll => RetryConfig::poll_retry_policy(),
+            CallType::LongPoll => self.retry_config.poll_reconnect.poll_retry_config(),
         }
     }
 
@@ -108,11 +171,21 @@ impl<SG> RetryClient<SG> {
     }
 }
 
+/// Credits a successful call to the retry budget it was made under, if any
+pub(crate) fn record_call_success(budget: Option<&RetryBudget>) {
+    if let Some(b) = budget {
+        b.record_success();
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct TonicErrorHandler<C: Clock> {
     backoff: ExponentialBackoff<C>,
     throttle_backoff: ExponentialBackoff<C>,
     max_retries: usize,
+    retryable_codes: Cow<'static, [Code]>,
+    retry_budget: Option<RetryBudget>,
+    long_poll_fatal_grace: Duration,
     call_type: CallType,
     call_name: &'static str,
 }
@@ -140,6 +213,9 @@ where
     ) -> Self {
         Self {
             max_retries: cfg.max_retries,
+            retryable_codes: cfg.retryable_codes.clone(),
+            retry_budget: cfg.retry_budget.clone(),
+            long_poll_fatal_grace: cfg.poll_reconnect.fatal_error_grace,
             call_type: CallType::from_call_name(call_name),
             call_name,
             backoff: cfg.into_exp_backoff(clock),
@@ -165,7 +241,7 @@ pub enum CallType {
     LongPoll,
 }
 impl CallType {
-    fn from_call_name(call_name: &str) -> Self {
+    pub(crate) fn from_call_name(call_name: &str) -> Self {
         match call_name {
             POLL_WORKFLOW_METH_NAME | POLL_ACTIVITY_METH_NAME => CallType::LongPoll,
             _ => CallType::Normal,
@@ -191,7 +267,14 @@ where
         let long_poll_allowed =
             is_long_poll && [Code::Cancelled, Code::DeadlineExceeded].contains(&e.code());
 
-        if RETRYABLE_ERROR_CODES.contains(&e.code()) || long_poll_allowed {
+        if self.retryable_codes.contains(&e.code()) || long_poll_allowed {
+            if let Some(budget) = self.retry_budget.as_ref() {
+                if !budget.record_failure() {
+                    debug!(error=?e, "gRPC call {} not retried, retry budget exhausted",
+                           self.call_name);
+                    return RetryPolicy::ForwardError(e);
+                }
+            }
             if current_attempt == 1 {
                 debug!(error=?e, "gRPC call {} failed on first attempt", self.call_name);
             } else if self.should_log_retry_warning(current_attempt) {
@@ -212,7 +295,7 @@ where
                     }
                 }
             }
-        } else if is_long_poll && self.backoff.get_elapsed_time() <= LONG_POLL_FATAL_GRACE {
+        } else if is_long_poll && self.backoff.get_elapsed_time() <= self.long_poll_fatal_grace {
             // We permit "fatal" errors while long polling for a while, because some proxies return
             // stupid error codes while getting ready, among other weird infra issues
             RetryPolicy::WaitRetry(self.backoff.max_interval)
@@ -326,6 +409,92 @@ where
         )
     }
 
+    async fn pause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: pause_activity_request::Activity,
+    ) -> Result<PauseActivityResponse> {
+        retry_call!(
+            self,
+            pause_activity,
+            workflow_id.clone(),
+            run_id.clone(),
+            activity.clone()
+        )
+    }
+
+    async fn unpause_activity(
+        &self,
+        workflow_id: String,
+        run_id: Option<String>,
+        activity: unpause_activity_request::Activity,
+        options: UnpauseActivityOptions,
+    ) -> Result<UnpauseActivityResponse> {
+        retry_call!(
+            self,
+            unpause_activity,
+            workflow_id.clone(),
+            run_id.clone(),
+            activity.clone(),
+            options.clone()
+        )
+    }
+
+    async fn complete_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        result: Option<Payloads>,
+    ) -> Result<()> {
+        retry_call!(
+            self,
+            complete_async_activity,
+            activity.clone(),
+            result.clone()
+        )
+    }
+
+    async fn fail_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        failure: Failure,
+        last_heartbeat_details: Option<Payloads>,
+    ) -> Result<()> {
+        retry_call!(
+            self,
+            fail_async_activity,
+            activity.clone(),
+            failure.clone(),
+            last_heartbeat_details.clone()
+        )
+    }
+
+    async fn cancel_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<()> {
+        retry_call!(
+            self,
+            cancel_async_activity,
+            activity.clone(),
+            details.clone()
+        )
+    }
+
+    async fn heartbeat_async_activity(
+        &self,
+        activity: ActivityIdentifier,
+        details: Option<Payloads>,
+    ) -> Result<AsyncActivityHeartbeat> {
+        retry_call!(
+            self,
+            heartbeat_async_activity,
+            activity.clone(),
+            details.clone()
+        )
+    }
+
     async fn signal_workflow_execution(
         &self,
         workflow_id: String,
@@ -401,6 +570,21 @@ where
         )
     }
 
+    async fn get_filtered_workflow_execution_history(
+        &self,
+        execution: WorkflowExecution,
+        page_token: Vec<u8>,
+        filter_type: HistoryEventFilterType,
+    ) -> Result<GetWorkflowExecutionHistoryResponse> {
+        retry_call!(
+            self,
+            get_filtered_workflow_execution_history,
+            execution.clone(),
+            page_token.clone(),
+            filter_type
+        )
+    }
+
     async fn respond_legacy_query(
         &self,
         task_token: TaskToken,
@@ -444,6 +628,21 @@ where
         )
     }
 
+    async fn reset_workflow_execution(
+        &self,
+        execution: WorkflowExecution,
+        workflow_task_finish_event_id: i64,
+        options: ResetWorkflowOptions,
+    ) -> Result<ResetWorkflowExecutionResponse> {
+        retry_call!(
+            self,
+            reset_workflow_execution,
+            execution.clone(),
+            workflow_task_finish_event_id,
+            options.clone()
+        )
+    }
+
     async fn register_namespace(
         &self,
         options: RegisterNamespaceOptions,
@@ -451,6 +650,13 @@ where
         retry_call!(self, register_namespace, options.clone())
     }
 
+    async fn update_namespace(
+        &self,
+        options: UpdateNamespaceOptions,
+    ) -> Result<UpdateNamespaceResponse> {
+        retry_call!(self, update_namespace, options.clone())
+    }
+
     async fn list_namespaces(&self) -> Result<ListNamespacesResponse> {
         retry_call!(self, list_namespaces,)
     }
@@ -459,6 +665,77 @@ where
         retry_call!(self, describe_namespace, namespace.clone())
     }
 
+    async fn delete_namespace(&self, namespace: Namespace) -> Result<DeleteNamespaceResponse> {
+        retry_call!(self, delete_namespace, namespace.clone())
+    }
+
+    async fn add_search_attributes(
+        &self,
+        search_attributes: HashMap<String, IndexedValueType>,
+    ) -> Result<AddSearchAttributesResponse> {
+        retry_call!(self, add_search_attributes, search_attributes.clone())
+    }
+
+    async fn remove_search_attributes(
+        &self,
+        search_attributes: Vec<String>,
+    ) -> Result<RemoveSearchAttributesResponse> {
+        retry_call!(self, remove_search_attributes, search_attributes.clone())
+    }
+
+    async fn list_search_attributes(&self) -> Result<ListSearchAttributesResponse> {
+        retry_call!(self, list_search_attributes,)
+    }
+
+    async fn create_nexus_endpoint(
+        &self,
+        spec: EndpointSpec,
+    ) -> Result<CreateNexusEndpointResponse> {
+        retry_call!(self, create_nexus_endpoint, spec.clone())
+    }
+
+    async fn get_nexus_endpoint(&self, id: String) -> Result<GetNexusEndpointResponse> {
+        retry_call!(self, get_nexus_endpoint, id.clone())
+    }
+
+    async fn update_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+        spec: EndpointSpec,
+    ) -> Result<UpdateNexusEndpointResponse> {
+        retry_call!(
+            self,
+            update_nexus_endpoint,
+            id.clone(),
+            version,
+            spec.clone()
+        )
+    }
+
+    async fn delete_nexus_endpoint(
+        &self,
+        id: String,
+        version: i64,
+    ) -> Result<DeleteNexusEndpointResponse> {
+        retry_call!(self, delete_nexus_endpoint, id.clone(), version)
+    }
+
+    async fn list_nexus_endpoints(
+        &self,
+        page_size: i32,
+        next_page_token: Vec<u8>,
+        name: Option<String>,
+    ) -> Result<ListNexusEndpointsResponse> {
+        retry_call!(
+            self,
+            list_nexus_endpoints,
+            page_size,
+            next_page_token.clone(),
+            name.clone()
+        )
+    }
+
     async fn list_open_workflow_executions(
         &self,
         maximum_page_size: i32,
@@ -523,10 +800,119 @@ where
         )
     }
 
+    async fn count_workflow_executions(&self, query: String) -> Result<WorkflowExecutionCount> {
+        retry_call!(self, count_workflow_executions, query.clone())
+    }
+
+    async fn list_schedules(
+        &self,
+        maximum_page_size: i32,
+        next_page_token: Vec<u8>,
+    ) -> Result<ListSchedulesResponse> {
+        retry_call!(
+            self,
+            list_schedules,
+            maximum_page_size,
+            next_page_token.clone()
+        )
+    }
+
+    async fn list_task_queue_partitions(
+        &self,
+        task_queue: String,
+    ) -> Result<ListTaskQueuePartitionsResponse> {
+        retry_call!(self, list_task_queue_partitions, task_queue.clone())
+    }
+
     async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse> {
         retry_call!(self, get_search_attributes)
     }
 
+    async fn start_batch_operation(
+        &self,
+        options: BatchOperationOptions,
+    ) -> Result<StartBatchOperationResponse> {
+        retry_call!(self, start_batch_operation, options.clone())
+    }
+
+    async fn describe_batch_operation(&self, job_id: String) -> Result<BatchOperationProgress> {
+        retry_call!(self, describe_batch_operation, job_id.clone())
+    }
+
+    async fn stop_batch_operation(
+        &self,
+        job_id: String,
+        reason: String,
+    ) -> Result<StopBatchOperationResponse> {
+        retry_call!(self, stop_batch_operation, job_id.clone(), reason.clone())
+    }
+
+    async fn create_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        options: CreateScheduleOptions,
+    ) -> Result<CreateScheduleResponse> {
+        // Generated once so that retries are deduplicated by the server
+        let options = CreateScheduleOptions {
+            request_id: Some(
+                options
+                    .request_id
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            ),
+            ..options
+        };
+        retry_call!(
+            self,
+            create_schedule,
+            schedule_id.clone(),
+            schedule.clone(),
+            options.clone()
+        )
+    }
+
+    async fn describe_schedule(&self, schedule_id: String) -> Result<DescribeScheduleResponse> {
+        retry_call!(self, describe_schedule, schedule_id.clone())
+    }
+
+    async fn update_schedule(
+        &self,
+        schedule_id: String,
+        schedule: Schedule,
+        conflict_token: Vec<u8>,
+        request_id: Option<String>,
+    ) -> Result<UpdateScheduleResponse> {
+        let request_id = Some(request_id.unwrap_or_else(|| Uuid::new_v4().to_string()));
+        retry_call!(
+            self,
+            update_schedule,
+            schedule_id.clone(),
+            schedule.clone(),
+            conflict_token.clone(),
+            request_id.clone()
+        )
+    }
+
+    async fn patch_schedule(
+        &self,
+        schedule_id: String,
+        patch: SchedulePatch,
+        request_id: Option<String>,
+    ) -> Result<PatchScheduleResponse> {
+        let request_id = Some(request_id.unwrap_or_else(|| Uuid::new_v4().to_string()));
+        retry_call!(
+            self,
+            patch_schedule,
+            schedule_id.clone(),
+            patch.clone(),
+            request_id.clone()
+        )
+    }
+
+    async fn delete_schedule(&self, schedule_id: String) -> Result<DeleteScheduleResponse> {
+        retry_call!(self, delete_schedule, schedule_id.clone())
+    }
+
     async fn update_workflow_execution(
         &self,
         workflow_id: String,
@@ -546,6 +932,83 @@ where
         )
     }
 
+    async fn start_workflow_update(
+        &self,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<UpdateWorkflowExecutionResponse> {
+        retry_call!(self, start_workflow_update, update.clone(), wait_for)
+    }
+
+    async fn start_update_with_start(
+        &self,
+        start: UpdateWithStartWorkflow,
+        update: WorkflowUpdate,
+        wait_for: UpdateWaitStage,
+    ) -> Result<ExecuteMultiOperationResponse> {
+        retry_call!(
+            self,
+            start_update_with_start,
+            start.clone(),
+            update.clone(),
+            wait_for
+        )
+    }
+
+    async fn poll_workflow_execution_update(
+        &self,
+        update_ref: update::v1::UpdateRef,
+        wait_for: UpdateWaitStage,
+    ) -> Result<PollWorkflowExecutionUpdateResponse> {
+        retry_call!(
+            self,
+            poll_workflow_execution_update,
+            update_ref.clone(),
+            wait_for
+        )
+    }
+
+    async fn update_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        operation: BuildIdOperation,
+    ) -> Result<()> {
+        retry_call!(
+            self,
+            update_worker_build_id_compatibility,
+            task_queue.clone(),
+            operation.clone()
+        )
+    }
+
+    async fn get_worker_build_id_compatibility(
+        &self,
+        task_queue: String,
+        max_sets: i32,
+    ) -> Result<CompatibleVersionSets> {
+        retry_call!(
+            self,
+            get_worker_build_id_compatibility,
+            task_queue.clone(),
+            max_sets
+        )
+    }
+
+    async fn get_worker_task_reachability(
+        &self,
+        build_ids: Vec<String>,
+        task_queues: Vec<String>,
+        reachability: TaskReachability,
+    ) -> Result<BuildIdReachability> {
+        retry_call!(
+            self,
+            get_worker_task_reachability,
+            build_ids.clone(),
+            task_queues.clone(),
+            reachability
+        )
+    }
+
     fn get_options(&self) -> &ClientOptions {
         self.client.get_options()
     }
@@ -558,7 +1021,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::MockWorkflowClientTrait;
+    use crate::{MockWorkflowClientTrait, ReconnectPolicy};
     use assert_matches::assert_matches;
     use backoff::Clock;
     use std::{ops::Add, time::Instant};
@@ -572,6 +1035,9 @@ mod tests {
         max_interval: Duration::from_millis(2),
         max_elapsed_time: None,
         max_retries: 10,
+        retryable_codes: Cow::Borrowed(&RETRYABLE_ERROR_CODES),
+        retry_budget: None,
+        poll_reconnect: ReconnectPolicy::DEFAULT,
     };
 
     #[tokio::test]
@@ -601,6 +1067,33 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn schedule_request_id_is_kept_across_retries() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let seen_c = seen.clone();
+        let mut mock_client = MockWorkflowClientTrait::new();
+        mock_client
+            .expect_patch_schedule()
+            .returning(move |_, _, request_id| {
+                let mut seen = seen_c.lock();
+                seen.push(request_id);
+                if seen.len() < 3 {
+                    Err(Status::unavailable("try again"))
+                } else {
+                    Ok(Default::default())
+                }
+            })
+            .times(3);
+        let retry_client = RetryClient::new(mock_client, TEST_RETRY_CONFIG);
+        retry_client
+            .patch_schedule("sched".to_string(), SchedulePatch::default(), None)
+            .await
+            .unwrap();
+        let seen = seen.lock();
+        assert!(seen[0].is_some());
+        assert!(seen.iter().all(|id| *id == seen[0]));
+    }
+
     struct FixedClock(Instant);
     impl Clock for FixedClock {
         fn now(&self) -> Instant {
@@ -622,6 +1115,9 @@ mod tests {
             for call_name in [POLL_WORKFLOW_METH_NAME, POLL_ACTIVITY_METH_NAME] {
                 let mut err_handler = TonicErrorHandler {
                     max_retries: TEST_RETRY_CONFIG.max_retries,
+                    retryable_codes: TEST_RETRY_CONFIG.retryable_codes,
+                    retry_budget: None,
+                    long_poll_fatal_grace: ReconnectPolicy::DEFAULT.fatal_error_grace,
                     call_type: CallType::LongPoll,
                     call_name,
                     backoff: TEST_RETRY_CONFIG.into_exp_backoff(FixedClock(Instant::now())),
@@ -634,7 +1130,7 @@ mod tests {
                     .backoff
                     .clock
                     .0
-                    .add(LONG_POLL_FATAL_GRACE + Duration::from_secs(1));
+                    .add(ReconnectPolicy::DEFAULT.fatal_error_grace + Duration::from_secs(1));
                 let result = err_handler.handle(2, Status::new(code, "Ahh"));
                 assert_matches!(result, RetryPolicy::ForwardError(_));
             }
@@ -647,6 +1143,9 @@ mod tests {
             for call_name in [POLL_WORKFLOW_METH_NAME, POLL_ACTIVITY_METH_NAME] {
                 let mut err_handler = TonicErrorHandler {
                     max_retries: TEST_RETRY_CONFIG.max_retries,
+                    retryable_codes: TEST_RETRY_CONFIG.retryable_codes,
+                    retry_budget: None,
+                    long_poll_fatal_grace: ReconnectPolicy::DEFAULT.fatal_error_grace,
                     call_type: CallType::LongPoll,
                     call_name,
                     backoff: TEST_RETRY_CONFIG.into_exp_backoff(FixedClock(Instant::now())),
@@ -659,7 +1158,7 @@ mod tests {
                     .backoff
                     .clock
                     .0
-                    .add(LONG_POLL_FATAL_GRACE + Duration::from_secs(1));
+                    .add(ReconnectPolicy::DEFAULT.fatal_error_grace + Duration::from_secs(1));
                 let result = err_handler.handle(2, Status::new(code, "Ahh"));
                 assert_matches!(result, RetryPolicy::WaitRetry(_));
             }
@@ -693,10 +1192,196 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn operator_helpers_are_retried() {
+        let mut mock_client = MockWorkflowClientTrait::new();
+        mock_client
+            .expect_add_search_attributes()
+            .returning(|_| Err(Status::new(Code::Unavailable, "retryable failure")))
+            .times(2);
+        mock_client
+            .expect_add_search_attributes()
+            .withf(|attrs| attrs.get("CustomerId") == Some(&IndexedValueType::Keyword))
+            .returning(|_| Ok(Default::default()))
+            .times(1);
+        let retry_client = RetryClient::new(mock_client, TEST_RETRY_CONFIG);
+        let result = retry_client
+            .add_search_attributes(HashMap::from([(
+                "CustomerId".to_string(),
+                IndexedValueType::Keyword,
+            )]))
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn nexus_endpoint_helpers_are_retried() {
+        let mut mock_client = MockWorkflowClientTrait::new();
+        mock_client
+            .expect_update_nexus_endpoint()
+            .returning(|_, _, _| Err(Status::new(Code::Unavailable, "retryable failure")))
+            .times(2);
+        mock_client
+            .expect_update_nexus_endpoint()
+            .withf(|id, version, spec| id == "endpoint-id" && *version == 3 && spec.name == "ep")
+            .returning(|_, _, _| Ok(Default::default()))
+            .times(1);
+        let retry_client = RetryClient::new(mock_client, TEST_RETRY_CONFIG);
+        let result = retry_client
+            .update_nexus_endpoint(
+                "endpoint-id".to_string(),
+                3,
+                EndpointSpec {
+                    name: "ep".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn activity_pause_helpers_are_retried() {
+        let mut mock_client = MockWorkflowClientTrait::new();
+        mock_client
+            .expect_unpause_activity()
+            .returning(|_, _, _, _| Err(Status::new(Code::Unavailable, "retryable failure")))
+            .times(2);
+        mock_client
+            .expect_unpause_activity()
+            .withf(|wid, _, activity, opts| {
+                wid == "wid"
+                    && *activity == unpause_activity_request::Activity::Id("act".to_string())
+                    && opts.reset_attempts
+            })
+            .returning(|_, _, _, _| Ok(Default::default()))
+            .times(1);
+        let retry_client = RetryClient::new(mock_client, TEST_RETRY_CONFIG);
+        let result = retry_client
+            .unpause_activity(
+                "wid".to_string(),
+                None,
+                unpause_activity_request::Activity::Id("act".to_string()),
+                UnpauseActivityOptions {
+                    reset_attempts: true,
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn custom_retryable_codes() {
+        let mut mock_client = MockWorkflowClientTrait::new();
+        mock_client
+            .expect_cancel_activity_task()
+            .returning(|_, _| Err(Status::new(Code::Unavailable, "not retried")))
+            .times(1);
+        let retry_client = RetryClient::new(
+            mock_client,
+            RetryConfig {
+                retryable_codes: Cow::Borrowed(&[Code::Internal]),
+                ..TEST_RETRY_CONFIG
+            },
+        );
+        let result = retry_client
+            .cancel_activity_task(vec![1].into(), None)
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn retry_budget_limits_retries() {
+        let budget = RetryBudget::new(4.0, 1.0);
+        let mut mock_client = MockWorkflowClientTrait::new();
+        // The first failure leaves 3 of 4 tokens, so it's retried. The second leaves only half,
+        // so the call gives up after two attempts.
+        mock_client
+            .expect_cancel_activity_task()
+            .returning(|_, _| Err(Status::new(Code::Unavailable, "flapping")))
+            .times(2);
+        let retry_client = RetryClient::new(
+            mock_client,
+            RetryConfig {
+                retry_budget: Some(budget.clone()),
+                ..TEST_RETRY_CONFIG
+            },
+        );
+        let result = retry_client
+            .cancel_activity_task(vec![1].into(), None)
+            .await;
+        assert!(result.is_err());
+        // The budget is depleted, so later calls aren't retried either until successes refill it
+        assert!(!budget.record_failure());
+        budget.record_success();
+        budget.record_success();
+        budget.record_success();
+        assert!(budget.record_failure());
+    }
+
+    #[tokio::test]
+    async fn long_polls_ignore_retry_budget() {
+        let budget = RetryBudget::new(1.0, 1.0);
+        let fake_retry = RetryClient::new(
+            (),
+            RetryConfig {
+                retry_budget: Some(budget),
+                ..TEST_RETRY_CONFIG
+            },
+        );
+        let mut err_handler = TonicErrorHandler::new(
+            fake_retry.get_retry_config(POLL_WORKFLOW_METH_NAME),
+            RetryConfig::throttle_retry_policy(),
+            POLL_WORKFLOW_METH_NAME,
+        );
+        for i in 1..=5 {
+            let result = err_handler.handle(i, Status::new(Code::Unavailable, "Ahh"));
+            assert_matches!(result, RetryPolicy::WaitRetry(_));
+        }
+    }
+
+    #[test]
+    fn long_polls_surface_errors_after_max_outage() {
+        let fake_retry = RetryClient::new(
+            (),
+            RetryConfig {
+                poll_reconnect: ReconnectPolicy {
+                    max_outage: Some(Duration::from_secs(30)),
+                    fatal_error_grace: Duration::from_secs(5),
+                    ..ReconnectPolicy::DEFAULT
+                },
+                ..TEST_RETRY_CONFIG
+            },
+        );
+        let start = Instant::now();
+        let mut err_handler = TonicErrorHandler::new_with_clock(
+            fake_retry.get_retry_config(POLL_ACTIVITY_METH_NAME),
+            RetryConfig::throttle_retry_policy(),
+            POLL_ACTIVITY_METH_NAME,
+            FixedClock(start),
+            FixedClock(start),
+        );
+        let result = err_handler.handle(1, Status::new(Code::Unavailable, "down"));
+        assert_matches!(result, RetryPolicy::WaitRetry(_));
+        err_handler.backoff.clock.0 = start.add(Duration::from_secs(10));
+        let result = err_handler.handle(2, Status::new(Code::Unavailable, "down"));
+        assert_matches!(result, RetryPolicy::WaitRetry(_));
+        // Beyond the grace for errors which aren't retryable
+        let result = err_handler.handle(3, Status::new(Code::InvalidArgument, "weird proxy"));
+        assert_matches!(result, RetryPolicy::ForwardError(_));
+        err_handler.backoff.clock.0 = start.add(Duration::from_secs(31));
+        let result = err_handler.handle(4, Status::new(Code::Unavailable, "down"));
+        assert_matches!(result, RetryPolicy::ForwardError(_));
+    }
+
     #[tokio::test]
     async fn retry_resource_exhausted() {
         let mut err_handler = TonicErrorHandler {
             max_retries: TEST_RETRY_CONFIG.max_retries,
+            retryable_codes: TEST_RETRY_CONFIG.retryable_codes,
+            retry_budget: None,
+            long_poll_fatal_grace: ReconnectPolicy::DEFAULT.fatal_error_grace,
             call_type: CallType::Normal,
             call_name: POLL_WORKFLOW_METH_NAME,
             backoff: TEST_RETRY_CONFIG.into_exp_backoff(FixedClock(Instant::now())),
@@ -707,6 +1392,9 @@ mod tests {
                 max_interval: Duration::from_millis(10),
                 max_elapsed_time: None,
                 max_retries: 10,
+                retryable_codes: Cow::Borrowed(&RETRYABLE_ERROR_CODES),
+                retry_budget: None,
+                poll_reconnect: ReconnectPolicy::DEFAULT,
             }
             .into_exp_backoff(FixedClock(Instant::now())),
         };
@@ -0,0 +1,462 @@
+use crate::WorkflowOptions;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+use temporal_sdk_core_protos::{
+    coresdk::IntoPayloadsExt,
+    temporal::api::{
+        common::v1::{Payload, WorkflowType},
+        enums::v1::{ScheduleOverlapPolicy, TaskQueueKind},
+        schedule::v1 as sched,
+        taskqueue::v1::TaskQueue,
+        workflow::v1::NewWorkflowExecutionInfo,
+    },
+};
+
+/// A schedule, which takes its action at the times its spec matches. See
+/// [crate::WorkflowClientTrait::create_schedule].
+#[derive(Clone, Debug, derive_builder::Builder)]
+pub struct Schedule {
+    /// When the action is taken
+    pub spec: ScheduleSpec,
+    /// What is done each time
+    pub action: ScheduleAction,
+    /// How overlapping, missed, and failed actions are handled
+    #[builder(default)]
+    pub policies: SchedulePolicies,
+    /// Informative note about the schedule's state, such as why it was paused
+    #[builder(setter(into), default)]
+    pub note: String,
+    /// If true, no actions are taken until the schedule is unpaused
+    #[builder(default)]
+    pub paused: bool,
+    /// If set, the schedule takes its action at most this many more times, then stops
+    #[builder(setter(strip_option), default)]
+    pub remaining_actions: Option<u64>,
+}
+
+/// When a schedule takes its action: at every time matched by any of the calendars, intervals, or
+/// cron strings, except those matched by an excluded calendar, within the optional start and end
+/// times.
+#[derive(Clone, Debug, Default, PartialEq, derive_builder::Builder)]
+#[builder(default)]
+pub struct ScheduleSpec {
+    /// Calendar based times to act at
+    #[builder(setter(each(name = "calendar")))]
+    pub calendars: Vec<CalendarSpec>,
+    /// Intervals to act at
+    #[builder(setter(each(name = "interval")))]
+    pub intervals: Vec<IntervalSpec>,
+    /// Cron strings to act at, in the traditional format with optional seconds and years, or
+    /// shorthands like `@hourly`
+    #[builder(setter(each(name = "cron_string", into)))]
+    pub cron_strings: Vec<String>,
+    /// Times which are not acted at, even if otherwise matched
+    #[builder(setter(each(name = "exclude_calendar")))]
+    pub exclude_calendars: Vec<CalendarSpec>,
+    /// If set, no actions are taken before this time
+    #[builder(setter(strip_option))]
+    pub start_time: Option<SystemTime>,
+    /// If set, no actions are taken after this time
+    #[builder(setter(strip_option))]
+    pub end_time: Option<SystemTime>,
+    /// If set, each action is delayed by a random amount of up to this much, spreading the load
+    /// of many schedules matching the same times
+    #[builder(setter(strip_option))]
+    pub jitter: Option<Duration>,
+    /// IANA name of the time zone calendars and cron strings are interpreted in. UTC if empty.
+    #[builder(setter(into))]
+    pub timezone_name: String,
+}
+
+/// Matches times by calendar fields. Each field matches any of its ranges. Left empty, `second`,
+/// `minute`, and `hour` match zero, and the other fields match every value, so the default spec
+/// matches midnight every day.
+#[derive(Clone, Debug, Default, PartialEq, Eq, derive_builder::Builder)]
+#[builder(default, setter(into))]
+pub struct CalendarSpec {
+    /// Seconds, 0 to 59
+    pub second: Vec<ScheduleRange>,
+    /// Minutes, 0 to 59
+    pub minute: Vec<ScheduleRange>,
+    /// Hours, 0 to 23
+    pub hour: Vec<ScheduleRange>,
+    /// Days of the month, 1 to 31
+    pub day_of_month: Vec<ScheduleRange>,
+    /// Months, 1 to 12
+    pub month: Vec<ScheduleRange>,
+    /// Years
+    pub year: Vec<ScheduleRange>,
+    /// Days of the week, 0 (Sunday) to 6
+    pub day_of_week: Vec<ScheduleRange>,
+    /// Free form description of what the calendar matches
+    pub comment: String,
+}
+
+/// An inclusive range of values of a calendar field, matching every `step`th value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduleRange {
+    /// First value matched
+    pub start: i32,
+    /// Last value matched, at most
+    pub end: i32,
+    /// Distance between matched values
+    pub step: i32,
+}
+
+impl ScheduleRange {
+    /// Matches a single value
+    pub const fn value(v: i32) -> Self {
+        Self::range(v, v)
+    }
+
+    /// Matches every value from `start` to `end`, inclusive
+    pub const fn range(start: i32, end: i32) -> Self {
+        Self {
+            start,
+            end,
+            step: 1,
+        }
+    }
+
+    /// Matches only every `step`th value of the range
+    pub const fn with_step(self, step: i32) -> Self {
+        Self { step, ..self }
+    }
+}
+
+/// Matches times which are a whole number of `every` past the epoch, plus `offset`. For example an
+/// interval of an hour with an offset of 15 minutes matches 15 minutes past each hour.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSpec {
+    /// Length of the interval
+    pub every: Duration,
+    /// Offset of matched times within each interval
+    pub offset: Duration,
+}
+
+impl IntervalSpec {
+    /// Matches every `every`, starting at the epoch
+    pub const fn every(every: Duration) -> Self {
+        Self {
+            every,
+            offset: Duration::ZERO,
+        }
+    }
+}
+
+/// How a schedule handles overlapping, missed, and failed actions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchedulePolicies {
+    /// What happens when an action is due while the previous one's workflow is still running.
+    /// Unspecified means the server's default, which skips the action.
+    pub overlap: ScheduleOverlapPolicy,
+    /// How long after a missed time, such as during an outage, the action may still be taken. The
+    /// server's default is a year.
+    pub catchup_window: Option<Duration>,
+    /// If true, the schedule is paused when a workflow it started fails or times out
+    pub pause_on_failure: bool,
+}
+
+/// What a schedule does each time its spec matches
+#[derive(Clone, Debug)]
+pub enum ScheduleAction {
+    /// Start a workflow
+    StartWorkflow {
+        /// Id of started workflows, to which the server appends the time they were scheduled for
+        workflow_id: String,
+        /// Type of workflow to start
+        workflow_type: String,
+        /// Task queue to start workflows on
+        task_queue: String,
+        /// Input to started workflows
+        input: Vec<Payload>,
+        /// Other options of started workflows. Eager start isn't supported.
+        options: WorkflowOptions,
+    },
+}
+
+/// Options used only when creating a schedule
+#[derive(Clone, Debug, Default)]
+pub struct CreateScheduleOptions {
+    /// Changes to apply right after the schedule is created, like triggering it immediately
+    pub initial_patch: Option<SchedulePatch>,
+    /// Memo attached to the schedule
+    pub memo: Option<HashMap<String, Payload>>,
+    /// Search attributes of the schedule, for filtering schedule lists
+    pub search_attributes: Option<HashMap<String, Payload>>,
+    /// Deduplicates creation requests. Generated if not set.
+    pub request_id: Option<String>,
+}
+
+/// One-off changes to a schedule which can be made without replacing it. See
+/// [crate::WorkflowClientTrait::patch_schedule].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchedulePatch {
+    /// If set, take the action now, with this overlap policy
+    pub trigger_immediately: Option<ScheduleOverlapPolicy>,
+    /// Take the actions which would have been taken in these time ranges
+    pub backfills: Vec<ScheduleBackfill>,
+    /// If set, pause the schedule with this note
+    pub pause: Option<String>,
+    /// If set, unpause the schedule with this note
+    pub unpause: Option<String>,
+}
+
+/// Actions to take as if a schedule had been running over a range of past times
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduleBackfill {
+    /// Start of the range, inclusive
+    pub start_time: SystemTime,
+    /// End of the range, inclusive
+    pub end_time: SystemTime,
+    /// Overlap policy of the backfilled actions
+    pub overlap: ScheduleOverlapPolicy,
+}
+
+impl SchedulePatch {
+    /// Take the action now
+    pub fn trigger(overlap: ScheduleOverlapPolicy) -> Self {
+        Self {
+            trigger_immediately: Some(overlap),
+            ..Default::default()
+        }
+    }
+
+    /// Take the actions which would have been taken over the given time ranges
+    pub fn backfill(backfills: impl IntoIterator<Item = ScheduleBackfill>) -> Self {
+        Self {
+            backfills: backfills.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Pause the schedule, noting why
+    pub fn pause(note: impl Into<String>) -> Self {
+        Self {
+            pause: Some(note.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Unpause the schedule, noting why
+    pub fn unpause(note: impl Into<String>) -> Self {
+        Self {
+            unpause: Some(note.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The server only pauses or unpauses when given a non-empty note
+fn state_change_note(note: Option<String>, default: &str) -> String {
+    match note {
+        Some(n) if n.is_empty() => default.to_string(),
+        Some(n) => n,
+        None => String::new(),
+    }
+}
+
+impl From<SchedulePatch> for sched::SchedulePatch {
+    fn from(p: SchedulePatch) -> Self {
+        Self {
+            trigger_immediately: p.trigger_immediately.map(|overlap| {
+                sched::TriggerImmediatelyRequest {
+                    overlap_policy: overlap as i32,
+                }
+            }),
+            backfill_request: p
+                .backfills
+                .into_iter()
+                .map(|b| sched::BackfillRequest {
+                    start_time: Some(b.start_time.into()),
+                    end_time: Some(b.end_time.into()),
+                    overlap_policy: b.overlap as i32,
+                })
+                .collect(),
+            pause: state_change_note(p.pause, "Paused"),
+            unpause: state_change_note(p.unpause, "Unpaused"),
+        }
+    }
+}
+
+impl From<Schedule> for sched::Schedule {
+    fn from(s: Schedule) -> Self {
+        Self {
+            spec: Some(s.spec.into()),
+            action: Some(s.action.into()),
+            policies: Some(sched::SchedulePolicies {
+                overlap_policy: s.policies.overlap as i32,
+                catchup_window: s.policies.catchup_window.and_then(|d| d.try_into().ok()),
+                pause_on_failure: s.policies.pause_on_failure,
+            }),
+            state: Some(sched::ScheduleState {
+                notes: s.note,
+                paused: s.paused,
+                limited_actions: s.remaining_actions.is_some(),
+                remaining_actions: s.remaining_actions.unwrap_or_default() as i64,
+            }),
+        }
+    }
+}
+
+impl From<ScheduleSpec> for sched::ScheduleSpec {
+    fn from(s: ScheduleSpec) -> Self {
+        Self {
+            structured_calendar: s.calendars.into_iter().map(Into::into).collect(),
+            cron_string: s.cron_strings,
+            interval: s
+                .intervals
+                .into_iter()
+                .map(|i| sched::IntervalSpec {
+                    interval: i.every.try_into().ok(),
+                    phase: i.offset.try_into().ok(),
+                })
+                .collect(),
+            exclude_structured_calendar: s.exclude_calendars.into_iter().map(Into::into).collect(),
+            start_time: s.start_time.map(Into::into),
+            end_time: s.end_time.map(Into::into),
+            jitter: s.jitter.and_then(|d| d.try_into().ok()),
+            timezone_name: s.timezone_name,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<CalendarSpec> for sched::StructuredCalendarSpec {
+    fn from(c: CalendarSpec) -> Self {
+        let ranges = |rs: Vec<ScheduleRange>| {
+            rs.into_iter()
+                .map(|r| sched::Range {
+                    start: r.start,
+                    end: r.end,
+                    step: r.step,
+                })
+                .collect()
+        };
+        Self {
+            second: ranges(c.second),
+            minute: ranges(c.minute),
+            hour: ranges(c.hour),
+            day_of_month: ranges(c.day_of_month),
+            month: ranges(c.month),
+            year: ranges(c.year),
+            day_of_week: ranges(c.day_of_week),
+            comment: c.comment,
+        }
+    }
+}
+
+impl From<ScheduleAction> for sched::ScheduleAction {
+    fn from(a: ScheduleAction) -> Self {
+        let ScheduleAction::StartWorkflow {
+            workflow_id,
+            workflow_type,
+            task_queue,
+            input,
+            options,
+        } = a;
+        Self {
+            action: Some(sched::schedule_action::Action::StartWorkflow(
+                NewWorkflowExecutionInfo {
+                    workflow_id,
+                    workflow_type: Some(WorkflowType {
+                        name: workflow_type,
+                    }),
+                    task_queue: Some(TaskQueue {
+                        name: task_queue,
+                        kind: TaskQueueKind::Normal as i32,
+                        normal_name: "".to_string(),
+                    }),
+                    input: input.into_payloads(),
+                    workflow_execution_timeout: options
+                        .execution_timeout
+                        .and_then(|d| d.try_into().ok()),
+                    workflow_run_timeout: options.run_timeout.and_then(|d| d.try_into().ok()),
+                    workflow_task_timeout: options.task_timeout.and_then(|d| d.try_into().ok()),
+                    workflow_id_reuse_policy: options.id_reuse_policy as i32,
+                    retry_policy: options.retry_policy,
+                    cron_schedule: options.cron_schedule.unwrap_or_default(),
+                    search_attributes: options.search_attributes.map(Into::into),
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_specs() {
+        let spec = ScheduleSpecBuilder::default()
+            .calendar(
+                CalendarSpecBuilder::default()
+                    .hour(vec![ScheduleRange::range(9, 17).with_step(2)])
+                    .day_of_week(vec![ScheduleRange::range(1, 5)])
+                    .build()
+                    .unwrap(),
+            )
+            .interval(IntervalSpec::every(Duration::from_secs(3600)))
+            .cron_string("@daily")
+            .jitter(Duration::from_secs(30))
+            .build()
+            .unwrap();
+        let proto = sched::ScheduleSpec::from(spec);
+        assert_eq!(
+            proto.structured_calendar[0].hour,
+            vec![sched::Range {
+                start: 9,
+                end: 17,
+                step: 2
+            }]
+        );
+        assert!(proto.structured_calendar[0].second.is_empty());
+        assert_eq!(proto.interval[0].interval.as_ref().unwrap().seconds, 3600);
+        assert_eq!(proto.cron_string, vec!["@daily"]);
+        assert_eq!(proto.jitter.unwrap().seconds, 30);
+    }
+
+    #[test]
+    fn converts_schedules() {
+        let schedule = ScheduleBuilder::default()
+            .spec(ScheduleSpec::default())
+            .action(ScheduleAction::StartWorkflow {
+                workflow_id: "wf".to_string(),
+                workflow_type: "Report".to_string(),
+                task_queue: "tq".to_string(),
+                input: vec![],
+                options: Default::default(),
+            })
+            .remaining_actions(3)
+            .build()
+            .unwrap();
+        let proto = sched::Schedule::from(schedule);
+        let state = proto.state.unwrap();
+        assert!(state.limited_actions);
+        assert_eq!(state.remaining_actions, 3);
+        let Some(sched::schedule_action::Action::StartWorkflow(start)) =
+            proto.action.unwrap().action
+        else {
+            panic!("Should start a workflow");
+        };
+        assert_eq!(start.workflow_type.unwrap().name, "Report");
+        assert_eq!(start.task_queue.unwrap().name, "tq");
+    }
+
+    #[test]
+    fn patches_always_pause_with_a_note() {
+        let patch = sched::SchedulePatch::from(SchedulePatch::pause(""));
+        assert_eq!(patch.pause, "Paused");
+        assert!(patch.unpause.is_empty());
+        let patch =
+            sched::SchedulePatch::from(SchedulePatch::trigger(ScheduleOverlapPolicy::AllowAll));
+        assert_eq!(
+            patch.trigger_immediately.unwrap().overlap_policy,
+            ScheduleOverlapPolicy::AllowAll as i32
+        );
+    }
+}
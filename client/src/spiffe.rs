@@ -0,0 +1,342 @@
+//! Sourcing the client's mTLS identity from a SPIFFE Workload API, as served by a SPIRE agent.
+//! The API streams a new X.509 SVID whenever the current one is rotated, and the client reconnects
+//! with each one.
+// The Workload API is only reachable over unix domain sockets
+#![cfg_attr(not(unix), allow(unused_imports))]
+
+use crate::{
+    tls_reload::{Reconnected, WeakReloadableChannel},
+    ClientInitError, ClientOptions, ClientTlsConfig,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use futures::future::BoxFuture;
+use http::{uri::PathAndQuery, Uri};
+use std::{
+    env, io,
+    path::PathBuf,
+    task::{Context, Poll},
+    time::Duration,
+};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tonic::{
+    codec::{ProstCodec, Streaming},
+    metadata::MetadataValue,
+    transport::Endpoint,
+};
+use tower::Service;
+
+/// Environment variable which the Workload API socket address is read from if not configured
+const SOCKET_ENV_VAR: &str = "SPIFFE_ENDPOINT_SOCKET";
+/// The Workload API rejects calls without this header
+const WORKLOAD_API_HEADER: &str = "workload.spiffe.io";
+const FETCH_X509_SVID_PATH: &str = "/SpiffeWorkloadAPI/FetchX509SVID";
+/// How long to wait before reconnecting to the Workload API after losing the stream
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How often to check, while waiting for new SVIDs, whether the client is still around
+const LIVENESS_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Options for sourcing the client's mTLS identity from a SPIFFE Workload API. The server's CA is
+/// still taken from [crate::TlsConfig::server_root_ca_cert], if set.
+#[derive(Clone, Debug, Default)]
+pub struct SpiffeWorkloadOptions {
+    /// Path to the Workload API's unix domain socket. If unset, it is read from the
+    /// `SPIFFE_ENDPOINT_SOCKET` environment variable (for example `unix:///tmp/spire-agent.sock`).
+    pub socket_path: Option<PathBuf>,
+    /// SPIFFE ID of the SVID to use, when the workload is issued more than one. If unset, the
+    /// first (default) SVID is used.
+    pub spiffe_id: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509SvidRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509SvidResponse {
+    #[prost(message, repeated, tag = "1")]
+    svids: Vec<X509Svid>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509Svid {
+    #[prost(string, tag = "1")]
+    spiffe_id: String,
+    /// ASN.1 DER encoded certificate chain, leaf first
+    #[prost(bytes = "vec", tag = "2")]
+    x509_svid: Vec<u8>,
+    /// ASN.1 DER encoded PKCS#8 private key
+    #[prost(bytes = "vec", tag = "3")]
+    x509_svid_key: Vec<u8>,
+}
+
+/// A stream of SVIDs from the Workload API
+pub(crate) struct SvidStream(Streaming<X509SvidResponse>);
+
+impl SpiffeWorkloadOptions {
+    /// Connects to the Workload API and waits for the first SVID
+    pub(crate) async fn connect(&self) -> Result<(ClientTlsConfig, SvidStream), ClientInitError> {
+        let mut stream = self.open_stream().await?;
+        let first = stream
+            .0
+            .message()
+            .await
+            .map_err(|s| api_err(format!("receiving SVID failed: {s}")))?
+            .ok_or_else(|| api_err("stream ended before providing an SVID"))?;
+        Ok((self.select_identity(first)?, stream))
+    }
+
+    fn socket_path(&self) -> Result<PathBuf, ClientInitError> {
+        if let Some(p) = self.socket_path.as_ref() {
+            return Ok(p.clone());
+        }
+        let addr = env::var(SOCKET_ENV_VAR)
+            .map_err(|_| api_err(format!("no socket path set and {SOCKET_ENV_VAR} is unset")))?;
+        addr.strip_prefix("unix://")
+            .or_else(|| addr.strip_prefix("unix:"))
+            .map(PathBuf::from)
+            .ok_or_else(|| api_err(format!("only unix socket addresses are supported: {addr}")))
+    }
+
+    #[cfg(not(unix))]
+    async fn open_stream(&self) -> Result<SvidStream, ClientInitError> {
+        Err(api_err(
+            "the Workload API is only supported on unix platforms",
+        ))
+    }
+
+    #[cfg(unix)]
+    async fn open_stream(&self) -> Result<SvidStream, ClientInitError> {
+        let connector = UnixConnector(self.socket_path()?);
+        // The URI is only required by the endpoint, the connector ignores it
+        let channel = Endpoint::from_static("http://localhost")
+            .connect_with_connector(connector)
+            .await
+            .map_err(|e| api_err(format!("connecting failed: {e}")))?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready()
+            .await
+            .map_err(|e| api_err(format!("connecting failed: {e}")))?;
+        let mut req = tonic::Request::new(X509SvidRequest {});
+        req.metadata_mut()
+            .insert(WORKLOAD_API_HEADER, MetadataValue::from_static("true"));
+        let resp = grpc
+            .server_streaming(
+                req,
+                PathAndQuery::from_static(FETCH_X509_SVID_PATH),
+                ProstCodec::<X509SvidRequest, X509SvidResponse>::default(),
+            )
+            .await
+            .map_err(|s| api_err(format!("fetching SVIDs failed: {s}")))?;
+        Ok(SvidStream(resp.into_inner()))
+    }
+
+    fn select_identity(&self, resp: X509SvidResponse) -> Result<ClientTlsConfig, ClientInitError> {
+        let svid = match self.spiffe_id.as_ref() {
+            Some(id) => resp.svids.into_iter().find(|s| &s.spiffe_id == id),
+            None => resp.svids.into_iter().next(),
+        }
+        .ok_or_else(|| api_err("no matching SVID was provided"))?;
+        let mut client_cert = String::new();
+        for cert in split_der_sequence(&svid.x509_svid)
+            .ok_or_else(|| api_err("SVID certificate chain is malformed"))?
+        {
+            client_cert.push_str(&der_to_pem("CERTIFICATE", cert));
+        }
+        Ok(ClientTlsConfig {
+            client_cert: client_cert.into_bytes(),
+            client_private_key: der_to_pem("PRIVATE KEY", &svid.x509_svid_key).into_bytes(),
+        })
+    }
+}
+
+impl WeakReloadableChannel {
+    /// Reconnects with each new SVID from the stream for as long as any clone of the channel
+    /// exists, reopening the stream if it is lost
+    pub(crate) fn spawn_svid_watcher(
+        self,
+        opts: ClientOptions,
+        spiffe: SpiffeWorkloadOptions,
+        stream: SvidStream,
+    ) {
+        tokio::spawn(watch_svids(self, opts, spiffe, stream));
+    }
+}
+
+async fn watch_svids(
+    channel: WeakReloadableChannel,
+    opts: ClientOptions,
+    spiffe: SpiffeWorkloadOptions,
+    mut stream: SvidStream,
+) {
+    loop {
+        let msg = tokio::select! {
+            m = stream.0.message() => m,
+            _ = tokio::time::sleep(LIVENESS_CHECK_INTERVAL) => {
+                if !channel.is_alive() {
+                    return;
+                }
+                continue;
+            }
+        };
+        match msg {
+            Ok(Some(resp)) => {
+                let reconnected = match spiffe.select_identity(resp) {
+                    Ok(tls) => {
+                        channel
+                            .reconnect(&opts, |target| {
+                                target.client_tls = Some(tls);
+                                true
+                            })
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                match reconnected {
+                    Ok(Reconnected::Replaced | Reconnected::Unchanged) => {
+                        info!("Reconnected with rotated SPIFFE SVID")
+                    }
+                    Ok(Reconnected::Dropped) => return,
+                    Err(e) => warn!(error=%e, "Couldn't reconnect with rotated SPIFFE SVID"),
+                }
+            }
+            Ok(None) | Err(_) => {
+                warn!("Lost SPIFFE Workload API stream, reconnecting");
+                loop {
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    if !channel.is_alive() {
+                        return;
+                    }
+                    match spiffe.open_stream().await {
+                        Ok(s) => {
+                            stream = s;
+                            break;
+                        }
+                        Err(e) => warn!(error=%e, "Couldn't reconnect to SPIFFE Workload API"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn api_err(msg: impl Into<String>) -> ClientInitError {
+    ClientInitError::WorkloadApiError(msg.into())
+}
+
+/// Splits concatenated DER values (like the certificates of a chain) apart. Returns None if the
+/// input isn't a well formed sequence of DER values.
+fn split_der_sequence(mut der: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut values = vec![];
+    while !der.is_empty() {
+        let len_byte = *der.get(1)?;
+        let (header_len, content_len) = if len_byte < 0x80 {
+            (2, len_byte as usize)
+        } else {
+            let num_len_bytes = (len_byte & 0x7f) as usize;
+            if num_len_bytes == 0 || num_len_bytes > 4 {
+                return None;
+            }
+            let len_bytes = der.get(2..2 + num_len_bytes)?;
+            let len = len_bytes
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            (2 + num_len_bytes, len)
+        };
+        let total = header_len + content_len;
+        values.push(der.get(..total)?);
+        der = &der[total..];
+    }
+    Some(values)
+}
+
+fn der_to_pem(label: &str, der: &[u8]) -> String {
+    let encoded = BASE64_STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+#[cfg(unix)]
+#[derive(Clone)]
+struct UnixConnector(PathBuf);
+
+#[cfg(unix)]
+impl Service<Uri> for UnixConnector {
+    type Response = UnixStream;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<UnixStream>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let path = self.0.clone();
+        Box::pin(async move { UnixStream::connect(path).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_der_chain() {
+        let short = [0x30, 0x02, 0xaa, 0xbb];
+        let mut long = vec![0x30, 0x81, 0x80];
+        long.extend([0xcc; 0x80]);
+        let chain = [short.as_slice(), long.as_slice()].concat();
+        assert_eq!(
+            split_der_sequence(&chain).unwrap(),
+            vec![short.as_slice(), long.as_slice()]
+        );
+        // Truncated
+        assert!(split_der_sequence(&chain[..chain.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn formats_pem() {
+        let pem = der_to_pem("CERTIFICATE", &[0; 60]);
+        let lines: Vec<_> = pem.lines().collect();
+        assert_eq!(lines[0], "-----BEGIN CERTIFICATE-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[2].len(), 16);
+        assert_eq!(lines[3], "-----END CERTIFICATE-----");
+    }
+
+    #[test]
+    fn selects_svid_by_id() {
+        let svid = |id: &str, key: u8| X509Svid {
+            spiffe_id: id.to_string(),
+            x509_svid: vec![0x30, 0x01, key],
+            x509_svid_key: vec![key],
+        };
+        let resp = X509SvidResponse {
+            svids: vec![svid("spiffe://td/a", 1), svid("spiffe://td/b", 2)],
+        };
+        let default = SpiffeWorkloadOptions::default()
+            .select_identity(resp.clone())
+            .unwrap();
+        assert_eq!(
+            default.client_private_key,
+            der_to_pem("PRIVATE KEY", &[1]).into_bytes()
+        );
+        let chosen = SpiffeWorkloadOptions {
+            spiffe_id: Some("spiffe://td/b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            chosen.select_identity(resp.clone()).unwrap().client_cert,
+            der_to_pem("CERTIFICATE", &[0x30, 0x01, 2]).into_bytes()
+        );
+        let missing = SpiffeWorkloadOptions {
+            spiffe_id: Some("spiffe://td/c".to_string()),
+            ..Default::default()
+        };
+        assert!(missing.select_identity(resp).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Reloading of client TLS certificates without rebuilding clients. The channel all clones of a
+//! client share is swapped for one using the new certificate, which new calls then use. Calls
+//! already in flight finish on the old channel.
+
+use crate::{balance::FrontendChannel, ClientInitError, ClientOptions, ClientTlsConfig};
+use parking_lot::RwLock;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Weak},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tonic::{body::BoxBody, transport::Channel};
+use tower::Service;
+use url::Url;
+
+/// Where to (re)load the client's mTLS certificate and private key from. The files are read when
+/// connecting, overriding [crate::TlsConfig::client_tls_config], and then polled for changes.
+/// When either changes the client reconnects with the new pair.
+#[derive(Clone, Debug)]
+pub struct TlsReloadConfig {
+    /// Path to the PEM encoded client certificate
+    pub client_cert_path: PathBuf,
+    /// Path to the PEM encoded client private key
+    pub client_private_key_path: PathBuf,
+    /// How often to check the files for changes. Must be at least 100ms.
+    pub poll_interval: Duration,
+}
+
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl TlsReloadConfig {
+    pub(crate) async fn load(&self) -> Result<ClientTlsConfig, ClientInitError> {
+        if self.poll_interval < MIN_POLL_INTERVAL {
+            return Err(ClientInitError::InvalidTlsConfig(format!(
+                "TLS reload poll interval must be at least {MIN_POLL_INTERVAL:?}"
+            )));
+        }
+        let read = |path: PathBuf| async move {
+            tokio::fs::read(&path)
+                .await
+                .map_err(|e| ClientInitError::TlsFileReadError(path, e))
+        };
+        Ok(ClientTlsConfig {
+            client_cert: read(self.client_cert_path.clone()).await?,
+            client_private_key: read(self.client_private_key_path.clone()).await?,
+        })
+    }
+}
+
+/// The endpoint a channel is connected to, and the mTLS identity it presents
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelTarget {
+    pub(crate) url: Url,
+    /// Overrides any configured [crate::TlsConfig::client_tls_config] if set
+    pub(crate) client_tls: Option<ClientTlsConfig>,
+}
+
+/// A [Channel] (or several) which can be replaced for all of its clones at once
+#[derive(Clone, Debug)]
+pub(crate) struct ReloadableChannel {
+    shared: Arc<Shared>,
+    generation: u64,
+    current: FrontendChannel,
+}
+
+#[derive(Debug)]
+struct Shared {
+    channel: RwLock<SharedChannel>,
+    /// Held while reconnecting, so the background tasks which reconnect for different reasons
+    /// (failover, certificate rotation) never undo each other's changes to the target
+    reconnecting: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug)]
+struct SharedChannel {
+    generation: u64,
+    channel: FrontendChannel,
+    target: ChannelTarget,
+}
+
+impl ReloadableChannel {
+    pub(crate) fn new(channel: FrontendChannel, target: ChannelTarget) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                channel: RwLock::new(SharedChannel {
+                    generation: 0,
+                    channel: channel.clone(),
+                    target,
+                }),
+                reconnecting: Default::default(),
+            }),
+            generation: 0,
+            current: channel,
+        }
+    }
+
+    /// Makes every clone of this channel use the provided one for calls started from now on
+    #[cfg(test)]
+    pub(crate) fn replace(&self, channel: FrontendChannel) {
+        let mut shared = self.shared.channel.write();
+        shared.generation += 1;
+        shared.channel = channel;
+    }
+
+    /// Returns a handle which can replace the channel, but doesn't keep it alive
+    pub(crate) fn downgrade(&self) -> WeakReloadableChannel {
+        WeakReloadableChannel(Arc::downgrade(&self.shared))
+    }
+
+    /// Polls the files in `cfg` for changes for as long as any clone of this channel exists,
+    /// reconnecting with the new certificate whenever they do change
+    pub(crate) fn spawn_reloader(&self, opts: ClientOptions, cfg: TlsReloadConfig) {
+        tokio::spawn(reload_loop(self.downgrade(), opts, cfg));
+    }
+}
+
+/// A handle to a [ReloadableChannel] which doesn't keep it alive, for background tasks which
+/// replace it
+pub(crate) struct WeakReloadableChannel(Weak<Shared>);
+
+/// The outcome of [WeakReloadableChannel::reconnect]
+#[derive(Debug)]
+pub(crate) enum Reconnected {
+    /// Calls started from now on use the new channel
+    Replaced,
+    /// The requested change left the target as it was, so nothing was done
+    Unchanged,
+    /// No clones of the channel remain
+    Dropped,
+}
+
+impl WeakReloadableChannel {
+    /// Returns true if any clone of the channel still exists
+    pub(crate) fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+
+    /// Connects to the channel's current target as modified by `change`, then makes every clone of
+    /// the channel use the new connection. The current target is read only once any other
+    /// reconnect has finished, so changes made by one never get lost to another.
+    pub(crate) async fn reconnect(
+        &self,
+        opts: &ClientOptions,
+        change: impl FnOnce(&mut ChannelTarget) -> bool,
+    ) -> Result<Reconnected, ClientInitError> {
+        let Some(shared) = self.0.upgrade() else {
+            return Ok(Reconnected::Dropped);
+        };
+        let _reconnecting = shared.reconnecting.lock().await;
+        let mut target = shared.channel.read().target.clone();
+        if !change(&mut target) {
+            return Ok(Reconnected::Unchanged);
+        }
+        let mut target_opts = opts.clone();
+        target_opts.target_url = target.url.clone();
+        let channel = target_opts
+            .connect_channel(target.client_tls.as_ref())
+            .await?;
+        // Only this upgraded handle is left if every clone was dropped while connecting
+        if Arc::strong_count(&shared) == 1 {
+            return Ok(Reconnected::Dropped);
+        }
+        let mut current = shared.channel.write();
+        current.generation += 1;
+        current.channel = channel;
+        current.target = target;
+        Ok(Reconnected::Replaced)
+    }
+}
+
+async fn reload_loop(channel: WeakReloadableChannel, opts: ClientOptions, cfg: TlsReloadConfig) {
+    loop {
+        tokio::time::sleep(cfg.poll_interval).await;
+        if !channel.is_alive() {
+            return;
+        }
+        let fresh = match cfg.load().await {
+            Ok(f) => f,
+            Err(e) => {
+                warn!(error=%e, "Couldn't read client TLS files, will retry");
+                continue;
+            }
+        };
+        let reconnected = channel
+            .reconnect(&opts, |target| {
+                let unchanged = target.client_tls.as_ref().is_some_and(|loaded| {
+                    fresh.client_cert == loaded.client_cert
+                        && fresh.client_private_key == loaded.client_private_key
+                });
+                target.client_tls = Some(fresh);
+                !unchanged
+            })
+            .await;
+        match reconnected {
+            Ok(Reconnected::Replaced) => {
+                info!("Reconnected with reloaded client TLS certificate")
+            }
+            Ok(Reconnected::Unchanged) => {}
+            Ok(Reconnected::Dropped) => return,
+            Err(e) => {
+                warn!(error=%e, "Couldn't connect with reloaded client TLS certificate, will retry")
+            }
+        }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for ReloadableChannel {
+    type Response = <Channel as Service<http::Request<BoxBody>>>::Response;
+    type Error = <Channel as Service<http::Request<BoxBody>>>::Error;
+    type Future = <FrontendChannel as Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Only switch channels here, so the one polled ready is always the one called
+        {
+            let shared = self.shared.channel.read();
+            if shared.generation != self.generation {
+                self.generation = shared.generation;
+                self.current = shared.channel.clone();
+            }
+        }
+        self.current.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        self.current.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+    use tonic::transport::Endpoint;
+
+    #[tokio::test]
+    async fn clones_pick_up_replaced_channel_when_polled() {
+        let lazy = || {
+            Endpoint::from_static("http://localhost:7233")
+                .connect_lazy()
+                .into()
+        };
+        let orig = ReloadableChannel::new(
+            lazy(),
+            ChannelTarget {
+                url: Url::parse("http://localhost:7233").unwrap(),
+                client_tls: None,
+            },
+        );
+        let mut clone = orig.clone();
+        orig.replace(lazy());
+        assert_eq!(clone.generation, 0);
+        poll_fn(|cx| clone.poll_ready(cx)).await.unwrap();
+        assert_eq!(clone.generation, 1);
+        // Clones made after the replacement start out on the old channel, too, until polled
+        let mut later = orig.clone();
+        poll_fn(|cx| later.poll_ready(cx)).await.unwrap();
+        assert_eq!(later.generation, 1);
+    }
+
+    #[tokio::test]
+    async fn loads_files() {
+        let dir = std::env::temp_dir().join(format!("tls-reload-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cfg = TlsReloadConfig {
+            client_cert_path: dir.join("cert.pem"),
+            client_private_key_path: dir.join("key.pem"),
+            poll_interval: Duration::from_secs(1),
+        };
+        assert!(matches!(
+            cfg.load().await,
+            Err(ClientInitError::TlsFileReadError(p, _)) if p == cfg.client_cert_path
+        ));
+        std::fs::write(&cfg.client_cert_path, b"cert").unwrap();
+        std::fs::write(&cfg.client_private_key_path, b"key").unwrap();
+        let loaded = cfg.load().await.unwrap();
+        assert_eq!(loaded.client_cert, b"cert");
+        assert_eq!(loaded.client_private_key, b"key");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_busy_poll_interval() {
+        let cfg = TlsReloadConfig {
+            client_cert_path: "cert.pem".into(),
+            client_private_key_path: "key.pem".into(),
+            poll_interval: Duration::ZERO,
+        };
+        assert!(matches!(
+            cfg.load().await,
+            Err(ClientInitError::InvalidTlsConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Debug;
+use tonic::metadata::MetadataMap;
+
+#[cfg(feature = "telemetry")]
+pub use otel::OtelTracePropagator;
+
+/// Writes the trace context of the code making a call into the call's metadata, so that traces
+/// recorded by the server, and by services behind it, link up with the caller's. See
+/// [crate::ClientOptions::trace_propagator].
+pub trait TracePropagator: Debug + Send + Sync {
+    /// Adds trace context headers to the metadata of an outgoing call. Called on the task making
+    /// the call, so thread and task local trace context is that of the caller. Headers already
+    /// set on the call should be left alone.
+    fn inject(&self, metadata: &mut MetadataMap);
+}
+
+#[cfg(feature = "telemetry")]
+mod otel {
+    use super::TracePropagator;
+    use opentelemetry::{
+        propagation::{Injector, TextMapPropagator},
+        trace::{TraceContextExt, TraceFlags},
+        Context,
+    };
+    use std::{str::FromStr, sync::Arc};
+    use tonic::metadata::{MetadataKey, MetadataMap};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    const TRACEPARENT_HEADER: &str = "traceparent";
+    const TRACESTATE_HEADER: &str = "tracestate";
+    const SUPPORTED_TRACEPARENT_VERSION: u8 = 0;
+
+    /// Propagates the trace context of the caller. That's the context of the current `tracing` span
+    /// if it's recorded with `tracing-opentelemetry`, or otherwise the current OpenTelemetry
+    /// [Context], which is only current while it's attached, for example by a future wrapped with
+    /// `opentelemetry::trace::FutureExt::with_context`.
+    #[derive(Clone, Debug, Default)]
+    pub enum OtelTracePropagator {
+        /// Writes `traceparent` and `tracestate` headers, as the W3C Trace Context spec
+        /// describes. Nothing is written unless a valid span context is current.
+        #[default]
+        W3cTraceContext,
+        /// Uses the propagator installed with `opentelemetry::global::set_text_map_propagator`
+        Global,
+        /// Uses the provided propagator, for example to write B3 or Jaeger headers
+        Custom(Arc<dyn TextMapPropagator + Send + Sync>),
+    }
+
+    impl TracePropagator for OtelTracePropagator {
+        fn inject(&self, metadata: &mut MetadataMap) {
+            let cx = current_context();
+            let mut injector = MetadataInjector(metadata);
+            match self {
+                OtelTracePropagator::W3cTraceContext => inject_w3c(&cx, &mut injector),
+                OtelTracePropagator::Global => {
+                    opentelemetry::global::get_text_map_propagator(|p| {
+                        p.inject_context(&cx, &mut injector)
+                    })
+                }
+                OtelTracePropagator::Custom(p) => p.inject_context(&cx, &mut injector),
+            }
+        }
+    }
+
+    /// The context of the current `tracing` span, if it's recorded as an OpenTelemetry span
+    fn current_context() -> Context {
+        let span_cx = tracing::Span::current().context();
+        if span_cx.span().span_context().is_valid() {
+            span_cx
+        } else {
+            Context::current()
+        }
+    }
+
+    fn inject_w3c(cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let sc = span.span_context();
+        if !sc.is_valid() {
+            return;
+        }
+        injector.set(
+            TRACEPARENT_HEADER,
+            format!(
+                "{:02x}-{:032x}-{:016x}-{:02x}",
+                SUPPORTED_TRACEPARENT_VERSION,
+                sc.trace_id(),
+                sc.span_id(),
+                sc.trace_flags() & TraceFlags::SAMPLED
+            ),
+        );
+        let state = sc.trace_state().header();
+        if !state.is_empty() {
+            injector.set(TRACESTATE_HEADER, state);
+        }
+    }
+
+    struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+    impl Injector for MetadataInjector<'_> {
+        fn set(&mut self, key: &str, value: String) {
+            // Invalid keys or values are dropped, rather than failing the call
+            if let (Ok(key), Ok(value)) = (MetadataKey::from_str(key), value.parse()) {
+                if !self.0.contains_key(&key) {
+                    self.0.insert(key, value);
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use opentelemetry::trace::{SpanContext, SpanId, TraceId, TraceState};
+
+        #[test]
+        fn writes_w3c_headers_for_current_span() {
+            let mut md = MetadataMap::new();
+            OtelTracePropagator::W3cTraceContext.inject(&mut md);
+            assert!(md.is_empty());
+
+            let sc = SpanContext::new(
+                TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+                SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+                TraceFlags::SAMPLED,
+                true,
+                TraceState::from_key_value([("vendor", "value")]).unwrap(),
+            );
+            let _guard = Context::new().with_remote_span_context(sc).attach();
+            md.insert(TRACESTATE_HEADER, "caller=set".parse().unwrap());
+            OtelTracePropagator::W3cTraceContext.inject(&mut md);
+            assert_eq!(
+                md.get(TRACEPARENT_HEADER).unwrap(),
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+            );
+            assert_eq!(md.get(TRACESTATE_HEADER).unwrap(), "caller=set");
+        }
+
+        #[test]
+        fn writes_headers_for_current_tracing_span() {
+            use opentelemetry::trace::TracerProvider as _;
+            use tracing_subscriber::layer::SubscriberExt;
+
+            let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+            let subscriber = tracing_subscriber::registry()
+                .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+            tracing::subscriber::with_default(subscriber, || {
+                let span = tracing::info_span!("caller");
+                let _entered = span.enter();
+                let mut md = MetadataMap::new();
+                OtelTracePropagator::W3cTraceContext.inject(&mut md);
+
+                let sc = span.context().span().span_context().clone();
+                assert!(sc.is_valid());
+                assert_eq!(
+                    md.get(TRACEPARENT_HEADER).unwrap(),
+                    format!("00-{:032x}-{:016x}-01", sc.trace_id(), sc.span_id()).as_str()
+                );
+            });
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use temporal_sdk_core_protos::temporal::api::{
+    enums::v1::TaskReachability,
+    taskqueue::v1::CompatibleVersionSet as ProtoCompatibleVersionSet,
+    workflowservice::v1::{
+        update_worker_build_id_compatibility_request::{
+            AddNewCompatibleVersion, MergeSets, Operation,
+        },
+        GetWorkerBuildIdCompatibilityResponse, GetWorkerTaskReachabilityResponse,
+        UpdateWorkerBuildIdCompatibilityRequest,
+    },
+};
+
+/// An operation which modifies the Build ID compatibility sets of a task queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildIdOperation {
+    /// Adds a new Build ID in a new set, which becomes the default set for the queue. The new set
+    /// is incompatible with every existing set.
+    AddNewDefault(String),
+    /// Adds a new Build ID to the set containing `existing_compatible_build_id`, making it that
+    /// set's default. If `make_set_default` is true, that set also becomes the queue's default.
+    AddNewCompatible {
+        /// The Build ID being added
+        new_build_id: String,
+        /// A Build ID which already exists in the set the new one should be added to
+        existing_compatible_build_id: String,
+        /// Whether the set should also become the overall default for the queue
+        make_set_default: bool,
+    },
+    /// Promotes the set containing the given Build ID to be the queue's default set
+    PromoteSetByBuildId(String),
+    /// Promotes the given Build ID to be the default within its set
+    PromoteBuildIdWithinSet(String),
+    /// Merges the set containing `secondary_set_build_id` into the set containing
+    /// `primary_set_build_id`. The primary set's default remains the default of the merged set.
+    MergeSets {
+        /// A Build ID in the set which will be merged into
+        primary_set_build_id: String,
+        /// A Build ID in the set which will be merged away
+        secondary_set_build_id: String,
+    },
+}
+
+impl BuildIdOperation {
+    /// Converts this operation into a request targeting the provided namespace and task queue
+    pub fn into_request(
+        self,
+        namespace: String,
+        task_queue: String,
+    ) -> UpdateWorkerBuildIdCompatibilityRequest {
+        UpdateWorkerBuildIdCompatibilityRequest {
+            namespace,
+            task_queue,
+            operation: Some(self.into()),
+        }
+    }
+}
+
+impl From<BuildIdOperation> for Operation {
+    fn from(op: BuildIdOperation) -> Self {
+        match op {
+            BuildIdOperation::AddNewDefault(bid) => Operation::AddNewBuildIdInNewDefaultSet(bid),
+            BuildIdOperation::AddNewCompatible {
+                new_build_id,
+                existing_compatible_build_id,
+                make_set_default,
+            } => Operation::AddNewCompatibleBuildId(AddNewCompatibleVersion {
+                new_build_id,
+                existing_compatible_build_id,
+                make_set_default,
+            }),
+            BuildIdOperation::PromoteSetByBuildId(bid) => Operation::PromoteSetByBuildId(bid),
+            BuildIdOperation::PromoteBuildIdWithinSet(bid) => {
+                Operation::PromoteBuildIdWithinSet(bid)
+            }
+            BuildIdOperation::MergeSets {
+                primary_set_build_id,
+                secondary_set_build_id,
+            } => Operation::MergeSets(MergeSets {
+                primary_set_build_id,
+                secondary_set_build_id,
+            }),
+        }
+    }
+}
+
+/// A set of Build IDs which are compatible with one another
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibleVersionSet {
+    /// Build IDs in the set, ordered oldest to newest. The last one is the set's default.
+    pub build_ids: Vec<String>,
+}
+
+impl CompatibleVersionSet {
+    /// The Build ID new tasks on this set will be dispatched to
+    pub fn default_build_id(&self) -> Option<&str> {
+        self.build_ids.last().map(String::as_str)
+    }
+
+    /// Returns true if the set contains the provided Build ID
+    pub fn contains(&self, build_id: &str) -> bool {
+        self.build_ids.iter().any(|b| b == build_id)
+    }
+}
+
+impl From<ProtoCompatibleVersionSet> for CompatibleVersionSet {
+    fn from(s: ProtoCompatibleVersionSet) -> Self {
+        Self {
+            build_ids: s.build_ids,
+        }
+    }
+}
+
+/// The Build ID compatibility sets of a task queue
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibleVersionSets {
+    /// Sets ordered oldest to newest. The last one is the queue's default set.
+    pub sets: Vec<CompatibleVersionSet>,
+}
+
+impl CompatibleVersionSets {
+    /// The set new workflows on the queue will be started on
+    pub fn default_set(&self) -> Option<&CompatibleVersionSet> {
+        self.sets.last()
+    }
+
+    /// The Build ID new workflows on the queue will be started on
+    pub fn default_build_id(&self) -> Option<&str> {
+        self.default_set().and_then(|s| s.default_build_id())
+    }
+
+    /// Returns the set containing the provided Build ID, if any
+    pub fn set_containing(&self, build_id: &str) -> Option<&CompatibleVersionSet> {
+        self.sets.iter().find(|s| s.contains(build_id))
+    }
+}
+
+impl From<GetWorkerBuildIdCompatibilityResponse> for CompatibleVersionSets {
+    fn from(r: GetWorkerBuildIdCompatibilityResponse) -> Self {
+        Self {
+            sets: r.major_version_sets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Reachability of tasks for a set of Build IDs, keyed by Build ID and then by task queue
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildIdReachability {
+    /// Per Build ID, the kinds of tasks which may reach it on each task queue. An empty list means
+    /// it is unreachable on that queue.
+    pub build_ids: HashMap<String, HashMap<String, Vec<TaskReachability>>>,
+}
+
+impl BuildIdReachability {
+    /// Returns the kinds of tasks which may reach `build_id` on `task_queue`
+    pub fn reachability(&self, build_id: &str, task_queue: &str) -> &[TaskReachability] {
+        self.build_ids
+            .get(build_id)
+            .and_then(|tqs| tqs.get(task_queue))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if no tasks can reach `build_id` on any of the task queues it was queried for,
+    /// meaning workers with that Build ID may be retired. Returns false if the Build ID was not
+    /// part of the query, or if the server did not compute reachability for some task queue (which
+    /// it can do when too many task queues are queried at once).
+    pub fn is_unreachable(&self, build_id: &str) -> bool {
+        self.build_ids
+            .get(build_id)
+            .is_some_and(|tqs| !tqs.is_empty() && tqs.values().all(|r| r.is_empty()))
+    }
+}
+
+impl From<GetWorkerTaskReachabilityResponse> for BuildIdReachability {
+    fn from(r: GetWorkerTaskReachabilityResponse) -> Self {
+        let build_ids = r
+            .build_id_reachability
+            .into_iter()
+            .map(|bid| {
+                let tqs = bid
+                    .task_queue_reachability
+                    .into_iter()
+                    .map(|tqr| {
+                        let reach = tqr
+                            .reachability
+                            .into_iter()
+                            .map(|r| {
+                                TaskReachability::try_from(r)
+                                    .unwrap_or(TaskReachability::Unspecified)
+                            })
+                            .collect();
+                        (tqr.task_queue, reach)
+                    })
+                    .collect();
+                (bid.build_id, tqs)
+            })
+            .collect();
+        Self { build_ids }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::temporal::api::taskqueue::v1::{
+        BuildIdReachability as ProtoBuildIdReachability, TaskQueueReachability,
+    };
+
+    #[test]
+    fn default_set_and_build_id() {
+        let sets: CompatibleVersionSets = GetWorkerBuildIdCompatibilityResponse {
+            major_version_sets: vec![
+                ProtoCompatibleVersionSet {
+                    build_ids: vec!["1.0".to_string(), "1.1".to_string()],
+                },
+                ProtoCompatibleVersionSet {
+                    build_ids: vec!["2.0".to_string(), "2.1".to_string()],
+                },
+            ],
+        }
+        .into();
+        assert_eq!(sets.default_build_id(), Some("2.1"));
+        assert_eq!(
+            sets.set_containing("1.0").unwrap().default_build_id(),
+            Some("1.1")
+        );
+        assert!(sets.set_containing("3.0").is_none());
+        assert_eq!(CompatibleVersionSets::default().default_build_id(), None);
+    }
+
+    #[test]
+    fn operation_into_request() {
+        let req = BuildIdOperation::AddNewCompatible {
+            new_build_id: "1.1".to_string(),
+            existing_compatible_build_id: "1.0".to_string(),
+            make_set_default: true,
+        }
+        .into_request("ns".to_string(), "tq".to_string());
+        assert_eq!(req.namespace, "ns");
+        assert_eq!(req.task_queue, "tq");
+        assert_eq!(
+            req.operation,
+            Some(Operation::AddNewCompatibleBuildId(
+                AddNewCompatibleVersion {
+                    new_build_id: "1.1".to_string(),
+                    existing_compatible_build_id: "1.0".to_string(),
+                    make_set_default: true,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn reachability_lookup() {
+        let reach: BuildIdReachability = GetWorkerTaskReachabilityResponse {
+            build_id_reachability: vec![
+                ProtoBuildIdReachability {
+                    build_id: "old".to_string(),
+                    task_queue_reachability: vec![TaskQueueReachability {
+                        task_queue: "tq".to_string(),
+                        reachability: vec![],
+                    }],
+                },
+                ProtoBuildIdReachability {
+                    build_id: "new".to_string(),
+                    task_queue_reachability: vec![TaskQueueReachability {
+                        task_queue: "tq".to_string(),
+                        reachability: vec![TaskReachability::NewWorkflows as i32],
+                    }],
+                },
+            ],
+        }
+        .into();
+        assert!(reach.is_unreachable("old"));
+        assert!(!reach.is_unreachable("new"));
+        assert!(!reach.is_unreachable("unknown"));
+        assert_eq!(
+            reach.reachability("new", "tq"),
+            &[TaskReachability::NewWorkflows]
+        );
+        assert!(reach.reachability("new", "other").is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use temporal_sdk_core_protos::{
+    coresdk::{FromJsonPayloadExt, PayloadDeserializeErr},
+    temporal::api::{common::v1::Payload, workflowservice::v1::CountWorkflowExecutionsResponse},
+};
+
+/// How many workflow executions match a visibility query. See
+/// [crate::WorkflowClientTrait::count_workflow_executions].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowExecutionCount {
+    /// Approximately how many executions match the query. If the query groups by a field, this is
+    /// the sum of the counts of [Self::groups], which may not cover every matching execution.
+    pub count: i64,
+    /// If the query groups by a field, as in `GROUP BY ExecutionStatus`, the approximate count of
+    /// each group. May be incomplete.
+    pub groups: Vec<WorkflowExecutionCountGroup>,
+}
+
+/// The executions matching a grouping visibility query which share values of the grouped by
+/// fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowExecutionCountGroup {
+    /// The values of the grouped by fields, in the order they were grouped by
+    pub values: Vec<Payload>,
+    /// Approximately how many executions are in the group
+    pub count: i64,
+}
+
+impl WorkflowExecutionCountGroup {
+    /// Decodes the values of the grouped by fields, which the server JSON encodes. For example
+    /// `values_as::<String>()` gives names like `"Running"` when grouping by `ExecutionStatus`.
+    pub fn values_as<T: FromJsonPayloadExt>(&self) -> Result<Vec<T>, PayloadDeserializeErr> {
+        self.values.iter().map(T::from_json_payload).collect()
+    }
+}
+
+impl From<CountWorkflowExecutionsResponse> for WorkflowExecutionCount {
+    fn from(resp: CountWorkflowExecutionsResponse) -> Self {
+        Self {
+            count: resp.count,
+            groups: resp
+                .groups
+                .into_iter()
+                .map(|g| WorkflowExecutionCountGroup {
+                    values: g.group_values,
+                    count: g.count,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::{
+        coresdk::AsJsonPayloadExt,
+        temporal::api::workflowservice::v1::count_workflow_executions_response::AggregationGroup,
+    };
+
+    #[test]
+    fn decodes_group_values() {
+        let group = |status: &str, count| AggregationGroup {
+            group_values: vec![status.as_json_payload().unwrap()],
+            count,
+        };
+        let count = WorkflowExecutionCount::from(CountWorkflowExecutionsResponse {
+            count: 5,
+            groups: vec![group("Running", 3), group("Completed", 2)],
+        });
+        assert_eq!(count.count, 5);
+        let statuses: Vec<_> = count
+            .groups
+            .iter()
+            .map(|g| (g.values_as::<String>().unwrap(), g.count))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (vec!["Running".to_string()], 3),
+                (vec!["Completed".to_string()], 2)
+            ]
+        );
+    }
+}
@@ -1,19 +1,21 @@
 use crate::{InterceptedMetricsSvc, RawClientLike};
 use anyhow::{anyhow, bail};
-use std::marker::PhantomData;
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use std::{marker::PhantomData, time::Duration};
 use temporal_sdk_core_protos::{
     coresdk::FromPayloadsExt,
     temporal::api::{
         common::v1::{Payload, WorkflowExecution},
-        enums::v1::HistoryEventFilterType,
+        enums::v1::{HistoryEventFilterType, RetryState},
         failure::v1::Failure,
         history::v1::history_event::Attributes,
         workflowservice::v1::GetWorkflowExecutionHistoryRequest,
     },
 };
+use tonic::Code;
 
 /// Enumerates terminal states for a particular workflow execution
-// TODO: Add non-proto failure types, flesh out details, etc.
+// TODO: Add non-proto failure types, etc.
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum WorkflowExecutionResult<T> {
@@ -24,23 +26,55 @@ pub enum WorkflowExecutionResult<T> {
     /// The workflow was cancelled
     Cancelled(Vec<Payload>),
     /// The workflow was terminated
-    Terminated(Vec<Payload>),
+    Terminated {
+        /// Why the workflow was terminated
+        reason: String,
+        /// Details recorded with the termination
+        details: Vec<Payload>,
+    },
     /// The workflow timed out
-    TimedOut,
+    TimedOut {
+        /// Whether the workflow's retry policy, if it had one, gave up on retrying it
+        retry_state: RetryState,
+    },
     /// The workflow continued as new
     ContinuedAsNew,
 }
 
+/// What happened when starting a workflow, see [crate::WfClientExt::start_workflow_or_attach] and
+/// [crate::WfClientExt::signal_with_start_workflow]
+pub enum WorkflowStartOutcome<CT> {
+    /// A new run was started
+    Started(UntypedWorkflowHandle<CT>),
+    /// A run with the same workflow id was already running, and nothing was started
+    AttachedToRunning(UntypedWorkflowHandle<CT>),
+}
+
+impl<CT> WorkflowStartOutcome<CT> {
+    /// The handle to the started or already running run
+    pub fn into_handle(self) -> UntypedWorkflowHandle<CT> {
+        match self {
+            WorkflowStartOutcome::Started(h) | WorkflowStartOutcome::AttachedToRunning(h) => h,
+        }
+    }
+}
+
 /// Options for fetching workflow results
 #[derive(Debug, Clone, Copy)]
 pub struct GetWorkflowResultOpts {
     /// If true (the default), follows to the next workflow run in the execution chain while
     /// retrieving results.
     pub follow_runs: bool,
+    /// How many long polls for the result in a row may hit their deadline, as they can over a slow
+    /// link, before the error is returned. Polls are retried with exponential backoff.
+    pub max_deadline_exceeded_retries: usize,
 }
 impl Default for GetWorkflowResultOpts {
     fn default() -> Self {
-        Self { follow_runs: true }
+        Self {
+            follow_runs: true,
+            max_deadline_exceeded_retries: 10,
+        }
     }
 }
 
@@ -91,18 +125,23 @@ where
         }
     }
 
+    /// The workflow execution the handle refers to
     pub fn info(&self) -> &WorkflowExecutionInfo {
         &self.info
     }
 
+    /// Wait for the workflow to close and return its result. Long polls history for the close
+    /// event, and if `opts.follow_runs` is set follows the chain of runs across continue-as-new,
+    /// retries, and cron to the result of the last one.
     pub async fn get_workflow_result(
         &self,
         opts: GetWorkflowResultOpts,
     ) -> Result<WorkflowExecutionResult<RT>, anyhow::Error> {
         let mut next_page_tok = vec![];
         let mut run_id = self.info.run_id.clone().unwrap_or_default();
+        let mut retries = DeadlineRetries::new(opts.max_deadline_exceeded_retries);
         loop {
-            let server_res = self
+            let server_res = match self
                 .client
                 .clone()
                 .workflow_client_mut()
@@ -118,8 +157,23 @@ where
                     next_page_token: next_page_tok.clone(),
                     ..Default::default()
                 })
-                .await?
-                .into_inner();
+                .await
+            {
+                Ok(r) => {
+                    retries.reset();
+                    r.into_inner()
+                }
+                // The server answers long polls before the call's deadline when it can, but a
+                // slow link may still hit it. There's nothing to do but poll again.
+                Err(e) if e.code() == Code::DeadlineExceeded => match retries.next_delay() {
+                    Some(delay) => {
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                    None => return Err(e.into()),
+                },
+                Err(e) => return Err(e.into()),
+            };
 
             let mut history = server_res
                 .history
@@ -133,58 +187,186 @@ where
             next_page_tok = vec![];
 
             let event_attrs = history.events.pop().and_then(|ev| ev.attributes);
+            match close_outcome(event_attrs, opts.follow_runs)? {
+                CloseOutcome::Closed(res) => return Ok(res),
+                CloseOutcome::NextRun(next) => run_id = next,
+            }
+        }
+    }
+}
 
-            macro_rules! follow {
-                ($attrs:ident) => {
-                    if opts.follow_runs && $attrs.new_execution_run_id != "" {
-                        run_id = $attrs.new_execution_run_id;
-                        continue;
-                    }
-                };
+/// Backs off between long polls which hit their deadline, giving up after too many in a row
+struct DeadlineRetries {
+    backoff: ExponentialBackoff,
+    max: usize,
+    remaining: usize,
+}
+
+impl DeadlineRetries {
+    fn new(max: usize) -> Self {
+        Self {
+            backoff: ExponentialBackoff {
+                current_interval: Duration::from_millis(100),
+                initial_interval: Duration::from_millis(100),
+                max_interval: Duration::from_secs(5),
+                max_elapsed_time: None,
+                ..Default::default()
+            },
+            max,
+            remaining: max,
+        }
+    }
+
+    /// How long to wait before polling again, or None if there are no retries left
+    fn next_delay(&mut self) -> Option<Duration> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        self.backoff.next_backoff()
+    }
+
+    /// Called when a poll succeeds, so only polls failing in a row count against the limit
+    fn reset(&mut self) {
+        self.backoff.reset();
+        self.remaining = self.max;
+    }
+}
+
+/// What to do after seeing a run's close event
+#[allow(clippy::large_enum_variant)]
+enum CloseOutcome<RT> {
+    /// The chain of runs is done, with this result
+    Closed(WorkflowExecutionResult<RT>),
+    /// Follow the chain to the run with this id
+    NextRun(String),
+}
+
+fn close_outcome<RT: FromPayloadsExt>(
+    event_attrs: Option<Attributes>,
+    follow_runs: bool,
+) -> Result<CloseOutcome<RT>, anyhow::Error> {
+    macro_rules! follow {
+        ($attrs:ident) => {
+            if follow_runs && $attrs.new_execution_run_id != "" {
+                return Ok(CloseOutcome::NextRun($attrs.new_execution_run_id));
             }
+        };
+    }
 
-            break match event_attrs {
-                Some(Attributes::WorkflowExecutionCompletedEventAttributes(attrs)) => {
-                    follow!(attrs);
-                    Ok(WorkflowExecutionResult::Succeeded(RT::from_payloads(
-                        attrs.result,
-                    )))
-                }
-                Some(Attributes::WorkflowExecutionFailedEventAttributes(attrs)) => {
-                    follow!(attrs);
-                    Ok(WorkflowExecutionResult::Failed(
-                        attrs.failure.unwrap_or_default(),
-                    ))
-                }
-                Some(Attributes::WorkflowExecutionCanceledEventAttributes(attrs)) => Ok(
-                    WorkflowExecutionResult::Cancelled(Vec::from_payloads(attrs.details)),
-                ),
-                Some(Attributes::WorkflowExecutionTimedOutEventAttributes(attrs)) => {
-                    follow!(attrs);
-                    Ok(WorkflowExecutionResult::TimedOut)
-                }
-                Some(Attributes::WorkflowExecutionTerminatedEventAttributes(attrs)) => Ok(
-                    WorkflowExecutionResult::Terminated(Vec::from_payloads(attrs.details)),
-                ),
-                Some(Attributes::WorkflowExecutionContinuedAsNewEventAttributes(attrs)) => {
-                    if opts.follow_runs {
-                        if !attrs.new_execution_run_id.is_empty() {
-                            run_id = attrs.new_execution_run_id;
-                            continue;
-                        } else {
-                            bail!("New execution run id was empty in continue as new event!");
-                        }
-                    } else {
-                        Ok(WorkflowExecutionResult::ContinuedAsNew)
-                    }
-                }
-                o => Err(anyhow!(
-                    "Server returned an event that didn't match the CloseEvent filter. \
-                     This is either a server bug or a new event the SDK does not understand. \
-                     Event details: {:?}",
-                    o
-                )),
-            };
+    let res = match event_attrs {
+        Some(Attributes::WorkflowExecutionCompletedEventAttributes(attrs)) => {
+            follow!(attrs);
+            WorkflowExecutionResult::Succeeded(RT::from_payloads(attrs.result))
+        }
+        Some(Attributes::WorkflowExecutionFailedEventAttributes(attrs)) => {
+            follow!(attrs);
+            WorkflowExecutionResult::Failed(attrs.failure.unwrap_or_default())
+        }
+        Some(Attributes::WorkflowExecutionCanceledEventAttributes(attrs)) => {
+            WorkflowExecutionResult::Cancelled(Vec::from_payloads(attrs.details))
         }
+        Some(Attributes::WorkflowExecutionTimedOutEventAttributes(attrs)) => {
+            follow!(attrs);
+            WorkflowExecutionResult::TimedOut {
+                retry_state: attrs.retry_state(),
+            }
+        }
+        Some(Attributes::WorkflowExecutionTerminatedEventAttributes(attrs)) => {
+            WorkflowExecutionResult::Terminated {
+                reason: attrs.reason,
+                details: Vec::from_payloads(attrs.details),
+            }
+        }
+        Some(Attributes::WorkflowExecutionContinuedAsNewEventAttributes(attrs)) => {
+            if !follow_runs {
+                WorkflowExecutionResult::ContinuedAsNew
+            } else if !attrs.new_execution_run_id.is_empty() {
+                return Ok(CloseOutcome::NextRun(attrs.new_execution_run_id));
+            } else {
+                bail!("New execution run id was empty in continue as new event!");
+            }
+        }
+        o => bail!(
+            "Server returned an event that didn't match the CloseEvent filter. \
+             This is either a server bug or a new event the SDK does not understand. \
+             Event details: {:?}",
+            o
+        ),
+    };
+    Ok(CloseOutcome::Closed(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::temporal::api::history::v1::{
+        WorkflowExecutionCompletedEventAttributes, WorkflowExecutionContinuedAsNewEventAttributes,
+        WorkflowExecutionTerminatedEventAttributes, WorkflowExecutionTimedOutEventAttributes,
+    };
+
+    #[test]
+    fn follows_runs_only_when_asked() {
+        let can = || {
+            Some(Attributes::WorkflowExecutionContinuedAsNewEventAttributes(
+                WorkflowExecutionContinuedAsNewEventAttributes {
+                    new_execution_run_id: "next".to_string(),
+                    ..Default::default()
+                },
+            ))
+        };
+        assert!(matches!(
+            close_outcome::<Vec<Payload>>(can(), true).unwrap(),
+            CloseOutcome::NextRun(r) if r == "next"
+        ));
+        assert!(matches!(
+            close_outcome::<Vec<Payload>>(can(), false).unwrap(),
+            CloseOutcome::Closed(WorkflowExecutionResult::ContinuedAsNew)
+        ));
+        // Retried runs are followed too
+        let timed_out = Some(Attributes::WorkflowExecutionTimedOutEventAttributes(
+            WorkflowExecutionTimedOutEventAttributes {
+                new_execution_run_id: "retry".to_string(),
+                ..Default::default()
+            },
+        ));
+        assert!(matches!(
+            close_outcome::<Vec<Payload>>(timed_out, true).unwrap(),
+            CloseOutcome::NextRun(r) if r == "retry"
+        ));
+        let completed = Some(Attributes::WorkflowExecutionCompletedEventAttributes(
+            WorkflowExecutionCompletedEventAttributes::default(),
+        ));
+        assert!(matches!(
+            close_outcome::<Vec<Payload>>(completed, true).unwrap(),
+            CloseOutcome::Closed(WorkflowExecutionResult::Succeeded(p)) if p.is_empty()
+        ));
+    }
+
+    #[test]
+    fn terminations_carry_their_reason() {
+        let terminated = Some(Attributes::WorkflowExecutionTerminatedEventAttributes(
+            WorkflowExecutionTerminatedEventAttributes {
+                reason: "cleanup".to_string(),
+                ..Default::default()
+            },
+        ));
+        assert!(matches!(
+            close_outcome::<Vec<Payload>>(terminated, true).unwrap(),
+            CloseOutcome::Closed(WorkflowExecutionResult::Terminated { reason, .. })
+                if reason == "cleanup"
+        ));
+        assert!(close_outcome::<Vec<Payload>>(None, true).is_err());
+    }
+
+    #[test]
+    fn deadline_retries_back_off_and_run_out() {
+        let mut retries = DeadlineRetries::new(3);
+        let first = retries.next_delay().unwrap();
+        assert!(first <= Duration::from_millis(150));
+        retries.next_delay().unwrap();
+        retries.next_delay().unwrap();
+        assert_eq!(retries.next_delay(), None);
+        // A successful poll in between starts the count over
+        retries.reset();
+        assert!(retries.next_delay().is_some());
+        assert_eq!(DeadlineRetries::new(0).next_delay(), None);
     }
 }
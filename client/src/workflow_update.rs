@@ -0,0 +1,431 @@
+use crate::{Result, WorkflowClientTrait, WorkflowOptions};
+use std::time::Duration;
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::{Header, Payload, Payloads, WorkflowExecution},
+    enums::v1::{UpdateWorkflowExecutionLifecycleStage, WorkflowIdConflictPolicy},
+    failure::v1::Failure,
+    update::v1::{outcome, Outcome, UpdateRef},
+    workflowservice::v1::{
+        execute_multi_operation_response::{response, Response},
+        UpdateWorkflowExecutionResponse,
+    },
+};
+use tonic::{Code, Status};
+use uuid::Uuid;
+
+/// An update to send to a workflow. See [execute_workflow_update].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkflowUpdate {
+    /// Id of the workflow to update
+    pub workflow_id: String,
+    /// Run of the workflow to update. If empty, the latest run is updated.
+    pub run_id: String,
+    /// Name of the update handler to invoke
+    pub name: String,
+    /// Arguments to the handler
+    pub args: Option<Payloads>,
+    /// Headers passed to the handler
+    pub header: Option<Header>,
+    /// Id of the update, unique within the workflow. Retries of the update reuse it, so the
+    /// update is delivered at most once.
+    pub update_id: String,
+}
+
+impl WorkflowUpdate {
+    /// An update of the latest run of a workflow, with a random update id
+    pub fn new(workflow_id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            workflow_id: workflow_id.into(),
+            run_id: String::new(),
+            name: name.into(),
+            args: None,
+            header: None,
+            update_id: Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub(crate) fn execution(&self) -> WorkflowExecution {
+        WorkflowExecution {
+            workflow_id: self.workflow_id.clone(),
+            run_id: self.run_id.clone(),
+        }
+    }
+}
+
+/// How far through its lifecycle [execute_workflow_update] waits for an update to get
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateWaitStage {
+    /// Until the workflow has accepted the update, after validating it
+    Accepted,
+    /// Until the update has completed, or been rejected
+    Completed,
+}
+
+impl From<UpdateWaitStage> for UpdateWorkflowExecutionLifecycleStage {
+    fn from(s: UpdateWaitStage) -> Self {
+        match s {
+            UpdateWaitStage::Accepted => UpdateWorkflowExecutionLifecycleStage::Accepted,
+            UpdateWaitStage::Completed => UpdateWorkflowExecutionLifecycleStage::Completed,
+        }
+    }
+}
+
+/// The workflow to start, unless it's already running, when sending an update with
+/// [execute_update_with_start]
+#[derive(Clone, Debug)]
+pub struct UpdateWithStartWorkflow {
+    /// Input to the workflow
+    pub input: Vec<Payload>,
+    /// Task queue to start the workflow on
+    pub task_queue: String,
+    /// Type of the workflow
+    pub workflow_type: String,
+    /// Id of the start request, generated if unset
+    pub request_id: Option<String>,
+    /// Options to start the workflow with. `id_conflict_policy` must be set, to say whether a
+    /// running workflow is updated or the call fails. Cron schedules, start delays, and eager
+    /// start can't be used when starting with an update.
+    pub options: WorkflowOptions,
+}
+
+impl UpdateWithStartWorkflow {
+    pub(crate) fn validate(&self) -> Result<()> {
+        let invalid = if self.options.id_conflict_policy == WorkflowIdConflictPolicy::Unspecified {
+            "a workflow id conflict policy must be set"
+        } else if self.options.cron_schedule.is_some() {
+            "cron schedules can't be used"
+        } else if self.options.start_delay.is_some() {
+            "start delays can't be used"
+        } else if self.options.enable_eager_workflow_start {
+            "eager workflow start can't be used"
+        } else {
+            return Ok(());
+        };
+        Err(Status::invalid_argument(format!(
+            "Invalid update-with-start: {invalid}"
+        )))
+    }
+}
+
+/// Where an update got to, as returned by [execute_workflow_update]
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkflowUpdateOutcome {
+    /// The workflow accepted the update, which hasn't completed yet. Its outcome can be waited
+    /// for by polling with the reference.
+    Accepted(UpdateRef),
+    /// The update completed successfully, with the handler's result
+    Completed(Option<Payloads>),
+    /// The workflow rejected the update, or its handler failed
+    Failed(Failure),
+}
+
+/// What happened to the workflow and the update sent by [execute_update_with_start]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateWithStartOutcome {
+    /// The run which was updated
+    pub run_id: String,
+    /// True if the run was started by the call, false if it was already running
+    pub started: bool,
+    /// Where the update got to
+    pub update: WorkflowUpdateOutcome,
+}
+
+/// Sends an update to a workflow and waits for it to reach the given stage, polling for the
+/// update's progress whenever the server returns before it got there. Sending is retried with the
+/// same update id, so the update is delivered at most once. If `timeout` is set and the update
+/// hasn't reached the stage by then, fails with `DeadlineExceeded`.
+pub async fn execute_workflow_update(
+    client: &impl WorkflowClientTrait,
+    update: WorkflowUpdate,
+    wait_for: UpdateWaitStage,
+    timeout: Option<Duration>,
+) -> Result<WorkflowUpdateOutcome> {
+    with_timeout(timeout, async {
+        let resp = client.start_workflow_update(update, wait_for).await?;
+        wait_for_stage(client, resp, wait_for).await
+    })
+    .await
+}
+
+/// Like [execute_workflow_update], but starts the workflow first if it isn't running, atomically
+/// with sending the update. If starting or updating fails, neither happens, and the error is the
+/// one the failed operation would have returned on its own. Whether a running workflow is updated
+/// depends on the start's `id_conflict_policy`.
+pub async fn execute_update_with_start(
+    client: &impl WorkflowClientTrait,
+    start: UpdateWithStartWorkflow,
+    update: WorkflowUpdate,
+    wait_for: UpdateWaitStage,
+    timeout: Option<Duration>,
+) -> Result<UpdateWithStartOutcome> {
+    with_timeout(timeout, async {
+        let resp = client
+            .start_update_with_start(start, update, wait_for)
+            .await?;
+        let (mut start_resp, mut update_resp) = (None, None);
+        for r in resp.responses {
+            match r {
+                Response {
+                    response: Some(response::Response::StartWorkflow(s)),
+                } => start_resp = Some(s),
+                Response {
+                    response: Some(response::Response::UpdateWorkflow(u)),
+                } => update_resp = Some(u),
+                _ => {}
+            }
+        }
+        let (Some(start_resp), Some(update_resp)) = (start_resp, update_resp) else {
+            return Err(Status::internal(
+                "Update-with-start response was missing the start or update response",
+            ));
+        };
+        Ok(UpdateWithStartOutcome {
+            run_id: start_resp.run_id,
+            started: start_resp.started,
+            update: wait_for_stage(client, update_resp, wait_for).await?,
+        })
+    })
+    .await
+}
+
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    fut: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(t) => tokio::time::timeout(t, fut).await.map_err(|_| {
+            Status::deadline_exceeded("Update didn't reach the awaited stage in time")
+        })?,
+        None => fut.await,
+    }
+}
+
+/// Polls for the progress of a sent update until it reaches the awaited stage
+async fn wait_for_stage(
+    client: &impl WorkflowClientTrait,
+    resp: UpdateWorkflowExecutionResponse,
+    wait_for: UpdateWaitStage,
+) -> Result<WorkflowUpdateOutcome> {
+    let mut stage = resp.stage();
+    let update_ref = resp
+        .update_ref
+        .ok_or_else(|| Status::internal("Update response was missing its reference"))?;
+    let mut outcome = resp.outcome;
+    loop {
+        if let Some(o) =
+            reached(wait_for, stage, outcome.take(), &update_ref).map_err(Status::internal)?
+        {
+            return Ok(o);
+        }
+        match client
+            .poll_workflow_execution_update(update_ref.clone(), wait_for)
+            .await
+        {
+            Ok(resp) => {
+                stage = resp.stage();
+                outcome = resp.outcome;
+            }
+            // The server answers before the call's deadline when it can, but a slow link may
+            // still hit it. There's nothing to do but poll again.
+            Err(e) if e.code() == Code::DeadlineExceeded => {
+                stage = UpdateWorkflowExecutionLifecycleStage::Unspecified;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The outcome of the update if it has reached the awaited stage
+fn reached(
+    wait_for: UpdateWaitStage,
+    stage: UpdateWorkflowExecutionLifecycleStage,
+    outcome: Option<Outcome>,
+    update_ref: &UpdateRef,
+) -> Result<Option<WorkflowUpdateOutcome>, &'static str> {
+    if let Some(o) = outcome {
+        return match o.value {
+            Some(outcome::Value::Success(p)) => Ok(Some(WorkflowUpdateOutcome::Completed(Some(p)))),
+            Some(outcome::Value::Failure(f)) => Ok(Some(WorkflowUpdateOutcome::Failed(f))),
+            None => Ok(Some(WorkflowUpdateOutcome::Completed(None))),
+        };
+    }
+    Ok(match (wait_for, stage) {
+        (_, UpdateWorkflowExecutionLifecycleStage::Completed) => {
+            return Err("Update completed, but the server didn't return its outcome")
+        }
+        (UpdateWaitStage::Accepted, UpdateWorkflowExecutionLifecycleStage::Accepted) => {
+            Some(WorkflowUpdateOutcome::Accepted(update_ref.clone()))
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockWorkflowClientTrait;
+    use temporal_sdk_core_protos::temporal::api::workflowservice::v1::{
+        ExecuteMultiOperationResponse, PollWorkflowExecutionUpdateResponse,
+        StartWorkflowExecutionResponse,
+    };
+
+    #[tokio::test]
+    async fn polls_until_completed() {
+        let mut client = MockWorkflowClientTrait::new();
+        let update = WorkflowUpdate::new("wf", "set_value");
+        let update_ref = UpdateRef {
+            workflow_execution: Some(update.execution()),
+            update_id: update.update_id.clone(),
+        };
+        let r = update_ref.clone();
+        client
+            .expect_start_workflow_update()
+            .times(1)
+            .returning(move |_, _| {
+                Ok(UpdateWorkflowExecutionResponse {
+                    update_ref: Some(r.clone()),
+                    outcome: None,
+                    stage: UpdateWorkflowExecutionLifecycleStage::Accepted as i32,
+                })
+            });
+        let mut polls = 0;
+        client
+            .expect_poll_workflow_execution_update()
+            .times(2)
+            .returning(move |r, _| {
+                polls += 1;
+                if polls == 1 {
+                    return Err(Status::deadline_exceeded("slow"));
+                }
+                Ok(PollWorkflowExecutionUpdateResponse {
+                    outcome: Some(Outcome {
+                        value: Some(outcome::Value::Success(Payloads::default())),
+                    }),
+                    stage: UpdateWorkflowExecutionLifecycleStage::Completed as i32,
+                    update_ref: Some(r),
+                })
+            });
+        let outcome = execute_workflow_update(&client, update, UpdateWaitStage::Completed, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            WorkflowUpdateOutcome::Completed(Some(Payloads::default()))
+        );
+    }
+
+    #[tokio::test]
+    async fn update_with_start_reports_start_and_polls_update() {
+        let mut client = MockWorkflowClientTrait::new();
+        let update = WorkflowUpdate::new("wf", "set_value");
+        let update_ref = UpdateRef {
+            workflow_execution: Some(update.execution()),
+            update_id: update.update_id.clone(),
+        };
+        let r = update_ref.clone();
+        client
+            .expect_start_update_with_start()
+            .times(1)
+            .returning(move |_, _, _| {
+                Ok(ExecuteMultiOperationResponse {
+                    responses: vec![
+                        Response {
+                            response: Some(response::Response::StartWorkflow(
+                                StartWorkflowExecutionResponse {
+                                    run_id: "run".to_string(),
+                                    started: true,
+                                    ..Default::default()
+                                },
+                            )),
+                        },
+                        Response {
+                            response: Some(response::Response::UpdateWorkflow(
+                                UpdateWorkflowExecutionResponse {
+                                    update_ref: Some(r.clone()),
+                                    outcome: None,
+                                    stage: UpdateWorkflowExecutionLifecycleStage::Accepted as i32,
+                                },
+                            )),
+                        },
+                    ],
+                })
+            });
+        client
+            .expect_poll_workflow_execution_update()
+            .times(1)
+            .returning(move |r, _| {
+                Ok(PollWorkflowExecutionUpdateResponse {
+                    outcome: Some(Outcome {
+                        value: Some(outcome::Value::Success(Payloads::default())),
+                    }),
+                    stage: UpdateWorkflowExecutionLifecycleStage::Completed as i32,
+                    update_ref: Some(r),
+                })
+            });
+        let start = UpdateWithStartWorkflow {
+            input: vec![],
+            task_queue: "tq".to_string(),
+            workflow_type: "wf_type".to_string(),
+            request_id: None,
+            options: WorkflowOptions {
+                id_conflict_policy: WorkflowIdConflictPolicy::UseExisting,
+                ..Default::default()
+            },
+        };
+        let outcome =
+            execute_update_with_start(&client, start, update, UpdateWaitStage::Completed, None)
+                .await
+                .unwrap();
+        assert_eq!(
+            outcome,
+            UpdateWithStartOutcome {
+                run_id: "run".to_string(),
+                started: true,
+                update: WorkflowUpdateOutcome::Completed(Some(Payloads::default())),
+            }
+        );
+    }
+
+    #[test]
+    fn update_with_start_needs_a_conflict_policy() {
+        let mut start = UpdateWithStartWorkflow {
+            input: vec![],
+            task_queue: "tq".to_string(),
+            workflow_type: "wf_type".to_string(),
+            request_id: None,
+            options: Default::default(),
+        };
+        assert_eq!(start.validate().unwrap_err().code(), Code::InvalidArgument);
+        start.options.id_conflict_policy = WorkflowIdConflictPolicy::Fail;
+        start.validate().unwrap();
+        start.options.cron_schedule = Some("@hourly".to_string());
+        assert_eq!(start.validate().unwrap_err().code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn stops_at_acceptance_when_asked() {
+        let update_ref = UpdateRef::default();
+        let accepted = UpdateWorkflowExecutionLifecycleStage::Accepted;
+        assert_eq!(
+            reached(UpdateWaitStage::Accepted, accepted, None, &update_ref).unwrap(),
+            Some(WorkflowUpdateOutcome::Accepted(update_ref.clone()))
+        );
+        assert_eq!(
+            reached(UpdateWaitStage::Completed, accepted, None, &update_ref).unwrap(),
+            None
+        );
+        let rejected = Outcome {
+            value: Some(outcome::Value::Failure(Failure::default())),
+        };
+        assert_eq!(
+            reached(
+                UpdateWaitStage::Accepted,
+                UpdateWorkflowExecutionLifecycleStage::Completed,
+                Some(rejected),
+                &update_ref
+            )
+            .unwrap(),
+            Some(WorkflowUpdateOutcome::Failed(Failure::default()))
+        );
+    }
+}
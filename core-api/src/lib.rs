@@ -72,6 +72,11 @@ pub trait Worker: Send + Sync {
     /// immediately. Underlying validation errors are swallowed and logged, this has been agreed to
     /// be optimal behavior for the user as we don't want to break activity execution due to badly
     /// configured heartbeat options.
+    ///
+    /// Local activities may also call this with their task token to report progress. Their
+    /// heartbeats are never sent to the server. Instead core remembers the latest details, which
+    /// are recorded in the activity's marker, and uses them to restart the
+    /// [WorkerConfig::local_activity_stall_timeout] timer if one is configured.
     fn record_activity_heartbeat(&self, details: ActivityHeartbeat);
 
     /// Request that a workflow be evicted by its run id. This will generate a workflow activation
@@ -2,7 +2,7 @@ pub mod metrics;
 
 use crate::telemetry::metrics::CoreMeter;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     net::SocketAddr,
     sync::Arc,
@@ -45,6 +45,20 @@ pub struct TelemetryOptions {
     /// A prefix to be applied to all core-created metrics. Defaults to "temporal_".
     #[builder(default = "METRIC_PREFIX.to_string()")]
     pub metric_prefix: String,
+    /// Optionally rename core-created metrics, after `metric_prefix` is applied
+    #[builder(setter(into, strip_option), default)]
+    pub metric_name_mapper: Option<Arc<dyn MetricNameMapper>>,
+    /// If set, only the core-created metrics with these names are emitted. Names are given
+    /// without `metric_prefix`, ex: `workflow_completed`.
+    #[builder(setter(into, strip_option), default)]
+    pub metric_allowlist: Option<HashSet<String>>,
+    /// Core-created metrics with these names aren't emitted. Names are given without
+    /// `metric_prefix`, ex: `workflow_completed`.
+    #[builder(setter(into), default)]
+    pub metric_denylist: HashSet<String>,
+    /// Optionally export core's tracing spans to an OTel collector
+    #[builder(setter(into, strip_option), default)]
+    pub tracing: Option<OtelTracingOptions>,
 }
 
 /// Options for exporting to an OpenTelemetry Collector
@@ -58,22 +72,121 @@ pub struct OtelCollectorOptions {
     /// Optionally specify how frequently metrics should be exported. Defaults to 1 second.
     #[builder(default = "Duration::from_secs(1)")]
     pub metric_periodicity: Duration,
-    /// Specifies the aggregation temporality for metric export. Defaults to cumulative.
+    /// Specifies the aggregation temporality for metric export. Defaults to cumulative. Some
+    /// backends, like Datadog, require delta. With delta, each export covers the
+    /// `metric_periodicity` since the last one.
     #[builder(default = "MetricTemporality::Cumulative")]
     pub metric_temporality: MetricTemporality,
-    /// A map of tags to be applied to all metrics
+    /// A map of tags to be applied to all metrics. They are exported as resource attributes, and
+    /// may override the default `service.name`.
     #[builder(default)]
     pub global_tags: HashMap<String, String>,
     /// If set to true, use f64 seconds for durations instead of u64 milliseconds
     #[builder(default)]
     pub use_seconds_for_durations: bool,
+    /// The prefix core's metrics are created with. Must match [TelemetryOptions::metric_prefix]
+    /// for core's histograms to get their default buckets. Defaults to "temporal_".
+    #[builder(setter(into), default = "METRIC_PREFIX.to_string()")]
+    pub metric_prefix: String,
+    /// Overrides the bucket boundaries of histograms, by metric name. Names must match a
+    /// metric's full name, or its name after `metric_prefix`. Boundaries are in the unit the
+    /// histogram records, so seconds for durations if `use_seconds_for_durations` is set, and
+    /// must be finite and strictly increasing.
+    #[builder(default)]
+    pub histogram_bucket_overrides: HashMap<String, Vec<f64>>,
+    /// Which protocol the collector is spoken to with. Defaults to gRPC.
+    #[builder(default)]
+    pub protocol: OtlpProtocol,
+    /// A proxy to send exports through, ex: `http://proxy.internal:3128`. Only used with
+    /// [OtlpProtocol::Http]. If unset, the standard `HTTP(S)_PROXY` environment variables are
+    /// respected.
+    #[builder(default)]
+    pub http_proxy: Option<Url>,
+    /// TLS settings for connecting to the collector, for ex: to trust a private CA or to use mTLS.
+    /// `https` urls are verified against the system's trusted roots without this.
+    #[builder(default)]
+    pub tls: Option<OtlpTlsOptions>,
+}
+
+/// How metrics are sent to an OTel collector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC, usually served on port 4317
+    #[default]
+    Grpc,
+    /// OTLP as protobuf over HTTP, usually served on port 4318. The path for the kind of data
+    /// being exported, ex: `/v1/metrics`, is appended to the collector's url.
+    Http,
+}
+
+/// Options for exporting core's tracing spans (ex: poll loops, workflow task processing, RPC calls)
+/// to an OTel collector, for investigating performance
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct OtelTracingOptions {
+    /// The url of the OTel collector to export spans to
+    pub url: Url,
+    /// Optional set of HTTP headers to send to the Collector, e.g for authentication.
+    #[builder(default)]
+    pub headers: HashMap<String, String>,
+    /// Which protocol the collector is spoken to with. Defaults to gRPC.
+    #[builder(default)]
+    pub protocol: OtlpProtocol,
+    /// A proxy to send exports through. Only used with [OtlpProtocol::Http].
+    #[builder(default)]
+    pub http_proxy: Option<Url>,
+    /// TLS settings for connecting to the collector
+    #[builder(default)]
+    pub tls: Option<OtlpTlsOptions>,
+    /// An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html)
+    /// filter string picking which spans are exported. Defaults to core's at info level and above.
+    #[builder(setter(into), default = "DEFAULT_SPAN_FILTER.to_string()")]
+    pub filter: String,
+    /// The fraction of traces to export, from 0 to 1. Spans within a trace which is exported are
+    /// always exported too. Defaults to 1, which exports everything.
+    #[builder(default = "1.0")]
+    pub sample_ratio: f64,
+    /// Attributes attached to every span, as resource attributes. May override the default
+    /// `service.name`.
+    #[builder(default)]
+    pub global_tags: HashMap<String, String>,
+}
+
+const DEFAULT_SPAN_FILTER: &str = "temporal_sdk_core=info,temporal_client=info,temporal_sdk=info";
+
+/// TLS settings for an OTel collector connection
+#[derive(Debug, Clone, Default)]
+pub struct OtlpTlsOptions {
+    /// Root CA certificate the collector's certificate is issued by, encoded as PEM
+    pub server_root_ca_cert: Option<Vec<u8>>,
+    /// Identity to present to the collector, if it requires mTLS
+    pub client_identity: Option<OtlpClientIdentity>,
+}
+
+/// A certificate and private key used to authenticate to an OTel collector
+#[derive(Clone)]
+pub struct OtlpClientIdentity {
+    /// The certificate for this client, encoded as PEM
+    pub client_cert: Vec<u8>,
+    /// The private key for this client, encoded as PEM
+    pub client_private_key: Vec<u8>,
+}
+
+impl Debug for OtlpClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtlpClientIdentity").finish_non_exhaustive()
+    }
 }
 
 /// Options for exporting metrics to Prometheus
 #[derive(Debug, Clone, derive_builder::Builder)]
 pub struct PrometheusExporterOptions {
-    pub socket_addr: SocketAddr,
-    // A map of tags to be applied to all metrics
+    /// The address to serve the scrape endpoint on. Must be set, unless the endpoint is served
+    /// from a listener the caller has already bound, in which case it must not be.
+    #[builder(setter(strip_option), default)]
+    pub socket_addr: Option<SocketAddr>,
+    /// Labels to attach to every exported series, ex: the cluster or region a worker runs in.
+    /// Names must be valid prometheus label names, and must not be one which core's metrics are
+    /// already labeled with, like `namespace` or `task_queue`.
     #[builder(default)]
     pub global_tags: HashMap<String, String>,
     /// If set true, all counters will include a "_total" suffix
@@ -86,6 +199,16 @@ pub struct PrometheusExporterOptions {
     /// If set to true, use f64 seconds for durations instead of u64 milliseconds
     #[builder(default)]
     pub use_seconds_for_durations: bool,
+    /// The prefix core's metrics are created with. Must match [TelemetryOptions::metric_prefix]
+    /// for core's histograms to get their default buckets. Defaults to "temporal_".
+    #[builder(setter(into), default = "METRIC_PREFIX.to_string()")]
+    pub metric_prefix: String,
+    /// Overrides the bucket boundaries of histograms, by metric name. Names must match a
+    /// metric's full name, or its name after `metric_prefix`. Boundaries are in the unit the
+    /// histogram records, so seconds for durations if `use_seconds_for_durations` is set, and
+    /// must be finite and strictly increasing.
+    #[builder(default)]
+    pub histogram_bucket_overrides: HashMap<String, Vec<f64>>,
 }
 
 /// Control where logs go
@@ -99,11 +222,13 @@ pub enum Logger {
     /// Forward logs to Lang - collectable with `fetch_global_buffered_logs`.
     Forward {
         /// An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html) filter string.
+        /// May be replaced later with `TelemetryInstance::set_forwarded_log_filter`.
         filter: String,
     },
     // Push logs to Lang. Can used with temporal_sdk_core::telemetry::CoreLogBufferedConsumer to buffer.
     Push {
         /// An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html) filter string.
+        /// May be replaced later with `TelemetryInstance::set_forwarded_log_filter`.
         filter: String,
         /// Trait invoked on each log.
         consumer: Arc<dyn CoreLogConsumer>,
@@ -116,7 +241,8 @@ pub enum Logger {
 pub enum MetricTemporality {
     /// Successive data points repeat the starting timestamp
     Cumulative,
-    /// Successive data points advance the starting timestamp
+    /// Successive data points advance the starting timestamp. Up-down counters are still reported
+    /// cumulatively, since a delta of a non-monotonic sum means little on its own.
     Delta,
 }
 
@@ -137,12 +263,25 @@ pub struct CoreLog {
     pub timestamp: SystemTime,
     /// Message level
     pub level: Level,
-    /// Arbitrary k/v pairs (span k/vs are collapsed with event k/vs here). We could change this
-    /// to include them in `span_contexts` instead, but there's probably not much value for log
-    /// forwarding.
+    /// Arbitrary k/v pairs. Those of the enclosing spans are collapsed with the event's own here,
+    /// with the event's taking precedence, then those of inner spans over outer ones. [Self::spans]
+    /// keeps each span's apart.
     pub fields: HashMap<String, serde_json::Value>,
     /// A list of the outermost to the innermost span names
     pub span_contexts: Vec<String>,
+    /// The spans the log was emitted within, from the outermost to the innermost
+    pub spans: Vec<CoreLogSpan>,
+}
+
+/// A span which a [CoreLog] was emitted within
+#[derive(Debug, Clone)]
+pub struct CoreLogSpan {
+    /// Span name
+    pub name: String,
+    /// The module within core the span was created in
+    pub target: String,
+    /// The span's k/v pairs, as last recorded
+    pub fields: HashMap<String, serde_json::Value>,
 }
 
 impl CoreLog {
@@ -155,6 +294,15 @@ impl CoreLog {
     }
 }
 
+/// Renames core-created metrics, ex: to fit an organization's naming conventions.
+///
+/// Exporters only give histograms named `metric_prefix` plus core's name for them their default
+/// buckets, so renamed histograms may need their buckets set explicitly.
+pub trait MetricNameMapper: Send + Sync + Debug {
+    /// Return the name to emit the metric named `name` (including `metric_prefix`) with
+    fn map_name(&self, name: &str) -> String;
+}
+
 /// Consumer trait for use with push logger.
 pub trait CoreLogConsumer: Send + Sync + Debug {
     /// Invoked synchronously for every single log.
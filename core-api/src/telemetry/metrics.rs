@@ -219,7 +219,7 @@ pub enum MetricEvent<I: BufferInstrumentRef> {
         update: MetricUpdateVal,
     },
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MetricKind {
     Counter,
     Gauge,
@@ -1,8 +1,11 @@
 use crate::errors::WorkflowErrorType;
 use std::{
-    collections::{HashMap, HashSet},
-    time::Duration,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
+use temporal_sdk_core_protos::search_attributes::SearchAttributeSchema;
 
 const MAX_OUTSTANDING_WFT_DEFAULT: usize = 100;
 const MAX_CONCURRENT_WFT_POLLS_DEFAULT: usize = 5;
@@ -46,6 +49,13 @@ pub struct WorkerConfig {
     /// concurrently
     #[builder(default = "100")]
     pub max_outstanding_local_activities: usize,
+    /// Limits, per local activity type, the number of local activities of that type which may be
+    /// given to this worker concurrently. These apply on top of
+    /// [WorkerConfig::max_outstanding_local_activities], and a local activity waiting on its type's
+    /// limit does not occupy one of those slots, so one busy type cannot starve the others. Zero
+    /// values will cause building the options to fail.
+    #[builder(default)]
+    pub max_outstanding_local_activities_per_type: HashMap<String, usize>,
     /// Maximum number of concurrent poll workflow task requests we will perform at a time on this
     /// worker's task queue. See also [WorkerConfig::nonsticky_to_sticky_poll_ratio]. Must be at
     /// least 1.
@@ -67,7 +77,9 @@ pub struct WorkerConfig {
     #[builder(default = "false")]
     pub no_remote_activities: bool,
     /// How long a workflow task is allowed to sit on the sticky queue before it is timed out
-    /// and moved to the non-sticky queue where it may be picked up by any worker.
+    /// and moved to the non-sticky queue where it may be picked up by any worker. Each time this
+    /// happens the `sticky_queue_fallback` metric is incremented by whichever worker receives the
+    /// rescheduled task, which can be used to tune this value.
     #[builder(default = "Duration::from_secs(10)")]
     pub sticky_queue_schedule_to_start_timeout: Duration,
 
@@ -82,6 +94,13 @@ pub struct WorkerConfig {
     #[builder(default = "Duration::from_secs(30)")]
     pub default_heartbeat_throttle_interval: Duration,
 
+    /// If set, used as the interval for throttling activity heartbeats instead of one derived
+    /// from the activity's heartbeat timeout. Still capped at 80% of the heartbeat timeout, if
+    /// there is one, and at `max_heartbeat_throttle_interval`. Lang may override this for
+    /// individual activities via `ActivityHeartbeat.throttle_interval`.
+    #[builder(default)]
+    pub heartbeat_throttle_interval: Option<Duration>,
+
     /// Sets the maximum number of activities per second the task queue will dispatch, controlled
     /// server-side. Note that this only takes effect upon an activity poll request. If multiple
     /// workers on the same queue have different values set, they will thrash with the last poller
@@ -96,6 +115,39 @@ pub struct WorkerConfig {
     #[builder(default)]
     pub max_worker_activities_per_second: Option<f64>,
 
+    /// If set, the worker waits on this limiter before every activity poll, in addition to
+    /// respecting [WorkerConfig::max_worker_activities_per_second]. The limiter may be shared
+    /// between workers, or backed by an external coordinator, to enforce a limit across a fleet.
+    #[builder(default)]
+    pub activity_rate_limiter: Option<Arc<dyn ActivityRateLimiter>>,
+
+    /// Limits, per activity type, the number of activities per second this worker will dispatch to
+    /// lang. An activity received while its type is over the limit holds its slot until the limit
+    /// allows it to be dispatched, without delaying activities of other types. Negative, zero, or
+    /// NaN values will cause building the options to fail.
+    #[builder(default)]
+    pub activity_type_rate_limits: HashMap<String, f64>,
+
+    /// Activity types whose successful results this worker may memoize. If reporting the success
+    /// of such an activity to the server fails, the result is remembered, and should this worker
+    /// then receive a retry of the same activity (same workflow run, activity id, and input), it
+    /// reports the remembered result instead of delivering the task to lang. Only idempotent
+    /// activities should be opted in.
+    #[builder(default)]
+    pub memoized_activity_types: HashSet<String>,
+
+    /// The maximum number of results remembered for [WorkerConfig::memoized_activity_types]. Once
+    /// full, the least recently recorded results are forgotten first.
+    #[builder(default = "1000")]
+    pub max_memoized_activity_results: usize,
+
+    /// Maps activity types to the task queue activities of that type should be scheduled on. Only
+    /// applies to activities scheduled without a task queue, or on this worker's own queue, so
+    /// routing policy (ex: sending some types to a dedicated GPU queue) can live in one place
+    /// rather than in every workflow.
+    #[builder(default)]
+    pub activity_task_queue_routes: HashMap<String, String>,
+
     /// # UNDER DEVELOPMENT
     /// If set to true this worker will opt-in to the whole-worker versioning feature.
     /// `worker_build_id` will be used as the version.
@@ -123,12 +175,27 @@ pub struct WorkerConfig {
     #[builder(default)]
     pub graceful_shutdown_period: Option<Duration>,
 
+    /// If set, activities which are still running this long after core delivers them a cancel are
+    /// cancelled again, "hard". Core then releases their slot, reports them to the server, and
+    /// ignores their eventual completion. Otherwise, activities which ignore cancellation hold
+    /// their slot until they finish.
+    #[builder(default)]
+    pub activity_cancel_grace_period: Option<Duration>,
+
     /// The amount of time core will wait before timing out activities using its own local timers
     /// after one of them elapses. This is to avoid racing with server's own tracking of the
     /// timeout.
     #[builder(default = "Duration::from_secs(5)")]
     pub local_timeout_buffer_for_activities: Duration,
 
+    /// If a local activity is retrying and the backoff would exceed this value, lang is told to
+    /// schedule a timer and retry the activity after it fires, rather than core backing off within
+    /// the workflow task. Lower values hand off to timers sooner, higher values keep more retries
+    /// within a single workflow task. Used for local activities which don't set their own
+    /// `local_retry_threshold`.
+    #[builder(default = "Duration::from_secs(60)")]
+    pub default_local_activity_retry_threshold: Duration,
+
     /// Any error types listed here will cause any workflow being processed by this worker to fail,
     /// rather than simply failing the workflow task.
     #[builder(default)]
@@ -138,6 +205,76 @@ pub struct WorkerConfig {
     /// map key).
     #[builder(default)]
     pub workflow_types_to_failure_errors: HashMap<String, HashSet<WorkflowErrorType>>,
+
+    /// If set true, the worker starts in standby mode. A worker in standby keeps its client and
+    /// workflow cache, but does not poll for any tasks (nor accept eagerly started workflows)
+    /// until it is made active. Useful for active/passive deployments which want to fail over
+    /// without paying worker startup costs.
+    #[builder(default = "false")]
+    pub start_in_standby: bool,
+
+    /// Thresholds on the size of workflow task completions sent to the server. Exceeding an error
+    /// threshold fails the workflow with a non-retryable failure describing what was too large,
+    /// rather than letting the server reject the completion. All thresholds are disabled by
+    /// default.
+    #[builder(default)]
+    pub payload_size_limits: PayloadSizeLimits,
+
+    /// If set, an activity slot which a poller has held for at least this long while waiting on a
+    /// long poll may be taken over by a high priority (priority key below the default)
+    /// activity requesting eager execution. The poller keeps polling, and waits for a slot to
+    /// free up if it receives a task. Prevents idle polls from starving latency sensitive work.
+    ///
+    /// Only high priority eager activities preempt slots. Slots are not otherwise prioritized:
+    /// when a slot frees up it goes to whichever poller or eager request asks for it next, not to
+    /// the highest priority work waiting for one.
+    #[builder(default)]
+    pub slot_preemption_grace_period: Option<Duration>,
+
+    /// How long a workflow task may wait on outstanding local activities before core heartbeats
+    /// it (completing it and immediately requesting a new one). Regardless of this setting, the
+    /// heartbeat happens no later than 80% of the way through the workflow task timeout, which is
+    /// also the default.
+    #[builder(default)]
+    pub workflow_task_heartbeat_interval: Option<Duration>,
+
+    /// If set, a local activity which goes this long without reporting progress (by recording a
+    /// heartbeat with its task token) is considered stuck. It is timed out with a `Heartbeat`
+    /// timeout, cancelled, and retried according to its retry policy. The timer starts when each
+    /// attempt is dispatched and restarts with every progress report.
+    #[builder(default)]
+    pub local_activity_stall_timeout: Option<Duration>,
+
+    /// The flags lang may record in workflow task completions. If any are registered, core warns
+    /// about completions which use unregistered flags, and about histories which recorded flags
+    /// newer than this registry knows of. If none are, lang's flags aren't checked.
+    #[builder(default)]
+    pub lang_flags: LangFlagRegistry,
+
+    /// Adjustments core makes to the durations of timers workflows start, before sending them to
+    /// the server, so lang SDKs don't each need to
+    #[builder(default)]
+    pub timer_policy: TimerPolicy,
+
+    /// If set true, core answers workflow metadata queries (`__temporal_workflow_metadata`)
+    /// itself, with the metadata lang recorded using `SetWorkflowMetadata` commands. Otherwise
+    /// they're delivered to lang like any other query.
+    #[builder(default = "false")]
+    pub answer_workflow_metadata_queries: bool,
+
+    /// If set true, core answers stack trace queries (`__stack_trace`) itself, with the stacks
+    /// lang reported with its last activation completion, asking lang to report them with a
+    /// `ReportStackTrace` job if it hasn't since the workflow was last activated. Otherwise they're
+    /// delivered to lang like any other query.
+    #[builder(default = "false")]
+    pub answer_stack_trace_queries: bool,
+
+    /// The types of the search attributes registered with the namespace, such as those returned by
+    /// `get_search_attributes`. If set, core checks the search attributes of upserts and child
+    /// workflow starts against them, failing the workflow task with a description of every invalid
+    /// attribute rather than letting the server reject the completion.
+    #[builder(default)]
+    pub search_attribute_schema: Option<SearchAttributeSchema>,
 }
 
 impl WorkerConfig {
@@ -166,6 +303,157 @@ impl WorkerConfig {
     }
 }
 
+/// Size thresholds, in bytes, checked against workflow task completions and local activity results
+/// before they are recorded.
+/// Command sizes are their encoded size, which is dominated by any payloads they carry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayloadSizeLimits {
+    /// Log a warning when any one command is larger than this
+    pub command_warn_bytes: Option<usize>,
+    /// Fail the workflow when any one command is larger than this. Update results larger than
+    /// this fail just the update.
+    pub command_error_bytes: Option<usize>,
+    /// Log a warning when the entire completion is larger than this
+    pub completion_warn_bytes: Option<usize>,
+    /// Keep the entire completion within this. Answers to queries, then update results, are
+    /// failed (largest first) until it fits. The workflow is failed only if its commands alone
+    /// are larger than this.
+    pub completion_error_bytes: Option<usize>,
+    /// Fail any local activity whose successful result is larger than this, with a non-retryable
+    /// application failure of type `LocalActivityResultTooLarge`. Local activity results are
+    /// recorded in history as markers, so large results bloat history and can get the workflow
+    /// task completion rejected.
+    pub local_activity_result_error_bytes: Option<usize>,
+}
+
+/// How core adjusts timer durations. Timers are always rounded up to whole milliseconds, the
+/// precision the server keeps, so they never fire before the requested duration has elapsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimerPolicy {
+    /// The longest any timer may be. What happens to longer ones is set by
+    /// `exceeding_max_duration`.
+    pub max_duration: Option<Duration>,
+    /// What to do with timers longer than `max_duration`
+    pub exceeding_max_duration: ExceedingMaxTimerDuration,
+    /// If set, up to this much is added to each timer, so that timers many workflows start at the
+    /// same time don't all fire at once. The amount added is derived from the workflow's run id
+    /// and the timer's sequence number, so it is the same each time a timer is started. Jitter
+    /// never takes a timer past `max_duration`.
+    pub max_jitter: Option<Duration>,
+}
+
+/// What core does with timers longer than [TimerPolicy::max_duration]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExceedingMaxTimerDuration {
+    /// Shorten the timer to the maximum duration
+    #[default]
+    Clamp,
+    /// Fail the workflow task, with an error saying which timer was too long
+    FailWorkflowTask,
+}
+
+/// The flags a lang SDK records in workflow task completions (as `used_internal_flags`) when a
+/// workflow takes a code path which changes what it records in history, so that replays of that
+/// history know to take the same path. Core reports the flags a history has recorded in each
+/// activation's `available_internal_flags`, just as it does for its own flags.
+///
+/// Flags are numbered from 1 and numbers must never be reused once released, so the highest
+/// registered flag is the version of the registry. A history which recorded a flag above that
+/// version was written by a newer version of the SDK.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LangFlagRegistry {
+    flags: BTreeMap<u32, String>,
+}
+
+impl LangFlagRegistry {
+    /// Registers a flag, describing the change in behavior it marks
+    pub fn register(mut self, flag: u32, description: impl Into<String>) -> Self {
+        self.flags.insert(flag, description.into());
+        self
+    }
+
+    /// Returns true if no flags are registered
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// Returns true if the flag has been registered
+    pub fn contains(&self, flag: u32) -> bool {
+        self.flags.contains_key(&flag)
+    }
+
+    /// The description a flag was registered with
+    pub fn description(&self, flag: u32) -> Option<&str> {
+        self.flags.get(&flag).map(String::as_str)
+    }
+
+    /// The highest registered flag, or 0 if none are registered
+    pub fn version(&self) -> u32 {
+        self.flags.keys().next_back().copied().unwrap_or_default()
+    }
+}
+
+/// Caps on the number of task slots which may be in use at once across every worker initialized on
+/// the same runtime, enforced in addition to each worker's own maximums. Lets a process bound its
+/// overall resource usage without statically partitioning it between workers. A `None` limit
+/// leaves that kind of slot unconstrained beyond the per-worker maximum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SharedSlotLimits {
+    /// Maximum workflow tasks outstanding across all workers
+    pub max_outstanding_workflow_tasks: Option<usize>,
+    /// Maximum activities outstanding across all workers
+    pub max_outstanding_activities: Option<usize>,
+    /// Maximum local activities outstanding across all workers
+    pub max_outstanding_local_activities: Option<usize>,
+}
+
+/// Limits the rate at which workers receive activities. Implementations may coordinate with some
+/// external service (ex: Redis) so that a limit like "N calls per second to vendor X" holds across
+/// every worker using the limiter, not just within one process.
+#[async_trait::async_trait]
+pub trait ActivityRateLimiter: Send + Sync + Debug {
+    /// Resolves once the worker may poll for another activity. Each resolution permits the worker
+    /// to receive at most one activity, though the permit goes unused if the poll comes back empty.
+    async fn acquire(&self);
+}
+
+/// Which kinds of tasks a worker is currently polling for. Workers always start out
+/// [WorkerMode::Combined] (or workflow only, if created with `no_remote_activities`) and may be
+/// switched between modes at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkerMode {
+    /// Poll for both workflow and activity tasks
+    #[default]
+    Combined,
+    /// Poll only for workflow tasks. Local activities continue to run, since they are part of
+    /// workflow task processing.
+    WorkflowOnly,
+    /// Poll only for activity tasks. Cached workflows are retained, but will not make progress
+    /// until workflow polling resumes.
+    ActivityOnly,
+}
+
+/// An activity which lang handed off to be completed outside the worker (ex: by a human, through
+/// the client), by completing it with `WillCompleteAsync`. Core no longer holds a slot for or
+/// heartbeats such activities, but remembers them for diagnostic purposes until their
+/// start-to-close or schedule-to-close timeout elapses (or a day passes, if they have neither), or
+/// lang reports they were completed externally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingActivityHandoff {
+    /// The task token the activity must be completed with
+    pub task_token: Vec<u8>,
+    /// The activity's id
+    pub activity_id: String,
+    /// The activity's type
+    pub activity_type: String,
+    /// The id of the workflow which scheduled the activity
+    pub workflow_id: String,
+    /// The run id of the workflow which scheduled the activity
+    pub workflow_run_id: String,
+    /// When lang handed the activity off
+    pub handed_off_at: SystemTime,
+}
+
 impl WorkerConfigBuilder {
     fn validate(&self) -> Result<(), String> {
         if self.max_concurrent_wft_polls == Some(0) {
@@ -183,6 +471,23 @@ impl WorkerConfigBuilder {
                     .to_owned(),
             );
         }
+        if let Some(Some(ref x)) = self.workflow_task_heartbeat_interval {
+            if x.is_zero() {
+                return Err("`workflow_task_heartbeat_interval` must be nonzero".to_owned());
+            }
+        }
+        if let Some(Some(ref x)) = self.local_activity_stall_timeout {
+            if x.is_zero() {
+                return Err("`local_activity_stall_timeout` must be nonzero".to_owned());
+            }
+        }
+        if let Some(ref flags) = self.lang_flags {
+            if flags.contains(0) {
+                return Err(
+                    "`lang_flags` are numbered from 1, flag 0 can't be registered".to_owned(),
+                );
+            }
+        }
         if let Some(Some(ref x)) = self.max_worker_activities_per_second {
             if !x.is_normal() || x.is_sign_negative() {
                 return Err(
@@ -190,6 +495,29 @@ impl WorkerConfigBuilder {
                 );
             }
         }
+        if let Some(ref limits) = self.max_outstanding_local_activities_per_type {
+            if let Some((act_type, _)) = limits.iter().find(|(_, x)| **x == 0) {
+                return Err(format!(
+                    "`max_outstanding_local_activities_per_type` for activity type `{act_type}` \
+                     must be at least 1"
+                ));
+            }
+        }
+        if let Some(ref limits) = self.activity_type_rate_limits {
+            // The limiter spaces dispatches `1 / rate` seconds apart, which must be a nonzero
+            // duration that can be represented
+            if let Some((act_type, _)) = limits.iter().find(|(_, x)| {
+                !x.is_normal()
+                    || x.is_sign_negative()
+                    || !Duration::try_from_secs_f64(x.recip()).is_ok_and(|p| !p.is_zero())
+            }) {
+                return Err(format!(
+                    "`activity_type_rate_limits` for activity type `{act_type}` must be positive \
+                     and nonzero, and neither so small nor so large that the time between \
+                     dispatches can't be represented"
+                ));
+            }
+        }
         if matches!(self.max_concurrent_wft_polls, Some(1))
             && self.max_cached_workflows > Some(0)
             && self
@@ -4,21 +4,50 @@ pub mod take_cell;
 
 use crate::MetricsContext;
 use derive_more::DebugCustom;
+use parking_lot::Mutex;
 use std::{
     fmt::{Debug, Formatter},
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc,
+        Arc, Weak,
     },
+    time::{Duration, Instant},
 };
+use temporal_sdk_core_api::worker::SharedSlotLimits;
 use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore, TryAcquireError};
 use tokio_util::sync::CancellationToken;
 
+/// Semaphores shared by all workers initialized on the same runtime, which bound slot usage across
+/// all of them. See [SharedSlotLimits].
+#[derive(Clone, Default)]
+pub(crate) struct SharedSlots {
+    pub(crate) workflow_tasks: Option<Arc<Semaphore>>,
+    pub(crate) activities: Option<Arc<Semaphore>>,
+    pub(crate) local_activities: Option<Arc<Semaphore>>,
+}
+
+impl From<SharedSlotLimits> for SharedSlots {
+    fn from(l: SharedSlotLimits) -> Self {
+        let mk = |lim: Option<usize>| lim.map(|l| Arc::new(Semaphore::new(l)));
+        Self {
+            workflow_tasks: mk(l.max_outstanding_workflow_tasks),
+            activities: mk(l.max_outstanding_activities),
+            local_activities: mk(l.max_outstanding_local_activities),
+        }
+    }
+}
+
 /// Wraps a [Semaphore] with a function call that is fed the available permits any time a permit is
 /// acquired or restored through the provided methods
 #[derive(Clone)]
 pub(crate) struct MeteredSemaphore {
     sem: Arc<Semaphore>,
+    /// If set, a permit from this semaphore must also be acquired for each permit handed out. It
+    /// is shared with other workers' semaphores to enforce a limit across all of them.
+    shared: Option<Arc<Semaphore>>,
+    /// If set, permits claimed with [MeteredSemaphore::claim_preemptibly] may be taken over by
+    /// [MeteredSemaphore::try_preempt] once they have gone unused for the grace period.
+    preemption: Option<Arc<Preemption>>,
     /// The number of permit owners who have acquired a permit from the semaphore, but are not yet
     /// meaningfully using that permit. This is useful for giving a more semantically accurate count
     /// of used task slots, since we typically wait for a permit first before polling, but that slot
@@ -36,14 +65,64 @@ impl MeteredSemaphore {
     ) -> Self {
         Self {
             sem: Arc::new(Semaphore::new(inital_permits)),
+            shared: None,
+            preemption: None,
             unused_claimants: Arc::new(AtomicUsize::new(0)),
             metrics_ctx,
             record_fn,
         }
     }
 
+    /// Additionally require a permit from the provided semaphore for each permit handed out
+    pub fn with_shared(mut self, shared: Option<Arc<Semaphore>>) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Allow permits claimed preemptibly to be taken over after going unused for `grace`
+    pub fn with_preemption(mut self, grace: Option<Duration>) -> Self {
+        self.preemption = grace.map(|grace| {
+            Arc::new(Preemption {
+                grace,
+                claims: Default::default(),
+            })
+        });
+        self
+    }
+
     pub fn available_permits(&self) -> usize {
-        self.sem.available_permits()
+        available(&self.sem, &self.shared)
+    }
+
+    /// Registers an acquired, not yet used, permit as one which may be preempted if preemption is
+    /// enabled. The holder must call [PreemptibleClaim::into_permit] once it needs the permit, which
+    /// returns `None` if it was preempted in the meantime.
+    pub fn claim_preemptibly(&self, permit: OwnedMeteredSemPermit) -> PreemptibleClaim {
+        let claim = Arc::new(ClaimSlot {
+            since: Instant::now(),
+            permit: Mutex::new(Some(permit)),
+        });
+        if let Some(p) = self.preemption.as_ref() {
+            let mut claims = p.claims.lock();
+            claims.retain(|c| c.strong_count() > 0);
+            claims.push(Arc::downgrade(&claim));
+        }
+        PreemptibleClaim(claim)
+    }
+
+    /// Takes over the oldest preemptible claim which has gone unused for at least the grace period,
+    /// if there is one.
+    pub fn try_preempt(&self) -> Option<OwnedMeteredSemPermit> {
+        let p = self.preemption.as_ref()?;
+        let mut claims = p.claims.lock();
+        claims.retain(|c| c.strong_count() > 0);
+        let ix = claims.iter().position(|c| {
+            c.upgrade()
+                .is_some_and(|c| c.since.elapsed() >= p.grace && c.permit.lock().is_some())
+        })?;
+        let claim = claims.remove(ix).upgrade()?;
+        let permit = claim.permit.lock().take();
+        permit
     }
 
     #[cfg(test)]
@@ -53,19 +132,33 @@ impl MeteredSemaphore {
 
     pub async fn acquire_owned(&self) -> Result<OwnedMeteredSemPermit, AcquireError> {
         let res = self.sem.clone().acquire_owned().await?;
-        Ok(self.build_owned(res))
+        let shared = match self.shared.as_ref() {
+            Some(s) => Some(s.clone().acquire_owned().await?),
+            None => None,
+        };
+        Ok(self.build_owned(res, shared))
     }
 
     pub fn try_acquire_owned(&self) -> Result<OwnedMeteredSemPermit, TryAcquireError> {
         let res = self.sem.clone().try_acquire_owned()?;
-        Ok(self.build_owned(res))
+        let shared = self
+            .shared
+            .as_ref()
+            .map(|s| s.clone().try_acquire_owned())
+            .transpose()?;
+        Ok(self.build_owned(res, shared))
     }
 
-    fn build_owned(&self, res: OwnedSemaphorePermit) -> OwnedMeteredSemPermit {
+    fn build_owned(
+        &self,
+        res: OwnedSemaphorePermit,
+        shared: Option<OwnedSemaphorePermit>,
+    ) -> OwnedMeteredSemPermit {
         self.unused_claimants.fetch_add(1, Ordering::Release);
         self.record();
         OwnedMeteredSemPermit {
             inner: res,
+            _shared: shared,
             unused_claimants: Some(self.unused_claimants.clone()),
             record_fn: self.record_owned(),
         }
@@ -74,7 +167,7 @@ impl MeteredSemaphore {
     fn record(&self) {
         (self.record_fn)(
             &self.metrics_ctx,
-            self.sem.available_permits() + self.unused_claimants.load(Ordering::Acquire),
+            self.available_permits() + self.unused_claimants.load(Ordering::Acquire),
         );
     }
 
@@ -82,6 +175,7 @@ impl MeteredSemaphore {
         let rcf = self.record_fn;
         let mets = self.metrics_ctx.clone();
         let sem = self.sem.clone();
+        let shared = self.shared.clone();
         let uc = self.unused_claimants.clone();
         // When being called from the drop impl, the semaphore permit isn't actually dropped yet,
         // so account for that.
@@ -89,12 +183,41 @@ impl MeteredSemaphore {
             let extra = usize::from(add_one);
             rcf(
                 &mets,
-                sem.available_permits() + uc.load(Ordering::Acquire) + extra,
+                available(&sem, &shared) + uc.load(Ordering::Acquire) + extra,
             )
         })
     }
 }
 
+struct Preemption {
+    grace: Duration,
+    /// Outstanding claims, oldest first
+    claims: Mutex<Vec<Weak<ClaimSlot>>>,
+}
+
+struct ClaimSlot {
+    since: Instant,
+    permit: Mutex<Option<OwnedMeteredSemPermit>>,
+}
+
+/// A permit which is held, but not yet used, and may be preempted. See
+/// [MeteredSemaphore::claim_preemptibly].
+pub(crate) struct PreemptibleClaim(Arc<ClaimSlot>);
+
+impl PreemptibleClaim {
+    /// Return the permit, or `None` if it was preempted
+    pub(crate) fn into_permit(self) -> Option<OwnedMeteredSemPermit> {
+        self.0.permit.lock().take()
+    }
+}
+
+fn available(sem: &Semaphore, shared: &Option<Arc<Semaphore>>) -> usize {
+    let local = sem.available_permits();
+    shared
+        .as_ref()
+        .map_or(local, |s| local.min(s.available_permits()))
+}
+
 /// A version of [MeteredSemaphore] that can be closed and supports waiting for close to complete.
 /// Once closed, no permits will be handed out.
 /// Close completes when all permits have been returned.
@@ -153,6 +276,24 @@ impl ClosableMeteredSemaphore {
         })
     }
 
+    /// Like [Self::try_acquire_owned], but if no permit is available, attempts to preempt one. See
+    /// [MeteredSemaphore::try_preempt].
+    pub fn try_acquire_or_preempt_owned(
+        self: &Arc<Self>,
+    ) -> Result<TrackedOwnedMeteredSemPermit, TryAcquireError> {
+        match self.try_acquire_owned() {
+            Err(TryAcquireError::NoPermits) => {
+                let permit = self.inner.try_preempt().ok_or(TryAcquireError::NoPermits)?;
+                self.outstanding_permits.fetch_add(1, Ordering::Release);
+                Ok(TrackedOwnedMeteredSemPermit {
+                    inner: Some(permit),
+                    on_drop: self.on_permit_dropped(),
+                })
+            }
+            r => r,
+        }
+    }
+
     fn on_permit_dropped(self: &Arc<Self>) -> Box<dyn Fn() + Send + Sync> {
         let sem = self.clone();
         Box::new(move || {
@@ -190,6 +331,8 @@ impl Drop for TrackedOwnedMeteredSemPermit {
 /// Wraps an [OwnedSemaphorePermit] to update metrics when it's dropped
 pub(crate) struct OwnedMeteredSemPermit {
     inner: OwnedSemaphorePermit,
+    /// Permit from the shared semaphore, if the issuing semaphore had one
+    _shared: Option<OwnedSemaphorePermit>,
     /// See [MeteredSemaphore::unused_claimants]. If present when dropping, used to decrement the
     /// count.
     unused_claimants: Option<Arc<AtomicUsize>>,
@@ -273,4 +416,46 @@ mod tests {
         let perm = sem.try_acquire_owned().unwrap_err();
         assert_matches!(perm, TryAcquireError::Closed);
     }
+
+    #[tokio::test]
+    async fn unused_claims_preemptible_after_grace() {
+        let sem = MeteredSemaphore::new(1, MetricsContext::no_op(), |_, _| {})
+            .with_preemption(Some(Duration::from_millis(50)));
+        let claim = sem.claim_preemptibly(sem.try_acquire_owned().unwrap());
+        // Too soon
+        assert!(sem.try_preempt().is_none());
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let preempted = sem.try_preempt().unwrap();
+        assert!(claim.into_permit().is_none());
+        // No claims left to preempt
+        assert!(sem.try_preempt().is_none());
+        drop(preempted);
+        let claim = sem.claim_preemptibly(sem.try_acquire_owned().unwrap());
+        assert!(claim.into_permit().is_some());
+    }
+
+    #[tokio::test]
+    async fn shared_semaphore_limits_across_semaphores() {
+        let shared = SharedSlots::from(SharedSlotLimits {
+            max_outstanding_activities: Some(2),
+            ..Default::default()
+        });
+        let mk = || {
+            MeteredSemaphore::new(2, MetricsContext::no_op(), |_, _| {})
+                .with_shared(shared.activities.clone())
+        };
+        let (sem_a, sem_b) = (mk(), mk());
+        let perm_a = sem_a.try_acquire_owned().unwrap();
+        let _perm_b = sem_b.try_acquire_owned().unwrap();
+        // Each semaphore has a local permit left, but the shared budget is exhausted
+        assert_eq!(sem_a.available_permits(), 0);
+        assert_matches!(
+            sem_a.try_acquire_owned().unwrap_err(),
+            TryAcquireError::NoPermits
+        );
+        // The local permit was returned when the shared one could not be acquired
+        assert_eq!(sem_a.sem.available_permits(), 1);
+        drop(perm_a);
+        sem_b.acquire_owned().await.unwrap();
+    }
 }
@@ -1,10 +1,10 @@
 use crate::{
     advance_fut, job_assert, prost_dur,
     test_help::{
-        build_fake_worker, build_mock_pollers, canned_histories, gen_assert_and_reply,
-        mock_manual_poller, mock_poller, mock_poller_from_resps, mock_worker, poll_and_reply,
-        single_hist_mock_sg, test_worker_cfg, MockPollCfg, MockWorkerInputs, MocksHolder,
-        QueueResponse, ResponseType, WorkerExt, WorkflowCachingPolicy, TEST_Q,
+        build_fake_sdk, build_fake_worker, build_mock_pollers, canned_histories,
+        gen_assert_and_reply, mock_manual_poller, mock_poller, mock_poller_from_resps, mock_worker,
+        poll_and_reply, single_hist_mock_sg, test_worker_cfg, MockPollCfg, MockWorkerInputs,
+        MocksHolder, QueueResponse, ResponseType, WorkerExt, WorkflowCachingPolicy, TEST_Q,
     },
     worker::client::mocks::{mock_manual_workflow_client, mock_workflow_client},
     ActivityHeartbeat, Worker, WorkerConfigBuilder,
@@ -20,10 +20,10 @@ use std::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant, SystemTime},
 };
 use temporal_client::WorkflowOptions;
-use temporal_sdk::{ActivityOptions, WfContext};
+use temporal_sdk::{interceptors::TraceContextPropagationInterceptor, ActivityOptions, WfContext};
 use temporal_sdk_core_api::{
     errors::{CompleteActivityError, PollActivityError},
     Worker as WorkerTrait,
@@ -34,7 +34,10 @@ use temporal_sdk_core_protos::{
             activity_execution_result, activity_resolution, ActivityExecutionResult,
             ActivityResolution, Success,
         },
-        activity_task::{activity_task, ActivityCancelReason, ActivityTask, Cancel},
+        activity_task::{
+            activity_task, ActivityCancelReason, ActivityCancellationDetails, ActivityTask, Cancel,
+            Start,
+        },
         workflow_activation::{workflow_activation_job, ResolveActivity, WorkflowActivationJob},
         workflow_commands::{
             ActivityCancellationType, CompleteWorkflowExecution, RequestCancelActivity,
@@ -43,11 +46,15 @@ use temporal_sdk_core_protos::{
         workflow_completion::WorkflowActivationCompletion,
         ActivityTaskCompletion,
     },
+    default_wes_attribs,
     temporal::api::{
         command::v1::{command::Attributes, ScheduleActivityTaskCommandAttributes},
+        common::v1::{ActivityType, Header, Priority},
         enums::v1::EventType,
+        failure::v1::{failure::FailureInfo, ApplicationFailureInfo, Failure},
         history::v1::{
             history_event::Attributes as EventAttributes, ActivityTaskScheduledEventAttributes,
+            WorkflowExecutionStartedEventAttributes,
         },
         workflowservice::v1::{
             PollActivityTaskQueueResponse, RecordActivityTaskHeartbeatResponse,
@@ -55,7 +62,8 @@ use temporal_sdk_core_protos::{
             RespondActivityTaskFailedResponse, RespondWorkflowTaskCompletedResponse,
         },
     },
-    TestHistoryBuilder, DEFAULT_WORKFLOW_TYPE,
+    utilities::inject_trace_context,
+    TaskToken, TestHistoryBuilder, DEFAULT_ACTIVITY_TYPE, DEFAULT_WORKFLOW_TYPE,
 };
 use temporal_sdk_core_test_utils::{fanout_tasks, start_timer_cmd, TestWorker};
 use tokio::{join, sync::Barrier, time::sleep};
@@ -136,6 +144,59 @@ async fn activity_not_found_returns_ok() {
     core.drain_activity_poller_and_shutdown().await;
 }
 
+#[tokio::test]
+async fn per_activity_heartbeat_throttle_override_is_respected() {
+    let hb_count = Arc::new(AtomicUsize::new(0));
+    let hb_count_clone = hb_count.clone();
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_record_activity_heartbeat()
+        .returning(move |_, _| {
+            hb_count_clone.fetch_add(1, Ordering::SeqCst);
+            Ok(RecordActivityTaskHeartbeatResponse::default())
+        });
+    mock_client
+        .expect_complete_activity_task()
+        .times(1)
+        .returning(|_, _| Ok(RespondActivityTaskCompletedResponse::default()));
+
+    let core = mock_worker(MocksHolder::from_client_with_activities(
+        mock_client,
+        [PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            // Would normally be throttled to 8 seconds
+            heartbeat_timeout: Some(prost_dur!(from_secs(10))),
+            ..Default::default()
+        }
+        .into()],
+    ));
+
+    let act = core.poll_activity_task().await.unwrap();
+    core.record_activity_heartbeat(ActivityHeartbeat {
+        task_token: act.task_token.clone(),
+        details: vec![vec![1_u8].into()],
+        throttle_interval: Some(prost_dur!(from_millis(10))),
+    });
+    sleep(Duration::from_millis(50)).await;
+    // The override sticks around for subsequent heartbeats
+    core.record_activity_heartbeat(ActivityHeartbeat {
+        task_token: act.task_token.clone(),
+        details: vec![vec![2_u8].into()],
+        throttle_interval: None,
+    });
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(hb_count.load(Ordering::SeqCst), 2);
+
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![1].into())),
+    })
+    .await
+    .unwrap();
+    core.drain_activity_poller_and_shutdown().await;
+}
+
 #[tokio::test]
 async fn heartbeats_report_cancels_only_once() {
     let mut mock_client = mock_workflow_client();
@@ -145,6 +206,7 @@ async fn heartbeats_report_cancels_only_once() {
         .returning(|_, _| {
             Ok(RecordActivityTaskHeartbeatResponse {
                 cancel_requested: true,
+                ..Default::default()
             })
         });
     mock_client
@@ -180,6 +242,7 @@ async fn heartbeats_report_cancels_only_once() {
     core.record_activity_heartbeat(ActivityHeartbeat {
         task_token: act.task_token.clone(),
         details: vec![vec![1_u8, 2, 3].into()],
+        throttle_interval: None,
     });
     // We have to wait a beat for the heartbeat to be processed
     sleep(Duration::from_millis(10)).await;
@@ -188,8 +251,10 @@ async fn heartbeats_report_cancels_only_once() {
         &act,
         ActivityTask {
             task_token,
-            variant: Some(activity_task::Variant::Cancel(_)),
-            ..
+            variant: Some(activity_task::Variant::Cancel(Cancel {
+                details: Some(ActivityCancellationDetails { from_heartbeat: true, .. }),
+                ..
+            })),
         } => { task_token == &vec![1] }
     );
 
@@ -199,6 +264,7 @@ async fn heartbeats_report_cancels_only_once() {
     core.record_activity_heartbeat(ActivityHeartbeat {
         task_token: act.task_token.clone(),
         details: vec![vec![1_u8, 2, 3].into()],
+        throttle_interval: None,
     });
     // Wait delay again to flush heartbeat
     sleep(Duration::from_millis(10)).await;
@@ -232,6 +298,316 @@ async fn heartbeats_report_cancels_only_once() {
     core.drain_activity_poller_and_shutdown().await;
 }
 
+#[tokio::test]
+async fn paused_activities_are_cancelled_and_failed_for_retry() {
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_record_activity_heartbeat()
+        .times(1)
+        .returning(|_, _| {
+            Ok(RecordActivityTaskHeartbeatResponse {
+                activity_paused: true,
+                ..Default::default()
+            })
+        });
+    mock_client.expect_cancel_activity_task().times(0);
+    mock_client
+        .expect_fail_activity_task()
+        .withf(|_, f, _| {
+            matches!(
+                f,
+                Some(Failure {
+                    failure_info: Some(FailureInfo::ApplicationFailureInfo(
+                        ApplicationFailureInfo { r#type, non_retryable: false, .. }
+                    )),
+                    ..
+                }) if r#type == "ActivityPause"
+            )
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(RespondActivityTaskFailedResponse::default()));
+
+    let core = mock_worker(MocksHolder::from_client_with_activities(
+        mock_client,
+        [PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            heartbeat_timeout: Some(prost_dur!(from_millis(1))),
+            ..Default::default()
+        }
+        .into()],
+    ));
+
+    let act = core.poll_activity_task().await.unwrap();
+    core.record_activity_heartbeat(ActivityHeartbeat {
+        task_token: act.task_token.clone(),
+        details: vec![vec![1_u8, 2, 3].into()],
+        throttle_interval: None,
+    });
+    sleep(Duration::from_millis(10)).await;
+    let act = core.poll_activity_task().await.unwrap();
+    assert_matches!(
+        &act,
+        ActivityTask {
+            variant: Some(activity_task::Variant::Cancel(Cancel { reason, .. })),
+            ..
+        } => { *reason == ActivityCancelReason::Paused as i32 }
+    );
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act.task_token,
+        result: Some(ActivityExecutionResult::cancel_from_details(None)),
+    })
+    .await
+    .unwrap();
+    core.drain_activity_poller_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn unhandled_cancels_are_hard_cancelled_after_grace_period() {
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_record_activity_heartbeat()
+        .times(1)
+        .returning(|_, _| {
+            Ok(RecordActivityTaskHeartbeatResponse {
+                cancel_requested: true,
+                ..Default::default()
+            })
+        });
+    mock_client
+        .expect_cancel_activity_task()
+        .times(1)
+        .returning(|_, _| Ok(RespondActivityTaskCanceledResponse::default()));
+
+    let mut mh = MocksHolder::from_client_with_activities(
+        mock_client,
+        [PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            heartbeat_timeout: Some(prost_dur!(from_secs(10))),
+            ..Default::default()
+        }
+        .into()],
+    );
+    mh.worker_cfg(|wc| wc.activity_cancel_grace_period = Some(Duration::from_millis(50)));
+    let core = mock_worker(mh);
+
+    let act = core.poll_activity_task().await.unwrap();
+    core.record_activity_heartbeat(ActivityHeartbeat {
+        task_token: act.task_token.clone(),
+        details: vec![],
+        throttle_interval: None,
+    });
+    let cancel = core.poll_activity_task().await.unwrap();
+    assert!(!cancel.is_hard_cancel());
+    assert_matches!(
+        cancel.variant,
+        Some(activity_task::Variant::Cancel(Cancel {
+            hard_cancel: false,
+            ..
+        }))
+    );
+    // Lang ignores the cancel, so it's followed up with a hard cancel once the grace period passes
+    let hard_cancel = core.poll_activity_task().await.unwrap();
+    assert_eq!(hard_cancel.task_token, act.task_token);
+    assert!(hard_cancel.is_hard_cancel());
+    // The activity was already reported, so lang's eventual completion is ignored
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![1].into())),
+    })
+    .await
+    .unwrap();
+    core.drain_activity_poller_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn handed_off_activities_are_tracked_until_timeout_or_completion() {
+    let mut mock_client = mock_workflow_client();
+    mock_client.expect_complete_activity_task().never();
+    let core = mock_worker(MocksHolder::from_client_with_activities(
+        mock_client,
+        [
+            PollActivityTaskQueueResponse {
+                task_token: vec![1],
+                activity_id: "act1".to_string(),
+                start_to_close_timeout: Some(prost_dur!(from_secs(100))),
+                ..Default::default()
+            }
+            .into(),
+            PollActivityTaskQueueResponse {
+                task_token: vec![2],
+                activity_id: "act2".to_string(),
+                start_to_close_timeout: Some(prost_dur!(from_millis(50))),
+                ..Default::default()
+            }
+            .into(),
+        ],
+    ));
+
+    for _ in 0..2 {
+        let act = core.poll_activity_task().await.unwrap();
+        core.complete_activity_task(ActivityTaskCompletion {
+            task_token: act.task_token,
+            result: Some(ActivityExecutionResult::will_complete_async()),
+        })
+        .await
+        .unwrap();
+    }
+    let mut handoffs = core
+        .pending_activity_handoffs()
+        .into_iter()
+        .map(|h| h.activity_id)
+        .collect::<Vec<_>>();
+    handoffs.sort();
+    assert_eq!(handoffs, ["act1", "act2"]);
+    // The second activity's attempt has timed out, so it is no longer pending
+    sleep(Duration::from_millis(100)).await;
+    let handoffs = core.pending_activity_handoffs();
+    assert_eq!(handoffs.len(), 1);
+    assert_eq!(handoffs[0].task_token, vec![1]);
+    // The first is forgotten once it's been completed externally
+    core.activity_handoff_completed(&TaskToken(vec![1]));
+    assert!(core.pending_activity_handoffs().is_empty());
+    core.drain_activity_poller_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn handed_off_activities_without_start_to_close_expire_at_schedule_to_close() {
+    let mut mock_client = mock_workflow_client();
+    mock_client.expect_complete_activity_task().never();
+    let core = mock_worker(MocksHolder::from_client_with_activities(
+        mock_client,
+        [PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            scheduled_time: Some(SystemTime::now().into()),
+            schedule_to_close_timeout: Some(prost_dur!(from_millis(50))),
+            ..Default::default()
+        }
+        .into()],
+    ));
+
+    let act = core.poll_activity_task().await.unwrap();
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act.task_token,
+        result: Some(ActivityExecutionResult::will_complete_async()),
+    })
+    .await
+    .unwrap();
+    assert_eq!(core.pending_activity_handoffs().len(), 1);
+    sleep(Duration::from_millis(100)).await;
+    assert!(core.pending_activity_handoffs().is_empty());
+    core.drain_activity_poller_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn rate_limited_activity_types_do_not_hold_up_others() {
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_activity_task()
+        .times(3)
+        .returning(|_, _| Ok(RespondActivityTaskCompletedResponse::default()));
+    let act = |tt: u8, act_type: &str| PollActivityTaskQueueResponse {
+        task_token: vec![tt],
+        activity_id: format!("act{tt}"),
+        activity_type: Some(ActivityType {
+            name: act_type.to_string(),
+        }),
+        ..Default::default()
+    };
+    let mut mh = MocksHolder::from_client_with_activities(
+        mock_client,
+        [
+            act(1, "chatty").into(),
+            act(2, "chatty").into(),
+            act(3, "quiet").into(),
+        ],
+    );
+    mh.worker_cfg(|wc| {
+        wc.activity_type_rate_limits = HashMap::from([("chatty".to_string(), 5.0)]);
+    });
+    let core = mock_worker(mh);
+
+    let start = Instant::now();
+    let mut received = vec![];
+    for _ in 0..3 {
+        let task = core.poll_activity_task().await.unwrap();
+        received.push(task.task_token.clone());
+        core.complete_activity_task(ActivityTaskCompletion {
+            task_token: task.task_token,
+            result: Some(ActivityExecutionResult::ok(vec![1].into())),
+        })
+        .await
+        .unwrap();
+    }
+    // The second chatty activity had to wait for the first's token to be replenished, but the
+    // quiet one behind it did not
+    assert_eq!(received, [vec![1], vec![3], vec![2]]);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    core.drain_activity_poller_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn memoized_results_reported_for_retries() {
+    let mut mock_client = mock_workflow_client();
+    let completions = Arc::new(AtomicUsize::new(0));
+    let completions_clone = completions.clone();
+    mock_client
+        .expect_complete_activity_task()
+        .times(3)
+        .returning(move |tt, result| {
+            match completions_clone.fetch_add(1, Ordering::SeqCst) {
+                0 => return Err(tonic::Status::unavailable("nope")),
+                1 => {
+                    assert_eq!(tt.0, vec![2]);
+                    assert_eq!(result.unwrap().payloads[0].data, vec![7]);
+                }
+                _ => {}
+            }
+            Ok(RespondActivityTaskCompletedResponse::default())
+        });
+    let act = |tt: u8, attempt: i32, act_type: &str| PollActivityTaskQueueResponse {
+        task_token: vec![tt],
+        activity_id: act_type.to_string(),
+        activity_type: Some(ActivityType {
+            name: act_type.to_string(),
+        }),
+        attempt,
+        ..Default::default()
+    };
+    let mut mh = MocksHolder::from_client_with_activities(
+        mock_client,
+        [
+            act(1, 1, "idempotent").into(),
+            act(2, 2, "idempotent").into(),
+            act(3, 1, "other").into(),
+        ],
+    );
+    mh.worker_cfg(|wc| {
+        wc.memoized_activity_types = HashSet::from(["idempotent".to_string()]);
+    });
+    let core = mock_worker(mh);
+
+    let task = core.poll_activity_task().await.unwrap();
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: task.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![7].into())),
+    })
+    .await
+    .unwrap();
+    // The retry is answered from the memo, so lang never sees it
+    let task = core.poll_activity_task().await.unwrap();
+    assert_eq!(task.task_token, vec![3]);
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: task.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![1].into())),
+    })
+    .await
+    .unwrap();
+    core.drain_activity_poller_and_shutdown().await;
+}
+
 #[tokio::test]
 async fn activity_cancel_interrupts_poll() {
     let mut mock_poller = mock_manual_poller();
@@ -270,6 +646,7 @@ async fn activity_cancel_interrupts_poll() {
             async {
                 Ok(RecordActivityTaskHeartbeatResponse {
                     cancel_requested: true,
+                    ..Default::default()
                 })
             }
             .boxed()
@@ -294,6 +671,7 @@ async fn activity_cancel_interrupts_poll() {
                 task_token: act.task_token,
 
                 details: vec![vec![1_u8, 2, 3].into()],
+                throttle_interval: None,
             });
             last_finisher.store(1, Ordering::SeqCst);
         },
@@ -390,10 +768,12 @@ async fn many_concurrent_heartbeat_cancels() {
                 if calls < 5 {
                     Ok(RecordActivityTaskHeartbeatResponse {
                         cancel_requested: false,
+                        ..Default::default()
                     })
                 } else {
                     Ok(RecordActivityTaskHeartbeatResponse {
                         cancel_requested: true,
+                        ..Default::default()
                     })
                 }
             }
@@ -422,6 +802,7 @@ async fn many_concurrent_heartbeat_cancels() {
             worker.record_activity_heartbeat(ActivityHeartbeat {
                 task_token: task_token.clone(),
                 details: vec![],
+                throttle_interval: None,
             });
             sleep(Duration::from_millis(50)).await;
         }
@@ -512,6 +893,7 @@ async fn can_heartbeat_acts_during_shutdown() {
         .returning(|_, _| {
             Ok(RecordActivityTaskHeartbeatResponse {
                 cancel_requested: false,
+                ..Default::default()
             })
         });
     mock_client
@@ -538,6 +920,7 @@ async fn can_heartbeat_acts_during_shutdown() {
         task_token: act.task_token.clone(),
 
         details: vec![vec![1_u8, 2, 3].into()],
+        throttle_interval: None,
     });
     core.complete_activity_task(ActivityTaskCompletion {
         task_token: act.task_token,
@@ -550,7 +933,8 @@ async fn can_heartbeat_acts_during_shutdown() {
 }
 
 /// Verifies that if a user has tried to record a heartbeat and then immediately after failed the
-/// activity, that we flush those details before reporting the failure completion.
+/// activity, that we flush those details before reporting the failure completion, and attach them
+/// to the failure as well.
 #[tokio::test]
 async fn complete_act_with_fail_flushes_heartbeat() {
     let last_hb = 50;
@@ -565,12 +949,16 @@ async fn complete_act_with_fail_flushes_heartbeat() {
             *lsp.borrow_mut() = payload;
             Ok(RecordActivityTaskHeartbeatResponse {
                 cancel_requested: false,
+                ..Default::default()
             })
         });
     mock_client
         .expect_fail_activity_task()
         .times(1)
-        .returning(|_, _| Ok(RespondActivityTaskFailedResponse::default()));
+        .returning(move |_, _, last_details| {
+            assert_eq!(last_details.unwrap().payloads[0].data, &[last_hb]);
+            Ok(RespondActivityTaskFailedResponse::default())
+        });
 
     let core = mock_worker(MocksHolder::from_client_with_activities(
         mock_client,
@@ -589,6 +977,7 @@ async fn complete_act_with_fail_flushes_heartbeat() {
         core.record_activity_heartbeat(ActivityHeartbeat {
             task_token: act.task_token.clone(),
             details: vec![vec![i].into()],
+            throttle_interval: None,
         });
     }
     core.complete_activity_task(ActivityTaskCompletion {
@@ -708,6 +1097,158 @@ async fn no_eager_activities_requested_when_worker_options_disable_remote_activi
     assert_eq!(num_eager_requested.load(Ordering::Relaxed), 0);
 }
 
+#[tokio::test]
+async fn activity_priority_propagates_to_schedule_command() {
+    let wfid = "fake_wf_id";
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let priority = Priority {
+        priority_key: 2,
+        ..Default::default()
+    };
+
+    let mut mock = mock_workflow_client();
+    let expected = priority.clone();
+    mock.expect_complete_workflow_task()
+        .times(1)
+        .returning(move |req| {
+            assert_matches!(
+                req.commands[0].attributes.as_ref(),
+                Some(Attributes::ScheduleActivityTaskCommandAttributes(
+                    ScheduleActivityTaskCommandAttributes { priority: Some(p), .. }
+                )) if p == &expected
+            );
+            Ok(Default::default())
+        });
+    let mut mock = single_hist_mock_sg(wfid, t, [1], mock, true);
+    let mut mock_poller = mock_manual_poller();
+    mock_poller
+        .expect_poll()
+        .returning(|| futures::future::pending().boxed());
+    mock.set_act_poller(Box::new(mock_poller));
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.no_remote_activities = true;
+    });
+    let core = mock_worker(mock);
+
+    let wf_task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        wf_task.run_id,
+        ScheduleActivity {
+            seq: 1,
+            activity_id: "act_id".to_string(),
+            task_queue: TEST_Q.to_string(),
+            priority: Some(priority),
+            ..Default::default()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+    core.drain_pollers_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn activities_routed_to_configured_task_queues() {
+    let wfid = "fake_wf_id";
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+
+    let mut mock = mock_workflow_client();
+    mock.expect_complete_workflow_task()
+        .times(1)
+        .returning(move |req| {
+            let tqs = req
+                .commands
+                .into_iter()
+                .map(|c| match c.attributes {
+                    Some(Attributes::ScheduleActivityTaskCommandAttributes(attrs)) => {
+                        attrs.task_queue.unwrap().name
+                    }
+                    _ => panic!("Unexpected command"),
+                })
+                .collect_vec();
+            assert_eq!(tqs, ["gpu_q", TEST_Q, "explicit_q"]);
+            Ok(Default::default())
+        });
+    let mut mock = single_hist_mock_sg(wfid, t, [1], mock, true);
+    let mut mock_poller = mock_manual_poller();
+    mock_poller
+        .expect_poll()
+        .returning(|| futures::future::pending().boxed());
+    mock.set_act_poller(Box::new(mock_poller));
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.no_remote_activities = true;
+        wc.activity_task_queue_routes =
+            HashMap::from([("gpu_act".to_string(), "gpu_q".to_string())]);
+    });
+    let core = mock_worker(mock);
+
+    let wf_task = core.poll_workflow_activation().await.unwrap();
+    let sched = |seq: u32, activity_type: &str, task_queue: &str| {
+        ScheduleActivity {
+            seq,
+            activity_id: seq.to_string(),
+            activity_type: activity_type.to_string(),
+            task_queue: task_queue.to_string(),
+            ..Default::default()
+        }
+        .into()
+    };
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        wf_task.run_id,
+        vec![
+            sched(1, "gpu_act", TEST_Q),
+            sched(2, "cpu_act", TEST_Q),
+            // Explicitly chosen queues are left alone
+            sched(3, "gpu_act", "explicit_q"),
+        ],
+    ))
+    .await
+    .unwrap();
+    core.drain_pollers_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn polled_activity_priority_exposed_in_start() {
+    let priority = Priority {
+        priority_key: 4,
+        ..Default::default()
+    };
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_activity_task()
+        .times(1)
+        .returning(|_, _| Ok(RespondActivityTaskCompletedResponse::default()));
+    let core = mock_worker(MocksHolder::from_client_with_activities(
+        mock_client,
+        [PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            priority: Some(priority.clone()),
+            ..Default::default()
+        }
+        .into()],
+    ));
+
+    let act = core.poll_activity_task().await.unwrap();
+    assert_matches!(
+        act.variant,
+        Some(activity_task::Variant::Start(Start { priority: Some(p), .. })) if p == priority
+    );
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![1].into())),
+    })
+    .await
+    .unwrap();
+    core.drain_activity_poller_and_shutdown().await;
+}
+
 /// This test verifies that activity tasks which come as replies to completing a WFT are properly
 /// delivered via polling.
 #[tokio::test]
@@ -1073,7 +1614,7 @@ async fn graceful_shutdown(#[values(true, false)] at_max_outstanding: bool) {
     mock_client
         .expect_fail_activity_task()
         .times(3)
-        .returning(|_, _| Ok(Default::default()));
+        .returning(|_, _, _| Ok(Default::default()));
 
     let max_outstanding = if at_max_outstanding { 3_usize } else { 100 };
     let mw = MockWorkerInputs {
@@ -1104,7 +1645,8 @@ async fn graceful_shutdown(#[values(true, false)] at_max_outstanding: bool) {
         assert_matches!(
             cancel.variant,
             Some(activity_task::Variant::Cancel(Cancel {
-                reason: r
+                reason: r,
+                ..
             })) if r == ActivityCancelReason::WorkerShutdown as i32
         );
         seen_tts.insert(cancel.task_token);
@@ -1189,3 +1731,48 @@ async fn activities_must_be_flushed_to_server_on_shutdown(#[values(true, false)]
     };
     join!(shutdown_task, complete_task);
 }
+
+#[tokio::test]
+async fn trace_context_propagated_to_scheduled_activities() {
+    let mut trace_context = HashMap::new();
+    trace_context.insert("traceparent".to_string(), "00-abc-def-01".to_string());
+    let mut headers = HashMap::new();
+    inject_trace_context(&mut headers, &trace_context);
+
+    let mut t = TestHistoryBuilder::default();
+    t.add(WorkflowExecutionStartedEventAttributes {
+        header: Some(Header {
+            fields: headers.clone(),
+        }),
+        ..default_wes_attribs()
+    });
+    t.add_full_wf_task();
+    let scheduled_event_id = t.add_activity_task_scheduled("1");
+    let started_event_id = t.add_activity_task_started(scheduled_event_id);
+    t.add_activity_task_completed(scheduled_event_id, started_event_id, Default::default());
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+    mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+        asserts.then(move |wft| {
+            assert_matches!(
+                wft.commands[0].attributes.as_ref(),
+                Some(Attributes::ScheduleActivityTaskCommandAttributes(a))
+                    if a.header.as_ref().map(|h| &h.fields) == Some(&headers)
+            );
+        });
+    });
+    let mut worker = build_fake_sdk(mock_cfg);
+    worker.set_worker_interceptor(TraceContextPropagationInterceptor::default());
+    worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+        ctx.activity(ActivityOptions {
+            activity_type: DEFAULT_ACTIVITY_TYPE.to_string(),
+            start_to_close_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        })
+        .await;
+        Ok(().into())
+    });
+    worker.run().await.unwrap();
+}
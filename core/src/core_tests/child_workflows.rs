@@ -10,13 +10,16 @@ use temporal_client::WorkflowOptions;
 use temporal_sdk::{ChildWorkflowOptions, Signal, WfContext, WorkflowResult};
 use temporal_sdk_core_api::Worker;
 use temporal_sdk_core_protos::coresdk::{
-    child_workflow::{child_workflow_result, ChildWorkflowCancellationType},
-    workflow_activation::{workflow_activation_job, WorkflowActivationJob},
+    child_workflow::{child_workflow_result, ChildWorkflowCancellationType, ChildWorkflowResult},
+    workflow_activation::{
+        workflow_activation_job, ResolveChildWorkflowExecution, WorkflowActivationJob,
+    },
     workflow_commands::{
         CancelChildWorkflowExecution, CompleteWorkflowExecution, StartChildWorkflowExecution,
     },
     workflow_completion::WorkflowActivationCompletion,
 };
+use temporal_sdk_core_protos::temporal::api::command::v1::command;
 use tokio::join;
 
 const SIGNAME: &str = "SIGNAME";
@@ -89,7 +92,7 @@ async fn parent_cancels_child_wf(ctx: WfContext) -> WorkflowResult<()> {
         .await
         .into_started()
         .expect("Child should get started");
-    start_res.cancel(&ctx);
+    start_res.cancel_with_reason(&ctx, "Parent is done with it".to_string());
     let stat = start_res
         .result()
         .await
@@ -102,7 +105,18 @@ async fn parent_cancels_child_wf(ctx: WfContext) -> WorkflowResult<()> {
 #[tokio::test]
 async fn cancel_child_workflow() {
     let t = canned_histories::single_child_workflow_cancelled("child-id-1");
-    let mut worker = build_fake_sdk(MockPollCfg::from_resps(t, [ResponseType::AllHistory]));
+    let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+    mock_cfg.completion_asserts = Some(Box::new(|wft| {
+        for c in &wft.commands {
+            if let Some(
+                command::Attributes::RequestCancelExternalWorkflowExecutionCommandAttributes(a),
+            ) = &c.attributes
+            {
+                assert_eq!(a.reason, "Parent is done with it");
+            }
+        }
+    }));
+    let mut worker = build_fake_sdk(mock_cfg);
     worker.register_wf(DEFAULT_WORKFLOW_TYPE, parent_cancels_child_wf);
     worker.run().await.unwrap();
 }
@@ -154,6 +168,7 @@ async fn cancel_child_workflow_lang_thinks_not_started_but_is(
         act.run_id,
         CancelChildWorkflowExecution {
             child_workflow_seq: 1,
+            ..Default::default()
         }
         .into(),
     ))
@@ -172,6 +187,85 @@ async fn cancel_child_workflow_lang_thinks_not_started_but_is(
     // a not-found workflow, which couldn't happen in this case.
 }
 
+#[rstest::rstest]
+#[case::abandon(ChildWorkflowCancellationType::Abandon, 8)]
+#[case::try_cancel(ChildWorkflowCancellationType::TryCancel, 8)]
+#[case::wait_cancel_requested(ChildWorkflowCancellationType::WaitCancellationRequested, 13)]
+#[case::wait_cancel_completed(ChildWorkflowCancellationType::WaitCancellationCompleted, 17)]
+#[tokio::test]
+async fn child_workflow_cancellation_types(
+    #[case] cancellation_type: ChildWorkflowCancellationType,
+    #[case] resolved_at_history_length: u32,
+) {
+    // Abandoning sends no cancel request, otherwise the request is delivered to the child in one
+    // workflow task and the child is cancelled in a later one
+    let t = match cancellation_type {
+        ChildWorkflowCancellationType::Abandon => {
+            canned_histories::single_child_workflow_abandon_cancelled("child-id-1")
+        }
+        _ => canned_histories::single_child_workflow_cancel_requested_then_cancelled("child-id-1"),
+    };
+    let mock = mock_workflow_client();
+    let mock = single_hist_mock_sg("fakeid", t, [ResponseType::AllHistory], mock, true);
+    let core = mock_worker(mock);
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        StartChildWorkflowExecution {
+            seq: 1,
+            cancellation_type: cancellation_type as i32,
+            ..Default::default()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CancelChildWorkflowExecution {
+            child_workflow_seq: 1,
+            ..Default::default()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+
+    let act = loop {
+        let act = core.poll_workflow_activation().await.unwrap();
+        if !act.jobs.is_empty()
+            && act.jobs.iter().all(|j| {
+                matches!(
+                    j.variant,
+                    Some(workflow_activation_job::Variant::ResolveChildWorkflowExecution(_))
+                )
+            })
+        {
+            break act;
+        }
+        core.complete_workflow_activation(WorkflowActivationCompletion::empty(act.run_id))
+            .await
+            .unwrap();
+    };
+    assert_eq!(act.history_length, resolved_at_history_length);
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(
+                workflow_activation_job::Variant::ResolveChildWorkflowExecution(
+                    ResolveChildWorkflowExecution {
+                        result: Some(ChildWorkflowResult {
+                            status: Some(child_workflow_result::Status::Cancelled(_)),
+                        }),
+                        ..
+                    }
+                )
+            ),
+        }]
+    );
+}
+
 #[tokio::test]
 async fn cancel_already_complete_child_ignored() {
     let t = canned_histories::single_child_workflow("child-id-1");
@@ -212,6 +306,7 @@ async fn cancel_already_complete_child_ignored() {
         vec![
             CancelChildWorkflowExecution {
                 child_workflow_seq: 1,
+                ..Default::default()
             }
             .into(),
             CompleteWorkflowExecution { result: None }.into(),
@@ -21,8 +21,8 @@ use std::{
 };
 use temporal_client::WorkflowOptions;
 use temporal_sdk::{
-    ActContext, ActivityCancelledError, LocalActivityOptions, WfContext, WorkflowFunction,
-    WorkflowResult,
+    ActContext, ActivityCancelledError, LocalActivityExecutor, LocalActivityOptions, WfContext,
+    WorkflowFunction, WorkflowResult,
 };
 use temporal_sdk_core_api::{
     errors::{PollActivityError, PollWfError},
@@ -296,8 +296,9 @@ async fn local_act_fail_and_retry(#[case] eventually_pass: bool) {
     assert_eq!(expected_attempts, attempts.load(Ordering::Relaxed));
 }
 
+#[rstest::rstest]
 #[tokio::test]
-async fn local_act_retry_long_backoff_uses_timer() {
+async fn local_act_retry_long_backoff_uses_timer(#[values(false, true)] worker_threshold: bool) {
     let mut t = TestHistoryBuilder::default();
     t.add_by_type(EventType::WorkflowExecutionStarted);
     t.add_full_wf_task();
@@ -327,19 +328,26 @@ async fn local_act_retry_long_backoff_uses_timer() {
         [1.into(), 2.into(), ResponseType::AllHistory],
         mock,
     );
-    let mut worker = mock_sdk_cfg(mh, |w| w.max_cached_workflows = 1);
+    let mut worker = mock_sdk_cfg(mh, |w| {
+        w.max_cached_workflows = 1;
+        if worker_threshold {
+            w.default_local_activity_retry_threshold = Duration::from_millis(500);
+        }
+    });
+    // This will make the second backoff 65 seconds, plenty to use timer - or only 650ms, which
+    // uses a timer when the worker lowers the default threshold
+    let backoff_coefficient = if worker_threshold { 10. } else { 1_000. };
 
     worker.register_wf(
         DEFAULT_WORKFLOW_TYPE.to_owned(),
-        |ctx: WfContext| async move {
+        move |ctx: WfContext| async move {
             let la_res = ctx
                 .local_activity(LocalActivityOptions {
                     activity_type: DEFAULT_ACTIVITY_TYPE.to_string(),
                     input: "hi".as_json_payload().expect("serializes fine"),
                     retry_policy: RetryPolicy {
                         initial_interval: Some(prost_dur!(from_millis(65))),
-                        // This will make the second backoff 65 seconds, plenty to use timer
-                        backoff_coefficient: 1_000.,
+                        backoff_coefficient,
                         maximum_interval: Some(prost_dur!(from_secs(600))),
                         maximum_attempts: 3,
                         non_retryable_error_types: vec![],
@@ -353,12 +361,17 @@ async fn local_act_retry_long_backoff_uses_timer() {
             Ok(().into())
         },
     );
-    worker.register_activity(
-        DEFAULT_ACTIVITY_TYPE,
-        move |_ctx: ActContext, _: String| async move {
-            Result::<(), _>::Err(anyhow!("Oh no I failed!"))
-        },
-    );
+    let attempts = Arc::new(SegQueue::new());
+    let attempts_c = attempts.clone();
+    worker.register_activity(DEFAULT_ACTIVITY_TYPE, move |ctx: ActContext, _: String| {
+        let info = ctx.get_info();
+        attempts_c.push((
+            info.attempt,
+            info.scheduled_time,
+            info.previous_attempt_failure.clone(),
+        ));
+        async move { Result::<(), _>::Err(anyhow!("Oh no I failed!")) }
+    });
     worker
         .submit_wf(
             wf_id.to_owned(),
@@ -369,6 +382,17 @@ async fn local_act_retry_long_backoff_uses_timer() {
         .await
         .unwrap();
     worker.run_until_done().await.unwrap();
+    // Attempts resumed after the timer still see the original schedule time and prior failure
+    let attempts: Vec<_> = std::iter::from_fn(|| attempts.pop()).collect();
+    assert_eq!(
+        attempts.iter().map(|a| a.0).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert!(attempts.iter().all(|a| a.1 == attempts[0].1));
+    assert!(attempts[0].2.is_none());
+    for (_, _, prev_fail) in &attempts[1..] {
+        assert_eq!(prev_fail.as_ref().unwrap().message, "Oh no I failed!");
+    }
 }
 
 #[tokio::test]
@@ -409,6 +433,107 @@ async fn local_act_null_result() {
     worker.run_until_done().await.unwrap();
 }
 
+#[rstest::rstest]
+#[tokio::test]
+async fn local_acts_run_on_dedicated_executor(#[values(false, true)] blocking_pool: bool) {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_local_activity_marker(1, "1", None, None, |_| {});
+    t.add_workflow_execution_completed();
+
+    let wf_id = "fakeid";
+    let mock = mock_workflow_client();
+    let mh = MockPollCfg::from_resp_batches(wf_id, t, [1], mock);
+    let mut worker = mock_sdk_cfg(mh, |w| w.max_cached_workflows = 1);
+    let la_runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("la-executor")
+        .enable_all()
+        .build()
+        .unwrap();
+    let executor = if blocking_pool {
+        LocalActivityExecutor::BlockingPool { max_threads: 1 }
+    } else {
+        LocalActivityExecutor::Runtime(la_runtime.handle().clone())
+    };
+    worker
+        .inner_mut()
+        .set_local_activity_executor(executor, None)
+        .unwrap();
+
+    worker.register_wf(
+        DEFAULT_WORKFLOW_TYPE.to_owned(),
+        |ctx: WfContext| async move {
+            ctx.local_activity(LocalActivityOptions {
+                activity_type: "where".to_string(),
+                input: "hi".as_json_payload().expect("serializes fine"),
+                ..Default::default()
+            })
+            .await;
+            Ok(().into())
+        },
+    );
+    let ran_on = Arc::new(parking_lot::Mutex::new(None));
+    let ran_on_c = ran_on.clone();
+    worker.register_activity("where", move |_ctx: ActContext, _: String| {
+        let ran_on = ran_on_c.clone();
+        async move {
+            let thread = std::thread::current();
+            *ran_on.lock() = Some((thread.id(), thread.name().map(ToString::to_string)));
+            Ok(())
+        }
+    });
+    worker
+        .submit_wf(
+            wf_id.to_owned(),
+            DEFAULT_WORKFLOW_TYPE.to_owned(),
+            vec![],
+            WorkflowOptions::default(),
+        )
+        .await
+        .unwrap();
+    worker.run_until_done().await.unwrap();
+
+    let (thread_id, thread_name) = ran_on.lock().take().expect("Activity ran");
+    assert_ne!(thread_id, std::thread::current().id());
+    if !blocking_pool {
+        assert_eq!(thread_name.as_deref(), Some("la-executor"));
+    }
+    la_runtime.shutdown_background();
+}
+
+#[tokio::test]
+async fn zero_thread_blocking_pool_is_rejected() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mock = mock_workflow_client();
+    let mh = MockPollCfg::from_resp_batches("fakeid", t, [1], mock);
+    let mut worker = mock_sdk_cfg(mh, |_| {});
+    assert!(worker
+        .inner_mut()
+        .set_local_activity_executor(LocalActivityExecutor::BlockingPool { max_threads: 0 }, None)
+        .is_err());
+
+    worker.register_wf(
+        DEFAULT_WORKFLOW_TYPE.to_owned(),
+        |_: WfContext| async move { Ok(().into()) },
+    );
+    worker
+        .submit_wf(
+            "fakeid".to_owned(),
+            DEFAULT_WORKFLOW_TYPE.to_owned(),
+            vec![],
+            WorkflowOptions::default(),
+        )
+        .await
+        .unwrap();
+    worker.run_until_done().await.unwrap();
+}
+
 #[tokio::test]
 async fn local_act_command_immediately_follows_la_marker() {
     // This repro only works both when cache is off, and there is at least one heartbeat wft
@@ -1390,3 +1515,70 @@ async fn local_activity_after_wf_complete_is_discarded() {
     join!(wf_poller, at_poller);
     core.drain_pollers_and_shutdown().await;
 }
+
+#[tokio::test]
+async fn forced_wft_heartbeat_happens_immediately() {
+    let wfid = "fake_wf_id";
+    let mut t = TestHistoryBuilder::default();
+    // Long enough that a normal heartbeat would never happen during the test
+    t.add_wfe_started_with_wft_timeout(Duration::from_secs(600));
+    t.add_full_wf_task();
+    t.add_full_wf_task();
+    t.add_workflow_task_scheduled_and_started();
+
+    let mock = mock_workflow_client();
+    let mut mock_cfg = MockPollCfg::from_resp_batches(
+        wfid,
+        t,
+        [ResponseType::ToTaskNum(1), ResponseType::OneTask(2)],
+        mock,
+    );
+    mock_cfg.make_poll_stream_interminable = true;
+    mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+        asserts
+            .then(move |wft| {
+                assert!(wft.force_create_new_workflow_task);
+                assert_eq!(wft.commands.len(), 0);
+            })
+            .then(move |wft| {
+                assert!(!wft.force_create_new_workflow_task);
+                assert_eq!(wft.commands.len(), 2);
+                assert_eq!(wft.commands[0].command_type(), CommandType::RecordMarker);
+            });
+    });
+    let mut mock = build_mock_pollers(mock_cfg);
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.force_workflow_task_heartbeat(&task.run_id);
+    // Completing would block until the LA resolves if the heartbeat were not forced
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        schedule_local_activity_cmd(
+            1,
+            "act-id",
+            ActivityCancellationType::TryCancel,
+            Duration::from_secs(60),
+        ),
+    ))
+    .await
+    .unwrap();
+
+    let act_task = core.poll_activity_task().await.unwrap();
+    core.complete_activity_task(ActivityTaskCompletion {
+        task_token: act_task.task_token,
+        result: Some(ActivityExecutionResult::ok(vec![1].into())),
+    })
+    .await
+    .unwrap();
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::ResolveActivity(_)),
+        }]
+    );
+    core.complete_execution(&task.run_id).await;
+    core.drain_pollers_and_shutdown().await;
+}
@@ -2,6 +2,7 @@ mod activity_tasks;
 mod child_workflows;
 mod determinism;
 mod local_activities;
+mod nexus;
 mod queries;
 mod replay_flag;
 mod updates;
@@ -0,0 +1,427 @@
+use crate::{
+    replay::TestHistoryBuilder,
+    test_help::{build_mock_pollers, mock_worker, single_hist_mock_sg, MockPollCfg, ResponseType},
+    worker::client::mocks::mock_workflow_client,
+};
+use temporal_sdk_core_api::Worker;
+use temporal_sdk_core_protos::{
+    coresdk::{
+        nexus::{nexus_operation_result, NexusOperationResult},
+        workflow_activation::{
+            resolve_nexus_operation_start, workflow_activation_job, ResolveNexusOperation,
+            ResolveNexusOperationStart, WorkflowActivationJob,
+        },
+        workflow_commands::{
+            CompleteWorkflowExecution, RequestCancelNexusOperation, ScheduleNexusOperation,
+        },
+        workflow_completion::WorkflowActivationCompletion,
+    },
+    temporal::api::{
+        command::v1::command,
+        common::v1::Payload,
+        enums::v1::{CommandType, EventType, WorkflowTaskFailedCause},
+        failure::v1::Failure,
+        history::v1::{
+            NexusOperationCancelRequestedEventAttributes, NexusOperationCanceledEventAttributes,
+            NexusOperationCompletedEventAttributes, NexusOperationFailedEventAttributes,
+            NexusOperationScheduledEventAttributes, NexusOperationStartedEventAttributes,
+            NexusOperationTimedOutEventAttributes,
+        },
+    },
+};
+
+const ENDPOINT: &str = "endpoint";
+const SERVICE: &str = "service";
+const OPERATION: &str = "operation";
+
+#[derive(Clone, Copy, Debug)]
+enum Outcome {
+    Completed,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+fn schedule_cmd() -> ScheduleNexusOperation {
+    ScheduleNexusOperation {
+        seq: 1,
+        endpoint: ENDPOINT.to_string(),
+        service: SERVICE.to_string(),
+        operation: OPERATION.to_string(),
+        ..Default::default()
+    }
+}
+
+fn add_scheduled(t: &mut TestHistoryBuilder) -> i64 {
+    t.add(NexusOperationScheduledEventAttributes {
+        endpoint: ENDPOINT.to_string(),
+        service: SERVICE.to_string(),
+        operation: OPERATION.to_string(),
+        workflow_task_completed_event_id: 4,
+        ..Default::default()
+    })
+}
+
+fn add_outcome(t: &mut TestHistoryBuilder, scheduled_event_id: i64, outcome: Outcome) {
+    let failure = Some(Failure {
+        message: format!("{outcome:?}"),
+        ..Default::default()
+    });
+    match outcome {
+        Outcome::Completed => t.add(NexusOperationCompletedEventAttributes {
+            scheduled_event_id,
+            result: Some(Payload::from(b"done")),
+            ..Default::default()
+        }),
+        Outcome::Failed => t.add(NexusOperationFailedEventAttributes {
+            scheduled_event_id,
+            failure,
+            ..Default::default()
+        }),
+        Outcome::Cancelled => t.add(NexusOperationCanceledEventAttributes {
+            scheduled_event_id,
+            failure,
+            ..Default::default()
+        }),
+        Outcome::TimedOut => t.add(NexusOperationTimedOutEventAttributes {
+            scheduled_event_id,
+            failure,
+            ..Default::default()
+        }),
+    };
+}
+
+fn assert_outcome(job: &WorkflowActivationJob, outcome: Outcome) {
+    let Some(workflow_activation_job::Variant::ResolveNexusOperation(ResolveNexusOperation {
+        seq: 1,
+        result: Some(NexusOperationResult {
+            status: Some(status),
+        }),
+    })) = &job.variant
+    else {
+        panic!("Expected the nexus operation to be resolved, got {job:?}");
+    };
+    match (outcome, status) {
+        (Outcome::Completed, nexus_operation_result::Status::Completed(p)) => {
+            assert_eq!(p.data, b"done")
+        }
+        (Outcome::Failed, nexus_operation_result::Status::Failed(f))
+        | (Outcome::Cancelled, nexus_operation_result::Status::Cancelled(f))
+        | (Outcome::TimedOut, nexus_operation_result::Status::TimedOut(f)) => {
+            assert_eq!(f.message, format!("{outcome:?}"))
+        }
+        (o, s) => panic!("Expected {o:?} outcome, got {s:?}"),
+    }
+}
+
+#[rstest::rstest]
+#[case::completed(Outcome::Completed)]
+#[case::failed(Outcome::Failed)]
+#[case::cancelled(Outcome::Cancelled)]
+#[case::timed_out(Outcome::TimedOut)]
+#[tokio::test]
+async fn async_operation_started_then_resolved(#[case] outcome: Outcome) {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let scheduled_event_id = add_scheduled(&mut t);
+    t.add(NexusOperationStartedEventAttributes {
+        scheduled_event_id,
+        operation_id: "op-id".to_string(),
+        ..Default::default()
+    });
+    t.add_full_wf_task();
+    add_outcome(&mut t, scheduled_event_id, outcome);
+    t.add_workflow_task_scheduled_and_started();
+
+    let mock = single_hist_mock_sg(
+        "fakeid",
+        t,
+        [ResponseType::AllHistory],
+        mock_workflow_client(),
+        true,
+    );
+    let core = mock_worker(mock);
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        schedule_cmd().into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::ResolveNexusOperationStart(
+                ResolveNexusOperationStart {
+                    seq: 1,
+                    status: Some(resolve_nexus_operation_start::Status::OperationId(op_id)),
+                }
+            )),
+        }] if op_id == "op-id"
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(act.run_id))
+        .await
+        .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(act.jobs.len(), 1);
+    assert_outcome(&act.jobs[0], outcome);
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+}
+
+#[rstest::rstest]
+#[case::completed(Outcome::Completed)]
+#[case::failed(Outcome::Failed)]
+#[tokio::test]
+async fn sync_operation_resolves_start_and_result_together(#[case] outcome: Outcome) {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let scheduled_event_id = add_scheduled(&mut t);
+    add_outcome(&mut t, scheduled_event_id, outcome);
+    t.add_workflow_task_scheduled_and_started();
+
+    let mock = single_hist_mock_sg(
+        "fakeid",
+        t,
+        [ResponseType::AllHistory],
+        mock_workflow_client(),
+        true,
+    );
+    let core = mock_worker(mock);
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        schedule_cmd().into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [
+            WorkflowActivationJob {
+                variant: Some(
+                    workflow_activation_job::Variant::ResolveNexusOperationStart(
+                        ResolveNexusOperationStart {
+                            seq: 1,
+                            status: Some(resolve_nexus_operation_start::Status::StartedSync(true)),
+                        }
+                    )
+                ),
+            },
+            _
+        ]
+    );
+    assert_outcome(&act.jobs[1], outcome);
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn cancel_before_sent_resolves_start_without_scheduling() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mock = single_hist_mock_sg(
+        "fakeid",
+        t,
+        [ResponseType::AllHistory],
+        mock_workflow_client(),
+        true,
+    );
+    let core = mock_worker(mock);
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        act.run_id,
+        vec![
+            schedule_cmd().into(),
+            RequestCancelNexusOperation { seq: 1 }.into(),
+        ],
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(
+                workflow_activation_job::Variant::ResolveNexusOperationStart(
+                    ResolveNexusOperationStart {
+                        seq: 1,
+                        status: Some(resolve_nexus_operation_start::Status::CancelledBeforeStart(
+                            _
+                        )),
+                    }
+                )
+            ),
+        }]
+    );
+    // The schedule command was dropped, so only the completion reaches the server
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn cancel_after_start_requests_cancel_and_waits_for_outcome() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let scheduled_event_id = add_scheduled(&mut t);
+    t.add(NexusOperationStartedEventAttributes {
+        scheduled_event_id,
+        operation_id: "op-id".to_string(),
+        ..Default::default()
+    });
+    t.add_full_wf_task();
+    t.add(NexusOperationCancelRequestedEventAttributes {
+        scheduled_event_id,
+        workflow_task_completed_event_id: 9,
+    });
+    add_outcome(&mut t, scheduled_event_id, Outcome::Cancelled);
+    t.add_workflow_task_scheduled_and_started();
+
+    let mut mh = MockPollCfg::from_resp_batches(
+        "fakeid",
+        t,
+        [ResponseType::AllHistory],
+        mock_workflow_client(),
+    );
+    mh.completion_asserts_from_expectations(|mut asserts| {
+        asserts.then(|_| {}).then(move |wft| {
+            assert_eq!(wft.commands.len(), 1);
+            assert_eq!(
+                wft.commands[0].command_type(),
+                CommandType::RequestCancelNexusOperation
+            );
+            assert_matches!(
+                wft.commands[0].attributes.as_ref().unwrap(),
+                command::Attributes::RequestCancelNexusOperationCommandAttributes(a)
+                if a.scheduled_event_id == scheduled_event_id
+            );
+        });
+    });
+    let core = mock_worker(build_mock_pollers(mh));
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        schedule_cmd().into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::ResolveNexusOperationStart(_)),
+        }]
+    );
+    // Cancelling twice only requests cancellation once
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        act.run_id,
+        vec![
+            RequestCancelNexusOperation { seq: 1 }.into(),
+            RequestCancelNexusOperation { seq: 1 }.into(),
+        ],
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(act.jobs.len(), 1);
+    assert_outcome(&act.jobs[0], Outcome::Cancelled);
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn mismatched_scheduled_event_is_nondeterministic() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let scheduled_event_id = add_scheduled(&mut t);
+    t.add(NexusOperationStartedEventAttributes {
+        scheduled_event_id,
+        operation_id: "op-id".to_string(),
+        ..Default::default()
+    });
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mut mh = MockPollCfg::from_resp_batches(
+        "fakeid",
+        t,
+        // History is delivered again after the eviction
+        [ResponseType::AllHistory, ResponseType::AllHistory],
+        mock_workflow_client(),
+    );
+    mh.num_expected_fails = 1;
+    mh.expect_fail_wft_matcher =
+        Box::new(|_, cause, _| matches!(cause, WorkflowTaskFailedCause::NonDeterministicError));
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 2);
+    let core = mock_worker(mock);
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        ScheduleNexusOperation {
+            operation: "other-operation".to_string(),
+            ..schedule_cmd()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(act.run_id))
+        .await
+        .unwrap();
+
+    // Starting over and scheduling the recorded operation gets past the scheduled event
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        schedule_cmd().into(),
+    ))
+    .await
+    .unwrap();
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::ResolveNexusOperationStart(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+    core.shutdown().await;
+}
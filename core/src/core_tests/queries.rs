@@ -6,21 +6,25 @@ use crate::{
     worker::{client::mocks::mock_workflow_client, LEGACY_QUERY_ID},
 };
 use futures_util::stream;
+use prost::Message;
 use std::{
     collections::{HashMap, VecDeque},
     time::Duration,
 };
 use temporal_sdk_core_api::Worker as WorkerTrait;
 use temporal_sdk_core_protos::{
+    constants::{STACK_TRACE_QUERY_TYPE, WORKFLOW_METADATA_QUERY_TYPE},
     coresdk::{
         workflow_activation::{
             remove_from_cache::EvictionReason, workflow_activation_job, WorkflowActivationJob,
         },
         workflow_commands::{
             query_result, ActivityCancellationType, CompleteWorkflowExecution,
-            ContinueAsNewWorkflowExecution, QueryResult, RequestCancelActivity,
+            ContinueAsNewWorkflowExecution, QueryResult, QuerySuccess, RequestCancelActivity,
+            SetWorkflowMetadata,
         },
-        workflow_completion::WorkflowActivationCompletion,
+        workflow_completion::{StackTrace, Success, TaskStack, WorkflowActivationCompletion},
+        FromJsonPayloadExt,
     },
     temporal::api::{
         common::v1::Payload,
@@ -28,11 +32,12 @@ use temporal_sdk_core_protos::{
         failure::v1::Failure,
         history::v1::{history_event, ActivityTaskCancelRequestedEventAttributes, History},
         query::v1::WorkflowQuery,
+        sdk::v1::{WorkflowDefinition, WorkflowInteractionDefinition, WorkflowMetadata},
         workflowservice::v1::{
             GetWorkflowExecutionHistoryResponse, RespondWorkflowTaskCompletedResponse,
         },
     },
-    TestHistoryBuilder,
+    TestHistoryBuilder, DEFAULT_WORKFLOW_TYPE,
 };
 use temporal_sdk_core_test_utils::{
     query_ok, schedule_activity_cmd, start_timer_cmd, WorkerTestHelpers,
@@ -724,6 +729,140 @@ async fn new_query_fail() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn oversized_query_answer_fails_query_not_workflow() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = VecDeque::from(vec![{
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), 1.into());
+        pr.queries = HashMap::new();
+        pr.queries.insert(
+            "q1".to_string(),
+            WorkflowQuery {
+                query_type: "query-type".to_string(),
+                query_args: Some(b"hi".into()),
+                header: Default::default(),
+            },
+        );
+        pr
+    }]);
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_workflow_task()
+        .times(1)
+        .returning(|resp| {
+            // The workflow's own command goes out untouched, only the answer is failed
+            assert_eq!(resp.commands.len(), 1);
+            assert_eq!(resp.commands[0].command_type(), CommandType::StartTimer);
+            assert_matches!(
+                resp.query_responses.as_slice(),
+                [QueryResult {
+                    variant: Some(query_result::Variant::Failed(f)),
+                    ..
+                }] if f.message.contains("exceeding the limit of 1000 bytes")
+            );
+            Ok(RespondWorkflowTaskCompletedResponse::default())
+        });
+
+    let mut mock = single_hist_mock_sg(wfid, t, tasks, mock_client, true);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 10;
+        wc.payload_size_limits.completion_error_bytes = Some(1000);
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs[0],
+        WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::QueryWorkflow(_)),
+        }
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        query_ok(
+            "q1",
+            Payload {
+                data: vec![0; 2000],
+                ..Default::default()
+            },
+        ),
+    ))
+    .await
+    .unwrap();
+    core.drain_pollers_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn oversized_legacy_query_answer_fails_query() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = [{
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), 1.into());
+        pr.query = Some(WorkflowQuery {
+            query_type: "query-type".to_string(),
+            query_args: Some(b"hi".into()),
+            header: None,
+        });
+        pr
+    }];
+    let mut mock = mock_workflow_client();
+    mock.expect_respond_legacy_query()
+        .times(1)
+        .returning(|_, qr| {
+            assert_matches!(
+                qr.variant,
+                Some(query_result::Variant::Failed(f))
+                    if f.message.contains("exceeding the limit of 1000 bytes")
+            );
+            Ok(Default::default())
+        });
+    let mock = MockPollCfg::from_resp_batches(wfid, t, tasks, mock);
+    let mut mock = build_mock_pollers(mock);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 10;
+        wc.payload_size_limits.completion_error_bytes = Some(1000);
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::QueryWorkflow(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        query_ok(
+            LEGACY_QUERY_ID.to_string(),
+            Payload {
+                data: vec![0; 2000],
+                ..Default::default()
+            },
+        ),
+    ))
+    .await
+    .unwrap();
+
+    core.shutdown().await;
+}
+
 /// This test verifies that if we get a task with a legacy query in it while in the middle of
 /// processing some local-only work (in this case, resolving an activity as soon as it was
 /// cancelled) that we do not combine the legacy query with the resolve job.
@@ -980,3 +1119,453 @@ async fn queries_arent_lost_in_buffer_void() {
 
     core.shutdown().await;
 }
+
+#[rstest::rstest]
+#[tokio::test]
+async fn workflow_metadata_query_answered_by_core(#[values(false, true)] with_lang_query: bool) {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = VecDeque::from(vec![hist_to_poll_resp(&t, wfid.to_owned(), 1.into()), {
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), ResponseType::OneTask(2));
+        pr.queries = HashMap::from([(
+            "meta".to_string(),
+            WorkflowQuery {
+                query_type: WORKFLOW_METADATA_QUERY_TYPE.to_string(),
+                ..Default::default()
+            },
+        )]);
+        if with_lang_query {
+            pr.queries.insert(
+                "lang".to_string(),
+                WorkflowQuery {
+                    query_type: "query-type".to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        pr
+    }]);
+    let mut mh = MockPollCfg::from_resp_batches(wfid, t, tasks, mock_workflow_client());
+    mh.completion_asserts = Some(Box::new(move |c| {
+        if c.commands[0].command_type() != CommandType::CompleteWorkflowExecution {
+            return;
+        }
+        assert_eq!(c.query_responses.len(), 1 + with_lang_query as usize);
+        let resp = c
+            .query_responses
+            .iter()
+            .find(|qr| qr.query_id == "meta")
+            .unwrap();
+        let payload = assert_matches!(
+            &resp.variant,
+            Some(query_result::Variant::Succeeded(QuerySuccess { response: Some(p) })) => p
+        );
+        let metadata = WorkflowMetadata::decode(payload.data.as_slice()).unwrap();
+        let definition = metadata.definition.unwrap();
+        // Lang didn't provide a type, so core fills it in
+        assert_eq!(definition.r#type, DEFAULT_WORKFLOW_TYPE);
+        assert_eq!(definition.signal_definitions[0].name, "sig");
+        assert_eq!(metadata.current_details, "waiting");
+    }));
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 10;
+        wc.answer_workflow_metadata_queries = true;
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        task.run_id,
+        vec![
+            SetWorkflowMetadata {
+                metadata: Some(WorkflowMetadata {
+                    definition: Some(WorkflowDefinition {
+                        signal_definitions: vec![WorkflowInteractionDefinition {
+                            name: "sig".to_string(),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }),
+                    current_details: "waiting".to_string(),
+                }),
+            }
+            .into(),
+            start_timer_cmd(1, Duration::from_secs(1)),
+        ],
+    ))
+    .await
+    .unwrap();
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_execution(&task.run_id).await;
+
+    if with_lang_query {
+        // Only the query core can't answer makes it to lang
+        let task = core.poll_workflow_activation().await.unwrap();
+        assert_matches!(
+            task.jobs.as_slice(),
+            [WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::QueryWorkflow(q)),
+            }] => assert_eq!(q.query_id, "lang")
+        );
+        core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            task.run_id,
+            query_ok("lang", "response"),
+        ))
+        .await
+        .unwrap();
+    }
+    core.drain_pollers_and_shutdown().await;
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn workflow_metadata_legacy_query_goes_to_lang_unless_opted_in(
+    #[values(false, true)] opted_in: bool,
+) {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = [
+        hist_to_poll_resp(&t, wfid.to_owned(), 1.into()),
+        {
+            let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), 1.into());
+            pr.query = Some(WorkflowQuery {
+                query_type: WORKFLOW_METADATA_QUERY_TYPE.to_string(),
+                ..Default::default()
+            });
+            pr.started_event_id = 0;
+            pr
+        },
+        hist_to_poll_resp(&t, wfid.to_owned(), 2.into()),
+    ]
+    .map(|r| r.resp);
+
+    let mut mock = mock_workflow_client();
+    mock.expect_complete_workflow_task()
+        .returning(|_| Ok(Default::default()));
+    mock.expect_respond_legacy_query()
+        .times(1)
+        .withf(move |_, qr| {
+            let payload = assert_matches!(
+                &qr.variant,
+                Some(query_result::Variant::Succeeded(QuerySuccess { response: Some(p) })) => p
+            );
+            if opted_in {
+                let metadata = WorkflowMetadata::decode(payload.data.as_slice()).unwrap();
+                metadata.current_details == "waiting"
+                    && metadata.definition.unwrap().signal_definitions[0].name == "sig"
+            } else {
+                payload.data == b"from lang"
+            }
+        })
+        .returning(|_, _| Ok(Default::default()));
+    let mut mock = MocksHolder::from_wft_stream(mock, stream::iter(tasks));
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 1;
+        wc.answer_workflow_metadata_queries = opted_in;
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        task.run_id,
+        vec![
+            SetWorkflowMetadata {
+                metadata: Some(WorkflowMetadata {
+                    definition: Some(WorkflowDefinition {
+                        signal_definitions: vec![WorkflowInteractionDefinition {
+                            name: "sig".to_string(),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }),
+                    current_details: "waiting".to_string(),
+                }),
+            }
+            .into(),
+            start_timer_cmd(1, Duration::from_secs(1)),
+        ],
+    ))
+    .await
+    .unwrap();
+
+    // When opted in, core answers the query without involving lang
+    if !opted_in {
+        let task = core.poll_workflow_activation().await.unwrap();
+        assert_matches!(
+            task.jobs.as_slice(),
+            [WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::QueryWorkflow(q)),
+            }] => assert_eq!(q.query_type, WORKFLOW_METADATA_QUERY_TYPE)
+        );
+        core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            task.run_id,
+            query_ok(LEGACY_QUERY_ID, "from lang"),
+        ))
+        .await
+        .unwrap();
+    }
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::FireTimer(_)),
+        }]
+    );
+    core.complete_execution(&task.run_id).await;
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn stack_trace_queries_answered_from_reported_stacks() {
+    let wfid = "fake_wf_id";
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_we_signaled("sig", vec![]);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+    let stack_query = || {
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), 1.into());
+        pr.query = Some(WorkflowQuery {
+            query_type: STACK_TRACE_QUERY_TYPE.to_string(),
+            ..Default::default()
+        });
+        pr.started_event_id = 0;
+        pr
+    };
+    let tasks = [
+        hist_to_poll_resp(&t, wfid.to_owned(), 1.into()),
+        stack_query(),
+        stack_query(),
+        hist_to_poll_resp(&t, wfid.to_owned(), 2.into()),
+    ]
+    .map(|r| r.resp);
+
+    let mut mock = mock_workflow_client();
+    mock.expect_complete_workflow_task()
+        .returning(|_| Ok(Default::default()));
+    mock.expect_respond_legacy_query()
+        .times(2)
+        .withf(|_, qr| {
+            let payload = assert_matches!(
+                &qr.variant,
+                Some(query_result::Variant::Succeeded(QuerySuccess { response: Some(p) })) => p
+            );
+            String::from_json_payload(payload).unwrap() == "main:\nwaiting for signal\n"
+        })
+        .returning(|_, _| Ok(Default::default()));
+    let mut mock = MocksHolder::from_wft_stream(mock, stream::iter(tasks));
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 1;
+        wc.answer_stack_trace_queries = true;
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(task.run_id))
+        .await
+        .unwrap();
+
+    // No stacks were reported with the last completion, so lang is asked for them
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::ReportStackTrace(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion {
+        run_id: task.run_id,
+        status: Some(
+            Success {
+                stack_trace: Some(StackTrace {
+                    tasks: vec![TaskStack {
+                        name: "main".to_string(),
+                        stack: "waiting for signal".to_string(),
+                    }],
+                }),
+                ..Default::default()
+            }
+            .into(),
+        ),
+    })
+    .await
+    .unwrap();
+
+    // The second query is answered with the same stacks without lang being involved, so the next
+    // activation is for the signal
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::SignalWorkflow(_)),
+        }]
+    );
+    core.complete_execution(&task.run_id).await;
+
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn stack_trace_query_shipped_with_other_queries() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = VecDeque::from(vec![hist_to_poll_resp(&t, wfid.to_owned(), 1.into()), {
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), ResponseType::OneTask(2));
+        pr.queries = HashMap::new();
+        pr.queries.insert(
+            "q1".to_string(),
+            WorkflowQuery {
+                query_type: "query-type".to_string(),
+                ..Default::default()
+            },
+        );
+        pr.queries.insert(
+            "stack".to_string(),
+            WorkflowQuery {
+                query_type: STACK_TRACE_QUERY_TYPE.to_string(),
+                ..Default::default()
+            },
+        );
+        pr
+    }]);
+    let mut mh = MockPollCfg::from_resp_batches(wfid, t, tasks, mock_workflow_client());
+    mh.completion_asserts = Some(Box::new(|c| {
+        if c.commands[0].command_type() == CommandType::CompleteWorkflowExecution {
+            let mut ids = c
+                .query_responses
+                .iter()
+                .map(|qr| qr.query_id.as_str())
+                .collect::<Vec<_>>();
+            ids.sort();
+            assert_eq!(ids, vec!["q1", "stack"]);
+        }
+    }));
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 10;
+        wc.answer_stack_trace_queries = true;
+    });
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+
+    // Lang is asked for its stacks in the same activation as the other query
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(task.jobs.len(), 2);
+    assert!(task.jobs.iter().any(|j| matches!(
+        j.variant,
+        Some(workflow_activation_job::Variant::ReportStackTrace(_))
+    )));
+    core.complete_workflow_activation(WorkflowActivationCompletion {
+        run_id: task.run_id,
+        status: Some(
+            Success {
+                commands: vec![query_ok("q1", "hi").into()],
+                stack_trace: Some(StackTrace {
+                    tasks: vec![TaskStack {
+                        name: "main".to_string(),
+                        stack: "done".to_string(),
+                    }],
+                }),
+                ..Default::default()
+            }
+            .into(),
+        ),
+    })
+    .await
+    .unwrap();
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn stack_trace_queries_go_to_lang_unless_opted_in() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let tasks = [
+        hist_to_poll_resp(&t, wfid.to_owned(), 1.into()),
+        {
+            let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), 1.into());
+            pr.query = Some(WorkflowQuery {
+                query_type: STACK_TRACE_QUERY_TYPE.to_string(),
+                ..Default::default()
+            });
+            pr.started_event_id = 0;
+            pr
+        },
+        hist_to_poll_resp(&t, wfid.to_owned(), 2.into()),
+    ]
+    .map(|r| r.resp);
+
+    let mut mock = mock_workflow_client();
+    mock.expect_complete_workflow_task()
+        .returning(|_| Ok(Default::default()));
+    mock.expect_respond_legacy_query()
+        .times(1)
+        .withf(|_, qr| {
+            let payload = assert_matches!(
+                &qr.variant,
+                Some(query_result::Variant::Succeeded(QuerySuccess { response: Some(p) })) => p
+            );
+            payload.data == b"from lang"
+        })
+        .returning(|_, _| Ok(Default::default()));
+    let mut mock = MocksHolder::from_wft_stream(mock, stream::iter(tasks));
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    let core = mock_worker(mock);
+
+    // Stacks reported by lang aren't used to answer the query
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion {
+        run_id: task.run_id,
+        status: Some(
+            Success {
+                commands: vec![start_timer_cmd(1, Duration::from_secs(1)).into()],
+                stack_trace: Some(StackTrace {
+                    tasks: vec![TaskStack {
+                        name: "main".to_string(),
+                        stack: "waiting for timer".to_string(),
+                    }],
+                }),
+                ..Default::default()
+            }
+            .into(),
+        ),
+    })
+    .await
+    .unwrap();
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::QueryWorkflow(q)),
+        }] => assert_eq!(q.query_type, STACK_TRACE_QUERY_TYPE)
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        query_ok(LEGACY_QUERY_ID, "from lang"),
+    ))
+    .await
+    .unwrap();
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    core.complete_execution(&task.run_id).await;
+    core.shutdown().await;
+}
@@ -1,12 +1,24 @@
-use crate::test_help::{build_mock_pollers, mock_worker, MockPollCfg, ResponseType};
+use crate::{
+    prost_dur,
+    test_help::{build_mock_pollers, hist_to_poll_resp, mock_worker, MockPollCfg, ResponseType},
+};
 use temporal_sdk_core_api::Worker;
 use temporal_sdk_core_protos::{
     coresdk::{
         workflow_activation::{workflow_activation_job, WorkflowActivationJob},
-        workflow_commands::{update_response::Response, CompleteWorkflowExecution, UpdateResponse},
+        workflow_commands::{
+            update_response::Response, CompleteWorkflowExecution, StartTimer, UpdateResponse,
+        },
         workflow_completion::WorkflowActivationCompletion,
     },
-    temporal::api::{common::v1::Payload, enums::v1::EventType},
+    temporal::api::{
+        common::v1::Payload,
+        enums::v1::EventType,
+        history::v1::TimerFiredEventAttributes,
+        protocol::v1::{message::SequencingId, Message},
+        update,
+    },
+    utilities::pack_any,
     TestHistoryBuilder,
 };
 
@@ -71,3 +83,142 @@ async fn replay_with_empty_first_task() {
     .await
     .unwrap();
 }
+
+#[tokio::test]
+async fn redelivered_update_requests_reach_lang_once() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let request = update_request("upd1", 2);
+    let mut resp = hist_to_poll_resp(&t, "fake_wf_id", 1.into()).resp;
+    resp.messages = vec![request.clone(), request];
+    let mock = MockPollCfg::from_resps(t, [ResponseType::Raw(resp)]);
+    let core = mock_worker(build_mock_pollers(mock));
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::DoUpdate(_)),
+            },
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::StartWorkflow(_)),
+            },
+        ]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        task.run_id,
+        vec![
+            UpdateResponse {
+                protocol_instance_id: "upd1".to_string(),
+                response: Some(Response::Rejected("nope".into())),
+            }
+            .into(),
+            CompleteWorkflowExecution { result: None }.into(),
+        ],
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn update_requests_delivered_in_history_order() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+    t.add(TimerFiredEventAttributes {
+        started_event_id: timer_started_event_id,
+        timer_id: "1".to_string(),
+    });
+    t.add_workflow_task_scheduled_and_started();
+
+    // Neither request can be delivered until the timer has fired, and the second is listed first
+    let mut resp = hist_to_poll_resp(&t, "fake_wf_id", ResponseType::AllHistory).resp;
+    resp.messages = vec![
+        update_request("upd2", 7),
+        update_request("upd1", 6),
+        update_request("upd2", 7),
+    ];
+    let mock = MockPollCfg::from_resps(t, [ResponseType::Raw(resp)]);
+    let mut mock = build_mock_pollers(mock);
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    let core = mock_worker(mock);
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        task.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::StartWorkflow(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        task.run_id,
+        StartTimer {
+            seq: 1,
+            start_to_fire_timeout: Some(prost_dur!(from_secs(1))),
+            ..Default::default()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+
+    let task = core.poll_workflow_activation().await.unwrap();
+    let updates = task
+        .jobs
+        .iter()
+        .filter_map(|j| match &j.variant {
+            Some(workflow_activation_job::Variant::DoUpdate(u)) => {
+                Some(u.protocol_instance_id.as_str())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(updates, ["upd1", "upd2"]);
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+        task.run_id,
+        vec![
+            UpdateResponse {
+                protocol_instance_id: "upd1".to_string(),
+                response: Some(Response::Rejected("nope".into())),
+            }
+            .into(),
+            UpdateResponse {
+                protocol_instance_id: "upd2".to_string(),
+                response: Some(Response::Rejected("nope".into())),
+            }
+            .into(),
+            CompleteWorkflowExecution { result: None }.into(),
+        ],
+    ))
+    .await
+    .unwrap();
+}
+
+fn update_request(update_id: &str, sequenced_after_event: i64) -> Message {
+    Message {
+        id: format!("{update_id}/request"),
+        protocol_instance_id: update_id.to_string(),
+        sequencing_id: Some(SequencingId::EventId(sequenced_after_event)),
+        body: Some(
+            pack_any(
+                "type.googleapis.com/temporal.api.update.v1.Request".to_string(),
+                &update::v1::Request {
+                    meta: Some(update::v1::Meta {
+                        update_id: update_id.to_string(),
+                        identity: "fake".to_string(),
+                    }),
+                    input: Some(update::v1::Input {
+                        name: "update".to_string(),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .unwrap(),
+        ),
+    }
+}
@@ -39,6 +39,7 @@ async fn after_shutdown_of_worker_get_shutdown_err() {
                 workflow_command::Variant::StartTimer(StartTimer {
                     seq: 1,
                     start_to_fire_timeout: Some(prost_dur!(from_secs(1))),
+                    ..Default::default()
                 }),
             ))
             .await
@@ -86,6 +87,44 @@ async fn shutdown_worker_can_complete_pending_activation() {
     });
 }
 
+#[tokio::test]
+async fn backpressure_holds_activations_until_lifted() {
+    let t = canned_histories::single_timer("1");
+    let worker = build_fake_worker("fake_wf_id", t, [1]);
+    worker.set_backpressure(true);
+    let poll = worker.poll_workflow_activation();
+    tokio::pin!(poll);
+    assert!(tokio::time::timeout(Duration::from_millis(100), &mut poll)
+        .await
+        .is_err());
+
+    worker.set_backpressure(false);
+    let res = poll.await.unwrap();
+    assert_eq!(res.jobs.len(), 1);
+}
+
+#[tokio::test]
+async fn backpressure_does_not_hold_up_shutdown() {
+    let t = canned_histories::single_timer("1");
+    let worker = build_fake_worker("fake_wf_id", t, [1]);
+    let res = worker.poll_workflow_activation().await.unwrap();
+    worker.set_backpressure(true);
+
+    tokio::join!(worker.shutdown(), async {
+        worker
+            .complete_workflow_activation(WorkflowActivationCompletion::from_cmds(
+                res.run_id,
+                vec![start_timer_cmd(1, Duration::from_secs(1))],
+            ))
+            .await
+            .unwrap();
+        assert_matches!(
+            worker.poll_workflow_activation().await.unwrap_err(),
+            PollWfError::ShutDown
+        );
+    });
+}
+
 #[tokio::test]
 async fn worker_shutdown_during_poll_doesnt_deadlock() {
     let (tx, rx) = watch::channel(false);
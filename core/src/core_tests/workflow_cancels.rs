@@ -1,17 +1,32 @@
 use crate::{
     job_assert,
     test_help::{
-        build_fake_worker, canned_histories, gen_assert_and_reply, poll_and_reply, ResponseType,
-        WorkflowCachingPolicy::NonSticky,
+        build_fake_worker, build_mock_pollers, canned_histories, gen_assert_and_reply, mock_worker,
+        poll_and_reply, MockPollCfg, ResponseType, WorkflowCachingPolicy::NonSticky,
     },
 };
 use rstest::rstest;
 use std::time::Duration;
-use temporal_sdk_core_protos::coresdk::{
-    workflow_activation::{workflow_activation_job, WorkflowActivationJob},
-    workflow_commands::{
-        CancelWorkflowExecution, CompleteWorkflowExecution, FailWorkflowExecution,
+use temporal_sdk_core_api::Worker;
+use temporal_sdk_core_protos::{
+    coresdk::{
+        workflow_activation::{
+            workflow_activation_job, CancelWorkflow, NotifyWorkflowTerminated,
+            WorkflowActivationJob,
+        },
+        workflow_commands::{
+            CancelWorkflowExecution, CompleteWorkflowExecution, FailWorkflowExecution,
+        },
+        workflow_completion::WorkflowActivationCompletion,
     },
+    temporal::api::{
+        enums::v1::EventType,
+        history::v1::{
+            WorkflowExecutionCancelRequestedEventAttributes,
+            WorkflowExecutionTerminatedEventAttributes, WorkflowExecutionTimedOutEventAttributes,
+        },
+    },
+    TestHistoryBuilder,
 };
 use temporal_sdk_core_test_utils::start_timer_cmd;
 
@@ -122,3 +137,111 @@ async fn immediate_cancel() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn termination_reported_when_seen_in_history() {
+    let mut t = canned_histories::single_timer("1");
+    t.add(WorkflowExecutionTerminatedEventAttributes {
+        reason: "no longer needed".to_string(),
+        details: None,
+        identity: "operator".to_string(),
+    });
+    let mut mock = build_mock_pollers(MockPollCfg::from_resps(t, [ResponseType::AllHistory]));
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    let core = mock_worker(mock);
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::FireTimer(_)),
+            },
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::NotifyWorkflowTerminated(
+                    NotifyWorkflowTerminated { reason, identity, .. }
+                )),
+            }
+        ] => {
+            assert_eq!(reason, "no longer needed");
+            assert_eq!(identity, "operator");
+        }
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn task_interrupted_by_timeout_replays_cleanly() {
+    let mut t = canned_histories::single_timer("1");
+    t.add(WorkflowExecutionTimedOutEventAttributes::default());
+    let mut mock = build_mock_pollers(MockPollCfg::from_resps(t, [ResponseType::AllHistory]));
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    let core = mock_worker(mock);
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::StartWorkflow(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn cancel_identity_reported_on_replay() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add(WorkflowExecutionCancelRequestedEventAttributes {
+        cause: "no longer needed".to_string(),
+        identity: "operator".to_string(),
+        ..Default::default()
+    });
+    t.add_full_wf_task();
+    t.add_cancelled();
+    let core = build_fake_worker("fake_wf_id", t, [ResponseType::AllHistory]);
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        act.jobs.as_slice(),
+        [
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::StartWorkflow(_)),
+            },
+            WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::CancelWorkflow(CancelWorkflow {
+                    reason,
+                    identity,
+                    ..
+                })),
+            }
+        ] => {
+            assert_eq!(reason, "no longer needed");
+            assert_eq!(identity, "operator");
+        }
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CancelWorkflowExecution {}.into(),
+    ))
+    .await
+    .unwrap();
+}
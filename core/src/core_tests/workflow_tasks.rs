@@ -4,10 +4,11 @@ use crate::{
     job_assert,
     replay::TestHistoryBuilder,
     test_help::{
-        build_fake_worker, build_mock_pollers, build_multihist_mock_sg, canned_histories,
-        gen_assert_and_fail, gen_assert_and_reply, hist_to_poll_resp, mock_sdk, mock_sdk_cfg,
-        mock_worker, poll_and_reply, poll_and_reply_clears_outstanding_evicts, single_hist_mock_sg,
-        test_worker_cfg, FakeWfResponses, MockPollCfg, MocksHolder, ResponseType, WorkerExt,
+        build_fake_sdk, build_fake_worker, build_mock_pollers, build_multihist_mock_sg,
+        canned_histories, gen_assert_and_fail, gen_assert_and_reply, hist_to_poll_resp, mock_sdk,
+        mock_sdk_cfg, mock_worker, poll_and_reply, poll_and_reply_clears_outstanding_evicts,
+        single_hist_mock_sg, test_worker_cfg, FakeWfResponses, MockPollCfg, MocksHolder,
+        ResponseType, WorkerExt,
         WorkflowCachingPolicy::{self, AfterEveryReply, NonSticky},
     },
     worker::client::mocks::{mock_manual_workflow_client, mock_workflow_client},
@@ -22,34 +23,38 @@ use std::{
         mpsc::sync_channel,
         Arc,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use temporal_client::WorkflowOptions;
 use temporal_sdk::{ActivityOptions, CancellableFuture, WfContext};
-use temporal_sdk_core_api::{errors::PollWfError, Worker as WorkerTrait};
+use temporal_sdk_core_api::{errors::PollWfError, worker::LangFlagRegistry, Worker as WorkerTrait};
 use temporal_sdk_core_protos::{
     coresdk::{
         activity_result::{self as ar, activity_resolution, ActivityResolution},
         common::VersioningIntent,
         workflow_activation::{
-            remove_from_cache::EvictionReason, workflow_activation_job, FireTimer, ResolveActivity,
-            StartWorkflow, UpdateRandomSeed, WorkflowActivationJob,
+            notify_late_resolution, remove_from_cache::EvictionReason, workflow_activation_job,
+            FireTimer, NotifyLateResolution, ResolveActivity, StartWorkflow, UpdateRandomSeed,
+            WorkflowActivation, WorkflowActivationJob,
         },
         workflow_commands::{
             ActivityCancellationType, CancelTimer, CompleteWorkflowExecution,
             ContinueAsNewWorkflowExecution, FailWorkflowExecution, RequestCancelActivity,
             ScheduleActivity, SetPatchMarker, StartChildWorkflowExecution,
+            UpsertWorkflowSearchAttributes,
         },
         workflow_completion::WorkflowActivationCompletion,
+        AsJsonPayloadExt,
     },
     default_act_sched, default_wes_attribs,
+    search_attributes::SearchAttributeSchema,
     temporal::api::{
         command::v1::command::Attributes,
         common::v1::{Payload, RetryPolicy, WorkerVersionStamp},
-        enums::v1::{EventType, WorkflowTaskFailedCause},
-        failure::v1::Failure,
+        enums::v1::{ContinueAsNewInitiator, EventType, IndexedValueType, WorkflowTaskFailedCause},
+        failure::v1::{failure::FailureInfo, Failure},
         history::v1::{
-            history_event, TimerFiredEventAttributes,
+            history_event, ActivityTaskFailedEventAttributes, TimerFiredEventAttributes,
             WorkflowPropertiesModifiedExternallyEventAttributes,
         },
         workflowservice::v1::{
@@ -503,6 +508,94 @@ async fn abandoned_activities_ignore_start_and_complete(hist_batches: &'static [
     worker.run_until_done().await.unwrap();
 }
 
+#[rstest]
+#[case::incremental(&[1, 2, 3])]
+#[case::replay(&[3])]
+#[tokio::test]
+async fn abandoned_activity_late_resolution(
+    #[case] hist_batches: &'static [usize],
+    #[values(true, false)] deliver_late_resolution: bool,
+) {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    let scheduled_event_id = t.add_activity_task_scheduled("1");
+    let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+    t.add_timer_fired(timer_started_event_id, "1".to_string());
+    t.add_full_wf_task();
+    let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+    let started_event_id = t.add_activity_task_started(scheduled_event_id);
+    t.add(ActivityTaskFailedEventAttributes {
+        scheduled_event_id,
+        started_event_id,
+        failure: Some(Failure::application_failure("oh no".to_string(), false)),
+        ..Default::default()
+    });
+    t.add_timer_fired(timer_started_event_id, "2".to_string());
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+    let core = build_fake_worker("fake_wf_id", t, hist_batches);
+
+    // The failure is only passed on to lang if it asked for it
+    let last_activation: &dyn Fn(&WorkflowActivation) = if deliver_late_resolution {
+        &job_assert!(
+            workflow_activation_job::Variant::NotifyLateResolution(NotifyLateResolution {
+                resolution: Some(notify_late_resolution::Resolution::Activity(
+                    ResolveActivity {
+                        seq: 1,
+                        result: Some(ActivityResolution {
+                            status: Some(activity_resolution::Status::Failed(_)),
+                        })
+                    }
+                )),
+            }),
+            workflow_activation_job::Variant::FireTimer(_)
+        )
+    } else {
+        &job_assert!(workflow_activation_job::Variant::FireTimer(_))
+    };
+    poll_and_reply(
+        &core,
+        NonSticky,
+        &[
+            gen_assert_and_reply(
+                &job_assert!(workflow_activation_job::Variant::StartWorkflow(_)),
+                vec![
+                    ScheduleActivity {
+                        seq: 1,
+                        activity_id: "1".to_string(),
+                        cancellation_type: ActivityCancellationType::Abandon as i32,
+                        deliver_late_resolution,
+                        ..default_act_sched()
+                    }
+                    .into(),
+                    start_timer_cmd(1, Duration::from_secs(1)),
+                ],
+            ),
+            gen_assert_and_reply(
+                &job_assert!(workflow_activation_job::Variant::FireTimer(_)),
+                vec![RequestCancelActivity { seq: 1 }.into()],
+            ),
+            gen_assert_and_reply(
+                &job_assert!(workflow_activation_job::Variant::ResolveActivity(
+                    ResolveActivity {
+                        seq: 1,
+                        result: Some(ActivityResolution {
+                            status: Some(activity_resolution::Status::Cancelled(..)),
+                        })
+                    }
+                )),
+                vec![start_timer_cmd(2, Duration::from_secs(1))],
+            ),
+            gen_assert_and_reply(
+                last_activation,
+                vec![CompleteWorkflowExecution { result: None }.into()],
+            ),
+        ],
+    )
+    .await;
+}
+
 #[rstest(hist_batches, case::incremental(&[1, 3]), case::replay(&[3]))]
 #[tokio::test]
 async fn scheduled_activity_cancellation_try_cancel_task_canceled(hist_batches: &'static [usize]) {
@@ -1685,6 +1778,17 @@ async fn history_byte_size_and_can_suggestion_in_activation() {
     let activation = worker.poll_workflow_activation().await.unwrap();
     assert_eq!(activation.history_size_bytes, 70);
     assert!(activation.continue_as_new_suggested);
+    // Evictions carry the latest values too
+    worker.request_workflow_eviction(&activation.run_id);
+    worker
+        .complete_workflow_activation(WorkflowActivationCompletion::empty(activation.run_id))
+        .await
+        .unwrap();
+    let activation = worker.poll_workflow_activation().await.unwrap();
+    assert!(activation.is_only_eviction());
+    assert_eq!(activation.history_length, 7);
+    assert_eq!(activation.history_size_bytes, 70);
+    assert!(activation.continue_as_new_suggested);
 }
 
 /// This test verifies that WFTs which come as replies to completing a WFT are properly delivered
@@ -2359,8 +2463,10 @@ async fn ensure_fetching_fail_during_complete_sends_task_failure() {
     core.shutdown().await;
 }
 
+// Flags missing from the registry are warned about, but still recorded
+#[rstest]
 #[tokio::test]
-async fn lang_internal_flags() {
+async fn lang_internal_flags(#[values(false, true)] with_registry: bool) {
     let mut t = TestHistoryBuilder::default();
     t.add_by_type(EventType::WorkflowExecutionStarted);
     t.add_full_wf_task();
@@ -2382,7 +2488,12 @@ async fn lang_internal_flags() {
         assert_matches!(c.sdk_metadata.lang_used_flags.as_slice(), &[2]);
     }));
     let mut mock = build_mock_pollers(mh);
-    mock.worker_cfg(|wc| wc.max_cached_workflows = 1);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 1;
+        if with_registry {
+            wc.lang_flags = LangFlagRegistry::default().register(1, "first flag");
+        }
+    });
     let core = mock_worker(mock);
 
     let act = core.poll_workflow_activation().await.unwrap();
@@ -2402,6 +2513,46 @@ async fn lang_internal_flags() {
     core.shutdown().await;
 }
 
+// Histories which recorded lang flags newer than the registry knows of still replay, and report
+// those flags to lang
+#[tokio::test]
+async fn lang_flags_newer_than_registry_are_reported() {
+    let registry = LangFlagRegistry::default()
+        .register(1, "first flag")
+        .register(2, "second flag");
+    assert_eq!(registry.version(), 2);
+    assert_eq!(registry.description(1), Some("first flag"));
+    assert!(!registry.contains(3));
+    assert!(test_worker_cfg()
+        .lang_flags(LangFlagRegistry::default().register(0, "zero"))
+        .build()
+        .is_err());
+
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.set_flags_first_wft(&[], &[1, 3]);
+    t.add_workflow_execution_completed();
+
+    let mh = MockPollCfg::from_resp_batches(
+        "fake_wf_id",
+        t,
+        [ResponseType::AllHistory],
+        mock_workflow_client(),
+    );
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 1;
+        wc.lang_flags = registry;
+    });
+    let core = mock_worker(mock);
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(act.available_internal_flags.as_slice(), [1, 3]);
+    core.complete_execution(&act.run_id).await;
+    core.shutdown().await;
+}
+
 // Verify we send all core internal flags on the first non-replay WFT
 #[tokio::test]
 async fn core_internal_flags() {
@@ -2519,6 +2670,7 @@ async fn jobs_are_in_appropriate_order() {
             SetPatchMarker {
                 patch_id: p1.to_string(),
                 deprecated: false,
+                data: None,
             }
             .into(),
             start_timer_cmd(1, Duration::from_secs(1)),
@@ -2855,3 +3007,190 @@ async fn sets_build_id_from_wft_complete() {
         .unwrap();
     worker.run_until_done().await.unwrap();
 }
+
+#[tokio::test]
+async fn oversized_completion_fails_workflow() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_workflow_execution_failed();
+
+    let mut mh = MockPollCfg::from_resp_batches("fake_wf_id", t, [1], mock_workflow_client());
+    mh.completion_asserts_from_expectations(|mut asserts| {
+        asserts.then(|wft| {
+            // Retrying the task would only produce the same oversized completion, so the workflow
+            // is failed in place of whatever it was trying to do
+            assert_eq!(wft.commands.len(), 1);
+            assert_matches!(
+                wft.commands[0].attributes.as_ref().unwrap(),
+                Attributes::FailWorkflowExecutionCommandAttributes(a)
+                if a.failure.as_ref().is_some_and(|f| {
+                    f.message.contains("CompleteWorkflowExecution: 1 command(s)")
+                        && matches!(
+                            &f.failure_info,
+                            Some(FailureInfo::ApplicationFailureInfo(ai)) if ai.non_retryable
+                        )
+                })
+            );
+        });
+    });
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.payload_size_limits.command_error_bytes = Some(1000);
+    });
+    let core = mock_worker(mock);
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id.clone(),
+        CompleteWorkflowExecution {
+            result: Some(Payload {
+                data: vec![0; 2000],
+                ..Default::default()
+            }),
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+    // The run is evicted, since it thinks it sent the original commands
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        evict_act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    core.shutdown().await;
+}
+
+#[rstest]
+#[tokio::test]
+async fn start_delay_not_reported_as_cron_interval(#[values(false, true)] replay: bool) {
+    let delay = Duration::from_secs(60 * 60);
+    let mut t = TestHistoryBuilder::default();
+    t.add_wfe_started_with_start_delay(delay);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mock = if replay {
+        MockPollCfg::from_resps(t, [ResponseType::AllHistory])
+    } else {
+        MockPollCfg::from_hist_builder(t)
+    };
+    let core = mock_worker(build_mock_pollers(mock));
+
+    let act = core.poll_workflow_activation().await.unwrap();
+    let start = assert_matches!(
+        act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::StartWorkflow(s)),
+        }] => s
+    );
+    assert_eq!(start.start_delay, Some(delay.try_into().unwrap()));
+    assert_eq!(start.cron_schedule_to_schedule_interval, None);
+    // Workflow time is that of the first workflow task, after the delay
+    let started_at = SystemTime::try_from(start.start_time.clone().unwrap()).unwrap();
+    let wf_time = SystemTime::try_from(act.timestamp.clone().unwrap()).unwrap();
+    assert!(wf_time.duration_since(started_at).unwrap() >= delay);
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        act.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    ))
+    .await
+    .unwrap();
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn last_completion_result_and_failure_visible_to_workflow() {
+    let mut t = TestHistoryBuilder::default();
+    let mut wes_attrs = default_wes_attribs();
+    wes_attrs.cron_schedule = "* * * * *".to_string();
+    wes_attrs.initiator = ContinueAsNewInitiator::CronSchedule as i32;
+    wes_attrs.last_completion_result = Some("last".as_json_payload().unwrap().into());
+    wes_attrs.continued_failure = Some(Failure {
+        message: "run failed".to_string(),
+        ..Default::default()
+    });
+    t.add(wes_attrs);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mut worker = build_fake_sdk(MockPollCfg::from_hist_builder(t));
+    worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+        assert_eq!(
+            ctx.last_completion_result(),
+            Some("last".as_json_payload().unwrap())
+        );
+        assert_eq!(ctx.last_failure().unwrap().message, "run failed");
+        Ok(().into())
+    });
+    worker.run().await.unwrap();
+}
+
+#[rstest]
+#[case::upsert(false)]
+#[case::child_start(true)]
+#[tokio::test]
+async fn invalid_search_attributes_fail_wft(#[case] child_start: bool) {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_workflow_task_scheduled_and_started();
+
+    let mut mh = MockPollCfg::from_resp_batches("fake_wf_id", t, [1], mock_workflow_client());
+    mh.num_expected_fails = 1;
+    mh.expect_fail_wft_matcher = Box::new(|_, _, f| {
+        f.as_ref().is_some_and(|f| {
+            f.message.contains(
+                "Invalid search attributes: Search attribute Count couldn't be decoded: \"seven\" \
+                 isn't a valid Int; Search attribute Unknown isn't registered",
+            )
+        })
+    });
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.search_attribute_schema = Some(SearchAttributeSchema::from_iter([
+            ("Count".to_string(), IndexedValueType::Int),
+            ("Name".to_string(), IndexedValueType::Keyword),
+        ]));
+    });
+    let core = mock_worker(mock);
+
+    let search_attributes = HashMap::from([
+        ("Count".to_string(), "seven".as_json_payload().unwrap()),
+        ("Name".to_string(), "a-name".as_json_payload().unwrap()),
+        ("Unknown".to_string(), 1.as_json_payload().unwrap()),
+    ]);
+    let cmd = if child_start {
+        StartChildWorkflowExecution {
+            seq: 1,
+            workflow_id: "child".to_string(),
+            search_attributes,
+            ..Default::default()
+        }
+        .into()
+    } else {
+        UpsertWorkflowSearchAttributes { search_attributes }.into()
+    };
+    let act = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(act.run_id, cmd))
+        .await
+        .unwrap();
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        evict_act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    core.shutdown().await;
+}
@@ -108,6 +108,7 @@ impl TemporaliteConfig {
             port,
             args,
             has_test_service: false,
+            unix_socket: None,
             output,
             err_output,
         })
@@ -129,6 +130,11 @@ pub struct TemporalDevServerConfig {
     /// Port to use or obtains a free one if none given.
     #[builder(default)]
     pub port: Option<u16>,
+    /// If set, the server is also served on a Unix domain socket at this path, for clients which
+    /// connect through one. Connections to the socket are forwarded to the server's port. Only
+    /// supported on Unix.
+    #[builder(default)]
+    pub unix_socket: Option<PathBuf>,
     /// Sqlite DB filename if persisting or non-persistent if none.
     #[builder(default)]
     pub db_filename: Option<String>,
@@ -201,6 +207,7 @@ impl TemporalDevServerConfig {
             port,
             args,
             has_test_service: false,
+            unix_socket: self.unix_socket.clone(),
             output,
             err_output,
         })
@@ -216,6 +223,11 @@ pub struct TestServerConfig {
     /// Port to use or obtains a free one if none given.
     #[builder(default)]
     pub port: Option<u16>,
+    /// If set, the server is also served on a Unix domain socket at this path, for clients which
+    /// connect through one. Connections to the socket are forwarded to the server's port. Only
+    /// supported on Unix.
+    #[builder(default)]
+    pub unix_socket: Option<PathBuf>,
     /// Additional arguments to the test server.
     #[builder(default)]
     pub extra_args: Vec<String>,
@@ -253,6 +265,7 @@ impl TestServerConfig {
             port,
             args,
             has_test_service: true,
+            unix_socket: self.unix_socket.clone(),
             output,
             err_output,
         })
@@ -265,6 +278,7 @@ struct EphemeralServerConfig {
     port: u16,
     args: Vec<String>,
     has_test_service: bool,
+    unix_socket: Option<PathBuf>,
     output: Stdio,
     err_output: Stdio,
 }
@@ -276,11 +290,27 @@ pub struct EphemeralServer {
     pub target: String,
     /// Whether the target implements the gRPC TestService
     pub has_test_service: bool,
+    /// Absolute path of the Unix domain socket the frontend is also served on, if one was asked
+    /// for
+    pub unix_socket: Option<PathBuf>,
     child: tokio::process::Child,
+    forwarder: Option<SocketForwarder>,
 }
 
 impl EphemeralServer {
     async fn start(config: EphemeralServerConfig) -> anyhow::Result<EphemeralServer> {
+        let target = format!("127.0.0.1:{}", config.port);
+        // The socket is bound before the process is started, so that failing to bind it cannot
+        // leave the process running without anything to stop it
+        let forwarder = config
+            .unix_socket
+            .map(|path| SocketForwarder::start(path, config.port))
+            .transpose()?;
+        let unix_socket = forwarder.as_ref().map(|f| f.path.clone());
+        let target_url = match unix_socket.as_deref() {
+            Some(path) => unix_socket_url(path)?,
+            None => Url::parse(&format!("http://{target}"))?,
+        };
         // Start process
         let child = tokio::process::Command::new(config.exe_path)
             .args(config.args)
@@ -288,12 +318,12 @@ impl EphemeralServer {
             .stdout(config.output)
             .stderr(config.err_output)
             .spawn()?;
-        let target = format!("127.0.0.1:{}", config.port);
-        let target_url = format!("http://{target}");
         let success = Ok(EphemeralServer {
             target,
             has_test_service: config.has_test_service,
+            unix_socket,
             child,
+            forwarder,
         });
 
         // Try to connect every 100ms for 5s
@@ -301,7 +331,7 @@ impl EphemeralServer {
         // server is up?
         let client_options = ClientOptionsBuilder::default()
             .identity("online_checker".to_owned())
-            .target_url(Url::parse(&target_url)?)
+            .target_url(target_url)
             .client_name("online-checker".to_owned())
             .client_version("0.1.0".to_owned())
             .build()?;
@@ -314,12 +344,21 @@ impl EphemeralServer {
         Err(anyhow!("Failed connecting to test server after 5 seconds"))
     }
 
+    /// URL for [temporal_client::ClientOptions::target_url] which connects through
+    /// [Self::unix_socket], if the server is served on one
+    pub fn unix_socket_url(&self) -> Option<Url> {
+        self.unix_socket
+            .as_deref()
+            .and_then(|p| unix_socket_url(p).ok())
+    }
+
     /// Shutdown the server (i.e. kill the child process). This does not attempt
     /// a kill if the child process appears completed, but such a check is not
     /// atomic so a kill could still fail as completed if completed just before
     /// kill.
     #[cfg(not(target_family = "unix"))]
     pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        self.forwarder.take();
         // Only kill if there is a PID
         if self.child.id().is_some() {
             Ok(self.child.kill().await?)
@@ -343,6 +382,7 @@ impl EphemeralServer {
         // run on Linux with Python 3.7 (does not happen on Python 3.10 nor does
         // it happen on Temporalite nor does it happen in Rust integration
         // tests). Don't alter without running that scenario. EX: SIGINT works but not SIGKILL
+        self.forwarder.take();
         if let Some(pid) = self.child.id() {
             let nix_pid = nix::unistd::Pid::from_raw(pid as i32);
             Ok(spawn_blocking(move || {
@@ -357,6 +397,65 @@ impl EphemeralServer {
     }
 }
 
+fn unix_socket_url(path: &Path) -> anyhow::Result<Url> {
+    Ok(Url::parse(&format!("unix://{}", path.display()))?)
+}
+
+/// Forwards connections made to a Unix domain socket to the server's port, until dropped
+#[derive(Debug)]
+struct SocketForwarder {
+    path: PathBuf,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl SocketForwarder {
+    #[cfg(target_family = "unix")]
+    fn start(path: PathBuf, port: u16) -> anyhow::Result<Self> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = std::env::current_dir()?.join(path);
+        // Sockets aren't removed when a process dies, so one may be left from an earlier run
+        if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_socket()) {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        let task = tokio::spawn(async move {
+            loop {
+                let mut inbound = match listener.accept().await {
+                    Ok((inbound, _)) => inbound,
+                    Err(e) => {
+                        warn!(error=%e, "Couldn't accept ephemeral server socket connection");
+                        sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                tokio::spawn(async move {
+                    if let Ok(mut outbound) =
+                        tokio::net::TcpStream::connect(("127.0.0.1", port)).await
+                    {
+                        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                    }
+                });
+            }
+        });
+        Ok(Self { path, task })
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    fn start(_: PathBuf, _: u16) -> anyhow::Result<Self> {
+        Err(anyhow!(
+            "Serving ephemeral servers on Unix domain sockets is only supported on Unix"
+        ))
+    }
+}
+
+impl Drop for SocketForwarder {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
 /// Where to find an executable. Can be a path or download.
 #[derive(Debug, Clone)]
 pub enum EphemeralExe {
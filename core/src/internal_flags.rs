@@ -38,6 +38,10 @@ pub(crate) enum InternalFlags {
         lang: BTreeSet<u32>,
         core_since_last_complete: HashSet<CoreInternalFlags>,
         lang_since_last_complete: HashSet<u32>,
+        /// Flags first sent with the most recent WFT completion. The server may yet drop that
+        /// task, so they're only known to be recorded once another completion is sent.
+        core_last_sent: Vec<CoreInternalFlags>,
+        lang_last_sent: Vec<u32>,
     },
     Disabled,
 }
@@ -50,6 +54,8 @@ impl InternalFlags {
                 lang: Default::default(),
                 core_since_last_complete: Default::default(),
                 lang_since_last_complete: Default::default(),
+                core_last_sent: Default::default(),
+                lang_last_sent: Default::default(),
             },
             false => Self::Disabled,
         }
@@ -124,13 +130,15 @@ impl InternalFlags {
                 lang_since_last_complete,
                 core,
                 lang,
+                core_last_sent,
+                lang_last_sent,
             } => {
-                let core_newly_used: Vec<_> = core_since_last_complete
+                *core_last_sent = core_since_last_complete
                     .iter()
                     .filter(|f| !core.contains(f))
-                    .map(|p| *p as u32)
+                    .copied()
                     .collect();
-                let lang_newly_used: Vec<_> = lang_since_last_complete
+                *lang_last_sent = lang_since_last_complete
                     .iter()
                     .filter(|f| !lang.contains(f))
                     .copied()
@@ -138,8 +146,8 @@ impl InternalFlags {
                 core.extend(core_since_last_complete.iter());
                 lang.extend(lang_since_last_complete.iter());
                 WorkflowTaskCompletedMetadata {
-                    core_used_flags: core_newly_used,
-                    lang_used_flags: lang_newly_used,
+                    core_used_flags: core_last_sent.iter().map(|p| *p as u32).collect(),
+                    lang_used_flags: lang_last_sent.clone(),
                     sdk_name: "".to_string(),
                     sdk_version: "".to_string(),
                 }
@@ -148,6 +156,27 @@ impl InternalFlags {
         }
     }
 
+    /// Forgets that the flags sent with the most recent WFT completion were recorded, because the
+    /// server dropped that task (ex: a speculative task for a rejected update). They are sent
+    /// again with the next completion.
+    pub fn forget_last_sent(&mut self) {
+        if let Self::Enabled {
+            core,
+            lang,
+            core_last_sent,
+            lang_last_sent,
+            ..
+        } = self
+        {
+            for f in core_last_sent.drain(..) {
+                core.remove(&f);
+            }
+            for f in lang_last_sent.drain(..) {
+                lang.remove(&f);
+            }
+        }
+    }
+
     pub fn all_lang(&self) -> impl Iterator<Item = u32> + '_ {
         match self {
             Self::Enabled { lang, .. } => Either::Left(lang.iter().copied()),
@@ -230,4 +259,26 @@ mod tests {
         assert_matches!(gathered.core_used_flags.as_slice(), &[]);
         assert_matches!(gathered.lang_used_flags.as_slice(), &[]);
     }
+
+    #[test]
+    fn flags_from_dropped_wft_are_sent_again() {
+        let mut f = InternalFlags::new(&Capabilities {
+            sdk_metadata: true,
+            ..Default::default()
+        });
+        f.add_lang_used([1]);
+        f.try_use(CoreInternalFlags::IdAndTypeDeterminismChecks, true);
+        f.gather_for_wft_complete();
+        f.forget_last_sent();
+        assert!(!f.try_use(CoreInternalFlags::IdAndTypeDeterminismChecks, false));
+
+        let gathered = f.gather_for_wft_complete();
+        assert_matches!(gathered.core_used_flags.as_slice(), &[1]);
+        assert_matches!(gathered.lang_used_flags.as_slice(), &[1]);
+        // Only the most recent completion can be dropped
+        f.gather_for_wft_complete();
+        f.forget_last_sent();
+        let gathered = f.gather_for_wft_complete();
+        assert_matches!(gathered.core_used_flags.as_slice(), &[]);
+    }
 }
@@ -38,22 +38,28 @@ pub use temporal_sdk_core_api as api;
 pub use temporal_sdk_core_protos as protos;
 pub use temporal_sdk_core_protos::TaskToken;
 pub use url::Url;
-pub use worker::{Worker, WorkerConfig, WorkerConfigBuilder};
+pub use worker::{
+    ActivityRateLimiter, PendingActivityHandoff, Worker, WorkerConfig, WorkerConfigBuilder,
+    WorkerMode,
+};
 
 use crate::{
+    abstractions::SharedSlots,
     replay::{HistoryForReplay, ReplayWorkerInput},
     telemetry::{
         metrics::MetricsContext, remove_trace_subscriber_for_current_thread,
-        set_trace_subscriber_for_current_thread, telemetry_init, TelemetryInstance,
+        set_trace_subscriber_for_current_thread, telemetry_init,
+        tokio_metrics::start_tokio_metrics_sampler, TelemetryInstance,
     },
     worker::client::WorkerClientBag,
 };
 use futures::Stream;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use temporal_client::{ConfiguredClient, TemporalServiceClientWithMetrics};
 use temporal_sdk_core_api::{
     errors::{CompleteActivityError, PollActivityError, PollWfError},
     telemetry::TelemetryOptions,
+    worker::SharedSlotLimits,
     Worker as WorkerTrait,
 };
 use temporal_sdk_core_protos::coresdk::ActivityHeartbeat;
@@ -103,6 +109,7 @@ where
         sticky_q,
         client_bag,
         Some(&runtime.telemetry),
+        runtime.shared_slots.clone(),
     ))
 }
 
@@ -183,6 +190,8 @@ pub struct CoreRuntime {
     telemetry: TelemetryInstance,
     runtime: Option<tokio::runtime::Runtime>,
     runtime_handle: tokio::runtime::Handle,
+    shared_slots: SharedSlots,
+    tokio_metrics_sampler: Option<tokio::task::AbortHandle>,
 }
 
 impl CoreRuntime {
@@ -243,6 +252,8 @@ impl CoreRuntime {
             telemetry,
             runtime: None,
             runtime_handle,
+            shared_slots: Default::default(),
+            tokio_metrics_sampler: None,
         }
     }
 
@@ -260,10 +271,51 @@ impl CoreRuntime {
     pub fn telemetry_mut(&mut self) -> &mut TelemetryInstance {
         &mut self.telemetry
     }
+
+    /// Bound the number of task slots which may be in use at once across all workers initialized
+    /// on this runtime, in addition to each worker's own configured maximums. Only affects workers
+    /// initialized after this is called, and those workers share one budget even if it is called
+    /// again later.
+    ///
+    /// Note that pollers hold a slot while waiting for a task, so the limits should leave room for
+    /// every worker's pollers or some workers may never receive tasks.
+    pub fn set_shared_slot_limits(&mut self, limits: SharedSlotLimits) {
+        self.shared_slots = limits.into();
+    }
+
+    /// Record metrics about the tokio runtime, like how many of its worker threads are busy and
+    /// how many tasks are queued, sampling them every `interval`. They're recorded with the
+    /// telemetry instance's metric meter, so call this after attaching any late-initialized meter.
+    /// Does nothing if there is no meter. Replaces sampling started by an earlier call. Fails if
+    /// `interval` is zero.
+    ///
+    /// Per-worker queue depths and blocking pool usage are only recorded when built with
+    /// `RUSTFLAGS="--cfg tokio_unstable"`.
+    pub fn enable_tokio_metrics(&mut self, interval: Duration) -> Result<(), anyhow::Error> {
+        if interval.is_zero() {
+            return Err(anyhow::anyhow!(
+                "Tokio metrics sampling interval must be nonzero"
+            ));
+        }
+        if let Some(sampler) = self.tokio_metrics_sampler.take() {
+            sampler.abort();
+        }
+        if let Some(meter) = self.telemetry.get_temporal_metric_meter() {
+            self.tokio_metrics_sampler = Some(start_tokio_metrics_sampler(
+                &self.runtime_handle,
+                meter,
+                interval,
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Drop for CoreRuntime {
     fn drop(&mut self) {
+        if let Some(sampler) = self.tokio_metrics_sampler.take() {
+            sampler.abort();
+        }
         remove_trace_subscriber_for_current_thread();
     }
 }
@@ -1,7 +1,8 @@
 mod poll_buffer;
 
 pub(crate) use poll_buffer::{
-    new_activity_task_buffer, new_workflow_task_buffer, WorkflowTaskPoller,
+    new_activity_task_buffer, new_workflow_task_buffer, RunPriorities, WorkflowTaskPoller,
+    DEFAULT_PRIORITY_KEY,
 };
 pub use temporal_client::{
     Client, ClientOptions, ClientOptionsBuilder, ClientTlsConfig, RetryClient, RetryConfig,
@@ -9,9 +10,11 @@ pub use temporal_client::{
 };
 
 use crate::abstractions::OwnedMeteredSemPermit;
+use std::sync::Arc;
 use temporal_sdk_core_protos::temporal::api::workflowservice::v1::{
     PollActivityTaskQueueResponse, PollWorkflowTaskQueueResponse,
 };
+use tokio::sync::watch;
 
 #[cfg(test)]
 use futures::Future;
@@ -62,6 +65,126 @@ where
     }
 }
 
+/// Allows pollers to be temporarily halted without shutting them down. While the gate is closed,
+/// pollers will not acquire permits or issue new poll requests. Polls which are already in flight
+/// when the gate closes are not interrupted.
+///
+/// The gate can also apply backpressure, during which only the first poller of each buffer may
+/// issue new polls, and can pause one kind of poller while leaving the other running. Each clone
+/// of a gate applies to a single [PollerKind], see [PollGate::for_kind].
+#[derive(Clone, Debug)]
+pub(crate) struct PollGate {
+    state: Arc<watch::Sender<GateState>>,
+    kind: PollerKind,
+}
+
+/// The kinds of pollers which a [PollGate] can pause independently
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PollerKind {
+    Workflow,
+    Activity,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GateState {
+    open: bool,
+    backpressured: bool,
+    workflows_paused: bool,
+    activities_paused: bool,
+}
+
+impl GateState {
+    fn paused(&self, kind: PollerKind) -> bool {
+        match kind {
+            PollerKind::Workflow => self.workflows_paused,
+            PollerKind::Activity => self.activities_paused,
+        }
+    }
+}
+
+impl PollGate {
+    /// Creates a new gate for workflow pollers
+    pub(crate) fn new(open: bool) -> Self {
+        Self {
+            state: Arc::new(
+                watch::channel(GateState {
+                    open,
+                    backpressured: false,
+                    workflows_paused: false,
+                    activities_paused: false,
+                })
+                .0,
+            ),
+            kind: PollerKind::Workflow,
+        }
+    }
+
+    /// Returns a gate sharing this one's state which applies to the provided kind of poller
+    pub(crate) fn for_kind(&self, kind: PollerKind) -> Self {
+        Self {
+            state: self.state.clone(),
+            kind,
+        }
+    }
+
+    pub(crate) fn set_open(&self, open: bool) {
+        self.state.send_modify(|s| s.open = open);
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.state.borrow().open
+    }
+
+    pub(crate) fn set_backpressured(&self, backpressured: bool) {
+        self.state.send_modify(|s| s.backpressured = backpressured);
+    }
+
+    pub(crate) fn is_backpressured(&self) -> bool {
+        self.state.borrow().backpressured
+    }
+
+    pub(crate) fn set_paused(&self, kind: PollerKind, paused: bool) {
+        self.state.send_modify(|s| match kind {
+            PollerKind::Workflow => s.workflows_paused = paused,
+            PollerKind::Activity => s.activities_paused = paused,
+        });
+    }
+
+    pub(crate) fn is_paused(&self, kind: PollerKind) -> bool {
+        self.state.borrow().paused(kind)
+    }
+
+    /// Returns true if the gate is open and this gate's kind of poller is not paused
+    pub(crate) fn allows_polling(&self) -> bool {
+        let s = self.state.borrow();
+        s.open && !s.paused(self.kind)
+    }
+
+    /// Resolves once backpressure is not applied (immediately, if it already isn't)
+    pub(crate) async fn wait_backpressure_lifted(&self) {
+        let mut rx = self.state.subscribe();
+        // The sender lives as long as we do, so this cannot fail
+        let _ = rx.wait_for(|s| !s.backpressured).await;
+    }
+
+    /// Resolves once the gate is open for the poller with the provided index (immediately, if it
+    /// already is). Pollers other than the first also wait for backpressure to be lifted.
+    pub(crate) async fn wait_open(&self, poller_ix: usize) {
+        let mut rx = self.state.subscribe();
+        let kind = self.kind;
+        // The sender lives as long as we do, so this cannot fail
+        let _ = rx
+            .wait_for(|s| s.open && !s.paused(kind) && (poller_ix == 0 || !s.backpressured))
+            .await;
+    }
+}
+
+impl Default for PollGate {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
 #[cfg(test)]
 mockall::mock! {
     pub ManualPoller<T: Send + Sync + 'static> {}
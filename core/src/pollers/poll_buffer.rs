@@ -1,21 +1,28 @@
 use crate::{
     abstractions::{dbg_panic, MeteredSemaphore, OwnedMeteredSemPermit},
-    pollers::{self, Poller},
+    pollers::{self, PollGate, Poller},
     worker::client::WorkerClient,
 };
 use futures::{prelude::stream::FuturesUnordered, StreamExt};
 use futures_util::{future::BoxFuture, FutureExt};
 use governor::{Quota, RateLimiter};
+use lru::LruCache;
 use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::BinaryHeap,
     fmt::Debug,
     future::Future,
+    num::NonZeroUsize,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
 };
+use temporal_sdk_core_api::worker::ActivityRateLimiter;
 use temporal_sdk_core_protos::temporal::api::{
+    common::v1::Priority,
+    history::v1::history_event,
     taskqueue::v1::TaskQueue,
     workflowservice::v1::{PollActivityTaskQueueResponse, PollWorkflowTaskQueueResponse},
 };
@@ -29,8 +36,14 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+/// Priority key assumed for tasks which do not specify one. Matches the server's default.
+pub(crate) const DEFAULT_PRIORITY_KEY: i32 = 3;
+
+type BufferedPoll<T> = pollers::Result<(T, OwnedMeteredSemPermit)>;
+type RankFn<T> = Arc<dyn Fn(&T) -> i32 + Send + Sync>;
+
 pub struct LongPollBuffer<T> {
-    buffered_polls: Mutex<UnboundedReceiver<pollers::Result<(T, OwnedMeteredSemPermit)>>>,
+    buffered_polls: Mutex<PrioritizedReceiver<T>>,
     shutdown: CancellationToken,
     join_handles: FuturesUnordered<JoinHandle<()>>,
     /// Pollers won't actually start polling until initialized & value is sent
@@ -63,17 +76,147 @@ where
     }
 }
 
+/// Hands out buffered poll responses. If a ranking function is provided, all responses which are
+/// currently buffered are handed out in rank order (lowest first, as with priority keys), and
+/// otherwise in the order they arrived. Errors are always handed out before any responses.
+struct PrioritizedReceiver<T> {
+    rx: UnboundedReceiver<BufferedPoll<T>>,
+    rank_fn: Option<RankFn<T>>,
+    ready: BinaryHeap<RankedPoll<T>>,
+    next_seq: u64,
+}
+
+struct RankedPoll<T> {
+    rank: i32,
+    seq: u64,
+    item: BufferedPoll<T>,
+}
+impl<T> PartialEq for RankedPoll<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank && self.seq == other.seq
+    }
+}
+impl<T> Eq for RankedPoll<T> {}
+impl<T> PartialOrd for RankedPoll<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for RankedPoll<T> {
+    // Reversed, since the heap pops the greatest element and we want the lowest rank & sequence
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other
+            .rank
+            .cmp(&self.rank)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<T> PrioritizedReceiver<T> {
+    fn new(rx: UnboundedReceiver<BufferedPoll<T>>, rank_fn: Option<RankFn<T>>) -> Self {
+        Self {
+            rx,
+            rank_fn,
+            ready: BinaryHeap::new(),
+            next_seq: 0,
+        }
+    }
+
+    async fn recv(&mut self) -> Option<BufferedPoll<T>> {
+        let Some(rank_fn) = self.rank_fn.clone() else {
+            return self.rx.recv().await;
+        };
+        if self.ready.is_empty() {
+            let first = self.rx.recv().await?;
+            self.push(first, &*rank_fn);
+        }
+        while let Ok(item) = self.rx.try_recv() {
+            self.push(item, &*rank_fn);
+        }
+        self.ready.pop().map(|rp| rp.item)
+    }
+
+    fn push(&mut self, item: BufferedPoll<T>, rank_fn: &dyn Fn(&T) -> i32) {
+        let rank = match &item {
+            Ok((t, _)) => rank_fn(t),
+            Err(_) => i32::MIN,
+        };
+        self.ready.push(RankedPoll {
+            rank,
+            seq: self.next_seq,
+            item,
+        });
+        self.next_seq += 1;
+    }
+}
+
+/// Converts an (optional) task priority into a rank where lower values should be handed out first
+fn priority_rank(priority: Option<&Priority>) -> i32 {
+    match priority.map(|p| p.priority_key) {
+        Some(k) if k > 0 => k,
+        _ => DEFAULT_PRIORITY_KEY,
+    }
+}
+
+/// Remembers the priorities of workflow runs this worker has polled tasks for, since only tasks
+/// which include the start of history carry them. Shared by the normal and sticky workflow task
+/// buffers, as a run's later tasks usually arrive on the sticky queue. Those only arrive for runs
+/// in the workflow cache, so runs beyond its size are forgotten, least recently polled first.
+#[derive(Clone)]
+pub(crate) struct RunPriorities(Arc<parking_lot::Mutex<LruCache<String, i32>>>);
+
+impl RunPriorities {
+    pub(crate) fn new(max_cached_workflows: usize) -> Self {
+        let capacity = NonZeroUsize::new(max_cached_workflows).unwrap_or(NonZeroUsize::MIN);
+        Self(Arc::new(parking_lot::Mutex::new(LruCache::new(capacity))))
+    }
+
+    fn rank(&self, resp: &PollWorkflowTaskQueueResponse) -> i32 {
+        let run_id = resp
+            .workflow_execution
+            .as_ref()
+            .map(|we| we.run_id.as_str())
+            .unwrap_or_default();
+        let started = resp
+            .history
+            .as_ref()
+            .and_then(|h| h.events.first())
+            .and_then(|e| match &e.attributes {
+                Some(history_event::Attributes::WorkflowExecutionStartedEventAttributes(a)) => {
+                    Some(a)
+                }
+                _ => None,
+            });
+        let mut runs = self.0.lock();
+        match started {
+            Some(attrs) => {
+                let rank = priority_rank(attrs.priority.as_ref());
+                runs.put(run_id.to_string(), rank);
+                rank
+            }
+            None => runs.get(run_id).copied().unwrap_or(DEFAULT_PRIORITY_KEY),
+        }
+    }
+}
+
+fn activity_priority_rank(resp: &PollActivityTaskQueueResponse) -> i32 {
+    priority_rank(resp.priority.as_ref())
+}
+
 impl<T> LongPollBuffer<T>
 where
     T: Send + Debug + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new<FT, DelayFut>(
         poll_fn: impl Fn() -> FT + Send + Sync + 'static,
         poll_semaphore: Arc<MeteredSemaphore>,
         max_pollers: usize,
+        poll_gate: PollGate,
         shutdown: CancellationToken,
         num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
         pre_permit_delay: Option<impl Fn() -> DelayFut + Send + Sync + 'static>,
+        rank_fn: Option<RankFn<T>>,
     ) -> Self
     where
         FT: Future<Output = pollers::Result<T>> + Send,
@@ -86,12 +229,13 @@ where
         let pf = Arc::new(poll_fn);
         let nph = num_pollers_handler.map(Arc::new);
         let pre_permit_delay = pre_permit_delay.map(Arc::new);
-        for _ in 0..max_pollers {
+        for poller_ix in 0..max_pollers {
             let tx = tx.clone();
             let pf = pf.clone();
             let shutdown = shutdown.clone();
             let ap = active_pollers.clone();
             let poll_semaphore = poll_semaphore.clone();
+            let poll_gate = poll_gate.clone();
             let nph = nph.clone();
             let pre_permit_delay = pre_permit_delay.clone();
             let mut wait_for_start = wait_for_start.resubscribe();
@@ -107,6 +251,10 @@ where
                     if shutdown.is_cancelled() {
                         break;
                     }
+                    tokio::select! {
+                        _ = poll_gate.wait_open(poller_ix) => (),
+                        _ = shutdown.cancelled() => break,
+                    }
                     if let Some(ref ppd) = pre_permit_delay {
                         tokio::select! {
                             _ = ppd() => (),
@@ -122,18 +270,41 @@ where
                     } else {
                         break;
                     };
+                    let claim = poll_semaphore.claim_preemptibly(permit);
                     let _active_guard = ActiveCounter::new(ap.as_ref(), nph);
                     let r = tokio::select! {
                         r = pf() => r,
                         _ = shutdown.cancelled() => break,
                     };
+                    let permit = match claim.into_permit() {
+                        Some(p) => p,
+                        // Our permit was handed to more urgent work while we were polling. Errors
+                        // don't need one, but a task must wait for a new permit.
+                        None if r.is_ok() => {
+                            let permit = tokio::select! {
+                                p = poll_semaphore.acquire_owned() => p,
+                                _ = shutdown.cancelled() => break,
+                            };
+                            if let Ok(p) = permit {
+                                p
+                            } else {
+                                break;
+                            }
+                        }
+                        None => {
+                            if let Err(e) = r {
+                                let _ = tx.send(Err(e));
+                            }
+                            continue;
+                        }
+                    };
                     let _ = tx.send(r.map(|r| (r, permit)));
                 }
             });
             join_handles.push(jh);
         }
         Self {
-            buffered_polls: Mutex::new(rx),
+            buffered_polls: Mutex::new(PrioritizedReceiver::new(rx, rank_fn)),
             shutdown,
             join_handles,
             starter,
@@ -157,7 +328,7 @@ where
         }
 
         let mut locked = self.buffered_polls.lock().await;
-        (*locked).recv().await
+        locked.recv().await
     }
 
     fn notify_shutdown(&self) {
@@ -229,8 +400,10 @@ pub(crate) fn new_workflow_task_buffer(
     task_queue: TaskQueue,
     concurrent_pollers: usize,
     semaphore: Arc<MeteredSemaphore>,
+    poll_gate: PollGate,
     shutdown: CancellationToken,
     num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
+    run_priorities: RunPriorities,
 ) -> PollWorkflowTaskBuffer {
     LongPollBuffer::new(
         move || {
@@ -240,9 +413,13 @@ pub(crate) fn new_workflow_task_buffer(
         },
         semaphore,
         concurrent_pollers,
+        poll_gate,
         shutdown,
         num_pollers_handler,
         None::<fn() -> BoxFuture<'static, ()>>,
+        Some(Arc::new(move |resp: &PollWorkflowTaskQueueResponse| {
+            run_priorities.rank(resp)
+        })),
     )
 }
 
@@ -254,14 +431,30 @@ pub(crate) fn new_activity_task_buffer(
     concurrent_pollers: usize,
     semaphore: Arc<MeteredSemaphore>,
     max_tps: Option<f64>,
+    poll_gate: PollGate,
     shutdown: CancellationToken,
     num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
     max_worker_acts_per_sec: Option<f64>,
+    external_rate_limiter: Option<Arc<dyn ActivityRateLimiter>>,
 ) -> PollActivityTaskBuffer {
     let rate_limiter = max_worker_acts_per_sec.and_then(|ps| {
         Quota::with_period(Duration::from_secs_f64(ps.recip()))
             .map(|q| Arc::new(RateLimiter::direct(q)))
     });
+    let has_limits = rate_limiter.is_some() || external_rate_limiter.is_some();
+    let pre_permit_delay = has_limits.then_some(move || {
+        let rl = rate_limiter.clone();
+        let external = external_rate_limiter.clone();
+        async move {
+            if let Some(rl) = rl {
+                rl.until_ready().await;
+            }
+            if let Some(external) = external {
+                external.acquire().await;
+            }
+        }
+        .boxed()
+    });
     LongPollBuffer::new(
         move || {
             let client = client.clone();
@@ -270,14 +463,11 @@ pub(crate) fn new_activity_task_buffer(
         },
         semaphore,
         concurrent_pollers,
+        poll_gate,
         shutdown,
         num_pollers_handler,
-        rate_limiter.map(|rl| {
-            move || {
-                let rl = rl.clone();
-                async move { rl.until_ready().await }.boxed()
-            }
-        }),
+        pre_permit_delay,
+        Some(Arc::new(activity_priority_rank)),
     )
 }
 
@@ -325,8 +515,15 @@ mod tests {
     };
     use futures::FutureExt;
     use std::time::Duration;
-    use temporal_sdk_core_protos::temporal::api::enums::v1::TaskQueueKind;
-    use tokio::{select, sync::mpsc::channel};
+    use temporal_sdk_core_protos::temporal::api::{
+        common::v1::WorkflowExecution,
+        enums::v1::TaskQueueKind,
+        history::v1::{History, HistoryEvent, WorkflowExecutionStartedEventAttributes},
+    };
+    use tokio::{
+        select,
+        sync::mpsc::{channel, unbounded_channel},
+    };
 
     #[tokio::test]
     async fn only_polls_once_with_1_poller() {
@@ -355,8 +552,10 @@ mod tests {
                 MetricsContext::no_op(),
                 |_, _| {},
             )),
+            PollGate::default(),
             CancellationToken::new(),
             None::<fn(usize)>,
+            RunPriorities::new(1),
         );
 
         // Poll a bunch of times, "interrupting" it each time, we should only actually have polled
@@ -383,4 +582,203 @@ mod tests {
         pb.poll().await.unwrap().unwrap();
         pb.shutdown().await;
     }
+
+    #[tokio::test]
+    async fn closed_gate_prevents_polling() {
+        let mut mock_client = mock_manual_workflow_client();
+        mock_client
+            .expect_poll_workflow_task()
+            .times(1..)
+            .returning(move |_| async { Ok(Default::default()) }.boxed());
+
+        let gate = PollGate::new(false);
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            1,
+            Arc::new(MeteredSemaphore::new(1, MetricsContext::no_op(), |_, _| {})),
+            gate.clone(),
+            CancellationToken::new(),
+            None::<fn(usize)>,
+            RunPriorities::new(1),
+        );
+
+        // Nothing should come out while the gate is closed
+        select! {
+            _ = pb.poll() => panic!("Should not have polled while gate is closed"),
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+        }
+        gate.set_open(true);
+        pb.poll().await.unwrap().unwrap();
+        pb.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn backpressure_limits_to_one_concurrent_poll() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let mut mock_client = mock_manual_workflow_client();
+        let (inf, maxf) = (in_flight.clone(), max_in_flight.clone());
+        mock_client.expect_poll_workflow_task().returning(move |_| {
+            let (inf, maxf) = (inf.clone(), maxf.clone());
+            async move {
+                let now = inf.fetch_add(1, Ordering::SeqCst) + 1;
+                maxf.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                inf.fetch_sub(1, Ordering::SeqCst);
+                Ok(Default::default())
+            }
+            .boxed()
+        });
+
+        let gate = PollGate::default();
+        gate.set_backpressured(true);
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            4,
+            Arc::new(MeteredSemaphore::new(
+                10,
+                MetricsContext::no_op(),
+                |_, _| {},
+            )),
+            gate.clone(),
+            CancellationToken::new(),
+            None::<fn(usize)>,
+            RunPriorities::new(1),
+        );
+
+        for _ in 0..3 {
+            pb.poll().await.unwrap().unwrap();
+        }
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        gate.set_backpressured(false);
+        for _ in 0..6 {
+            pb.poll().await.unwrap().unwrap();
+        }
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+        pb.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn preempted_poller_waits_for_new_permit() {
+        let mut mock_client = mock_manual_workflow_client();
+        mock_client.expect_poll_workflow_task().returning(move |_| {
+            async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(Default::default())
+            }
+            .boxed()
+        });
+        let sem = Arc::new(
+            MeteredSemaphore::new(1, MetricsContext::no_op(), |_, _| {})
+                .with_preemption(Some(Duration::from_millis(10))),
+        );
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            1,
+            sem.clone(),
+            PollGate::default(),
+            CancellationToken::new(),
+            None::<fn(usize)>,
+            RunPriorities::new(1),
+        );
+
+        {
+            let poll = pb.poll();
+            tokio::pin!(poll);
+            // Let the poller acquire its permit and sit on the long poll past the grace period
+            select! {
+                _ = &mut poll => panic!("Poll should still be in flight"),
+                _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+            }
+            let preempted = sem
+                .try_preempt()
+                .expect("Idle poller's permit must be preemptible");
+            // The poll completes, but the task can't be handed out until a permit is free
+            select! {
+                _ = &mut poll => panic!("Task should wait for a permit"),
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            }
+            drop(preempted);
+            poll.await.unwrap().unwrap();
+        }
+        pb.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn buffered_polls_handed_out_in_priority_order() {
+        let sem = MeteredSemaphore::new(10, MetricsContext::no_op(), |_, _| {});
+        let (tx, rx) = unbounded_channel();
+        let mut prx = PrioritizedReceiver::new(rx, Some(Arc::new(activity_priority_rank)));
+        let with_key = |k: i32| PollActivityTaskQueueResponse {
+            activity_id: k.to_string(),
+            priority: Some(Priority {
+                priority_key: k,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for resp in [with_key(5), with_key(0), with_key(1), with_key(3)] {
+            tx.send(Ok((resp, sem.try_acquire_owned().unwrap())))
+                .unwrap();
+        }
+        tx.send(Err(tonic::Status::internal("bonk"))).unwrap();
+
+        assert!(prx.recv().await.unwrap().is_err());
+        let mut order = vec![];
+        for _ in 0..4 {
+            order.push(prx.recv().await.unwrap().unwrap().0.activity_id);
+        }
+        // Unset priority is treated as the default, and ties are broken by arrival order
+        assert_eq!(order, vec!["1", "0", "3", "5"]);
+    }
+
+    #[test]
+    fn later_workflow_tasks_ranked_by_their_runs_priority() {
+        let priorities = RunPriorities::new(1);
+        let task = |run_id: &str, started_with_key: Option<i32>| PollWorkflowTaskQueueResponse {
+            workflow_execution: Some(WorkflowExecution {
+                run_id: run_id.to_string(),
+                ..Default::default()
+            }),
+            history: started_with_key.map(|k| History {
+                events: vec![HistoryEvent {
+                    attributes: Some(
+                        history_event::Attributes::WorkflowExecutionStartedEventAttributes(
+                            WorkflowExecutionStartedEventAttributes {
+                                priority: Some(Priority {
+                                    priority_key: k,
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            },
+                        ),
+                    ),
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        };
+        assert_eq!(priorities.rank(&task("run1", Some(1))), 1);
+        // Sticky tasks don't include the start of history
+        assert_eq!(priorities.rank(&task("run1", None)), 1);
+        assert_eq!(priorities.rank(&task("run2", None)), DEFAULT_PRIORITY_KEY);
+        // Runs beyond the cache size are forgotten
+        assert_eq!(priorities.rank(&task("run2", Some(5))), 5);
+        assert_eq!(priorities.rank(&task("run1", None)), DEFAULT_PRIORITY_KEY);
+    }
 }
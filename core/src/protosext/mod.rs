@@ -14,14 +14,15 @@ use std::{
     time::{Duration, SystemTime},
 };
 use temporal_sdk_core_protos::{
-    constants::{LOCAL_ACTIVITY_MARKER_NAME, PATCH_MARKER_NAME},
+    constants::{LOCAL_ACTIVITY_MARKER_NAME, MUTABLE_SIDE_EFFECT_MARKER_NAME, PATCH_MARKER_NAME},
     coresdk::{
         activity_result::{activity_execution_result, activity_execution_result::Status},
         common::{
-            decode_change_marker_details, extract_local_activity_marker_data,
+            decode_change_marker_data, decode_change_marker_details,
+            decode_mutable_side_effect_marker_details, extract_local_activity_marker_data,
             extract_local_activity_marker_details,
         },
-        external_data::LocalActivityMarkerData,
+        external_data::{LocalActivityMarkerData, MutableSideEffectMarkerData},
         workflow_activation::{
             query_to_job, workflow_activation_job, QueryWorkflow, WorkflowActivation,
             WorkflowActivationJob,
@@ -163,6 +164,14 @@ pub(crate) trait HistoryEventExt {
     /// If this history event represents a `patched` marker, return the info about
     /// it. Returns `None` if it is any other kind of event or marker.
     fn get_patch_marker_details(&self) -> Option<(String, bool)>;
+    /// If this history event represents a `patched` marker which was recorded with data, return
+    /// that data.
+    fn get_patch_marker_data(&self) -> Option<Payload>;
+    /// If this history event represents a mutable side effect marker, return its metadata and the
+    /// value it recorded.
+    fn get_mutable_side_effect_marker_details(
+        &self,
+    ) -> Option<(MutableSideEffectMarkerData, Option<Payload>)>;
     /// If this history event represents a local activity marker, return true.
     fn is_local_activity_marker(&self) -> bool;
     /// If this history event represents a local activity marker, return the marker id info.
@@ -191,6 +200,36 @@ impl HistoryEventExt for HistoryEvent {
         }
     }
 
+    fn get_patch_marker_data(&self) -> Option<Payload> {
+        match &self.attributes {
+            Some(history_event::Attributes::MarkerRecordedEventAttributes(
+                MarkerRecordedEventAttributes {
+                    marker_name,
+                    details,
+                    ..
+                },
+            )) if marker_name == PATCH_MARKER_NAME => decode_change_marker_data(details),
+            _ => None,
+        }
+    }
+
+    fn get_mutable_side_effect_marker_details(
+        &self,
+    ) -> Option<(MutableSideEffectMarkerData, Option<Payload>)> {
+        match &self.attributes {
+            Some(history_event::Attributes::MarkerRecordedEventAttributes(
+                MarkerRecordedEventAttributes {
+                    marker_name,
+                    details,
+                    ..
+                },
+            )) if marker_name == MUTABLE_SIDE_EFFECT_MARKER_NAME => {
+                decode_mutable_side_effect_marker_details(details)
+            }
+            _ => None,
+        }
+    }
+
     fn is_local_activity_marker(&self) -> bool {
         if self.event_type() == EventType::MarkerRecorded {
             return matches!(&self.attributes,
@@ -316,6 +355,7 @@ pub struct ValidScheduleLA {
     pub retry_policy: RetryPolicy,
     pub local_retry_threshold: Duration,
     pub cancellation_type: ActivityCancellationType,
+    pub previous_attempt_failure: Option<Failure>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -345,7 +385,12 @@ impl Default for LACloseTimeouts {
 }
 
 impl ValidScheduleLA {
-    pub fn from_schedule_la(v: ScheduleLocalActivity) -> Result<Self, anyhow::Error> {
+    /// Validates the schedule command, using `default_retry_threshold` if it doesn't set its own
+    /// local retry threshold
+    pub fn from_schedule_la(
+        v: ScheduleLocalActivity,
+        default_retry_threshold: Duration,
+    ) -> Result<Self, anyhow::Error> {
         let original_schedule_time = v
             .original_schedule_time
             .map(|x| {
@@ -405,7 +450,7 @@ impl ValidScheduleLA {
             .local_retry_threshold
             .clone()
             .try_into_or_none()
-            .unwrap_or_else(|| Duration::from_secs(60));
+            .unwrap_or(default_retry_threshold);
         let cancellation_type = ActivityCancellationType::try_from(v.cancellation_type)
             .unwrap_or(ActivityCancellationType::WaitCancellationCompleted);
         Ok(ValidScheduleLA {
@@ -421,6 +466,7 @@ impl ValidScheduleLA {
             retry_policy,
             local_retry_threshold,
             cancellation_type,
+            previous_attempt_failure: v.previous_attempt_failure,
         })
     }
 }
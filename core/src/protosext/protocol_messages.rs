@@ -14,15 +14,6 @@ pub struct IncomingProtocolMessage {
     pub sequencing_id: Option<SequencingId>,
     pub body: IncomingProtocolMessageBody,
 }
-impl IncomingProtocolMessage {
-    pub fn processable_after_event_id(&self) -> Option<i64> {
-        match self.sequencing_id {
-            None => Some(0),
-            Some(SequencingId::EventId(id)) => Some(id),
-            Some(SequencingId::CommandIndex(_)) => None,
-        }
-    }
-}
 impl TryFrom<Message> for IncomingProtocolMessage {
     type Error = anyhow::Error;
 
@@ -3,6 +3,7 @@
 //! users during testing.
 
 use crate::{
+    abstractions::SharedSlots,
     worker::{
         client::mocks::{mock_manual_workflow_client, MockManualWorkerClient},
         PostActivateHookData,
@@ -115,7 +116,13 @@ where
                 hist_allow_tx.send("Failed".to_string()).unwrap();
                 async move { Ok(RespondWorkflowTaskFailedResponse::default()) }.boxed()
             });
-        let mut worker = Worker::new(self.config, None, Arc::new(client), None);
+        let mut worker = Worker::new(
+            self.config,
+            None,
+            Arc::new(client),
+            None,
+            SharedSlots::default(),
+        );
         worker.set_post_activate_hook(post_activate);
         shutdown_tok(worker.shutdown_token());
         Ok(worker)
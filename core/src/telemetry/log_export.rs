@@ -2,7 +2,7 @@ use futures::channel::mpsc::{channel, Receiver, Sender};
 use parking_lot::Mutex;
 use ringbuf::{Consumer, HeapRb, Producer};
 use std::{collections::HashMap, fmt, sync::Arc, time::SystemTime};
-use temporal_sdk_core_api::telemetry::{CoreLog, CoreLogConsumer};
+use temporal_sdk_core_api::telemetry::{CoreLog, CoreLogConsumer, CoreLogSpan};
 use tracing_subscriber::Layer;
 
 type CoreLogsOut = Consumer<CoreLog, Arc<HeapRb<CoreLog>>>;
@@ -69,9 +69,6 @@ where
 
     fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
         let mut fields = HashMap::new();
-        let mut visitor = JsonVisitor(&mut fields);
-        event.record(&mut visitor);
-
         let mut spans = vec![];
         if let Some(scope) = ctx.event_scope(event) {
             for span in scope.from_root() {
@@ -81,9 +78,16 @@ where
                 for (k, v) in field_data {
                     fields.insert(k.to_string(), v.clone());
                 }
-                spans.push(span.name().to_string());
+                spans.push(CoreLogSpan {
+                    name: span.name().to_string(),
+                    target: span.metadata().target().to_string(),
+                    fields: field_data.clone(),
+                });
             }
         }
+        // Recorded last so the event's own fields win over its spans'
+        let mut visitor = JsonVisitor(&mut fields);
+        event.record(&mut visitor);
 
         // "message" is the magic default field keyname for the string passed to the event
         let message = fields.remove("message").unwrap_or_default();
@@ -94,7 +98,8 @@ where
             timestamp: SystemTime::now(),
             level: *event.metadata().level(),
             fields,
-            span_contexts: spans,
+            span_contexts: spans.iter().map(|s| s.name.clone()).collect(),
+            spans,
         };
         self.consumer.on_log(log);
     }
@@ -255,6 +260,17 @@ mod tests {
         assert_eq!(info_msg.fields.get("foo"), Some(&"bar".into()));
         assert_eq!(info_msg.fields.get("bros"), Some(&"brohemian".into()));
         assert_eq!(info_msg.fields.get("thing"), Some(&"hi".into()));
+        assert_eq!(info_msg.target, module_path!());
+        assert_eq!(info_msg.level, Level::INFO);
+        // Each span's fields are also kept apart
+        let spans = info_msg
+            .spans
+            .iter()
+            .map(|s| (s.name.as_str(), s.fields.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [("yayspan", 1), ("instrumented", 2)]);
+        assert_eq!(info_msg.spans[1].fields.get("thing"), Some(&"hi".into()));
+        assert_eq!(info_msg.span_contexts, ["yayspan", "instrumented"]);
     }
 
     #[tokio::test]
@@ -303,6 +319,31 @@ mod tests {
         assert_logs(consumer.0.lock().unwrap().drain(..).collect());
     }
 
+    #[tokio::test]
+    async fn forwarded_log_filter_changes_at_runtime() {
+        let consumer = Arc::new(CaptureConsumer(Mutex::new(Vec::new())));
+        let opts = TelemetryOptionsBuilder::default()
+            .logging(Logger::Push {
+                filter: construct_filter_string(Level::INFO, Level::WARN),
+                consumer: consumer.clone(),
+            })
+            .build()
+            .unwrap();
+        let instance = telemetry_init(opts).unwrap();
+        let _g = tracing::subscriber::set_default(instance.trace_subscriber().unwrap().clone());
+
+        instance
+            .set_forwarded_log_filter(&format!("warn,{}=debug", module_path!()))
+            .unwrap();
+        write_logs();
+        let logs = consumer.0.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert!(logs.iter().any(|l| l.message == "debug"));
+
+        instance.set_forwarded_log_filter("error").unwrap();
+        write_logs();
+        assert!(consumer.0.lock().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_push_stream_output() {
         let (consumer, stream) = CoreLogStreamConsumer::new(100);
@@ -1,11 +1,21 @@
 use crate::{abstractions::dbg_panic, telemetry::TelemetryInstance};
 
-use std::{fmt::Debug, iter::Iterator, sync::Arc, time::Duration};
-use temporal_sdk_core_api::telemetry::metrics::{
-    BufferAttributes, BufferInstrumentRef, CoreMeter, Counter, Gauge, GaugeF64, Histogram,
-    HistogramDuration, HistogramF64, LazyBufferInstrument, MetricAttributes, MetricCallBufferer,
-    MetricEvent, MetricKeyValue, MetricKind, MetricParameters, MetricUpdateVal, NewAttributes,
-    NoOpCoreMeter,
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    iter::Iterator,
+    sync::Arc,
+    time::Duration,
+};
+use temporal_sdk_core_api::telemetry::{
+    metrics::{
+        BufferAttributes, BufferInstrumentRef, CoreMeter, Counter, Gauge, GaugeF64, Histogram,
+        HistogramDuration, HistogramF64, LazyBufferInstrument, MetricAttributes,
+        MetricCallBufferer, MetricEvent, MetricKeyValue, MetricKind, MetricParameters,
+        MetricUpdateVal, NewAttributes, NoOpCoreMeter, NoOpInstrument,
+    },
+    MetricNameMapper,
 };
 
 /// Used to track context associated with metrics, and record/update them
@@ -36,11 +46,20 @@ struct Instruments {
     act_execution_failed: Arc<dyn Counter>,
     act_sched_to_start_latency: Arc<dyn HistogramDuration>,
     act_exec_latency: Arc<dyn HistogramDuration>,
+    act_dispatch_throttled: Arc<dyn Counter>,
+    act_dispatch_throttle_latency: Arc<dyn HistogramDuration>,
+    la_task_received_counter: Arc<dyn Counter>,
+    la_execution_failed: Arc<dyn Counter>,
+    la_sched_to_start_latency: Arc<dyn HistogramDuration>,
+    la_exec_latency: Arc<dyn HistogramDuration>,
+    la_e2e_latency: Arc<dyn HistogramDuration>,
+    sdk_flag_recorded: Arc<dyn Counter>,
     worker_registered: Arc<dyn Counter>,
     num_pollers: Arc<dyn Gauge>,
     task_slots_available: Arc<dyn Gauge>,
     sticky_cache_hit: Arc<dyn Counter>,
     sticky_cache_miss: Arc<dyn Counter>,
+    sticky_queue_fallback: Arc<dyn Counter>,
     sticky_cache_size: Arc<dyn Gauge>,
     sticky_cache_evictions: Arc<dyn Counter>,
 }
@@ -183,6 +202,53 @@ impl MetricsContext {
         self.instruments.act_exec_latency.record(dur, &self.kvs);
     }
 
+    /// An activity task was held back by its type's dispatch rate limit for the provided duration
+    pub(crate) fn act_dispatch_throttled(&self, dur: Duration) {
+        self.instruments.act_dispatch_throttled.add(1, &self.kvs);
+        self.instruments
+            .act_dispatch_throttle_latency
+            .record(dur, &self.kvs);
+    }
+
+    /// A count of local activity tasks dispatched to lang
+    pub(crate) fn la_task_received(&self) {
+        self.instruments.la_task_received_counter.add(1, &self.kvs);
+    }
+
+    /// A local activity execution failed
+    pub(crate) fn la_execution_failed(&self) {
+        self.instruments.la_execution_failed.add(1, &self.kvs);
+    }
+
+    /// Record time a local activity task waited between being scheduled and being dispatched
+    pub(crate) fn la_sched_to_start_latency(&self, dur: Duration) {
+        self.instruments
+            .la_sched_to_start_latency
+            .record(dur, &self.kvs);
+    }
+
+    /// Record time it took to complete a local activity attempt, from the time core dispatched
+    /// the task to the time lang responded with a completion (failure or success).
+    pub(crate) fn la_execution_latency(&self, dur: Duration) {
+        self.instruments.la_exec_latency.record(dur, &self.kvs);
+    }
+
+    /// Record time from a local activity first being scheduled to it completing successfully,
+    /// including all retries.
+    pub(crate) fn la_e2e_latency(&self, dur: Duration) {
+        self.instruments.la_e2e_latency.record(dur, &self.kvs);
+    }
+
+    /// A workflow task completion recorded an sdk flag for the first time in the workflow's
+    /// history. `source` is either "core" or "lang".
+    pub(crate) fn sdk_flag_recorded(&self, source: &'static str, flag: u32) {
+        let ctx = self.with_new_attrs([
+            MetricKeyValue::new(KEY_FLAG_SOURCE, source),
+            MetricKeyValue::new(KEY_FLAG, flag as i64),
+        ]);
+        ctx.instruments.sdk_flag_recorded.add(1, &ctx.kvs);
+    }
+
     /// A worker was registered
     pub(crate) fn worker_registered(&self) {
         self.instruments.worker_registered.add(1, &self.kvs);
@@ -210,6 +276,11 @@ impl MetricsContext {
         self.instruments.sticky_cache_miss.add(1, &self.kvs);
     }
 
+    /// A workflow task timed out on a sticky queue and was moved to the normal queue
+    pub(crate) fn sticky_queue_fallback(&self, count: u64) {
+        self.instruments.sticky_queue_fallback.add(count, &self.kvs);
+    }
+
     /// Record current cache size (in number of wfs, not bytes)
     pub(crate) fn cache_size(&self, size: u64) {
         self.instruments.sticky_cache_size.record(size, &self.kvs);
@@ -304,6 +375,50 @@ impl Instruments {
                 unit: "duration".into(),
                 description: "Histogram of activity execution latencies".into(),
             }),
+            act_dispatch_throttled: meter.counter(MetricParameters {
+                name: "activity_task_dispatch_throttled".into(),
+                description: "Count of activity tasks held back by their type's rate limit".into(),
+                unit: "".into(),
+            }),
+            act_dispatch_throttle_latency: meter.histogram_duration(MetricParameters {
+                name: "activity_task_dispatch_throttle_latency".into(),
+                unit: "duration".into(),
+                description: "Histogram of time activity tasks were held back by their type's \
+                              rate limit"
+                    .into(),
+            }),
+            la_task_received_counter: meter.counter(MetricParameters {
+                name: "local_activity_task_received".into(),
+                description: "Count of local activity tasks dispatched to lang".into(),
+                unit: "".into(),
+            }),
+            la_execution_failed: meter.counter(MetricParameters {
+                name: "local_activity_execution_failed".into(),
+                description: "Count of local activity task execution failures".into(),
+                unit: "".into(),
+            }),
+            la_sched_to_start_latency: meter.histogram_duration(MetricParameters {
+                name: LA_SCHED_TO_START_LATENCY_NAME.into(),
+                unit: "duration".into(),
+                description: "Histogram of local activity schedule-to-start latencies".into(),
+            }),
+            la_exec_latency: meter.histogram_duration(MetricParameters {
+                name: LA_EXEC_LATENCY_NAME.into(),
+                unit: "duration".into(),
+                description: "Histogram of local activity execution latencies".into(),
+            }),
+            la_e2e_latency: meter.histogram_duration(MetricParameters {
+                name: LA_E2E_LATENCY_NAME.into(),
+                unit: "duration".into(),
+                description: "Histogram of local activity schedule-to-successful-completion \
+                              latencies"
+                    .into(),
+            }),
+            sdk_flag_recorded: meter.counter(MetricParameters {
+                name: "sdk_flag_recorded".into(),
+                description: "Count of sdk flags newly recorded in workflow histories".into(),
+                unit: "".into(),
+            }),
             // name kept as worker start for compat with old sdk / what users expect
             worker_registered: meter.counter(MetricParameters {
                 name: "worker_start".into(),
@@ -333,6 +448,14 @@ impl Instruments {
                         .into(),
                 unit: "".into(),
             }),
+            sticky_queue_fallback: meter.counter(MetricParameters {
+                name: "sticky_queue_fallback".into(),
+                description: "Count of workflow tasks which were not picked up from a sticky \
+                              queue before its schedule-to-start timeout, and were moved to the \
+                              normal queue"
+                    .into(),
+                unit: "".into(),
+            }),
             sticky_cache_size: meter.gauge(MetricParameters {
                 name: STICKY_CACHE_SIZE_NAME.into(),
                 description: "Current number of cached workflows".into(),
@@ -354,6 +477,29 @@ const KEY_ACT_TYPE: &str = "activity_type";
 const KEY_POLLER_TYPE: &str = "poller_type";
 const KEY_WORKER_TYPE: &str = "worker_type";
 const KEY_EAGER: &str = "eager";
+const KEY_FLAG: &str = "flag";
+const KEY_FLAG_SOURCE: &str = "flag_source";
+
+/// Attribute keys which core's and the client's metrics may be recorded with
+#[cfg(feature = "otel")]
+pub(super) static RESERVED_ATTRIBUTE_KEYS: &[&str] = &[
+    KEY_NAMESPACE,
+    KEY_WF_TYPE,
+    KEY_TASK_QUEUE,
+    KEY_ACT_TYPE,
+    KEY_POLLER_TYPE,
+    KEY_WORKER_TYPE,
+    KEY_EAGER,
+    KEY_FLAG,
+    KEY_FLAG_SOURCE,
+    "service_name",
+    // Attached by the client's metrics
+    "attempt",
+    "endpoint",
+    "operation",
+    "reason",
+    "status_code",
+];
 
 pub(crate) fn workflow_poller() -> MetricKeyValue {
     MetricKeyValue::new(KEY_POLLER_TYPE, "workflow_task")
@@ -393,6 +539,9 @@ pub(super) const WF_TASK_REPLAY_LATENCY_NAME: &str = "workflow_task_replay_laten
 pub(super) const WF_TASK_EXECUTION_LATENCY_NAME: &str = "workflow_task_execution_latency";
 pub(super) const ACT_SCHED_TO_START_LATENCY_NAME: &str = "activity_schedule_to_start_latency";
 pub(super) const ACT_EXEC_LATENCY_NAME: &str = "activity_execution_latency";
+pub(super) const LA_SCHED_TO_START_LATENCY_NAME: &str = "local_activity_schedule_to_start_latency";
+pub(super) const LA_EXEC_LATENCY_NAME: &str = "local_activity_execution_latency";
+pub(super) const LA_E2E_LATENCY_NAME: &str = "local_activity_endtoend_latency";
 pub(super) const NUM_POLLERS_NAME: &str = "num_pollers";
 pub(super) const TASK_SLOTS_AVAILABLE_NAME: &str = "worker_task_slots_available";
 pub(super) const STICKY_CACHE_SIZE_NAME: &str = "sticky_cache_size";
@@ -451,13 +600,15 @@ define_latency_buckets!(
         [1., 10., 20., 50., 100., 200., 500., 1000.]
     ),
     (
-        ACT_EXEC_LATENCY_NAME,
+        ACT_EXEC_LATENCY_NAME | LA_EXEC_LATENCY_NAME | LA_E2E_LATENCY_NAME,
         ACT_EXE_MS_BUCKETS,
         ACT_EXE_S_BUCKETS,
         [50., 100., 500., 1000., 5000., 10_000., 60_000.]
     ),
     (
-        WF_TASK_SCHED_TO_START_LATENCY_NAME | ACT_SCHED_TO_START_LATENCY_NAME,
+        WF_TASK_SCHED_TO_START_LATENCY_NAME
+            | ACT_SCHED_TO_START_LATENCY_NAME
+            | LA_SCHED_TO_START_LATENCY_NAME,
         TASK_SCHED_TO_START_MS_BUCKETS,
         TASK_SCHED_TO_START_S_BUCKETS,
         [100., 500., 1000., 5000., 10_000., 100_000., 1_000_000.]
@@ -470,7 +621,9 @@ define_latency_buckets!(
     )
 );
 
-/// Buffers [MetricEvent]s for periodic consumption by lang
+/// Buffers [MetricEvent]s for periodic consumption by lang. Each instrument is only defined once,
+/// by the first [MetricEvent::Create] for its name and kind, so lang need only initialize it once
+/// no matter how many workers record to it.
 #[derive(Debug)]
 pub struct MetricsCallBuffer<I>
 where
@@ -478,16 +631,23 @@ where
 {
     calls_rx: crossbeam_channel::Receiver<MetricEvent<I>>,
     calls_tx: LogErrOnFullSender<MetricEvent<I>>,
+    instruments: Mutex<HashMap<(String, MetricKind), LazyBufferInstrument<I>>>,
 }
 #[derive(Clone, Debug)]
 struct LogErrOnFullSender<I>(crossbeam_channel::Sender<I>);
 impl<I> LogErrOnFullSender<I> {
-    fn send(&self, v: I) {
-        if let Err(crossbeam_channel::TrySendError::Full(_)) = self.0.try_send(v) {
-            error!(
-                "Core's metrics buffer is full! Dropping call to record metrics. \
-                 Make sure you drain the metric buffer often!"
-            );
+    /// Returns false if the value was dropped
+    fn send(&self, v: I) -> bool {
+        match self.0.try_send(v) {
+            Ok(()) => true,
+            Err(crossbeam_channel::TrySendError::Full(_)) => {
+                error!(
+                    "Core's metrics buffer is full! Dropping call to record metrics. \
+                     Make sure you drain the metric buffer often!"
+                );
+                false
+            }
+            Err(crossbeam_channel::TrySendError::Disconnected(_)) => false,
         }
     }
 }
@@ -502,15 +662,27 @@ where
         MetricsCallBuffer {
             calls_rx,
             calls_tx: LogErrOnFullSender(calls_tx),
+            instruments: Default::default(),
         }
     }
     fn new_instrument(&self, params: MetricParameters, kind: MetricKind) -> BufferInstrument<I> {
-        let hole = LazyBufferInstrument::hole();
-        self.calls_tx.send(MetricEvent::Create {
-            params,
-            kind,
-            populate_into: hole.clone(),
-        });
+        let key = (params.name.to_string(), kind);
+        let mut instruments = self.instruments.lock();
+        let hole = if let Some(hole) = instruments.get(&key) {
+            hole.clone()
+        } else {
+            let hole = LazyBufferInstrument::hole();
+            let sent = self.calls_tx.send(MetricEvent::Create {
+                params,
+                kind,
+                populate_into: hole.clone(),
+            });
+            // If lang never heard of it, the next request for it must define it again
+            if sent {
+                instruments.insert(key, hole.clone());
+            }
+            hole
+        };
         BufferInstrument {
             instrument_ref: hole,
             tx: self.calls_tx.clone(),
@@ -653,12 +825,56 @@ where
     }
 }
 
+/// How core-created metrics are named, and which are emitted at all
+#[derive(Debug)]
+pub(crate) struct MetricNaming {
+    pub(crate) prefix: String,
+    pub(crate) mapper: Option<Arc<dyn MetricNameMapper>>,
+    pub(crate) allowlist: Option<HashSet<String>>,
+    pub(crate) denylist: HashSet<String>,
+}
+
+impl MetricNaming {
+    pub(crate) fn prefixed(prefix: String) -> Self {
+        Self {
+            prefix,
+            mapper: None,
+            allowlist: None,
+            denylist: HashSet::new(),
+        }
+    }
+
+    /// The name to emit the core metric named `name` with, or `None` if it shouldn't be emitted
+    fn name_for(&self, name: &str) -> Option<String> {
+        if self.denylist.contains(name)
+            || self.allowlist.as_ref().is_some_and(|a| !a.contains(name))
+        {
+            return None;
+        }
+        let prefixed = self.prefix.clone() + name;
+        Some(match &self.mapper {
+            Some(mapper) => mapper.map_name(&prefixed),
+            None => prefixed,
+        })
+    }
+}
+
+/// Applies [MetricNaming] to the metrics created with the wrapped meter. Metrics which aren't
+/// emitted are given no-op instruments.
 #[derive(Debug, derive_more::Constructor)]
-pub(crate) struct PrefixedMetricsMeter<CM> {
-    prefix: String,
+pub(crate) struct NamedMetricsMeter<CM> {
+    naming: Arc<MetricNaming>,
     meter: CM,
 }
-impl<CM: CoreMeter> CoreMeter for PrefixedMetricsMeter<CM> {
+
+impl<CM> NamedMetricsMeter<CM> {
+    fn rename(&self, mut params: MetricParameters) -> Option<MetricParameters> {
+        params.name = self.naming.name_for(&params.name)?.into();
+        Some(params)
+    }
+}
+
+impl<CM: CoreMeter> CoreMeter for NamedMetricsMeter<CM> {
     fn new_attributes(&self, attribs: NewAttributes) -> MetricAttributes {
         self.meter.new_attributes(attribs)
     }
@@ -671,44 +887,57 @@ impl<CM: CoreMeter> CoreMeter for PrefixedMetricsMeter<CM> {
         self.meter.extend_attributes(existing, attribs)
     }
 
-    fn counter(&self, mut params: MetricParameters) -> Arc<dyn Counter> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.counter(params)
+    fn counter(&self, params: MetricParameters) -> Arc<dyn Counter> {
+        match self.rename(params) {
+            Some(params) => self.meter.counter(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 
-    fn histogram(&self, mut params: MetricParameters) -> Arc<dyn Histogram> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.histogram(params)
+    fn histogram(&self, params: MetricParameters) -> Arc<dyn Histogram> {
+        match self.rename(params) {
+            Some(params) => self.meter.histogram(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 
-    fn histogram_f64(&self, mut params: MetricParameters) -> Arc<dyn HistogramF64> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.histogram_f64(params)
+    fn histogram_f64(&self, params: MetricParameters) -> Arc<dyn HistogramF64> {
+        match self.rename(params) {
+            Some(params) => self.meter.histogram_f64(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 
-    fn histogram_duration(&self, mut params: MetricParameters) -> Arc<dyn HistogramDuration> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.histogram_duration(params)
+    fn histogram_duration(&self, params: MetricParameters) -> Arc<dyn HistogramDuration> {
+        match self.rename(params) {
+            Some(params) => self.meter.histogram_duration(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 
-    fn gauge(&self, mut params: MetricParameters) -> Arc<dyn Gauge> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.gauge(params)
+    fn gauge(&self, params: MetricParameters) -> Arc<dyn Gauge> {
+        match self.rename(params) {
+            Some(params) => self.meter.gauge(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 
-    fn gauge_f64(&self, mut params: MetricParameters) -> Arc<dyn GaugeF64> {
-        params.name = (self.prefix.clone() + &*params.name).into();
-        self.meter.gauge_f64(params)
+    fn gauge_f64(&self, params: MetricParameters) -> Arc<dyn GaugeF64> {
+        match self.rename(params) {
+            Some(params) => self.meter.gauge_f64(params),
+            None => Arc::new(NoOpInstrument),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::telemetry_init;
     use std::any::Any;
     use temporal_sdk_core_api::telemetry::{
         metrics::{BufferInstrumentRef, CustomMetricAttributes},
-        METRIC_PREFIX,
+        TelemetryOptionsBuilder, METRIC_PREFIX,
     };
     use tracing::subscriber::NoSubscriber;
 
@@ -764,7 +993,7 @@ mod tests {
         a1.set(Arc::new(DummyCustomAttrs(1))).unwrap();
         // Verify all metrics are created. This number will need to get updated any time a metric
         // is added.
-        let num_metrics = 23;
+        let num_metrics = 32;
         #[allow(clippy::needless_range_loop)] // Sorry clippy, this reads easier.
         for metric_num in 1..=num_metrics {
             let hole = assert_matches!(&events[metric_num],
@@ -809,6 +1038,66 @@ mod tests {
         );
     }
 
+    #[derive(Debug)]
+    struct DottedNames;
+    impl MetricNameMapper for DottedNames {
+        fn map_name(&self, name: &str) -> String {
+            format!("acme.{}", name.replace('_', "."))
+        }
+    }
+
+    fn created_names(call_buffer: &MetricsCallBuffer<DummyInstrumentRef>) -> Vec<String> {
+        call_buffer
+            .retrieve()
+            .into_iter()
+            .filter_map(|e| match e {
+                MetricEvent::Create { params, .. } => Some(params.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn core_metric_names_mapped_and_filtered() {
+        let call_buffer = Arc::new(MetricsCallBuffer::new(100));
+        let telem = telemetry_init(
+            TelemetryOptionsBuilder::default()
+                .metrics(call_buffer.clone() as Arc<dyn CoreMeter>)
+                .metric_name_mapper(Arc::new(DottedNames) as Arc<dyn MetricNameMapper>)
+                .metric_denylist(HashSet::from(["workflow_failed".to_string()]))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        let meter = telem.get_temporal_metric_meter().unwrap();
+        meter.inner.counter("workflow_completed".into());
+        meter.inner.counter("workflow_failed".into());
+        // Metrics lang defines itself are left alone
+        telem
+            .get_metric_meter()
+            .unwrap()
+            .inner
+            .counter("workflow_failed".into());
+        assert_eq!(
+            created_names(&call_buffer),
+            ["acme.temporal.workflow.completed", "workflow_failed"]
+        );
+
+        let call_buffer = Arc::new(MetricsCallBuffer::new(100));
+        let telem = telemetry_init(
+            TelemetryOptionsBuilder::default()
+                .metrics(call_buffer.clone() as Arc<dyn CoreMeter>)
+                .metric_allowlist(HashSet::from(["sticky_cache_size".to_string()]))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        let meter = telem.get_temporal_metric_meter().unwrap();
+        meter.inner.counter("workflow_completed".into());
+        meter.inner.gauge(STICKY_CACHE_SIZE_NAME.into());
+        assert_eq!(created_names(&call_buffer), ["temporal_sticky_cache_size"]);
+    }
+
     #[test]
     fn metric_buffer() {
         let call_buffer = MetricsCallBuffer::new(10);
@@ -949,4 +1238,52 @@ mod tests {
                && d == Duration::from_secs_f64(1.2)
         );
     }
+
+    #[test]
+    fn buffered_instruments_defined_once() {
+        let call_buffer = MetricsCallBuffer::new(10);
+        let params = || MetricParameters {
+            name: "ctr".into(),
+            description: "a counter".into(),
+            unit: "".into(),
+        };
+        let ctr_1 = call_buffer.counter(params());
+        let ctr_2 = call_buffer.counter(params());
+        // Same name, but a different kind of instrument
+        call_buffer.gauge(params());
+        let attrs = call_buffer.new_attributes(NewAttributes::default());
+        ctr_1.add(1, &attrs);
+        ctr_2.add(2, &attrs);
+
+        let calls = call_buffer.retrieve();
+        assert_eq!(calls.len(), 5);
+        let ctr = assert_matches!(
+            &calls[0],
+            MetricEvent::Create {
+                populate_into,
+                kind: MetricKind::Counter,
+                ..
+            }
+            => populate_into
+        );
+        ctr.set(Arc::new(DummyInstrumentRef(1))).unwrap();
+        assert_matches!(
+            &calls[1],
+            MetricEvent::Create {
+                kind: MetricKind::Gauge,
+                ..
+            }
+        );
+        for (call, val) in calls[3..].iter().zip([1, 2]) {
+            assert_matches!(
+                call,
+                MetricEvent::Update {
+                    instrument,
+                    update: MetricUpdateVal::Delta(v),
+                    ..
+                }
+                if instrument.get().0 == 1 && *v == val
+            );
+        }
+    }
 }
@@ -7,15 +7,24 @@ pub(crate) mod metrics;
 mod otel;
 #[cfg(feature = "otel")]
 mod prometheus_server;
+pub(crate) mod tokio_metrics;
 
 #[cfg(feature = "otel")]
 pub use metrics::{default_buckets_for, MetricsCallBuffer};
 #[cfg(feature = "otel")]
-pub use otel::{build_otlp_metric_exporter, start_prometheus_metric_exporter};
+pub use otel::{
+    build_otlp_metric_exporter, start_prometheus_metric_exporter,
+    start_prometheus_metric_exporter_with_listener,
+};
+#[cfg(feature = "otel")]
+pub use prometheus_server::PrometheusListener;
 
 pub use log_export::{CoreLogBuffer, CoreLogBufferedConsumer, CoreLogStreamConsumer};
 
-use crate::telemetry::{log_export::CoreLogConsumerLayer, metrics::PrefixedMetricsMeter};
+use crate::telemetry::{
+    log_export::CoreLogConsumerLayer,
+    metrics::{MetricNaming, NamedMetricsMeter},
+};
 use itertools::Itertools;
 use parking_lot::Mutex;
 use std::{
@@ -32,12 +41,15 @@ use temporal_sdk_core_api::telemetry::{
     CoreLog, CoreTelemetry, Logger, TelemetryOptions,
 };
 use tracing::{Level, Subscriber};
-use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Layer};
+use tracing_subscriber::{layer::SubscriberExt, reload, EnvFilter, Layer};
 
 const TELEM_SERVICE_NAME: &str = "temporal-core-sdk";
 
 const FORWARD_LOG_BUFFER_SIZE: usize = 2048;
 
+/// Swaps the filter of the layer forwarding logs to lang
+type ForwardFilterReloader = Box<dyn Fn(EnvFilter) -> Result<(), reload::Error> + Send + Sync>;
+
 /// Help you construct an [EnvFilter] compatible filter string which will forward all core module
 /// traces at `core_level` and all others (from 3rd party modules, etc) at `other_level`.
 pub fn construct_filter_string(core_level: Level, other_level: Level) -> String {
@@ -48,13 +60,17 @@ pub fn construct_filter_string(core_level: Level, other_level: Level) -> String
 
 /// Holds initialized tracing/metrics exporters, etc
 pub struct TelemetryInstance {
-    metric_prefix: String,
+    metric_naming: Arc<MetricNaming>,
     logs_out: Option<Mutex<CoreLogBuffer>>,
     metrics: Option<Arc<dyn CoreMeter + 'static>>,
     /// The tracing subscriber which is associated with this telemetry instance. May be `None` if
     /// the user has not opted into any tracing configuration.
     trace_subscriber: Option<Arc<dyn Subscriber + Send + Sync>>,
     attach_service_name: bool,
+    forward_filter: Option<ForwardFilterReloader>,
+    /// Kept alive for as long as spans may be exported
+    #[cfg(feature = "otel")]
+    _span_export: Option<otel::SpanExport>,
 }
 
 impl TelemetryInstance {
@@ -66,11 +82,14 @@ impl TelemetryInstance {
         attach_service_name: bool,
     ) -> Self {
         Self {
-            metric_prefix,
+            metric_naming: Arc::new(MetricNaming::prefixed(metric_prefix)),
             logs_out,
             metrics,
             trace_subscriber,
             attach_service_name,
+            forward_filter: None,
+            #[cfg(feature = "otel")]
+            _span_export: None,
         }
     }
 
@@ -81,21 +100,35 @@ impl TelemetryInstance {
         self.trace_subscriber.clone()
     }
 
+    /// Replace the filter which picks the logs forwarded to lang with [Logger::Forward] or
+    /// [Logger::Push], ex: to see debug logs from one module while investigating it. Takes an
+    /// [EnvFilter] string, like the logger's own `filter`. Fails if the string is invalid, or if
+    /// logs aren't being forwarded.
+    pub fn set_forwarded_log_filter(&self, filter: &str) -> Result<(), anyhow::Error> {
+        let reload = self
+            .forward_filter
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Logs aren't being forwarded to lang"))?;
+        reload(EnvFilter::try_new(filter)?)?;
+        Ok(())
+    }
+
     /// Some metric meters cannot be initialized until after a tokio runtime has started and after
     /// other telemetry has initted (ex: prometheus). They can be attached here.
     pub fn attach_late_init_metrics(&mut self, meter: Arc<dyn CoreMeter + 'static>) {
         self.metrics = Some(meter);
     }
 
-    /// Returns our wrapper for metric meters, including the `metric_prefix` from
-    /// [TelemetryOptions]. This should be used to initialize clients or for any other
-    /// temporal-owned metrics. User defined metrics should use [Self::get_metric_meter].
+    /// Returns our wrapper for metric meters, including the `metric_prefix`, name mapping, and
+    /// allow and deny lists from [TelemetryOptions]. This should be used to initialize clients or
+    /// for any other temporal-owned metrics. User defined metrics should use
+    /// [Self::get_metric_meter].
     pub fn get_temporal_metric_meter(&self) -> Option<TemporalMeter> {
         self.metrics.clone().map(|m| {
             let kvs = self.default_kvs();
             let attribs = NewAttributes::new(kvs);
             TemporalMeter::new(
-                Arc::new(PrefixedMetricsMeter::new(self.metric_prefix.clone(), m))
+                Arc::new(NamedMetricsMeter::new(self.metric_naming.clone(), m))
                     as Arc<dyn CoreMeter>,
                 attribs,
             )
@@ -173,9 +206,24 @@ pub fn telemetry_init(opts: TelemetryOptions) -> Result<TelemetryInstance, anyho
     let mut console_pretty_layer = None;
     let mut console_compact_layer = None;
     let mut forward_layer = None;
+    let mut forward_filter: Option<ForwardFilterReloader> = None;
     // ===================================
 
-    let tracing_sub = opts.logging.map(|logger| {
+    #[cfg(feature = "otel")]
+    let span_export = opts
+        .tracing
+        .as_ref()
+        .map(otel::SpanExport::new)
+        .transpose()?;
+    #[cfg(not(feature = "otel"))]
+    if opts.tracing.is_some() {
+        return Err(anyhow::anyhow!(
+            "Exporting spans requires the `otel` feature"
+        ));
+    }
+
+    let subscribed = opts.logging.is_some() || opts.tracing.is_some();
+    if let Some(logger) = opts.logging {
         match logger {
             Logger::Console { filter } => {
                 // This is silly dupe but can't be avoided without boxing.
@@ -207,30 +255,53 @@ pub fn telemetry_init(opts: TelemetryOptions) -> Result<TelemetryInstance, anyho
                 let (export_layer, lo) =
                     CoreLogConsumerLayer::new_buffered(FORWARD_LOG_BUFFER_SIZE);
                 logs_out = Some(Mutex::new(lo));
-                forward_layer = Some(export_layer.with_filter(EnvFilter::new(filter)));
+                let (filter, handle) = reload::Layer::new(EnvFilter::new(filter));
+                forward_filter = Some(Box::new(move |f| handle.reload(f)));
+                forward_layer = Some(export_layer.with_filter(filter));
             }
             Logger::Push { filter, consumer } => {
-                forward_layer =
-                    Some(CoreLogConsumerLayer::new(consumer).with_filter(EnvFilter::new(filter)));
+                let (filter, handle) = reload::Layer::new(EnvFilter::new(filter));
+                forward_filter = Some(Box::new(move |f| handle.reload(f)));
+                forward_layer = Some(CoreLogConsumerLayer::new(consumer).with_filter(filter));
             }
         };
+    }
+    let tracing_sub = subscribed.then(|| {
         let reg = tracing_subscriber::registry()
             .with(console_pretty_layer)
             .with(console_compact_layer)
             .with(forward_layer);
 
+        #[cfg(feature = "otel")]
+        let reg = reg.with(
+            span_export
+                .as_ref()
+                .zip(opts.tracing.as_ref())
+                .map(|(export, topts)| export.layer().with_filter(EnvFilter::new(&topts.filter))),
+        );
         #[cfg(feature = "tokio-console")]
         let reg = reg.with(console_subscriber::spawn());
         Arc::new(reg) as Arc<dyn Subscriber + Send + Sync>
     });
 
-    Ok(TelemetryInstance::new(
-        tracing_sub,
-        logs_out,
-        opts.metric_prefix,
-        opts.metrics,
-        opts.attach_service_name,
-    ))
+    Ok(TelemetryInstance {
+        #[cfg(feature = "otel")]
+        _span_export: span_export,
+        forward_filter,
+        metric_naming: Arc::new(MetricNaming {
+            prefix: opts.metric_prefix.clone(),
+            mapper: opts.metric_name_mapper,
+            allowlist: opts.metric_allowlist,
+            denylist: opts.metric_denylist,
+        }),
+        ..TelemetryInstance::new(
+            tracing_sub,
+            logs_out,
+            opts.metric_prefix,
+            opts.metrics,
+            opts.attach_service_name,
+        )
+    })
 }
 
 /// Initialize telemetry/tracing globally. Useful for testing. Only takes affect when called
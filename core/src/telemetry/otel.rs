@@ -2,19 +2,22 @@ use super::{
     default_buckets_for,
     metrics::{
         ACT_EXEC_LATENCY_NAME, ACT_SCHED_TO_START_LATENCY_NAME, DEFAULT_MS_BUCKETS,
+        LA_E2E_LATENCY_NAME, LA_EXEC_LATENCY_NAME, LA_SCHED_TO_START_LATENCY_NAME,
         WF_E2E_LATENCY_NAME, WF_TASK_EXECUTION_LATENCY_NAME, WF_TASK_REPLAY_LATENCY_NAME,
         WF_TASK_SCHED_TO_START_LATENCY_NAME,
     },
-    prometheus_server::PromServer,
+    prometheus_server::{PromServer, PrometheusListener},
     TELEM_SERVICE_NAME,
 };
 use crate::{abstractions::dbg_panic, telemetry::metrics::DEFAULT_S_BUCKETS};
 use opentelemetry::{
     self,
-    metrics::{Meter, MeterProvider as MeterProviderT, Unit},
+    metrics::{Meter, MeterProvider as MeterProviderT, MetricsError, Unit},
+    trace::TracerProvider as TracerProviderT,
     KeyValue,
 };
 use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_prometheus::PrometheusExporter;
 use opentelemetry_sdk::{
     metrics::{
         data::Temporality,
@@ -22,7 +25,9 @@ use opentelemetry_sdk::{
         reader::{AggregationSelector, DefaultAggregationSelector, TemporalitySelector},
         Aggregation, Instrument, InstrumentKind, MeterProviderBuilder, PeriodicReader, View,
     },
-    runtime, AttributeSet, Resource,
+    runtime,
+    trace::{self as sdktrace, Sampler, TracerProvider},
+    AttributeSet, Resource,
 };
 use parking_lot::RwLock;
 use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
@@ -31,10 +36,18 @@ use temporal_sdk_core_api::telemetry::{
         CoreMeter, Counter, Gauge, GaugeF64, Histogram, HistogramDuration, HistogramF64,
         MetricAttributes, MetricParameters, NewAttributes,
     },
-    MetricTemporality, OtelCollectorOptions, PrometheusExporterOptions,
+    MetricTemporality, OtelCollectorOptions, OtelTracingOptions, OtlpProtocol, OtlpTlsOptions,
+    PrometheusExporterOptions,
 };
-use tokio::task::AbortHandle;
-use tonic::metadata::MetadataMap;
+use tokio::{runtime::Runtime, task::AbortHandle};
+use tonic::{
+    metadata::MetadataMap,
+    transport::{Certificate, ClientTlsConfig, Identity},
+};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+use url::Url;
 
 /// Chooses appropriate aggregators for our metrics
 #[derive(Debug, Clone)]
@@ -67,16 +80,28 @@ impl AggregationSelector for SDKAggSelector {
     }
 }
 
+/// Histograms which have their own default buckets, rather than the global ones
+static HISTOGRAMS_WITH_DEFAULT_BUCKETS: &[&str] = &[
+    WF_E2E_LATENCY_NAME,
+    WF_TASK_EXECUTION_LATENCY_NAME,
+    WF_TASK_REPLAY_LATENCY_NAME,
+    WF_TASK_SCHED_TO_START_LATENCY_NAME,
+    ACT_SCHED_TO_START_LATENCY_NAME,
+    ACT_EXEC_LATENCY_NAME,
+    LA_SCHED_TO_START_LATENCY_NAME,
+    LA_EXEC_LATENCY_NAME,
+    LA_E2E_LATENCY_NAME,
+];
+
 fn histo_view(
-    metric_name: &'static str,
-    use_seconds: bool,
+    metric_name: String,
+    buckets: Vec<f64>,
 ) -> opentelemetry::metrics::Result<Box<dyn View>> {
-    let buckets = default_buckets_for(metric_name, use_seconds);
     new_view(
-        Instrument::new().name(format!("*{metric_name}")),
+        Instrument::new().name(metric_name),
         opentelemetry_sdk::metrics::Stream::new().aggregation(
             Aggregation::ExplicitBucketHistogram {
-                boundaries: buckets.to_vec(),
+                boundaries: buckets,
                 record_min_max: true,
             },
         ),
@@ -84,23 +109,45 @@ fn histo_view(
 }
 
 pub(super) fn augment_meter_provider_with_defaults(
-    mpb: MeterProviderBuilder,
+    mut mpb: MeterProviderBuilder,
     global_tags: &HashMap<String, String>,
     use_seconds: bool,
+    metric_prefix: &str,
+    bucket_overrides: &HashMap<String, Vec<f64>>,
 ) -> opentelemetry::metrics::Result<MeterProviderBuilder> {
     // Some histograms are actually gauges, but we have to use histograms otherwise they forget
     // their value between collections since we don't use callbacks.
-    Ok(mpb
-        .with_view(histo_view(WF_E2E_LATENCY_NAME, use_seconds)?)
-        .with_view(histo_view(WF_TASK_EXECUTION_LATENCY_NAME, use_seconds)?)
-        .with_view(histo_view(WF_TASK_REPLAY_LATENCY_NAME, use_seconds)?)
-        .with_view(histo_view(
-            WF_TASK_SCHED_TO_START_LATENCY_NAME,
-            use_seconds,
-        )?)
-        .with_view(histo_view(ACT_SCHED_TO_START_LATENCY_NAME, use_seconds)?)
-        .with_view(histo_view(ACT_EXEC_LATENCY_NAME, use_seconds)?)
-        .with_resource(default_resource(global_tags)))
+
+    // Every view matching an instrument produces a stream, so defaults which are overridden must
+    // be left out. Overrides may or may not include the metric prefix.
+    let overridden: Vec<&str> = bucket_overrides
+        .keys()
+        .map(|k| k.strip_prefix(metric_prefix).unwrap_or(k))
+        .collect();
+    for name in HISTOGRAMS_WITH_DEFAULT_BUCKETS {
+        if !overridden.contains(name) {
+            mpb = mpb.with_view(histo_view(
+                format!("{metric_prefix}{name}"),
+                default_buckets_for(name, use_seconds).to_vec(),
+            )?);
+        }
+    }
+    for (name, buckets) in bucket_overrides {
+        if buckets.iter().any(|b| !b.is_finite()) || buckets.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(MetricsError::Config(format!(
+                "Histogram bucket overrides for {name} must be finite and strictly increasing, \
+                 got {buckets:?}"
+            )));
+        }
+        mpb = mpb.with_view(histo_view(name.clone(), buckets.clone())?);
+        if !name.starts_with(metric_prefix) {
+            mpb = mpb.with_view(histo_view(
+                format!("{metric_prefix}{name}"),
+                buckets.clone(),
+            )?);
+        }
+    }
+    Ok(mpb.with_resource(default_resource(global_tags)))
 }
 
 /// OTel has no built-in synchronous Gauge. Histograms used to be able to serve that purpose, but
@@ -158,13 +205,27 @@ impl<U> MemoryGauge<U> {
 pub fn build_otlp_metric_exporter(
     opts: OtelCollectorOptions,
 ) -> Result<CoreOtelMeter, anyhow::Error> {
-    let exporter = opentelemetry_otlp::TonicExporterBuilder::default()
-        .with_endpoint(opts.url.to_string())
-        .with_metadata(MetadataMap::from_headers((&opts.headers).try_into()?))
-        .build_metrics_exporter(
-            Box::new(SDKAggSelector::new(opts.use_seconds_for_durations)),
-            Box::new(metric_temporality_to_selector(opts.metric_temporality)),
-        )?;
+    let agg_selector = Box::new(SDKAggSelector::new(opts.use_seconds_for_durations));
+    let temporality_selector = metric_temporality_to_selector(opts.metric_temporality);
+    let exporter = match opts.protocol {
+        OtlpProtocol::Grpc => {
+            let mut builder = opentelemetry_otlp::TonicExporterBuilder::default()
+                .with_endpoint(opts.url.to_string())
+                .with_metadata(MetadataMap::from_headers((&opts.headers).try_into()?));
+            if let Some(tls) = &opts.tls {
+                builder = builder.with_tls_config(tonic_tls_config(tls));
+            }
+            builder.build_metrics_exporter(agg_selector, temporality_selector)?
+        }
+        OtlpProtocol::Http => opentelemetry_otlp::HttpExporterBuilder::default()
+            .with_endpoint(opts.url.to_string())
+            .with_headers(opts.headers.clone())
+            .with_http_client(http_export_client(
+                opts.http_proxy.as_ref(),
+                opts.tls.as_ref(),
+            )?)
+            .build_metrics_exporter(agg_selector, temporality_selector)?,
+    };
     let reader = PeriodicReader::builder(exporter, runtime::Tokio)
         .with_interval(opts.metric_periodicity)
         .build();
@@ -172,6 +233,8 @@ pub fn build_otlp_metric_exporter(
         MeterProviderBuilder::default().with_reader(reader),
         &opts.global_tags,
         opts.use_seconds_for_durations,
+        &opts.metric_prefix,
+        &opts.histogram_bucket_overrides,
     )?
     .build();
     Ok::<_, anyhow::Error>(CoreOtelMeter {
@@ -180,6 +243,130 @@ pub fn build_otlp_metric_exporter(
     })
 }
 
+fn tonic_tls_config(tls: &OtlpTlsOptions) -> ClientTlsConfig {
+    let mut cfg = ClientTlsConfig::new();
+    if let Some(root_cert) = &tls.server_root_ca_cert {
+        cfg = cfg.ca_certificate(Certificate::from_pem(root_cert));
+    }
+    if let Some(identity) = &tls.client_identity {
+        cfg = cfg.identity(Identity::from_pem(
+            &identity.client_cert,
+            &identity.client_private_key,
+        ));
+    }
+    cfg
+}
+
+fn http_export_client(
+    proxy: Option<&Url>,
+    tls: Option<&OtlpTlsOptions>,
+) -> Result<reqwest::Client, anyhow::Error> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+    }
+    if let Some(tls) = tls {
+        if let Some(root_cert) = &tls.server_root_ca_cert {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(root_cert)?);
+        }
+        if let Some(identity) = &tls.client_identity {
+            // With rustls, the cert and key are read from a single PEM bundle
+            let pem = [
+                identity.client_cert.as_slice(),
+                b"\n",
+                identity.client_private_key.as_slice(),
+            ]
+            .concat();
+            builder = builder.identity(reqwest::Identity::from_pem(&pem)?);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Exports core's tracing spans to an OTel collector, in batches. Telemetry may be initialized
+/// before any Tokio runtime exists, so exports happen on a small runtime of their own. Its thread
+/// has no trace subscriber, so the exporter's own spans are never exported.
+pub(super) struct SpanExport {
+    provider: Option<TracerProvider>,
+    runtime: Option<Runtime>,
+}
+
+impl SpanExport {
+    pub(super) fn new(opts: &OtelTracingOptions) -> Result<Self, anyhow::Error> {
+        if !(0.0..=1.0).contains(&opts.sample_ratio) {
+            return Err(anyhow::anyhow!(
+                "Span sample ratio must be between 0 and 1, got {}",
+                opts.sample_ratio
+            ));
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("temporal-span-export")
+            .enable_all()
+            .build()?;
+        // The gRPC channel and the batch processor's task are spawned on the current runtime
+        let rg = runtime.enter();
+        let exporter = match opts.protocol {
+            OtlpProtocol::Grpc => {
+                let mut builder = opentelemetry_otlp::TonicExporterBuilder::default()
+                    .with_endpoint(opts.url.to_string())
+                    .with_metadata(MetadataMap::from_headers((&opts.headers).try_into()?));
+                if let Some(tls) = &opts.tls {
+                    builder = builder.with_tls_config(tonic_tls_config(tls));
+                }
+                builder.build_span_exporter()?
+            }
+            OtlpProtocol::Http => opentelemetry_otlp::HttpExporterBuilder::default()
+                .with_endpoint(opts.url.to_string())
+                .with_headers(opts.headers.clone())
+                .with_http_client(http_export_client(
+                    opts.http_proxy.as_ref(),
+                    opts.tls.as_ref(),
+                )?)
+                .build_span_exporter()?,
+        };
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_config(
+                sdktrace::config()
+                    .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                        opts.sample_ratio,
+                    ))))
+                    .with_resource(default_resource(&opts.global_tags)),
+            )
+            .build();
+        drop(rg);
+        Ok(Self {
+            provider: Some(provider),
+            runtime: Some(runtime),
+        })
+    }
+
+    /// A tracing layer which records spans to be exported
+    pub(super) fn layer<S>(&self) -> OpenTelemetryLayer<S, sdktrace::Tracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let tracer = self
+            .provider
+            .as_ref()
+            .expect("Provider is only taken on drop")
+            .tracer(TELEM_SERVICE_NAME);
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    }
+}
+
+impl Drop for SpanExport {
+    fn drop(&mut self) {
+        // Shutting down the provider flushes buffered spans, which needs the runtime to still be
+        // running. The runtime may be dropped from async code, so it's not waited on.
+        drop(self.provider.take());
+        if let Some(rt) = self.runtime.take() {
+            rt.shutdown_background();
+        }
+    }
+}
+
 pub struct StartedPromServer {
     pub meter: Arc<CoreOtelMeter>,
     pub bound_addr: SocketAddr,
@@ -195,22 +382,64 @@ pub fn start_prometheus_metric_exporter(
 ) -> Result<StartedPromServer, anyhow::Error> {
     let (srv, exporter) =
         PromServer::new(&opts, SDKAggSelector::new(opts.use_seconds_for_durations))?;
+    let bound_addr = srv.bound_addr()?;
+    let (meter, abort_handle) = run_prom_server(&opts, srv, exporter)?;
+    Ok(StartedPromServer {
+        meter,
+        bound_addr,
+        abort_handle,
+    })
+}
+
+pub struct StartedPromListener {
+    pub meter: Arc<CoreOtelMeter>,
+    pub abort_handle: AbortHandle,
+}
+
+/// Like [start_prometheus_metric_exporter], but serves the scrape endpoint from a listener the
+/// caller has already bound, such as a Unix socket. [PrometheusExporterOptions::socket_addr] must
+/// not be set.
+///
+/// Requires a Tokio runtime to exist.
+pub fn start_prometheus_metric_exporter_with_listener(
+    opts: PrometheusExporterOptions,
+    listener: impl Into<PrometheusListener>,
+) -> Result<StartedPromListener, anyhow::Error> {
+    let (srv, exporter) = PromServer::with_listener(
+        &opts,
+        listener.into(),
+        SDKAggSelector::new(opts.use_seconds_for_durations),
+    )?;
+    let (meter, abort_handle) = run_prom_server(&opts, srv, exporter)?;
+    Ok(StartedPromListener {
+        meter,
+        abort_handle,
+    })
+}
+
+fn run_prom_server(
+    opts: &PrometheusExporterOptions,
+    srv: PromServer,
+    exporter: PrometheusExporter,
+) -> Result<(Arc<CoreOtelMeter>, AbortHandle), anyhow::Error> {
+    // Global tags are already labels on every series, so they're kept out of the resource, which
+    // would repeat them on `target_info`
     let meter_provider = augment_meter_provider_with_defaults(
         MeterProviderBuilder::default().with_reader(exporter),
-        &opts.global_tags,
+        &HashMap::new(),
         opts.use_seconds_for_durations,
+        &opts.metric_prefix,
+        &opts.histogram_bucket_overrides,
     )?
     .build();
-    let bound_addr = srv.bound_addr()?;
     let handle = tokio::spawn(async move { srv.run().await });
-    Ok(StartedPromServer {
-        meter: Arc::new(CoreOtelMeter {
+    Ok((
+        Arc::new(CoreOtelMeter {
             meter: meter_provider.meter(TELEM_SERVICE_NAME),
             use_seconds_for_durations: opts.use_seconds_for_durations,
         }),
-        bound_addr,
-        abort_handle: handle.abort_handle(),
-    })
+        handle.abort_handle(),
+    ))
 }
 
 #[derive(Debug)]
@@ -346,9 +575,170 @@ impl TemporalitySelector for ConstantTemporality {
     }
 }
 
-fn metric_temporality_to_selector(t: MetricTemporality) -> impl TemporalitySelector + Clone {
+/// Uses delta temporality for everything except up-down counters, which are always cumulative, as
+/// the OTel spec suggests for delta preferring exporters. Many backends which want deltas don't
+/// accept non-monotonic sums reported that way.
+#[derive(Clone)]
+struct DeltaPreferredTemporality;
+
+impl TemporalitySelector for DeltaPreferredTemporality {
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        match kind {
+            InstrumentKind::UpDownCounter | InstrumentKind::ObservableUpDownCounter => {
+                Temporality::Cumulative
+            }
+            _ => Temporality::Delta,
+        }
+    }
+}
+
+fn metric_temporality_to_selector(t: MetricTemporality) -> Box<dyn TemporalitySelector> {
     match t {
-        MetricTemporality::Cumulative => ConstantTemporality(Temporality::Cumulative),
-        MetricTemporality::Delta => ConstantTemporality(Temporality::Delta),
+        MetricTemporality::Cumulative => Box::new(ConstantTemporality(Temporality::Cumulative)),
+        MetricTemporality::Delta => Box::new(DeltaPreferredTemporality),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Encoder, Registry, TextEncoder};
+    use temporal_sdk_core_api::telemetry::METRIC_PREFIX;
+
+    fn prom_meter_with_overrides(
+        bucket_overrides: &HashMap<String, Vec<f64>>,
+    ) -> opentelemetry::metrics::Result<(CoreOtelMeter, Registry)> {
+        let registry = Registry::new();
+        let exporter = opentelemetry_prometheus::exporter()
+            .with_aggregation_selector(SDKAggSelector::new(false))
+            .without_scope_info()
+            .without_units()
+            .with_registry(registry.clone())
+            .build()?;
+        let mp = augment_meter_provider_with_defaults(
+            MeterProviderBuilder::default().with_reader(exporter),
+            &HashMap::new(),
+            false,
+            METRIC_PREFIX,
+            bucket_overrides,
+        )?
+        .build();
+        let meter = CoreOtelMeter {
+            meter: mp.meter(TELEM_SERVICE_NAME),
+            use_seconds_for_durations: false,
+        };
+        Ok((meter, registry))
+    }
+
+    fn bucket_bounds(registry: &Registry, metric: &str) -> Vec<String> {
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buf)
+            .unwrap();
+        String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .filter(|l| l.starts_with(&format!("{metric}_bucket")))
+            .filter_map(|l| Some(l.split("le=\"").nth(1)?.split('"').next()?.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn histogram_bucket_overrides_are_used() {
+        let (meter, registry) = prom_meter_with_overrides(&HashMap::from([
+            ("workflow_task_execution_latency".to_string(), vec![1., 2.]),
+            // Spelled with core's prefix, which must replace the default view all the same
+            ("temporal_workflow_endtoend_latency".to_string(), vec![5.]),
+            ("payload_size".to_string(), vec![1024., 1_048_576.]),
+        ]))
+        .unwrap();
+        let attrs = meter.new_attributes(NewAttributes::new(vec![]));
+        for metric in [
+            "temporal_workflow_task_execution_latency",
+            "temporal_workflow_endtoend_latency",
+            "temporal_activity_execution_latency",
+        ] {
+            meter
+                .histogram(MetricParameters::from(metric))
+                .record(1, &attrs);
+        }
+        meter
+            .histogram(MetricParameters::from("payload_size"))
+            .record(2000, &attrs);
+
+        assert_eq!(
+            bucket_bounds(&registry, "temporal_workflow_task_execution_latency"),
+            ["1", "2", "+Inf"]
+        );
+        assert_eq!(
+            bucket_bounds(&registry, "temporal_workflow_endtoend_latency"),
+            ["5", "+Inf"]
+        );
+        assert_eq!(
+            bucket_bounds(&registry, "payload_size"),
+            ["1024", "1048576", "+Inf"]
+        );
+        // Histograms without overrides keep their defaults
+        assert_eq!(
+            bucket_bounds(&registry, "temporal_activity_execution_latency").len(),
+            default_buckets_for(ACT_EXEC_LATENCY_NAME, false).len() + 1
+        );
+    }
+
+    #[test]
+    fn overrides_do_not_apply_to_metrics_sharing_a_suffix() {
+        let (meter, registry) = prom_meter_with_overrides(&HashMap::from([(
+            "activity_execution_latency".to_string(),
+            vec![1., 2.],
+        )]))
+        .unwrap();
+        let attrs = meter.new_attributes(NewAttributes::new(vec![]));
+        for metric in [
+            "temporal_activity_execution_latency",
+            "temporal_local_activity_execution_latency",
+        ] {
+            meter
+                .histogram(MetricParameters::from(metric))
+                .record(1, &attrs);
+        }
+
+        assert_eq!(
+            bucket_bounds(&registry, "temporal_activity_execution_latency"),
+            ["1", "2", "+Inf"]
+        );
+        assert_eq!(
+            bucket_bounds(&registry, "temporal_local_activity_execution_latency").len(),
+            default_buckets_for(LA_EXEC_LATENCY_NAME, false).len() + 1
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::unsorted(vec![2., 1.])]
+    #[case::duplicated(vec![1., 1.])]
+    #[case::nan(vec![1., f64::NAN])]
+    #[case::infinite(vec![1., f64::INFINITY])]
+    fn invalid_histogram_bucket_overrides_are_rejected(#[case] buckets: Vec<f64>) {
+        let res =
+            prom_meter_with_overrides(&HashMap::from([("payload_size".to_string(), buckets)]));
+        assert_matches!(res, Err(MetricsError::Config(_)));
+    }
+
+    #[test]
+    fn delta_temporality_keeps_up_down_counters_cumulative() {
+        let selector = metric_temporality_to_selector(MetricTemporality::Delta);
+        for kind in [
+            InstrumentKind::Counter,
+            InstrumentKind::Histogram,
+            InstrumentKind::ObservableCounter,
+            InstrumentKind::ObservableGauge,
+        ] {
+            assert_eq!(selector.temporality(kind), Temporality::Delta);
+        }
+        for kind in [
+            InstrumentKind::UpDownCounter,
+            InstrumentKind::ObservableUpDownCounter,
+        ] {
+            assert_eq!(selector.temporality(kind), Temporality::Cumulative);
+        }
     }
 }
@@ -1,3 +1,4 @@
+use super::metrics::RESERVED_ATTRIBUTE_KEYS;
 use http_body_util::Full;
 use hyper::{body::Bytes, header::CONTENT_TYPE, service::service_fn, Method, Request, Response};
 use hyper_util::{
@@ -9,11 +10,35 @@ use opentelemetry_sdk::metrics::reader::AggregationSelector;
 use prometheus::{Encoder, Registry, TextEncoder};
 use std::net::{SocketAddr, TcpListener};
 use temporal_sdk_core_api::telemetry::PrometheusExporterOptions;
-use tokio::io;
+use tokio::io::{self, AsyncRead, AsyncWrite};
+
+/// A listener the prometheus scrape endpoint may be served from, for when binding a TCP socket
+/// at [PrometheusExporterOptions::socket_addr] isn't suitable
+#[derive(Debug)]
+pub enum PrometheusListener {
+    /// An already bound TCP listener
+    Tcp(TcpListener),
+    /// A Unix domain socket listener
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl From<TcpListener> for PrometheusListener {
+    fn from(l: TcpListener) -> Self {
+        Self::Tcp(l)
+    }
+}
+
+#[cfg(unix)]
+impl From<std::os::unix::net::UnixListener> for PrometheusListener {
+    fn from(l: std::os::unix::net::UnixListener) -> Self {
+        Self::Unix(l)
+    }
+}
 
 /// Exposes prometheus metrics for scraping
 pub(super) struct PromServer {
-    listener: TcpListener,
+    listener: PrometheusListener,
     registry: Registry,
 }
 
@@ -22,7 +47,50 @@ impl PromServer {
         opts: &PrometheusExporterOptions,
         aggregation: impl AggregationSelector + Send + Sync + 'static,
     ) -> Result<(Self, PrometheusExporter), anyhow::Error> {
-        let registry = Registry::new();
+        let Some(addr) = opts.socket_addr else {
+            return Err(anyhow::anyhow!(
+                "A socket address to serve prometheus metrics on must be set"
+            ));
+        };
+        let listener = TcpListener::bind(addr)?;
+        Self::from_listener(opts, listener.into(), aggregation)
+    }
+
+    pub fn with_listener(
+        opts: &PrometheusExporterOptions,
+        listener: PrometheusListener,
+        aggregation: impl AggregationSelector + 'static,
+    ) -> Result<(Self, PrometheusExporter), anyhow::Error> {
+        if opts.socket_addr.is_some() {
+            return Err(anyhow::anyhow!(
+                "A socket address must not be set when serving prometheus metrics from a listener"
+            ));
+        }
+        Self::from_listener(opts, listener, aggregation)
+    }
+
+    fn from_listener(
+        opts: &PrometheusExporterOptions,
+        listener: PrometheusListener,
+        aggregation: impl AggregationSelector + 'static,
+    ) -> Result<(Self, PrometheusExporter), anyhow::Error> {
+        if let Some(bad) = opts.global_tags.keys().find(|k| !is_valid_label_name(k)) {
+            return Err(anyhow::anyhow!("Invalid prometheus label name: {bad:?}"));
+        }
+        // The registry doesn't deduplicate labels, so a global tag sharing a name with one of the
+        // attributes core's metrics carry would make for invalid series
+        if let Some(taken) = opts
+            .global_tags
+            .keys()
+            .find(|k| RESERVED_ATTRIBUTE_KEYS.contains(&k.as_str()))
+        {
+            return Err(anyhow::anyhow!(
+                "Global tag {taken:?} collides with a label core's metrics are recorded with"
+            ));
+        }
+        // Global tags are attached by the registry, so that every series carries them
+        let labels = (!opts.global_tags.is_empty()).then(|| opts.global_tags.clone());
+        let registry = Registry::new_custom(None, labels)?;
         let exporter = opentelemetry_prometheus::exporter()
             .with_aggregation_selector(aggregation)
             .without_scope_info()
@@ -37,44 +105,74 @@ impl PromServer {
         } else {
             exporter
         };
-        Ok((
-            Self {
-                listener: TcpListener::bind(opts.socket_addr)?,
-                registry,
-            },
-            exporter.build()?,
-        ))
+        Ok((Self { listener, registry }, exporter.build()?))
     }
 
     pub async fn run(self) -> Result<(), anyhow::Error> {
         // Spin up hyper server to serve metrics for scraping. We use hyper since we already depend
         // on it via Tonic.
-        self.listener.set_nonblocking(true)?;
-        let listener = tokio::net::TcpListener::from_std(self.listener)?;
-        loop {
-            let (stream, _) = listener.accept().await?;
-            let io = TokioIo::new(stream);
-            let regclone = self.registry.clone();
-            tokio::task::spawn(async move {
-                let server = auto::Builder::new(TokioExecutor::new());
-                if let Err(e) = server
-                    .serve_connection(
-                        io,
-                        service_fn(move |req| metrics_req(req, regclone.clone())),
-                    )
-                    .await
-                {
-                    warn!("Error serving metrics connection: {:?}", e);
+        match self.listener {
+            PrometheusListener::Tcp(listener) => {
+                listener.set_nonblocking(true)?;
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    serve_connection(stream, self.registry.clone());
+                }
+            }
+            #[cfg(unix)]
+            PrometheusListener::Unix(listener) => {
+                listener.set_nonblocking(true)?;
+                let listener = tokio::net::UnixListener::from_std(listener)?;
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    serve_connection(stream, self.registry.clone());
                 }
-            });
+            }
         }
     }
 
+    /// The address the server listens on, if it's listening on a TCP socket
     pub fn bound_addr(&self) -> io::Result<SocketAddr> {
-        self.listener.local_addr()
+        match &self.listener {
+            PrometheusListener::Tcp(l) => l.local_addr(),
+            #[cfg(unix)]
+            PrometheusListener::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Prometheus server is listening on a Unix socket",
+            )),
+        }
     }
 }
 
+fn serve_connection(
+    stream: impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    registry: Registry,
+) {
+    let io = TokioIo::new(stream);
+    tokio::task::spawn(async move {
+        let server = auto::Builder::new(TokioExecutor::new());
+        if let Err(e) = server
+            .serve_connection(
+                io,
+                service_fn(move |req| metrics_req(req, registry.clone())),
+            )
+            .await
+        {
+            warn!("Error serving metrics connection: {:?}", e);
+        }
+    });
+}
+
+/// Prometheus label names must match `[a-zA-Z_][a-zA-Z0-9_]*`, and those starting with `__` are
+/// reserved
+fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__")
+}
+
 /// Serves prometheus metrics in the expected format for scraping
 async fn metrics_req(
     req: Request<hyper::body::Incoming>,
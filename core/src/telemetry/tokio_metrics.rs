@@ -0,0 +1,220 @@
+//! Periodic sampling of the metrics tokio keeps about the runtime core runs on. Helpful for telling
+//! whether a slow worker is starved of runtime threads, or blocked on something else.
+//!
+//! Queue depths of the individual worker threads and blocking pool usage are only available when
+//! built with `RUSTFLAGS="--cfg tokio_unstable"`.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use temporal_sdk_core_api::telemetry::metrics::{
+    CoreMeter, Gauge, GaugeF64, MetricAttributes, MetricParameters, TemporalMeter,
+};
+use tokio::{
+    runtime::{Handle, RuntimeMetrics},
+    task::AbortHandle,
+    time::MissedTickBehavior,
+};
+
+/// Start recording the metrics of the runtime `handle` refers to with `meter`, sampling them
+/// every `interval`, until aborted with the returned handle.
+pub(crate) fn start_tokio_metrics_sampler(
+    handle: &Handle,
+    meter: TemporalMeter,
+    interval: Duration,
+) -> AbortHandle {
+    let mut sampler = TokioMetricsSampler::new(handle.metrics(), meter);
+    handle
+        .spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                sampler.sample();
+            }
+        })
+        .abort_handle()
+}
+
+struct TokioMetricsSampler {
+    metrics: RuntimeMetrics,
+    attributes: MetricAttributes,
+    workers: Arc<dyn Gauge>,
+    busy_workers: Arc<dyn GaugeF64>,
+    alive_tasks: Arc<dyn Gauge>,
+    global_queue_depth: Arc<dyn Gauge>,
+    #[cfg(tokio_unstable)]
+    local_queue_depth: Arc<dyn Gauge>,
+    #[cfg(tokio_unstable)]
+    blocking_threads: Arc<dyn Gauge>,
+    #[cfg(tokio_unstable)]
+    idle_blocking_threads: Arc<dyn Gauge>,
+    #[cfg(tokio_unstable)]
+    blocking_queue_depth: Arc<dyn Gauge>,
+    /// Total time all workers had spent busy at the last sample, and when it was taken
+    last_busy: (Duration, Instant),
+}
+
+impl TokioMetricsSampler {
+    fn new(metrics: RuntimeMetrics, meter: TemporalMeter) -> Self {
+        let gauge = |name: &'static str, description: &'static str| {
+            meter.inner.gauge(MetricParameters {
+                name: name.into(),
+                description: description.into(),
+                unit: "".into(),
+            })
+        };
+        let last_busy = (total_busy_duration(&metrics), Instant::now());
+        Self {
+            attributes: meter.inner.new_attributes(meter.default_attribs.clone()),
+            workers: gauge("tokio_workers", "Number of tokio runtime worker threads"),
+            busy_workers: meter.inner.gauge_f64(MetricParameters {
+                name: "tokio_busy_workers".into(),
+                description: "Average number of tokio runtime worker threads which were busy \
+                              since the last sample"
+                    .into(),
+                unit: "".into(),
+            }),
+            alive_tasks: gauge(
+                "tokio_alive_tasks",
+                "Number of tasks alive in the tokio runtime",
+            ),
+            global_queue_depth: gauge(
+                "tokio_global_queue_depth",
+                "Number of tasks waiting in the tokio runtime's global queue",
+            ),
+            #[cfg(tokio_unstable)]
+            local_queue_depth: gauge(
+                "tokio_local_queue_depth",
+                "Number of tasks waiting in the local queues of all tokio runtime worker threads",
+            ),
+            #[cfg(tokio_unstable)]
+            blocking_threads: gauge(
+                "tokio_blocking_threads",
+                "Number of threads in the tokio runtime's blocking pool",
+            ),
+            #[cfg(tokio_unstable)]
+            idle_blocking_threads: gauge(
+                "tokio_idle_blocking_threads",
+                "Number of idle threads in the tokio runtime's blocking pool",
+            ),
+            #[cfg(tokio_unstable)]
+            blocking_queue_depth: gauge(
+                "tokio_blocking_queue_depth",
+                "Number of tasks waiting for a thread in the tokio runtime's blocking pool",
+            ),
+            last_busy,
+            metrics,
+        }
+    }
+
+    fn sample(&mut self) {
+        let m = &self.metrics;
+        let attrs = &self.attributes;
+        self.workers.record(m.num_workers() as u64, attrs);
+        self.alive_tasks.record(m.num_alive_tasks() as u64, attrs);
+        self.global_queue_depth
+            .record(m.global_queue_depth() as u64, attrs);
+
+        let (last_total, last_at) = self.last_busy;
+        let now = (total_busy_duration(m), Instant::now());
+        let elapsed = now.1.duration_since(last_at).as_secs_f64();
+        if elapsed > 0.0 {
+            let busy = now.0.saturating_sub(last_total).as_secs_f64();
+            self.busy_workers.record(busy / elapsed, attrs);
+        }
+        self.last_busy = now;
+
+        #[cfg(tokio_unstable)]
+        {
+            let local_depth: usize = (0..m.num_workers())
+                .map(|w| m.worker_local_queue_depth(w))
+                .sum();
+            self.local_queue_depth.record(local_depth as u64, attrs);
+            self.blocking_threads
+                .record(m.num_blocking_threads() as u64, attrs);
+            self.idle_blocking_threads
+                .record(m.num_idle_blocking_threads() as u64, attrs);
+            self.blocking_queue_depth
+                .record(m.blocking_queue_depth() as u64, attrs);
+        }
+    }
+}
+
+fn total_busy_duration(metrics: &RuntimeMetrics) -> Duration {
+    (0..metrics.num_workers())
+        .map(|w| metrics.worker_total_busy_duration(w))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        telemetry::{metrics::MetricsCallBuffer, TelemetryInstance},
+        CoreRuntime,
+    };
+    use temporal_sdk_core_api::telemetry::{
+        metrics::{BufferInstrumentRef, MetricCallBufferer, MetricEvent, MetricUpdateVal},
+        TelemetryOptions, METRIC_PREFIX,
+    };
+
+    #[derive(Debug, Clone)]
+    struct DummyInstrumentRef(String);
+    impl BufferInstrumentRef for DummyInstrumentRef {}
+
+    #[tokio::test]
+    async fn zero_interval_rejected() {
+        let mut runtime = CoreRuntime::new_assume_tokio(TelemetryOptions::default()).unwrap();
+        assert!(runtime.enable_tokio_metrics(Duration::ZERO).is_err());
+        runtime
+            .enable_tokio_metrics(Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn samples_runtime_metrics() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+        let call_buffer = Arc::new(MetricsCallBuffer::<DummyInstrumentRef>::new(100));
+        let telem = TelemetryInstance::new(
+            None,
+            None,
+            METRIC_PREFIX.to_string(),
+            Some(call_buffer.clone()),
+            false,
+        );
+        let sampler = start_tokio_metrics_sampler(
+            runtime.handle(),
+            telem.get_temporal_metric_meter().unwrap(),
+            Duration::from_millis(10),
+        );
+        std::thread::sleep(Duration::from_millis(100));
+        sampler.abort();
+
+        let mut workers = vec![];
+        for event in call_buffer.retrieve() {
+            match event {
+                MetricEvent::Create {
+                    params,
+                    populate_into,
+                    ..
+                } => populate_into
+                    .set(Arc::new(DummyInstrumentRef(params.name.to_string())))
+                    .unwrap(),
+                MetricEvent::Update {
+                    instrument,
+                    update: MetricUpdateVal::Value(v),
+                    ..
+                } if instrument.get().0 == "temporal_tokio_workers" => workers.push(v),
+                _ => {}
+            }
+        }
+        assert!(!workers.is_empty());
+        assert!(workers.iter().all(|&w| w == 2));
+    }
+}
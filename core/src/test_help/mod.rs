@@ -1,6 +1,7 @@
 pub(crate) use temporal_sdk_core_test_utils::canned_histories;
 
 use crate::{
+    abstractions::SharedSlots,
     pollers::{BoxedPoller, MockManualPoller, MockPoller},
     protosext::ValidPollWFTQResponse,
     replay::TestHistoryBuilder,
@@ -165,6 +166,7 @@ pub(crate) fn mock_worker(mocks: MocksHolder) -> Worker {
             act_poller,
         },
         None,
+        SharedSlots::default(),
     )
 }
 
@@ -1,6 +1,8 @@
 mod activity_heartbeat_manager;
 mod activity_task_poller_stream;
+mod activity_type_rate_limits;
 mod local_activities;
+mod result_memo;
 
 pub(crate) use local_activities::{
     ExecutingLAId, LACompleteAction, LocalActRequest, LocalActivityExecutionResult,
@@ -12,12 +14,15 @@ use crate::{
         ClosableMeteredSemaphore, MeteredSemaphore, OwnedMeteredSemPermit,
         TrackedOwnedMeteredSemPermit, UsedMeteredSemPermit,
     },
-    pollers::BoxedActPoller,
+    pollers::{BoxedActPoller, PollGate},
+    retry_logic::RetryPolicyExt,
     telemetry::metrics::{activity_type, eager, workflow_type, MetricsContext},
     worker::{
         activities::{
             activity_heartbeat_manager::ActivityHeartbeatError,
             activity_task_poller_stream::new_activity_task_poller,
+            activity_type_rate_limits::ActivityTypeRateLimits,
+            result_memo::{ActivityResultMemo, MemoKey},
         },
         client::WorkerClient,
     },
@@ -31,21 +36,27 @@ use futures::{
     Stream, StreamExt,
 };
 use std::{
+    collections::{HashMap, HashSet},
     convert::TryInto,
     future,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
+use temporal_sdk_core_api::worker::PendingActivityHandoff;
 use temporal_sdk_core_protos::{
     coresdk::{
         activity_result::{self as ar, activity_execution_result as aer},
-        activity_task::{ActivityCancelReason, ActivityTask},
-        ActivityHeartbeat,
+        activity_task::{
+            activity_task, ActivityCancelReason, ActivityCancellationDetails, ActivityTask, Cancel,
+        },
+        ActivityHeartbeat, IntoPayloadsExt,
     },
     temporal::api::{
+        common::v1::Payload,
+        enums::v1::TimeoutType,
         failure::v1::{failure::FailureInfo, ApplicationFailureInfo, CanceledFailureInfo, Failure},
         workflowservice::v1::PollActivityTaskQueueResponse,
     },
@@ -63,6 +74,12 @@ use tokio_util::sync::CancellationToken;
 use tracing::Span;
 
 type OutstandingActMap = Arc<DashMap<TaskToken, RemoteInFlightActInfo>>;
+/// Handed off activities, along with the task which forgets them once their attempt times out
+type HandedOffActMap = Arc<DashMap<TaskToken, (PendingActivityHandoff, JoinHandle<()>)>>;
+
+/// How long a handed off activity is remembered if the server did not tell us any timeout which
+/// would bound its attempt
+const MAX_HANDOFF_TRACKING: Duration = Duration::from_secs(60 * 60 * 24);
 
 #[derive(Debug)]
 struct PendingActivityCancel {
@@ -70,6 +87,9 @@ struct PendingActivityCancel {
     reason: ActivityCancelReason,
     /// Set true if we should assume the server has already forgotten about this activity
     consider_not_found: bool,
+    details: ActivityCancellationDetails,
+    /// Set if this follows up on an earlier cancel which lang did not act on in time
+    hard: bool,
 }
 
 impl PendingActivityCancel {
@@ -78,6 +98,19 @@ impl PendingActivityCancel {
             task_token,
             reason,
             consider_not_found: false,
+            details: Default::default(),
+            hard: false,
+        }
+    }
+
+    /// A cancel core learned about from the response to a heartbeat
+    pub fn from_heartbeat(task_token: TaskToken, reason: ActivityCancelReason) -> Self {
+        Self {
+            details: ActivityCancellationDetails {
+                from_heartbeat: true,
+                ..Default::default()
+            },
+            ..Self::new(task_token, reason)
         }
     }
 }
@@ -85,6 +118,7 @@ impl PendingActivityCancel {
 /// Contains details that core wants to store while an activity is running.
 #[derive(Debug)]
 struct InFlightActInfo {
+    pub activity_id: String,
     pub activity_type: String,
     pub workflow_type: String,
     /// Only kept for logging reasons
@@ -108,27 +142,71 @@ struct RemoteInFlightActInfo {
     pub known_not_found: bool,
     /// Handle to the task containing local timeout tracking, if any.
     pub local_timeouts_task: Option<JoinHandle<()>>,
+    /// Handle to the task which will hard cancel this activity if it does not complete within the
+    /// cancel grace period, if any.
+    hard_cancel_task: Option<JoinHandle<()>>,
     /// Used to reset the local heartbeat timeout every time we record a heartbeat
     timeout_resetter: Option<Arc<Notify>>,
+    /// Set if lang overrode the heartbeat throttle interval for this activity
+    throttle_interval_override: Option<Duration>,
+    /// The most recent heartbeat details lang recorded, attached to failures so they are not
+    /// lost if the final heartbeat could not be delivered
+    last_heartbeat_details: Vec<Payload>,
+    /// When the server will have timed out this attempt, or [MAX_HANDOFF_TRACKING] after it
+    /// started if it has no close timeouts
+    attempt_deadline: Instant,
+    /// Set if this activity's type has opted in to result memoization
+    memo_key: Option<MemoKey>,
     /// The permit from the max concurrent semaphore
     _permit: UsedMeteredSemPermit,
 }
 impl RemoteInFlightActInfo {
     fn new(poll_resp: &PollActivityTaskQueueResponse, permit: UsedMeteredSemPermit) -> Self {
         let wec = poll_resp.workflow_execution.clone().unwrap_or_default();
+        let start_time = Instant::now();
+        let start_to_close = poll_resp
+            .start_to_close_timeout
+            .clone()
+            .and_then(|d| d.try_into().ok())
+            .map(|d: Duration| start_time + d);
+        // Schedule-to-close is measured from when the activity was first scheduled
+        let schedule_to_close = poll_resp
+            .schedule_to_close_timeout
+            .clone()
+            .and_then(|d| d.try_into().ok())
+            .map(|d: Duration| {
+                let already_elapsed = poll_resp
+                    .scheduled_time
+                    .clone()
+                    .and_then(|t| SystemTime::try_from(t).ok())
+                    .and_then(|t| t.elapsed().ok())
+                    .unwrap_or_default();
+                start_time + d.saturating_sub(already_elapsed)
+            });
+        let attempt_deadline = [start_to_close, schedule_to_close]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(start_time + MAX_HANDOFF_TRACKING);
         Self {
             base: InFlightActInfo {
+                activity_id: poll_resp.activity_id.clone(),
                 activity_type: poll_resp.activity_type.clone().unwrap_or_default().name,
                 workflow_type: poll_resp.workflow_type.clone().unwrap_or_default().name,
                 workflow_id: wec.workflow_id,
                 workflow_run_id: wec.run_id,
-                start_time: Instant::now(),
+                start_time,
             },
             heartbeat_timeout: poll_resp.heartbeat_timeout.clone(),
             issued_cancel_to_lang: None,
             known_not_found: false,
             local_timeouts_task: None,
+            hard_cancel_task: None,
             timeout_resetter: None,
+            throttle_interval_override: None,
+            last_heartbeat_details: vec![],
+            attempt_deadline,
+            memo_key: None,
             _permit: permit,
         }
     }
@@ -144,6 +222,10 @@ pub(crate) struct WorkerActivityTasks {
     activity_task_stream: Mutex<BoxStream<'static, Result<ActivityTask, PollActivityError>>>,
     /// Activities that have been issued to lang but not yet completed
     outstanding_activity_tasks: OutstandingActMap,
+    /// Activities lang has handed off to be completed outside the worker
+    handed_off_activities: HandedOffActMap,
+    /// Successful results which could not be reported to the server, if memoization is enabled
+    result_memo: Option<Arc<ActivityResultMemo>>,
     /// Ensures we don't exceed this worker's maximum concurrent activity limit for activities. This
     /// semaphore is used to limit eager activities but shares the same underlying
     /// [MeteredSemaphore] that is used to limit the concurrency for non-eager activities.
@@ -160,6 +242,7 @@ pub(crate) struct WorkerActivityTasks {
 
     max_heartbeat_throttle_interval: Duration,
     default_heartbeat_throttle_interval: Duration,
+    heartbeat_throttle_interval: Option<Duration>,
 
     /// Wakes every time an activity is removed from the outstanding map
     complete_notify: Arc<Notify>,
@@ -182,9 +265,16 @@ impl WorkerActivityTasks {
         metrics: MetricsContext,
         max_heartbeat_throttle_interval: Duration,
         default_heartbeat_throttle_interval: Duration,
+        heartbeat_throttle_interval: Option<Duration>,
         graceful_shutdown: Option<Duration>,
+        cancel_grace_period: Option<Duration>,
+        type_rate_limits: &HashMap<String, f64>,
+        result_memo: Option<(&HashSet<String>, usize)>,
         local_timeout_buffer: Duration,
     ) -> Self {
+        let result_memo = result_memo
+            .and_then(|(types, max_results)| ActivityResultMemo::new(types, max_results))
+            .map(Arc::new);
         let shutdown_initiated_token = CancellationToken::new();
         let outstanding_activity_tasks = Arc::new(DashMap::new());
         let server_poller_stream =
@@ -197,6 +287,8 @@ impl WorkerActivityTasks {
             eager_activities_rx,
             server_poller_stream,
             eager_activities_semaphore.clone(),
+            ActivityTypeRateLimits::new(type_rate_limits, metrics.clone()).map(Arc::new),
+            shutdown_initiated_token.clone(),
             start_tasks_stream_complete.clone(),
         );
         let (cancels_tx, cancels_rx) = unbounded_channel();
@@ -214,7 +306,9 @@ impl WorkerActivityTasks {
             start_tasks_stream_complete,
             complete_notify: complete_notify.clone(),
             grace_period: graceful_shutdown,
+            cancel_grace_period,
             cancels_tx,
+            result_memo: result_memo.clone(),
             local_timeout_buffer,
             shutdown_initiated_token: shutdown_initiated_token.clone(),
             metrics: metrics.clone(),
@@ -231,8 +325,11 @@ impl WorkerActivityTasks {
             metrics,
             max_heartbeat_throttle_interval,
             default_heartbeat_throttle_interval,
+            heartbeat_throttle_interval,
             poll_returned_shutdown_token: CancellationToken::new(),
             outstanding_activity_tasks,
+            handed_off_activities: Default::default(),
+            result_memo,
             completers_lock: Default::default(),
         }
     }
@@ -242,6 +339,8 @@ impl WorkerActivityTasks {
         non_poll_tasks_rx: UnboundedReceiver<TrackedPermittedTqResp>,
         poller_stream: impl Stream<Item = Result<PermittedTqResp, tonic::Status>>,
         eager_activities_semaphore: Arc<ClosableMeteredSemaphore>,
+        type_rate_limits: Option<Arc<ActivityTypeRateLimits>>,
+        shutdown_token: CancellationToken,
         on_complete_token: CancellationToken,
     ) -> impl Stream<Item = Result<(PermittedTqResp, bool), PollActivityError>> {
         let non_poll_stream = stream::unfold(
@@ -271,8 +370,45 @@ impl WorkerActivityTasks {
         let poller_stream = poller_stream.map(|res| res.map(|task| (task, false)));
 
         // Prefer eager activities over polling the server
-        stream::select_with_strategy(non_poll_stream, poller_stream, |_: &mut ()| PollNext::Left)
-            .map(|res| Some(res.map_err(Into::into)))
+        let starts = stream::select_with_strategy(non_poll_stream, poller_stream, |_: &mut ()| {
+            PollNext::Left
+        })
+        .map(|res| res.map_err(Into::into));
+        // Tasks held back by their type's rate limit wait concurrently, so they don't hold up
+        // tasks of other types. The number waiting is bounded by the activity slots they hold.
+        // Tasks still waiting when shutdown begins are dropped, freeing their slots, and will be
+        // retried by the server once they time out.
+        let starts = if let Some(limits) = type_rate_limits {
+            starts
+                .map(move |res| {
+                    let limits = limits.clone();
+                    let shutdown_token = shutdown_token.clone();
+                    async move {
+                        if let Ok((task, _)) = &res {
+                            let act_type =
+                                task.resp.activity_type.as_ref().map(|t| t.name.as_str());
+                            tokio::select! {
+                                biased;
+
+                                _ = limits.until_ready(act_type.unwrap_or_default()) => {}
+                                _ = shutdown_token.cancelled() => {
+                                    debug!(activity_id=%task.resp.activity_id,
+                                           "Dropping rate limited activity task on shutdown");
+                                    return None;
+                                }
+                            }
+                        }
+                        Some(res)
+                    }
+                })
+                .buffer_unordered(usize::MAX)
+                .filter_map(future::ready)
+                .left_stream()
+        } else {
+            starts.right_stream()
+        };
+        starts
+            .map(Some)
             .chain(futures::stream::once(async move {
                 on_complete_token.cancel();
                 None
@@ -290,6 +426,32 @@ impl WorkerActivityTasks {
         let _ = self.completers_lock.write().await;
         self.poll_returned_shutdown_token.cancelled().await;
         self.heartbeat_manager.shutdown().await;
+        let pending_handoffs = self.pending_handoffs();
+        if !pending_handoffs.is_empty() {
+            info!(
+                activities=?pending_handoffs
+                    .iter()
+                    .map(|h| &h.activity_id)
+                    .collect::<Vec<_>>(),
+                "Worker shut down with handed off activities which have not yet timed out. They \
+                 must still be completed externally."
+            );
+        }
+    }
+
+    /// Returns the handed off activities which have not yet timed out or been completed
+    pub(crate) fn pending_handoffs(&self) -> Vec<PendingActivityHandoff> {
+        self.handed_off_activities
+            .iter()
+            .map(|e| e.value().0.clone())
+            .collect()
+    }
+
+    /// Forget a handed off activity, because it has been completed externally
+    pub(crate) fn handoff_completed(&self, task_token: &TaskToken) {
+        if let Some((_, (_, expiry_task))) = self.handed_off_activities.remove(task_token) {
+            expiry_task.abort();
+        }
     }
 
     /// Exclusive poll for activity tasks
@@ -313,6 +475,33 @@ impl WorkerActivityTasks {
         client: &dyn WorkerClient,
     ) {
         if let Some((_, act_info)) = self.outstanding_activity_tasks.remove(&task_token) {
+            if matches!(status, aer::Status::WillCompleteAsync(_)) {
+                debug!(task_token=%task_token, activity_id=%act_info.base.activity_id,
+                       "Activity handed off to be completed externally");
+                // The server will have timed the attempt out by its deadline, so there's no point
+                // remembering it any longer than that
+                let handed_off = self.handed_off_activities.clone();
+                let tt = task_token.clone();
+                let deadline = act_info.attempt_deadline;
+                let expiry_task = tokio::task::spawn(async move {
+                    tokio::time::sleep_until(deadline.into()).await;
+                    handed_off.remove(&tt);
+                });
+                self.handed_off_activities.insert(
+                    task_token.clone(),
+                    (
+                        PendingActivityHandoff {
+                            task_token: task_token.0.clone(),
+                            activity_id: act_info.base.activity_id.clone(),
+                            activity_type: act_info.base.activity_type.clone(),
+                            workflow_id: act_info.base.workflow_id.clone(),
+                            workflow_run_id: act_info.base.workflow_run_id.clone(),
+                            handed_off_at: SystemTime::now(),
+                        },
+                        expiry_task,
+                    ),
+                );
+            }
             let act_metrics = self.metrics.with_new_attrs([
                 activity_type(act_info.base.activity_type),
                 workflow_type(act_info.base.workflow_type),
@@ -321,10 +510,14 @@ impl WorkerActivityTasks {
             Span::current().record("run_id", act_info.base.workflow_run_id);
             act_metrics.act_execution_latency(act_info.base.start_time.elapsed());
             let known_not_found = act_info.known_not_found;
+            let last_heartbeat_details = act_info.last_heartbeat_details.into_payloads();
 
             if let Some(jh) = act_info.local_timeouts_task {
                 jh.abort()
             };
+            if let Some(jh) = act_info.hard_cancel_task {
+                jh.abort()
+            };
             self.heartbeat_manager.evict(task_token.clone()).await;
 
             // No need to report activities which we already know the server doesn't care about
@@ -332,29 +525,60 @@ impl WorkerActivityTasks {
                 let _flushing_guard = self.completers_lock.read().await;
                 let maybe_net_err = match status {
                     aer::Status::WillCompleteAsync(_) => None,
-                    aer::Status::Completed(ar::Success { result }) => client
-                        .complete_activity_task(task_token.clone(), result.map(Into::into))
-                        .await
-                        .err(),
+                    aer::Status::Completed(ar::Success { result }) => {
+                        let to_memoize = self
+                            .result_memo
+                            .as_ref()
+                            .zip(act_info.memo_key)
+                            .map(|(memo, key)| (memo, key, result.clone()));
+                        let err = client
+                            .complete_activity_task(task_token.clone(), result.map(Into::into))
+                            .await
+                            .err();
+                        if let Some((memo, key, result)) = to_memoize {
+                            if err
+                                .as_ref()
+                                .is_some_and(|e| e.code() != tonic::Code::NotFound)
+                            {
+                                debug!(task_token=%task_token,
+                                       "Memoizing activity result which could not be reported");
+                                memo.record(key, result);
+                            }
+                        }
+                        err
+                    }
                     aer::Status::Failed(ar::Failure { failure }) => {
                         act_metrics.act_execution_failed();
                         client
-                            .fail_activity_task(task_token.clone(), failure.map(Into::into))
+                            .fail_activity_task(
+                                task_token.clone(),
+                                failure.map(Into::into),
+                                last_heartbeat_details,
+                            )
                             .await
                             .err()
                     }
                     aer::Status::Cancelled(ar::Cancellation { failure }) => {
-                        if matches!(
-                            act_info.issued_cancel_to_lang,
-                            Some(ActivityCancelReason::WorkerShutdown),
-                        ) {
+                        if let Some(
+                            reason @ (ActivityCancelReason::WorkerShutdown
+                            | ActivityCancelReason::Paused),
+                        ) = act_info.issued_cancel_to_lang
+                        {
                             // We report cancels for graceful shutdown as failures, so we
                             // don't wait for the whole timeout to elapse, which is what would
-                            // happen anyway.
+                            // happen anyway. Paused activities weren't asked to cancel, so the
+                            // server wouldn't accept a cancel for them. Failing the attempt
+                            // instead leaves the server to retry it once unpaused.
+                            let failure = if reason == ActivityCancelReason::Paused {
+                                activity_paused_failure()
+                            } else {
+                                worker_shutdown_failure()
+                            };
                             client
                                 .fail_activity_task(
                                     task_token.clone(),
-                                    Some(worker_shutdown_failure()),
+                                    Some(failure),
+                                    last_heartbeat_details,
                                 )
                                 .await
                                 .err()
@@ -401,16 +625,38 @@ impl WorkerActivityTasks {
         }
     }
 
+    /// If the task is a retry of an activity whose result is memoized, removes and returns that
+    /// result
+    pub(crate) fn take_memoized_result(&self, task: &ActivityTask) -> Option<Option<Payload>> {
+        let memo = self.result_memo.as_ref()?;
+        let Some(activity_task::Variant::Start(ref start)) = task.variant else {
+            return None;
+        };
+        if start.attempt <= 1 {
+            return None;
+        }
+        let act_info = self
+            .outstanding_activity_tasks
+            .get(&TaskToken(task.task_token.clone()))?;
+        memo.take(act_info.memo_key.as_ref()?)
+    }
+
     /// Attempt to record an activity heartbeat
     pub(crate) fn record_heartbeat(
         &self,
         details: ActivityHeartbeat,
     ) -> Result<(), ActivityHeartbeatError> {
         // TODO: Propagate these back as cancels. Silent fails is too nonobvious
-        let at_info = self
+        let mut at_info = self
             .outstanding_activity_tasks
-            .get(&TaskToken(details.task_token.clone()))
+            .get_mut(&TaskToken(details.task_token.clone()))
             .ok_or(ActivityHeartbeatError::UnknownActivity)?;
+        if let Some(ti) = details.throttle_interval.clone() {
+            at_info.throttle_interval_override = Some(
+                ti.try_into()
+                    .or(Err(ActivityHeartbeatError::InvalidThrottleInterval))?,
+            );
+        }
         let heartbeat_timeout: Duration = at_info
             .heartbeat_timeout
             .clone()
@@ -424,22 +670,29 @@ impl WorkerActivityTasks {
         // There is a bug in the server that translates non-set heartbeat timeouts into 0 duration.
         // That's why we treat 0 the same way as None, otherwise we wouldn't know which aggregation
         // delay to use, and using 0 is not a good idea as SDK would hammer the server too hard.
+        let configured = at_info
+            .throttle_interval_override
+            .or(self.heartbeat_throttle_interval);
         let throttle_interval = if heartbeat_timeout.as_millis() == 0 {
-            self.default_heartbeat_throttle_interval
+            configured.unwrap_or(self.default_heartbeat_throttle_interval)
         } else {
-            heartbeat_timeout.mul_f64(0.8)
+            let derived = heartbeat_timeout.mul_f64(0.8);
+            configured.map_or(derived, |c| c.min(derived))
         };
         let throttle_interval =
             std::cmp::min(throttle_interval, self.max_heartbeat_throttle_interval);
+        at_info.last_heartbeat_details.clone_from(&details.details);
         self.heartbeat_manager
             .record(details, throttle_interval, at_info.timeout_resetter.clone())
     }
 
     /// Returns a handle that the workflows management side can use to interact with this manager
-    pub(crate) fn get_handle_for_workflows(&self) -> ActivitiesFromWFTsHandle {
+    /// Eager activity slots are not reserved while `poll_gate` does not allow polling.
+    pub(crate) fn get_handle_for_workflows(&self, poll_gate: PollGate) -> ActivitiesFromWFTsHandle {
         ActivitiesFromWFTsHandle {
             sem: self.eager_activities_semaphore.clone(),
             tx: self.eager_activities_tx.clone(),
+            poll_gate,
         }
     }
 
@@ -455,7 +708,10 @@ struct ActivityTaskStream<SrcStrm> {
     start_tasks_stream_complete: CancellationToken,
     complete_notify: Arc<Notify>,
     grace_period: Option<Duration>,
+    /// How long lang has to act on a cancel before the activity is hard cancelled
+    cancel_grace_period: Option<Duration>,
     cancels_tx: UnboundedSender<PendingActivityCancel>,
+    result_memo: Option<Arc<ActivityResultMemo>>,
     /// The extra time we'll wait for local timeouts before firing them, to avoid racing with server
     local_timeout_buffer: Duration,
     /// Token which is cancelled once shutdown is beginning
@@ -487,7 +743,16 @@ where
                         if let Some(mut details) =
                             self.outstanding_tasks.get_mut(&next_pc.task_token)
                         {
-                            if details.issued_cancel_to_lang.is_some() {
+                            if next_pc.hard {
+                                Some(Ok(ActivityTask {
+                                    task_token: next_pc.task_token.0,
+                                    variant: Some(activity_task::Variant::Cancel(Cancel {
+                                        reason: next_pc.reason as i32,
+                                        details: Some(next_pc.details),
+                                        hard_cancel: true,
+                                    })),
+                                }))
+                            } else if details.issued_cancel_to_lang.is_some() {
                                 // Don't double-issue cancellations
                                 None
                             } else {
@@ -497,9 +762,23 @@ where
                                 {
                                     details.known_not_found = true;
                                 }
+                                if let Some(gp) = self.cancel_grace_period {
+                                    let cancels_tx = cancels_tx.clone();
+                                    let hard_cancel = PendingActivityCancel {
+                                        task_token: next_pc.task_token.clone(),
+                                        hard: true,
+                                        details: next_pc.details.clone(),
+                                        ..next_pc
+                                    };
+                                    details.hard_cancel_task = Some(tokio::spawn(async move {
+                                        tokio::time::sleep(gp).await;
+                                        let _ = cancels_tx.send(hard_cancel);
+                                    }));
+                                }
                                 Some(Ok(ActivityTask::cancel_from_ids(
                                     next_pc.task_token.0,
                                     next_pc.reason,
+                                    next_pc.details,
                                 )))
                             }
                         } else {
@@ -536,6 +815,10 @@ where
                             let mut outstanding_info = outstanding_entry.insert(
                                 RemoteInFlightActInfo::new(&task.resp, task.permit.into_used()),
                             );
+                            outstanding_info.memo_key = self
+                                .result_memo
+                                .as_ref()
+                                .and_then(|memo| memo.key_for(&task.resp));
                             // If we have already waited the grace period and issued cancels,
                             // this will have been set true, indicating anything that happened
                             // to be buffered/in-flight/etc should get an immediate cancel. This
@@ -553,10 +836,12 @@ where
                                 // possibility of clock skew messing things up, and it's relative
                                 // unlikeliness compared to the other timeouts.
                                 let local_timeout_buffer = self.local_timeout_buffer;
-                                static HEARTBEAT_TYPE: &str = "heartbeat";
                                 let timeout_at = [
-                                    (HEARTBEAT_TYPE, task.resp.heartbeat_timeout.clone()),
-                                    ("start_to_close", task.resp.start_to_close_timeout.clone()),
+                                    (TimeoutType::Heartbeat, task.resp.heartbeat_timeout.clone()),
+                                    (
+                                        TimeoutType::StartToClose,
+                                        task.resp.start_to_close_timeout.clone(),
+                                    ),
                                 ]
                                 .into_iter()
                                 .filter_map(|(k, d)| {
@@ -567,7 +852,7 @@ where
                                 if let Some((timeout_type, timeout_at)) = timeout_at {
                                     let sleep_time = timeout_at + local_timeout_buffer;
                                     let cancel_tx = cancels_tx.clone();
-                                    let resetter = if timeout_type == HEARTBEAT_TYPE {
+                                    let resetter = if timeout_type == TimeoutType::Heartbeat {
                                         Some(Arc::new(Notify::new()))
                                     } else {
                                         None
@@ -588,19 +873,38 @@ where
                                             debug!(
                                                 task_token=%tt,
                                                 "Timing out activity due to elapsed local \
-                                                 {timeout_type} timer"
+                                                 {timeout_type:?} timer"
                                             );
                                             let _ = cancel_tx.send(PendingActivityCancel {
                                                 task_token: tt,
                                                 reason: ActivityCancelReason::TimedOut,
                                                 consider_not_found: true,
+                                                details: ActivityCancellationDetails {
+                                                    timeout_type: timeout_type as i32,
+                                                    ..Default::default()
+                                                },
+                                                hard: false,
                                             });
                                         }));
                                     outstanding_info.timeout_resetter = resetter;
                                 }
                             }
 
-                            ActivityTask::start_from_poll_resp(task.resp)
+                            let next_retry_delay =
+                                task.resp.retry_policy.as_ref().map(|rp| {
+                                    rp.should_retry(task.resp.attempt.max(1) as usize, None)
+                                });
+                            let mut act_task = ActivityTask::start_from_poll_resp(task.resp);
+                            if let (
+                                Some(activity_task::Variant::Start(start)),
+                                Some(next_retry_delay),
+                            ) = (act_task.variant.as_mut(), next_retry_delay)
+                            {
+                                start.next_retry_delay =
+                                    next_retry_delay.and_then(|d| d.try_into().ok());
+                                start.is_last_attempt = next_retry_delay.is_none();
+                            }
+                            act_task
                         }))
                     }
                 };
@@ -643,16 +947,29 @@ where
 pub(crate) struct ActivitiesFromWFTsHandle {
     sem: Arc<ClosableMeteredSemaphore>,
     tx: UnboundedSender<TrackedPermittedTqResp>,
+    poll_gate: PollGate,
 }
 
 impl ActivitiesFromWFTsHandle {
     /// Returns a handle that can be used to reserve an activity slot. EX: When requesting eager
     /// dispatch of an activity to this worker upon workflow task completion
     pub(crate) fn reserve_slot(&self) -> Option<TrackedOwnedMeteredSemPermit> {
+        if !self.poll_gate.allows_polling() {
+            return None;
+        }
         // TODO: check if rate limit is not exceeded and count this reservation towards the rate limit
         self.sem.try_acquire_owned().ok()
     }
 
+    /// Like [Self::reserve_slot], but may preempt a slot held by an idle poller if none are free.
+    /// Used for high priority activities.
+    pub(crate) fn reserve_slot_preempting(&self) -> Option<TrackedOwnedMeteredSemPermit> {
+        if !self.poll_gate.allows_polling() {
+            return None;
+        }
+        self.sem.try_acquire_or_preempt_owned().ok()
+    }
+
     /// Queue new activity tasks for dispatch received from non-polling sources (ex: eager returns
     /// from WFT completion)
     pub(crate) fn add_tasks(&self, tasks: impl IntoIterator<Item = TrackedPermittedTqResp>) {
@@ -676,6 +993,23 @@ pub(crate) struct TrackedPermittedTqResp {
     pub resp: PollActivityTaskQueueResponse,
 }
 
+fn activity_paused_failure() -> Failure {
+    Failure {
+        message: "Activity was paused".to_string(),
+        source: "".to_string(),
+        stack_trace: "".to_string(),
+        encoded_attributes: None,
+        cause: None,
+        failure_info: Some(FailureInfo::ApplicationFailureInfo(
+            ApplicationFailureInfo {
+                r#type: "ActivityPause".to_string(),
+                non_retryable: false,
+                details: None,
+            },
+        )),
+    }
+}
+
 fn worker_shutdown_failure() -> Failure {
     Failure {
         message: "Worker is shutting down and this activity did not complete in time".to_string(),
@@ -697,9 +1031,15 @@ fn worker_shutdown_failure() -> Failure {
 mod tests {
     use super::*;
     use crate::{
-        pollers::new_activity_task_buffer, prost_dur, worker::client::mocks::mock_workflow_client,
+        pollers::{new_activity_task_buffer, PollGate},
+        prost_dur,
+        worker::{client::mocks::mock_workflow_client, ActivityRateLimiter},
+    };
+    use std::sync::atomic::AtomicUsize;
+    use temporal_sdk_core_protos::{
+        coresdk::{activity_result::ActivityExecutionResult, activity_task::Cancel},
+        temporal::api::common::v1::ActivityType,
     };
-    use temporal_sdk_core_protos::coresdk::activity_result::ActivityExecutionResult;
 
     #[tokio::test]
     async fn per_worker_ratelimit() {
@@ -741,9 +1081,11 @@ mod tests {
             5, // Lots of concurrent pollers, to ensure we don't poll to much when that's the case
             sem.clone(),
             None,
+            PollGate::default(),
             shutdown_token.clone(),
             None::<fn(usize)>,
             Some(2.0),
+            None,
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -753,6 +1095,10 @@ mod tests {
             Duration::from_secs(1),
             Duration::from_secs(1),
             None,
+            None,
+            None,
+            &Default::default(),
+            None,
             Duration::from_secs(5),
         );
         let start = Instant::now();
@@ -781,6 +1127,176 @@ mod tests {
         atm.shutdown().await;
     }
 
+    #[derive(Debug)]
+    struct PermitLimiter(tokio::sync::Semaphore);
+    #[async_trait::async_trait]
+    impl ActivityRateLimiter for PermitLimiter {
+        async fn acquire(&self) {
+            self.0.acquire().await.unwrap().forget();
+        }
+    }
+
+    #[tokio::test]
+    async fn external_ratelimit() {
+        let mut mock_client = mock_workflow_client();
+        mock_client
+            .expect_poll_activity_task()
+            .times(1)
+            .returning(move |_, _| {
+                Ok(PollActivityTaskQueueResponse {
+                    task_token: vec![1],
+                    activity_id: "act1".to_string(),
+                    ..Default::default()
+                })
+            });
+        mock_client
+            .expect_poll_activity_task()
+            .times(1)
+            .returning(move |_, _| {
+                Ok(PollActivityTaskQueueResponse {
+                    task_token: vec![2],
+                    activity_id: "act2".to_string(),
+                    ..Default::default()
+                })
+            });
+        mock_client
+            .expect_complete_activity_task()
+            .times(2)
+            .returning(|_, _| Ok(Default::default()));
+        let mock_client = Arc::new(mock_client);
+        let sem = Arc::new(MeteredSemaphore::new(
+            10,
+            MetricsContext::no_op(),
+            MetricsContext::available_task_slots,
+        ));
+        let shutdown_token = CancellationToken::new();
+        let limiter = Arc::new(PermitLimiter(tokio::sync::Semaphore::new(1)));
+        let ap = new_activity_task_buffer(
+            mock_client.clone(),
+            "tq".to_string(),
+            5,
+            sem.clone(),
+            None,
+            PollGate::default(),
+            shutdown_token.clone(),
+            None::<fn(usize)>,
+            None,
+            Some(limiter.clone()),
+        );
+        let atm = WorkerActivityTasks::new(
+            sem.clone(),
+            Box::new(ap),
+            mock_client.clone(),
+            MetricsContext::no_op(),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            None,
+            None,
+            None,
+            &Default::default(),
+            None,
+            Duration::from_secs(5),
+        );
+        let t1 = atm.poll().await.unwrap();
+        // No more permits, so we shouldn't be able to get another task
+        assert!(tokio::time::timeout(Duration::from_millis(100), atm.poll())
+            .await
+            .is_err());
+        limiter.0.add_permits(1);
+        let t2 = atm.poll().await.unwrap();
+        shutdown_token.cancel();
+        // Need to complete the tasks so shutdown will resolve
+        atm.complete(
+            TaskToken(t1.task_token),
+            ActivityExecutionResult::ok(vec![1].into()).status.unwrap(),
+            mock_client.as_ref(),
+        )
+        .await;
+        atm.complete(
+            TaskToken(t2.task_token),
+            ActivityExecutionResult::ok(vec![1].into()).status.unwrap(),
+            mock_client.as_ref(),
+        )
+        .await;
+        atm.initiate_shutdown();
+        assert_matches!(atm.poll().await.unwrap_err(), PollActivityError::ShutDown);
+        atm.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn type_ratelimited_task_released_on_shutdown() {
+        let mut mock_client = mock_workflow_client();
+        let task_num = AtomicUsize::new(0);
+        mock_client
+            .expect_poll_activity_task()
+            .returning(move |_, _| {
+                let num = task_num.fetch_add(1, Ordering::Relaxed);
+                Ok(PollActivityTaskQueueResponse {
+                    task_token: vec![num as u8],
+                    activity_id: format!("act{num}"),
+                    activity_type: Some(ActivityType {
+                        name: "slow".to_string(),
+                    }),
+                    ..Default::default()
+                })
+            });
+        mock_client
+            .expect_complete_activity_task()
+            .times(1)
+            .returning(|_, _| Ok(Default::default()));
+        let mock_client = Arc::new(mock_client);
+        let sem = Arc::new(MeteredSemaphore::new(
+            2,
+            MetricsContext::no_op(),
+            MetricsContext::available_task_slots,
+        ));
+        let shutdown_token = CancellationToken::new();
+        let ap = new_activity_task_buffer(
+            mock_client.clone(),
+            "tq".to_string(),
+            1,
+            sem.clone(),
+            None,
+            PollGate::default(),
+            shutdown_token.clone(),
+            None::<fn(usize)>,
+            None,
+            None,
+        );
+        let atm = WorkerActivityTasks::new(
+            sem.clone(),
+            Box::new(ap),
+            mock_client.clone(),
+            MetricsContext::no_op(),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            None,
+            None,
+            None,
+            &HashMap::from([("slow".to_string(), 0.01)]),
+            None,
+            Duration::from_secs(5),
+        );
+        let t1 = atm.poll().await.unwrap();
+        // The next task is held back by the rate limit, keeping its slot
+        assert!(tokio::time::timeout(Duration::from_millis(100), atm.poll())
+            .await
+            .is_err());
+        assert_eq!(sem.available_permits(), 0);
+
+        shutdown_token.cancel();
+        atm.initiate_shutdown();
+        atm.complete(
+            TaskToken(t1.task_token),
+            ActivityExecutionResult::ok(vec![1].into()).status.unwrap(),
+            mock_client.as_ref(),
+        )
+        .await;
+        assert_matches!(atm.poll().await.unwrap_err(), PollActivityError::ShutDown);
+        atm.shutdown().await;
+        assert_eq!(sem.available_permits(), 2);
+    }
+
     #[tokio::test]
     async fn local_timeouts() {
         let mut mock_client = mock_workflow_client();
@@ -834,9 +1350,11 @@ mod tests {
             1,
             sem.clone(),
             None,
+            PollGate::default(),
             shutdown_token.clone(),
             None::<fn(usize)>,
             None,
+            None,
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -846,6 +1364,10 @@ mod tests {
             Duration::from_secs(1),
             Duration::from_secs(1),
             None,
+            None,
+            None,
+            &Default::default(),
+            None,
             Duration::from_millis(100), // Short buffer for unit test
         );
 
@@ -909,9 +1431,11 @@ mod tests {
             1,
             sem.clone(),
             None,
+            PollGate::default(),
             shutdown_token.clone(),
             None::<fn(usize)>,
             None,
+            None,
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -921,6 +1445,10 @@ mod tests {
             Duration::from_secs(1),
             Duration::from_secs(1),
             None,
+            None,
+            None,
+            &Default::default(),
+            None,
             Duration::from_millis(0), // No buffer in this test
         );
 
@@ -933,6 +1461,7 @@ mod tests {
                 atm.record_heartbeat(ActivityHeartbeat {
                     task_token: t.task_token.clone(),
                     details: vec![],
+                    throttle_interval: None,
                 })
                 .unwrap();
             }
@@ -942,6 +1471,11 @@ mod tests {
             // We should now time out since we're failing to heartbeat again
             let should_timeout = atm.poll().await.unwrap();
             assert!(should_timeout.is_timeout());
+            assert_matches!(
+                should_timeout.variant,
+                Some(activity_task::Variant::Cancel(Cancel { details: Some(d), .. }))
+                    if d.timeout_type == TimeoutType::Heartbeat as i32
+            );
             // Verify at least the two heartbeats elapsed before we got timed out
             assert!(start.elapsed() > Duration::from_millis(120));
         };
@@ -73,6 +73,9 @@ pub enum ActivityHeartbeatError {
     /// to heartbeat.
     #[error("Unable to parse activity heartbeat timeout.")]
     InvalidHeartbeatTimeout,
+    /// Lang overrode the heartbeat throttle interval with an unparseable (ex: negative) duration
+    #[error("Unable to parse activity heartbeat throttle interval.")]
+    InvalidThrottleInterval,
 }
 
 /// Manages activity heartbeating for a worker. Allows sending new heartbeats or requesting and
@@ -140,12 +143,24 @@ impl ActivityHeartbeatManager {
                                     .record_activity_heartbeat(tt.clone(), details.into_payloads())
                                     .await
                                 {
-                                    Ok(RecordActivityTaskHeartbeatResponse { cancel_requested }) => {
-                                        if cancel_requested {
+                                    Ok(RecordActivityTaskHeartbeatResponse {
+                                        cancel_requested,
+                                        activity_paused,
+                                    }) => {
+                                        // A cancel takes precedence, since it ends the activity
+                                        // for good while a pause only holds up its retries
+                                        let reason = if cancel_requested {
+                                            Some(ActivityCancelReason::Cancelled)
+                                        } else if activity_paused {
+                                            Some(ActivityCancelReason::Paused)
+                                        } else {
+                                            None
+                                        };
+                                        if let Some(reason) = reason {
                                             cancels_tx
-                                                .send(PendingActivityCancel::new(
+                                                .send(PendingActivityCancel::from_heartbeat(
                                                     tt.clone(),
-                                                    ActivityCancelReason::Cancelled,
+                                                    reason,
                                                 ))
                                                 .expect(
                                                     "Receive half of heartbeat cancels not blocked",
@@ -159,7 +174,7 @@ impl ActivityHeartbeatManager {
                                         debug!(task_token = %tt,
                                            "Activity not found when recording heartbeat");
                                         cancels_tx
-                                            .send(PendingActivityCancel::new(
+                                            .send(PendingActivityCancel::from_heartbeat(
                                                 tt.clone(),
                                                 ActivityCancelReason::NotFound,
                                             ))
@@ -314,6 +329,7 @@ impl HeartbeatStreamState {
                 let state = o.get_mut();
                 state.last_recorded_details = Some(hb.details);
                 state.timeout_resetter = hb.timeout_resetter;
+                state.throttle_interval = hb.throttle_interval;
                 None
             }
         }
@@ -538,6 +554,7 @@ mod test {
                     metadata: Default::default(),
                     data: vec![payload_data],
                 }],
+                throttle_interval: None,
             },
             // Mimic the same delay we would apply in activity task manager
             throttle_interval,
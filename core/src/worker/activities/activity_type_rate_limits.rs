@@ -0,0 +1,92 @@
+use crate::telemetry::metrics::{activity_type, MetricsContext};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Token buckets limiting the rate at which activities of particular types are dispatched to lang
+pub(super) struct ActivityTypeRateLimits {
+    limiters: HashMap<String, DefaultDirectRateLimiter>,
+    metrics: MetricsContext,
+}
+
+impl ActivityTypeRateLimits {
+    /// Returns None if there are no limits to enforce
+    pub(super) fn new(limits: &HashMap<String, f64>, metrics: MetricsContext) -> Option<Self> {
+        let limiters = limits
+            .iter()
+            .filter_map(|(act_type, per_sec)| {
+                // Rates are validated, so the period is representable
+                Duration::try_from_secs_f64(per_sec.recip())
+                    .ok()
+                    .and_then(Quota::with_period)
+                    .map(|q| (act_type.clone(), RateLimiter::direct(q)))
+            })
+            .collect::<HashMap<_, _>>();
+        if limiters.is_empty() {
+            return None;
+        }
+        Some(Self { limiters, metrics })
+    }
+
+    /// Resolves once an activity of the provided type may be dispatched
+    pub(super) async fn until_ready(&self, act_type: &str) {
+        let Some(limiter) = self.limiters.get(act_type) else {
+            return;
+        };
+        if limiter.check().is_ok() {
+            return;
+        }
+        let started_waiting = Instant::now();
+        limiter.until_ready().await;
+        let metrics = self
+            .metrics
+            .with_new_attrs([activity_type(act_type.to_string())]);
+        metrics.act_dispatch_throttled(started_waiting.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_help::test_worker_cfg;
+
+    #[tokio::test]
+    async fn only_limits_configured_types() {
+        let limits = ActivityTypeRateLimits::new(
+            &HashMap::from([("slow".to_string(), 10.0)]),
+            MetricsContext::no_op(),
+        )
+        .unwrap();
+        let start = Instant::now();
+        for _ in 0..10 {
+            limits.until_ready("fast").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+        for _ in 0..3 {
+            limits.until_ready("slow").await;
+        }
+        // The first is immediately permitted, and the following two each wait 100ms
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+
+    #[test]
+    fn no_limits_is_none() {
+        assert!(ActivityTypeRateLimits::new(&HashMap::new(), MetricsContext::no_op()).is_none());
+    }
+
+    #[test]
+    fn unrepresentable_rates_rejected() {
+        for rate in [1e-20, 1e300] {
+            let res = test_worker_cfg()
+                .activity_type_rate_limits(HashMap::from([("act".to_string(), rate)]))
+                .build();
+            assert!(res.is_err(), "rate {rate} should be rejected");
+        }
+        test_worker_cfg()
+            .activity_type_rate_limits(HashMap::from([("act".to_string(), 1e-6)]))
+            .build()
+            .unwrap();
+    }
+}
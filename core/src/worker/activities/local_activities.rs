@@ -2,34 +2,40 @@ use crate::{
     abstractions::{dbg_panic, MeteredSemaphore, OwnedMeteredSemPermit, UsedMeteredSemPermit},
     protosext::ValidScheduleLA,
     retry_logic::RetryPolicyExt,
+    telemetry::metrics::{activity_type, workflow_type},
     worker::workflow::HeartbeatTimeoutMsg,
     MetricsContext, TaskToken,
 };
 use futures::{stream::BoxStream, Stream};
 use futures_util::{future, future::AbortRegistration, stream, StreamExt};
 use parking_lot::{Mutex, MutexGuard};
+use prost::Message;
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::{Debug, Formatter},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant, SystemTime},
 };
 use temporal_sdk_core_protos::{
     coresdk::{
         activity_result::{Cancellation, Failure as ActFail, Success},
-        activity_task::{activity_task, ActivityCancelReason, ActivityTask, Cancel, Start},
+        activity_task::{
+            activity_task, ActivityCancelReason, ActivityCancellationDetails, ActivityTask, Cancel,
+            Start,
+        },
     },
     temporal::api::{
-        common::v1::WorkflowExecution,
+        common::v1::{Payload, Payloads, WorkflowExecution},
         enums::v1::TimeoutType,
-        failure::v1::{failure, Failure as APIFailure, TimeoutFailureInfo},
+        failure::v1::{failure, ApplicationFailureInfo, Failure as APIFailure, TimeoutFailureInfo},
     },
 };
 use tokio::{
     sync::{
         mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
-        Notify,
+        Notify, OwnedSemaphorePermit, Semaphore,
     },
     task::JoinHandle,
     time::sleep,
@@ -51,9 +57,16 @@ pub(crate) struct LocalInFlightActInfo {
     pub la_info: NewLocalAct,
     pub dispatch_time: Instant,
     pub attempt: u32,
+    /// The most recent progress lang reported for this attempt
+    last_progress: Vec<Payload>,
     _permit: UsedMeteredSemPermit,
+    _type_permit: Option<OwnedSemaphorePermit>,
 }
 
+/// Failure type used when a local activity's result exceeds
+/// [temporal_sdk_core_api::worker::PayloadSizeLimits::local_activity_result_error_bytes]
+const LA_RESULT_TOO_LARGE_FAILURE_TYPE: &str = "LocalActivityResultTooLarge";
+
 #[derive(Debug, Clone)]
 pub(crate) enum LocalActivityExecutionResult {
     Completed(Success),
@@ -80,6 +93,48 @@ impl LocalActivityExecutionResult {
         })
     }
 
+    /// Replaces a successful result whose payload is larger than `limit` bytes with a
+    /// non-retryable failure explaining why, since the result would otherwise be recorded in a
+    /// marker in history.
+    pub(crate) fn enforce_result_size(self, activity_type: &str, limit: usize) -> Self {
+        let size = match &self {
+            Self::Completed(Success { result: Some(p) }) => p.encoded_len(),
+            _ => return self,
+        };
+        if size <= limit {
+            return self;
+        }
+        warn!(
+            activity_type,
+            size, limit, "Local activity result is too large"
+        );
+        Self::Failed(ActFail {
+            failure: Some(APIFailure {
+                message: format!(
+                    "Local activity `{activity_type}` returned a result of {size} bytes, exceeding \
+                     the limit of {limit} bytes. Local activity results are recorded in workflow \
+                     history, so large results should instead be stored elsewhere and returned \
+                     by reference, or the activity should be run as a regular activity."
+                ),
+                failure_info: Some(failure::FailureInfo::ApplicationFailureInfo(
+                    ApplicationFailureInfo {
+                        r#type: LA_RESULT_TOO_LARGE_FAILURE_TYPE.to_string(),
+                        non_retryable: true,
+                        ..Default::default()
+                    },
+                )),
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn failure(&self) -> Option<&APIFailure> {
+        match self {
+            Self::Failed(f) | Self::TimedOut(f) => f.failure.as_ref(),
+            _ => None,
+        }
+    }
+
     fn get_timeout_type(&self) -> Option<TimeoutType> {
         match self {
             Self::TimedOut(ActFail {
@@ -107,6 +162,9 @@ pub(crate) struct LocalActivityResolution {
     pub attempt: u32,
     pub backoff: Option<prost_types::Duration>,
     pub original_schedule_time: Option<SystemTime>,
+    /// The most recent progress reported by the attempt which produced this resolution, recorded
+    /// in the marker
+    pub last_progress: Vec<Payload>,
 }
 
 #[derive(Clone)]
@@ -166,6 +224,11 @@ pub(crate) struct LocalActivityManager {
     /// Set once workflows have finished shutting down, and thus we know we will no longer receive
     /// any requests to spawn new LAs
     workflows_have_shut_down: CancellationToken,
+    metrics: MetricsContext,
+    /// Successful results larger than this many bytes are turned into failures
+    max_result_bytes: Option<usize>,
+    /// Attempts which go this long without reporting progress are timed out
+    stall_timeout: Option<Duration>,
 
     rcvs: tokio::sync::Mutex<RcvChans>,
     shutdown_complete_tok: CancellationToken,
@@ -201,25 +264,35 @@ impl LAMData {
 }
 
 impl LocalActivityManager {
+    #[allow(clippy::too_many_arguments)] // Not much worth combining here
     pub(crate) fn new(
         max_concurrent: usize,
         namespace: String,
         heartbeat_timeout_tx: UnboundedSender<HeartbeatTimeoutMsg>,
         metrics_context: MetricsContext,
+        shared_slots: Option<Arc<Semaphore>>,
+        per_type_limits: &HashMap<String, usize>,
+        max_result_bytes: Option<usize>,
+        stall_timeout: Option<Duration>,
     ) -> Self {
         let (act_req_tx, act_req_rx) = unbounded_channel();
         let (cancels_req_tx, cancels_req_rx) = unbounded_channel();
         let shutdown_complete_tok = CancellationToken::new();
         let semaphore = MeteredSemaphore::new(
             max_concurrent,
-            metrics_context,
+            metrics_context.clone(),
             MetricsContext::available_task_slots,
-        );
+        )
+        .with_shared(shared_slots);
         Self {
             namespace,
             rcvs: tokio::sync::Mutex::new(RcvChans::new(
                 act_req_rx,
                 semaphore,
+                per_type_limits
+                    .iter()
+                    .map(|(t, l)| (t.clone(), Arc::new(Semaphore::new(*l))))
+                    .collect(),
                 cancels_req_rx,
                 shutdown_complete_tok.clone(),
             )),
@@ -234,6 +307,9 @@ impl LocalActivityManager {
                 next_tt_num: 0,
             }),
             workflows_have_shut_down: Default::default(),
+            metrics: metrics_context,
+            max_result_bytes,
+            stall_timeout,
         }
     }
 
@@ -245,6 +321,10 @@ impl LocalActivityManager {
             "fake_ns".to_string(),
             hb_tx,
             MetricsContext::no_op(),
+            None,
+            &Default::default(),
+            None,
+            None,
         )
     }
 
@@ -305,7 +385,11 @@ impl LocalActivityManager {
                             });
 
                             // Set up timeouts for the new activity
-                            match TimeoutBag::new(&act, self.cancels_req_tx.clone()) {
+                            match TimeoutBag::new(
+                                &act,
+                                self.stall_timeout,
+                                self.cancels_req_tx.clone(),
+                            ) {
                                 Ok(tb) => {
                                     lai.timeout_bag = Some(tb);
 
@@ -336,7 +420,7 @@ impl LocalActivityManager {
                     debug!(id=?id, "Cancelling local activity");
                     let mut dlock = self.dat.lock();
                     if let Some(lai) = dlock.la_info.get_mut(&id) {
-                        if let Some(immediate_res) = self.cancel_one_la(id.seq_num, lai) {
+                        if let Some(immediate_res) = self.cancel_one_la(id.seq_num, lai, false) {
                             immediate_resolutions.push(immediate_res);
                         }
                     }
@@ -351,7 +435,7 @@ impl LocalActivityManager {
                         .iter_mut()
                         .filter(|(id, _)| id.run_id == run_id);
                     for (laid, lainf) in las_for_run {
-                        if let Some(immediate_res) = self.cancel_one_la(laid.seq_num, lainf) {
+                        if let Some(immediate_res) = self.cancel_one_la(laid.seq_num, lainf, true) {
                             immediate_resolutions.push(immediate_res);
                         }
                     }
@@ -375,7 +459,7 @@ impl LocalActivityManager {
     /// Returns the next pending local-activity related action, or None if shutdown has initiated
     /// and there are no more remaining actions to take.
     pub(crate) async fn next_pending(&self) -> Option<NextPendingLAAction> {
-        let (new_or_retry, permit) = match self.rcvs.lock().await.next().await? {
+        let (new_or_retry, permit, type_permit) = match self.rcvs.lock().await.next().await? {
             NewOrCancel::Cancel(c) => {
                 return match c {
                     CancelOrTimeout::Cancel(c) => {
@@ -413,17 +497,24 @@ impl LocalActivityManager {
                     }
                 };
             }
-            NewOrCancel::New(n, perm) => (n, perm),
+            NewOrCancel::New(n, perm, type_perm) => (n, perm, type_perm),
         };
 
         // It is important that there are no await points after receiving from the channel, as
         // it would mean dropping this future would cause us to drop the activity request.
-        let (new_la, attempt) = match new_or_retry {
-            NewOrRetry::New(n) => {
+        let is_new = matches!(new_or_retry, NewOrRetry::New(_));
+        let (new_la, attempt, previous_attempt_failure) = match new_or_retry {
+            NewOrRetry::New(mut n) => {
                 let explicit_attempt_num_or_1 = n.schedule_cmd.attempt.max(1);
-                (n, explicit_attempt_num_or_1)
+                // Set if lang is resuming this activity after a timer backoff
+                let previous_failure = n.schedule_cmd.previous_attempt_failure.take();
+                (n, explicit_attempt_num_or_1, previous_failure)
             }
-            NewOrRetry::Retry { in_flight, attempt } => (in_flight, attempt),
+            NewOrRetry::Retry {
+                in_flight,
+                attempt,
+                previous_failure,
+            } => (in_flight, attempt, previous_failure),
         };
         let la_info_for_in_flight_map = new_la.clone();
         let id = ExecutingLAId {
@@ -441,10 +532,15 @@ impl LocalActivityManager {
             .get_mut(&id)
             .map(|lai| lai.backing_off_task.take());
 
+        let la_metrics = self.metrics.with_new_attrs([
+            activity_type(sa.activity_type.clone()),
+            workflow_type(new_la.workflow_type.clone()),
+        ]);
         // If this task sat in the queue for too long, return a timeout for it instead
+        let sat_for = new_la.schedule_time.elapsed().unwrap_or_default();
         if let Some(s2s) = sa.schedule_to_start_timeout.as_ref() {
-            let sat_for = new_la.schedule_time.elapsed().unwrap_or_default();
             if sat_for > *s2s {
+                la_metrics.la_execution_failed();
                 return Some(NextPendingLAAction::Autocomplete(
                     LACompleteAction::Report {
                         run_id: new_la.workflow_exec_info.run_id,
@@ -457,6 +553,7 @@ impl LocalActivityManager {
                             attempt,
                             backoff: None,
                             original_schedule_time: orig_sched_time,
+                            last_progress: vec![],
                         },
                         task: None,
                     },
@@ -464,6 +561,13 @@ impl LocalActivityManager {
             }
         }
 
+        la_metrics.la_task_received();
+        // Locally retried attempts reuse the original schedule time, so only the first attempt has
+        // a meaningful schedule-to-start latency.
+        if is_new {
+            la_metrics.la_sched_to_start_latency(sat_for);
+        }
+
         let la_info = dat.la_info.get_mut(&id).expect("Activity must exist");
         let tt = la_info.task_token.clone();
         if let Some(to) = la_info.timeout_bag.as_mut() {
@@ -475,11 +579,14 @@ impl LocalActivityManager {
                 la_info: la_info_for_in_flight_map,
                 dispatch_time: Instant::now(),
                 attempt,
+                last_progress: vec![],
                 _permit: permit.into_used(),
+                _type_permit: type_permit,
             },
         );
 
         let (schedule_to_close, start_to_close) = sa.close_timeouts.into_sched_and_start();
+        let next_retry_delay = sa.retry_policy.should_retry(attempt as usize, None);
         Some(NextPendingLAAction::Dispatch(ActivityTask {
             task_token: tt.0,
             variant: Some(activity_task::Variant::Start(Start {
@@ -491,7 +598,7 @@ impl LocalActivityManager {
                 header_fields: sa.headers,
                 input: sa.arguments,
                 heartbeat_details: vec![],
-                scheduled_time: Some(new_la.schedule_time.into()),
+                scheduled_time: Some(orig_sched_time.unwrap_or(new_la.schedule_time).into()),
                 current_attempt_scheduled_time: Some(new_la.schedule_time.into()),
                 started_time: Some(SystemTime::now().into()),
                 attempt,
@@ -505,8 +612,12 @@ impl LocalActivityManager {
                     .try_into()
                     .ok(),
                 heartbeat_timeout: None,
+                next_retry_delay: next_retry_delay.and_then(|d| d.try_into().ok()),
+                is_last_attempt: next_retry_delay.is_none(),
                 retry_policy: Some(sa.retry_policy),
                 is_local: true,
+                priority: None,
+                previous_attempt_failure,
             })),
         }))
     }
@@ -553,15 +664,44 @@ impl LocalActivityManager {
                 };
             }
 
+            let status = match self.max_result_bytes {
+                Some(limit) => {
+                    status.enforce_result_size(&info.la_info.schedule_cmd.activity_type, limit)
+                }
+                None => status,
+            };
+            let la_metrics = self.metrics.with_new_attrs([
+                activity_type(info.la_info.schedule_cmd.activity_type.clone()),
+                workflow_type(info.la_info.workflow_type.clone()),
+            ]);
+            la_metrics.la_execution_latency(info.dispatch_time.elapsed());
+            match &status {
+                LocalActivityExecutionResult::Completed(_) => {
+                    let sched_time = info
+                        .la_info
+                        .schedule_cmd
+                        .original_schedule_time
+                        .unwrap_or(info.la_info.schedule_time);
+                    la_metrics.la_e2e_latency(sched_time.elapsed().unwrap_or_default());
+                }
+                LocalActivityExecutionResult::Failed(_)
+                | LocalActivityExecutionResult::TimedOut(_) => la_metrics.la_execution_failed(),
+                _ => {}
+            }
+
             let mut is_timeout = false;
             let outcome = match &status {
                 LocalActivityExecutionResult::Failed(fail) => Outcome::FailurePath {
                     backoff: calc_backoff!(fail),
                 },
                 LocalActivityExecutionResult::TimedOut(fail)
-                    if matches!(status.get_timeout_type(), Some(TimeoutType::StartToClose)) =>
+                    if matches!(
+                        status.get_timeout_type(),
+                        Some(TimeoutType::StartToClose | TimeoutType::Heartbeat)
+                    ) =>
                 {
-                    // Start to close timeouts are retryable, other timeout types aren't.
+                    // Start to close and stall (heartbeat) timeouts are retryable, other timeout
+                    // types aren't.
                     is_timeout = true;
                     Outcome::FailurePath {
                         backoff: calc_backoff!(fail),
@@ -575,6 +715,7 @@ impl LocalActivityManager {
                 | LocalActivityExecutionResult::Cancelled { .. } => Outcome::JustReport,
             };
 
+            let timeout_type = status.get_timeout_type();
             let mut resolution = LocalActivityResolution {
                 seq: info.la_info.schedule_cmd.seq,
                 result: status,
@@ -582,6 +723,7 @@ impl LocalActivityManager {
                 attempt: info.attempt,
                 backoff: None,
                 original_schedule_time: info.la_info.schedule_cmd.original_schedule_time,
+                last_progress: info.last_progress,
             };
             // We want to generate a cancel task if the reason for failure was a timeout.
             let task = if is_timeout {
@@ -589,6 +731,11 @@ impl LocalActivityManager {
                     task_token: task_token.clone().0,
                     variant: Some(activity_task::Variant::Cancel(Cancel {
                         reason: ActivityCancelReason::TimedOut as i32,
+                        details: Some(ActivityCancellationDetails {
+                            timeout_type: timeout_type.unwrap_or(TimeoutType::Unspecified) as i32,
+                            ..Default::default()
+                        }),
+                        hard_cancel: false,
                     })),
                 })
             } else {
@@ -626,6 +773,7 @@ impl LocalActivityManager {
                         }
                         // Immediately create a new task token for the to-be-retried LA
                         let tt = dlock.gen_next_token();
+                        let previous_failure = resolution.result.failure().cloned();
                         // Send the retry request after waiting the backoff duration
                         let send_chan = self.act_req_tx.clone();
                         let jh = tokio::spawn(async move {
@@ -635,6 +783,7 @@ impl LocalActivityManager {
                                 .send(NewOrRetry::Retry {
                                     in_flight: info.la_info,
                                     attempt: info.attempt + 1,
+                                    previous_failure,
                                 })
                                 .expect("Receive half of LA request channel cannot be dropped");
                         });
@@ -684,6 +833,30 @@ impl LocalActivityManager {
         }
     }
 
+    /// Record progress reported by lang for an executing local activity. The progress is included
+    /// in the activity's marker, and restarts its stall timer if one is configured.
+    pub(crate) fn record_progress(&self, task_token: &TaskToken, details: Vec<Payload>) {
+        let mut dlock = self.dat.lock();
+        let Some(info) = dlock.outstanding_activity_tasks.get(task_token) else {
+            debug!(task_token = %task_token, "Progress reported for untracked local activity");
+            return;
+        };
+        let exec_id = ExecutingLAId {
+            run_id: info.la_info.workflow_exec_info.run_id.clone(),
+            seq_num: info.la_info.schedule_cmd.seq,
+        };
+        if let Some(tb) = dlock
+            .la_info
+            .get_mut(&exec_id)
+            .and_then(|lai| lai.timeout_bag.as_mut())
+        {
+            tb.restart_stall_timer(&details);
+        }
+        if let Some(info) = dlock.outstanding_activity_tasks.get_mut(task_token) {
+            info.last_progress = details;
+        }
+    }
+
     pub(crate) fn workflows_have_shutdown(&self) {
         self.workflows_have_shut_down.cancel();
         self.set_shutdown_complete_if_ready(&mut self.dat.lock());
@@ -724,6 +897,7 @@ impl LocalActivityManager {
         &self,
         seq: u32,
         lai: &mut LocalActivityInfo,
+        workflow_completed: bool,
     ) -> Option<LocalActivityResolution> {
         // First check if this ID is currently backing off, if so abort the backoff
         // task
@@ -736,6 +910,7 @@ impl LocalActivityManager {
                 attempt: 0,
                 backoff: None,
                 original_schedule_time: None,
+                last_progress: vec![],
             });
         }
 
@@ -744,6 +919,11 @@ impl LocalActivityManager {
                 task_token: lai.task_token.0.clone(),
                 variant: Some(activity_task::Variant::Cancel(Cancel {
                     reason: ActivityCancelReason::Cancelled as i32,
+                    details: Some(ActivityCancellationDetails {
+                        workflow_completed,
+                        ..Default::default()
+                    }),
+                    hard_cancel: false,
                 })),
             }))
             .expect("Receive half of LA cancel channel cannot be dropped");
@@ -771,14 +951,25 @@ pub(crate) enum LACompleteAction {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 enum NewOrRetry {
     New(NewLocalAct),
     Retry {
         in_flight: NewLocalAct,
         attempt: u32,
+        previous_failure: Option<APIFailure>,
     },
 }
 
+impl NewOrRetry {
+    fn activity_type(&self) -> &str {
+        match self {
+            NewOrRetry::New(n) => &n.schedule_cmd.activity_type,
+            NewOrRetry::Retry { in_flight, .. } => &in_flight.schedule_cmd.activity_type,
+        }
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 enum CancelOrTimeout {
@@ -791,7 +982,11 @@ enum CancelOrTimeout {
 
 #[allow(clippy::large_enum_variant)]
 enum NewOrCancel {
-    New(NewOrRetry, OwnedMeteredSemPermit),
+    New(
+        NewOrRetry,
+        OwnedMeteredSemPermit,
+        Option<OwnedSemaphorePermit>,
+    ),
     Cancel(CancelOrTimeout),
 }
 
@@ -805,11 +1000,37 @@ impl RcvChans {
     fn new(
         new_reqs: UnboundedReceiver<NewOrRetry>,
         new_sem: MeteredSemaphore,
+        per_type_sems: HashMap<String, Arc<Semaphore>>,
         cancels: UnboundedReceiver<CancelOrTimeout>,
         shutdown_completed: CancellationToken,
     ) -> Self {
         let cancel_stream = UnboundedReceiverStream::new(cancels).map(NewOrCancel::Cancel);
-        let new_stream = UnboundedReceiverStream::new(new_reqs)
+        let new_reqs = UnboundedReceiverStream::new(new_reqs);
+        let new_reqs = if per_type_sems.is_empty() {
+            new_reqs.map(|req| (req, None)).left_stream()
+        } else {
+            // Requests of limited types wait for a permit for their type before contending for
+            // the overall slots. They wait concurrently, so that a type which is at its limit does
+            // not hold up requests of other types queued behind it.
+            new_reqs
+                .map(move |req| {
+                    let type_sem = per_type_sems.get(req.activity_type()).cloned();
+                    async move {
+                        let type_permit = match type_sem {
+                            Some(sem) => Some(
+                                sem.acquire_owned()
+                                    .await
+                                    .expect("Local activity type semaphore is never closed"),
+                            ),
+                            None => None,
+                        };
+                        (req, type_permit)
+                    }
+                })
+                .buffer_unordered(usize::MAX)
+                .right_stream()
+        };
+        let new_stream = new_reqs
             // Get a permit for each new activity request
             .zip(stream::unfold(new_sem, |new_sem| async move {
                 let permit = new_sem
@@ -818,7 +1039,7 @@ impl RcvChans {
                     .expect("Local activity semaphore is never closed");
                 Some((permit, new_sem))
             }))
-            .map(|(req, permit)| NewOrCancel::New(req, permit));
+            .map(|((req, type_permit), permit)| NewOrCancel::New(req, permit, type_permit));
         Self {
             inner: tokio_stream::StreamExt::merge(cancel_stream, new_stream)
                 .take_until(async move { shutdown_completed.cancelled().await })
@@ -839,6 +1060,8 @@ struct TimeoutBag {
     sched_to_close_handle: JoinHandle<()>,
     start_to_close_dur_and_dat: Option<(Duration, CancelOrTimeout)>,
     start_to_close_handle: Option<JoinHandle<()>>,
+    stall_timeout_dur_and_dat: Option<(Duration, CancelOrTimeout)>,
+    stall_handle: Option<JoinHandle<()>>,
     cancel_chan: UnboundedSender<CancelOrTimeout>,
 }
 
@@ -849,6 +1072,7 @@ impl TimeoutBag {
     /// Returns error in the event the activity is *already* timed out
     fn new(
         new_la: &NewLocalAct,
+        stall_timeout: Option<Duration>,
         cancel_chan: UnboundedSender<CancelOrTimeout>,
     ) -> Result<TimeoutBag, LocalActivityResolution> {
         let (schedule_to_close, start_to_close) =
@@ -865,6 +1089,7 @@ impl TimeoutBag {
             attempt: new_la.schedule_cmd.attempt,
             backoff: None,
             original_schedule_time: new_la.schedule_cmd.original_schedule_time,
+            last_progress: vec![],
         };
         // Remove any time already elapsed since the scheduling time
         let schedule_to_close = schedule_to_close
@@ -879,6 +1104,7 @@ impl TimeoutBag {
             resolution,
         };
         let start_to_close_dur_and_dat = start_to_close.map(|d| (d, timeout_dat.clone()));
+        let stall_timeout_dur_and_dat = stall_timeout.map(|d| (d, timeout_dat.clone()));
         let fut_dat = schedule_to_close.map(|s2c| (s2c, timeout_dat));
 
         let cancel_chan_clone = cancel_chan.clone();
@@ -894,6 +1120,8 @@ impl TimeoutBag {
             sched_to_close_handle: scheduling,
             start_to_close_dur_and_dat,
             start_to_close_handle: None,
+            stall_timeout_dur_and_dat,
+            stall_handle: None,
             cancel_chan,
         })
     }
@@ -911,6 +1139,40 @@ impl TimeoutBag {
                     resolution.runtime = started_t.elapsed();
                 }
 
+                cchan.send(dat).expect("receive half not dropped");
+            }));
+        }
+        self.restart_stall_timer(&[]);
+    }
+
+    /// (Re)starts the stall timer if one is configured, which times out the activity if it does
+    /// not report progress again before it elapses. `last_progress` is attached to the timeout.
+    fn restart_stall_timer(&mut self, last_progress: &[Payload]) {
+        if let Some((stall_timeout, mut dat)) = self.stall_timeout_dur_and_dat.as_ref().cloned() {
+            if let Some(h) = self.stall_handle.take() {
+                h.abort();
+            }
+            let cchan = self.cancel_chan.clone();
+            let last_details = (!last_progress.is_empty()).then(|| Payloads {
+                payloads: last_progress.to_vec(),
+            });
+            self.stall_handle = Some(tokio::spawn(async move {
+                sleep(stall_timeout).await;
+                if let CancelOrTimeout::Timeout { resolution, .. } = &mut dat {
+                    resolution.result = LocalActivityExecutionResult::TimedOut(ActFail {
+                        failure: Some(APIFailure {
+                            message: "Local activity stopped reporting progress".to_string(),
+                            failure_info: Some(failure::FailureInfo::TimeoutFailureInfo(
+                                TimeoutFailureInfo {
+                                    timeout_type: TimeoutType::Heartbeat as i32,
+                                    last_heartbeat_details: last_details,
+                                },
+                            )),
+                            ..Default::default()
+                        }),
+                    });
+                }
+
                 cchan.send(dat).expect("receive half not dropped");
             }));
         }
@@ -923,6 +1185,9 @@ impl Drop for TimeoutBag {
         if let Some(x) = self.start_to_close_handle.as_ref() {
             x.abort()
         }
+        if let Some(x) = self.stall_handle.as_ref() {
+            x.abort()
+        }
     }
 }
 
@@ -932,7 +1197,7 @@ mod tests {
     use crate::{prost_dur, protosext::LACloseTimeouts};
     use futures_util::FutureExt;
     use temporal_sdk_core_protos::temporal::api::{
-        common::v1::RetryPolicy,
+        common::v1::{Payload, RetryPolicy},
         failure::v1::{failure::FailureInfo, ApplicationFailureInfo, Failure},
     };
     use tokio::task::yield_now;
@@ -997,6 +1262,54 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn per_type_max_concurrent_respected() {
+        let (hb_tx, _hb_rx) = unbounded_channel();
+        let lam = LocalActivityManager::new(
+            5,
+            "fake_ns".to_string(),
+            hb_tx,
+            MetricsContext::no_op(),
+            None,
+            &HashMap::from([("hot".to_string(), 1)]),
+            None,
+            None,
+        );
+        lam.enqueue(
+            [(1, "hot"), (2, "hot"), (3, "cold"), (4, "hot")].map(|(i, act_type)| {
+                NewLocalAct {
+                    schedule_cmd: ValidScheduleLA {
+                        seq: i,
+                        activity_id: i.to_string(),
+                        activity_type: act_type.to_string(),
+                        ..Default::default()
+                    },
+                    workflow_type: "".to_string(),
+                    workflow_exec_info: Default::default(),
+                    schedule_time: SystemTime::now(),
+                }
+                .into()
+            }),
+        );
+        let next_id = || async {
+            let next = lam.next_pending().await.unwrap().unwrap();
+            assert_matches!(
+                next.variant.unwrap(),
+                activity_task::Variant::Start(Start {activity_id, ..}) => activity_id
+            )
+        };
+        let first = lam.next_pending().await.unwrap().unwrap();
+        // The second hot activity must wait for the first, but the cold one need not
+        assert_eq!(next_id().await, "3");
+        assert!(next_id().now_or_never().is_none());
+        lam.complete(
+            &TaskToken(first.task_token),
+            LocalActivityExecutionResult::Completed(Default::default()),
+        );
+        assert_eq!(next_id().await, "2");
+        assert!(next_id().now_or_never().is_none());
+    }
+
     #[tokio::test]
     async fn no_work_doesnt_deadlock_with_complete() {
         let lam = LocalActivityManager::test(5);
@@ -1135,6 +1448,125 @@ mod tests {
         assert_matches!(res, LACompleteAction::Report { .. });
     }
 
+    #[tokio::test]
+    async fn oversized_results_fail_without_retry() {
+        let (hb_tx, _hb_rx) = unbounded_channel();
+        let lam = LocalActivityManager::new(
+            5,
+            "fake_ns".to_string(),
+            hb_tx,
+            MetricsContext::no_op(),
+            None,
+            &Default::default(),
+            Some(100),
+            None,
+        );
+        lam.enqueue((1..=2).map(|i| {
+            NewLocalAct {
+                schedule_cmd: ValidScheduleLA {
+                    seq: i,
+                    activity_id: i.to_string(),
+                    activity_type: "big".to_string(),
+                    attempt: 1,
+                    retry_policy: RetryPolicy {
+                        initial_interval: Some(prost_dur!(from_secs(1))),
+                        maximum_attempts: 10,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                workflow_type: "".to_string(),
+                workflow_exec_info: Default::default(),
+                schedule_time: SystemTime::now(),
+            }
+            .into()
+        }));
+        let payload = |bytes| Success {
+            result: Some(Payload {
+                metadata: Default::default(),
+                data: vec![0; bytes],
+            }),
+        };
+
+        let small_tt = TaskToken(lam.next_pending().await.unwrap().unwrap().task_token);
+        let res = lam.complete(
+            &small_tt,
+            LocalActivityExecutionResult::Completed(payload(10)),
+        );
+        assert_matches!(
+            res,
+            LACompleteAction::Report { resolution, .. }
+            if matches!(resolution.result, LocalActivityExecutionResult::Completed(_))
+        );
+
+        let big_tt = TaskToken(lam.next_pending().await.unwrap().unwrap().task_token);
+        let res = lam.complete(
+            &big_tt,
+            LocalActivityExecutionResult::Completed(payload(1000)),
+        );
+        let fail = assert_matches!(
+            res,
+            LACompleteAction::Report {
+                resolution: LocalActivityResolution {
+                    result: LocalActivityExecutionResult::Failed(ActFail { failure: Some(f) }),
+                    ..
+                },
+                ..
+            } => f
+        );
+        assert!(fail.message.contains("exceeding the limit of 100 bytes"));
+        let app_fail = fail.maybe_application_failure().unwrap();
+        assert_eq!(app_fail.r#type, LA_RESULT_TOO_LARGE_FAILURE_TYPE);
+        assert!(app_fail.non_retryable);
+    }
+
+    #[tokio::test]
+    async fn retry_state_exposed_in_start() {
+        let lam = LocalActivityManager::test(1);
+        lam.enqueue([NewLocalAct {
+            schedule_cmd: ValidScheduleLA {
+                seq: 1,
+                activity_id: "1".to_string(),
+                attempt: 1,
+                retry_policy: RetryPolicy {
+                    initial_interval: Some(prost_dur!(from_millis(10))),
+                    backoff_coefficient: 1.0,
+                    maximum_attempts: 2,
+                    ..Default::default()
+                },
+                local_retry_threshold: Duration::from_secs(5),
+                ..Default::default()
+            },
+            workflow_type: "".to_string(),
+            workflow_exec_info: Default::default(),
+            schedule_time: SystemTime::now(),
+        }
+        .into()]);
+
+        let next = lam.next_pending().await.unwrap().unwrap();
+        let start = assert_matches!(next.variant, Some(activity_task::Variant::Start(s)) => s);
+        assert_eq!(start.next_retry_delay, Some(prost_dur!(from_millis(10))));
+        assert!(!start.is_last_attempt);
+        assert!(start.previous_attempt_failure.is_none());
+        let res = lam.complete(
+            &TaskToken(next.task_token),
+            LocalActivityExecutionResult::Failed(ActFail {
+                failure: Some(Failure {
+                    message: "boom".to_string(),
+                    ..Default::default()
+                }),
+            }),
+        );
+        assert_matches!(res, LACompleteAction::WillBeRetried(_));
+
+        let next = lam.next_pending().await.unwrap().unwrap();
+        let start = assert_matches!(next.variant, Some(activity_task::Variant::Start(s)) => s);
+        assert_eq!(start.attempt, 2);
+        assert_eq!(start.next_retry_delay, None);
+        assert!(start.is_last_attempt);
+        assert_eq!(start.previous_attempt_failure.unwrap().message, "boom");
+    }
+
     #[tokio::test]
     async fn can_cancel_during_local_backoff() {
         let lam = LocalActivityManager::test(1);
@@ -1315,6 +1747,64 @@ mod tests {
         assert_eq!(lam.num_outstanding(), 0);
     }
 
+    #[tokio::test]
+    async fn stalled_la_times_out_with_last_progress() {
+        let (hb_tx, _hb_rx) = unbounded_channel();
+        let stall_timeout = Duration::from_millis(100);
+        let lam = LocalActivityManager::new(
+            5,
+            "fake_ns".to_string(),
+            hb_tx,
+            MetricsContext::no_op(),
+            None,
+            &Default::default(),
+            None,
+            Some(stall_timeout),
+        );
+        lam.enqueue([NewLocalAct {
+            schedule_cmd: ValidScheduleLA {
+                seq: 1,
+                activity_id: 1.to_string(),
+                retry_policy: RetryPolicy {
+                    maximum_attempts: 1,
+                    ..Default::default()
+                },
+                close_timeouts: LACloseTimeouts::StartOnly(Duration::from_secs(60)),
+                ..Default::default()
+            },
+            workflow_type: "".to_string(),
+            workflow_exec_info: WorkflowExecution {
+                workflow_id: "".to_string(),
+                run_id: "run_id".to_string(),
+            },
+            schedule_time: SystemTime::now(),
+        }
+        .into()]);
+        let next = lam.next_pending().await.unwrap().unwrap();
+        let tt = TaskToken(next.task_token);
+
+        // Reporting progress keeps the activity alive past the stall timeout
+        let progress = Payload {
+            data: b"halfway".to_vec(),
+            ..Default::default()
+        };
+        sleep(stall_timeout / 2).await;
+        lam.record_progress(&tt, vec![progress.clone()]);
+        sleep(stall_timeout / 2 + Duration::from_millis(10)).await;
+        assert_eq!(lam.num_outstanding(), 1);
+        assert!(lam.rcvs.lock().await.next().now_or_never().is_none());
+
+        let res = lam.next_pending().await.unwrap();
+        assert!(res.is_timeout(true));
+        assert_matches!(
+            res,
+            NextPendingLAAction::Autocomplete(LACompleteAction::Report { resolution, .. })
+            if resolution.last_progress == vec![progress.clone()] &&
+               resolution.result.get_timeout_type() == Some(TimeoutType::Heartbeat)
+        );
+        assert_eq!(lam.num_outstanding(), 0);
+    }
+
     #[tokio::test]
     async fn idempotency_enforced() {
         let lam = LocalActivityManager::test(10);
@@ -0,0 +1,138 @@
+use lru::LruCache;
+use parking_lot::Mutex;
+use prost::Message;
+use siphasher::sip::SipHasher13;
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+};
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::Payload, workflowservice::v1::PollActivityTaskQueueResponse,
+};
+
+/// Identifies an activity independently of which attempt is executing
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct MemoKey {
+    run_id: String,
+    activity_id: String,
+    input_hash: u64,
+}
+
+impl MemoKey {
+    fn new(resp: &PollActivityTaskQueueResponse) -> Self {
+        let mut hasher = SipHasher13::new();
+        resp.activity_type
+            .as_ref()
+            .map(|t| t.name.as_str())
+            .hash(&mut hasher);
+        for payload in resp.input.iter().flat_map(|i| i.payloads.iter()) {
+            payload.encode_to_vec().hash(&mut hasher);
+        }
+        Self {
+            run_id: resp
+                .workflow_execution
+                .as_ref()
+                .map(|we| we.run_id.clone())
+                .unwrap_or_default(),
+            activity_id: resp.activity_id.clone(),
+            input_hash: hasher.finish(),
+        }
+    }
+}
+
+/// Remembers successful results of opted-in activity types whose completion could not be
+/// delivered to the server, so that a retry of the same activity received by this worker can be
+/// answered without executing it again.
+pub(super) struct ActivityResultMemo {
+    activity_types: HashSet<String>,
+    results: Mutex<LruCache<MemoKey, Option<Payload>>>,
+}
+
+impl ActivityResultMemo {
+    /// Returns None if memoization is not enabled for any activity type
+    pub(super) fn new(activity_types: &HashSet<String>, max_results: usize) -> Option<Self> {
+        if activity_types.is_empty() {
+            return None;
+        }
+        let max_results = NonZeroUsize::new(max_results)?;
+        Some(Self {
+            activity_types: activity_types.clone(),
+            results: Mutex::new(LruCache::new(max_results)),
+        })
+    }
+
+    /// Returns the key results of this task should be memoized under, if its type is opted in
+    pub(super) fn key_for(&self, resp: &PollActivityTaskQueueResponse) -> Option<MemoKey> {
+        let act_type = resp.activity_type.as_ref()?;
+        self.activity_types
+            .contains(&act_type.name)
+            .then(|| MemoKey::new(resp))
+    }
+
+    pub(super) fn record(&self, key: MemoKey, result: Option<Payload>) {
+        self.results.lock().put(key, result);
+    }
+
+    /// Removes and returns the memoized result for the key, if there is one. Results are only
+    /// ever used once, since once reported successfully the server will not retry the activity.
+    pub(super) fn take(&self, key: &MemoKey) -> Option<Option<Payload>> {
+        self.results.lock().pop(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::temporal::api::common::v1::{
+        ActivityType, Payloads, WorkflowExecution,
+    };
+
+    fn resp(act_type: &str, attempt: i32, input: &[u8]) -> PollActivityTaskQueueResponse {
+        PollActivityTaskQueueResponse {
+            activity_id: "act".to_string(),
+            activity_type: Some(ActivityType {
+                name: act_type.to_string(),
+            }),
+            workflow_execution: Some(WorkflowExecution {
+                workflow_id: "wf".to_string(),
+                run_id: "run".to_string(),
+            }),
+            attempt,
+            input: Some(Payloads {
+                payloads: vec![input.to_vec().into()],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keys_ignore_attempt_but_not_input() {
+        let memo = ActivityResultMemo::new(&HashSet::from(["memo".to_string()]), 10).unwrap();
+        assert!(memo.key_for(&resp("other", 1, b"in")).is_none());
+        let key = memo.key_for(&resp("memo", 1, b"in")).unwrap();
+        assert_eq!(Some(&key), memo.key_for(&resp("memo", 2, b"in")).as_ref());
+        assert_ne!(
+            Some(&key),
+            memo.key_for(&resp("memo", 2, b"other")).as_ref()
+        );
+    }
+
+    #[test]
+    fn results_are_bounded_and_used_once() {
+        let memo = ActivityResultMemo::new(&HashSet::from(["memo".to_string()]), 1).unwrap();
+        let first = memo.key_for(&resp("memo", 1, b"1")).unwrap();
+        let second = memo.key_for(&resp("memo", 1, b"2")).unwrap();
+        memo.record(first.clone(), Some(b"r1".to_vec().into()));
+        memo.record(second.clone(), Some(b"r2".to_vec().into()));
+        assert_eq!(memo.take(&first), None);
+        assert_eq!(memo.take(&second), Some(Some(b"r2".to_vec().into())));
+        assert_eq!(memo.take(&second), None);
+    }
+
+    #[test]
+    fn disabled_without_types_or_capacity() {
+        assert!(ActivityResultMemo::new(&HashSet::new(), 10).is_none());
+        assert!(ActivityResultMemo::new(&HashSet::from(["memo".to_string()]), 0).is_none());
+    }
+}
@@ -123,6 +123,7 @@ pub(crate) trait WorkerClient: Sync + Send {
         &self,
         task_token: TaskToken,
         failure: Option<Failure>,
+        last_heartbeat_details: Option<Payloads>,
     ) -> Result<RespondActivityTaskFailedResponse>;
     async fn fail_workflow_task(
         &self,
@@ -298,6 +299,7 @@ impl WorkerClient for WorkerClientBag {
         &self,
         task_token: TaskToken,
         failure: Option<Failure>,
+        last_heartbeat_details: Option<Payloads>,
     ) -> Result<RespondActivityTaskFailedResponse> {
         Ok(self
             .client
@@ -307,8 +309,7 @@ impl WorkerClient for WorkerClientBag {
                 failure,
                 identity: self.identity.clone(),
                 namespace: self.namespace.clone(),
-                // TODO: Implement - https://github.com/temporalio/sdk-core/issues/293
-                last_heartbeat_details: None,
+                last_heartbeat_details,
                 worker_version: self.worker_version_stamp(),
             })
             .await?
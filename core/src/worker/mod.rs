@@ -3,7 +3,9 @@ pub(crate) mod client;
 mod slot_provider;
 mod workflow;
 
-pub use temporal_sdk_core_api::worker::{WorkerConfig, WorkerConfigBuilder};
+pub use temporal_sdk_core_api::worker::{
+    ActivityRateLimiter, PendingActivityHandoff, WorkerConfig, WorkerConfigBuilder, WorkerMode,
+};
 
 pub(crate) use activities::{
     ExecutingLAId, LocalActRequest, LocalActivityExecutionResult, LocalActivityResolution,
@@ -14,10 +16,11 @@ pub(crate) use workflow::{wft_poller::new_wft_poller, LEGACY_QUERY_ID};
 use temporal_client::WorkerKey;
 
 use crate::{
-    abstractions::{dbg_panic, MeteredSemaphore},
+    abstractions::{dbg_panic, MeteredSemaphore, SharedSlots},
     errors::CompleteWfError,
     pollers::{
-        new_activity_task_buffer, new_workflow_task_buffer, BoxedActPoller, WorkflowTaskPoller,
+        new_activity_task_buffer, new_workflow_task_buffer, BoxedActPoller, PollGate, PollerKind,
+        RunPriorities, WorkflowTaskPoller,
     },
     protosext::validate_activity_completion,
     telemetry::{
@@ -47,7 +50,7 @@ use std::{
 };
 use temporal_sdk_core_protos::{
     coresdk::{
-        activity_result::activity_execution_result,
+        activity_result::{activity_execution_result, ActivityExecutionResult, Success},
         activity_task::ActivityTask,
         workflow_activation::{remove_from_cache::EvictionReason, WorkflowActivation},
         workflow_completion::WorkflowActivationCompletion,
@@ -88,6 +91,9 @@ pub struct Worker {
     local_act_mgr: Arc<LocalActivityManager>,
     /// Has shutdown been called?
     shutdown_token: CancellationToken,
+    /// Closed while the worker is in standby mode, preventing all polling. Also used to pause one
+    /// kind of polling depending on the [WorkerMode].
+    poll_gate: PollGate,
     /// Will be called at the end of each activation completion
     #[allow(clippy::type_complexity)] // Sorry clippy, there's no simple way to re-use here.
     post_activate_hook: Option<Box<dyn Fn(&Self, PostActivateHookData) + Send + Sync>>,
@@ -105,6 +111,7 @@ impl WorkerTrait for Worker {
 
     #[instrument(skip(self))]
     async fn poll_activity_task(&self) -> Result<ActivityTask, PollActivityError> {
+        self.wait_backpressure_lifted().await;
         loop {
             match self.activity_poll().await.transpose() {
                 Some(r) => break r,
@@ -203,6 +210,7 @@ impl Worker {
         sticky_queue_name: Option<String>,
         client: Arc<dyn WorkerClient>,
         telem_instance: Option<&TelemetryInstance>,
+        shared_slots: SharedSlots,
     ) -> Self {
         info!(task_queue=%config.task_queue, namespace=%config.namespace, "Initializing worker");
 
@@ -212,12 +220,13 @@ impl Worker {
             client,
             TaskPollers::Real,
             telem_instance,
+            shared_slots,
         )
     }
 
     #[cfg(test)]
     pub(crate) fn new_test(config: WorkerConfig, client: impl WorkerClient + 'static) -> Self {
-        Self::new(config, None, Arc::new(client), None)
+        Self::new(config, None, Arc::new(client), None, SharedSlots::default())
     }
 
     #[allow(clippy::too_many_arguments)] // Not much worth combining here
@@ -227,6 +236,7 @@ impl Worker {
         client: Arc<dyn WorkerClient>,
         task_pollers: TaskPollers,
         telem_instance: Option<&TelemetryInstance>,
+        shared_slots: SharedSlots,
     ) -> Self {
         let metrics = if let Some(ti) = telem_instance {
             MetricsContext::top_level(config.namespace.clone(), config.task_queue.clone(), ti)
@@ -235,16 +245,24 @@ impl Worker {
         };
         metrics.worker_registered();
         let shutdown_token = CancellationToken::new();
-        let wft_semaphore = Arc::new(MeteredSemaphore::new(
-            config.max_outstanding_workflow_tasks,
-            metrics.with_new_attrs([workflow_worker_type()]),
-            MetricsContext::available_task_slots,
-        ));
-        let act_semaphore = Arc::new(MeteredSemaphore::new(
-            config.max_outstanding_activities,
-            metrics.with_new_attrs([activity_worker_type()]),
-            MetricsContext::available_task_slots,
-        ));
+        let poll_gate = PollGate::new(!config.start_in_standby);
+        let wft_semaphore = Arc::new(
+            MeteredSemaphore::new(
+                config.max_outstanding_workflow_tasks,
+                metrics.with_new_attrs([workflow_worker_type()]),
+                MetricsContext::available_task_slots,
+            )
+            .with_shared(shared_slots.workflow_tasks),
+        );
+        let act_semaphore = Arc::new(
+            MeteredSemaphore::new(
+                config.max_outstanding_activities,
+                metrics.with_new_attrs([activity_worker_type()]),
+                MetricsContext::available_task_slots,
+            )
+            .with_shared(shared_slots.activities)
+            .with_preemption(config.slot_preemption_grace_period),
+        );
         let (external_wft_tx, external_wft_rx) = unbounded_channel();
         let (wft_stream, act_poller) = match task_pollers {
             TaskPollers::Real => {
@@ -255,6 +273,7 @@ impl Worker {
                 };
                 let max_sticky_polls = config.max_sticky_polls();
                 let wft_metrics = metrics.with_new_attrs([workflow_poller()]);
+                let run_priorities = RunPriorities::new(config.max_cached_workflows);
                 let wf_task_poll_buffer = new_workflow_task_buffer(
                     client.clone(),
                     TaskQueue {
@@ -264,10 +283,12 @@ impl Worker {
                     },
                     max_nonsticky_polls,
                     wft_semaphore.clone(),
+                    poll_gate.clone(),
                     shutdown_token.child_token(),
                     Some(move |np| {
                         wft_metrics.record_num_pollers(np);
                     }),
+                    run_priorities.clone(),
                 );
                 let sticky_queue_poller = sticky_queue_name.as_ref().map(|sqn| {
                     let sticky_metrics = metrics.with_new_attrs([workflow_sticky_poller()]);
@@ -280,10 +301,12 @@ impl Worker {
                         },
                         max_sticky_polls,
                         wft_semaphore.clone(),
+                        poll_gate.clone(),
                         shutdown_token.child_token(),
                         Some(move |np| {
                             sticky_metrics.record_num_pollers(np);
                         }),
+                        run_priorities.clone(),
                     )
                 });
                 let act_poll_buffer = if config.no_remote_activities {
@@ -296,9 +319,11 @@ impl Worker {
                         config.max_concurrent_at_polls,
                         act_semaphore.clone(),
                         config.max_task_queue_activities_per_second,
+                        poll_gate.for_kind(PollerKind::Activity),
                         shutdown_token.child_token(),
                         Some(move |np| act_metrics.record_num_pollers(np)),
                         config.max_worker_activities_per_second,
+                        config.activity_rate_limiter.clone(),
                     );
                     Some(Box::from(ap) as BoxedActPoller)
                 };
@@ -349,6 +374,10 @@ impl Worker {
             config.namespace.clone(),
             hb_tx,
             metrics.with_new_attrs([local_activity_worker_type()]),
+            shared_slots.local_activities,
+            &config.max_outstanding_local_activities_per_type,
+            config.payload_size_limits.local_activity_result_error_bytes,
+            config.local_activity_stall_timeout,
         ));
         let at_task_mgr = act_poller.map(|ap| {
             WorkerActivityTasks::new(
@@ -358,7 +387,14 @@ impl Worker {
                 metrics.clone(),
                 config.max_heartbeat_throttle_interval,
                 config.default_heartbeat_throttle_interval,
+                config.heartbeat_throttle_interval,
                 config.graceful_shutdown_period,
+                config.activity_cancel_grace_period,
+                &config.activity_type_rate_limits,
+                Some((
+                    &config.memoized_activity_types,
+                    config.max_memoized_activity_results,
+                )),
                 config.local_timeout_buffer_for_activities,
             )
         });
@@ -371,6 +407,7 @@ impl Worker {
             config.namespace.clone(),
             config.task_queue.clone(),
             wft_semaphore.clone(),
+            poll_gate.clone(),
             external_wft_tx,
         );
         let worker_key = client.workers().register(Box::new(provider));
@@ -403,15 +440,16 @@ impl Worker {
                 la_sink,
                 local_act_mgr.clone(),
                 hb_rx,
-                at_task_mgr
-                    .as_ref()
-                    .map(|mgr| mgr.get_handle_for_workflows()),
+                at_task_mgr.as_ref().map(|mgr| {
+                    mgr.get_handle_for_workflows(poll_gate.for_kind(PollerKind::Activity))
+                }),
                 telem_instance,
             ),
             at_task_mgr,
             local_act_mgr,
             config,
             shutdown_token,
+            poll_gate,
             post_activate_hook: None,
             // Non-local activities are already complete if configured not to poll for them.
             non_local_activities_complete: Arc::new(AtomicBool::new(!poll_on_non_local_activities)),
@@ -451,6 +489,128 @@ impl Worker {
         self.shutdown_token.clone()
     }
 
+    /// Places the worker into (or takes it out of) standby mode. While in standby, the worker does
+    /// not poll for workflow or activity tasks and will not accept eagerly started workflows, but
+    /// its client connection and workflow cache are kept warm so that it may become active again
+    /// immediately. Polls already in flight when entering standby are allowed to complete.
+    pub fn set_standby(&self, standby: bool) {
+        if standby == self.is_standby() {
+            return;
+        }
+        info!(
+            task_queue=%self.config.task_queue,
+            namespace=%self.config.namespace,
+            standby,
+            "Worker standby mode changed",
+        );
+        self.poll_gate.set_open(!standby);
+    }
+
+    /// Returns true if the worker is currently in standby mode. See [Worker::set_standby].
+    pub fn is_standby(&self) -> bool {
+        !self.poll_gate.is_open()
+    }
+
+    /// Switches which kinds of tasks the worker polls for. Pollers for a kind of task which is no
+    /// longer wanted stop issuing new polls (in-flight polls complete normally), and stop
+    /// claiming task slots, leaving them free for other workers sharing a slot budget. Pollers
+    /// are restarted when switching back. Eager workflow start and eager activity execution are
+    /// only accepted while the corresponding kind of polling is enabled. Slots freed by switching
+    /// modes are not set aside for higher priority eager work; they go to whoever asks next.
+    ///
+    /// Returns an error if asked to poll for activities when the worker was created with
+    /// `no_remote_activities`.
+    pub fn set_mode(&self, mode: WorkerMode) -> Result<(), anyhow::Error> {
+        if mode != WorkerMode::WorkflowOnly && self.at_task_mgr.is_none() {
+            return Err(anyhow::anyhow!(
+                "Worker for task queue {} was created with `no_remote_activities` and cannot poll \
+                 for activities",
+                self.config.task_queue
+            ));
+        }
+        if mode == self.mode() {
+            return Ok(());
+        }
+        info!(
+            task_queue=%self.config.task_queue,
+            namespace=%self.config.namespace,
+            ?mode,
+            "Worker mode changed",
+        );
+        self.poll_gate
+            .set_paused(PollerKind::Workflow, mode == WorkerMode::ActivityOnly);
+        self.poll_gate
+            .set_paused(PollerKind::Activity, mode == WorkerMode::WorkflowOnly);
+        Ok(())
+    }
+
+    /// Returns the kinds of tasks the worker is currently polling for. See [Worker::set_mode].
+    pub fn mode(&self) -> WorkerMode {
+        if self.at_task_mgr.is_none() || self.poll_gate.is_paused(PollerKind::Activity) {
+            WorkerMode::WorkflowOnly
+        } else if self.poll_gate.is_paused(PollerKind::Workflow) {
+            WorkerMode::ActivityOnly
+        } else {
+            WorkerMode::Combined
+        }
+    }
+
+    /// Signals that lang is struggling to keep up (ex: its event loop is saturated) and core should
+    /// slow down, or that it has recovered. While backpressure is applied, each of the worker's
+    /// pollers issues at most one poll request at a time, and calls to poll for workflow
+    /// activations or activity tasks wait for it to be lifted before delivering anything, unless
+    /// the worker is shutting down. Completions are still accepted. Slot limits are unaffected.
+    pub fn set_backpressure(&self, backpressured: bool) {
+        if backpressured == self.is_backpressured() {
+            return;
+        }
+        debug!(
+            task_queue=%self.config.task_queue,
+            backpressured,
+            "Worker backpressure changed",
+        );
+        self.poll_gate.set_backpressured(backpressured);
+    }
+
+    /// Returns true if lang has currently applied backpressure. See [Worker::set_backpressure].
+    pub fn is_backpressured(&self) -> bool {
+        self.poll_gate.is_backpressured()
+    }
+
+    /// Resolves once lang has lifted backpressure, or the worker has begun shutting down
+    async fn wait_backpressure_lifted(&self) {
+        tokio::select! {
+            _ = self.poll_gate.wait_backpressure_lifted() => {}
+            _ = self.shutdown_token.cancelled() => {}
+        }
+    }
+
+    /// Asks core to heartbeat the current workflow task of the provided run as soon as it is
+    /// waiting on local activities, instead of after the configured heartbeat interval. Lang
+    /// should call this when it knows an activation will take a long time to resolve, ex: when it
+    /// is about to schedule a very large number of local activities. Has no effect if the run
+    /// has no outstanding workflow task.
+    pub fn force_workflow_task_heartbeat(&self, run_id: &str) {
+        self.workflows.force_wft_heartbeat(run_id);
+    }
+
+    /// Returns the activities lang has handed off to be completed outside the worker which have not
+    /// yet timed out. See [PendingActivityHandoff].
+    pub fn pending_activity_handoffs(&self) -> Vec<PendingActivityHandoff> {
+        self.at_task_mgr
+            .as_ref()
+            .map(|atm| atm.pending_handoffs())
+            .unwrap_or_default()
+    }
+
+    /// Tell the worker that an activity lang handed off has been completed outside the worker, so
+    /// it need no longer be reported by [Worker::pending_activity_handoffs].
+    pub fn activity_handoff_completed(&self, task_token: &TaskToken) {
+        if let Some(atm) = self.at_task_mgr.as_ref() {
+            atm.handoff_completed(task_token);
+        }
+    }
+
     /// Returns number of currently cached workflows
     pub async fn cached_workflows(&self) -> usize {
         self.workflows
@@ -533,16 +693,45 @@ impl Worker {
             }
         };
 
-        tokio::select! {
+        let r = tokio::select! {
             biased;
 
             r = local_activities_poll => r,
             r = act_mgr_poll => r,
+        };
+        // Hard cancelled activities are finished without waiting on lang, before lang is told
+        if let (Ok(Some(task)), Some(atm)) = (&r, &self.at_task_mgr) {
+            if task.is_hard_cancel() {
+                atm.complete(
+                    TaskToken(task.task_token.clone()),
+                    ActivityExecutionResult::cancel_from_details(None)
+                        .status
+                        .expect("cancel result has status"),
+                    &*self.wf_client,
+                )
+                .await;
+            } else if let Some(result) = atm.take_memoized_result(task) {
+                info!(task_token=%TaskToken(task.task_token.clone()),
+                      "Reporting memoized result for retried activity instead of executing it");
+                atm.complete(
+                    TaskToken(task.task_token.clone()),
+                    activity_execution_result::Status::Completed(Success { result }),
+                    &*self.wf_client,
+                )
+                .await;
+                return Ok(None);
+            }
         }
+        r
     }
 
     /// Attempt to record an activity heartbeat
     pub(crate) fn record_heartbeat(&self, details: ActivityHeartbeat) {
+        let tt = TaskToken(details.task_token.clone());
+        if tt.is_local_activity_task() {
+            self.local_act_mgr.record_progress(&tt, details.details);
+            return;
+        }
         if let Some(at_mgr) = self.at_task_mgr.as_ref() {
             let tt = details.task_token.clone();
             if let Err(e) = at_mgr.record_heartbeat(details) {
@@ -579,6 +768,7 @@ impl Worker {
 
     #[instrument(skip(self), fields(run_id, workflow_id, task_queue=%self.config.task_queue))]
     pub(crate) async fn next_workflow_activation(&self) -> Result<WorkflowActivation, PollWfError> {
+        self.wait_backpressure_lifted().await;
         let r = self.workflows.next_workflow_activation().await;
         // In the event workflows are shutdown, begin shutdown of everything else, since that's
         // about to happen anyway. Tell the local activity manager that, so that it can know to
@@ -693,6 +883,7 @@ mod tests {
         advance_fut, test_help::test_worker_cfg, worker::client::mocks::mock_workflow_client,
     };
     use futures::FutureExt;
+    use std::time::Duration;
 
     use temporal_sdk_core_protos::temporal::api::workflowservice::v1::PollActivityTaskQueueResponse;
 
@@ -736,6 +927,87 @@ mod tests {
         assert_eq!(worker.at_task_mgr.unwrap().remaining_activity_capacity(), 5);
     }
 
+    #[tokio::test]
+    async fn standby_worker_does_not_poll_until_activated() {
+        let mut mock_client = mock_workflow_client();
+        mock_client
+            .expect_poll_activity_task()
+            .times(1)
+            .returning(|_, _| {
+                Ok(PollActivityTaskQueueResponse {
+                    task_token: vec![1],
+                    activity_id: "act1".to_string(),
+                    ..Default::default()
+                })
+            });
+
+        let cfg = test_worker_cfg()
+            .max_outstanding_activities(1_usize)
+            .max_concurrent_at_polls(1_usize)
+            .start_in_standby(true)
+            .build()
+            .unwrap();
+        let worker = Worker::new_test(cfg, mock_client);
+        assert!(worker.is_standby());
+        let fut = worker.poll_activity_task();
+        tokio::pin!(fut);
+        tokio::select! {
+            _ = &mut fut => panic!("Standby worker should not have polled"),
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+        }
+        worker.set_standby(false);
+        assert!(!worker.is_standby());
+        let task = fut.await.unwrap();
+        assert_eq!(task.task_token, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn workflow_only_mode_pauses_activity_polling() {
+        let mut mock_client = mock_workflow_client();
+        mock_client
+            .expect_poll_activity_task()
+            .times(1)
+            .returning(|_, _| {
+                Ok(PollActivityTaskQueueResponse {
+                    task_token: vec![1],
+                    activity_id: "act1".to_string(),
+                    ..Default::default()
+                })
+            });
+
+        let cfg = test_worker_cfg()
+            .max_outstanding_activities(1_usize)
+            .max_concurrent_at_polls(1_usize)
+            .build()
+            .unwrap();
+        let worker = Worker::new_test(cfg, mock_client);
+        assert_eq!(worker.mode(), WorkerMode::Combined);
+        worker.set_mode(WorkerMode::WorkflowOnly).unwrap();
+        assert_eq!(worker.mode(), WorkerMode::WorkflowOnly);
+        let fut = worker.poll_activity_task();
+        tokio::pin!(fut);
+        tokio::select! {
+            _ = &mut fut => panic!("Workflow only worker should not have polled for activities"),
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+        }
+        worker.set_mode(WorkerMode::Combined).unwrap();
+        let task = fut.await.unwrap();
+        assert_eq!(task.task_token, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn cannot_poll_activities_without_remote_activities() {
+        let cfg = test_worker_cfg()
+            .no_remote_activities(true)
+            .build()
+            .unwrap();
+        let worker = Worker::new_test(cfg, mock_workflow_client());
+        assert_eq!(worker.mode(), WorkerMode::WorkflowOnly);
+        assert!(worker.set_mode(WorkerMode::ActivityOnly).is_err());
+        assert!(worker.set_mode(WorkerMode::Combined).is_err());
+        worker.set_mode(WorkerMode::WorkflowOnly).unwrap();
+    }
+
     #[test]
     fn max_polls_calculated_properly() {
         let mut wcb = WorkerConfigBuilder::default();
@@ -4,6 +4,7 @@
 
 use crate::{
     abstractions::{MeteredSemaphore, OwnedMeteredSemPermit},
+    pollers::PollGate,
     protosext::ValidPollWFTQResponse,
     worker::workflow::wft_poller::validate_wft,
 };
@@ -48,6 +49,9 @@ pub struct SlotProvider {
     namespace: String,
     task_queue: String,
     wft_semaphore: Arc<MeteredSemaphore>,
+    /// Slots are not handed out while this is closed (the worker is in standby) or workflow
+    /// polling is paused
+    poll_gate: PollGate,
     external_wft_tx: WFTStreamSender,
 }
 
@@ -56,12 +60,14 @@ impl SlotProvider {
         namespace: String,
         task_queue: String,
         wft_semaphore: Arc<MeteredSemaphore>,
+        poll_gate: PollGate,
         external_wft_tx: WFTStreamSender,
     ) -> Self {
         Self {
             namespace,
             task_queue,
             wft_semaphore,
+            poll_gate,
             external_wft_tx,
         }
     }
@@ -75,6 +81,9 @@ impl SlotProviderTrait for SlotProvider {
         &self.task_queue
     }
     fn try_reserve_wft_slot(&self) -> Option<Box<dyn SlotTrait + Send>> {
+        if !self.poll_gate.allows_polling() {
+            return None;
+        }
         match self.wft_semaphore.try_acquire_owned().ok() {
             Some(permit) => Some(Box::new(Slot::new(permit, self.external_wft_tx.clone()))),
             None => None,
@@ -117,6 +126,7 @@ mod tests {
             "my_namespace".to_string(),
             "my_queue".to_string(),
             wft_semaphore,
+            PollGate::default(),
             external_wft_tx,
         );
 
@@ -142,6 +152,7 @@ mod tests {
                 "my_namespace".to_string(),
                 "my_queue".to_string(),
                 wft_semaphore,
+                PollGate::default(),
                 external_wft_tx,
             );
             assert!(provider.try_reserve_wft_slot().is_some());
@@ -163,6 +174,7 @@ mod tests {
                 "my_namespace".to_string(),
                 "my_queue".to_string(),
                 wft_semaphore.clone(),
+                PollGate::default(),
                 external_wft_tx,
             );
             let slot = provider.try_reserve_wft_slot();
@@ -172,4 +184,26 @@ mod tests {
         }
         assert_eq!(wft_semaphore.available_permits(), 2);
     }
+
+    #[tokio::test]
+    async fn no_slots_when_gate_closed() {
+        let wft_semaphore = Arc::new(MeteredSemaphore::new(
+            2,
+            crate::MetricsContext::no_op(),
+            |_, _| {},
+        ));
+        let (external_wft_tx, _) = unbounded_channel();
+        let gate = PollGate::new(false);
+        let provider = SlotProvider::new(
+            "my_namespace".to_string(),
+            "my_queue".to_string(),
+            wft_semaphore.clone(),
+            gate.clone(),
+            external_wft_tx,
+        );
+        assert!(provider.try_reserve_wft_slot().is_none());
+        assert_eq!(wft_semaphore.available_permits(), 2);
+        gate.set_open(true);
+        assert!(provider.try_reserve_wft_slot().is_some());
+    }
 }
@@ -6,7 +6,10 @@ use prost_types::Timestamp;
 use std::sync::mpsc::{self, Receiver, Sender};
 use temporal_sdk_core_protos::{
     coresdk::workflow_activation::{start_workflow_from_attribs, WorkflowActivationJob},
-    temporal::api::history::v1::WorkflowExecutionStartedEventAttributes,
+    temporal::api::{
+        common::v1::{Memo, Payload},
+        history::v1::WorkflowExecutionStartedEventAttributes,
+    },
     utilities::TryIntoOrNone,
 };
 
@@ -57,6 +60,23 @@ impl DrivenWorkflow {
         self.started_attrs.as_ref()
     }
 
+    /// Merge memo fields upserted by the workflow into the memo remembered from the started event,
+    /// so that it stays the workflow's current memo. Fields upserted with an empty payload are
+    /// removed, as they are by the server.
+    pub(super) fn upsert_memo(&mut self, upserted: &Memo) {
+        let Some(started) = self.started_attrs.as_mut() else {
+            return;
+        };
+        let memo = started.memo.get_or_insert_with(Default::default);
+        for (k, v) in &upserted.fields {
+            if *v == Payload::default() {
+                memo.fields.remove(k);
+            } else {
+                memo.fields.insert(k.clone(), v.clone());
+            }
+        }
+    }
+
     /// Enqueue a new job to be sent to the driven workflow
     pub(super) fn send_job(&mut self, job: OutgoingJob) {
         self.outgoing_wf_activation_jobs.push(job);
@@ -14,7 +14,7 @@ use std::convert::{TryFrom, TryInto};
 use temporal_sdk_core_protos::{
     coresdk::{
         activity_result::{self as ar, activity_resolution, ActivityResolution, Cancellation},
-        workflow_activation::ResolveActivity,
+        workflow_activation::{NotifyLateResolution, ResolveActivity},
         workflow_commands::{ActivityCancellationType, ScheduleActivity},
     },
     temporal::api::{
@@ -51,7 +51,7 @@ fsm! {
     ScheduledEventRecorded --(ActivityTaskTimedOut(ActivityTaskTimedOutEventAttributes),
         shared on_task_timed_out) --> TimedOut;
     ScheduledEventRecorded --(Cancel, shared on_canceled) --> ScheduledActivityCancelCommandCreated;
-    ScheduledEventRecorded --(Abandon, on_abandoned) --> Canceled;
+    ScheduledEventRecorded --(Abandon, shared on_abandoned) --> Canceled;
 
     Started --(ActivityTaskCompleted(ActivityTaskCompletedEventAttributes),
         on_activity_task_completed) --> Completed;
@@ -60,7 +60,7 @@ fsm! {
     Started --(ActivityTaskTimedOut(ActivityTaskTimedOutEventAttributes),
         shared on_activity_task_timed_out) --> TimedOut;
     Started --(Cancel, shared on_canceled) --> StartedActivityCancelCommandCreated;
-    Started --(Abandon, on_abandoned) --> Canceled;
+    Started --(Abandon, shared on_abandoned) --> Canceled;
 
     ScheduledActivityCancelCommandCreated --(CommandRequestCancelActivityTask) --> ScheduledActivityCancelCommandCreated;
     ScheduledActivityCancelCommandCreated --(ActivityTaskCancelRequested) --> ScheduledActivityCancelEventRecorded;
@@ -86,6 +86,10 @@ fsm! {
     Canceled --(ActivityTaskStarted(i64), shared on_activity_task_started) --> Canceled;
     Canceled --(ActivityTaskCompleted(ActivityTaskCompletedEventAttributes),
         shared on_activity_task_completed) --> Canceled;
+    Canceled --(ActivityTaskFailed(ActivityTaskFailedEventAttributes),
+        shared on_activity_task_failed) --> Canceled;
+    Canceled --(ActivityTaskTimedOut(ActivityTaskTimedOutEventAttributes),
+        shared on_activity_task_timed_out) --> Canceled;
 }
 
 #[derive(Debug, derive_more::Display)]
@@ -124,6 +128,7 @@ impl ActivityMachine {
                 scheduled_event_id: 0,
                 started_event_id: 0,
                 cancelled_before_sent: false,
+                cancel_resolved: false,
             },
         );
         OnEventWrapper::on_event_mut(&mut s, ActivityMachineEvents::Schedule)
@@ -134,6 +139,7 @@ impl ActivityMachine {
                 s.shared_state().attrs.clone(),
                 use_compatible_version,
             )),
+            user_metadata: s.shared_state().attrs.user_metadata.clone(),
         };
         NewMachineWithCommand {
             command,
@@ -255,35 +261,37 @@ impl WFMachinesAdapter for ActivityMachine {
         my_command: ActivityMachineCommand,
         event_info: Option<EventInfo>,
     ) -> Result<Vec<MachineResponse>, WFMachinesError> {
-        Ok(match my_command {
-            ActivityMachineCommand::Complete(result) => {
-                vec![ResolveActivity {
-                    seq: self.shared_state.attrs.seq,
-                    result: Some(ActivityResolution {
-                        status: Some(activity_resolution::Status::Completed(ar::Success {
-                            result: convert_payloads(event_info, result)?,
-                        })),
-                    }),
-                }
-                .into()]
-            }
-            ActivityMachineCommand::Fail(failure) => {
-                vec![ResolveActivity {
-                    seq: self.shared_state.attrs.seq,
-                    result: Some(ActivityResolution {
-                        status: Some(activity_resolution::Status::Failed(ar::Failure {
-                            failure: Some(failure),
-                        })),
-                    }),
-                }
-                .into()]
-            }
+        let resolution = match my_command {
+            ActivityMachineCommand::Complete(result) => ResolveActivity {
+                seq: self.shared_state.attrs.seq,
+                result: Some(ActivityResolution {
+                    status: Some(activity_resolution::Status::Completed(ar::Success {
+                        result: convert_payloads(event_info, result)?,
+                    })),
+                }),
+            },
+            ActivityMachineCommand::Fail(failure) => ResolveActivity {
+                seq: self.shared_state.attrs.seq,
+                result: Some(ActivityResolution {
+                    status: Some(activity_resolution::Status::Failed(ar::Failure {
+                        failure: Some(failure),
+                    })),
+                }),
+            },
             ActivityMachineCommand::RequestCancellation(c) => {
-                self.machine_responses_from_cancel_request(c)
+                return Ok(self.machine_responses_from_cancel_request(c));
             }
-            ActivityMachineCommand::Cancel(attrs) => {
-                vec![self.create_cancelation_resolve(attrs).into()]
+            ActivityMachineCommand::Cancel(attrs) => self.create_cancelation_resolve(attrs),
+        };
+        // Lang already saw this activity resolve as cancelled, so this can only be delivered as
+        // a late resolution
+        Ok(if self.shared_state.cancel_resolved {
+            vec![NotifyLateResolution {
+                resolution: Some(resolution.into()),
             }
+            .into()]
+        } else {
+            vec![resolution.into()]
         })
     }
 
@@ -361,6 +369,9 @@ pub(super) struct SharedState {
     attrs: ScheduleActivity,
     cancellation_type: ActivityCancellationType,
     cancelled_before_sent: bool,
+    /// Set once lang has been sent a cancellation resolution without waiting for the cancel to
+    /// be confirmed, after which any resolution is a late one
+    cancel_resolved: bool,
     internal_flags: InternalFlagsRef,
 }
 
@@ -408,6 +419,7 @@ impl ScheduleCommandCreated {
 
     pub(super) fn on_canceled(self, dat: &mut SharedState) -> ActivityMachineTransition<Canceled> {
         dat.cancelled_before_sent = true;
+        dat.cancel_resolved = true;
         match dat.cancellation_type {
             ActivityCancellationType::Abandon => {
                 dbg_panic!("Can't get on_canceled transition with Abandon cancelation type");
@@ -419,6 +431,7 @@ impl ScheduleCommandCreated {
 
     pub(super) fn on_abandoned(self, dat: &mut SharedState) -> ActivityMachineTransition<Canceled> {
         dat.cancelled_before_sent = true;
+        dat.cancel_resolved = true;
         ActivityMachineTransition::default()
     }
 }
@@ -452,7 +465,8 @@ impl ScheduledEventRecorded {
             ScheduledActivityCancelCommandCreated::default(),
         )
     }
-    pub(super) fn on_abandoned(self) -> ActivityMachineTransition<Canceled> {
+    pub(super) fn on_abandoned(self, dat: &mut SharedState) -> ActivityMachineTransition<Canceled> {
+        dat.cancel_resolved = true;
         notify_lang_activity_cancelled(None)
     }
 }
@@ -498,7 +512,8 @@ impl Started {
             StartedActivityCancelCommandCreated::default(),
         )
     }
-    pub(super) fn on_abandoned(self) -> ActivityMachineTransition<Canceled> {
+    pub(super) fn on_abandoned(self, dat: &mut SharedState) -> ActivityMachineTransition<Canceled> {
+        dat.cancel_resolved = true;
         notify_lang_activity_cancelled(None)
     }
 }
@@ -583,7 +598,8 @@ where
 {
     match &dat.cancellation_type {
         // At this point if we are in TryCancel mode, we've already sent a cancellation failure
-        // to lang unblocking it, so there is no need to send another activation.
+        // to lang unblocking it, so the result is only sent on if lang asked for late resolutions.
+        ActivityCancellationType::TryCancel if dat.attrs.deliver_late_resolution => notifier(dat),
         ActivityCancellationType::TryCancel => ActivityMachineTransition::default(),
         ActivityCancellationType::WaitCancellationCompleted => notifier(dat),
         // Abandon results in going into Cancelled immediately, so we should never reach this state
@@ -673,15 +689,58 @@ impl Canceled {
         dat: &mut SharedState,
         attrs: ActivityTaskCompletedEventAttributes,
     ) -> ActivityMachineTransition<Canceled> {
-        // Abandoned activities might complete anyway. Ignore the result.
+        // Abandoned activities might complete anyway. The result is ignored unless lang asked
+        // for late resolutions.
         if dat.cancellation_type == ActivityCancellationType::Abandon {
-            TransitionResult::default()
+            late_resolution(dat, |_| ActivityMachineCommand::Complete(attrs.result))
         } else {
             TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
                 "Non-Abandon cancel mode activities cannot be completed after being cancelled: {attrs:?}"
             )))
         }
     }
+    pub(super) fn on_activity_task_failed(
+        self,
+        dat: &mut SharedState,
+        attrs: ActivityTaskFailedEventAttributes,
+    ) -> ActivityMachineTransition<Canceled> {
+        if dat.cancellation_type == ActivityCancellationType::Abandon {
+            late_resolution(dat, |dat| {
+                ActivityMachineCommand::Fail(new_failure(dat, attrs))
+            })
+        } else {
+            TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Non-Abandon cancel mode activities cannot fail after being cancelled: {attrs:?}"
+            )))
+        }
+    }
+    pub(super) fn on_activity_task_timed_out(
+        self,
+        dat: &mut SharedState,
+        attrs: ActivityTaskTimedOutEventAttributes,
+    ) -> ActivityMachineTransition<Canceled> {
+        if dat.cancellation_type == ActivityCancellationType::Abandon {
+            late_resolution(dat, |dat| {
+                ActivityMachineCommand::Fail(new_timeout_failure(dat, attrs))
+            })
+        } else {
+            TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Non-Abandon cancel mode activities cannot time out after being cancelled: {attrs:?}"
+            )))
+        }
+    }
+}
+
+/// Resolutions of abandoned activities are only passed on to lang if it asked for them
+fn late_resolution(
+    dat: &SharedState,
+    resolution: impl FnOnce(&SharedState) -> ActivityMachineCommand,
+) -> ActivityMachineTransition<Canceled> {
+    if dat.attrs.deliver_late_resolution {
+        TransitionResult::commands(vec![resolution(dat)])
+    } else {
+        TransitionResult::default()
+    }
 }
 
 fn create_request_cancel_activity_task_command<S>(
@@ -691,6 +750,8 @@ fn create_request_cancel_activity_task_command<S>(
 where
     S: Into<ActivityMachineState>,
 {
+    dat.cancel_resolved =
+        dat.cancellation_type != ActivityCancellationType::WaitCancellationCompleted;
     let cmd = Command {
         command_type: CommandType::RequestCancelActivityTask as i32,
         attributes: Some(
@@ -700,6 +761,7 @@ where
                 },
             ),
         ),
+        user_metadata: None,
     };
     ActivityMachineTransition::ok(
         vec![ActivityMachineCommand::RequestCancellation(cmd)],
@@ -889,6 +951,7 @@ mod test {
                     attrs: Default::default(),
                     cancellation_type: Default::default(),
                     cancelled_before_sent: false,
+                    cancel_resolved: false,
                     internal_flags: Rc::new(RefCell::new(InternalFlags::new(&Default::default()))),
                 },
             );
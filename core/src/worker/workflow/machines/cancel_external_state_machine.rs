@@ -78,6 +78,7 @@ pub(super) fn new_external_cancel(
     let cmd = Command {
         command_type: CommandType::RequestCancelExternalWorkflowExecution as i32,
         attributes: Some(cmd_attrs),
+        user_metadata: None,
     };
     NewMachineWithCommand {
         command: cmd,
@@ -235,11 +236,14 @@ mod tests {
 
     async fn cancel_sender(ctx: WfContext) -> WorkflowResult<()> {
         let res = ctx
-            .cancel_external(NamespacedWorkflowExecution {
-                namespace: "some_namespace".to_string(),
-                workflow_id: "fake_wid".to_string(),
-                run_id: "fake_rid".to_string(),
-            })
+            .cancel_external_with_reason(
+                NamespacedWorkflowExecution {
+                    namespace: "some_namespace".to_string(),
+                    workflow_id: "fake_wid".to_string(),
+                    run_id: "fake_rid".to_string(),
+                },
+                "Sender is done with it".to_string(),
+            )
             .await;
         if res.is_err() {
             Err(anyhow::anyhow!("Cancel fail!"))
@@ -278,8 +282,9 @@ mod tests {
             asserts
                 .then(|wft| {
                     assert_matches!(
-                        wft.commands[0].command_type(),
-                        CommandType::RequestCancelExternalWorkflowExecution
+                        wft.commands[0].attributes.as_ref().unwrap(),
+                        command::Attributes::RequestCancelExternalWorkflowExecutionCommandAttributes(a)
+                            if a.reason == "Sender is done with it"
                     );
                 })
                 .then(move |wft| {
@@ -40,6 +40,7 @@ pub(super) fn cancel_workflow(attribs: CancelWorkflowExecution) -> NewMachineWit
     let command = Command {
         command_type: CommandType::CancelWorkflowExecution as i32,
         attributes: Some(attribs.into()),
+        user_metadata: None,
     };
     NewMachineWithCommand {
         command,
@@ -116,11 +117,17 @@ impl Cancellable for CancelWorkflowMachine {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_help::{build_fake_sdk, canned_histories, MockPollCfg};
+    use crate::{
+        replay::TestHistoryBuilder,
+        test_help::{build_fake_sdk, canned_histories, MockPollCfg},
+    };
     use std::time::Duration;
     use temporal_sdk::{WfContext, WfExitValue, WorkflowResult};
     use temporal_sdk_core_protos::{
         coresdk::workflow_activation::{workflow_activation_job, WorkflowActivationJob},
+        temporal::api::{
+            enums::v1::EventType, history::v1::WorkflowExecutionCancelRequestedEventAttributes,
+        },
         DEFAULT_WORKFLOW_TYPE,
     };
     use temporal_sdk_core_test_utils::interceptors::ActivationAssertionsInterceptor;
@@ -178,4 +185,25 @@ mod tests {
         worker.set_worker_interceptor(aai);
         worker.run().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn cancel_reason_given_to_lang() {
+        let mut t = TestHistoryBuilder::default();
+        t.add_by_type(EventType::WorkflowExecutionStarted);
+        t.add_full_wf_task();
+        t.add(WorkflowExecutionCancelRequestedEventAttributes {
+            cause: "No longer needed".to_string(),
+            ..Default::default()
+        });
+        t.add_full_wf_task();
+        t.add_cancelled();
+
+        let mut worker = build_fake_sdk(MockPollCfg::from_hist_builder(t));
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+            ctx.cancelled().await;
+            assert_eq!(ctx.cancelled_reason().as_deref(), Some("No longer needed"));
+            Ok(WfExitValue::<()>::Cancelled)
+        });
+        worker.run().await.unwrap();
+    }
 }
@@ -19,9 +19,10 @@ use temporal_sdk_core_protos::{
             ChildWorkflowCancellationType, ChildWorkflowResult,
         },
         workflow_activation::{
-            resolve_child_workflow_execution_start, ResolveChildWorkflowExecution,
-            ResolveChildWorkflowExecutionStart, ResolveChildWorkflowExecutionStartCancelled,
-            ResolveChildWorkflowExecutionStartFailure, ResolveChildWorkflowExecutionStartSuccess,
+            resolve_child_workflow_execution_start, NotifyLateResolution,
+            ResolveChildWorkflowExecution, ResolveChildWorkflowExecutionStart,
+            ResolveChildWorkflowExecutionStartCancelled, ResolveChildWorkflowExecutionStartFailure,
+            ResolveChildWorkflowExecutionStartSuccess,
         },
         workflow_commands::StartChildWorkflowExecution,
     },
@@ -78,11 +79,15 @@ fsm! {
     // Abandon & try cancel modes may immediately move to cancelled
     Started --(Cancel, shared on_cancelled) --> Cancelled;
     Started --(CommandRequestCancelExternalWorkflowExecution) --> Started;
+    // The server delivered the cancel request to the child, which is when wait cancellation
+    // requested mode moves to cancelled. Other modes keep waiting, or have already resolved.
+    Started --(CancellationRequested, shared on_cancellation_requested) --> Cancelled;
+    Started --(CancellationRequested, shared on_cancellation_requested) --> Started;
 
     // Ignore any spurious cancellations after resolution
     Cancelled --(Cancel) --> Cancelled;
     Cancelled --(ChildWorkflowExecutionCancelled,
-        on_child_workflow_execution_cancelled) --> Cancelled;
+        shared on_child_workflow_execution_cancelled) --> Cancelled;
     // Completions of any kind after cancellation are acceptable for abandoned children
     Cancelled --(ChildWorkflowExecutionCompleted(Option<Payloads>),
         shared on_child_workflow_execution_completed) --> Cancelled;
@@ -96,8 +101,18 @@ fsm! {
     StartFailed --(Cancel) --> StartFailed;
     TimedOut --(Cancel) --> TimedOut;
     Completed --(Cancel) --> Completed;
+
+    // The child may resolve before the cancel request issued for it is delivered
+    Cancelled --(CancellationRequested) --> Cancelled;
+    Completed --(CancellationRequested) --> Completed;
+    Failed --(CancellationRequested) --> Failed;
+    TimedOut --(CancellationRequested) --> TimedOut;
+    Terminated --(CancellationRequested) --> Terminated;
 }
 
+/// Reason sent when requesting cancellation of a child if lang didn't provide one
+const DEFAULT_CANCEL_REASON: &str = "Parent workflow requested cancel";
+
 pub struct ChildWorkflowExecutionStartedEvent {
     workflow_execution: WorkflowExecution,
     started_event_id: i64,
@@ -125,6 +140,7 @@ pub(super) struct ChildWorkflowInitiatedData {
     event_id: i64,
     wf_type: String,
     wf_id: String,
+    namespace: String,
     last_task_in_history: bool,
 }
 
@@ -133,10 +149,10 @@ pub(super) struct Cancelled {
     seen_cancelled_event: bool,
 }
 
-fn completion_of_not_abandoned_err() -> WFMachinesError {
+fn completion_after_cancelled_err() -> WFMachinesError {
     WFMachinesError::Nondeterminism(
-        "Child workflows which don't have the ABANDON cancellation type cannot complete after \
-         being cancelled."
+        "Child workflows with the WAIT_CANCELLATION_COMPLETED cancellation type cannot complete \
+         after being cancelled."
             .to_string(),
     )
 }
@@ -144,6 +160,7 @@ fn completion_of_not_abandoned_err() -> WFMachinesError {
 impl Cancelled {
     pub(super) fn on_child_workflow_execution_cancelled(
         self,
+        state: &mut SharedState,
     ) -> ChildWorkflowMachineTransition<Cancelled> {
         if self.seen_cancelled_event {
             ChildWorkflowMachineTransition::Err(WFMachinesError::Fatal(
@@ -153,7 +170,7 @@ impl Cancelled {
             ))
         } else {
             ChildWorkflowMachineTransition::ok(
-                [],
+                state.late_resolution(|| ChildWorkflowCommand::Cancel),
                 Cancelled {
                     seen_cancelled_event: true,
                 },
@@ -164,44 +181,58 @@ impl Cancelled {
     pub(super) fn on_child_workflow_execution_completed(
         self,
         state: &mut SharedState,
-        _: Option<Payloads>,
+        result: Option<Payloads>,
     ) -> ChildWorkflowMachineTransition<Cancelled> {
-        if !state.abandons() {
-            return ChildWorkflowMachineTransition::Err(completion_of_not_abandoned_err());
+        if !state.resolves_before_child() {
+            return ChildWorkflowMachineTransition::Err(completion_after_cancelled_err());
         }
-        ChildWorkflowMachineTransition::ok([], self)
+        ChildWorkflowMachineTransition::ok(
+            state.late_resolution(|| ChildWorkflowCommand::Complete(result)),
+            self,
+        )
     }
 
     pub(super) fn on_child_workflow_execution_failed(
         self,
         state: &mut SharedState,
-        _: ChildWorkflowExecutionFailedEventAttributes,
+        attrs: ChildWorkflowExecutionFailedEventAttributes,
     ) -> ChildWorkflowMachineTransition<Cancelled> {
-        if !state.abandons() {
-            return ChildWorkflowMachineTransition::Err(completion_of_not_abandoned_err());
+        if !state.resolves_before_child() {
+            return ChildWorkflowMachineTransition::Err(completion_after_cancelled_err());
         }
-        ChildWorkflowMachineTransition::ok([], self)
+        ChildWorkflowMachineTransition::ok(
+            state.late_resolution(|| ChildWorkflowCommand::Fail(failed_failure(state, attrs))),
+            self,
+        )
     }
 
     pub(super) fn on_child_workflow_execution_timed_out(
         self,
         state: &mut SharedState,
-        _: RetryState,
+        retry_state: RetryState,
     ) -> ChildWorkflowMachineTransition<Cancelled> {
-        if !state.abandons() {
-            return ChildWorkflowMachineTransition::Err(completion_of_not_abandoned_err());
+        if !state.resolves_before_child() {
+            return ChildWorkflowMachineTransition::Err(completion_after_cancelled_err());
         }
-        ChildWorkflowMachineTransition::ok([], self)
+        ChildWorkflowMachineTransition::ok(
+            state.late_resolution(|| {
+                ChildWorkflowCommand::Fail(timed_out_failure(state, retry_state))
+            }),
+            self,
+        )
     }
 
     pub(super) fn on_child_workflow_execution_terminated(
         self,
         state: &mut SharedState,
     ) -> ChildWorkflowMachineTransition<Cancelled> {
-        if !state.abandons() {
-            return ChildWorkflowMachineTransition::Err(completion_of_not_abandoned_err());
+        if !state.resolves_before_child() {
+            return ChildWorkflowMachineTransition::Err(completion_after_cancelled_err());
         }
-        ChildWorkflowMachineTransition::ok([], self)
+        ChildWorkflowMachineTransition::ok(
+            state.late_resolution(|| ChildWorkflowCommand::Fail(terminated_failure(state))),
+            self,
+        )
     }
 }
 
@@ -247,6 +278,14 @@ impl StartCommandCreated {
                     event_dat.wf_type, state.workflow_type
                 )));
             }
+            // Older histories may not record the namespace the child was started in
+            if !event_dat.namespace.is_empty() && event_dat.namespace != state.namespace {
+                return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                    "Child workflow namespace of scheduled event '{}' does not \
+                     match child workflow namespace of command '{}'",
+                    event_dat.namespace, state.namespace
+                )));
+            }
         }
         state.initiated_event_id = event_dat.event_id;
         ChildWorkflowMachineTransition::default()
@@ -321,12 +360,7 @@ impl Started {
         attrs: ChildWorkflowExecutionFailedEventAttributes,
     ) -> ChildWorkflowMachineTransition<Failed> {
         ChildWorkflowMachineTransition::ok(
-            vec![ChildWorkflowCommand::Fail(Failure {
-                message: "Child Workflow execution failed".to_owned(),
-                failure_info: failure_info_from_state(state, attrs.retry_state()),
-                cause: attrs.failure.map(Box::new),
-                ..Default::default()
-            })],
+            vec![ChildWorkflowCommand::Fail(failed_failure(state, attrs))],
             Failed::default(),
         )
     }
@@ -336,21 +370,10 @@ impl Started {
         retry_state: RetryState,
     ) -> ChildWorkflowMachineTransition<TimedOut> {
         ChildWorkflowMachineTransition::ok(
-            vec![ChildWorkflowCommand::Fail(Failure {
-                message: "Child Workflow execution timed out".to_owned(),
-                cause: Some(Box::new(Failure {
-                    message: "Timed out".to_owned(),
-                    failure_info: Some(FailureInfo::TimeoutFailureInfo(
-                        failure::TimeoutFailureInfo {
-                            last_heartbeat_details: None,
-                            timeout_type: TimeoutType::StartToClose as i32,
-                        },
-                    )),
-                    ..Default::default()
-                })),
-                failure_info: failure_info_from_state(state, retry_state),
-                ..Default::default()
-            })],
+            vec![ChildWorkflowCommand::Fail(timed_out_failure(
+                state,
+                retry_state,
+            ))],
             TimedOut::default(),
         )
     }
@@ -367,18 +390,7 @@ impl Started {
         state: &mut SharedState,
     ) -> ChildWorkflowMachineTransition<Terminated> {
         ChildWorkflowMachineTransition::ok(
-            vec![ChildWorkflowCommand::Fail(Failure {
-                message: "Child Workflow execution terminated".to_owned(),
-                cause: Some(Box::new(Failure {
-                    message: "Terminated".to_owned(),
-                    failure_info: Some(FailureInfo::TerminatedFailureInfo(
-                        failure::TerminatedFailureInfo {},
-                    )),
-                    ..Default::default()
-                })),
-                failure_info: failure_info_from_state(state, RetryState::NonRetryableFailure),
-                ..Default::default()
-            })],
+            vec![ChildWorkflowCommand::Fail(terminated_failure(state))],
             Terminated::default(),
         )
     }
@@ -388,17 +400,31 @@ impl Started {
     ) -> ChildWorkflowMachineTransition<StartedOrCancelled> {
         let dest = match state.cancel_type {
             ChildWorkflowCancellationType::Abandon | ChildWorkflowCancellationType::TryCancel => {
+                state.cancel_resolved = true;
                 StartedOrCancelled::Cancelled(Default::default())
             }
             _ => StartedOrCancelled::Started(Default::default()),
         };
         TransitionResult::ok(
             [ChildWorkflowCommand::IssueCancelAfterStarted {
-                reason: "Parent workflow requested cancel".to_string(),
+                reason: state.cancel_reason.clone(),
             }],
             dest,
         )
     }
+    fn on_cancellation_requested(
+        self,
+        state: &mut SharedState,
+    ) -> ChildWorkflowMachineTransition<CancelledOrStarted> {
+        if state.cancel_type == ChildWorkflowCancellationType::WaitCancellationRequested {
+            TransitionResult::ok(
+                [ChildWorkflowCommand::Cancel],
+                CancelledOrStarted::Cancelled(Default::default()),
+            )
+        } else {
+            TransitionResult::ok([], CancelledOrStarted::Started(self))
+        }
+    }
 }
 
 #[derive(Default, Clone)]
@@ -418,12 +444,33 @@ pub(super) struct SharedState {
     workflow_type: String,
     cancelled_before_sent: bool,
     cancel_type: ChildWorkflowCancellationType,
+    /// Sent to the server as the reason when requesting cancellation of the child
+    cancel_reason: String,
+    deliver_late_resolution: bool,
+    /// Set once lang has been told the child is cancelled while the child itself may still be
+    /// running, after which any resolution is a late one
+    cancel_resolved: bool,
     internal_flags: InternalFlagsRef,
 }
 
 impl SharedState {
-    fn abandons(&self) -> bool {
-        matches!(self.cancel_type, ChildWorkflowCancellationType::Abandon)
+    /// Whether lang is told the child is cancelled before the child itself has resolved, in which
+    /// case the child can still complete in any way afterward
+    fn resolves_before_child(&self) -> bool {
+        self.cancel_type != ChildWorkflowCancellationType::WaitCancellationCompleted
+    }
+
+    /// Resolutions of children lang was already told are cancelled are only passed on to lang if
+    /// it asked for them
+    fn late_resolution(
+        &self,
+        resolution: impl FnOnce() -> ChildWorkflowCommand,
+    ) -> Vec<ChildWorkflowCommand> {
+        if self.deliver_late_resolution {
+            vec![resolution()]
+        } else {
+            vec![]
+        }
     }
 }
 
@@ -442,6 +489,9 @@ impl ChildWorkflowMachine {
                 workflow_type: attribs.workflow_type.clone(),
                 namespace: attribs.namespace.clone(),
                 cancel_type: attribs.cancellation_type(),
+                cancel_reason: DEFAULT_CANCEL_REASON.to_string(),
+                deliver_late_resolution: attribs.deliver_late_resolution,
+                cancel_resolved: false,
                 internal_flags,
                 run_id: "".to_string(),
                 initiated_event_id: 0,
@@ -453,6 +503,7 @@ impl ChildWorkflowMachine {
             .expect("Scheduling child workflows doesn't fail");
         let cmd = Command {
             command_type: CommandType::StartChildWorkflowExecution as i32,
+            user_metadata: attribs.user_metadata.clone(),
             attributes: Some(start_child_workflow_cmd_to_api(
                 attribs,
                 use_compatible_version,
@@ -464,6 +515,47 @@ impl ChildWorkflowMachine {
         }
     }
 
+    /// Called once the cancel request core issued for this child has been delivered to it
+    pub(super) fn cancellation_requested(
+        &mut self,
+    ) -> Result<Vec<MachineResponse>, WFMachinesError> {
+        let cmds =
+            OnEventWrapper::on_event_mut(self, ChildWorkflowMachineEvents::CancellationRequested)
+                .map_err(|e| match e {
+                MachineError::InvalidTransition => WFMachinesError::Nondeterminism(format!(
+                "Invalid transition while handling delivered cancel request for child workflow \
+                 {} in state {}",
+                self.shared_state.workflow_id,
+                self.state(),
+            )),
+                MachineError::Underlying(e) => e,
+            })?;
+        let resps = cmds
+            .into_iter()
+            .map(|c| self.adapt_response(c, None))
+            .flatten_ok()
+            .try_collect()?;
+        self.shared_state.cancel_resolved =
+            matches!(self.state(), ChildWorkflowMachineState::Cancelled(_));
+        Ok(resps)
+    }
+
+    /// Lang already saw this child resolve as cancelled if the cancel was resolved early, so any
+    /// resolution after that can only be delivered as a late one
+    fn resolution_responses(
+        &self,
+        resolution: ResolveChildWorkflowExecution,
+    ) -> Vec<MachineResponse> {
+        if self.shared_state.cancel_resolved {
+            vec![NotifyLateResolution {
+                resolution: Some(resolution.into()),
+            }
+            .into()]
+        } else {
+            vec![resolution.into()]
+        }
+    }
+
     fn resolve_cancelled_msg(&self) -> ResolveChildWorkflowExecution {
         let failure = Failure {
             message: "Child Workflow execution cancelled".to_owned(),
@@ -510,6 +602,7 @@ impl TryFrom<HistEventData> for ChildWorkflowMachineEvents {
                         event_id: e.event_id,
                         wf_type: attrs.workflow_type.unwrap_or_default().name,
                         wf_id: attrs.workflow_id,
+                        namespace: attrs.namespace,
                         last_task_in_history,
                     })
                 } else {
@@ -653,30 +746,26 @@ impl WFMachinesAdapter for ChildWorkflowMachine {
                 .into()]
             }
             ChildWorkflowCommand::Complete(result) => {
-                vec![ResolveChildWorkflowExecution {
+                self.resolution_responses(ResolveChildWorkflowExecution {
                     seq: self.shared_state.lang_sequence_number,
                     result: Some(ChildWorkflowResult {
                         status: Some(ChildWorkflowStatus::Completed(wfr::Success {
                             result: convert_payloads(event_info, result)?,
                         })),
                     }),
-                }
-                .into()]
+                })
             }
             ChildWorkflowCommand::Fail(failure) => {
-                vec![ResolveChildWorkflowExecution {
+                self.resolution_responses(ResolveChildWorkflowExecution {
                     seq: self.shared_state.lang_sequence_number,
                     result: Some(ChildWorkflowResult {
                         status: Some(ChildWorkflowStatus::Failed(wfr::Failure {
                             failure: Some(failure),
                         })),
                     }),
-                }
-                .into()]
-            }
-            ChildWorkflowCommand::Cancel => {
-                vec![self.resolve_cancelled_msg().into()]
+                })
             }
+            ChildWorkflowCommand::Cancel => self.resolution_responses(self.resolve_cancelled_msg()),
             ChildWorkflowCommand::IssueCancelAfterStarted { reason } => {
                 let mut resps = vec![];
                 if self.shared_state.cancel_type != ChildWorkflowCancellationType::Abandon {
@@ -734,6 +823,16 @@ impl TryFrom<CommandType> for ChildWorkflowMachineEvents {
     }
 }
 
+impl ChildWorkflowMachine {
+    /// Set the reason to send when requesting cancellation of the child. Empty reasons are
+    /// ignored in favor of the default one.
+    pub(super) fn set_cancel_reason(&mut self, reason: String) {
+        if !reason.is_empty() {
+            self.shared_state.cancel_reason = reason;
+        }
+    }
+}
+
 impl Cancellable for ChildWorkflowMachine {
     fn cancel(&mut self) -> Result<Vec<MachineResponse>, MachineError<Self::Error>> {
         let event = ChildWorkflowMachineEvents::Cancel;
@@ -757,6 +856,51 @@ impl Cancellable for ChildWorkflowMachine {
     }
 }
 
+fn failed_failure(
+    state: &SharedState,
+    attrs: ChildWorkflowExecutionFailedEventAttributes,
+) -> Failure {
+    Failure {
+        message: "Child Workflow execution failed".to_owned(),
+        failure_info: failure_info_from_state(state, attrs.retry_state()),
+        cause: attrs.failure.map(Box::new),
+        ..Default::default()
+    }
+}
+
+fn timed_out_failure(state: &SharedState, retry_state: RetryState) -> Failure {
+    Failure {
+        message: "Child Workflow execution timed out".to_owned(),
+        cause: Some(Box::new(Failure {
+            message: "Timed out".to_owned(),
+            failure_info: Some(FailureInfo::TimeoutFailureInfo(
+                failure::TimeoutFailureInfo {
+                    last_heartbeat_details: None,
+                    timeout_type: TimeoutType::StartToClose as i32,
+                },
+            )),
+            ..Default::default()
+        })),
+        failure_info: failure_info_from_state(state, retry_state),
+        ..Default::default()
+    }
+}
+
+fn terminated_failure(state: &SharedState) -> Failure {
+    Failure {
+        message: "Child Workflow execution terminated".to_owned(),
+        cause: Some(Box::new(Failure {
+            message: "Terminated".to_owned(),
+            failure_info: Some(FailureInfo::TerminatedFailureInfo(
+                failure::TerminatedFailureInfo {},
+            )),
+            ..Default::default()
+        })),
+        failure_info: failure_info_from_state(state, RetryState::NonRetryableFailure),
+        ..Default::default()
+    }
+}
+
 fn failure_info_from_state(state: &SharedState, retry_state: RetryState) -> Option<FailureInfo> {
     Some(FailureInfo::ChildWorkflowExecutionFailureInfo(
         failure::ChildWorkflowExecutionFailureInfo {
@@ -793,6 +937,7 @@ mod test {
         internal_flags::InternalFlags,
         replay::TestHistoryBuilder,
         test_help::{build_fake_sdk, canned_histories, MockPollCfg},
+        worker::workflow::OutgoingJob,
     };
     use anyhow::anyhow;
     use rstest::{fixture, rstest};
@@ -801,9 +946,16 @@ mod test {
     use temporal_sdk_core_protos::{
         coresdk::{
             child_workflow::child_workflow_result,
-            workflow_activation::resolve_child_workflow_execution_start::Status as StartStatus,
+            workflow_activation::{
+                notify_late_resolution,
+                resolve_child_workflow_execution_start::Status as StartStatus,
+                workflow_activation_job,
+            },
+            FromJsonPayloadExt,
+        },
+        temporal::api::{
+            command::v1::command, history::v1::StartChildWorkflowExecutionInitiatedEventAttributes,
         },
-        temporal::api::history::v1::StartChildWorkflowExecutionInitiatedEventAttributes,
         DEFAULT_WORKFLOW_TYPE,
     };
 
@@ -943,6 +1095,109 @@ mod test {
         worker.run().await.unwrap();
     }
 
+    #[rstest]
+    #[case::parents_namespace(None, "default")]
+    #[case::other_namespace(Some("other-ns"), "other-ns")]
+    #[tokio::test]
+    async fn child_workflow_namespace(
+        #[case] namespace: Option<&'static str>,
+        #[case] expected_namespace: &'static str,
+    ) {
+        let mut t = TestHistoryBuilder::default();
+        t.add_by_type(EventType::WorkflowExecutionStarted);
+        t.add_full_wf_task();
+        let initiated_event_id = t.add(StartChildWorkflowExecutionInitiatedEventAttributes {
+            namespace: expected_namespace.to_owned(),
+            workflow_id: "child-id-1".to_owned(),
+            workflow_type: Some("child".into()),
+            ..Default::default()
+        });
+        let started_event_id = t.add(ChildWorkflowExecutionStartedEventAttributes {
+            namespace: expected_namespace.to_owned(),
+            initiated_event_id,
+            workflow_execution: Some(WorkflowExecution {
+                workflow_id: "child-id-1".to_owned(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        t.add(ChildWorkflowExecutionCompletedEventAttributes {
+            namespace: expected_namespace.to_owned(),
+            initiated_event_id,
+            started_event_id,
+            ..Default::default()
+        });
+        t.add_full_wf_task();
+        t.add_workflow_execution_completed();
+
+        let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+        mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+            asserts
+                .then(move |wft| {
+                    assert_matches!(
+                        wft.commands[0].attributes.as_ref().unwrap(),
+                        command::Attributes::StartChildWorkflowExecutionCommandAttributes(a)
+                            if a.namespace == expected_namespace
+                    );
+                })
+                .then(|wft| {
+                    assert_matches!(
+                        wft.commands[0].command_type(),
+                        CommandType::CompleteWorkflowExecution
+                    );
+                });
+        });
+
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, move |ctx: WfContext| async move {
+            let child = ctx.child_workflow(ChildWorkflowOptions {
+                workflow_id: "child-id-1".to_string(),
+                workflow_type: "child".to_string(),
+                namespace: namespace.map(ToString::to_string),
+                ..Default::default()
+            });
+            let started = child.start(&ctx).await.into_started().unwrap();
+            started.result().await;
+            Ok(().into())
+        });
+        worker.run().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn child_workflow_user_metadata_sent_with_command() {
+        let mut t = canned_histories::single_child_workflow("child-id-1");
+        t.set_wf_input(Payload::from([Expectation::Success as u8]));
+        let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+        mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+            asserts
+                .then(|wft| {
+                    let md = wft.commands[0].user_metadata.as_ref().unwrap();
+                    let as_str = |p: &Option<Payload>| {
+                        String::from_json_payload(p.as_ref().unwrap()).unwrap()
+                    };
+                    assert_eq!(as_str(&md.summary), "Child");
+                    assert_eq!(as_str(&md.details), "A longer story");
+                })
+                .then(|_| {})
+                .then(|_| {});
+        });
+
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+            let child = ctx.child_workflow(ChildWorkflowOptions {
+                workflow_id: "child-id-1".to_string(),
+                workflow_type: "child".to_string(),
+                summary: Some("Child".to_string()),
+                details: Some("A longer story".to_string()),
+                ..Default::default()
+            });
+            let started = child.start(&ctx).await.into_started().unwrap();
+            started.result().await;
+            Ok(().into())
+        });
+        worker.run().await.unwrap();
+    }
+
     async fn cancel_before_send_wf(ctx: WfContext) -> WorkflowResult<()> {
         let workflow_id = "child-id-1";
         let child = ctx.child_workflow(ChildWorkflowOptions {
@@ -1006,6 +1261,9 @@ mod test {
                     workflow_type: "".to_string(),
                     cancelled_before_sent: false,
                     cancel_type: Default::default(),
+                    cancel_reason: DEFAULT_CANCEL_REASON.to_string(),
+                    deliver_late_resolution: false,
+                    cancel_resolved: false,
                     internal_flags: Rc::new(RefCell::new(InternalFlags::new(&Default::default()))),
                 },
             );
@@ -1016,7 +1274,81 @@ mod test {
     }
 
     #[test]
-    fn abandoned_ok_with_completions() {
+    fn cancelled_before_child_resolves_ok_with_completions() {
+        for cancel_type in [
+            ChildWorkflowCancellationType::Abandon,
+            ChildWorkflowCancellationType::TryCancel,
+            ChildWorkflowCancellationType::WaitCancellationRequested,
+        ] {
+            let mut shared = SharedState {
+                initiated_event_id: 0,
+                started_event_id: 0,
+                lang_sequence_number: 0,
+                namespace: "".to_string(),
+                workflow_id: "".to_string(),
+                run_id: "".to_string(),
+                workflow_type: "".to_string(),
+                cancelled_before_sent: false,
+                cancel_type,
+                cancel_reason: DEFAULT_CANCEL_REASON.to_string(),
+                deliver_late_resolution: false,
+                cancel_resolved: false,
+                internal_flags: Rc::new(RefCell::new(InternalFlags::new(&Default::default()))),
+            };
+            let state = Cancelled {
+                seen_cancelled_event: true,
+            };
+            let res = state.on_child_workflow_execution_completed(&mut shared, None);
+            // Can't use assert_matches b/c not Debug.
+            assert!(matches!(
+                res,
+                TransitionResult::Ok {
+                    commands,
+                    new_state: Cancelled { .. }
+                }
+                if commands.is_empty()
+            ));
+            let state = Cancelled {
+                seen_cancelled_event: true,
+            };
+            let res = state.on_child_workflow_execution_failed(&mut shared, Default::default());
+            assert!(matches!(
+                res,
+                TransitionResult::Ok {
+                    commands,
+                    new_state: Cancelled { .. }
+                }
+                if commands.is_empty()
+            ));
+            let state = Cancelled {
+                seen_cancelled_event: true,
+            };
+            let res = state.on_child_workflow_execution_timed_out(&mut shared, Default::default());
+            assert!(matches!(
+                res,
+                TransitionResult::Ok {
+                    commands,
+                    new_state: Cancelled { .. }
+                }
+                if commands.is_empty()
+            ));
+            let state = Cancelled {
+                seen_cancelled_event: true,
+            };
+            let res = state.on_child_workflow_execution_terminated(&mut shared);
+            assert!(matches!(
+                res,
+                TransitionResult::Ok {
+                    commands,
+                    new_state: Cancelled { .. }
+                }
+                if commands.is_empty()
+            ));
+        }
+    }
+
+    #[test]
+    fn wait_cancellation_completed_not_ok_with_completions() {
         let mut shared = SharedState {
             initiated_event_id: 0,
             started_event_id: 0,
@@ -1026,57 +1358,77 @@ mod test {
             run_id: "".to_string(),
             workflow_type: "".to_string(),
             cancelled_before_sent: false,
-            cancel_type: ChildWorkflowCancellationType::Abandon,
+            cancel_type: ChildWorkflowCancellationType::WaitCancellationCompleted,
+            cancel_reason: DEFAULT_CANCEL_REASON.to_string(),
+            deliver_late_resolution: false,
+            cancel_resolved: false,
             internal_flags: Rc::new(RefCell::new(InternalFlags::new(&Default::default()))),
         };
         let state = Cancelled {
             seen_cancelled_event: true,
         };
         let res = state.on_child_workflow_execution_completed(&mut shared, None);
-        // Can't use assert_matches b/c not Debug.
         assert!(matches!(
             res,
-            TransitionResult::Ok {
-                commands,
-                new_state: Cancelled { .. }
-            }
-            if commands.is_empty()
+            TransitionResult::Err(WFMachinesError::Nondeterminism(_))
         ));
-        let state = Cancelled {
-            seen_cancelled_event: true,
-        };
-        let res = state.on_child_workflow_execution_failed(&mut shared, Default::default());
-        assert!(matches!(
-            res,
-            TransitionResult::Ok {
-                commands,
-                new_state: Cancelled { .. }
-            }
-            if commands.is_empty()
-        ));
-        let state = Cancelled {
-            seen_cancelled_event: true,
-        };
-        let res = state.on_child_workflow_execution_timed_out(&mut shared, Default::default());
-        assert!(matches!(
-            res,
-            TransitionResult::Ok {
-                commands,
-                new_state: Cancelled { .. }
-            }
-            if commands.is_empty()
-        ));
-        let state = Cancelled {
-            seen_cancelled_event: true,
-        };
-        let res = state.on_child_workflow_execution_terminated(&mut shared);
-        assert!(matches!(
-            res,
-            TransitionResult::Ok {
-                commands,
-                new_state: Cancelled { .. }
+    }
+
+    #[test]
+    fn late_resolutions_delivered_when_asked_for() {
+        for deliver_late_resolution in [true, false] {
+            let mut s = ChildWorkflowMachine::from_parts(
+                Cancelled::default().into(),
+                SharedState {
+                    initiated_event_id: 0,
+                    started_event_id: 0,
+                    lang_sequence_number: 1,
+                    namespace: "".to_string(),
+                    workflow_id: "".to_string(),
+                    run_id: "".to_string(),
+                    workflow_type: "".to_string(),
+                    cancelled_before_sent: false,
+                    cancel_type: ChildWorkflowCancellationType::Abandon,
+                    cancel_reason: DEFAULT_CANCEL_REASON.to_string(),
+                    deliver_late_resolution,
+                    cancel_resolved: true,
+                    internal_flags: Rc::new(RefCell::new(InternalFlags::new(&Default::default()))),
+                },
+            );
+            let cmds = OnEventWrapper::on_event_mut(
+                &mut s,
+                ChildWorkflowMachineEvents::ChildWorkflowExecutionFailed(Default::default()),
+            )
+            .unwrap();
+            let resps: Vec<_> = cmds
+                .into_iter()
+                .flat_map(|c| s.adapt_response(c, None).unwrap())
+                .collect();
+            if deliver_late_resolution {
+                assert_matches!(
+                    resps.as_slice(),
+                    [MachineResponse::PushWFJob(OutgoingJob {
+                        variant: workflow_activation_job::Variant::NotifyLateResolution(
+                            NotifyLateResolution {
+                                resolution: Some(
+                                    notify_late_resolution::Resolution::ChildWorkflowExecution(
+                                        ResolveChildWorkflowExecution {
+                                            seq: 1,
+                                            result: Some(ChildWorkflowResult {
+                                                status: Some(ChildWorkflowStatus::Failed(_)),
+                                            }),
+                                        }
+                                    )
+                                ),
+                            }
+                        ),
+                        ..
+                    })]
+                );
+            } else {
+                assert!(resps.is_empty());
             }
-            if commands.is_empty()
-        ));
+            assert!(matches!(s.state(), ChildWorkflowMachineState::Cancelled(_)));
+        }
     }
 }
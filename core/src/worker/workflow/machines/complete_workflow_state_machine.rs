@@ -98,6 +98,7 @@ impl Created {
         let cmd = Command {
             command_type: CommandType::CompleteWorkflowExecution as i32,
             attributes: Some(self.attribs.into()),
+            user_metadata: None,
         };
         TransitionResult::commands(vec![CompleteWFCommand::AddCommand(cmd)])
     }
@@ -65,6 +65,7 @@ impl Created {
         let cmd = ProtoCommand {
             command_type: CommandType::FailWorkflowExecution as i32,
             attributes: Some(self.attribs.into()),
+            user_metadata: None,
         };
         TransitionResult::commands(vec![FailWFCommand::AddCommand(cmd)])
     }
@@ -20,7 +20,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 use temporal_sdk_core_protos::{
-    constants::LOCAL_ACTIVITY_MARKER_NAME,
+    constants::{LOCAL_ACTIVITY_MARKER_NAME, LOCAL_ACTIVITY_MARKER_PROGRESS_KEY},
     coresdk::{
         activity_result::{
             ActivityResolution, Cancellation, DoBackoff, Failure as ActFail, Success,
@@ -32,6 +32,7 @@ use temporal_sdk_core_protos::{
     },
     temporal::api::{
         command::v1::{command, RecordMarkerCommandAttributes},
+        common::v1::{Payload, Payloads},
         enums::v1::{CommandType, EventType, RetryState},
         failure::v1::{failure::FailureInfo, Failure},
         history::v1::HistoryEvent,
@@ -113,6 +114,8 @@ pub(super) struct ResolveDat {
     pub(super) attempt: u32,
     pub(super) backoff: Option<prost_types::Duration>,
     pub(super) original_schedule_time: Option<SystemTime>,
+    /// The last progress lang reported for the attempt which produced this result
+    pub(super) progress: Vec<Payload>,
 }
 
 impl From<CompleteLocalActivityData> for ResolveDat {
@@ -141,6 +144,7 @@ impl From<CompleteLocalActivityData> for ResolveDat {
             attempt: d.marker_dat.attempt,
             backoff: d.marker_dat.backoff,
             original_schedule_time: d.marker_dat.original_schedule_time.try_into_or_none(),
+            progress: vec![],
         }
     }
 }
@@ -265,6 +269,7 @@ impl LocalActivityMachine {
         attempt: u32,
         backoff: Option<prost_types::Duration>,
         original_schedule_time: Option<SystemTime>,
+        progress: Vec<Payload>,
     ) -> Result<Vec<MachineResponse>, WFMachinesError> {
         self._try_resolve(
             ResolveDat {
@@ -273,6 +278,7 @@ impl LocalActivityMachine {
                 attempt,
                 backoff,
                 original_schedule_time,
+                progress,
             },
             false,
         )
@@ -335,6 +341,7 @@ impl SharedState {
             attempt: self.attrs.attempt,
             backoff: None,
             original_schedule_time: self.attrs.original_schedule_time,
+            progress: vec![],
         }
     }
 }
@@ -651,6 +658,7 @@ impl WFMachinesAdapter for LocalActivityMachine {
                 attempt,
                 backoff,
                 original_schedule_time,
+                progress,
             }) => {
                 let mut maybe_ok_result = None;
                 let mut maybe_failure = None;
@@ -677,6 +685,7 @@ impl WFMachinesAdapter for LocalActivityMachine {
                                 attempt: attempt + 1,
                                 backoff_duration: Some(b.clone()),
                                 original_schedule_time: original_schedule_time.map(Into::into),
+                                last_failure: maybe_failure.clone(),
                             }
                             .into(),
                         ),
@@ -749,12 +758,14 @@ impl WFMachinesAdapter for LocalActivityMachine {
                     MachineResponse::UpdateWFTime(complete_time),
                 ];
 
-                // Cancel-resolves of abandoned activities must be explicitly dropped from tracking
-                // to avoid unnecessary WFT heartbeating.
+                // Cancel-resolves of abandoned or try-cancelled activities must be explicitly
+                // dropped from tracking, since like remote activities the workflow doesn't wait
+                // for them to actually finish. Otherwise, we'd needlessly WFT heartbeat until they
+                // do.
                 if will_not_run_again
                     && matches!(
                         self.shared_state.attrs.cancellation_type,
-                        ActivityCancellationType::Abandon
+                        ActivityCancellationType::Abandon | ActivityCancellationType::TryCancel
                     )
                 {
                     responses.push(MachineResponse::AbandonLocalActivity(
@@ -763,20 +774,27 @@ impl WFMachinesAdapter for LocalActivityMachine {
                 }
 
                 if record_marker {
+                    let mut details = build_local_activity_marker_details(
+                        LocalActivityMarkerData {
+                            seq: self.shared_state.attrs.seq,
+                            attempt,
+                            activity_id: self.shared_state.attrs.activity_id.clone(),
+                            activity_type: self.shared_state.attrs.activity_type.clone(),
+                            complete_time: complete_time.map(Into::into),
+                            backoff,
+                            original_schedule_time: original_schedule_time.map(Into::into),
+                        },
+                        maybe_ok_result,
+                    );
+                    if !progress.is_empty() {
+                        details.insert(
+                            LOCAL_ACTIVITY_MARKER_PROGRESS_KEY.to_string(),
+                            Payloads { payloads: progress },
+                        );
+                    }
                     let marker_data = RecordMarkerCommandAttributes {
                         marker_name: LOCAL_ACTIVITY_MARKER_NAME.to_string(),
-                        details: build_local_activity_marker_details(
-                            LocalActivityMarkerData {
-                                seq: self.shared_state.attrs.seq,
-                                attempt,
-                                activity_id: self.shared_state.attrs.activity_id.clone(),
-                                activity_type: self.shared_state.attrs.activity_type.clone(),
-                                complete_time: complete_time.map(Into::into),
-                                backoff,
-                                original_schedule_time: original_schedule_time.map(Into::into),
-                            },
-                            maybe_ok_result,
-                        ),
+                        details,
                         header: None,
                         failure: maybe_failure,
                     };
@@ -1359,9 +1377,8 @@ mod tests {
         t.add_timer_fired(timer_started_event_id, "1".to_string());
         t.add_full_wf_task();
         // This extra workflow task serves to prevent looking ahead and pre-resolving during
-        // wait-cancel.
-        // TODO: including this on non wait-cancel seems to cause double-send of
-        //   marker recorded cmd
+        // wait-cancel. Non-wait cancels record their marker in the very next WFT completion, so
+        // their history can't have one here.
         if cancel_type == ActivityCancellationType::WaitCancellationCompleted {
             t.add_full_wf_task();
         }
@@ -1460,4 +1477,66 @@ mod tests {
         });
         worker.run().await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn no_wait_cancels_dont_wait_for_activity(
+        #[values(ActivityCancellationType::TryCancel, ActivityCancellationType::Abandon)]
+        cancel_type: ActivityCancellationType,
+    ) {
+        let mut t = TestHistoryBuilder::default();
+        t.add_wfe_started_with_wft_timeout(Duration::from_millis(200));
+        t.add_full_wf_task();
+        let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+        t.add_timer_fired(timer_started_event_id, "1".to_string());
+        t.add_full_wf_task();
+        t.add_local_activity_cancel_marker(1, "1");
+        let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+        t.add_timer_fired(timer_started_event_id, "2".to_string());
+        t.add_full_wf_task();
+        t.add_workflow_execution_completed();
+
+        let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+        mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+            asserts
+                .then(|wft| {
+                    assert_eq!(wft.commands.len(), 1);
+                    assert_eq!(wft.commands[0].command_type(), CommandType::StartTimer);
+                })
+                .then(|wft| {
+                    // The workflow task completes without waiting (and so needing to heartbeat)
+                    // for the activity, which ignores the cancel
+                    assert!(!wft.force_create_new_workflow_task);
+                    assert_eq!(wft.commands.len(), 2);
+                    assert_eq!(wft.commands[0].command_type(), CommandType::RecordMarker);
+                    assert_eq!(wft.commands[1].command_type(), CommandType::StartTimer);
+                })
+                .then(|wft| {
+                    assert_eq!(
+                        wft.commands[0].command_type(),
+                        CommandType::CompleteWorkflowExecution
+                    );
+                });
+        });
+
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, move |ctx: WfContext| async move {
+            let la = ctx.local_activity(LocalActivityOptions {
+                cancel_type,
+                input: ().as_json_payload().unwrap(),
+                activity_type: DEFAULT_ACTIVITY_TYPE.to_string(),
+                ..Default::default()
+            });
+            ctx.timer(Duration::from_secs(1)).await;
+            la.cancel(&ctx);
+            assert!(la.await.cancelled());
+            ctx.timer(Duration::from_secs(1)).await;
+            Ok(().into())
+        });
+        worker.register_activity(DEFAULT_ACTIVITY_TYPE, |_: ActContext, _: ()| async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        });
+        worker.run().await.unwrap();
+    }
 }
@@ -32,6 +32,7 @@ pub(super) fn modify_workflow_properties(
     let cmd = Command {
         command_type: CommandType::ModifyWorkflowProperties as i32,
         attributes: Some(lang_cmd.into()),
+        user_metadata: None,
     };
     NewMachineWithCommand {
         command: cmd,
@@ -121,9 +122,16 @@ mod tests {
         replay::TestHistoryBuilder,
         test_help::{build_fake_sdk, MockPollCfg},
     };
-    use temporal_sdk::WfContext;
+    use std::collections::HashMap;
+    use temporal_sdk::{WfContext, WfExitValue};
     use temporal_sdk_core_protos::{
-        temporal::api::{command::v1::command, common::v1::Payload},
+        coresdk::workflow_commands::ContinueAsNewWorkflowExecution,
+        default_wes_attribs,
+        temporal::api::{
+            command::v1::command,
+            common::v1::{Memo, Payload},
+            history::v1::WorkflowExecutionStartedEventAttributes,
+        },
         DEFAULT_WORKFLOW_TYPE,
     };
 
@@ -180,4 +188,66 @@ mod tests {
         });
         worker.run().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn upserted_memo_is_current_memo() {
+        let payload = |b: u8| Payload {
+            data: vec![b],
+            ..Default::default()
+        };
+        let mut t = TestHistoryBuilder::default();
+        t.add(WorkflowExecutionStartedEventAttributes {
+            memo: Some(Memo {
+                fields: HashMap::from([
+                    ("kept".to_string(), payload(1)),
+                    ("removed".to_string(), payload(2)),
+                ]),
+            }),
+            ..default_wes_attribs()
+        });
+        t.add_full_wf_task();
+        t.add_continued_as_new();
+
+        let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+        mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+            asserts.then(move |wft| {
+                assert_matches!(
+                    wft.commands.as_slice(),
+                    [_, Command {
+                        attributes: Some(
+                            command::Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(
+                                can
+                            )
+                        ),
+                        ..
+                    }] => {
+                        let fields = &can.memo.as_ref().unwrap().fields;
+                        assert_eq!(fields.len(), 2);
+                        assert_eq!(fields.get("kept"), Some(&payload(1)));
+                        assert_eq!(fields.get("added"), Some(&payload(3)));
+                    }
+                );
+            });
+        });
+
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, move |ctx: WfContext| async move {
+            assert_eq!(ctx.memo().len(), 2);
+            ctx.upsert_memo([
+                ("added".to_string(), payload(3)),
+                ("removed".to_string(), Payload::default()),
+            ]);
+            assert_eq!(
+                ctx.memo(),
+                HashMap::from([
+                    ("kept".to_string(), payload(1)),
+                    ("added".to_string(), payload(3)),
+                ])
+            );
+            Ok(WfExitValue::<()>::continue_as_new(
+                ContinueAsNewWorkflowExecution::default(),
+            ))
+        });
+        worker.run().await.unwrap();
+    }
 }
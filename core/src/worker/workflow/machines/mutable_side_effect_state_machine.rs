@@ -0,0 +1,256 @@
+use super::{workflow_machines::MachineResponse, NewMachineWithCommand};
+use crate::{
+    protosext::HistoryEventExt,
+    worker::workflow::{
+        machines::{Cancellable, EventInfo, HistEventData, WFMachinesAdapter},
+        WFMachinesError,
+    },
+};
+use anyhow::Context;
+use rustfsm::{fsm, StateMachine, TransitionResult};
+use temporal_sdk_core_protos::{
+    constants::MUTABLE_SIDE_EFFECT_MARKER_NAME,
+    coresdk::{
+        common::build_mutable_side_effect_marker_details,
+        external_data::MutableSideEffectMarkerData,
+    },
+    temporal::api::{
+        command::v1::{Command, RecordMarkerCommandAttributes},
+        common::v1::Payload,
+        enums::v1::CommandType,
+        history::v1::HistoryEvent,
+    },
+};
+
+fsm! {
+    pub(super) name MutableSideEffectMachine;
+    command MutableSideEffectMachineCommand;
+    error WFMachinesError;
+    shared_state SharedState;
+
+    Created --(CommandRecordMarker) --> MarkerCommandIssued;
+    // Once the marker is seen in history, all that's left is to check it's the one we expected
+    MarkerCommandIssued --(MarkerRecorded(MutableSideEffectMarkerData), shared on_marker_recorded)
+      --> MarkerRecorded;
+}
+
+/// Instantiates a MutableSideEffectMachine which records a marker for a new value of the mutable
+/// side effect `metadata` describes, and packs it together with the command to be sent to server.
+pub(super) fn record_mutable_side_effect(
+    metadata: MutableSideEffectMarkerData,
+    value: Option<Payload>,
+) -> Result<NewMachineWithCommand, WFMachinesError> {
+    let command = Command {
+        command_type: CommandType::RecordMarker as i32,
+        attributes: Some(
+            RecordMarkerCommandAttributes {
+                marker_name: MUTABLE_SIDE_EFFECT_MARKER_NAME.to_string(),
+                details: build_mutable_side_effect_marker_details(metadata.clone(), value)
+                    .context("While encoding mutable side effect marker details")?,
+                header: None,
+                failure: None,
+            }
+            .into(),
+        ),
+        user_metadata: None,
+    };
+    let machine = MutableSideEffectMachine::from_parts(
+        Created {}.into(),
+        SharedState {
+            id: metadata.id,
+            call_number: metadata.call_number,
+        },
+    );
+    Ok(NewMachineWithCommand {
+        command,
+        machine: machine.into(),
+    })
+}
+
+#[derive(Clone)]
+pub(super) struct SharedState {
+    id: String,
+    call_number: u32,
+}
+
+#[derive(Debug, derive_more::Display)]
+pub(super) enum MutableSideEffectMachineCommand {}
+
+#[derive(Debug, Default, Clone, derive_more::Display)]
+pub(super) struct Created {}
+
+#[derive(Debug, Default, Clone, derive_more::Display)]
+pub(super) struct MarkerCommandIssued {}
+
+impl MarkerCommandIssued {
+    pub(super) fn on_marker_recorded(
+        self,
+        dat: &mut SharedState,
+        recorded: MutableSideEffectMarkerData,
+    ) -> MutableSideEffectMachineTransition<MarkerRecorded> {
+        if recorded.id != dat.id || recorded.call_number != dat.call_number {
+            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Mutable side effect marker for id {} call {} does not match expected id {} \
+                 call {}",
+                recorded.id, recorded.call_number, dat.id, dat.call_number
+            )));
+        }
+        TransitionResult::default()
+    }
+}
+
+#[derive(Debug, Default, Clone, derive_more::Display)]
+pub(super) struct MarkerRecorded {}
+
+impl From<Created> for MarkerCommandIssued {
+    fn from(_: Created) -> Self {
+        Self {}
+    }
+}
+
+impl WFMachinesAdapter for MutableSideEffectMachine {
+    fn adapt_response(
+        &self,
+        _my_command: Self::Command,
+        _event_info: Option<EventInfo>,
+    ) -> Result<Vec<MachineResponse>, Self::Error> {
+        Err(Self::Error::Nondeterminism(
+            "MutableSideEffect does not use state machine commands".to_string(),
+        ))
+    }
+
+    fn matches_event(&self, event: &HistoryEvent) -> bool {
+        event.get_mutable_side_effect_marker_details().is_some()
+    }
+}
+
+impl Cancellable for MutableSideEffectMachine {}
+
+impl TryFrom<HistEventData> for MutableSideEffectMachineEvents {
+    type Error = WFMachinesError;
+
+    fn try_from(e: HistEventData) -> Result<Self, Self::Error> {
+        let e = e.event;
+        match e.get_mutable_side_effect_marker_details() {
+            Some((metadata, _)) => Ok(Self::MarkerRecorded(metadata)),
+            _ => Err(Self::Error::Nondeterminism(format!(
+                "MutableSideEffectMachine does not handle {e}"
+            ))),
+        }
+    }
+}
+
+impl TryFrom<CommandType> for MutableSideEffectMachineEvents {
+    type Error = WFMachinesError;
+
+    fn try_from(c: CommandType) -> Result<Self, Self::Error> {
+        match c {
+            CommandType::RecordMarker => Ok(Self::CommandRecordMarker),
+            _ => Err(Self::Error::Nondeterminism(format!(
+                "MutableSideEffectMachine does not handle command type {c:?}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        replay::TestHistoryBuilder,
+        test_help::{build_fake_sdk, MockPollCfg, ResponseType},
+    };
+    use rstest::rstest;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use temporal_sdk::WfContext;
+    use temporal_sdk_core_protos::{
+        coresdk::{AsJsonPayloadExt, FromJsonPayloadExt},
+        temporal::api::{
+            enums::v1::{CommandType, EventType},
+            history::v1::TimerFiredEventAttributes,
+        },
+        DEFAULT_WORKFLOW_TYPE,
+    };
+
+    #[rstest]
+    #[tokio::test]
+    async fn markers_only_recorded_when_value_changes(#[values(false, true)] replay: bool) {
+        let mut t = TestHistoryBuilder::default();
+        t.add_by_type(EventType::WorkflowExecutionStarted);
+        t.add_full_wf_task();
+        t.add_mutable_side_effect_marker("cfg", 1, "a", "a".as_json_payload().unwrap());
+        let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+        t.add(TimerFiredEventAttributes {
+            started_event_id: timer_started_event_id,
+            timer_id: "1".to_owned(),
+        });
+        t.add_full_wf_task();
+        // The second call didn't change the value, so only the third recorded a marker
+        t.add_mutable_side_effect_marker("cfg", 3, "b", "b".as_json_payload().unwrap());
+        t.add_workflow_execution_completed();
+
+        let mut mock_cfg = if replay {
+            MockPollCfg::from_resps(t, [ResponseType::AllHistory])
+        } else {
+            MockPollCfg::from_hist_builder(t)
+        };
+        if !replay {
+            mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+                asserts
+                    .then(|wft| {
+                        assert_eq!(
+                            wft.commands
+                                .iter()
+                                .map(|c| c.command_type())
+                                .collect::<Vec<_>>(),
+                            vec![CommandType::RecordMarker, CommandType::StartTimer]
+                        );
+                    })
+                    .then(|wft| {
+                        assert_eq!(
+                            wft.commands
+                                .iter()
+                                .map(|c| c.command_type())
+                                .collect::<Vec<_>>(),
+                            vec![
+                                CommandType::RecordMarker,
+                                CommandType::CompleteWorkflowExecution
+                            ]
+                        );
+                    });
+            });
+        }
+
+        // When replaying, the workflow sees different values now, which must be ignored
+        let offered = if replay {
+            ["x", "y", "z"]
+        } else {
+            ["a", "a", "b"]
+        };
+        let resolved = Arc::new(Mutex::new(vec![]));
+        let resolved_clone = resolved.clone();
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, move |ctx: WfContext| {
+            let resolved = resolved_clone.clone();
+            async move {
+                let mse = |v: &str| {
+                    ctx.mutable_side_effect("cfg", v.as_json_payload().unwrap(), v.to_string())
+                };
+                let first = mse(offered[0]).await;
+                ctx.timer(Duration::from_secs(1)).await;
+                let second = mse(offered[1]).await;
+                let third = mse(offered[2]).await;
+                resolved.lock().unwrap().extend(
+                    [first, second, third]
+                        .iter()
+                        .map(|p| String::from_json_payload(p).unwrap()),
+                );
+                Ok(().into())
+            }
+        });
+        worker.run().await.unwrap();
+        assert_eq!(*resolved.lock().unwrap(), vec!["a", "a", "b"]);
+    }
+}
@@ -0,0 +1,528 @@
+use super::{
+    workflow_machines::MachineResponse, Cancellable, EventInfo, NewMachineWithCommand,
+    OnEventWrapper, WFMachinesAdapter, WFMachinesError,
+};
+use crate::worker::workflow::machines::HistEventData;
+use rustfsm::{fsm, MachineError, StateMachine, TransitionResult};
+use std::convert::TryFrom;
+use temporal_sdk_core_protos::{
+    coresdk::{
+        nexus::{nexus_operation_result, NexusOperationResult},
+        workflow_activation::{
+            resolve_nexus_operation_start, ResolveNexusOperation, ResolveNexusOperationStart,
+        },
+        workflow_commands::ScheduleNexusOperation,
+    },
+    temporal::api::{
+        command::v1::{
+            command, Command, RequestCancelNexusOperationCommandAttributes,
+            ScheduleNexusOperationCommandAttributes,
+        },
+        common::v1::Payload,
+        enums::v1::{CommandType, EventType},
+        failure::v1::{self as failure, failure::FailureInfo, Failure},
+        history::v1::{
+            history_event, HistoryEvent, NexusOperationCanceledEventAttributes,
+            NexusOperationCompletedEventAttributes, NexusOperationFailedEventAttributes,
+            NexusOperationStartedEventAttributes, NexusOperationTimedOutEventAttributes,
+        },
+    },
+};
+
+fsm! {
+    pub(super) name NexusOperationMachine;
+    command NexusOperationCommand;
+    error WFMachinesError;
+    shared_state SharedState;
+
+    Created --(Schedule, on_schedule) --> ScheduleCommandCreated;
+
+    ScheduleCommandCreated --(CommandScheduleNexusOperation) --> ScheduleCommandCreated;
+    ScheduleCommandCreated --(NexusOperationScheduled(NexusOpScheduledData),
+        shared on_scheduled) --> ScheduledEventRecorded;
+    ScheduleCommandCreated --(Cancel, shared on_cancelled) --> Cancelled;
+
+    ScheduledEventRecorded --(NexusOperationStarted(NexusOperationStartedEventAttributes),
+        on_started) --> Started;
+    // Operations which finish without starting asynchronously have no started event
+    ScheduledEventRecorded --(NexusOperationCompleted(NexusOperationCompletedEventAttributes),
+        on_completed) --> Completed;
+    ScheduledEventRecorded --(NexusOperationFailed(NexusOperationFailedEventAttributes),
+        on_failed) --> Failed;
+    ScheduledEventRecorded --(NexusOperationCanceled(NexusOperationCanceledEventAttributes),
+        on_canceled) --> Cancelled;
+    ScheduledEventRecorded --(NexusOperationTimedOut(NexusOperationTimedOutEventAttributes),
+        on_timed_out) --> TimedOut;
+    // A cancel request doesn't resolve the operation, it keeps waiting for however it ends
+    ScheduledEventRecorded --(Cancel, shared on_issue_cancel) --> ScheduledEventRecorded;
+    ScheduledEventRecorded --(CommandRequestCancelNexusOperation) --> ScheduledEventRecorded;
+    ScheduledEventRecorded --(NexusOperationCancelRequested) --> ScheduledEventRecorded;
+
+    Started --(NexusOperationCompleted(NexusOperationCompletedEventAttributes),
+        on_completed) --> Completed;
+    Started --(NexusOperationFailed(NexusOperationFailedEventAttributes),
+        on_failed) --> Failed;
+    Started --(NexusOperationCanceled(NexusOperationCanceledEventAttributes),
+        on_canceled) --> Cancelled;
+    Started --(NexusOperationTimedOut(NexusOperationTimedOutEventAttributes),
+        on_timed_out) --> TimedOut;
+    Started --(Cancel, shared on_issue_cancel) --> Started;
+    Started --(CommandRequestCancelNexusOperation) --> Started;
+    Started --(NexusOperationCancelRequested) --> Started;
+
+    // Ignore any spurious cancellations after resolution
+    Completed --(Cancel) --> Completed;
+    Failed --(Cancel) --> Failed;
+    Cancelled --(Cancel) --> Cancelled;
+    TimedOut --(Cancel) --> TimedOut;
+}
+
+#[derive(Debug, derive_more::Display)]
+pub(super) enum NexusOperationCommand {
+    #[display(fmt = "Start")]
+    Start { operation_id: String },
+    #[display(fmt = "StartSync")]
+    StartSync,
+    #[display(fmt = "CancelBeforeSent")]
+    CancelBeforeSent,
+    #[display(fmt = "Complete")]
+    Complete(Option<Payload>),
+    #[display(fmt = "Fail")]
+    Fail(Option<Failure>),
+    #[display(fmt = "Cancel")]
+    Cancel(Option<Failure>),
+    #[display(fmt = "TimedOut")]
+    TimedOut(Option<Failure>),
+    #[display(fmt = "IssueCancel")]
+    IssueCancel,
+}
+
+pub(super) struct NexusOpScheduledData {
+    event_id: i64,
+    endpoint: String,
+    service: String,
+    operation: String,
+}
+
+#[derive(Clone)]
+pub(super) struct SharedState {
+    lang_seq_num: u32,
+    endpoint: String,
+    service: String,
+    operation: String,
+    scheduled_event_id: i64,
+    cancelled_before_sent: bool,
+    /// Set once a cancel request command has been issued, so repeated cancels don't issue more
+    cancel_requested: bool,
+}
+
+impl NexusOperationMachine {
+    /// Create a new nexus operation and immediately schedule it.
+    pub(super) fn new_scheduled(attribs: ScheduleNexusOperation) -> NewMachineWithCommand {
+        let mut s = Self::from_parts(
+            Created {}.into(),
+            SharedState {
+                lang_seq_num: attribs.seq,
+                endpoint: attribs.endpoint.clone(),
+                service: attribs.service.clone(),
+                operation: attribs.operation.clone(),
+                scheduled_event_id: 0,
+                cancelled_before_sent: false,
+                cancel_requested: false,
+            },
+        );
+        OnEventWrapper::on_event_mut(&mut s, NexusOperationMachineEvents::Schedule)
+            .expect("Scheduling nexus operations doesn't fail");
+        let command = Command {
+            command_type: CommandType::ScheduleNexusOperation as i32,
+            attributes: Some(
+                ScheduleNexusOperationCommandAttributes {
+                    endpoint: attribs.endpoint,
+                    service: attribs.service,
+                    operation: attribs.operation,
+                    input: attribs.input,
+                    schedule_to_close_timeout: attribs.schedule_to_close_timeout,
+                    nexus_header: attribs.nexus_header,
+                }
+                .into(),
+            ),
+            user_metadata: None,
+        };
+        NewMachineWithCommand {
+            command,
+            machine: s.into(),
+        }
+    }
+
+    fn resolve_start(&self, status: resolve_nexus_operation_start::Status) -> MachineResponse {
+        ResolveNexusOperationStart {
+            seq: self.shared_state.lang_seq_num,
+            status: Some(status),
+        }
+        .into()
+    }
+
+    fn resolve(&self, status: nexus_operation_result::Status) -> MachineResponse {
+        ResolveNexusOperation {
+            seq: self.shared_state.lang_seq_num,
+            result: Some(NexusOperationResult {
+                status: Some(status),
+            }),
+        }
+        .into()
+    }
+
+    fn cancel_before_sent_failure(&self) -> Failure {
+        Failure {
+            message: "Nexus Operation cancelled before scheduled".to_string(),
+            cause: Some(Box::new(Failure {
+                failure_info: Some(FailureInfo::CanceledFailureInfo(
+                    failure::CanceledFailureInfo::default(),
+                )),
+                ..Default::default()
+            })),
+            failure_info: Some(FailureInfo::NexusOperationExecutionFailureInfo(
+                failure::NexusOperationFailureInfo {
+                    scheduled_event_id: 0,
+                    endpoint: self.shared_state.endpoint.clone(),
+                    service: self.shared_state.service.clone(),
+                    operation: self.shared_state.operation.clone(),
+                    operation_id: "".to_string(),
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    fn cancel_request_command(&self) -> Command {
+        Command {
+            command_type: CommandType::RequestCancelNexusOperation as i32,
+            attributes: Some(
+                command::Attributes::RequestCancelNexusOperationCommandAttributes(
+                    RequestCancelNexusOperationCommandAttributes {
+                        scheduled_event_id: self.shared_state.scheduled_event_id,
+                    },
+                ),
+            ),
+            user_metadata: None,
+        }
+    }
+}
+
+impl TryFrom<HistEventData> for NexusOperationMachineEvents {
+    type Error = WFMachinesError;
+
+    fn try_from(e: HistEventData) -> Result<Self, Self::Error> {
+        let e = e.event;
+        Ok(match (e.event_type(), e.attributes) {
+            (
+                EventType::NexusOperationScheduled,
+                Some(history_event::Attributes::NexusOperationScheduledEventAttributes(sa)),
+            ) => Self::NexusOperationScheduled(NexusOpScheduledData {
+                event_id: e.event_id,
+                endpoint: sa.endpoint,
+                service: sa.service,
+                operation: sa.operation,
+            }),
+            (
+                EventType::NexusOperationStarted,
+                Some(history_event::Attributes::NexusOperationStartedEventAttributes(sa)),
+            ) => Self::NexusOperationStarted(sa),
+            (
+                EventType::NexusOperationCompleted,
+                Some(history_event::Attributes::NexusOperationCompletedEventAttributes(ca)),
+            ) => Self::NexusOperationCompleted(ca),
+            (
+                EventType::NexusOperationFailed,
+                Some(history_event::Attributes::NexusOperationFailedEventAttributes(fa)),
+            ) => Self::NexusOperationFailed(fa),
+            (
+                EventType::NexusOperationCanceled,
+                Some(history_event::Attributes::NexusOperationCanceledEventAttributes(ca)),
+            ) => Self::NexusOperationCanceled(ca),
+            (
+                EventType::NexusOperationTimedOut,
+                Some(history_event::Attributes::NexusOperationTimedOutEventAttributes(toa)),
+            ) => Self::NexusOperationTimedOut(toa),
+            (EventType::NexusOperationCancelRequested, _) => Self::NexusOperationCancelRequested,
+            (et, _) => {
+                return Err(WFMachinesError::Nondeterminism(format!(
+                    "Nexus operation machine does not handle this event or its attributes were \
+                     unset: {et:?} (id: {})",
+                    e.event_id
+                )))
+            }
+        })
+    }
+}
+
+impl TryFrom<CommandType> for NexusOperationMachineEvents {
+    type Error = ();
+
+    fn try_from(c: CommandType) -> Result<Self, Self::Error> {
+        Ok(match c {
+            CommandType::ScheduleNexusOperation => Self::CommandScheduleNexusOperation,
+            CommandType::RequestCancelNexusOperation => Self::CommandRequestCancelNexusOperation,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl WFMachinesAdapter for NexusOperationMachine {
+    fn adapt_response(
+        &self,
+        my_command: Self::Command,
+        _event_info: Option<EventInfo>,
+    ) -> Result<Vec<MachineResponse>, WFMachinesError> {
+        Ok(match my_command {
+            NexusOperationCommand::Start { operation_id } => {
+                vec![
+                    self.resolve_start(resolve_nexus_operation_start::Status::OperationId(
+                        operation_id,
+                    )),
+                ]
+            }
+            NexusOperationCommand::StartSync => {
+                vec![self.resolve_start(resolve_nexus_operation_start::Status::StartedSync(true))]
+            }
+            NexusOperationCommand::CancelBeforeSent => {
+                vec![self.resolve_start(
+                    resolve_nexus_operation_start::Status::CancelledBeforeStart(
+                        self.cancel_before_sent_failure(),
+                    ),
+                )]
+            }
+            NexusOperationCommand::Complete(result) => {
+                vec![self.resolve(nexus_operation_result::Status::Completed(
+                    result.unwrap_or_default(),
+                ))]
+            }
+            NexusOperationCommand::Fail(failure) => {
+                vec![self.resolve(nexus_operation_result::Status::Failed(
+                    failure.unwrap_or_default(),
+                ))]
+            }
+            NexusOperationCommand::Cancel(failure) => {
+                vec![self.resolve(nexus_operation_result::Status::Cancelled(
+                    failure.unwrap_or_default(),
+                ))]
+            }
+            NexusOperationCommand::TimedOut(failure) => {
+                vec![self.resolve(nexus_operation_result::Status::TimedOut(
+                    failure.unwrap_or_default(),
+                ))]
+            }
+            NexusOperationCommand::IssueCancel => {
+                vec![MachineResponse::IssueNewCommand(
+                    self.cancel_request_command(),
+                )]
+            }
+        })
+    }
+
+    fn matches_event(&self, event: &HistoryEvent) -> bool {
+        matches!(
+            event.event_type(),
+            EventType::NexusOperationScheduled
+                | EventType::NexusOperationStarted
+                | EventType::NexusOperationCompleted
+                | EventType::NexusOperationFailed
+                | EventType::NexusOperationCanceled
+                | EventType::NexusOperationTimedOut
+                | EventType::NexusOperationCancelRequested
+        )
+    }
+}
+
+impl Cancellable for NexusOperationMachine {
+    fn cancel(&mut self) -> Result<Vec<MachineResponse>, MachineError<Self::Error>> {
+        let cmds = OnEventWrapper::on_event_mut(self, NexusOperationMachineEvents::Cancel)?;
+        let mut resps = vec![];
+        for c in cmds {
+            resps.extend(
+                self.adapt_response(c, None)
+                    .map_err(MachineError::Underlying)?,
+            );
+        }
+        Ok(resps)
+    }
+
+    fn was_cancelled_before_sent_to_server(&self) -> bool {
+        self.shared_state.cancelled_before_sent
+    }
+}
+
+#[derive(Default, Clone)]
+pub(super) struct Created {}
+
+impl Created {
+    pub(super) fn on_schedule(self) -> NexusOperationMachineTransition<ScheduleCommandCreated> {
+        TransitionResult::default()
+    }
+}
+
+#[derive(Default, Clone)]
+pub(super) struct ScheduleCommandCreated {}
+
+impl ScheduleCommandCreated {
+    pub(super) fn on_scheduled(
+        self,
+        state: &mut SharedState,
+        event_dat: NexusOpScheduledData,
+    ) -> NexusOperationMachineTransition<ScheduledEventRecorded> {
+        if event_dat.endpoint != state.endpoint {
+            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Nexus operation endpoint of scheduled event '{}' does not match endpoint of \
+                 command '{}'",
+                event_dat.endpoint, state.endpoint
+            )));
+        }
+        if event_dat.service != state.service {
+            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Nexus operation service of scheduled event '{}' does not match service of \
+                 command '{}'",
+                event_dat.service, state.service
+            )));
+        }
+        if event_dat.operation != state.operation {
+            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                "Nexus operation of scheduled event '{}' does not match operation of command \
+                 '{}'",
+                event_dat.operation, state.operation
+            )));
+        }
+        state.scheduled_event_id = event_dat.event_id;
+        NexusOperationMachineTransition::default()
+    }
+
+    pub(super) fn on_cancelled(
+        self,
+        state: &mut SharedState,
+    ) -> NexusOperationMachineTransition<Cancelled> {
+        state.cancelled_before_sent = true;
+        NexusOperationMachineTransition::commands([NexusOperationCommand::CancelBeforeSent])
+    }
+}
+
+#[derive(Default, Clone)]
+pub(super) struct ScheduledEventRecorded {}
+
+impl ScheduledEventRecorded {
+    pub(super) fn on_started(
+        self,
+        sa: NexusOperationStartedEventAttributes,
+    ) -> NexusOperationMachineTransition<Started> {
+        NexusOperationMachineTransition::commands([NexusOperationCommand::Start {
+            operation_id: sa.operation_id,
+        }])
+    }
+
+    pub(super) fn on_completed(
+        self,
+        ca: NexusOperationCompletedEventAttributes,
+    ) -> NexusOperationMachineTransition<Completed> {
+        NexusOperationMachineTransition::commands([
+            NexusOperationCommand::StartSync,
+            NexusOperationCommand::Complete(ca.result),
+        ])
+    }
+
+    pub(super) fn on_failed(
+        self,
+        fa: NexusOperationFailedEventAttributes,
+    ) -> NexusOperationMachineTransition<Failed> {
+        NexusOperationMachineTransition::commands([
+            NexusOperationCommand::StartSync,
+            NexusOperationCommand::Fail(fa.failure),
+        ])
+    }
+
+    pub(super) fn on_canceled(
+        self,
+        ca: NexusOperationCanceledEventAttributes,
+    ) -> NexusOperationMachineTransition<Cancelled> {
+        NexusOperationMachineTransition::commands([
+            NexusOperationCommand::StartSync,
+            NexusOperationCommand::Cancel(ca.failure),
+        ])
+    }
+
+    pub(super) fn on_timed_out(
+        self,
+        toa: NexusOperationTimedOutEventAttributes,
+    ) -> NexusOperationMachineTransition<TimedOut> {
+        NexusOperationMachineTransition::commands([
+            NexusOperationCommand::StartSync,
+            NexusOperationCommand::TimedOut(toa.failure),
+        ])
+    }
+
+    pub(super) fn on_issue_cancel(
+        self,
+        state: &mut SharedState,
+    ) -> NexusOperationMachineTransition<ScheduledEventRecorded> {
+        NexusOperationMachineTransition::ok(issue_cancel(state), self)
+    }
+}
+
+#[derive(Default, Clone)]
+pub(super) struct Started {}
+
+impl Started {
+    pub(super) fn on_completed(
+        self,
+        ca: NexusOperationCompletedEventAttributes,
+    ) -> NexusOperationMachineTransition<Completed> {
+        NexusOperationMachineTransition::commands([NexusOperationCommand::Complete(ca.result)])
+    }
+
+    pub(super) fn on_failed(
+        self,
+        fa: NexusOperationFailedEventAttributes,
+    ) -> NexusOperationMachineTransition<Failed> {
+        NexusOperationMachineTransition::commands([NexusOperationCommand::Fail(fa.failure)])
+    }
+
+    pub(super) fn on_canceled(
+        self,
+        ca: NexusOperationCanceledEventAttributes,
+    ) -> NexusOperationMachineTransition<Cancelled> {
+        NexusOperationMachineTransition::commands([NexusOperationCommand::Cancel(ca.failure)])
+    }
+
+    pub(super) fn on_timed_out(
+        self,
+        toa: NexusOperationTimedOutEventAttributes,
+    ) -> NexusOperationMachineTransition<TimedOut> {
+        NexusOperationMachineTransition::commands([NexusOperationCommand::TimedOut(toa.failure)])
+    }
+
+    pub(super) fn on_issue_cancel(
+        self,
+        state: &mut SharedState,
+    ) -> NexusOperationMachineTransition<Started> {
+        NexusOperationMachineTransition::ok(issue_cancel(state), self)
+    }
+}
+
+/// Only the first cancel of an operation is sent to the server
+fn issue_cancel(state: &mut SharedState) -> Vec<NexusOperationCommand> {
+    if state.cancel_requested {
+        vec![]
+    } else {
+        state.cancel_requested = true;
+        vec![NexusOperationCommand::IssueCancel]
+    }
+}
+
+#[derive(Default, Clone)]
+pub(super) struct Completed {}
+
+#[derive(Default, Clone)]
+pub(super) struct Failed {}
+
+#[derive(Default, Clone)]
+pub(super) struct TimedOut {}
+
+#[derive(Default, Clone)]
+pub(super) struct Cancelled {}
@@ -44,7 +44,7 @@ use temporal_sdk_core_protos::{
         command::v1::{
             Command, RecordMarkerCommandAttributes, UpsertWorkflowSearchAttributesCommandAttributes,
         },
-        common::v1::SearchAttributes,
+        common::v1::{Payload, SearchAttributes},
         enums::v1::CommandType,
         history::v1::HistoryEvent,
     },
@@ -89,10 +89,12 @@ pub(super) enum PatchCommand {}
 /// are guaranteed to return the same value.
 /// `replaying_when_invoked`: If the workflow is replaying when this invocation occurs, this needs
 /// to be set to true.
+/// `data`: Optional data recorded in the marker, and handed back to lang when replaying.
 pub(super) fn has_change<'a>(
     patch_id: String,
     replaying_when_invoked: bool,
     deprecated: bool,
+    data: Option<Payload>,
     seen_in_peekahead: bool,
     existing_patch_ids: impl Iterator<Item = &'a str>,
     internal_flags: InternalFlagsRef,
@@ -108,13 +110,14 @@ pub(super) fn has_change<'a>(
         attributes: Some(
             RecordMarkerCommandAttributes {
                 marker_name: PATCH_MARKER_NAME.to_string(),
-                details: build_has_change_marker_details(&shared_state.patch_id, deprecated)
+                details: build_has_change_marker_details(&shared_state.patch_id, deprecated, data)
                     .context("While encoding patch marker details")?,
                 header: None,
                 failure: None,
             }
             .into(),
         ),
+        user_metadata: None,
     };
     let mut machine = PatchMachine::from_parts(initial_state, shared_state);
 
@@ -288,13 +291,14 @@ mod tests {
     use rstest::rstest;
     use std::{
         collections::{hash_map::RandomState, HashSet, VecDeque},
+        sync::{Arc, Mutex},
         time::Duration,
     };
     use temporal_sdk::{ActivityOptions, WfContext};
     use temporal_sdk_core_protos::{
         constants::PATCH_MARKER_NAME,
         coresdk::{
-            common::decode_change_marker_details,
+            common::{decode_change_marker_data, decode_change_marker_details},
             workflow_activation::{workflow_activation_job, NotifyHasPatch, WorkflowActivationJob},
             AsJsonPayloadExt, FromJsonPayloadExt,
         },
@@ -563,6 +567,7 @@ mod tests {
                         variant: Some(workflow_activation_job::Variant::NotifyHasPatch(
                             NotifyHasPatch {
                                 patch_id,
+                                data: None,
                             }
                         ))
                     } => patch_id == MY_PATCH_ID
@@ -810,4 +815,63 @@ mod tests {
         });
         worker.run().await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn patch_data_is_recorded_and_returned_on_replay(#[values(false, true)] replay: bool) {
+        let recorded = "chosen".as_json_payload().unwrap();
+        let mut t = TestHistoryBuilder::default();
+        t.add_by_type(EventType::WorkflowExecutionStarted);
+        t.add_full_wf_task();
+        t.set_flags_first_wft(
+            &[CoreInternalFlags::UpsertSearchAttributeOnPatch as u32],
+            &[],
+        );
+        t.add_has_change_marker_with_data(MY_PATCH_ID, false, Some(recorded.clone()));
+        t.add_upsert_search_attrs_for_patch(&[MY_PATCH_ID.to_string()]);
+        let timer_started_event_id = t.add_by_type(EventType::TimerStarted);
+        t.add(TimerFiredEventAttributes {
+            started_event_id: timer_started_event_id,
+            timer_id: "1".to_owned(),
+        });
+        t.add_full_wf_task();
+        t.add_workflow_execution_completed();
+
+        let mut mock_cfg = if replay {
+            MockPollCfg::from_resps(t, [ResponseType::AllHistory])
+        } else {
+            MockPollCfg::from_hist_builder(t)
+        };
+        if !replay {
+            let recorded = recorded.clone();
+            mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+                asserts.then(move |wft| {
+                    assert_matches!(
+                        wft.commands[0].attributes.as_ref().unwrap(),
+                        Attributes::RecordMarkerCommandAttributes(
+                            RecordMarkerCommandAttributes { details, .. })
+                        if decode_change_marker_data(details).as_ref() == Some(&recorded)
+                    );
+                });
+            });
+        }
+
+        // When replaying, whatever the workflow would choose now is ignored in favor of the
+        // recorded data
+        let offered = if replay { "other" } else { "chosen" };
+        let returned = Arc::new(Mutex::new(None));
+        let returned_clone = returned.clone();
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, move |ctx: WfContext| {
+            let returned = returned_clone.clone();
+            async move {
+                *returned.lock().unwrap() =
+                    ctx.patched_with_data(MY_PATCH_ID, offered.as_json_payload().unwrap());
+                ctx.timer(ONE_SECOND).await;
+                Ok(().into())
+            }
+        });
+        worker.run().await.unwrap();
+        assert_eq!(returned.lock().unwrap().as_ref(), Some(&recorded));
+    }
 }
@@ -83,7 +83,12 @@ pub(super) fn new_external_signal(
             },
             true,
         ),
-        Some(sig_we::Target::WorkflowExecution(we)) => (we, false),
+        Some(sig_we::Target::WorkflowExecution(mut we)) => {
+            if we.namespace.is_empty() {
+                we.namespace = this_namespace.to_string();
+            }
+            (we, false)
+        }
     };
 
     let mut s =
@@ -112,6 +117,7 @@ pub(super) fn new_external_signal(
     let cmd = Command {
         command_type: CommandType::SignalExternalWorkflowExecution as i32,
         attributes: Some(cmd_attrs),
+        user_metadata: None,
     };
     Ok(NewMachineWithCommand {
         command: cmd,
@@ -6,7 +6,8 @@ use super::{
 };
 use crate::worker::workflow::{machines::HistEventData, WFMachinesError};
 use rustfsm::{fsm, MachineError, StateMachine, TransitionResult};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, time::Duration};
+use temporal_sdk_core_api::worker::{ExceedingMaxTimerDuration, TimerPolicy};
 use temporal_sdk_core_protos::{
     coresdk::{
         workflow_activation::FireTimer,
@@ -61,12 +62,64 @@ pub(super) struct SharedState {
     cancelled_before_sent: bool,
 }
 
-/// Creates a new, scheduled, timer as a [CancellableCommand]
-pub(super) fn new_timer(attribs: StartTimer) -> NewMachineWithCommand {
+/// Creates a new, scheduled, timer as a [CancellableCommand], with its duration adjusted according
+/// to `policy`. `jitter_seed` determines how much jitter is added, if the policy asks for any.
+pub(super) fn new_timer(
+    mut attribs: StartTimer,
+    policy: &TimerPolicy,
+    jitter_seed: u64,
+) -> Result<NewMachineWithCommand, WFMachinesError> {
+    let requested = attribs
+        .start_to_fire_timeout
+        .clone()
+        .and_then(|d| Duration::try_from(d).ok());
+    if let Some(requested) = requested {
+        let adjusted = adjusted_duration(attribs.seq, requested, policy, jitter_seed)?;
+        attribs.start_to_fire_timeout = adjusted.try_into().ok();
+    }
     let (timer, add_cmd) = TimerMachine::new_scheduled(attribs);
-    NewMachineWithCommand {
+    Ok(NewMachineWithCommand {
         command: add_cmd,
         machine: timer.into(),
+    })
+}
+
+fn adjusted_duration(
+    seq: u32,
+    requested: Duration,
+    policy: &TimerPolicy,
+    jitter_seed: u64,
+) -> Result<Duration, WFMachinesError> {
+    let mut duration = round_up_to_millis(requested);
+    if let Some(max) = policy.max_duration {
+        if duration > max {
+            match policy.exceeding_max_duration {
+                ExceedingMaxTimerDuration::Clamp => duration = max,
+                ExceedingMaxTimerDuration::FailWorkflowTask => {
+                    return Err(WFMachinesError::Fatal(format!(
+                        "Timer {seq} duration of {requested:?} exceeds the maximum timer \
+                         duration of {max:?}"
+                    )))
+                }
+            }
+        }
+    }
+    if let Some(max_jitter) = policy.max_jitter {
+        let jitter = max_jitter.mul_f64(jitter_seed as f64 / u64::MAX as f64);
+        duration = round_up_to_millis(duration + jitter);
+        if let Some(max) = policy.max_duration {
+            duration = duration.min(max);
+        }
+    }
+    Ok(duration)
+}
+
+fn round_up_to_millis(d: Duration) -> Duration {
+    let sub_milli_nanos = d.subsec_nanos() % 1_000_000;
+    if sub_milli_nanos == 0 {
+        d
+    } else {
+        d + Duration::from_nanos(u64::from(1_000_000 - sub_milli_nanos))
     }
 }
 
@@ -79,6 +132,7 @@ impl TimerMachine {
         let cmd = Command {
             command_type: CommandType::StartTimer as i32,
             attributes: Some(s.shared_state().attrs.clone().into()),
+            user_metadata: s.shared_state().attrs.user_metadata.clone(),
         };
         (s, cmd)
     }
@@ -210,6 +264,7 @@ impl StartCommandRecorded {
         let cmd = Command {
             command_type: CommandType::CancelTimer as i32,
             attributes: Some(CancelTimer { seq: dat.attrs.seq }.into()),
+            user_metadata: None,
         };
         TransitionResult::ok(
             vec![TimerMachineCommand::IssueCancelCmd(cmd)],
@@ -267,9 +322,10 @@ mod test {
         replay::TestHistoryBuilder,
         test_help::{build_fake_sdk, canned_histories, MockPollCfg},
     };
-    use std::{mem::discriminant, time::Duration};
-    use temporal_sdk::{CancellableFuture, WfContext, WorkflowResult};
+    use std::mem::discriminant;
+    use temporal_sdk::{CancellableFuture, TimerOptions, WfContext, WorkflowResult};
     use temporal_sdk_core_protos::{
+        coresdk::FromJsonPayloadExt,
         temporal::api::{enums::v1::WorkflowTaskFailedCause, failure::v1::Failure},
         DEFAULT_WORKFLOW_TYPE,
     };
@@ -303,6 +359,37 @@ mod test {
         worker.run().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn timer_summary_sent_with_command() {
+        let t = canned_histories::single_timer("1");
+        let mut mock_cfg = MockPollCfg::from_hist_builder(t);
+        mock_cfg.completion_asserts_from_expectations(|mut asserts| {
+            asserts
+                .then(move |wft| {
+                    let summary = wft.commands[0]
+                        .user_metadata
+                        .as_ref()
+                        .and_then(|m| m.summary.as_ref())
+                        .unwrap();
+                    assert_eq!(String::from_json_payload(summary).unwrap(), "Nap time");
+                })
+                .then(move |wft| {
+                    assert_eq!(wft.commands[0].user_metadata, None);
+                });
+        });
+
+        let mut worker = build_fake_sdk(mock_cfg);
+        worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+            ctx.timer(TimerOptions {
+                duration: Duration::from_secs(5),
+                summary: Some("Nap time".to_string()),
+            })
+            .await;
+            Ok(().into())
+        });
+        worker.run().await.unwrap();
+    }
+
     #[tokio::test]
     async fn mismatched_timer_ids_errors() {
         let t = canned_histories::single_timer("badid");
@@ -392,4 +479,65 @@ mod test {
             assert_eq!(discriminant(&state), discriminant(s.state()));
         }
     }
+
+    #[test]
+    fn timers_rounded_up_to_millis() {
+        let policy = TimerPolicy::default();
+        for (requested, expected) in [
+            (Duration::from_micros(1), Duration::from_millis(1)),
+            (Duration::from_nanos(1_500_000), Duration::from_millis(2)),
+            (Duration::from_millis(3), Duration::from_millis(3)),
+            (Duration::ZERO, Duration::ZERO),
+        ] {
+            assert_eq!(
+                adjusted_duration(1, requested, &policy, 0).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn timers_over_max_duration() {
+        let mut policy = TimerPolicy {
+            max_duration: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        assert_eq!(
+            adjusted_duration(1, Duration::from_secs(30), &policy, 0).unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            adjusted_duration(1, Duration::from_secs(90), &policy, 0).unwrap(),
+            Duration::from_secs(60)
+        );
+        policy.exceeding_max_duration = ExceedingMaxTimerDuration::FailWorkflowTask;
+        let err = adjusted_duration(7, Duration::from_secs(90), &policy, 0).unwrap_err();
+        assert_matches!(err, WFMachinesError::Fatal(msg) if msg.contains("Timer 7"));
+    }
+
+    #[test]
+    fn jitter_added_within_bounds() {
+        let mut policy = TimerPolicy {
+            max_jitter: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let requested = Duration::from_secs(30);
+        assert_eq!(
+            adjusted_duration(1, requested, &policy, 0).unwrap(),
+            requested
+        );
+        assert_eq!(
+            adjusted_duration(1, requested, &policy, u64::MAX).unwrap(),
+            Duration::from_secs(40)
+        );
+        let halfway = adjusted_duration(1, requested, &policy, u64::MAX / 2).unwrap();
+        assert!(halfway > requested && halfway < Duration::from_secs(40));
+        assert_eq!(halfway.subsec_nanos() % 1_000_000, 0);
+        // Jitter never pushes a timer over the maximum
+        policy.max_duration = Some(Duration::from_secs(35));
+        assert_eq!(
+            adjusted_duration(1, requested, &policy, u64::MAX).unwrap(),
+            Duration::from_secs(35)
+        );
+    }
 }
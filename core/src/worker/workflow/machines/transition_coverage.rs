@@ -76,8 +76,10 @@ mod machine_coverage_report {
         fail_workflow_state_machine::FailWorkflowMachine,
         local_activity_state_machine::LocalActivityMachine,
         modify_workflow_properties_state_machine::ModifyWorkflowPropertiesMachine,
-        patch_state_machine::PatchMachine, signal_external_state_machine::SignalExternalMachine,
-        timer_state_machine::TimerMachine, update_state_machine::UpdateMachine,
+        mutable_side_effect_state_machine::MutableSideEffectMachine,
+        nexus_operation_state_machine::NexusOperationMachine, patch_state_machine::PatchMachine,
+        signal_external_state_machine::SignalExternalMachine, timer_state_machine::TimerMachine,
+        update_state_machine::UpdateMachine,
         upsert_search_attributes_state_machine::UpsertSearchAttributesMachine,
         workflow_task_state_machine::WorkflowTaskMachine,
     };
@@ -118,6 +120,8 @@ mod machine_coverage_report {
         let mut upsert_search_attr = UpsertSearchAttributesMachine::visualizer().to_owned();
         let mut modify_wf_props = ModifyWorkflowPropertiesMachine::visualizer().to_owned();
         let mut update = UpdateMachine::visualizer().to_owned();
+        let mut mutable_side_effect = MutableSideEffectMachine::visualizer().to_owned();
+        let mut nexus_operation = NexusOperationMachine::visualizer().to_owned();
 
         // This isn't at all efficient but doesn't need to be.
         // Replace transitions in the vizzes with green color if they are covered.
@@ -145,6 +149,10 @@ mod machine_coverage_report {
                     cover_transitions(m, &mut modify_wf_props, coverage)
                 }
                 m @ "UpdateMachine" => cover_transitions(m, &mut update, coverage),
+                m @ "MutableSideEffectMachine" => {
+                    cover_transitions(m, &mut mutable_side_effect, coverage)
+                }
+                m @ "NexusOperationMachine" => cover_transitions(m, &mut nexus_operation, coverage),
                 m => panic!("Unknown machine {m}"),
             }
         }
@@ -109,6 +109,11 @@ impl UpdateMachine {
         }
     }
 
+    /// True if lang rejected the update, which leaves no trace in history
+    pub(super) fn was_rejected(&self) -> bool {
+        matches!(self.state(), UpdateMachineState::Rejected(_))
+    }
+
     pub(crate) fn handle_response(
         &mut self,
         resp: UpdateResponse,
@@ -86,6 +86,7 @@ fn create_new(sa_map: SearchAttributes) -> NewMachineWithCommand {
                 },
             ),
         ),
+        user_metadata: None,
     };
     NewMachineWithCommand {
         command: cmd,
@@ -377,6 +378,7 @@ mod tests {
             vec![SetPatchMarker {
                 patch_id,
                 deprecated: false,
+                data: None,
             }
             .into()]
         } else {
@@ -1,19 +1,23 @@
 mod local_acts;
+mod message_sequencing;
 
 use super::{
     cancel_external_state_machine::new_external_cancel,
     cancel_workflow_state_machine::cancel_workflow,
     complete_workflow_state_machine::complete_workflow,
     continue_as_new_workflow_state_machine::continue_as_new,
-    fail_workflow_state_machine::fail_workflow, local_activity_state_machine::new_local_activity,
-    patch_state_machine::has_change, signal_external_state_machine::new_external_signal,
-    timer_state_machine::new_timer, upsert_search_attributes_state_machine::upsert_search_attrs,
-    workflow_machines::local_acts::LocalActivityData,
-    workflow_task_state_machine::WorkflowTaskMachine, Machines, NewMachineWithCommand,
-    TemporalStateMachine,
+    fail_workflow_state_machine::fail_workflow,
+    local_activity_state_machine::new_local_activity,
+    mutable_side_effect_state_machine::record_mutable_side_effect,
+    patch_state_machine::has_change,
+    signal_external_state_machine::new_external_signal,
+    timer_state_machine::new_timer,
+    upsert_search_attributes_state_machine::upsert_search_attrs,
+    workflow_machines::{local_acts::LocalActivityData, message_sequencing::MessageSequencer},
+    workflow_task_state_machine::WorkflowTaskMachine,
+    Machines, NewMachineWithCommand, TemporalStateMachine,
 };
 use crate::{
-    abstractions::dbg_panic,
     internal_flags::InternalFlags,
     protosext::{
         protocol_messages::{IncomingProtocolMessage, IncomingProtocolMessageBody},
@@ -27,6 +31,7 @@ use crate::{
                 activity_state_machine::ActivityMachine,
                 child_workflow_state_machine::ChildWorkflowMachine,
                 modify_workflow_properties_state_machine::modify_workflow_properties,
+                nexus_operation_state_machine::NexusOperationMachine,
                 patch_state_machine::VERSION_SEARCH_ATTR_KEY, update_state_machine::UpdateMachine,
                 upsert_search_attributes_state_machine::upsert_search_attrs_internal,
                 HistEventData,
@@ -49,24 +54,28 @@ use std::{
     sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
-use temporal_sdk_core_api::worker::WorkerConfig;
+use temporal_sdk_core_api::worker::{LangFlagRegistry, WorkerConfig};
 use temporal_sdk_core_protos::{
     coresdk::{
         common::{NamespacedWorkflowExecution, VersioningIntent},
+        external_data::MutableSideEffectMarkerData,
         workflow_activation,
         workflow_activation::{
-            workflow_activation_job, NotifyHasPatch, UpdateRandomSeed, WorkflowActivation,
+            workflow_activation_job, NotifyHasPatch, ResolveMutableSideEffect,
+            ResolveRequestCancelExternalWorkflow, UpdateRandomSeed, WorkflowActivation,
         },
         workflow_commands::{
-            request_cancel_external_workflow_execution as cancel_we, ContinueAsNewWorkflowExecution,
+            request_cancel_external_workflow_execution as cancel_we,
+            ContinueAsNewWorkflowExecution, MutableSideEffect, ScheduleActivity,
         },
     },
     temporal::api::{
         command::v1::{command::Attributes as ProtoCmdAttrs, Command as ProtoCommand},
+        common::v1::Payload,
         enums::v1::EventType,
-        history::v1::{history_event, HistoryEvent},
+        history::v1::{history_event, HistoryEvent, WorkflowTaskCompletedEventAttributes},
         protocol::v1::{message::SequencingId, Message as ProtocolMessage},
-        sdk::v1::WorkflowTaskCompletedMetadata,
+        sdk::v1::{WorkflowDefinition, WorkflowMetadata, WorkflowTaskCompletedMetadata},
     },
 };
 
@@ -81,8 +90,8 @@ pub(crate) struct WorkflowMachines {
     /// kept because the lang side polls & completes for every workflow task, but we do not need
     /// to poll the server that often during replay.
     last_history_from_server: HistoryUpdate,
-    /// Protocol messages that have yet to be processed for the current WFT.
-    protocol_msgs: Vec<IncomingProtocolMessage>,
+    /// Protocol messages that have yet to be processed, ordered relative to history
+    protocol_msgs: MessageSequencer,
     /// EventId of the last handled WorkflowTaskStarted event
     current_started_event_id: i64,
     /// The event id of the next workflow task started event that the machines need to process.
@@ -128,6 +137,9 @@ pub(crate) struct WorkflowMachines {
     /// If a machine key is in this map, that machine was created internally by core, not as a
     /// command from lang.
     machine_is_core_created: SparseSecondaryMap<MachineKey, ()>,
+    /// Maps the core created machines requesting cancellation of child workflows to the machines
+    /// of the children they cancel
+    child_cancel_requests: SparseSecondaryMap<MachineKey, MachineKey>,
     /// A mapping for accessing machines associated to a particular event, where the key is the id
     /// of the initiating event for that machine.
     machines_by_event_id: HashMap<i64, MachineKey>,
@@ -149,6 +161,12 @@ pub(crate) struct WorkflowMachines {
 
     /// Information about patch markers we have already seen while replaying history
     encountered_patch_markers: HashMap<String, ChangeInfo>,
+    /// Calls made so far and values recorded for mutable side effects, by id
+    mutable_side_effects: HashMap<String, MutableSideEffectInfo>,
+    /// Mutable side effect markers seen while peeking ahead in history, by id and call number
+    peeked_mutable_side_effects: HashMap<(String, u32), RecordedMutableSideEffect>,
+    /// Metadata lang most recently set for the workflow, reported by the built-in metadata query
+    workflow_metadata: WorkflowMetadata,
 
     /// Contains extra local-activity related data
     local_activity_data: LocalActivityData,
@@ -180,6 +198,18 @@ struct ChangeInfo {
     created_command: bool,
 }
 
+#[derive(Debug, Default)]
+struct MutableSideEffectInfo {
+    calls: u32,
+    last_recorded: Option<RecordedMutableSideEffect>,
+}
+
+#[derive(Debug, Clone)]
+struct RecordedMutableSideEffect {
+    equality_token: String,
+    value: Option<Payload>,
+}
+
 /// Returned by [TemporalStateMachine]s when handling events
 #[derive(Debug, derive_more::Display)]
 #[must_use]
@@ -240,11 +270,12 @@ impl WorkflowMachines {
         let mut observed_internal_flags = InternalFlags::new(basics.capabilities);
         // Peek ahead to determine used patches in the first WFT.
         if let Some(attrs) = basics.history.peek_next_wft_completed(0) {
+            warn_of_unknown_lang_flags(&basics.worker_config.lang_flags, attrs);
             observed_internal_flags.add_from_complete(attrs);
         };
         Self {
             last_history_from_server: basics.history,
-            protocol_msgs: vec![],
+            protocol_msgs: Default::default(),
             workflow_id: basics.workflow_id,
             workflow_type: basics.workflow_type,
             run_id: basics.run_id,
@@ -265,6 +296,7 @@ impl WorkflowMachines {
             current_wft_build_id: None,
             all_machines: Default::default(),
             machine_is_core_created: Default::default(),
+            child_cancel_requests: Default::default(),
             machines_by_event_id: Default::default(),
             machines_by_protocol_instance_id: Default::default(),
             id_to_machine: Default::default(),
@@ -272,6 +304,9 @@ impl WorkflowMachines {
             current_wf_task_commands: Default::default(),
             message_outbox: Default::default(),
             encountered_patch_markers: Default::default(),
+            workflow_metadata: Default::default(),
+            mutable_side_effects: Default::default(),
+            peeked_mutable_side_effects: Default::default(),
             local_activity_data: LocalActivityData::default(),
             have_seen_terminal_event: false,
             worker_config: basics.worker_config,
@@ -297,10 +332,7 @@ impl WorkflowMachines {
         update: HistoryUpdate,
         protocol_messages: Vec<IncomingProtocolMessage>,
     ) -> Result<()> {
-        if !self.protocol_msgs.is_empty() {
-            dbg_panic!("There are unprocessed protocol messages while receiving new work");
-        }
-        self.protocol_msgs = protocol_messages;
+        self.protocol_msgs.add(protocol_messages)?;
         self.new_history_from_server(update)?;
         Ok(())
     }
@@ -327,13 +359,20 @@ impl WorkflowMachines {
                 attempt,
                 backoff,
                 original_schedule_time,
+                last_progress,
             }) => {
                 let act_id = CommandID::LocalActivity(seq);
                 let mk = self.get_machine_key(act_id)?;
                 let mach = self.machine_mut(mk);
                 if let Machines::LocalActivityMachine(ref mut lam) = *mach {
-                    let resps =
-                        lam.try_resolve(result, runtime, attempt, backoff, original_schedule_time)?;
+                    let resps = lam.try_resolve(
+                        result,
+                        runtime,
+                        attempt,
+                        backoff,
+                        original_schedule_time,
+                        last_progress,
+                    )?;
                     if resps.is_empty() {
                         result_important = false;
                     }
@@ -356,6 +395,19 @@ impl WorkflowMachines {
             .take_all_reqs(&self.workflow_type, &self.workflow_id, &self.run_id)
     }
 
+    /// Returns the metadata to answer the built-in workflow metadata query with. The workflow's
+    /// type is filled in if lang didn't provide one.
+    pub(crate) fn workflow_metadata(&self) -> WorkflowMetadata {
+        let mut metadata = self.workflow_metadata.clone();
+        let definition = metadata
+            .definition
+            .get_or_insert_with(WorkflowDefinition::default);
+        if definition.r#type.is_empty() {
+            definition.r#type = self.workflow_type.clone();
+        }
+        metadata
+    }
+
     /// Returns the number of local activities we know we need to execute but have not yet finished
     pub(crate) fn outstanding_local_activity_count(&self) -> usize {
         self.local_activity_data.outstanding_la_count()
@@ -441,6 +493,14 @@ impl WorkflowMachines {
         }
     }
 
+    /// Fills in how long and large history is so far, for activations not built by
+    /// [Self::get_wf_activation]
+    pub(crate) fn add_history_info(&self, activation: &mut WorkflowActivation) {
+        activation.history_length = self.last_processed_event as u32;
+        activation.history_size_bytes = self.history_size_bytes;
+        activation.continue_as_new_suggested = self.continue_as_new_suggested;
+    }
+
     pub(crate) fn has_pending_jobs(&self) -> bool {
         !self.drive_me.peek_pending_jobs().is_empty()
     }
@@ -459,12 +519,27 @@ impl WorkflowMachines {
         if !self.worker_config.worker_build_id.is_empty() {
             self.current_wft_build_id = Some(self.worker_config.worker_build_id.clone());
         }
-        (*self.observed_internal_flags)
+        let metadata = (*self.observed_internal_flags)
             .borrow_mut()
-            .gather_for_wft_complete()
+            .gather_for_wft_complete();
+        for flag in &metadata.core_used_flags {
+            self.metrics.sdk_flag_recorded("core", *flag);
+        }
+        for flag in &metadata.lang_used_flags {
+            self.metrics.sdk_flag_recorded("lang", *flag);
+        }
+        metadata
     }
 
     pub(crate) fn add_lang_used_flags(&self, flags: Vec<u32>) {
+        let registry = &self.worker_config.lang_flags;
+        if !registry.is_empty() {
+            let unregistered: Vec<_> = flags.iter().filter(|f| !registry.contains(**f)).collect();
+            if !unregistered.is_empty() {
+                warn!(run_id=%self.run_id, flags=?unregistered,
+                      "Workflow task completion used lang flags which aren't registered");
+            }
+        }
         (*self.observed_internal_flags)
             .borrow_mut()
             .add_lang_used(flags);
@@ -492,6 +567,10 @@ impl WorkflowMachines {
             self.machines_by_event_id.remove(&mid);
             self.all_machines.remove(mkey);
         }
+        // Flags sent with the dropped task were never recorded, so must be sent again
+        (*self.observed_internal_flags)
+            .borrow_mut()
+            .forget_last_sent();
     }
 
     /// Iterate the state machines, which consists of grabbing any pending outgoing commands from
@@ -527,34 +606,13 @@ impl WorkflowMachines {
             return Ok(0);
         }
 
-        fn get_processable_messages(
-            me: &mut WorkflowMachines,
-            for_event_id: i64,
-        ) -> Vec<IncomingProtocolMessage> {
-            // Another thing to replace when `drain_filter` exists
-            let mut ret = vec![];
-            me.protocol_msgs = std::mem::take(&mut me.protocol_msgs)
-                .into_iter()
-                .filter_map(|x| {
-                    if x.processable_after_event_id()
-                        .is_some_and(|eid| eid <= for_event_id)
-                    {
-                        ret.push(x);
-                        None
-                    } else {
-                        Some(x)
-                    }
-                })
-                .collect();
-            ret
-        }
-
         // Peek to the next WFT complete and update ourselves with data we might need in it.
         if let Some(next_complete) = self
             .last_history_from_server
             .peek_next_wft_completed(self.last_processed_event)
         {
             // We update the internal flags before applying the current task
+            warn_of_unknown_lang_flags(&self.worker_config.lang_flags, next_complete);
             (*self.observed_internal_flags)
                 .borrow_mut()
                 .add_from_complete(next_complete);
@@ -630,7 +688,7 @@ impl WorkflowMachines {
             }
 
             // Process any messages that should be processed before the event we're about to handle
-            let processable_msgs = get_processable_messages(self, eid - 1);
+            let processable_msgs = self.protocol_msgs.take_processable(eid - 1);
             for msg in processable_msgs {
                 self.handle_protocol_message(msg)?;
             }
@@ -679,8 +737,19 @@ impl WorkflowMachines {
                 );
                 // Found a patch marker
                 self.drive_me.send_job(
-                    workflow_activation_job::Variant::NotifyHasPatch(NotifyHasPatch { patch_id })
-                        .into(),
+                    workflow_activation_job::Variant::NotifyHasPatch(NotifyHasPatch {
+                        patch_id,
+                        data: e.get_patch_marker_data(),
+                    })
+                    .into(),
+                );
+            } else if let Some((md, value)) = e.get_mutable_side_effect_marker_details() {
+                self.peeked_mutable_side_effects.insert(
+                    (md.id, md.call_number),
+                    RecordedMutableSideEffect {
+                        equality_token: md.equality_token,
+                        value,
+                    },
                 );
             } else if e.is_local_activity_marker() {
                 if let Some(la_dat) = e.clone().into_local_activity_marker_details() {
@@ -778,6 +847,14 @@ impl WorkflowMachines {
                         .to_string(),
                 ))
             } else {
+                if let Some(
+                    history_event::Attributes::WorkflowExecutionTerminatedEventAttributes(attrs),
+                ) = &event.attributes
+                {
+                    self.drive_me.send_job(
+                        workflow_activation::NotifyWorkflowTerminated::from(attrs.clone()).into(),
+                    );
+                }
                 Ok(EventHandlingOutcome::Normal)
             };
         }
@@ -1009,6 +1086,20 @@ impl WorkflowMachines {
                 } else {
                     return Err(WFMachinesError::Fatal(SEQIDERR.to_string()));
                 };
+                // A request may be delivered again, for example when the task it came in is
+                // retried, or alongside history which already shows it accepted. Lang only hears
+                // of it once. Rejected requests leave nothing in history, so one with the same id
+                // may legitimately be sent again, and is delivered as a new update.
+                if let Some(&mk) = self
+                    .machines_by_protocol_instance_id
+                    .get(&message.protocol_instance_id)
+                {
+                    if !matches!(self.machine(mk), Machines::UpdateMachine(m) if m.was_rejected()) {
+                        debug!(protocol_instance_id=%message.protocol_instance_id,
+                               "Ignoring update request which was already delivered");
+                        return Ok(());
+                    }
+                }
                 let um = UpdateMachine::init(
                     message.id,
                     message.protocol_instance_id.clone(),
@@ -1085,6 +1176,17 @@ impl WorkflowMachines {
                     // We don't need to notify lang about jobs created by core-internal machines
                     if !self.machine_is_core_created.contains_key(smk) {
                         self.drive_me.send_job(a);
+                    } else if let (
+                        Some(&child_key),
+                        workflow_activation_job::Variant::ResolveRequestCancelExternalWorkflow(
+                            ResolveRequestCancelExternalWorkflow { failure: None, .. },
+                        ),
+                    ) = (self.child_cancel_requests.get(smk), &a.variant)
+                    {
+                        if let Machines::ChildWorkflowMachine(cw) = self.machine_mut(child_key) {
+                            let resps = cw.cancellation_requested()?;
+                            self.process_machine_resps_impl(child_key, resps)?;
+                        }
                     }
                 }
                 MachineResponse::TriggerWFTaskStarted {
@@ -1123,10 +1225,13 @@ impl WorkflowMachines {
                             workflow_id: attrs.workflow_id,
                             run_id: attrs.run_id,
                         };
-                        self.add_cmd_to_wf_task(
+                        let key = self.add_cmd_to_wf_task(
                             new_external_cancel(0, we, attrs.child_workflow_only, attrs.reason),
                             CommandIdKind::CoreInternal,
                         );
+                        if matches!(self.machine(smk), Machines::ChildWorkflowMachine(_)) {
+                            self.child_cancel_requests.insert(key, smk);
+                        }
                     }
                     ProtoCmdAttrs::UpsertWorkflowSearchAttributesCommandAttributes(attrs) => {
                         self.add_cmd_to_wf_task(
@@ -1174,6 +1279,7 @@ impl WorkflowMachines {
                                 removed_act.attempt,
                                 None,
                                 removed_act.original_schedule_time,
+                                vec![],
                             )?;
                             self.process_machine_responses(smk, more_responses)?;
                         } else {
@@ -1236,9 +1342,14 @@ impl WorkflowMachines {
             match cmd {
                 WFCommand::AddTimer(attrs) => {
                     let seq = attrs.seq;
-                    self.add_cmd_to_wf_task(new_timer(attrs), CommandID::Timer(seq).into());
+                    let jitter_seed = timer_jitter_seed(&self.run_id, seq);
+                    self.add_cmd_to_wf_task(
+                        new_timer(attrs, &self.worker_config.timer_policy, jitter_seed)?,
+                        CommandID::Timer(seq).into(),
+                    );
                 }
                 WFCommand::UpsertSearchAttributes(attrs) => {
+                    self.validate_search_attributes(&attrs.search_attributes)?;
                     self.add_cmd_to_wf_task(
                         upsert_search_attrs(
                             attrs,
@@ -1251,8 +1362,9 @@ impl WorkflowMachines {
                 WFCommand::CancelTimer(attrs) => {
                     self.process_cancellation(CommandID::Timer(attrs.seq))?;
                 }
-                WFCommand::AddActivity(attrs) => {
+                WFCommand::AddActivity(mut attrs) => {
                     let seq = attrs.seq;
+                    self.route_activity(&mut attrs);
                     let use_compat = self.determine_use_compatible_flag(
                         attrs.versioning_intent(),
                         &attrs.task_queue,
@@ -1268,12 +1380,15 @@ impl WorkflowMachines {
                 }
                 WFCommand::AddLocalActivity(attrs) => {
                     let seq = attrs.seq;
-                    let attrs: ValidScheduleLA =
-                        ValidScheduleLA::from_schedule_la(attrs).map_err(|e| {
-                            WFMachinesError::Fatal(format!(
-                                "Invalid schedule local activity request (seq {seq}): {e}"
-                            ))
-                        })?;
+                    let attrs: ValidScheduleLA = ValidScheduleLA::from_schedule_la(
+                        attrs,
+                        self.worker_config.default_local_activity_retry_threshold,
+                    )
+                    .map_err(|e| {
+                        WFMachinesError::Fatal(format!(
+                            "Invalid schedule local activity request (seq {seq}): {e}"
+                        ))
+                    })?;
                     let (la, mach_resp) = new_local_activity(
                         attrs,
                         self.replaying,
@@ -1320,6 +1435,7 @@ impl WorkflowMachines {
                             attrs.patch_id.clone(),
                             self.replaying,
                             attrs.deprecated,
+                            attrs.data,
                             encountered_entry.is_some(),
                             self.encountered_patch_markers.keys().map(|s| s.as_str()),
                             self.observed_internal_flags.clone(),
@@ -1340,8 +1456,12 @@ impl WorkflowMachines {
                         }
                     }
                 }
-                WFCommand::AddChildWorkflow(attrs) => {
+                WFCommand::AddChildWorkflow(mut attrs) => {
+                    self.validate_search_attributes(&attrs.search_attributes)?;
                     let seq = attrs.seq;
+                    if attrs.namespace.is_empty() {
+                        attrs.namespace.clone_from(&self.worker_config.namespace);
+                    }
                     let use_compat = self.determine_use_compatible_flag(
                         attrs.versioning_intent(),
                         &attrs.task_queue,
@@ -1355,9 +1475,14 @@ impl WorkflowMachines {
                         CommandID::ChildWorkflowStart(seq).into(),
                     );
                 }
-                WFCommand::CancelChild(attrs) => self.process_cancellation(
-                    CommandID::ChildWorkflowStart(attrs.child_workflow_seq),
-                )?,
+                WFCommand::CancelChild(attrs) => {
+                    let id = CommandID::ChildWorkflowStart(attrs.child_workflow_seq);
+                    let m_key = self.get_machine_key(id)?;
+                    if let Machines::ChildWorkflowMachine(cw) = self.machine_mut(m_key) {
+                        cw.set_cancel_reason(attrs.reason);
+                    }
+                    self.process_cancellation(id)?
+                }
                 WFCommand::RequestCancelExternalWorkflow(attrs) => {
                     let (we, only_child) = match attrs.target {
                         None => {
@@ -1376,13 +1501,13 @@ impl WorkflowMachines {
                         ),
                         Some(cancel_we::Target::WorkflowExecution(we)) => (we, false),
                     };
+                    let reason = if attrs.reason.is_empty() {
+                        format!("Cancel requested by workflow with run id {}", self.run_id)
+                    } else {
+                        attrs.reason
+                    };
                     self.add_cmd_to_wf_task(
-                        new_external_cancel(
-                            attrs.seq,
-                            we,
-                            only_child,
-                            format!("Cancel requested by workflow with run id {}", self.run_id),
-                        ),
+                        new_external_cancel(attrs.seq, we, only_child, reason),
                         CommandID::CancelExternal(attrs.seq).into(),
                     );
                 }
@@ -1401,6 +1526,9 @@ impl WorkflowMachines {
                     unimplemented!("Query responses should not make it down into the machines")
                 }
                 WFCommand::ModifyWorkflowProperties(attrs) => {
+                    if let Some(memo) = attrs.upserted_memo.as_ref() {
+                        self.drive_me.upsert_memo(memo);
+                    }
                     self.add_cmd_to_wf_task(
                         modify_workflow_properties(attrs),
                         CommandIdKind::NeverResolves,
@@ -1420,12 +1548,97 @@ impl WorkflowMachines {
                         )));
                     }
                 }
+                WFCommand::MutableSideEffect(attrs) => {
+                    self.handle_mutable_side_effect(attrs)?;
+                }
+                WFCommand::SetWorkflowMetadata(attrs) => {
+                    self.workflow_metadata = attrs.metadata.unwrap_or_default();
+                }
+                WFCommand::AddNexusOperation(attrs) => {
+                    let seq = attrs.seq;
+                    self.add_cmd_to_wf_task(
+                        NexusOperationMachine::new_scheduled(attrs),
+                        CommandID::NexusOperation(seq).into(),
+                    );
+                }
+                WFCommand::RequestCancelNexusOperation(attrs) => {
+                    self.process_cancellation(CommandID::NexusOperation(attrs.seq))?;
+                }
                 WFCommand::NoCommandsFromLang => (),
             }
         }
         Ok(())
     }
 
+    /// Checks search attributes lang sent against the registered ones, if core was told of them.
+    /// Replayed commands were already accepted by the server, so aren't checked again.
+    fn validate_search_attributes(&self, attrs: &HashMap<String, Payload>) -> Result<()> {
+        match &self.worker_config.search_attribute_schema {
+            Some(schema) if !self.replaying => schema
+                .validate_payloads(attrs)
+                .map_err(|e| WFMachinesError::Fatal(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves a mutable side effect call, recording a marker if its value changed. When
+    /// replaying, markers are only recorded where history has them, and the recorded values are
+    /// used in place of the ones lang provided.
+    fn handle_mutable_side_effect(&mut self, attrs: MutableSideEffect) -> Result<()> {
+        let info = self
+            .mutable_side_effects
+            .entry(attrs.id.clone())
+            .or_default();
+        info.calls += 1;
+        let call_number = info.calls;
+        let to_record = if self.replaying {
+            let peeked = self
+                .peeked_mutable_side_effects
+                .remove(&(attrs.id.clone(), call_number));
+            if peeked.is_none() && info.last_recorded.is_none() {
+                return Err(WFMachinesError::Nondeterminism(format!(
+                    "Mutable side effect {} was called, but no value was ever recorded for it",
+                    attrs.id
+                )));
+            }
+            peeked
+        } else if info.last_recorded.as_ref().map(|r| &r.equality_token)
+            != Some(&attrs.equality_token)
+        {
+            Some(RecordedMutableSideEffect {
+                equality_token: attrs.equality_token,
+                value: attrs.value,
+            })
+        } else {
+            None
+        };
+        if let Some(recorded) = to_record {
+            info.last_recorded = Some(recorded.clone());
+            let machine = record_mutable_side_effect(
+                MutableSideEffectMarkerData {
+                    id: attrs.id.clone(),
+                    call_number,
+                    equality_token: recorded.equality_token,
+                },
+                recorded.value,
+            )?;
+            self.add_cmd_to_wf_task(machine, CommandIdKind::NeverResolves);
+        }
+        let value = self
+            .mutable_side_effects
+            .get(&attrs.id)
+            .and_then(|i| i.last_recorded.as_ref())
+            .and_then(|r| r.value.clone());
+        self.drive_me.send_job(
+            workflow_activation_job::Variant::ResolveMutableSideEffect(ResolveMutableSideEffect {
+                seq: attrs.seq,
+                value,
+            })
+            .into(),
+        );
+        Ok(())
+    }
+
     /// Given a command id to attempt to cancel, try to cancel it and return any jobs that should
     /// be included in the activation
     fn process_cancellation(&mut self, id: CommandID) -> Result<()> {
@@ -1529,6 +1742,21 @@ impl WorkflowMachines {
         attrs
     }
 
+    /// Targets the activity at the task queue configured for its type, if any, unless the workflow
+    /// explicitly chose some other queue
+    fn route_activity(&self, attrs: &mut ScheduleActivity) {
+        if !attrs.task_queue.is_empty() && attrs.task_queue != self.worker_config.task_queue {
+            return;
+        }
+        if let Some(tq) = self
+            .worker_config
+            .activity_task_queue_routes
+            .get(&attrs.activity_type)
+        {
+            attrs.task_queue.clone_from(tq);
+        }
+    }
+
     /// Given a user's versioning intent for a command and that command's target task queue,
     /// returns whether or not the command should set the flag for attempting to stick within the
     /// compatible version set
@@ -1579,6 +1807,35 @@ fn str_to_randomness_seed(run_id: &str) -> u64 {
     s.finish()
 }
 
+fn timer_jitter_seed(run_id: &str, seq: u32) -> u64 {
+    let mut s = SipHasher13::new();
+    run_id.hash(&mut s);
+    seq.hash(&mut s);
+    s.finish()
+}
+
+/// Histories recorded by a newer version of the lang SDK may have used flags this version doesn't
+/// know about, and so can't replay the way they were first executed.
+fn warn_of_unknown_lang_flags(
+    registry: &LangFlagRegistry,
+    attrs: &WorkflowTaskCompletedEventAttributes,
+) {
+    if registry.is_empty() {
+        return;
+    }
+    let version = registry.version();
+    let newer: Vec<_> = attrs
+        .sdk_metadata
+        .iter()
+        .flat_map(|md| md.lang_used_flags.iter())
+        .filter(|f| **f > version)
+        .collect();
+    if !newer.is_empty() {
+        warn!(flags=?newer, registry_version=version,
+              "History used lang flags newer than this worker's registry");
+    }
+}
+
 #[must_use]
 enum EventHandlingOutcome {
     SkipEvent { skip_next_event: bool },
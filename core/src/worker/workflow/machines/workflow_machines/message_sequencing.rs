@@ -0,0 +1,74 @@
+use crate::{
+    protosext::protocol_messages::IncomingProtocolMessage, worker::workflow::WFMachinesError,
+};
+use temporal_sdk_core_protos::temporal::api::protocol::v1::message::SequencingId;
+
+/// Orders the protocol messages (ex: update requests) which arrive with workflow tasks relative to
+/// history. A message is sequenced after some event, and must be handed to the state machines
+/// after that event has been applied and before the one following it, no matter which task it
+/// arrived with or where it appeared in the task's message list. Messages which are sequenced
+/// after events not yet applied are held until history catches up.
+#[derive(Default)]
+pub(super) struct MessageSequencer {
+    /// Messages which have yet to be processed, sorted by the event they must follow. Messages
+    /// sequenced after the same event keep the order they arrived in.
+    pending: Vec<SequencedMessage>,
+}
+
+struct SequencedMessage {
+    after_event_id: i64,
+    message: IncomingProtocolMessage,
+}
+
+impl MessageSequencer {
+    /// Buffer messages which arrived with a workflow task. Messages which aren't sequenced are
+    /// processed before any event. Copies of messages which are already buffered are dropped.
+    pub(super) fn add(
+        &mut self,
+        messages: impl IntoIterator<Item = IncomingProtocolMessage>,
+    ) -> Result<(), WFMachinesError> {
+        for message in messages {
+            let after_event_id = match message.sequencing_id {
+                None => 0,
+                Some(SequencingId::EventId(eid)) if eid >= 0 => eid,
+                Some(SequencingId::EventId(eid)) => {
+                    return Err(WFMachinesError::Fatal(format!(
+                        "Protocol message {} has invalid sequencing event id {eid}",
+                        message.id
+                    )))
+                }
+                Some(SequencingId::CommandIndex(_)) => {
+                    return Err(WFMachinesError::Fatal(format!(
+                        "Protocol message {} is sequenced by command index, which is only valid \
+                         for messages sent to the server",
+                        message.id
+                    )))
+                }
+            };
+            if self.pending.iter().any(|m| m.message.id == message.id) {
+                debug!(message_id=%message.id, "Dropping duplicate protocol message");
+                continue;
+            }
+            let ix = self
+                .pending
+                .partition_point(|m| m.after_event_id <= after_event_id);
+            self.pending.insert(
+                ix,
+                SequencedMessage {
+                    after_event_id,
+                    message,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove and return, in the order they must be processed, all buffered messages which are
+    /// sequenced at or before `event_id`.
+    pub(super) fn take_processable(&mut self, event_id: i64) -> Vec<IncomingProtocolMessage> {
+        let n = self
+            .pending
+            .partition_point(|m| m.after_event_id <= event_id);
+        self.pending.drain(..n).map(|m| m.message).collect()
+    }
+}
@@ -28,13 +28,15 @@ use std::{
 };
 use temporal_sdk_core_api::{errors::WorkflowErrorType, worker::WorkerConfig};
 use temporal_sdk_core_protos::{
+    constants::{STACK_TRACE_QUERY_TYPE, WORKFLOW_METADATA_QUERY_TYPE},
     coresdk::{
         workflow_activation::{
             create_evict_activation, query_to_job, remove_from_cache::EvictionReason,
-            workflow_activation_job, RemoveFromCache, WorkflowActivation,
+            workflow_activation_job, RemoveFromCache, ReportStackTrace, WorkflowActivation,
         },
-        workflow_commands::{FailWorkflowExecution, QueryResult},
-        workflow_completion,
+        workflow_commands::{query_result, FailWorkflowExecution, QueryResult, QuerySuccess},
+        workflow_completion::{self, StackTrace},
+        AsJsonPayloadExt,
     },
     temporal::api::{
         command::v1::command::Attributes as CmdAttribs, enums::v1::WorkflowTaskFailedCause,
@@ -97,6 +99,12 @@ pub(super) struct ManagedRun {
     paginator: Option<HistoryPaginator>,
     completion_waiting_on_page_fetch: Option<RunActivationCompletion>,
     config: Arc<WorkerConfig>,
+    /// Set if lang asked for the current WFT to be heartbeated as soon as it is waiting on local
+    /// activities, rather than after the usual heartbeat interval.
+    heartbeat_forced: bool,
+    /// The stacks lang most recently reported for the workflow's tasks. Cleared whenever the
+    /// workflow is activated again, since they may no longer be accurate after that.
+    stack_trace: Option<StackTrace>,
 }
 impl ManagedRun {
     pub(super) fn new(
@@ -121,6 +129,8 @@ impl ManagedRun {
             paginator: None,
             completion_waiting_on_page_fetch: None,
             config,
+            heartbeat_forced: false,
+            stack_trace: None,
         };
         let rua = me.incoming_wft(wft);
         (me, rua)
@@ -211,6 +221,8 @@ impl ManagedRun {
         self.wft = Some(OutstandingTask {
             info: wft_info,
             pending_queries,
+            core_query_responses: vec![],
+            stack_trace_query_ids: vec![],
             start_time,
             permit: pwft.permit,
         });
@@ -257,6 +269,7 @@ impl ManagedRun {
                         self.local_activity_request_sink.as_ref(),
                         start_time,
                         lawait.wft_timeout,
+                        self.config.workflow_task_heartbeat_interval,
                     );
                     // No activation needs to be sent to lang. We just need to wait for another
                     // heartbeat timeout or LAs to resolve
@@ -285,6 +298,7 @@ impl ManagedRun {
     ) -> Option<OutstandingTask> {
         debug!("Marking WFT completed");
         let retme = self.wft.take();
+        self.heartbeat_forced = false;
 
         // Only record latency metrics if we genuinely reported to server
         if let WFTReportStatus::Reported {
@@ -373,10 +387,23 @@ impl ManagedRun {
         &mut self,
         mut commands: Vec<WFCommand>,
         used_flags: Vec<u32>,
+        stack_trace: Option<StackTrace>,
         resp_chan: Option<oneshot::Sender<ActivationCompleteResult>>,
     ) -> Result<RunUpdateAct, NextPageReq> {
         let activation_was_only_eviction = self.activation_has_only_eviction();
-        let (task_token, has_pending_query, start_time) = if let Some(entry) = self.wft.as_ref() {
+        if stack_trace.is_some() {
+            self.stack_trace = stack_trace;
+        }
+        let (task_token, has_pending_query, start_time) = if let Some(entry) = self.wft.as_mut() {
+            commands.extend(
+                entry
+                    .core_query_responses
+                    .drain(..)
+                    .map(WFCommand::QueryResponse),
+            );
+            commands.extend(entry.stack_trace_query_ids.drain(..).map(|id| {
+                WFCommand::QueryResponse(stack_trace_query_response(id, self.stack_trace.as_ref()))
+            }));
             (
                 entry.info.task_token.clone(),
                 !entry.pending_queries.is_empty(),
@@ -590,6 +617,7 @@ impl ManagedRun {
                             failure: failure.failure,
                         })],
                         vec![],
+                        None,
                         resp_chan,
                     )
                     .unwrap_or_else(|e| {
@@ -712,6 +740,11 @@ impl ManagedRun {
                 if let Some(wola) = self.waiting_on_la.as_mut() {
                     wola.hb_timeout_handle.abort();
                 }
+                let hb_interval = if self.heartbeat_forced {
+                    Some(Duration::ZERO)
+                } else {
+                    self.config.workflow_task_heartbeat_interval
+                };
                 self.waiting_on_la = Some(WaitingOnLAs {
                     wft_timeout,
                     completion_dat: Some((data, completion.resp_chan)),
@@ -720,6 +753,7 @@ impl ManagedRun {
                         self.local_activity_request_sink.as_ref(),
                         start_t,
                         wft_timeout,
+                        hb_interval,
                     ),
                 });
                 Ok(None)
@@ -763,6 +797,18 @@ impl ManagedRun {
         };
         self.update_to_acts(Ok(maybe_act.into()))
     }
+    /// Called when lang asks for the current WFT to be heartbeated. If the run is already waiting
+    /// on local activities the heartbeat happens right away, otherwise it will happen as soon as
+    /// the current activation completes with local activities outstanding.
+    pub(super) fn force_heartbeat(&mut self) -> RunUpdateAct {
+        self.wft.as_ref()?;
+        if self.waiting_on_la.is_some() {
+            return self.heartbeat_timeout();
+        }
+        self.heartbeat_forced = true;
+        None
+    }
+
     /// Returns `true` if autocompletion should be issued, which will actually cause us to end up
     /// in [completion] again, at which point we'll start a new heartbeat timeout, which will
     /// immediately trigger and thus finish the completion, forcing a new task as it should.
@@ -902,12 +948,24 @@ impl ManagedRun {
                         if activation.jobs.is_empty() {
                             dbg_panic!("Should not send lang activation with no jobs");
                         }
+                        self.stack_trace = None;
                         Some(ActivationOrAuto::LangActivation(activation))
                     }
                     Some(ActivationOrAuto::ReadyForQueries(mut act)) => {
                         if let Some(wft) = self.wft.as_mut() {
-                            put_queries_in_act(&mut act, wft);
-                            Some(ActivationOrAuto::LangActivation(act))
+                            put_queries_in_act(
+                                &mut act,
+                                wft,
+                                &self.wfm.machines,
+                                &self.config,
+                                self.stack_trace.as_ref(),
+                            );
+                            if act.jobs.is_empty() {
+                                // Core answered all the queries itself, so lang has nothing to do
+                                Some(ActivationOrAuto::Autocomplete { run_id: act.run_id })
+                            } else {
+                                Some(ActivationOrAuto::LangActivation(act))
+                            }
                         } else {
                             dbg_panic!("Ready for queries but no WFT!");
                             None
@@ -926,8 +984,7 @@ impl ManagedRun {
                                     reason.message.clone(),
                                     reason.reason,
                                 );
-                                evict_act.history_length =
-                                    self.most_recently_processed_event_number() as u32;
+                                self.wfm.machines.add_history_info(&mut evict_act);
                                 Some(ActivationOrAuto::LangActivation(evict_act))
                             } else {
                                 None
@@ -1185,8 +1242,17 @@ impl ManagedRun {
     }
 }
 
-/// Drains pending queries from the workflow task and appends them to the activation's jobs
-fn put_queries_in_act(act: &mut WorkflowActivation, wft: &mut OutstandingTask) {
+/// Drains pending queries from the workflow task and appends them to the activation's jobs. Queries
+/// `config` has core answer itself are responded to right away rather than being given to lang.
+/// Stack trace queries are answered with `stack_trace` if set, otherwise lang is asked to report
+/// stacks.
+fn put_queries_in_act(
+    act: &mut WorkflowActivation,
+    wft: &mut OutstandingTask,
+    machines: &WorkflowMachines,
+    config: &WorkerConfig,
+    stack_trace: Option<&StackTrace>,
+) {
     // Nothing to do if there are no pending queries
     if wft.pending_queries.is_empty() {
         return;
@@ -1200,20 +1266,64 @@ fn put_queries_in_act(act: &mut WorkflowActivation, wft: &mut OutstandingTask) {
     }
 
     debug!(queries=?wft.pending_queries, "Dispatching queries");
-    let query_jobs = wft
-        .pending_queries
-        .drain(..)
-        .map(|q| workflow_activation_job::Variant::QueryWorkflow(q).into());
-    act.jobs.extend(query_jobs);
+    for q in wft.pending_queries.drain(..) {
+        if config.answer_workflow_metadata_queries && q.query_type == WORKFLOW_METADATA_QUERY_TYPE {
+            wft.core_query_responses.push(QueryResult {
+                query_id: q.query_id,
+                variant: Some(query_result::Variant::Succeeded(QuerySuccess {
+                    response: Some(machines.workflow_metadata().as_payload()),
+                })),
+            });
+        } else if config.answer_stack_trace_queries && q.query_type == STACK_TRACE_QUERY_TYPE {
+            if stack_trace.is_some() {
+                wft.core_query_responses
+                    .push(stack_trace_query_response(q.query_id, stack_trace));
+            } else {
+                if wft.stack_trace_query_ids.is_empty() {
+                    act.jobs.push(
+                        workflow_activation_job::Variant::ReportStackTrace(ReportStackTrace {})
+                            .into(),
+                    );
+                }
+                wft.stack_trace_query_ids.push(q.query_id);
+            }
+        } else {
+            act.jobs
+                .push(workflow_activation_job::Variant::QueryWorkflow(q).into());
+        }
+    }
 }
+
+/// Answers a stack trace query with the stacks lang reported, or a failure if it reported none
+fn stack_trace_query_response(query_id: String, stack_trace: Option<&StackTrace>) -> QueryResult {
+    let variant = match stack_trace.and_then(|st| st.to_string().as_json_payload().ok()) {
+        Some(response) => query_result::Variant::Succeeded(QuerySuccess {
+            response: Some(response),
+        }),
+        None => query_result::Variant::Failed(Failure::application_failure(
+            "Lang did not report a stack trace for the workflow".to_string(),
+            false,
+        )),
+    };
+    QueryResult {
+        query_id,
+        variant: Some(variant),
+    }
+}
+
 fn sink_heartbeat_timeout_start(
     run_id: String,
     sink: &dyn LocalActivityRequestSink,
     wft_start_time: Instant,
     wft_timeout: Duration,
+    heartbeat_interval: Option<Duration>,
 ) -> AbortHandle {
-    // The heartbeat deadline is 80% of the WFT timeout
-    let deadline = wft_start_time.add(wft_timeout.mul_f32(WFT_HEARTBEAT_TIMEOUT_FRACTION));
+    // The heartbeat deadline is 80% of the WFT timeout, or sooner if configured/forced
+    let mut after = wft_timeout.mul_f32(WFT_HEARTBEAT_TIMEOUT_FRACTION);
+    if let Some(interval) = heartbeat_interval {
+        after = after.min(interval);
+    }
+    let deadline = wft_start_time.add(after);
     let (abort_handle, abort_reg) = AbortHandle::new_pair();
     sink.sink_reqs(vec![LocalActRequest::StartHeartbeatTimeout {
         send_on_elapse: HeartbeatTimeoutMsg {
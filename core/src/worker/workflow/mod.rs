@@ -6,6 +6,7 @@ mod driven_workflow;
 mod history_update;
 mod machines;
 mod managed_run;
+mod payload_limits;
 mod run_cache;
 mod wft_extraction;
 pub(crate) mod wft_poller;
@@ -20,6 +21,7 @@ use crate::{
         UsedMeteredSemPermit,
     },
     internal_flags::InternalFlags,
+    pollers::DEFAULT_PRIORITY_KEY,
     protosext::{legacy_query_failure, protocol_messages::IncomingProtocolMessage},
     telemetry::{set_trace_subscriber_for_current_thread, TelemetryInstance, VecDisplayer},
     worker::{
@@ -28,6 +30,7 @@ use crate::{
         workflow::{
             history_update::HistoryPaginator,
             managed_run::RunUpdateAct,
+            payload_limits::enforce_completion_size,
             wft_extraction::{HistoryFetchReq, WFTExtractor, WFTStreamIn},
             wft_poller::validate_wft,
             workflow_stream::{LocalInput, LocalInputs, WFStream},
@@ -51,14 +54,14 @@ use std::{
     mem::discriminant,
     ops::DerefMut,
     rc::Rc,
-    result,
+    result, slice,
     sync::{atomic, atomic::AtomicBool, Arc},
     thread,
     time::{Duration, Instant},
 };
 use temporal_sdk_core_api::{
     errors::{CompleteWfError, PollWfError},
-    worker::WorkerConfig,
+    worker::{PayloadSizeLimits, WorkerConfig},
 };
 use temporal_sdk_core_protos::{
     coresdk::{
@@ -69,7 +72,7 @@ use temporal_sdk_core_protos::{
         workflow_commands::*,
         workflow_completion,
         workflow_completion::{
-            workflow_activation_completion, Failure, WorkflowActivationCompletion,
+            workflow_activation_completion, Failure, StackTrace, WorkflowActivationCompletion,
         },
     },
     temporal::api::{
@@ -125,6 +128,8 @@ pub(crate) struct Workflows {
     wft_semaphore: Arc<MeteredSemaphore>,
     local_act_mgr: Arc<LocalActivityManager>,
     ever_polled: AtomicBool,
+    /// Checked against workflow task completions before they are sent
+    payload_limits: PayloadSizeLimits,
 }
 
 pub(crate) struct WorkflowBasics {
@@ -162,6 +167,7 @@ impl Workflows {
         let (fetch_tx, fetch_rx) = unbounded_channel();
         let shutdown_tok = basics.shutdown_token.clone();
         let task_queue = basics.worker_config.task_queue.clone();
+        let payload_limits = basics.worker_config.payload_size_limits;
         let extracted_wft_stream = WFTExtractor::build(
             client.clone(),
             basics.worker_config.fetching_concurrency,
@@ -245,6 +251,7 @@ impl Workflows {
             wft_semaphore,
             local_act_mgr,
             ever_polled: AtomicBool::new(false),
+            payload_limits,
         }
     }
 
@@ -341,7 +348,8 @@ impl Workflows {
         };
 
         let mut wft_from_complete = None;
-        let wft_report_status = match completion_outcome.outcome {
+        let outcome = self.enforce_payload_limits(&run_id, completion_outcome.outcome);
+        let wft_report_status = match outcome {
             ActivationCompleteOutcome::ReportWFTSuccess(report) => match report {
                 ServerCommandsWithWorkflowInfo {
                     task_token,
@@ -482,6 +490,59 @@ impl Workflows {
         });
     }
 
+    /// Brings a successful completion, or legacy query answer, within the configured payload size
+    /// limits. If the workflow's own commands exceed them, they are replaced with one failing the
+    /// workflow, describing what was too large. Failing the workflow task instead would only see
+    /// the same oversized completion produced again on every retry.
+    fn enforce_payload_limits(
+        &self,
+        run_id: &str,
+        mut outcome: ActivationCompleteOutcome,
+    ) -> ActivationCompleteOutcome {
+        if let ActivationCompleteOutcome::ReportWFTSuccess(ServerCommandsWithWorkflowInfo {
+            action,
+            ..
+        }) = &mut outcome
+        {
+            match action {
+                ActivationAction::WftComplete {
+                    commands,
+                    messages,
+                    query_responses,
+                    ..
+                } => {
+                    if let Err(msg) = enforce_completion_size(
+                        &self.payload_limits,
+                        run_id,
+                        commands,
+                        messages,
+                        query_responses,
+                    ) {
+                        warn!(run_id, "Failing workflow: {msg}");
+                        // The run believes the original commands were sent, so it can't carry
+                        // on from here
+                        self.request_eviction(
+                            run_id,
+                            "Workflow task completion exceeded payload size limits",
+                            EvictionReason::Fatal,
+                        );
+                    }
+                }
+                ActivationAction::RespondLegacyQuery { result } => {
+                    // With no commands, only the answer itself can be failed
+                    let _ = enforce_completion_size(
+                        &self.payload_limits,
+                        run_id,
+                        &mut vec![],
+                        &mut [],
+                        slice::from_mut(result.as_mut()),
+                    );
+                }
+            }
+        }
+        outcome
+    }
+
     /// Request eviction of a workflow
     pub(super) fn request_eviction(
         &self,
@@ -497,6 +558,12 @@ impl Workflows {
         });
     }
 
+    /// Request that the current WFT of a run be heartbeated as soon as it is waiting on local
+    /// activities
+    pub(super) fn force_wft_heartbeat(&self, run_id: impl Into<String>) {
+        self.send_local(LocalInputs::ForceHeartbeat(run_id.into()));
+    }
+
     /// Send a `GetStateInfoMsg` to the workflow stream. Can be used to bump the stream if there
     /// would otherwise be no new inputs.
     pub(super) fn send_get_state_info_msg(&self) -> oneshot::Receiver<WorkflowStateInfo> {
@@ -674,14 +741,19 @@ impl Workflows {
                         .as_ref()
                         .map(|q| q.name == self.task_queue)
                         .unwrap_or_default();
+                    let high_priority = attrs.priority.as_ref().is_some_and(|p| {
+                        p.priority_key > 0 && p.priority_key < DEFAULT_PRIORITY_KEY
+                    });
                     if same_task_queue
                         && reserved.len() < MAX_EAGER_ACTIVITY_RESERVATIONS_PER_WORKFLOW_TASK
                     {
-                        if let Some(p) = self
-                            .activity_tasks_handle
-                            .as_ref()
-                            .and_then(|h| h.reserve_slot())
-                        {
+                        if let Some(p) = self.activity_tasks_handle.as_ref().and_then(|h| {
+                            if high_priority {
+                                h.reserve_slot_preempting()
+                            } else {
+                                h.reserve_slot()
+                            }
+                        }) {
                             reserved.push(p);
                         } else {
                             attrs.request_eager_execution = false;
@@ -800,6 +872,12 @@ pub(crate) struct OutstandingTask {
     pub info: WorkflowTaskInfo,
     /// Set if the outstanding task has quer(ies) which must be fulfilled upon finishing replay
     pub pending_queries: Vec<QueryWorkflow>,
+    /// Responses to queries core answers itself, like the workflow metadata query. They're sent
+    /// along with the completion of the activation the queries would otherwise have been in.
+    pub core_query_responses: Vec<QueryResult>,
+    /// Ids of stack trace queries waiting for lang to report stacks in reply to a
+    /// `ReportStackTrace` job
+    pub stack_trace_query_ids: Vec<String>,
     pub start_time: Instant,
     /// The WFT permit owned by this task, ensures we don't exceed max concurrent WFT, and makes
     /// sure the permit is automatically freed when we delete the task.
@@ -811,6 +889,10 @@ impl OutstandingTask {
         self.pending_queries
             .iter()
             .any(|q| q.query_id == LEGACY_QUERY_ID)
+            || self
+                .stack_trace_query_ids
+                .iter()
+                .any(|id| id == LEGACY_QUERY_ID)
     }
 }
 
@@ -1085,6 +1167,7 @@ fn validate_completion(
                 run_id: completion.run_id,
                 commands,
                 used_flags: success.used_internal_flags,
+                stack_trace: success.stack_trace,
             })
         }
         Some(workflow_activation_completion::Status::Failed(failure)) => {
@@ -1108,6 +1191,7 @@ enum ValidatedCompletion {
         run_id: String,
         commands: Vec<WFCommand>,
         used_flags: Vec<u32>,
+        stack_trace: Option<StackTrace>,
     },
     Fail {
         run_id: String,
@@ -1170,6 +1254,10 @@ pub enum WFCommand {
     UpsertSearchAttributes(UpsertWorkflowSearchAttributes),
     ModifyWorkflowProperties(ModifyWorkflowProperties),
     UpdateResponse(UpdateResponse),
+    MutableSideEffect(MutableSideEffect),
+    SetWorkflowMetadata(SetWorkflowMetadata),
+    AddNexusOperation(ScheduleNexusOperation),
+    RequestCancelNexusOperation(RequestCancelNexusOperation),
 }
 
 impl TryFrom<WorkflowCommand> for WFCommand {
@@ -1215,6 +1303,12 @@ impl TryFrom<WorkflowCommand> for WFCommand {
                 Ok(Self::ModifyWorkflowProperties(s))
             }
             workflow_command::Variant::UpdateResponse(s) => Ok(Self::UpdateResponse(s)),
+            workflow_command::Variant::MutableSideEffect(s) => Ok(Self::MutableSideEffect(s)),
+            workflow_command::Variant::SetWorkflowMetadata(s) => Ok(Self::SetWorkflowMetadata(s)),
+            workflow_command::Variant::ScheduleNexusOperation(s) => Ok(Self::AddNexusOperation(s)),
+            workflow_command::Variant::RequestCancelNexusOperation(s) => {
+                Ok(Self::RequestCancelNexusOperation(s))
+            }
         }
     }
 }
@@ -1244,6 +1338,7 @@ enum CommandID {
     ChildWorkflowStart(u32),
     SignalExternal(u32),
     CancelExternal(u32),
+    NexusOperation(u32),
 }
 
 /// Details remembered from the workflow execution started event that we may need to recall later.
@@ -1350,18 +1445,18 @@ impl LocalActivityRequestSink for LAReqSink {
 /// ## Invariants:
 /// * Queries always go in their own activation
 fn prepare_to_ship_activation(wfa: &mut WorkflowActivation) {
-    let any_job_is_query = wfa.jobs.iter().any(|j| {
+    // Lang reports stacks to answer stack trace queries, so that job goes with the queries
+    let is_query_job = |j: &WorkflowActivationJob| {
         matches!(
             j.variant,
-            Some(workflow_activation_job::Variant::QueryWorkflow(_))
-        )
-    });
-    let all_jobs_are_query = wfa.jobs.iter().all(|j| {
-        matches!(
-            j.variant,
-            Some(workflow_activation_job::Variant::QueryWorkflow(_))
+            Some(
+                workflow_activation_job::Variant::QueryWorkflow(_)
+                    | workflow_activation_job::Variant::ReportStackTrace(_)
+            )
         )
-    });
+    };
+    let any_job_is_query = wfa.jobs.iter().any(is_query_job);
+    let all_jobs_are_query = wfa.jobs.iter().all(is_query_job);
     if any_job_is_query && !all_jobs_are_query {
         dbg_panic!(
             "About to issue an activation that contains query jobs with non-query jobs: {:?}",
@@ -1383,7 +1478,8 @@ fn prepare_to_ship_activation(wfa: &mut WorkflowActivation) {
                 // In principle we should never actually need to sort these with the others, since
                 // queries always get their own activation, but, maintaining the semantic is
                 // reasonable.
-                workflow_activation_job::Variant::QueryWorkflow(_) => 4,
+                workflow_activation_job::Variant::QueryWorkflow(_)
+                | workflow_activation_job::Variant::ReportStackTrace(_) => 4,
                 // Also shouldn't ever end up anywhere but the end by construction, but no harm in
                 // double-checking.
                 workflow_activation_job::Variant::RemoveFromCache(_) => 5,
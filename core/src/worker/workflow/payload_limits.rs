@@ -0,0 +1,411 @@
+//! Checks outgoing workflow task completions against the configured [PayloadSizeLimits], so that
+//! oversized completions fail what was too large with a descriptive error instead of being
+//! rejected by the server as an opaque `ResourceExhausted`.
+
+use prost::Message;
+use std::{collections::BTreeMap, fmt::Write};
+use temporal_sdk_core_api::worker::PayloadSizeLimits;
+use temporal_sdk_core_protos::{
+    coresdk::workflow_commands::{query_result, QueryResult},
+    temporal::api::{
+        command::v1::{Command, FailWorkflowExecutionCommandAttributes},
+        enums::v1::CommandType,
+        failure::v1::{failure::FailureInfo, ApplicationFailureInfo, Failure},
+        protocol::v1::Message as ProtocolMessage,
+        update::v1::{outcome, Outcome, Response},
+    },
+};
+
+/// Application failure type of the failure used when a completion exceeds the limits
+pub(super) const PAYLOAD_LIMIT_FAILURE_TYPE: &str = "PayloadSizeLimitExceeded";
+
+const UPDATE_RESPONSE_TYPE_URL: &str = "type.googleapis.com/temporal.api.update.v1.Response";
+
+/// Brings a completion within the limits, logging warnings for any sizes over the warning
+/// thresholds.
+///
+/// Update results and query answers which are too large, or which push the completion over its
+/// limit, are failed on their own, leaving the workflow alone. Only if the workflow's commands are
+/// themselves too large is the workflow failed: the commands are then replaced with one failing it
+/// (keeping any protocol message commands, so update responses are still delivered in order), and
+/// a description of the problem is returned.
+pub(super) fn enforce_completion_size(
+    limits: &PayloadSizeLimits,
+    run_id: &str,
+    commands: &mut Vec<Command>,
+    messages: &mut [ProtocolMessage],
+    query_responses: &mut [QueryResult],
+) -> Result<(), String> {
+    if limits == &PayloadSizeLimits::default() {
+        return Ok(());
+    }
+
+    let commands_result = check_commands(limits, run_id, commands);
+    if let Err(msg) = &commands_result {
+        let failure = limit_exceeded_failure(msg.clone());
+        commands.retain(|c| c.command_type == CommandType::ProtocolMessage as i32);
+        commands.push(fail_workflow_command(failure));
+    }
+    let mut total = commands.iter().map(Message::encoded_len).sum::<usize>();
+
+    let mut message_sizes = vec![];
+    for msg in messages.iter_mut() {
+        let mut size = msg.encoded_len();
+        if let Some(limit) = limits.command_error_bytes.filter(|l| size > *l) {
+            if fail_update_result(
+                msg,
+                format!("Update result is {size} bytes, exceeding the limit of {limit} bytes"),
+            ) {
+                warn!(run_id, size, "Failing update whose result is too large");
+                size = msg.encoded_len();
+            }
+        }
+        total += size;
+        message_sizes.push(size);
+    }
+    let mut query_sizes = query_responses
+        .iter()
+        .map(Message::encoded_len)
+        .collect::<Vec<_>>();
+    total += query_sizes.iter().sum::<usize>();
+
+    if let Some(limit) = limits.completion_error_bytes.filter(|l| total > *l) {
+        let exceeded = |total| {
+            format!(
+                "The workflow task completion would be {total} bytes, exceeding the limit of \
+                 {limit} bytes"
+            )
+        };
+        // Query answers go first, since they can be asked for again without any harm done
+        for ix in indices_by_size_desc(&query_sizes) {
+            if total <= limit {
+                break;
+            }
+            let qr = &mut query_responses[ix];
+            if !matches!(qr.variant, Some(query_result::Variant::Succeeded(_))) {
+                continue;
+            }
+            warn!(
+                run_id,
+                query_id = %qr.query_id,
+                size = query_sizes[ix],
+                "Failing query whose answer doesn't fit in the completion"
+            );
+            qr.variant = Some(query_result::Variant::Failed(limit_exceeded_failure(
+                exceeded(total),
+            )));
+            let new_size = qr.encoded_len();
+            total = total - query_sizes[ix] + new_size;
+            query_sizes[ix] = new_size;
+        }
+        for ix in indices_by_size_desc(&message_sizes) {
+            if total <= limit {
+                break;
+            }
+            let msg = &mut messages[ix];
+            if fail_update_result(msg, exceeded(total)) {
+                warn!(
+                    run_id,
+                    size = message_sizes[ix],
+                    "Failing update whose result doesn't fit in the completion"
+                );
+                let new_size = msg.encoded_len();
+                total = total - message_sizes[ix] + new_size;
+                message_sizes[ix] = new_size;
+            }
+        }
+    }
+    if limits.completion_warn_bytes.is_some_and(|l| total > l) {
+        warn!(
+            run_id,
+            size = total,
+            "Workflow task completion is unusually large"
+        );
+    }
+
+    commands_result
+}
+
+/// Returns a description of the problem if any command, or all of them together, are over the
+/// error thresholds
+fn check_commands(
+    limits: &PayloadSizeLimits,
+    run_id: &str,
+    commands: &[Command],
+) -> Result<(), String> {
+    let mut problems = vec![];
+    let mut by_type: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut total = 0;
+    for (ix, cmd) in commands.iter().enumerate() {
+        let size = cmd.encoded_len();
+        total += size;
+        let cmd_type = command_type_name(cmd);
+        let entry = by_type.entry(cmd_type.clone()).or_default();
+        entry.0 += 1;
+        entry.1 += size;
+        if let Some(limit) = limits.command_error_bytes.filter(|l| size > *l) {
+            problems.push(format!(
+                "command #{ix} ({cmd_type}) is {size} bytes, exceeding the limit of {limit} bytes"
+            ));
+        } else if limits.command_warn_bytes.is_some_and(|l| size > l) {
+            warn!(run_id, command_type = %cmd_type, size, "Workflow command is unusually large");
+        }
+    }
+    if let Some(limit) = limits.completion_error_bytes.filter(|l| total > *l) {
+        problems.push(format!(
+            "the workflow's commands are {total} bytes, exceeding the completion limit of \
+             {limit} bytes"
+        ));
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let mut msg = format!("Payload size limits exceeded: {}.", problems.join("; "));
+    msg.push_str(" Size by command type:");
+    for (cmd_type, (count, bytes)) in by_type {
+        let _ = write!(msg, " {cmd_type}: {count} command(s), {bytes} bytes;");
+    }
+    msg.pop();
+    Err(msg)
+}
+
+/// If `msg` is an update response carrying a successful result, replaces the result with a
+/// failure described by `message` and returns true
+fn fail_update_result(msg: &mut ProtocolMessage, message: String) -> bool {
+    let Some(body) = msg
+        .body
+        .as_mut()
+        .filter(|b| b.type_url == UPDATE_RESPONSE_TYPE_URL)
+    else {
+        return false;
+    };
+    let Ok(mut resp) = Response::decode(body.value.as_slice()) else {
+        return false;
+    };
+    if !matches!(
+        resp.outcome,
+        Some(Outcome {
+            value: Some(outcome::Value::Success(_))
+        })
+    ) {
+        return false;
+    }
+    resp.outcome = Some(Outcome {
+        value: Some(outcome::Value::Failure(limit_exceeded_failure(message))),
+    });
+    body.value = resp.encode_to_vec();
+    true
+}
+
+fn indices_by_size_desc(sizes: &[usize]) -> Vec<usize> {
+    let mut ixs = (0..sizes.len()).collect::<Vec<_>>();
+    ixs.sort_by_key(|ix| std::cmp::Reverse(sizes[*ix]));
+    ixs
+}
+
+/// The failure an oversized completion is replaced with. It's non-retryable, since a retry would
+/// most likely produce the same completion again.
+fn limit_exceeded_failure(message: String) -> Failure {
+    Failure {
+        message,
+        failure_info: Some(FailureInfo::ApplicationFailureInfo(
+            ApplicationFailureInfo {
+                r#type: PAYLOAD_LIMIT_FAILURE_TYPE.to_string(),
+                non_retryable: true,
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+fn fail_workflow_command(failure: Failure) -> Command {
+    Command {
+        command_type: CommandType::FailWorkflowExecution as i32,
+        attributes: Some(
+            FailWorkflowExecutionCommandAttributes {
+                failure: Some(failure),
+            }
+            .into(),
+        ),
+        user_metadata: None,
+    }
+}
+
+fn command_type_name(cmd: &Command) -> String {
+    CommandType::try_from(cmd.command_type)
+        .map(|t| format!("{t:?}"))
+        .unwrap_or_else(|_| format!("Unknown({})", cmd.command_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::{
+        coresdk::workflow_commands::QuerySuccess,
+        temporal::api::{
+            command::v1::{
+                command::Attributes, ScheduleActivityTaskCommandAttributes,
+                StartTimerCommandAttributes,
+            },
+            common::v1::{Payload, Payloads},
+        },
+        utilities::pack_any,
+    };
+
+    fn big_activity(bytes: usize) -> Command {
+        Attributes::ScheduleActivityTaskCommandAttributes(ScheduleActivityTaskCommandAttributes {
+            activity_id: "act".to_string(),
+            input: Some(Payloads {
+                payloads: vec![Payload {
+                    metadata: Default::default(),
+                    data: vec![0; bytes],
+                }],
+            }),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn timer() -> Command {
+        Attributes::StartTimerCommandAttributes(StartTimerCommandAttributes {
+            timer_id: "timer".to_string(),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn big_query_answer(bytes: usize) -> QueryResult {
+        QueryResult {
+            query_id: "q".to_string(),
+            variant: Some(
+                QuerySuccess {
+                    response: Some(Payload {
+                        metadata: Default::default(),
+                        data: vec![0; bytes],
+                    }),
+                }
+                .into(),
+            ),
+        }
+    }
+
+    fn big_update_result(bytes: usize) -> ProtocolMessage {
+        let resp = Response {
+            meta: None,
+            outcome: Some(Outcome {
+                value: Some(outcome::Value::Success(Payloads {
+                    payloads: vec![Payload {
+                        metadata: Default::default(),
+                        data: vec![0; bytes],
+                    }],
+                })),
+            }),
+        };
+        ProtocolMessage {
+            id: "upd/complete".to_string(),
+            body: Some(pack_any(UPDATE_RESPONSE_TYPE_URL.to_string(), &resp).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn update_failed(msg: &ProtocolMessage) -> bool {
+        let resp = Response::decode(msg.body.as_ref().unwrap().value.as_slice()).unwrap();
+        matches!(
+            resp.outcome,
+            Some(Outcome {
+                value: Some(outcome::Value::Failure(_))
+            })
+        )
+    }
+
+    #[test]
+    fn within_limits_is_ok() {
+        let limits = PayloadSizeLimits {
+            command_error_bytes: Some(1000),
+            completion_error_bytes: Some(2000),
+            ..Default::default()
+        };
+        let cmds = [big_activity(500), timer()];
+        assert!(check_commands(&limits, "run", &cmds).is_ok());
+    }
+
+    #[test]
+    fn oversized_command_reports_breakdown() {
+        let limits = PayloadSizeLimits {
+            command_error_bytes: Some(1000),
+            ..Default::default()
+        };
+        let cmds = [timer(), big_activity(5000), big_activity(10)];
+        let err = check_commands(&limits, "run", &cmds).unwrap_err();
+        assert!(err.contains("command #1 (ScheduleActivityTask)"));
+        assert!(!err.contains("command #2"));
+        assert!(err.contains("ScheduleActivityTask: 2 command(s)"));
+        assert!(err.contains("StartTimer: 1 command(s)"));
+    }
+
+    #[test]
+    fn oversized_completion_fails_even_if_commands_are_small() {
+        let limits = PayloadSizeLimits {
+            command_error_bytes: Some(1000),
+            completion_error_bytes: Some(2000),
+            ..Default::default()
+        };
+        let cmds = [big_activity(900), big_activity(900), big_activity(900)];
+        let err = check_commands(&limits, "run", &cmds).unwrap_err();
+        assert!(err.contains("the workflow's commands are"));
+        assert!(!err.contains("command #"));
+    }
+
+    #[test]
+    fn query_answer_over_completion_limit_fails_only_the_query() {
+        let limits = PayloadSizeLimits {
+            completion_error_bytes: Some(2000),
+            ..Default::default()
+        };
+        let mut cmds = vec![big_activity(500), timer()];
+        let orig_cmds = cmds.clone();
+        let mut queries = [big_query_answer(10), big_query_answer(3000)];
+        enforce_completion_size(&limits, "run", &mut cmds, &mut [], &mut queries).unwrap();
+        assert_eq!(cmds, orig_cmds);
+        assert_matches!(
+            queries[0].variant,
+            Some(query_result::Variant::Succeeded(_))
+        );
+        assert_matches!(queries[1].variant, Some(query_result::Variant::Failed(_)));
+    }
+
+    #[test]
+    fn oversized_update_result_fails_only_the_update() {
+        let limits = PayloadSizeLimits {
+            command_error_bytes: Some(1000),
+            ..Default::default()
+        };
+        let mut cmds = vec![timer()];
+        let mut msgs = [big_update_result(5000)];
+        enforce_completion_size(&limits, "run", &mut cmds, &mut msgs, &mut []).unwrap();
+        assert_eq!(cmds, vec![timer()]);
+        assert!(update_failed(&msgs[0]));
+    }
+
+    #[test]
+    fn oversized_command_fails_workflow_but_keeps_protocol_messages() {
+        let limits = PayloadSizeLimits {
+            command_error_bytes: Some(1000),
+            ..Default::default()
+        };
+        let protocol_cmd: Command =
+            Attributes::ProtocolMessageCommandAttributes(Default::default()).into();
+        let mut cmds = vec![protocol_cmd.clone(), big_activity(5000)];
+        let mut msgs = [big_update_result(10)];
+        enforce_completion_size(&limits, "run", &mut cmds, &mut msgs, &mut []).unwrap_err();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0], protocol_cmd);
+        assert_eq!(
+            cmds[1].command_type,
+            CommandType::FailWorkflowExecution as i32
+        );
+        assert!(!update_failed(&msgs[0]));
+    }
+}
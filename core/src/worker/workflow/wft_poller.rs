@@ -5,7 +5,10 @@ use crate::{
     MetricsContext,
 };
 use futures::{stream, Stream};
-use temporal_sdk_core_protos::temporal::api::workflowservice::v1::PollWorkflowTaskQueueResponse;
+use temporal_sdk_core_protos::temporal::api::{
+    enums::v1::TimeoutType, history::v1::history_event::Attributes,
+    workflowservice::v1::PollWorkflowTaskQueueResponse,
+};
 
 pub(crate) fn new_wft_poller(
     poller: BoxedWFPoller,
@@ -24,6 +27,10 @@ pub(crate) fn new_wft_poller(
                     if let Some(dur) = wft.sched_to_start() {
                         metrics.wf_task_sched_to_start_latency(dur);
                     }
+                    let fallbacks = sticky_fallbacks_since_last_wft(&wft);
+                    if fallbacks > 0 {
+                        metrics.sticky_queue_fallback(fallbacks);
+                    }
                     let work = match validate_wft(wft) {
                         Ok(w) => w,
                         Err(e) => {
@@ -51,6 +58,21 @@ pub(crate) fn new_wft_poller(
     })
 }
 
+/// Counts workflow tasks which timed out waiting to be picked up from a sticky queue (and were thus
+/// rescheduled on the normal queue) since the last workflow task this workflow completed. The
+/// server only ever applies a schedule-to-start timeout to sticky workflow tasks.
+fn sticky_fallbacks_since_last_wft(wft: &PollWorkflowTaskQueueResponse) -> u64 {
+    wft.history
+        .iter()
+        .flat_map(|h| h.events.iter())
+        .filter(|e| e.event_id > wft.previous_started_event_id)
+        .filter(|e| {
+            matches!(&e.attributes, Some(Attributes::WorkflowTaskTimedOutEventAttributes(a))
+                if a.timeout_type == TimeoutType::ScheduleToStart as i32)
+        })
+        .count() as u64
+}
+
 pub(crate) fn validate_wft(
     wft: PollWorkflowTaskQueueResponse,
 ) -> Result<ValidPollWFTQResponse, tonic::Status> {
@@ -112,4 +134,34 @@ mod tests {
         pin_mut!(stream);
         assert_matches!(stream.next().await, Some(Err(_)));
     }
+
+    #[test]
+    fn counts_sticky_fallbacks_after_previous_started() {
+        use temporal_sdk_core_protos::temporal::api::history::v1::{
+            History, HistoryEvent, WorkflowTaskTimedOutEventAttributes,
+        };
+        let timed_out = |event_id, timeout_type: TimeoutType| HistoryEvent {
+            event_id,
+            attributes: Some(Attributes::WorkflowTaskTimedOutEventAttributes(
+                WorkflowTaskTimedOutEventAttributes {
+                    timeout_type: timeout_type as i32,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+        let wft = PollWorkflowTaskQueueResponse {
+            previous_started_event_id: 3,
+            history: Some(History {
+                events: vec![
+                    timed_out(2, TimeoutType::ScheduleToStart),
+                    timed_out(5, TimeoutType::ScheduleToStart),
+                    timed_out(8, TimeoutType::StartToClose),
+                    timed_out(10, TimeoutType::ScheduleToStart),
+                ],
+            }),
+            ..Default::default()
+        };
+        assert_eq!(sticky_fallbacks_since_last_wft(&wft), 2);
+    }
 }
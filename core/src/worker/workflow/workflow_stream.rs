@@ -133,6 +133,9 @@ impl WFStream {
                             LocalInputs::HeartbeatTimeout(hbt) => {
                                 state.process_heartbeat_timeout(hbt)
                             }
+                            LocalInputs::ForceHeartbeat(run_id) => {
+                                state.process_force_heartbeat(run_id)
+                            }
                             LocalInputs::RequestEviction(evict) => {
                                 state.request_eviction(evict).into_run_update_resp()
                             }
@@ -262,8 +265,14 @@ impl WFStream {
                 ValidatedCompletion::Success {
                     commands,
                     used_flags,
+                    stack_trace,
                     ..
-                } => match rh.successful_completion(commands, used_flags, complete.response_tx) {
+                } => match rh.successful_completion(
+                    commands,
+                    used_flags,
+                    stack_trace,
+                    complete.response_tx,
+                ) {
                     Ok(acts) => acts,
                     Err(npr) => {
                         self.runs_needing_fetching
@@ -394,6 +403,14 @@ impl WFStream {
         }
     }
 
+    fn process_force_heartbeat(&mut self, run_id: String) -> RunUpdateAct {
+        if let Some(rh) = self.runs.get_mut(&run_id) {
+            rh.force_heartbeat()
+        } else {
+            None
+        }
+    }
+
     /// Request a workflow eviction. This will (eventually, after replay is done) queue up an
     /// activation to evict the workflow from the lang side. Workflow will not *actually* be evicted
     /// until lang replies to that activation
@@ -430,11 +447,13 @@ impl WFStream {
         // want to clear the workflow task. This can really only happen in replay testing, where we
         // will generate poll responses with complete history but no attached query, and such a WFT
         // would never really exist. The server wouldn't send a workflow task with nothing to do,
-        // but they are very useful for testing complete replay.
+        // but they are very useful for testing complete replay. Jobs can only be pending after the
+        // final event when that event produced them, as termination does, and lang must still see
+        // those before the task is done.
         let saw_final = self
             .runs
             .get(run_id)
-            .map(|r| r.have_seen_terminal_event())
+            .map(|r| r.have_seen_terminal_event() && !r.more_pending_work())
             .unwrap_or_default();
         if !saw_final && matches!(wft_report_status, WFTReportStatus::NotReported) {
             return None;
@@ -589,6 +608,8 @@ pub(super) enum LocalInputs {
     PostActivation(PostActivationMsg),
     RequestEviction(RequestEvictMsg),
     HeartbeatTimeout(String),
+    #[from(ignore)]
+    ForceHeartbeat(String),
     GetStateInfo(GetStateInfoMsg),
 }
 impl LocalInputs {
@@ -600,6 +621,7 @@ impl LocalInputs {
             LocalInputs::PostActivation(pa) => &pa.run_id,
             LocalInputs::RequestEviction(re) => &re.run_id,
             LocalInputs::HeartbeatTimeout(hb) => hb,
+            LocalInputs::ForceHeartbeat(run_id) => run_id,
             LocalInputs::GetStateInfo(_) => return None,
         })
     }
@@ -61,6 +61,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             "coresdk.workflow_activation.WorkflowActivationJob.variant",
             "#[derive(::derive_more::From)]",
         )
+        .type_attribute(
+            "coresdk.workflow_activation.NotifyLateResolution.resolution",
+            "#[derive(::derive_more::From)]",
+        )
         .type_attribute(
             "coresdk.workflow_completion.WorkflowActivationCompletion.status",
             "#[derive(::derive_more::From)]",
@@ -118,14 +122,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "./protos/local/temporal/sdk/core/core_interface.proto",
                 "./protos/api_upstream/temporal/api/workflowservice/v1/service.proto",
                 "./protos/api_upstream/temporal/api/operatorservice/v1/service.proto",
+                "./protos/api_upstream/temporal/api/errordetails/v1/message.proto",
+                "./protos/api_cloud_upstream/temporal/api/cloud/cloudservice/v1/service.proto",
                 "./protos/testsrv_upstream/temporal/api/testservice/v1/service.proto",
                 "./protos/grpc/health/v1/health.proto",
+                "./protos/google/rpc/status.proto",
             ],
             &[
                 "./protos/api_upstream",
+                "./protos/api_cloud_upstream",
                 "./protos/local",
                 "./protos/testsrv_upstream",
                 "./protos/grpc",
+                "./protos",
             ],
         )?;
 
@@ -3,5 +3,28 @@
 /// Used as `marker_name` field when recording patch markers
 pub const PATCH_MARKER_NAME: &str = "core_patch";
 
+/// Used as `marker_name` field when recording mutable side effect markers
+pub const MUTABLE_SIDE_EFFECT_MARKER_NAME: &str = "core_mutable_side_effect";
+
+/// Key in mutable side effect marker details holding the marker's metadata
+pub const MUTABLE_SIDE_EFFECT_MARKER_DATA_KEY: &str = "data";
+
+/// Key in mutable side effect marker details holding the recorded value
+pub const MUTABLE_SIDE_EFFECT_MARKER_VALUE_KEY: &str = "value";
+
 /// Used as `marker_name` field when recording local activity markers
 pub const LOCAL_ACTIVITY_MARKER_NAME: &str = "core_local_activity";
+
+/// Key in local activity marker details holding the last progress the activity reported, if any
+pub const LOCAL_ACTIVITY_MARKER_PROGRESS_KEY: &str = "progress";
+
+/// Type of the built-in query core answers itself with the workflow's metadata, as set by
+/// `SetWorkflowMetadata` commands
+pub const WORKFLOW_METADATA_QUERY_TYPE: &str = "__temporal_workflow_metadata";
+
+/// Type of the built-in query answered with the stacks lang reports for a workflow's tasks
+pub const STACK_TRACE_QUERY_TYPE: &str = "__stack_trace";
+
+/// Header under which trace context is propagated between workflows, activities, and the clients
+/// which start them. Other Temporal SDKs use the same key, so traces connect across languages.
+pub const TRACING_HEADER_KEY: &str = "_tracer-data";
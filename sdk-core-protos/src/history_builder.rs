@@ -1,11 +1,11 @@
 use crate::{
-    constants::{LOCAL_ACTIVITY_MARKER_NAME, PATCH_MARKER_NAME},
+    constants::{LOCAL_ACTIVITY_MARKER_NAME, MUTABLE_SIDE_EFFECT_MARKER_NAME, PATCH_MARKER_NAME},
     coresdk::{
         common::{
             build_has_change_marker_details, build_local_activity_marker_details,
-            NamespacedWorkflowExecution,
+            build_mutable_side_effect_marker_details, NamespacedWorkflowExecution,
         },
-        external_data::LocalActivityMarkerData,
+        external_data::{LocalActivityMarkerData, MutableSideEffectMarkerData},
         workflow_commands::ScheduleActivity,
         AsJsonPayloadExt, IntoPayloadsExt,
     },
@@ -260,9 +260,42 @@ impl TestHistoryBuilder {
     }
 
     pub fn add_has_change_marker(&mut self, patch_id: &str, deprecated: bool) {
+        self.add_has_change_marker_with_data(patch_id, deprecated, None)
+    }
+
+    pub fn add_has_change_marker_with_data(
+        &mut self,
+        patch_id: &str,
+        deprecated: bool,
+        data: Option<Payload>,
+    ) {
         let attrs = MarkerRecordedEventAttributes {
             marker_name: PATCH_MARKER_NAME.to_string(),
-            details: build_has_change_marker_details(patch_id, deprecated).unwrap(),
+            details: build_has_change_marker_details(patch_id, deprecated, data).unwrap(),
+            workflow_task_completed_event_id: self.previous_task_completed_id,
+            ..Default::default()
+        };
+        self.build_and_push_event(EventType::MarkerRecorded, attrs.into());
+    }
+
+    pub fn add_mutable_side_effect_marker(
+        &mut self,
+        id: &str,
+        call_number: u32,
+        equality_token: &str,
+        value: Payload,
+    ) {
+        let attrs = MarkerRecordedEventAttributes {
+            marker_name: MUTABLE_SIDE_EFFECT_MARKER_NAME.to_string(),
+            details: build_mutable_side_effect_marker_details(
+                MutableSideEffectMarkerData {
+                    id: id.to_string(),
+                    call_number,
+                    equality_token: equality_token.to_string(),
+                },
+                Some(value),
+            )
+            .unwrap(),
             workflow_task_completed_event_id: self.previous_task_completed_id,
             ..Default::default()
         };
@@ -425,6 +458,18 @@ impl TestHistoryBuilder {
         self.add(wesattrs);
     }
 
+    /// Adds a workflow execution started event for a workflow started with a start delay. The
+    /// started event is backdated by `delay`, so the first workflow task, and everything after
+    /// it, happens once the delay has elapsed, as it would for a real delayed start.
+    pub fn add_wfe_started_with_start_delay(&mut self, delay: Duration) {
+        let mut wesattrs = default_wes_attribs();
+        wesattrs.first_workflow_task_backoff = Some(delay.try_into().unwrap());
+        let event_id = self.add(wesattrs);
+        self.modify_event(event_id, |e| {
+            e.event_time = Some((SystemTime::now() - delay).into());
+        });
+    }
+
     pub fn add_upsert_search_attrs_for_patch(&mut self, attribs: &[String]) {
         let mut indexed_fields = HashMap::new();
         indexed_fields.insert(
@@ -59,17 +59,20 @@ impl HistoryInfo {
                 let next_is_completed = next_event.map_or(false, |ne| {
                     ne.event_type == EventType::WorkflowTaskCompleted as i32
                 });
-                let next_is_failed_or_timeout_or_term = next_event.map_or(false, |ne| {
+                // A workflow terminated while a task is running still ran that task, so it's
+                // replayed like any other, letting the workflow learn of the termination
+                let next_is_term = next_event
+                    .is_some_and(|ne| ne.event_type() == EventType::WorkflowExecutionTerminated);
+                let next_is_failed_or_timeout = next_event.is_some_and(|ne| {
                     matches!(
                         ne.event_type(),
                         EventType::WorkflowTaskFailed
                             | EventType::WorkflowTaskTimedOut
-                            | EventType::WorkflowExecutionTerminated
                             | EventType::WorkflowExecutionTimedOut
                     )
                 });
 
-                if next_event.is_none() || next_is_completed {
+                if next_event.is_none() || next_is_completed || next_is_term {
                     let previous_started_event_id = workflow_task_started_event_id;
                     workflow_task_started_event_id = event.event_id;
                     if workflow_task_started_event_id == previous_started_event_id {
@@ -89,7 +92,7 @@ impl HistoryInfo {
                             wf_exe_started_attrs: started_attrs,
                         });
                     }
-                } else if next_event.is_some() && !next_is_failed_or_timeout_or_term {
+                } else if next_event.is_some() && !next_is_failed_or_timeout {
                     bail!(
                         "Invalid history! Event {next_event:?} should be WFT \
                            completed, failed, or timed out - or WE terminated."
@@ -208,7 +211,12 @@ impl From<HistoryInfo> for GetWorkflowExecutionHistoryResponse {
 
 #[cfg(test)]
 mod tests {
-    use crate::{temporal::api::enums::v1::EventType, TestHistoryBuilder};
+    use crate::{
+        temporal::api::{
+            enums::v1::EventType, history::v1::WorkflowExecutionTimedOutEventAttributes,
+        },
+        TestHistoryBuilder,
+    };
 
     fn single_timer(timer_id: &str) -> TestHistoryBuilder {
         let mut t = TestHistoryBuilder::default();
@@ -237,4 +245,24 @@ mod tests {
         assert_eq!(hi.events().len(), 5);
         assert_eq!(hi.events()[0].event_id, 4);
     }
+
+    #[test]
+    fn task_interrupted_by_termination_is_last_task() {
+        let mut t = single_timer("timer1");
+        t.add_workflow_execution_terminated();
+        let hi = t.get_full_history_info().unwrap();
+        assert_eq!(hi.wf_task_count(), 2);
+        assert_eq!(hi.workflow_task_started_event_id(), 8);
+        assert_eq!(hi.events().len(), 9);
+    }
+
+    #[test]
+    fn task_interrupted_by_timeout_is_not_last_task() {
+        let mut t = single_timer("timer1");
+        t.add(WorkflowExecutionTimedOutEventAttributes::default());
+        let hi = t.get_full_history_info().unwrap();
+        assert_eq!(hi.wf_task_count(), 1);
+        assert_eq!(hi.workflow_task_started_event_id(), 3);
+        assert_eq!(hi.events().len(), 9);
+    }
 }
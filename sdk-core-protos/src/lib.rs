@@ -3,6 +3,7 @@
 //! that will match the generated structs in this module.
 
 pub mod constants;
+pub mod search_attributes;
 pub mod utilities;
 
 #[cfg(feature = "history_builders")]
@@ -23,6 +24,7 @@ pub use task_token::TaskToken;
 pub static ENCODING_PAYLOAD_KEY: &str = "encoding";
 pub static JSON_ENCODING_VAL: &str = "json/plain";
 pub static PATCHED_MARKER_DETAILS_KEY: &str = "patch-data";
+pub static PATCHED_MARKER_PAYLOAD_KEY: &str = "patch-payload";
 
 #[allow(
     clippy::large_enum_variant,
@@ -64,18 +66,34 @@ pub mod coresdk {
         tonic::include_proto!("coresdk.activity_task");
 
         impl ActivityTask {
-            pub fn cancel_from_ids(task_token: Vec<u8>, reason: ActivityCancelReason) -> Self {
+            pub fn cancel_from_ids(
+                task_token: Vec<u8>,
+                reason: ActivityCancelReason,
+                details: ActivityCancellationDetails,
+            ) -> Self {
                 Self {
                     task_token,
                     variant: Some(activity_task::Variant::Cancel(Cancel {
                         reason: reason as i32,
+                        details: Some(details),
+                        hard_cancel: false,
                     })),
                 }
             }
 
+            pub fn is_hard_cancel(&self) -> bool {
+                matches!(
+                    &self.variant,
+                    Some(activity_task::Variant::Cancel(Cancel {
+                        hard_cancel: true,
+                        ..
+                    }))
+                )
+            }
+
             pub fn is_timeout(&self) -> bool {
                 match &self.variant {
-                    Some(activity_task::Variant::Cancel(Cancel { reason })) => {
+                    Some(activity_task::Variant::Cancel(Cancel { reason, .. })) => {
                         *reason == ActivityCancelReason::TimedOut as i32
                     }
                     _ => false,
@@ -273,18 +291,22 @@ pub mod coresdk {
         tonic::include_proto!("coresdk.common");
         use super::external_data::LocalActivityMarkerData;
         use crate::{
+            constants::{
+                MUTABLE_SIDE_EFFECT_MARKER_DATA_KEY, MUTABLE_SIDE_EFFECT_MARKER_VALUE_KEY,
+            },
             coresdk::{
-                external_data::PatchedMarkerData, AsJsonPayloadExt, FromJsonPayloadExt,
-                IntoPayloadsExt,
+                external_data::{MutableSideEffectMarkerData, PatchedMarkerData},
+                AsJsonPayloadExt, FromJsonPayloadExt, IntoPayloadsExt,
             },
             temporal::api::common::v1::{Payload, Payloads},
-            PATCHED_MARKER_DETAILS_KEY,
+            PATCHED_MARKER_DETAILS_KEY, PATCHED_MARKER_PAYLOAD_KEY,
         };
         use std::collections::HashMap;
 
         pub fn build_has_change_marker_details(
             patch_id: impl Into<String>,
             deprecated: bool,
+            data: Option<Payload>,
         ) -> anyhow::Result<HashMap<String, Payloads>> {
             let mut hm = HashMap::new();
             let encoded = PatchedMarkerData {
@@ -293,9 +315,53 @@ pub mod coresdk {
             }
             .as_json_payload()?;
             hm.insert(PATCHED_MARKER_DETAILS_KEY.to_string(), encoded.into());
+            if let Some(data) = data {
+                hm.insert(PATCHED_MARKER_PAYLOAD_KEY.to_string(), data.into());
+            }
             Ok(hm)
         }
 
+        pub fn build_mutable_side_effect_marker_details(
+            metadata: MutableSideEffectMarkerData,
+            value: Option<Payload>,
+        ) -> anyhow::Result<HashMap<String, Payloads>> {
+            let mut hm = HashMap::new();
+            hm.insert(
+                MUTABLE_SIDE_EFFECT_MARKER_DATA_KEY.to_string(),
+                metadata.as_json_payload()?.into(),
+            );
+            if let Some(value) = value {
+                hm.insert(
+                    MUTABLE_SIDE_EFFECT_MARKER_VALUE_KEY.to_string(),
+                    value.into(),
+                );
+            }
+            Ok(hm)
+        }
+
+        pub fn decode_mutable_side_effect_marker_details(
+            details: &HashMap<String, Payloads>,
+        ) -> Option<(MutableSideEffectMarkerData, Option<Payload>)> {
+            let data = details
+                .get(MUTABLE_SIDE_EFFECT_MARKER_DATA_KEY)?
+                .payloads
+                .first()?;
+            let metadata = MutableSideEffectMarkerData::from_json_payload(data).ok()?;
+            let value = details
+                .get(MUTABLE_SIDE_EFFECT_MARKER_VALUE_KEY)
+                .and_then(|p| p.payloads.first().cloned());
+            Some((metadata, value))
+        }
+
+        /// Returns the data a patch marker was recorded with, if it was recorded with any
+        pub fn decode_change_marker_data(details: &HashMap<String, Payloads>) -> Option<Payload> {
+            details
+                .get(PATCHED_MARKER_PAYLOAD_KEY)?
+                .payloads
+                .first()
+                .cloned()
+        }
+
         pub fn decode_change_marker_details(
             details: &HashMap<String, Payloads>,
         ) -> Option<(String, bool)> {
@@ -433,11 +499,12 @@ pub mod coresdk {
             },
             temporal::api::{
                 common::v1::Header,
-                enums::v1::WorkflowTaskFailedCause,
+                enums::v1::{ContinueAsNewInitiator, WorkflowTaskFailedCause},
                 history::v1::{
                     WorkflowExecutionCancelRequestedEventAttributes,
                     WorkflowExecutionSignaledEventAttributes,
                     WorkflowExecutionStartedEventAttributes,
+                    WorkflowExecutionTerminatedEventAttributes,
                 },
                 query::v1::WorkflowQuery,
             },
@@ -620,6 +687,24 @@ pub mod coresdk {
                     workflow_activation_job::Variant::DoUpdate(_) => {
                         write!(f, "DoUpdate")
                     }
+                    workflow_activation_job::Variant::NotifyLateResolution(_) => {
+                        write!(f, "NotifyLateResolution")
+                    }
+                    workflow_activation_job::Variant::ResolveMutableSideEffect(r) => {
+                        write!(f, "ResolveMutableSideEffect({})", r.seq)
+                    }
+                    workflow_activation_job::Variant::ReportStackTrace(_) => {
+                        write!(f, "ReportStackTrace")
+                    }
+                    workflow_activation_job::Variant::NotifyWorkflowTerminated(_) => {
+                        write!(f, "NotifyWorkflowTerminated")
+                    }
+                    workflow_activation_job::Variant::ResolveNexusOperationStart(r) => {
+                        write!(f, "ResolveNexusOperationStart({})", r.seq)
+                    }
+                    workflow_activation_job::Variant::ResolveNexusOperation(r) => {
+                        write!(f, "ResolveNexusOperation({})", r.seq)
+                    }
                 }
             }
         }
@@ -646,8 +731,22 @@ pub mod coresdk {
         }
 
         impl From<WorkflowExecutionCancelRequestedEventAttributes> for CancelWorkflow {
-            fn from(_a: WorkflowExecutionCancelRequestedEventAttributes) -> Self {
-                Self { details: vec![] }
+            fn from(a: WorkflowExecutionCancelRequestedEventAttributes) -> Self {
+                Self {
+                    details: vec![],
+                    reason: a.cause,
+                    identity: a.identity,
+                }
+            }
+        }
+
+        impl From<WorkflowExecutionTerminatedEventAttributes> for NotifyWorkflowTerminated {
+            fn from(a: WorkflowExecutionTerminatedEventAttributes) -> Self {
+                Self {
+                    reason: a.reason,
+                    details: Vec::from_payloads(a.details),
+                    identity: a.identity,
+                }
             }
         }
 
@@ -658,6 +757,15 @@ pub mod coresdk {
             randomness_seed: u64,
             start_time: Timestamp,
         ) -> StartWorkflow {
+            // The server uses the same field for the backoff of cron runs, retries, and
+            // continue-as-new, as it does for a start delay requested by the client
+            let is_start_delay = attrs.cron_schedule.is_empty()
+                && attrs.initiator() == ContinueAsNewInitiator::Unspecified;
+            let (cron_schedule_to_schedule_interval, start_delay) = if is_start_delay {
+                (None, attrs.first_workflow_task_backoff)
+            } else {
+                (attrs.first_workflow_task_backoff, None)
+            };
             StartWorkflow {
                 workflow_type: attrs.workflow_type.map(|wt| wt.name).unwrap_or_default(),
                 workflow_id,
@@ -687,18 +795,37 @@ pub mod coresdk {
                 attempt: attrs.attempt,
                 cron_schedule: attrs.cron_schedule,
                 workflow_execution_expiration_time: attrs.workflow_execution_expiration_time,
-                cron_schedule_to_schedule_interval: attrs.first_workflow_task_backoff,
+                cron_schedule_to_schedule_interval,
                 memo: attrs.memo,
                 search_attributes: attrs.search_attributes,
                 start_time: Some(start_time),
+                priority: attrs.priority,
+                start_delay,
             }
         }
     }
 
     pub mod workflow_completion {
         use crate::temporal::api::{enums::v1::WorkflowTaskFailedCause, failure};
+        use std::fmt::{Display, Formatter};
         tonic::include_proto!("coresdk.workflow_completion");
 
+        /// Formats the stacks as the answer to a stack trace query, one task after another
+        impl Display for StackTrace {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                for (i, task) in self.tasks.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    if !task.name.is_empty() {
+                        writeln!(f, "{}:", task.name)?;
+                    }
+                    writeln!(f, "{}", task.stack.trim_end())?;
+                }
+                Ok(())
+            }
+        }
+
         impl workflow_activation_completion::Status {
             pub const fn is_success(&self) -> bool {
                 match &self {
@@ -722,6 +849,10 @@ pub mod coresdk {
         tonic::include_proto!("coresdk.child_workflow");
     }
 
+    pub mod nexus {
+        tonic::include_proto!("coresdk.nexus");
+    }
+
     pub mod workflow_commands {
         tonic::include_proto!("coresdk.workflow_commands");
 
@@ -871,6 +1002,34 @@ pub mod coresdk {
             }
         }
 
+        impl Display for MutableSideEffect {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "MutableSideEffect({}, {})", self.seq, self.id)
+            }
+        }
+
+        impl Display for SetWorkflowMetadata {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "SetWorkflowMetadata")
+            }
+        }
+
+        impl Display for ScheduleNexusOperation {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "ScheduleNexusOperation({}, {}, {})",
+                    self.seq, self.service, self.operation
+                )
+            }
+        }
+
+        impl Display for RequestCancelNexusOperation {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "RequestCancelNexusOperation({})", self.seq)
+            }
+        }
+
         impl Display for UpdateResponse {
             fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
                 write!(
@@ -925,6 +1084,7 @@ pub mod coresdk {
             Self {
                 commands: v,
                 used_internal_flags: vec![],
+                stack_trace: None,
             }
         }
     }
@@ -1167,6 +1327,10 @@ pub mod coresdk {
                         heartbeat_timeout: r.heartbeat_timeout,
                         retry_policy: r.retry_policy.map(Into::into),
                         is_local: false,
+                        priority: r.priority,
+                        next_retry_delay: None,
+                        is_last_attempt: false,
+                        previous_attempt_failure: None,
                     },
                 )),
             }
@@ -1265,6 +1429,13 @@ pub mod coresdk {
                         v.started_event_id
                     )?;
                 }
+                Some(FailureInfo::NexusOperationExecutionFailureInfo(v)) => {
+                    write!(
+                        f,
+                        "Nexus Operation Failure: scheduled_event_id: {}",
+                        v.scheduled_event_id
+                    )?;
+                }
             }
             write!(f, ")")
         }
@@ -1427,6 +1598,63 @@ pub mod temporal {
                 tonic::include_proto!("temporal.api.batch.v1");
             }
         }
+        pub mod cloud {
+            pub mod account {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.account.v1");
+                }
+            }
+            pub mod cloudservice {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.cloudservice.v1");
+                }
+            }
+            pub mod connectivityrule {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.connectivityrule.v1");
+                }
+            }
+            pub mod identity {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.identity.v1");
+                }
+            }
+            pub mod namespace {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.namespace.v1");
+                }
+            }
+            pub mod nexus {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.nexus.v1");
+                }
+            }
+            pub mod operation {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.operation.v1");
+                }
+            }
+            pub mod region {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.region.v1");
+                }
+            }
+            pub mod resource {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.resource.v1");
+                }
+            }
+            pub mod sink {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.sink.v1");
+                }
+            }
+            pub mod usage {
+                pub mod v1 {
+                    tonic::include_proto!("temporal.api.cloud.usage.v1");
+                }
+            }
+        }
         pub mod command {
             pub mod v1 {
                 tonic::include_proto!("temporal.api.command.v1");
@@ -1443,51 +1671,39 @@ pub mod temporal {
 
                 impl From<command::Attributes> for Command {
                     fn from(c: command::Attributes) -> Self {
-                        match c {
-                            a @ Attributes::StartTimerCommandAttributes(_) => Self {
-                                command_type: CommandType::StartTimer as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::CancelTimerCommandAttributes(_) => Self {
-                                command_type: CommandType::CancelTimer as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::CompleteWorkflowExecutionCommandAttributes(_) => Self {
-                                command_type: CommandType::CompleteWorkflowExecution as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::FailWorkflowExecutionCommandAttributes(_) => Self {
-                                command_type: CommandType::FailWorkflowExecution as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::ScheduleActivityTaskCommandAttributes(_) => Self {
-                                command_type: CommandType::ScheduleActivityTask as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::RequestCancelActivityTaskCommandAttributes(_) => Self {
-                                command_type: CommandType::RequestCancelActivityTask as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(_) => {
-                                Self {
-                                    command_type: CommandType::ContinueAsNewWorkflowExecution
-                                        as i32,
-                                    attributes: Some(a),
-                                }
+                        let command_type = match &c {
+                            Attributes::StartTimerCommandAttributes(_) => CommandType::StartTimer,
+                            Attributes::CancelTimerCommandAttributes(_) => CommandType::CancelTimer,
+                            Attributes::CompleteWorkflowExecutionCommandAttributes(_) => {
+                                CommandType::CompleteWorkflowExecution
+                            }
+                            Attributes::FailWorkflowExecutionCommandAttributes(_) => {
+                                CommandType::FailWorkflowExecution
+                            }
+                            Attributes::ScheduleActivityTaskCommandAttributes(_) => {
+                                CommandType::ScheduleActivityTask
+                            }
+                            Attributes::RequestCancelActivityTaskCommandAttributes(_) => {
+                                CommandType::RequestCancelActivityTask
+                            }
+                            Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(_) => {
+                                CommandType::ContinueAsNewWorkflowExecution
+                            }
+                            Attributes::CancelWorkflowExecutionCommandAttributes(_) => {
+                                CommandType::CancelWorkflowExecution
+                            }
+                            Attributes::RecordMarkerCommandAttributes(_) => {
+                                CommandType::RecordMarker
+                            }
+                            Attributes::ProtocolMessageCommandAttributes(_) => {
+                                CommandType::ProtocolMessage
                             }
-                            a @ Attributes::CancelWorkflowExecutionCommandAttributes(_) => Self {
-                                command_type: CommandType::CancelWorkflowExecution as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::RecordMarkerCommandAttributes(_) => Self {
-                                command_type: CommandType::RecordMarker as i32,
-                                attributes: Some(a),
-                            },
-                            a @ Attributes::ProtocolMessageCommandAttributes(_) => Self {
-                                command_type: CommandType::ProtocolMessage as i32,
-                                attributes: Some(a),
-                            },
                             _ => unimplemented!(),
+                        };
+                        Self {
+                            command_type: command_type as i32,
+                            attributes: Some(c),
+                            user_metadata: None,
                         }
                     }
                 }
@@ -1557,6 +1773,7 @@ pub mod temporal {
                             retry_policy: s.retry_policy.map(Into::into),
                             request_eager_execution: !s.do_not_eagerly_execute,
                             use_compatible_version,
+                            priority: s.priority,
                         },
                     )
                 }
@@ -1586,6 +1803,7 @@ pub mod temporal {
                             cron_schedule: s.cron_schedule.clone(),
                             parent_close_policy: s.parent_close_policy,
                             use_compatible_version,
+                            priority: s.priority,
                         },
                     )
                 }
@@ -1782,6 +2000,11 @@ pub mod temporal {
                 tonic::include_proto!("temporal.api.enums.v1");
             }
         }
+        pub mod errordetails {
+            pub mod v1 {
+                tonic::include_proto!("temporal.api.errordetails.v1");
+            }
+        }
         pub mod failure {
             pub mod v1 {
                 tonic::include_proto!("temporal.api.failure.v1");
@@ -1847,7 +2070,9 @@ pub mod temporal {
                             | EventType::WorkflowExecutionFailed
                             | EventType::WorkflowExecutionUpdateAccepted
                             | EventType::WorkflowExecutionUpdateRejected
-                            | EventType::WorkflowExecutionUpdateCompleted => true,
+                            | EventType::WorkflowExecutionUpdateCompleted
+                            | EventType::NexusOperationScheduled
+                            | EventType::NexusOperationCancelRequested => true,
                             _ => false,
                         })
                     }
@@ -1885,6 +2110,12 @@ pub mod temporal {
                                 Attributes::WorkflowTaskCompletedEventAttributes(a) => Some(a.scheduled_event_id),
                                 Attributes::WorkflowTaskTimedOutEventAttributes(a) => Some(a.scheduled_event_id),
                                 Attributes::WorkflowTaskFailedEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationStartedEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationCompletedEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationFailedEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationTimedOutEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationCanceledEventAttributes(a) => Some(a.scheduled_event_id),
+                                Attributes::NexusOperationCancelRequestedEventAttributes(a) => Some(a.scheduled_event_id),
                                 _ => None
                             }
                         })
@@ -1985,6 +2216,13 @@ pub mod temporal {
                             Attributes::WorkflowPropertiesModifiedExternallyEventAttributes(_) => {EventType::WorkflowPropertiesModifiedExternally}
                             Attributes::ActivityPropertiesModifiedExternallyEventAttributes(_) => {EventType::ActivityPropertiesModifiedExternally}
                             Attributes::WorkflowPropertiesModifiedEventAttributes(_) => {EventType::WorkflowPropertiesModified}
+                            Attributes::NexusOperationScheduledEventAttributes(_) => {EventType::NexusOperationScheduled}
+                            Attributes::NexusOperationStartedEventAttributes(_) => {EventType::NexusOperationStarted}
+                            Attributes::NexusOperationCompletedEventAttributes(_) => {EventType::NexusOperationCompleted}
+                            Attributes::NexusOperationFailedEventAttributes(_) => {EventType::NexusOperationFailed}
+                            Attributes::NexusOperationCanceledEventAttributes(_) => {EventType::NexusOperationCanceled}
+                            Attributes::NexusOperationTimedOutEventAttributes(_) => {EventType::NexusOperationTimedOut}
+                            Attributes::NexusOperationCancelRequestedEventAttributes(_) => {EventType::NexusOperationCancelRequested}
                         }
                     }
                 }
@@ -1995,6 +2233,11 @@ pub mod temporal {
                 tonic::include_proto!("temporal.api.namespace.v1");
             }
         }
+        pub mod nexus {
+            pub mod v1 {
+                tonic::include_proto!("temporal.api.nexus.v1");
+            }
+        }
         pub mod operatorservice {
             pub mod v1 {
                 tonic::include_proto!("temporal.api.operatorservice.v1");
@@ -2030,7 +2273,31 @@ pub mod temporal {
         }
         pub mod sdk {
             pub mod v1 {
+                use crate::{temporal::api::common::v1::Payload, ENCODING_PAYLOAD_KEY};
+                use prost::Message;
+                use std::collections::HashMap;
                 tonic::include_proto!("temporal.api.sdk.v1");
+
+                impl WorkflowMetadata {
+                    /// Encodes the metadata as a `binary/protobuf` payload, which is how it is
+                    /// returned from the built-in workflow metadata query
+                    pub fn as_payload(&self) -> Payload {
+                        let metadata = HashMap::from([
+                            (
+                                ENCODING_PAYLOAD_KEY.to_string(),
+                                b"binary/protobuf".to_vec(),
+                            ),
+                            (
+                                "messageType".to_string(),
+                                b"temporal.api.sdk.v1.WorkflowMetadata".to_vec(),
+                            ),
+                        ]);
+                        Payload {
+                            metadata,
+                            data: self.encode_to_vec(),
+                        }
+                    }
+                }
             }
         }
         pub mod taskqueue {
@@ -2179,6 +2446,18 @@ pub mod grpc {
     }
 }
 
+#[allow(
+    clippy::all,
+    missing_docs,
+    rustdoc::broken_intra_doc_links,
+    rustdoc::bare_urls
+)]
+pub mod google {
+    pub mod rpc {
+        tonic::include_proto!("google.rpc");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::temporal::api::failure::v1::Failure;
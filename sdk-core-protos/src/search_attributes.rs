@@ -0,0 +1,718 @@
+//! Typed search attributes, which convert to and from the payloads visibility indexes them as
+//! without callers having to get each value's encoding right by hand.
+
+use crate::{
+    temporal::api::{
+        common::v1::{Payload, SearchAttributes},
+        enums::v1::IndexedValueType,
+        operatorservice::v1::ListSearchAttributesResponse,
+        workflow::v1::WorkflowExecutionInfo,
+        workflowservice::v1::GetSearchAttributesResponse,
+    },
+    ENCODING_PAYLOAD_KEY, JSON_ENCODING_VAL,
+};
+use prost_wkt_types::Timestamp;
+use serde_json::Value;
+use std::{
+    collections::{hash_map, HashMap},
+    fmt::{Display, Formatter},
+    time::SystemTime,
+};
+
+/// Payload metadata key the server reads a search attribute's type from
+pub static SEARCH_ATTRIBUTE_TYPE_KEY: &str = "type";
+
+/// The value of a search attribute, typed as visibility indexes it
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchAttributeValue {
+    /// Matched exactly, as a whole
+    Keyword(String),
+    /// Matched by full text search
+    Text(String),
+    /// A 64 bit integer
+    Int(i64),
+    /// A finite 64 bit float
+    Double(f64),
+    /// A boolean
+    Bool(bool),
+    /// A point in time, indexed to nanosecond precision
+    Datetime(SystemTime),
+    /// A list of keywords, which matches if any of them does
+    KeywordList(Vec<String>),
+}
+
+/// Errors converting search attributes to or from payloads
+#[derive(thiserror::Error, Debug)]
+pub enum SearchAttributeError {
+    /// Search attribute names can't be empty
+    #[error("Search attribute names can't be empty")]
+    EmptyName,
+    /// The value can't be indexed as its type
+    #[error("Search attribute {name} has an invalid value: {reason}")]
+    InvalidValue {
+        /// Name of the search attribute
+        name: String,
+        /// Why the value isn't valid
+        reason: String,
+    },
+    /// A payload couldn't be decoded as a search attribute
+    #[error("Search attribute {name} couldn't be decoded: {reason}")]
+    Decode {
+        /// Name of the search attribute
+        name: String,
+        /// Why decoding failed
+        reason: String,
+    },
+    /// The search attribute isn't registered with the server
+    #[error("Search attribute {name} isn't registered")]
+    Unregistered {
+        /// Name of the search attribute
+        name: String,
+    },
+    /// The value's type isn't the type the search attribute is registered with
+    #[error("Search attribute {name} is registered as {}, but the value is {}",
+            type_name(*.expected), type_name(*.actual))]
+    TypeMismatch {
+        /// Name of the search attribute
+        name: String,
+        /// The type the search attribute is registered with
+        expected: IndexedValueType,
+        /// The type of the value
+        actual: IndexedValueType,
+    },
+}
+
+/// Every search attribute which failed validation against a [SearchAttributeSchema], ordered by
+/// name, so that all of them can be fixed at once
+#[derive(thiserror::Error, Debug)]
+pub struct SearchAttributeValidationError {
+    /// Why each invalid search attribute is invalid
+    pub errors: Vec<SearchAttributeError>,
+}
+
+impl Display for SearchAttributeValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid search attributes: ")?;
+        for (i, e) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{e}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<SearchAttributeError> for SearchAttributeValidationError {
+    fn from(e: SearchAttributeError) -> Self {
+        Self { errors: vec![e] }
+    }
+}
+
+impl SearchAttributeValue {
+    /// The type the value is indexed as
+    pub fn indexed_value_type(&self) -> IndexedValueType {
+        match self {
+            SearchAttributeValue::Keyword(_) => IndexedValueType::Keyword,
+            SearchAttributeValue::Text(_) => IndexedValueType::Text,
+            SearchAttributeValue::Int(_) => IndexedValueType::Int,
+            SearchAttributeValue::Double(_) => IndexedValueType::Double,
+            SearchAttributeValue::Bool(_) => IndexedValueType::Bool,
+            SearchAttributeValue::Datetime(_) => IndexedValueType::Datetime,
+            SearchAttributeValue::KeywordList(_) => IndexedValueType::KeywordList,
+        }
+    }
+
+    /// Encodes the value as a JSON payload tagged with its type, as the server expects
+    pub fn to_payload(&self, name: &str) -> Result<Payload, SearchAttributeError> {
+        let invalid = |reason: &str| SearchAttributeError::InvalidValue {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        };
+        let json = match self {
+            SearchAttributeValue::Keyword(s) | SearchAttributeValue::Text(s) => {
+                serde_json::to_vec(s)
+            }
+            SearchAttributeValue::Int(i) => serde_json::to_vec(i),
+            SearchAttributeValue::Double(d) if !d.is_finite() => {
+                return Err(invalid("doubles must be finite"))
+            }
+            SearchAttributeValue::Double(d) => serde_json::to_vec(d),
+            SearchAttributeValue::Bool(b) => serde_json::to_vec(b),
+            SearchAttributeValue::Datetime(t) => serde_json::to_vec(&Timestamp::from(*t)),
+            SearchAttributeValue::KeywordList(l) => serde_json::to_vec(l),
+        }
+        .map_err(|e| invalid(&e.to_string()))?;
+        Ok(Payload {
+            metadata: HashMap::from([
+                (
+                    ENCODING_PAYLOAD_KEY.to_string(),
+                    JSON_ENCODING_VAL.as_bytes().to_vec(),
+                ),
+                (
+                    SEARCH_ATTRIBUTE_TYPE_KEY.to_string(),
+                    type_name(self.indexed_value_type()).as_bytes().to_vec(),
+                ),
+            ]),
+            data: json,
+        })
+    }
+
+    /// Decodes a search attribute payload. Its type is read from the payload's metadata when
+    /// present, and otherwise inferred from the JSON value, in which case strings are taken to be
+    /// keywords.
+    pub fn from_payload(name: &str, payload: &Payload) -> Result<Self, SearchAttributeError> {
+        let decode_err = |reason: String| SearchAttributeError::Decode {
+            name: name.to_string(),
+            reason,
+        };
+        let json: Value =
+            serde_json::from_slice(&payload.data).map_err(|e| decode_err(e.to_string()))?;
+        let ty = match declared_type(name, payload)? {
+            Some(t) => t,
+            None => match &json {
+                Value::String(_) => IndexedValueType::Keyword,
+                Value::Number(n) if n.is_i64() => IndexedValueType::Int,
+                Value::Number(_) => IndexedValueType::Double,
+                Value::Bool(_) => IndexedValueType::Bool,
+                Value::Array(_) => IndexedValueType::KeywordList,
+                _ => return Err(decode_err(format!("can't infer the type of {json}"))),
+            },
+        };
+        decode_json(name, json, ty)
+    }
+
+    /// Decodes a search attribute payload as the type it's registered with. Fails if the payload
+    /// declares a different type in its metadata.
+    pub fn from_payload_as(
+        name: &str,
+        payload: &Payload,
+        ty: IndexedValueType,
+    ) -> Result<Self, SearchAttributeError> {
+        if let Some(declared) = declared_type(name, payload)? {
+            if declared != ty {
+                return Err(SearchAttributeError::TypeMismatch {
+                    name: name.to_string(),
+                    expected: ty,
+                    actual: declared,
+                });
+            }
+        }
+        let json =
+            serde_json::from_slice(&payload.data).map_err(|e| SearchAttributeError::Decode {
+                name: name.to_string(),
+                reason: e.to_string(),
+            })?;
+        decode_json(name, json, ty)
+    }
+}
+
+/// The type a payload declares in its metadata, if it declares one
+fn declared_type(
+    name: &str,
+    payload: &Payload,
+) -> Result<Option<IndexedValueType>, SearchAttributeError> {
+    payload
+        .metadata
+        .get(SEARCH_ATTRIBUTE_TYPE_KEY)
+        .map(|t| {
+            let t = String::from_utf8_lossy(t);
+            type_from_name(&t).ok_or_else(|| SearchAttributeError::Decode {
+                name: name.to_string(),
+                reason: format!("unknown type {t}"),
+            })
+        })
+        .transpose()
+}
+
+fn decode_json(
+    name: &str,
+    json: Value,
+    ty: IndexedValueType,
+) -> Result<SearchAttributeValue, SearchAttributeError> {
+    let decode_err = |reason: String| SearchAttributeError::Decode {
+        name: name.to_string(),
+        reason,
+    };
+    let mismatch = || decode_err(format!("{json} isn't a valid {}", type_name(ty)));
+    Ok(match ty {
+        IndexedValueType::Keyword => {
+            SearchAttributeValue::Keyword(json.as_str().ok_or_else(mismatch)?.to_string())
+        }
+        IndexedValueType::Text => {
+            SearchAttributeValue::Text(json.as_str().ok_or_else(mismatch)?.to_string())
+        }
+        IndexedValueType::Int => SearchAttributeValue::Int(json.as_i64().ok_or_else(mismatch)?),
+        IndexedValueType::Double => {
+            SearchAttributeValue::Double(json.as_f64().ok_or_else(mismatch)?)
+        }
+        IndexedValueType::Bool => SearchAttributeValue::Bool(json.as_bool().ok_or_else(mismatch)?),
+        IndexedValueType::Datetime => {
+            let ts: Timestamp = serde_json::from_value(json.clone()).map_err(|_| mismatch())?;
+            SearchAttributeValue::Datetime(ts.try_into().map_err(|_| mismatch())?)
+        }
+        IndexedValueType::KeywordList => SearchAttributeValue::KeywordList(
+            serde_json::from_value(json.clone()).map_err(|_| mismatch())?,
+        ),
+        IndexedValueType::Unspecified => return Err(mismatch()),
+    })
+}
+
+const TYPE_NAMES: [(IndexedValueType, &str); 7] = [
+    (IndexedValueType::Keyword, "Keyword"),
+    (IndexedValueType::Text, "Text"),
+    (IndexedValueType::Int, "Int"),
+    (IndexedValueType::Double, "Double"),
+    (IndexedValueType::Bool, "Bool"),
+    (IndexedValueType::Datetime, "Datetime"),
+    (IndexedValueType::KeywordList, "KeywordList"),
+];
+
+/// The name the server uses for a type in payload metadata
+fn type_name(ty: IndexedValueType) -> &'static str {
+    TYPE_NAMES
+        .iter()
+        .find(|(t, _)| *t == ty)
+        .map(|(_, n)| *n)
+        .unwrap_or("Unspecified")
+}
+
+fn type_from_name(name: &str) -> Option<IndexedValueType> {
+    TYPE_NAMES.iter().find(|(_, n)| *n == name).map(|(t, _)| *t)
+}
+
+impl From<String> for SearchAttributeValue {
+    fn from(s: String) -> Self {
+        SearchAttributeValue::Keyword(s)
+    }
+}
+
+impl From<&str> for SearchAttributeValue {
+    fn from(s: &str) -> Self {
+        SearchAttributeValue::Keyword(s.to_string())
+    }
+}
+
+impl From<i64> for SearchAttributeValue {
+    fn from(i: i64) -> Self {
+        SearchAttributeValue::Int(i)
+    }
+}
+
+impl From<f64> for SearchAttributeValue {
+    fn from(d: f64) -> Self {
+        SearchAttributeValue::Double(d)
+    }
+}
+
+impl From<bool> for SearchAttributeValue {
+    fn from(b: bool) -> Self {
+        SearchAttributeValue::Bool(b)
+    }
+}
+
+impl From<SystemTime> for SearchAttributeValue {
+    fn from(t: SystemTime) -> Self {
+        SearchAttributeValue::Datetime(t)
+    }
+}
+
+impl From<Vec<String>> for SearchAttributeValue {
+    fn from(l: Vec<String>) -> Self {
+        SearchAttributeValue::KeywordList(l)
+    }
+}
+
+/// A set of typed search attributes, keyed by name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypedSearchAttributes {
+    attrs: HashMap<String, SearchAttributeValue>,
+}
+
+impl TypedSearchAttributes {
+    /// An empty set of search attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a search attribute, replacing any previous value. Strings are set as keywords; use
+    /// [SearchAttributeValue::Text] for full text search.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<SearchAttributeValue>,
+    ) -> Result<&mut Self, SearchAttributeError> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(SearchAttributeError::EmptyName);
+        }
+        let value = value.into();
+        if let SearchAttributeValue::Double(d) = value {
+            if !d.is_finite() {
+                return Err(SearchAttributeError::InvalidValue {
+                    name,
+                    reason: "doubles must be finite".to_string(),
+                });
+            }
+        }
+        self.attrs.insert(name, value);
+        Ok(self)
+    }
+
+    /// The value of a search attribute, if it's set
+    pub fn get(&self, name: &str) -> Option<&SearchAttributeValue> {
+        self.attrs.get(name)
+    }
+
+    /// Unsets a search attribute, returning its value
+    pub fn remove(&mut self, name: &str) -> Option<SearchAttributeValue> {
+        self.attrs.remove(name)
+    }
+
+    /// Iterates over the search attributes which are set
+    pub fn iter(&self) -> hash_map::Iter<'_, String, SearchAttributeValue> {
+        self.attrs.iter()
+    }
+
+    /// How many search attributes are set
+    pub fn len(&self) -> usize {
+        self.attrs.len()
+    }
+
+    /// Whether no search attributes are set
+    pub fn is_empty(&self) -> bool {
+        self.attrs.is_empty()
+    }
+
+    /// Encodes each search attribute as a payload
+    pub fn to_payloads(&self) -> Result<HashMap<String, Payload>, SearchAttributeError> {
+        self.attrs
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), v.to_payload(k)?)))
+            .collect()
+    }
+}
+
+impl TryFrom<TypedSearchAttributes> for HashMap<String, Payload> {
+    type Error = SearchAttributeError;
+
+    fn try_from(attrs: TypedSearchAttributes) -> Result<Self, Self::Error> {
+        attrs.to_payloads()
+    }
+}
+
+impl TryFrom<TypedSearchAttributes> for SearchAttributes {
+    type Error = SearchAttributeError;
+
+    fn try_from(attrs: TypedSearchAttributes) -> Result<Self, Self::Error> {
+        Ok(SearchAttributes {
+            indexed_fields: attrs.to_payloads()?,
+        })
+    }
+}
+
+impl TryFrom<&HashMap<String, Payload>> for TypedSearchAttributes {
+    type Error = SearchAttributeError;
+
+    fn try_from(payloads: &HashMap<String, Payload>) -> Result<Self, Self::Error> {
+        let attrs = payloads
+            .iter()
+            .map(|(k, p)| Ok((k.clone(), SearchAttributeValue::from_payload(k, p)?)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { attrs })
+    }
+}
+
+impl TryFrom<&SearchAttributes> for TypedSearchAttributes {
+    type Error = SearchAttributeError;
+
+    fn try_from(attrs: &SearchAttributes) -> Result<Self, Self::Error> {
+        (&attrs.indexed_fields).try_into()
+    }
+}
+
+impl<'a> IntoIterator for &'a TypedSearchAttributes {
+    type Item = (&'a String, &'a SearchAttributeValue);
+    type IntoIter = hash_map::Iter<'a, String, SearchAttributeValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The types search attributes are registered with, to check values against before they're sent,
+/// rather than having the server reject them. Converts from the server's search attribute
+/// metadata, as returned by `get_search_attributes` or the operator service's
+/// `list_search_attributes`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchAttributeSchema {
+    types: HashMap<String, IndexedValueType>,
+}
+
+impl SearchAttributeSchema {
+    /// A schema with no search attributes registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a search attribute's type, replacing any previous type
+    pub fn insert(&mut self, name: impl Into<String>, ty: IndexedValueType) -> &mut Self {
+        self.types.insert(name.into(), ty);
+        self
+    }
+
+    /// The type a search attribute is registered with, if it is registered
+    pub fn get(&self, name: &str) -> Option<IndexedValueType> {
+        self.types.get(name).copied()
+    }
+
+    /// Checks every attribute is registered, and that its value is of the registered type
+    pub fn validate(
+        &self,
+        attrs: &TypedSearchAttributes,
+    ) -> Result<(), SearchAttributeValidationError> {
+        self.collect_errors(attrs.iter().map(|(name, value)| {
+            let expected = self.registered_type(name)?;
+            let actual = value.indexed_value_type();
+            if expected != actual {
+                return Err(SearchAttributeError::TypeMismatch {
+                    name: name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+            Ok(())
+        }))
+    }
+
+    /// Checks every attribute is registered, and that its payload decodes as the registered type.
+    /// Payloads of JSON `null`, which unset an attribute, only need to be registered.
+    pub fn validate_payloads(
+        &self,
+        payloads: &HashMap<String, Payload>,
+    ) -> Result<(), SearchAttributeValidationError> {
+        self.collect_errors(payloads.iter().map(|(name, payload)| {
+            let ty = self.registered_type(name)?;
+            if payload.data != b"null" {
+                SearchAttributeValue::from_payload_as(name, payload, ty)?;
+            }
+            Ok(())
+        }))
+    }
+
+    fn registered_type(&self, name: &str) -> Result<IndexedValueType, SearchAttributeError> {
+        self.get(name)
+            .ok_or_else(|| SearchAttributeError::Unregistered {
+                name: name.to_string(),
+            })
+    }
+
+    fn collect_errors(
+        &self,
+        results: impl Iterator<Item = Result<(), SearchAttributeError>>,
+    ) -> Result<(), SearchAttributeValidationError> {
+        let mut errors: Vec<_> = results.filter_map(Result::err).collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        errors.sort_by(|a, b| error_name(a).cmp(error_name(b)));
+        Err(SearchAttributeValidationError { errors })
+    }
+}
+
+fn error_name(e: &SearchAttributeError) -> &str {
+    match e {
+        SearchAttributeError::EmptyName => "",
+        SearchAttributeError::InvalidValue { name, .. }
+        | SearchAttributeError::Decode { name, .. }
+        | SearchAttributeError::Unregistered { name }
+        | SearchAttributeError::TypeMismatch { name, .. } => name,
+    }
+}
+
+impl FromIterator<(String, IndexedValueType)> for SearchAttributeSchema {
+    fn from_iter<T: IntoIterator<Item = (String, IndexedValueType)>>(iter: T) -> Self {
+        Self {
+            types: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Types the server sends which it doesn't know of are left out
+fn schema_from_raw(raw: impl IntoIterator<Item = (String, i32)>) -> SearchAttributeSchema {
+    raw.into_iter()
+        .filter_map(|(name, ty)| {
+            IndexedValueType::try_from(ty)
+                .ok()
+                .filter(|ty| *ty != IndexedValueType::Unspecified)
+                .map(|ty| (name, ty))
+        })
+        .collect()
+}
+
+impl From<GetSearchAttributesResponse> for SearchAttributeSchema {
+    fn from(resp: GetSearchAttributesResponse) -> Self {
+        schema_from_raw(resp.keys)
+    }
+}
+
+impl From<ListSearchAttributesResponse> for SearchAttributeSchema {
+    fn from(resp: ListSearchAttributesResponse) -> Self {
+        schema_from_raw(
+            resp.system_attributes
+                .into_iter()
+                .chain(resp.custom_attributes),
+        )
+    }
+}
+
+impl WorkflowExecutionInfo {
+    /// The workflow's search attributes, decoded
+    pub fn typed_search_attributes(&self) -> Result<TypedSearchAttributes, SearchAttributeError> {
+        self.search_attributes
+            .as_ref()
+            .map(TryInto::try_into)
+            .unwrap_or_else(|| Ok(TypedSearchAttributes::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn round_trips_every_type() {
+        let when = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_000_000);
+        let mut attrs = TypedSearchAttributes::new();
+        attrs
+            .insert("kw", "some-id")
+            .unwrap()
+            .insert("txt", SearchAttributeValue::Text("a b c".to_string()))
+            .unwrap()
+            .insert("int", 7)
+            .unwrap()
+            .insert("dbl", 1.5)
+            .unwrap()
+            .insert("bool", true)
+            .unwrap()
+            .insert("when", when)
+            .unwrap()
+            .insert("tags", vec!["a".to_string(), "b".to_string()])
+            .unwrap();
+        let payloads = attrs.to_payloads().unwrap();
+        assert_eq!(
+            payloads["tags"].metadata[SEARCH_ATTRIBUTE_TYPE_KEY],
+            b"KeywordList"
+        );
+        assert_eq!(
+            payloads["when"].metadata[SEARCH_ATTRIBUTE_TYPE_KEY],
+            b"Datetime"
+        );
+        assert!(payloads["int"].is_json_payload());
+        assert_eq!(TypedSearchAttributes::try_from(&payloads).unwrap(), attrs);
+    }
+
+    #[test]
+    fn infers_types_of_untagged_payloads() {
+        let untagged = |json: &str| Payload {
+            metadata: HashMap::new(),
+            data: json.as_bytes().to_vec(),
+        };
+        let decode = |json| SearchAttributeValue::from_payload("attr", &untagged(json));
+        assert_eq!(
+            decode("\"hi\"").unwrap(),
+            SearchAttributeValue::Keyword("hi".to_string())
+        );
+        assert_eq!(decode("98").unwrap(), SearchAttributeValue::Int(98));
+        assert_eq!(decode("0.5").unwrap(), SearchAttributeValue::Double(0.5));
+        assert!(decode("{}").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let mut attrs = TypedSearchAttributes::new();
+        assert!(matches!(
+            attrs.insert("", 1),
+            Err(SearchAttributeError::EmptyName)
+        ));
+        assert!(matches!(
+            attrs.insert("dbl", f64::NAN),
+            Err(SearchAttributeError::InvalidValue { .. })
+        ));
+        let mut mistyped = SearchAttributeValue::Int(1).to_payload("int").unwrap();
+        mistyped.data = b"\"one\"".to_vec();
+        assert!(matches!(
+            SearchAttributeValue::from_payload("int", &mistyped),
+            Err(SearchAttributeError::Decode { .. })
+        ));
+    }
+
+    #[test]
+    fn validates_against_schema() {
+        let schema = SearchAttributeSchema::from(GetSearchAttributesResponse {
+            keys: HashMap::from([
+                ("CustomerId".to_string(), IndexedValueType::Keyword as i32),
+                ("Notes".to_string(), IndexedValueType::Text as i32),
+                ("Priority".to_string(), IndexedValueType::Int as i32),
+            ]),
+        });
+        let mut attrs = TypedSearchAttributes::new();
+        attrs
+            .insert("CustomerId", "c-1")
+            .unwrap()
+            .insert("Priority", 3)
+            .unwrap();
+        schema.validate(&attrs).unwrap();
+        schema
+            .validate_payloads(&attrs.to_payloads().unwrap())
+            .unwrap();
+
+        attrs
+            .insert("Notes", "not full text")
+            .unwrap()
+            .insert("Region", "eu")
+            .unwrap();
+        let errs = schema.validate(&attrs).unwrap_err().errors;
+        assert!(matches!(
+            errs.as_slice(),
+            [
+                SearchAttributeError::TypeMismatch {
+                    name,
+                    expected: IndexedValueType::Text,
+                    actual: IndexedValueType::Keyword,
+                },
+                SearchAttributeError::Unregistered { name: unregistered },
+            ] if name == "Notes" && unregistered == "Region"
+        ));
+    }
+
+    #[test]
+    fn validates_untagged_payloads_as_registered_type() {
+        let mut schema = SearchAttributeSchema::new();
+        schema
+            .insert("Notes", IndexedValueType::Text)
+            .insert("Priority", IndexedValueType::Int);
+        let untagged = |json: &str| Payload {
+            metadata: HashMap::new(),
+            data: json.as_bytes().to_vec(),
+        };
+        schema
+            .validate_payloads(&HashMap::from([
+                ("Notes".to_string(), untagged("\"some text\"")),
+                ("Priority".to_string(), untagged("null")),
+            ]))
+            .unwrap();
+        let errs = schema
+            .validate_payloads(&HashMap::from([(
+                "Priority".to_string(),
+                untagged("\"high\""),
+            )]))
+            .unwrap_err()
+            .errors;
+        assert!(matches!(
+            errs.as_slice(),
+            [SearchAttributeError::Decode { .. }]
+        ));
+    }
+}
@@ -1,4 +1,10 @@
+use crate::{
+    constants::TRACING_HEADER_KEY,
+    coresdk::{AsJsonPayloadExt, FromJsonPayloadExt},
+    temporal::api::common::v1::Payload,
+};
 use prost::{EncodeError, Message};
+use std::collections::HashMap;
 
 pub trait TryIntoOrNone<F, T> {
     /// Turn an option of something into an option of another thing, trying to convert along the way
@@ -26,3 +32,54 @@ pub fn pack_any<T: Message>(
     Message::encode(msg, &mut value)?;
     Ok(prost_wkt_types::Any { type_url, value })
 }
+
+/// Writes trace context into Temporal headers under [TRACING_HEADER_KEY], as the JSON object other
+/// Temporal SDKs use. `carrier` holds the context as a text map propagator writes it, ex: W3C
+/// `traceparent` and `tracestate` entries. Headers which already carry trace context keep it.
+pub fn inject_trace_context(
+    headers: &mut HashMap<String, Payload>,
+    carrier: &HashMap<String, String>,
+) {
+    if carrier.is_empty() || headers.contains_key(TRACING_HEADER_KEY) {
+        return;
+    }
+    if let Ok(payload) = carrier.as_json_payload() {
+        headers.insert(TRACING_HEADER_KEY.to_string(), payload);
+    }
+}
+
+/// Reads trace context written by [inject_trace_context], or another SDK, from Temporal headers, as
+/// a text map for a propagator to extract from. Returns `None` if there's none, or it's malformed.
+pub fn extract_trace_context(
+    headers: &HashMap<String, Payload>,
+) -> Option<HashMap<String, String>> {
+    headers
+        .get(TRACING_HEADER_KEY)
+        .and_then(|p| HashMap::from_json_payload(p).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_context_round_trips_through_headers() {
+        let carrier = HashMap::from([(
+            "traceparent".to_string(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+        )]);
+        let mut headers = HashMap::new();
+        assert_eq!(extract_trace_context(&headers), None);
+        inject_trace_context(&mut headers, &carrier);
+        assert_eq!(
+            std::str::from_utf8(&headers[TRACING_HEADER_KEY].data).unwrap(),
+            r#"{"traceparent":"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"}"#
+        );
+        // Context which is already present isn't overwritten
+        inject_trace_context(
+            &mut headers,
+            &HashMap::from([("traceparent".to_string(), "other".to_string())]),
+        );
+        assert_eq!(extract_trace_context(&headers), Some(carrier));
+    }
+}
@@ -9,8 +9,11 @@ use std::{
 use temporal_sdk_core_api::Worker;
 use temporal_sdk_core_protos::{
     coresdk::{activity_task, ActivityHeartbeat},
-    temporal::api::common::v1::{Payload, RetryPolicy, WorkflowExecution},
-    utilities::TryIntoOrNone,
+    temporal::api::{
+        common::v1::{Payload, Priority, RetryPolicy, WorkflowExecution},
+        failure::v1::Failure,
+    },
+    utilities::{extract_trace_context, TryIntoOrNone},
 };
 use tokio_util::sync::CancellationToken;
 
@@ -48,6 +51,14 @@ pub struct ActivityInfo {
     pub current_attempt_scheduled_time: Option<SystemTime>,
     pub retry_policy: Option<RetryPolicy>,
     pub is_local: bool,
+    /// Priority of this activity, as determined by the server
+    pub priority: Option<Priority>,
+    /// How long the retry policy says to wait before the next attempt if this one fails
+    pub next_retry_delay: Option<StdDuration>,
+    /// True if the retry policy allows no further attempts should this one fail
+    pub is_last_attempt: bool,
+    /// The failure of the previous attempt, if known (local activities only)
+    pub previous_attempt_failure: Option<Failure>,
 }
 
 impl ActContext {
@@ -79,6 +90,10 @@ impl ActContext {
             heartbeat_timeout,
             retry_policy,
             is_local,
+            priority,
+            next_retry_delay,
+            is_last_attempt,
+            previous_attempt_failure,
         } = task;
         let deadline = calculate_deadline(
             scheduled_time.as_ref(),
@@ -113,6 +128,10 @@ impl ActContext {
                         .try_into_or_none(),
                     retry_policy,
                     is_local,
+                    priority,
+                    next_retry_delay: next_retry_delay.try_into_or_none(),
+                    is_last_attempt,
+                    previous_attempt_failure,
                 },
             },
             first_arg,
@@ -147,6 +166,7 @@ impl ActContext {
         self.worker.record_activity_heartbeat(ActivityHeartbeat {
             task_token: self.info.task_token.clone(),
             details,
+            throttle_interval: None,
         })
     }
 
@@ -160,6 +180,13 @@ impl ActContext {
         &self.header_fields
     }
 
+    /// Get the trace context propagated to this activity in its headers, as a text map for an
+    /// OpenTelemetry propagator to extract from. See
+    /// [crate::interceptors::TraceContextPropagationInterceptor].
+    pub fn trace_context(&self) -> Option<HashMap<String, String>> {
+        extract_trace_context(&self.header_fields)
+    }
+
     /// Get custom Application Data
     pub fn app_data<T: Send + Sync + 'static>(&self) -> Option<&T> {
         self.app_data.get::<T>()
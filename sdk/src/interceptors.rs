@@ -2,9 +2,17 @@
 
 use crate::Worker;
 use anyhow::bail;
-use temporal_sdk_core_protos::coresdk::{
-    workflow_activation::{remove_from_cache::EvictionReason, WorkflowActivation},
-    workflow_completion::WorkflowActivationCompletion,
+use std::{cell::RefCell, collections::HashMap};
+use temporal_sdk_core_protos::{
+    constants::TRACING_HEADER_KEY,
+    coresdk::{
+        workflow_activation::{
+            remove_from_cache::EvictionReason, workflow_activation_job, WorkflowActivation,
+        },
+        workflow_commands::workflow_command,
+        workflow_completion::{workflow_activation_completion, WorkflowActivationCompletion},
+    },
+    temporal::api::common::v1::Payload,
 };
 
 /// Implementors can intercept certain actions that happen within the Worker.
@@ -12,6 +20,14 @@ use temporal_sdk_core_protos::coresdk::{
 /// Advanced usage only.
 #[async_trait::async_trait(?Send)]
 pub trait WorkerInterceptor {
+    /// Called every time a workflow activation completes, before
+    /// [Self::on_workflow_activation_completion], and may alter the completion, ex: to add headers
+    /// to the commands it contains.
+    fn modify_workflow_activation_completion(
+        &self,
+        _completion: &mut WorkflowActivationCompletion,
+    ) {
+    }
     /// Called every time a workflow activation completes (just before sending the completion to
     /// core).
     async fn on_workflow_activation_completion(&self, _completion: &WorkflowActivationCompletion) {}
@@ -48,3 +64,65 @@ impl WorkerInterceptor for FailOnNondeterminismInterceptor {
         Ok(())
     }
 }
+
+/// Propagates the trace context each workflow was started with, found in its headers under
+/// [TRACING_HEADER_KEY], to the activities, child workflows, signals, and continued-as-new runs
+/// it starts, so they appear in the same trace. Commands whose headers already carry trace context
+/// keep it. Running activities can read theirs with [crate::ActContext::trace_context].
+#[derive(Default)]
+pub struct TraceContextPropagationInterceptor {
+    run_contexts: RefCell<HashMap<String, Payload>>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl WorkerInterceptor for TraceContextPropagationInterceptor {
+    fn modify_workflow_activation_completion(&self, completion: &mut WorkflowActivationCompletion) {
+        let run_contexts = self.run_contexts.borrow();
+        let Some(context) = run_contexts.get(&completion.run_id) else {
+            return;
+        };
+        let Some(workflow_activation_completion::Status::Successful(success)) =
+            completion.status.as_mut()
+        else {
+            return;
+        };
+        for command in success.commands.iter_mut() {
+            let headers = match command.variant.as_mut() {
+                Some(workflow_command::Variant::ScheduleActivity(c)) => &mut c.headers,
+                Some(workflow_command::Variant::ScheduleLocalActivity(c)) => &mut c.headers,
+                Some(workflow_command::Variant::StartChildWorkflowExecution(c)) => &mut c.headers,
+                Some(workflow_command::Variant::SignalExternalWorkflowExecution(c)) => {
+                    &mut c.headers
+                }
+                Some(workflow_command::Variant::ContinueAsNewWorkflowExecution(c)) => {
+                    &mut c.headers
+                }
+                _ => continue,
+            };
+            headers
+                .entry(TRACING_HEADER_KEY.to_string())
+                .or_insert_with(|| context.clone());
+        }
+    }
+
+    async fn on_workflow_activation(
+        &self,
+        activation: &WorkflowActivation,
+    ) -> Result<(), anyhow::Error> {
+        let mut run_contexts = self.run_contexts.borrow_mut();
+        for job in &activation.jobs {
+            match &job.variant {
+                Some(workflow_activation_job::Variant::StartWorkflow(s)) => {
+                    if let Some(context) = s.headers.get(TRACING_HEADER_KEY) {
+                        run_contexts.insert(activation.run_id.clone(), context.clone());
+                    }
+                }
+                Some(workflow_activation_job::Variant::RemoveFromCache(_)) => {
+                    run_contexts.remove(&activation.run_id);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
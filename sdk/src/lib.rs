@@ -48,19 +48,24 @@ extern crate tracing;
 mod activity_context;
 mod app_data;
 pub mod interceptors;
+mod local_activity_executor;
 mod payload_converter;
 mod workflow_context;
 mod workflow_future;
 
 pub use activity_context::ActContext;
+pub use local_activity_executor::LocalActivityExecutor;
 pub use temporal_client::Namespace;
 pub use workflow_context::{
     ActivityOptions, CancellableFuture, ChildWorkflow, ChildWorkflowOptions, LocalActivityOptions,
     PendingChildWorkflow, Signal, SignalData, SignalWorkflowOptions, StartedChildWorkflow,
-    WfContext,
+    TimerOptions, WfContext,
 };
 
-use crate::{interceptors::WorkerInterceptor, workflow_context::ChildWfCommon};
+use crate::{
+    interceptors::WorkerInterceptor, local_activity_executor::LocalActivitySpawner,
+    workflow_context::ChildWfCommon,
+};
 use anyhow::{anyhow, bail, Context};
 use app_data::AppData;
 use futures::{future::BoxFuture, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
@@ -78,6 +83,7 @@ use temporal_client::ClientOptionsBuilder;
 use temporal_sdk_core::Url;
 use temporal_sdk_core_api::{
     errors::{PollActivityError, PollWfError},
+    telemetry::metrics::TemporalMeter,
     Worker as CoreWorker,
 };
 use temporal_sdk_core_protos::{
@@ -156,25 +162,32 @@ struct ActivityHalf {
     /// Maps activity type to the function for executing activities of that type
     activity_fns: HashMap<String, ActivityFunction>,
     task_tokens_to_cancels: HashMap<TaskToken, CancellationToken>,
+    local_activity_spawner: LocalActivitySpawner,
 }
 
 impl Worker {
     /// Create a new Rust SDK worker from a core worker
     pub fn new_from_core(worker: Arc<dyn CoreWorker>, task_queue: impl Into<String>) -> Self {
+        let task_queue = task_queue.into();
         Self {
+            activity_half: ActivityHalf {
+                activity_fns: Default::default(),
+                task_tokens_to_cancels: Default::default(),
+                local_activity_spawner: LocalActivitySpawner::new(
+                    Default::default(),
+                    None,
+                    &task_queue,
+                ),
+            },
             common: CommonWorker {
                 worker,
-                task_queue: task_queue.into(),
+                task_queue,
                 worker_interceptor: None,
             },
             workflow_half: WorkflowHalf {
                 workflows: Default::default(),
                 workflow_fns: Default::default(),
             },
-            activity_half: ActivityHalf {
-                activity_fns: Default::default(),
-                task_tokens_to_cancels: Default::default(),
-            },
             app_data: Some(Default::default()),
         }
     }
@@ -219,6 +232,24 @@ impl Worker {
         );
     }
 
+    /// Set where local activity functions are run. If `meter` is provided, the number of local
+    /// activities waiting for and running on the executor are reported with it.
+    ///
+    /// Returns an error if the executor is a [LocalActivityExecutor::BlockingPool] with
+    /// `max_threads` of zero, since no local activity could ever run on it.
+    pub fn set_local_activity_executor(
+        &mut self,
+        executor: LocalActivityExecutor,
+        meter: Option<TemporalMeter>,
+    ) -> Result<(), anyhow::Error> {
+        if let LocalActivityExecutor::BlockingPool { max_threads: 0 } = executor {
+            bail!("Local activity blocking pool must allow at least one thread");
+        }
+        self.activity_half.local_activity_spawner =
+            LocalActivitySpawner::new(executor, meter, &self.common.task_queue);
+        Ok(())
+    }
+
     /// Insert Custom App Context for Workflows and Activities
     pub fn insert_app_data<T: Send + Sync + 'static>(&mut self, data: T) {
         self.app_data.as_mut().map(|a| a.insert(data));
@@ -259,7 +290,12 @@ impl Worker {
         };
         let wf_completion_processor = async {
             UnboundedReceiverStream::new(completions_rx)
-                .map(Ok)
+                .map(|mut completion| {
+                    if let Some(ref i) = common.worker_interceptor {
+                        i.modify_workflow_activation_completion(&mut completion);
+                    }
+                    Ok(completion)
+                })
                 .try_for_each_concurrent(None, |completion| async {
                     if let Some(ref i) = common.worker_interceptor {
                         i.on_workflow_activation_completion(&completion).await;
@@ -471,6 +507,7 @@ impl ActivityHalf {
                         )
                     })?
                     .clone();
+                let is_local = start.is_local;
                 let ct = CancellationToken::new();
                 let task_token = activity.task_token;
                 self.task_tokens_to_cancels
@@ -484,7 +521,7 @@ impl ActivityHalf {
                     task_token.clone(),
                     start,
                 );
-                tokio::spawn(async move {
+                let run_activity = async move {
                     let output = AssertUnwindSafe((act_fn.act_func)(ctx, arg))
                         .catch_unwind()
                         .await;
@@ -518,7 +555,12 @@ impl ActivityHalf {
                         })
                         .await?;
                     Ok::<_, anyhow::Error>(())
-                });
+                };
+                if is_local {
+                    self.local_activity_spawner.spawn(run_activity);
+                } else {
+                    tokio::spawn(run_activity);
+                }
             }
             Some(activity_task::Variant::Cancel(_)) => {
                 if let Some(ct) = self
@@ -542,6 +584,7 @@ enum UnblockEvent {
     WorkflowComplete(u32, Box<ChildWorkflowResult>),
     SignalExternal(u32, Option<Failure>),
     CancelExternal(u32, Option<Failure>),
+    MutableSideEffect(u32, Option<Payload>),
 }
 
 /// Result of awaiting on a timer
@@ -639,6 +682,16 @@ impl Unblockable for CancelExternalWfResult {
     }
 }
 
+impl Unblockable for Payload {
+    type OtherDat = ();
+    fn unblock(ue: UnblockEvent, _: Self::OtherDat) -> Self {
+        match ue {
+            UnblockEvent::MutableSideEffect(_, value) => value.unwrap_or_default(),
+            _ => panic!("Invalid unblock event for mutable side effect"),
+        }
+    }
+}
+
 /// Identifier for cancellable operations
 #[derive(Debug, Clone)]
 pub enum CancellableID {
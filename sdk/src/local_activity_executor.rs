@@ -0,0 +1,165 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use temporal_sdk_core_api::telemetry::metrics::{
+    CoreMeter, Gauge, MetricAttributes, MetricKeyValue, MetricParameters, TemporalMeter,
+};
+use tokio::{runtime::Handle, sync::Semaphore};
+
+/// Determines where a [crate::Worker] runs local activity functions. Running them somewhere other
+/// than the worker's own runtime keeps CPU-heavy local activities from starving workflow
+/// processing and polling.
+#[derive(Clone, Debug, Default)]
+pub enum LocalActivityExecutor {
+    /// Run local activities on the same runtime as the rest of the worker
+    #[default]
+    Shared,
+    /// Spawn local activities onto the runtime with the provided handle
+    Runtime(Handle),
+    /// Run each local activity to completion on a thread from the blocking pool of the worker's
+    /// runtime, with at most `max_threads` running at once. Local activities beyond that wait
+    /// for a thread to free up.
+    BlockingPool {
+        /// The maximum number of local activities which may run at once. Must be at least one.
+        max_threads: usize,
+    },
+}
+
+/// Runs local activities using the configured [LocalActivityExecutor], tracking how many are
+/// waiting to run and how many are running.
+pub(crate) struct LocalActivitySpawner {
+    executor: LocalActivityExecutor,
+    blocking_permits: Option<Arc<Semaphore>>,
+    metrics: Arc<ExecutorMetrics>,
+}
+
+impl LocalActivitySpawner {
+    pub(crate) fn new(
+        executor: LocalActivityExecutor,
+        meter: Option<TemporalMeter>,
+        task_queue: &str,
+    ) -> Self {
+        let blocking_permits = match &executor {
+            LocalActivityExecutor::BlockingPool { max_threads } => {
+                Some(Arc::new(Semaphore::new(*max_threads)))
+            }
+            _ => None,
+        };
+        Self {
+            executor,
+            blocking_permits,
+            metrics: Arc::new(ExecutorMetrics::new(meter, task_queue)),
+        }
+    }
+
+    /// Runs the provided local activity future on the configured executor
+    pub(crate) fn spawn<F>(&self, fut: F)
+    where
+        F: Future<Output = Result<(), anyhow::Error>> + Send + 'static,
+    {
+        let metrics = self.metrics.clone();
+        metrics.queued();
+        let tracked = async move {
+            metrics.started();
+            let res = fut.await;
+            metrics.finished();
+            res
+        };
+        match &self.executor {
+            LocalActivityExecutor::Shared => {
+                tokio::spawn(tracked);
+            }
+            LocalActivityExecutor::Runtime(handle) => {
+                handle.spawn(tracked);
+            }
+            LocalActivityExecutor::BlockingPool { .. } => {
+                let permits = self
+                    .blocking_permits
+                    .clone()
+                    .expect("Blocking pool executor has permits");
+                let handle = Handle::current();
+                tokio::spawn(async move {
+                    let _permit = permits
+                        .acquire_owned()
+                        .await
+                        .expect("Local activity executor semaphore is never closed");
+                    tokio::task::spawn_blocking(move || handle.block_on(tracked)).await
+                });
+            }
+        }
+    }
+}
+
+struct ExecutorMetrics {
+    queued: AtomicU64,
+    running: AtomicU64,
+    instruments: Option<ExecutorInstruments>,
+}
+
+struct ExecutorInstruments {
+    queue_depth: Arc<dyn Gauge>,
+    running: Arc<dyn Gauge>,
+    attributes: MetricAttributes,
+}
+
+impl ExecutorMetrics {
+    fn new(meter: Option<TemporalMeter>, task_queue: &str) -> Self {
+        let instruments = meter.map(|meter| {
+            let mut attribs = meter.default_attribs.clone();
+            attribs.extend([MetricKeyValue::new("task_queue", task_queue.to_string())]);
+            ExecutorInstruments {
+                queue_depth: meter.inner.gauge(MetricParameters {
+                    name: "local_activity_executor_queue_depth".into(),
+                    description: "Current number of local activities waiting to start on the \
+                                  local activity executor"
+                        .into(),
+                    unit: "".into(),
+                }),
+                running: meter.inner.gauge(MetricParameters {
+                    name: "local_activity_executor_running".into(),
+                    description: "Current number of local activities running on the local \
+                                  activity executor"
+                        .into(),
+                    unit: "".into(),
+                }),
+                attributes: meter.inner.new_attributes(attribs),
+            }
+        });
+        Self {
+            queued: AtomicU64::new(0),
+            running: AtomicU64::new(0),
+            instruments,
+        }
+    }
+
+    fn queued(&self) {
+        let queued = self.queued.fetch_add(1, Ordering::AcqRel) + 1;
+        self.record(Some(queued), None);
+    }
+
+    fn started(&self) {
+        let queued = self.queued.fetch_sub(1, Ordering::AcqRel) - 1;
+        let running = self.running.fetch_add(1, Ordering::AcqRel) + 1;
+        self.record(Some(queued), Some(running));
+    }
+
+    fn finished(&self) {
+        let running = self.running.fetch_sub(1, Ordering::AcqRel) - 1;
+        self.record(None, Some(running));
+    }
+
+    fn record(&self, queued: Option<u64>, running: Option<u64>) {
+        if let Some(i) = self.instruments.as_ref() {
+            if let Some(q) = queued {
+                i.queue_depth.record(q, &i.attributes);
+            }
+            if let Some(r) = running {
+                i.running.record(r, &i.attributes);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ mod options;
 
 pub use options::{
     ActivityOptions, ChildWorkflowOptions, LocalActivityOptions, Signal, SignalData,
-    SignalWorkflowOptions,
+    SignalWorkflowOptions, TimerOptions,
 };
 
 use crate::{
@@ -35,12 +35,20 @@ use temporal_sdk_core_protos::{
         workflow_commands::{
             request_cancel_external_workflow_execution as cancel_we,
             signal_external_workflow_execution as sig_we, workflow_command,
-            CancelChildWorkflowExecution, ModifyWorkflowProperties,
-            RequestCancelExternalWorkflowExecution, SetPatchMarker,
-            SignalExternalWorkflowExecution, StartTimer, UpsertWorkflowSearchAttributes,
+            CancelChildWorkflowExecution, ModifyWorkflowProperties, MutableSideEffect,
+            RequestCancelExternalWorkflowExecution, SetPatchMarker, SetWorkflowMetadata,
+            SignalExternalWorkflowExecution, UpsertWorkflowSearchAttributes,
         },
     },
-    temporal::api::common::v1::{Memo, Payload},
+    search_attributes::{
+        SearchAttributeError, SearchAttributeSchema, SearchAttributeValidationError,
+        TypedSearchAttributes,
+    },
+    temporal::api::{
+        common::v1::{Memo, Payload},
+        failure::v1::Failure,
+        sdk::v1::WorkflowMetadata,
+    },
 };
 use tokio::sync::{mpsc, oneshot, watch};
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -53,7 +61,7 @@ pub struct WfContext {
     args: Arc<Vec<Payload>>,
 
     chan: Sender<RustWfCmd>,
-    am_cancelled: watch::Receiver<bool>,
+    am_cancelled: watch::Receiver<Option<String>>,
     pub(crate) shared: Arc<RwLock<WfContextSharedData>>,
 
     seq_nums: Arc<RwLock<WfCtxProtectedDat>>,
@@ -65,6 +73,7 @@ struct WfCtxProtectedDat {
     next_child_workflow_sequence_number: u32,
     next_cancel_external_wf_sequence_number: u32,
     next_signal_external_wf_sequence_number: u32,
+    next_mutable_side_effect_sequence_number: u32,
 }
 
 impl WfCtxProtectedDat {
@@ -93,16 +102,31 @@ impl WfCtxProtectedDat {
         self.next_signal_external_wf_sequence_number += 1;
         seq
     }
+    fn next_mutable_side_effect_seq(&mut self) -> u32 {
+        let seq = self.next_mutable_side_effect_sequence_number;
+        self.next_mutable_side_effect_sequence_number += 1;
+        seq
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub(crate) struct WfContextSharedData {
     /// Maps change ids -> resolved status
     pub changes: HashMap<String, bool>,
+    /// Maps change ids -> the data their markers were recorded with
+    pub patch_data: HashMap<String, Payload>,
     pub is_replaying: bool,
     pub wf_time: Option<SystemTime>,
     pub history_length: u32,
+    pub history_size_bytes: u64,
+    pub continue_as_new_suggested: bool,
     pub current_build_id: Option<String>,
+    /// The workflow's memo, as of the start of the workflow and any upserts made since
+    pub memo: HashMap<String, Payload>,
+    /// The result of the run this one continued from, if it completed
+    pub last_completion_result: Option<Payload>,
+    /// The failure of the run this one continued from, if it failed
+    pub last_failure: Option<Failure>,
 }
 
 // TODO: Dataconverter type interface to replace Payloads here. Possibly just use serde
@@ -114,7 +138,7 @@ impl WfContext {
         namespace: String,
         task_queue: String,
         args: Vec<Payload>,
-        am_cancelled: watch::Receiver<bool>,
+        am_cancelled: watch::Receiver<Option<String>>,
     ) -> (Self, Receiver<RustWfCmd>) {
         // We need to use a normal std channel since our receiving side is non-async
         let (chan, rx) = crossbeam_channel::unbounded();
@@ -132,6 +156,7 @@ impl WfContext {
                     next_child_workflow_sequence_number: 1,
                     next_cancel_external_wf_sequence_number: 1,
                     next_signal_external_wf_sequence_number: 1,
+                    next_mutable_side_effect_sequence_number: 1,
                 })),
             },
             rx,
@@ -158,39 +183,62 @@ impl WfContext {
         self.shared.read().history_length
     }
 
+    /// Return the size of history so far in bytes, as of the start of the current workflow task
+    pub fn history_size_bytes(&self) -> u64 {
+        self.shared.read().history_size_bytes
+    }
+
+    /// Returns true if the server suggests this workflow continue as new, for example because its
+    /// history is getting large
+    pub fn continue_as_new_suggested(&self) -> bool {
+        self.shared.read().continue_as_new_suggested
+    }
+
     /// Return the Build ID as it was when this point in the workflow was first reached. If this
     /// code is being executed for the first time, return this Worker's Build ID if it has one.
     pub fn current_build_id(&self) -> Option<String> {
         self.shared.read().current_build_id.clone()
     }
 
+    /// Return the workflow's memo, including any fields upserted so far with [Self::upsert_memo]
+    pub fn memo(&self) -> HashMap<String, Payload> {
+        self.shared.read().memo.clone()
+    }
+
+    /// Return the result of the previous run, if this workflow continued from one which completed.
+    /// For a cron or scheduled workflow this is the result of the last successful run, even if runs
+    /// have failed since.
+    pub fn last_completion_result(&self) -> Option<Payload> {
+        self.shared.read().last_completion_result.clone()
+    }
+
+    /// Return the failure of the previous run, if this workflow continued from one which failed,
+    /// for example a failed attempt being retried, or the last run of a cron workflow
+    pub fn last_failure(&self) -> Option<Failure> {
+        self.shared.read().last_failure.clone()
+    }
+
     /// A future that resolves if/when the workflow is cancelled
     pub async fn cancelled(&self) {
-        if *self.am_cancelled.borrow() {
-            return;
-        }
         self.am_cancelled
             .clone()
-            .changed()
+            .wait_for(Option::is_some)
             .await
             .expect("Cancelled send half not dropped");
     }
 
-    /// Request to create a timer
-    pub fn timer(&self, duration: Duration) -> impl CancellableFuture<TimerResult> {
+    /// The reason given for cancelling the workflow, which may be empty, if it has been cancelled
+    pub fn cancelled_reason(&self) -> Option<String> {
+        self.am_cancelled.borrow().clone()
+    }
+
+    /// Request to create a timer, from a duration or [TimerOptions]
+    pub fn timer(&self, opts: impl Into<TimerOptions>) -> impl CancellableFuture<TimerResult> {
         let seq = self.seq_nums.write().next_timer_seq();
         let (cmd, unblocker) = CancellableWFCommandFut::new(CancellableID::Timer(seq));
         self.send(
             CommandCreateRequest {
-                cmd: StartTimer {
-                    seq,
-                    start_to_fire_timeout: Some(
-                        duration
-                            .try_into()
-                            .expect("Durations must fit into 64 bits"),
-                    ),
-                }
-                .into(),
+                cmd: opts.into().into_command(seq).into(),
                 unblocker,
             }
             .into(),
@@ -259,11 +307,40 @@ impl WfContext {
         self.patch_impl(patch_id, true)
     }
 
+    /// Like [Self::patched], but records `data` in the patch marker when the patch is first
+    /// applied. Returns the recorded data if this workflow history was created with the patch, or
+    /// `None` if it wasn't. Useful for recording a value chosen when the patch was applied, which
+    /// must be the same whenever the workflow is replayed.
+    pub fn patched_with_data(&self, patch_id: &str, data: Payload) -> Option<Payload> {
+        if !self.patch_impl_with_data(patch_id, false, Some(data.clone())) {
+            return None;
+        }
+        // Markers recorded by a plain `patched` call have no data, in which case ours is used
+        Some(
+            self.shared
+                .write()
+                .patch_data
+                .entry(patch_id.to_string())
+                .or_insert(data)
+                .clone(),
+        )
+    }
+
     fn patch_impl(&self, patch_id: &str, deprecated: bool) -> bool {
+        self.patch_impl_with_data(patch_id, deprecated, None)
+    }
+
+    fn patch_impl_with_data(
+        &self,
+        patch_id: &str,
+        deprecated: bool,
+        data: Option<Payload>,
+    ) -> bool {
         self.send(
             workflow_command::Variant::SetPatchMarker(SetPatchMarker {
                 patch_id: patch_id.to_string(),
                 deprecated,
+                data,
             })
             .into(),
         );
@@ -292,7 +369,7 @@ impl WfContext {
     ) -> impl CancellableFuture<SignalExternalWfResult> {
         let options: SignalWorkflowOptions = opts.into();
         let target = sig_we::Target::WorkflowExecution(NamespacedWorkflowExecution {
-            namespace: self.namespace.clone(),
+            namespace: options.namespace.unwrap_or_else(|| self.namespace.clone()),
             workflow_id: options.workflow_id,
             run_id: options.run_id.unwrap_or_default(),
         });
@@ -310,13 +387,55 @@ impl WfContext {
         ))
     }
 
-    /// Add or create a set of search attributes
+    /// Add or create a set of typed search attributes, encoded as the server expects
+    pub fn upsert_typed_search_attributes(
+        &self,
+        attrs: &TypedSearchAttributes,
+    ) -> Result<(), SearchAttributeError> {
+        self.upsert_search_attributes(attrs.to_payloads()?);
+        Ok(())
+    }
+
+    /// Add or create a set of typed search attributes, once they're checked against the types
+    /// they're registered with. If any are invalid none are upserted, and the error says what's
+    /// wrong with each of them, where the server would fail the whole workflow task instead.
+    pub fn upsert_validated_search_attributes(
+        &self,
+        attrs: &TypedSearchAttributes,
+        schema: &SearchAttributeSchema,
+    ) -> Result<(), SearchAttributeValidationError> {
+        schema.validate(attrs)?;
+        self.upsert_typed_search_attributes(attrs)?;
+        Ok(())
+    }
+
+    /// Add or update memo fields. A field set to an empty payload is removed from the memo.
     pub fn upsert_memo(&self, attr_iter: impl IntoIterator<Item = (String, Payload)>) {
+        let fields = HashMap::from_iter(attr_iter);
+        {
+            let mut shared = self.shared.write();
+            for (k, v) in &fields {
+                if *v == Payload::default() {
+                    shared.memo.remove(k);
+                } else {
+                    shared.memo.insert(k.clone(), v.clone());
+                }
+            }
+        }
         self.send(RustWfCmd::NewNonblockingCmd(
             workflow_command::Variant::ModifyWorkflowProperties(ModifyWorkflowProperties {
-                upserted_memo: Some(Memo {
-                    fields: HashMap::from_iter(attr_iter),
-                }),
+                upserted_memo: Some(Memo { fields }),
+            }),
+        ))
+    }
+
+    /// Set the metadata returned when the workflow is sent the built-in workflow metadata query,
+    /// such as its registered handlers and current details, replacing any set before. Only used if
+    /// the worker is configured with `answer_workflow_metadata_queries`.
+    pub fn set_workflow_metadata(&self, metadata: WorkflowMetadata) {
+        self.send(RustWfCmd::NewNonblockingCmd(
+            workflow_command::Variant::SetWorkflowMetadata(SetWorkflowMetadata {
+                metadata: Some(metadata),
             }),
         ))
     }
@@ -338,6 +457,15 @@ impl WfContext {
     pub fn cancel_external(
         &self,
         target: NamespacedWorkflowExecution,
+    ) -> impl Future<Output = CancelExternalWfResult> {
+        self.cancel_external_with_reason(target, String::new())
+    }
+
+    /// Like [Self::cancel_external], giving the reason for the cancellation
+    pub fn cancel_external_with_reason(
+        &self,
+        target: NamespacedWorkflowExecution,
+        reason: String,
     ) -> impl Future<Output = CancelExternalWfResult> {
         let target = cancel_we::Target::WorkflowExecution(target);
         let seq = self.seq_nums.write().next_cancel_external_wf_seq();
@@ -347,6 +475,35 @@ impl WfContext {
                 cmd: RequestCancelExternalWorkflowExecution {
                     seq,
                     target: Some(target),
+                    reason,
+                }
+                .into(),
+                unblocker,
+            }
+            .into(),
+        );
+        cmd
+    }
+
+    /// Produce a value which may change over the course of the workflow, such as a configuration
+    /// value. The value is only recorded in history when its `equality_token` differs from the
+    /// one most recently recorded for `id`, and resolves to the most recently recorded value.
+    /// When replaying, the recorded values are used in place of the provided ones.
+    pub fn mutable_side_effect(
+        &self,
+        id: impl Into<String>,
+        value: Payload,
+        equality_token: impl Into<String>,
+    ) -> impl Future<Output = Payload> {
+        let seq = self.seq_nums.write().next_mutable_side_effect_seq();
+        let (cmd, unblocker) = WFCommandFut::new();
+        self.send(
+            CommandCreateRequest {
+                cmd: MutableSideEffect {
+                    seq,
+                    id: id.into(),
+                    value: Some(value),
+                    equality_token: equality_token.into(),
                 }
                 .into(),
                 unblocker,
@@ -544,6 +701,7 @@ struct LATimerBackoffFut<'a> {
     ctx: &'a WfContext,
     next_attempt: u32,
     next_sched_time: Option<prost_types::Timestamp>,
+    last_failure: Option<Failure>,
     did_cancel: AtomicBool,
 }
 impl<'a> LATimerBackoffFut<'a> {
@@ -555,6 +713,7 @@ impl<'a> LATimerBackoffFut<'a> {
             ctx,
             next_attempt: 1,
             next_sched_time: None,
+            last_failure: None,
             did_cancel: AtomicBool::new(false),
         }
     }
@@ -574,6 +733,7 @@ impl<'a> Future for LATimerBackoffFut<'a> {
                         let mut opts = self.la_opts.clone();
                         opts.attempt = Some(self.next_attempt);
                         opts.original_schedule_time = self.next_sched_time.clone();
+                        opts.previous_attempt_failure = self.last_failure.take();
                         self.current_fut = Box::pin(self.ctx.local_activity_no_timer_retry(opts));
                         Poll::Pending
                     } else {
@@ -599,16 +759,17 @@ impl<'a> Future for LATimerBackoffFut<'a> {
                     });
                 }
 
-                let timer_f = self.ctx.timer(
-                    b.backoff_duration
-                        .clone()
-                        .expect("Duration is set")
-                        .try_into()
-                        .expect("duration converts ok"),
-                );
+                let backoff: Duration = b
+                    .backoff_duration
+                    .clone()
+                    .expect("Duration is set")
+                    .try_into()
+                    .expect("duration converts ok");
+                let timer_f = self.ctx.timer(backoff);
                 self.timer_fut = Some(Box::pin(timer_f));
                 self.next_attempt = b.attempt;
                 self.next_sched_time = b.original_schedule_time.clone();
+                self.last_failure = b.last_failure.clone();
                 return Poll::Pending;
             }
         }
@@ -717,9 +878,15 @@ impl StartedChildWorkflow {
 
     /// Cancel the child workflow
     pub fn cancel(&self, cx: &WfContext) {
+        self.cancel_with_reason(cx, String::new())
+    }
+
+    /// Cancel the child workflow, giving the reason for it
+    pub fn cancel_with_reason(&self, cx: &WfContext, reason: String) {
         cx.send(RustWfCmd::NewNonblockingCmd(
             CancelChildWorkflowExecution {
                 child_workflow_seq: self.common.result_future.cancellable_id.seq_num(),
+                reason,
             }
             .into(),
         ));
@@ -6,12 +6,15 @@ use temporal_sdk_core_protos::{
         child_workflow::ChildWorkflowCancellationType,
         workflow_commands::{
             ActivityCancellationType, ScheduleActivity, ScheduleLocalActivity,
-            StartChildWorkflowExecution,
+            StartChildWorkflowExecution, StartTimer,
         },
+        AsJsonPayloadExt,
     },
     temporal::api::{
-        common::v1::{Payload, RetryPolicy},
+        common::v1::{Payload, Priority, RetryPolicy},
         enums::v1::ParentClosePolicy,
+        failure::v1::Failure,
+        sdk::v1::UserMetadata,
     },
 };
 
@@ -24,6 +27,39 @@ pub trait IntoWorkflowCommand {
     fn into_command(self, seq: u32) -> Self::WFCommandType;
 }
 
+/// Options for starting a timer
+#[derive(Default, Debug, Clone)]
+pub struct TimerOptions {
+    /// How long until the timer fires
+    pub duration: Duration,
+    /// Summary of the timer, shown by user interfaces to identify it
+    pub summary: Option<String>,
+}
+
+impl From<Duration> for TimerOptions {
+    fn from(duration: Duration) -> Self {
+        Self {
+            duration,
+            summary: None,
+        }
+    }
+}
+
+impl IntoWorkflowCommand for TimerOptions {
+    type WFCommandType = StartTimer;
+    fn into_command(self, seq: u32) -> StartTimer {
+        StartTimer {
+            seq,
+            start_to_fire_timeout: Some(
+                self.duration
+                    .try_into()
+                    .expect("Durations must fit into 64 bits"),
+            ),
+            user_metadata: user_metadata(self.summary, None),
+        }
+    }
+}
+
 /// Options for scheduling an activity
 #[derive(Default, Debug)]
 pub struct ActivityOptions {
@@ -66,6 +102,10 @@ pub struct ActivityOptions {
     pub cancellation_type: ActivityCancellationType,
     /// Activity retry policy
     pub retry_policy: Option<RetryPolicy>,
+    /// Priority of the activity. Unset fields are inherited from the workflow.
+    pub priority: Option<Priority>,
+    /// Summary of the activity, shown by user interfaces to identify it
+    pub summary: Option<String>,
 }
 
 impl IntoWorkflowCommand for ActivityOptions {
@@ -90,6 +130,8 @@ impl IntoWorkflowCommand for ActivityOptions {
             cancellation_type: self.cancellation_type as i32,
             arguments: vec![self.input],
             retry_policy: self.retry_policy,
+            priority: self.priority,
+            user_metadata: user_metadata(self.summary, None),
             ..Default::default()
         }
     }
@@ -117,6 +159,9 @@ pub struct LocalActivityOptions {
     /// Override schedule time when doing timer backoff.
     /// Ideally we would not expose this in a released Rust SDK, but it's needed for test.
     pub original_schedule_time: Option<prost_types::Timestamp>,
+    /// Override the previous attempt's failure when doing timer backoff.
+    /// Ideally we would not expose this in a released Rust SDK, but it's needed for test.
+    pub previous_attempt_failure: Option<Failure>,
     /// Retry backoffs over this amount will use a timer rather than a local retry
     pub timer_backoff_threshold: Option<Duration>,
     /// How the activity will cancel
@@ -150,6 +195,7 @@ impl IntoWorkflowCommand for LocalActivityOptions {
             seq,
             attempt: self.attempt.unwrap_or(1),
             original_schedule_time: self.original_schedule_time,
+            previous_attempt_failure: self.previous_attempt_failure,
             activity_id: match self.activity_id {
                 None => seq.to_string(),
                 Some(aid) => aid,
@@ -180,6 +226,8 @@ pub struct ChildWorkflowOptions {
     pub workflow_type: String,
     /// Task queue to schedule the workflow in
     pub task_queue: String,
+    /// Namespace to start the workflow in, or the parent's namespace if `None`
+    pub namespace: Option<String>,
     /// Input to send the child Workflow
     pub input: Vec<Payload>,
     /// Cancellation strategy for the child workflow
@@ -188,6 +236,12 @@ pub struct ChildWorkflowOptions {
     pub options: WorkflowOptions,
     /// How to respond to parent workflow ending
     pub parent_close_policy: ParentClosePolicy,
+    /// Priority of the child workflow. Unset fields are inherited from the parent.
+    pub priority: Option<Priority>,
+    /// Summary of the child workflow, shown by user interfaces as of its start
+    pub summary: Option<String>,
+    /// Longer form details of the child workflow, shown by user interfaces as of its start
+    pub details: Option<String>,
 }
 
 impl IntoWorkflowCommand for ChildWorkflowOptions {
@@ -198,6 +252,7 @@ impl IntoWorkflowCommand for ChildWorkflowOptions {
             workflow_id: self.workflow_id,
             workflow_type: self.workflow_type,
             task_queue: self.task_queue,
+            namespace: self.namespace.unwrap_or_default(),
             input: self.input,
             cancellation_type: self.cancel_type as i32,
             workflow_id_reuse_policy: self.options.id_reuse_policy as i32,
@@ -213,13 +268,29 @@ impl IntoWorkflowCommand for ChildWorkflowOptions {
             search_attributes: self.options.search_attributes.unwrap_or_default(),
             cron_schedule: self.options.cron_schedule.unwrap_or_default(),
             parent_close_policy: self.parent_close_policy as i32,
+            priority: self.priority,
+            user_metadata: user_metadata(self.summary, self.details),
             ..Default::default()
         }
     }
 }
 
+/// Metadata for user interfaces is made of JSON string payloads
+fn user_metadata(summary: Option<String>, details: Option<String>) -> Option<UserMetadata> {
+    if summary.is_none() && details.is_none() {
+        return None;
+    }
+    let as_payload = |s: String| s.as_json_payload().expect("Strings serialize to JSON");
+    Some(UserMetadata {
+        summary: summary.map(as_payload),
+        details: details.map(as_payload),
+    })
+}
+
 /// Options for sending a signal to an external workflow
 pub struct SignalWorkflowOptions {
+    /// The namespace the workflow is in, or the signalling workflow's namespace if `None`
+    pub namespace: Option<String>,
     /// The workflow's id
     pub workflow_id: String,
     /// The particular run to target, or latest if `None`
@@ -237,6 +308,7 @@ impl SignalWorkflowOptions {
         input: impl IntoIterator<Item = impl Into<Payload>>,
     ) -> Self {
         Self {
+            namespace: None,
             workflow_id: workflow_id.into(),
             run_id: Some(run_id.into()),
             signal: Signal::new(name, input),
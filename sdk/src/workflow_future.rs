@@ -53,7 +53,7 @@ impl WorkflowFunction {
         impl Future<Output = WorkflowResult<Payload>>,
         UnboundedSender<WorkflowActivation>,
     ) {
-        let (cancel_tx, cancel_rx) = watch::channel(false);
+        let (cancel_tx, cancel_rx) = watch::channel(None);
         let (wf_context, cmd_receiver) = WfContext::new(namespace, task_queue, args, cancel_rx);
         let (tx, incoming_activations) = unbounded_channel();
         (
@@ -105,7 +105,7 @@ pub struct WorkflowFuture {
     /// Commands by ID -> blocked status
     command_status: HashMap<CommandID, WFCommandFutInfo>,
     /// Use to notify workflow code of cancellation
-    cancel_sender: watch::Sender<bool>,
+    cancel_sender: watch::Sender<Option<String>>,
     /// Copy of the workflow context
     wf_ctx: WfContext,
     /// Mapping of sequence number to a StartChildWorkflowExecution request
@@ -127,6 +127,7 @@ impl WorkflowFuture {
             UnblockEvent::WorkflowComplete(seq, _) => CommandID::ChildWorkflowComplete(seq),
             UnblockEvent::SignalExternal(seq, _) => CommandID::SignalExternal(seq),
             UnblockEvent::CancelExternal(seq, _) => CommandID::CancelExternal(seq),
+            UnblockEvent::MutableSideEffect(seq, _) => CommandID::MutableSideEffect(seq),
         };
         let unblocker = self.command_status.remove(&cmd_id);
         let _ = unblocker
@@ -190,17 +191,28 @@ impl WorkflowFuture {
                     seq,
                     Box::new(result.context("Child Workflow execution must have a result")?),
                 ))?,
+                // Nexus operations can't be scheduled from the Rust SDK yet
+                Variant::ResolveNexusOperationStart(_) | Variant::ResolveNexusOperation(_) => {
+                    bail!("Nexus operations are not implemented in the Rust SDK")
+                }
                 Variant::UpdateRandomSeed(_) => (),
+                // Never asked for, operations are scheduled without `deliver_late_resolution`
+                Variant::NotifyLateResolution(_) => (),
+                // Stacks of the workflow's futures aren't available, so none are reported
+                Variant::ReportStackTrace(_) => (),
+                Variant::NotifyWorkflowTerminated(t) => {
+                    info!(reason = %t.reason, identity = %t.identity, "Workflow was terminated");
+                }
                 Variant::QueryWorkflow(q) => {
                     error!(
                         "Queries are not implemented in the Rust SDK. Got query '{}'",
                         q.query_id
                     );
                 }
-                Variant::CancelWorkflow(_) => {
+                Variant::CancelWorkflow(c) => {
                     // TODO: Cancel pending futures, etc
                     self.cancel_sender
-                        .send(true)
+                        .send(Some(c.reason))
                         .expect("Cancel rx not dropped");
                 }
                 Variant::SignalWorkflow(sig) => {
@@ -218,8 +230,12 @@ impl WorkflowFuture {
                         }
                     }
                 }
-                Variant::NotifyHasPatch(NotifyHasPatch { patch_id }) => {
-                    self.wf_ctx.shared.write().changes.insert(patch_id, true);
+                Variant::NotifyHasPatch(NotifyHasPatch { patch_id, data }) => {
+                    let mut shared = self.wf_ctx.shared.write();
+                    if let Some(data) = data {
+                        shared.patch_data.insert(patch_id.clone(), data);
+                    }
+                    shared.changes.insert(patch_id, true);
                 }
                 Variant::ResolveSignalExternalWorkflow(attrs) => {
                     self.unblock(UnblockEvent::SignalExternal(attrs.seq, attrs.failure))?;
@@ -227,6 +243,9 @@ impl WorkflowFuture {
                 Variant::ResolveRequestCancelExternalWorkflow(attrs) => {
                     self.unblock(UnblockEvent::CancelExternal(attrs.seq, attrs.failure))?;
                 }
+                Variant::ResolveMutableSideEffect(attrs) => {
+                    self.unblock(UnblockEvent::MutableSideEffect(attrs.seq, attrs.value))?;
+                }
                 Variant::DoUpdate(u) => {
                     if let Some(impls) = self.updates.get_mut(&u.name) {
                         let info = UpdateInfo {
@@ -320,11 +339,24 @@ impl Future for WorkflowFuture {
                 wlock.is_replaying = activation.is_replaying;
                 wlock.wf_time = activation.timestamp.try_into_or_none();
                 wlock.history_length = activation.history_length;
+                wlock.history_size_bytes = activation.history_size_bytes;
+                wlock.continue_as_new_suggested = activation.continue_as_new_suggested;
                 wlock.current_build_id = if activation.build_id_for_current_task.is_empty() {
                     None
                 } else {
                     Some(activation.build_id_for_current_task)
                 };
+                if let Some(sw) = activation.jobs.iter().find_map(|j| match j.variant {
+                    Some(Variant::StartWorkflow(ref sw)) => Some(sw),
+                    _ => None,
+                }) {
+                    wlock.memo = sw.memo.clone().map(|m| m.fields).unwrap_or_default();
+                    wlock.last_completion_result = sw
+                        .last_completion_result
+                        .clone()
+                        .and_then(|p| p.payloads.into_iter().next());
+                    wlock.last_failure = sw.continued_failure.clone();
+                }
             }
 
             let mut die_of_eviction_when_done = false;
@@ -474,6 +506,7 @@ impl WorkflowFuture {
                                 workflow_command::Variant::CancelChildWorkflowExecution(
                                     CancelChildWorkflowExecution {
                                         child_workflow_seq: seq,
+                                        reason: String::new(),
                                     },
                                 ),
                             );
@@ -499,6 +532,7 @@ impl WorkflowFuture {
                                         } else {
                                             cancel_we::Target::WorkflowExecution(execution)
                                         }),
+                                        reason: String::new(),
                                     },
                                 ),
                             );
@@ -534,6 +568,9 @@ impl WorkflowFuture {
                         workflow_command::Variant::RequestCancelExternalWorkflowExecution(req) => {
                             CommandID::CancelExternal(req.seq)
                         }
+                        workflow_command::Variant::MutableSideEffect(req) => {
+                            CommandID::MutableSideEffect(req.seq)
+                        }
                         _ => unimplemented!("Command type not implemented"),
                     };
                     self.command_status.insert(
@@ -622,6 +659,7 @@ enum CommandID {
     ChildWorkflowComplete(u32),
     SignalExternal(u32),
     CancelExternal(u32),
+    MutableSideEffect(u32),
 }
 
 fn update_response(
@@ -5,7 +5,8 @@
 //! We can use `clap` if this needs more arguments / other stuff later on.
 
 use prost::Message;
-use temporal_client::WorkflowClientTrait;
+use temporal_client::HistoryFetchExt;
+use temporal_sdk_core_protos::temporal::api::enums::v1::HistoryEventFilterType;
 use temporal_sdk_core_test_utils::get_integ_server_options;
 
 #[tokio::main]
@@ -17,10 +18,8 @@ async fn main() -> Result<(), anyhow::Error> {
         .expect("must provide workflow id as only argument");
     let run_id = std::env::args().nth(2);
     let hist = client
-        .get_workflow_execution_history(wf_id.clone(), run_id, vec![])
-        .await?
-        .history
-        .expect("history field must be populated");
+        .fetch_workflow_history(wf_id.clone(), run_id, HistoryEventFilterType::AllEvent)
+        .await?;
     // Serialize history to file
     let byteified = hist.encode_to_vec();
     tokio::fs::write(format!("{wf_id}_history.bin"), &byteified).await?;
@@ -740,6 +740,7 @@ pub fn start_timer_cmd(seq: u32, duration: Duration) -> workflow_command::Varian
     StartTimer {
         seq,
         start_to_fire_timeout: Some(duration.try_into().expect("duration fits")),
+        ..Default::default()
     }
     .into()
 }
@@ -803,6 +804,7 @@ where
             vec![StartTimer {
                 seq,
                 start_to_fire_timeout: Some(duration.try_into().expect("duration fits")),
+                ..Default::default()
             }
             .into()],
         ))
@@ -1,6 +1,12 @@
 use std::time::Duration;
-use temporal_client::{RetryClient, WorkflowClientTrait, WorkflowService};
-use temporal_sdk_core_protos::temporal::api::workflowservice::v1::DescribeNamespaceRequest;
+use temporal_client::{
+    tonic::{codec::CompressionEncoding, Code},
+    RetryClient, WfClientExt, WorkflowClientTrait, WorkflowOptions, WorkflowService,
+    WorkflowStartOutcome,
+};
+use temporal_sdk_core_protos::temporal::api::{
+    enums::v1::WorkflowIdConflictPolicy, workflowservice::v1::DescribeNamespaceRequest,
+};
 use temporal_sdk_core_test_utils::{get_integ_server_options, CoreWfStarter, NAMESPACE};
 
 #[tokio::test]
@@ -34,3 +40,55 @@ async fn calls_get_system_info() {
     let raw_client = opts.connect_no_namespace(None).await.unwrap();
     assert!(raw_client.get_client().capabilities().is_some());
 }
+
+#[tokio::test]
+async fn can_use_gzip_compression() {
+    let mut opts = get_integ_server_options();
+    opts.send_compression = Some(CompressionEncoding::Gzip);
+    opts.accept_compression = vec![CompressionEncoding::Gzip];
+    let mut raw_client = opts.connect_no_namespace(None).await.unwrap();
+    raw_client
+        .describe_namespace(DescribeNamespaceRequest {
+            namespace: NAMESPACE.to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn start_with_use_existing_attaches_to_running_workflow() {
+    let wf_id = "start_with_use_existing_attaches_to_running_workflow";
+    let mut starter = CoreWfStarter::new(wf_id);
+    let client = starter.get_client().await;
+    let start = |id_conflict_policy| {
+        client.start_workflow_or_attach(
+            vec![],
+            starter.get_task_queue().to_string(),
+            wf_id.to_string(),
+            "not_polled".to_string(),
+            None,
+            WorkflowOptions {
+                id_conflict_policy,
+                ..Default::default()
+            },
+        )
+    };
+
+    let first = start(WorkflowIdConflictPolicy::Fail).await.unwrap();
+    let WorkflowStartOutcome::Started(first) = first else {
+        panic!("First start must start a run");
+    };
+    let second = start(WorkflowIdConflictPolicy::UseExisting).await.unwrap();
+    let WorkflowStartOutcome::AttachedToRunning(second) = second else {
+        panic!("Second start must attach to the running run");
+    };
+    assert_eq!(first.info().run_id, second.info().run_id);
+    let res = start(WorkflowIdConflictPolicy::Fail).await;
+    assert!(matches!(res, Err(e) if e.code() == Code::AlreadyExists));
+
+    client
+        .terminate_workflow_execution(wf_id.to_string(), None)
+        .await
+        .unwrap();
+}
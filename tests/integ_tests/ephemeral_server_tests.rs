@@ -30,6 +30,22 @@ async fn temporal_cli_fixed() {
     server.shutdown().await.unwrap();
 }
 
+#[cfg(unix)]
+#[tokio::test]
+async fn temporal_cli_unix_socket() {
+    let socket = std::env::temp_dir().join(format!("{}.sock", uuid::Uuid::new_v4()));
+    let config = TemporalDevServerConfigBuilder::default()
+        .exe(default_cached_download())
+        .unix_socket(Some(socket.clone()))
+        .build()
+        .unwrap();
+    let mut server = config.start_server().await.unwrap();
+    assert_eq!(server.unix_socket.as_ref(), Some(&socket));
+    assert_ephemeral_server_at(&server, server.unix_socket_url().unwrap()).await;
+    server.shutdown().await.unwrap();
+    assert!(!socket.exists());
+}
+
 #[tokio::test]
 async fn temporalite_default() {
     let config = TemporaliteConfigBuilder::default()
@@ -116,10 +132,15 @@ fn fixed_cached_download(version: &str) -> EphemeralExe {
 }
 
 async fn assert_ephemeral_server(server: &EphemeralServer) {
+    let target_url = Url::try_from(&*format!("http://{}", server.target)).unwrap();
+    assert_ephemeral_server_at(server, target_url).await
+}
+
+async fn assert_ephemeral_server_at(server: &EphemeralServer, target_url: Url) {
     // Connect and describe namespace
     let mut client = ClientOptionsBuilder::default()
         .identity("integ_tester".to_string())
-        .target_url(Url::try_from(&*format!("http://{}", server.target)).unwrap())
+        .target_url(target_url)
         .client_name("temporal-core".to_string())
         .client_version("0.1.0".to_string())
         .build()
@@ -62,6 +62,7 @@ async fn activity_heartbeat() {
         core.record_activity_heartbeat(ActivityHeartbeat {
             task_token: task.task_token.clone(),
             details: vec![],
+            throttle_interval: None,
         });
     }
 
@@ -134,6 +135,7 @@ async fn many_act_fails_with_heartbeats() {
         core.record_activity_heartbeat(ActivityHeartbeat {
             task_token: task.task_token.clone(),
             details: vec![[i].into()],
+            throttle_interval: None,
         });
 
         let compl = if i == 3 {
@@ -1,11 +1,19 @@
 use assert_matches::assert_matches;
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 use temporal_client::{WorkflowClientTrait, WorkflowOptions, WorkflowService};
-use temporal_sdk_core::{init_worker, telemetry::start_prometheus_metric_exporter, CoreRuntime};
+use temporal_sdk_core::{
+    init_worker,
+    telemetry::{
+        build_otlp_metric_exporter, start_prometheus_metric_exporter,
+        start_prometheus_metric_exporter_with_listener, telemetry_init,
+    },
+    CoreRuntime,
+};
 use temporal_sdk_core_api::{
     telemetry::{
         metrics::{CoreMeter, MetricAttributes, MetricParameters},
-        PrometheusExporterOptionsBuilder, TelemetryOptions,
+        OtelCollectorOptionsBuilder, OtelTracingOptionsBuilder, OtlpProtocol,
+        PrometheusExporterOptionsBuilder, TelemetryOptions, TelemetryOptionsBuilder,
     },
     worker::WorkerConfigBuilder,
     Worker,
@@ -30,7 +38,13 @@ use temporal_sdk_core_protos::{
 use temporal_sdk_core_test_utils::{
     get_integ_server_options, get_integ_telem_options, CoreWfStarter, NAMESPACE,
 };
-use tokio::{join, sync::Barrier, task::AbortHandle};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    join,
+    net::TcpListener,
+    sync::Barrier,
+    task::AbortHandle,
+};
 
 static ANY_PORT: &str = "127.0.0.1:0";
 
@@ -318,6 +332,21 @@ async fn one_slot_worker_reports_available_slot() {
              service_name=\"temporal-core-sdk\",task_queue=\"one_slot_worker_tq\",\
              worker_type=\"LocalActivityWorker\"}} 1"
         )));
+        // Local activities report the same kinds of metrics as remote ones
+        assert!(body.contains(&format!(
+            "temporal_local_activity_task_received{{activity_type=\"test_act\",\
+             namespace=\"{NAMESPACE}\",service_name=\"temporal-core-sdk\",\
+             task_queue=\"one_slot_worker_tq\",worker_type=\"LocalActivityWorker\",\
+             workflow_type=\"whatever\"}} 1"
+        )));
+        assert!(body.contains(&format!(
+            "temporal_local_activity_schedule_to_start_latency_count{{activity_type=\"test_act\",\
+             namespace=\"{NAMESPACE}\",service_name=\"temporal-core-sdk\",\
+             task_queue=\"one_slot_worker_tq\",worker_type=\"LocalActivityWorker\",\
+             workflow_type=\"whatever\"}} 1"
+        )));
+        assert!(body.contains("temporal_local_activity_execution_latency"));
+        assert!(body.contains("temporal_local_activity_endtoend_latency"));
     };
     join!(wf_polling, act_polling, testing);
 }
@@ -522,3 +551,154 @@ async fn latency_metrics(
         assert!(matching_line.contains("le=\"100\""));
     }
 }
+
+#[tokio::test]
+async fn otlp_http_exports_to_collector() {
+    let listener = TcpListener::bind(ANY_PORT).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let meter = build_otlp_metric_exporter(
+        OtelCollectorOptionsBuilder::default()
+            .url(format!("http://{addr}").parse().unwrap())
+            .headers(HashMap::from([(
+                "authorization".to_string(),
+                "Bearer sekret".to_string(),
+            )]))
+            .protocol(OtlpProtocol::Http)
+            .metric_periodicity(Duration::from_millis(100))
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+    meter
+        .counter(MetricParameters::from("exported_counter"))
+        .add(1, &meter.new_attributes(Default::default()));
+
+    let (mut conn, _) = tokio::time::timeout(Duration::from_secs(10), listener.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut request = vec![];
+    let mut buf = [0; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = conn.read(&mut buf).await.unwrap();
+        assert_ne!(n, 0, "Connection closed before request headers were sent");
+        request.extend_from_slice(&buf[..n]);
+    }
+    conn.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+        .await
+        .unwrap();
+    let request = String::from_utf8_lossy(&request).to_lowercase();
+    assert!(request.starts_with("post /v1/metrics "));
+    assert!(request.contains("content-type: application/x-protobuf"));
+    assert!(request.contains("authorization: bearer sekret"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn prometheus_global_labels_served_over_unix_socket() {
+    let sock_path = std::env::temp_dir().join(format!("prom-{}.sock", uuid::Uuid::new_v4()));
+    let listener = std::os::unix::net::UnixListener::bind(&sock_path).unwrap();
+    let prom_info = start_prometheus_metric_exporter_with_listener(
+        PrometheusExporterOptionsBuilder::default()
+            .global_tags(HashMap::from([
+                ("cluster".to_string(), "blue".to_string()),
+                ("region".to_string(), "us-east".to_string()),
+            ]))
+            .build()
+            .unwrap(),
+        listener,
+    )
+    .unwrap();
+    let _aborter = AbortOnDrop {
+        ah: prom_info.abort_handle,
+    };
+    prom_info
+        .meter
+        .counter(MetricParameters::from("labeled_counter"))
+        .add(1, &prom_info.meter.new_attributes(Default::default()));
+
+    let mut conn = tokio::net::UnixStream::connect(&sock_path).await.unwrap();
+    conn.write_all(b"GET /metrics HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut body = String::new();
+    conn.read_to_string(&mut body).await.unwrap();
+    std::fs::remove_file(&sock_path).unwrap();
+    let line = body
+        .lines()
+        .find(|l| l.starts_with("labeled_counter{"))
+        .unwrap();
+    assert!(line.contains("cluster=\"blue\""));
+    assert!(line.contains("region=\"us-east\""));
+}
+
+#[tokio::test]
+async fn prometheus_rejects_invalid_global_labels() {
+    let res = start_prometheus_metric_exporter(
+        PrometheusExporterOptionsBuilder::default()
+            .socket_addr(ANY_PORT.parse().unwrap())
+            .global_tags(HashMap::from([(
+                "not-a-label".to_string(),
+                "x".to_string(),
+            )]))
+            .build()
+            .unwrap(),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn prometheus_rejects_global_labels_colliding_with_core_attributes() {
+    let res = start_prometheus_metric_exporter(
+        PrometheusExporterOptionsBuilder::default()
+            .socket_addr(ANY_PORT.parse().unwrap())
+            .global_tags(HashMap::from([("task_queue".to_string(), "x".to_string())]))
+            .build()
+            .unwrap(),
+    );
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn otlp_http_exports_spans_to_collector() {
+    let listener = TcpListener::bind(ANY_PORT).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let telem = telemetry_init(
+        TelemetryOptionsBuilder::default()
+            .tracing(
+                OtelTracingOptionsBuilder::default()
+                    .url(format!("http://{addr}").parse().unwrap())
+                    .protocol(OtlpProtocol::Http)
+                    .filter("info")
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+    tracing::subscriber::with_default(telem.trace_subscriber().unwrap(), || {
+        tracing::info_span!("exported_span").in_scope(|| {});
+    });
+    // Spans are batched, and flushed once telemetry is dropped
+    let dropper = tokio::task::spawn_blocking(move || drop(telem));
+
+    let (mut conn, _) = tokio::time::timeout(Duration::from_secs(10), listener.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    let mut request = vec![];
+    let mut buf = [0; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = conn.read(&mut buf).await.unwrap();
+        assert_ne!(n, 0, "Connection closed before request headers were sent");
+        request.extend_from_slice(&buf[..n]);
+    }
+    conn.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+        .await
+        .unwrap();
+    dropper.await.unwrap();
+    let request = String::from_utf8_lossy(&request).to_lowercase();
+    assert!(request.starts_with("post /v1/traces "));
+    assert!(request.contains("content-type: application/x-protobuf"));
+}
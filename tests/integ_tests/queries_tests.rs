@@ -29,11 +29,13 @@ async fn simple_query_legacy() {
             StartTimer {
                 seq: 0,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(500))),
+                ..Default::default()
             }
             .into(),
             StartTimer {
                 seq: 1,
                 start_to_fire_timeout: Some(prost_dur!(from_secs(3))),
+                ..Default::default()
             }
             .into(),
         ],
@@ -223,11 +225,13 @@ async fn fail_legacy_query() {
         StartTimer {
             seq: 1,
             start_to_fire_timeout: Some(prost_dur!(from_millis(500))),
+            ..Default::default()
         }
         .into(),
         StartTimer {
             seq: 2,
             start_to_fire_timeout: Some(prost_dur!(from_secs(3))),
+            ..Default::default()
         }
         .into(),
     ];
@@ -6,7 +6,10 @@ use std::{
     sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     time::Duration,
 };
-use temporal_client::WorkflowClientTrait;
+use temporal_client::{
+    execute_update_with_start, UpdateWaitStage, UpdateWithStartWorkflow, WorkflowClientTrait,
+    WorkflowOptions, WorkflowUpdate, WorkflowUpdateOutcome,
+};
 use temporal_sdk::{ActContext, ActivityOptions, LocalActivityOptions, UpdateContext, WfContext};
 use temporal_sdk_core::replay::HistoryForReplay;
 use temporal_sdk_core_api::Worker;
@@ -23,7 +26,7 @@ use temporal_sdk_core_protos::{
         ActivityTaskCompletion, AsJsonPayloadExt, IntoPayloadsExt,
     },
     temporal::api::{
-        enums::v1::{EventType, UpdateWorkflowExecutionLifecycleStage},
+        enums::v1::{EventType, UpdateWorkflowExecutionLifecycleStage, WorkflowIdConflictPolicy},
         update,
         update::v1::WaitPolicy,
     },
@@ -482,6 +485,121 @@ async fn update_speculative_wft() {
     join!(sender_task, processing_task);
 }
 
+// The speculative task which rejected the update is dropped by the server, along with the lang
+// flag its completion recorded. The flag must be recorded again with the next task, or the
+// history replays differently than it first ran.
+#[tokio::test]
+async fn rejected_update_replays_cleanly() {
+    let mut starter = init_core_and_create_wf("rejected_update_replays_cleanly").await;
+    let core = starter.get_worker().await;
+    let client = starter.get_client().await;
+    let workflow_id = starter.get_task_queue().to_string();
+
+    let barr = Barrier::new(2);
+    let sender_task = async {
+        barr.wait().await;
+        client
+            .update_workflow_execution(
+                workflow_id.to_string(),
+                "".to_string(),
+                "rejected_update".to_string(),
+                WaitPolicy {
+                    lifecycle_stage: UpdateWorkflowExecutionLifecycleStage::Completed as i32,
+                },
+                Some("hi".into()),
+            )
+            .await
+            .unwrap();
+        barr.wait().await;
+        client
+            .signal_workflow_execution(
+                workflow_id.to_string(),
+                "".to_string(),
+                "hi".into(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+    };
+
+    let processing_task = async {
+        let res = core.poll_workflow_activation().await.unwrap();
+        core.complete_workflow_activation(WorkflowActivationCompletion::empty(res.run_id))
+            .await
+            .unwrap();
+        barr.wait().await;
+
+        let res = core.poll_workflow_activation().await.unwrap();
+        let pid = assert_matches!(
+            res.jobs.as_slice(),
+            [WorkflowActivationJob {
+                variant: Some(workflow_activation_job::Variant::DoUpdate(d)),
+            }] => &d.protocol_instance_id
+        );
+        let mut completion = WorkflowActivationCompletion::from_cmd(
+            res.run_id,
+            UpdateResponse {
+                protocol_instance_id: pid.to_string(),
+                response: Some(update_response::Response::Rejected("nope!".into())),
+            }
+            .into(),
+        );
+        completion.add_internal_flags(1);
+        core.complete_workflow_activation(completion).await.unwrap();
+        barr.wait().await;
+
+        let res = core.poll_workflow_activation().await.unwrap();
+        assert!(res.available_internal_flags.is_empty());
+        let mut completion = WorkflowActivationCompletion::from_cmd(
+            res.run_id,
+            CompleteWorkflowExecution { result: None }.into(),
+        );
+        completion.add_internal_flags(1);
+        core.complete_workflow_activation(completion).await.unwrap();
+    };
+    join!(sender_task, processing_task);
+
+    let history = client
+        .get_workflow_execution_history(workflow_id.clone(), None, vec![])
+        .await
+        .unwrap()
+        .history
+        .unwrap();
+    let replay_worker = init_core_replay_preloaded(
+        &workflow_id,
+        [HistoryForReplay::new(history, workflow_id.clone())],
+    );
+    let res = replay_worker.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        res.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::StartWorkflow(_)),
+        }]
+    );
+    replay_worker
+        .complete_workflow_activation(WorkflowActivationCompletion::empty(res.run_id))
+        .await
+        .unwrap();
+    let res = replay_worker.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        res.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::SignalWorkflow(_)),
+        }]
+    );
+    assert_eq!(res.available_internal_flags, [1]);
+    let mut completion = WorkflowActivationCompletion::from_cmd(
+        res.run_id,
+        CompleteWorkflowExecution { result: None }.into(),
+    );
+    completion.add_internal_flags(1);
+    replay_worker
+        .complete_workflow_activation(completion)
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn update_with_local_acts() {
     let wf_name = "update_with_local_acts";
@@ -904,3 +1022,53 @@ async fn worker_restarted_in_middle_of_update() {
         .await
         .unwrap();
 }
+
+#[tokio::test]
+async fn update_with_start() {
+    let wf_name = "update_with_start";
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.worker_config.no_remote_activities(true);
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name.to_owned(), |ctx: WfContext| async move {
+        ctx.update_handler(
+            "update",
+            |_: &_, _: ()| Ok(()),
+            move |_: UpdateContext, _: ()| async { Ok("done") },
+        );
+        ctx.timer(Duration::from_secs(1)).await;
+        Ok(().into())
+    });
+
+    let wf_id = starter.get_task_queue().to_string();
+    worker.expect_workflow_completion(&wf_id, None);
+    let start = UpdateWithStartWorkflow {
+        input: vec![],
+        task_queue: starter.get_task_queue().to_string(),
+        workflow_type: wf_name.to_string(),
+        request_id: None,
+        options: WorkflowOptions {
+            id_conflict_policy: WorkflowIdConflictPolicy::Fail,
+            ..Default::default()
+        },
+    };
+    let mut update = WorkflowUpdate::new(&wf_id, "update");
+    update.args = [().as_json_payload().unwrap()].into_payloads();
+    let update = async {
+        let outcome = execute_update_with_start(
+            client.as_ref(),
+            start,
+            update,
+            UpdateWaitStage::Completed,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(outcome.started);
+        assert_matches!(outcome.update, WorkflowUpdateOutcome::Completed(Some(_)));
+    };
+    let run = async {
+        worker.run_until_done().await.unwrap();
+    };
+    join!(update, run);
+}
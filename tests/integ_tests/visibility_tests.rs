@@ -1,8 +1,8 @@
 use assert_matches::assert_matches;
 use std::{sync::Arc, time::Duration};
 use temporal_client::{
-    ListClosedFilters, ListOpenFilters, Namespace, RegisterNamespaceOptions, StartTimeFilter,
-    WorkflowClientTrait, WorkflowExecutionFilter,
+    ListClosedFilters, ListOpenFilters, Namespace, NamespaceAdminExt, RegisterNamespaceOptions,
+    StartTimeFilter, UpdateNamespaceOptions, WorkflowClientTrait, WorkflowExecutionFilter,
 };
 use temporal_sdk_core_protos::coresdk::workflow_activation::{
     workflow_activation_job, WorkflowActivationJob,
@@ -89,12 +89,10 @@ async fn client_list_open_closed_workflow_executions() {
 
 #[tokio::test]
 async fn client_create_namespace() {
-    let client = Arc::new(
-        get_integ_server_options()
-            .connect(NAMESPACE.to_owned(), None)
-            .await
-            .expect("Must connect"),
-    );
+    let client = get_integ_server_options()
+        .connect(NAMESPACE.to_owned(), None)
+        .await
+        .expect("Must connect");
 
     let register_options = RegisterNamespaceOptions::builder()
         .namespace("test-create-namespace")
@@ -102,36 +100,25 @@ async fn client_create_namespace() {
         .build()
         .unwrap();
 
-    client
-        .register_namespace(register_options.clone())
+    let described = client
+        .register_namespace_and_wait(register_options.clone(), Duration::from_secs(12))
         .await
         .unwrap();
+    let namespace_info = described.namespace_info.unwrap();
+    assert_eq!(namespace_info.name, register_options.namespace);
+    assert_eq!(namespace_info.description, register_options.description);
 
-    //#Hack, not sure how else to wait for a proper response.  RegisterNamespace isn't safe to read
-    //after write
-    let mut attempts = 0;
-    let wait_time = Duration::from_secs(1);
-    loop {
-        attempts += 1;
-        let resp = client
-            .describe_namespace(Namespace::Name(register_options.namespace.clone()))
-            .await;
-
-        match resp {
-            Ok(n) => {
-                let namespace_info = n.namespace_info.unwrap();
-                assert_eq!(namespace_info.name, register_options.namespace);
-                assert_eq!(namespace_info.description, register_options.description);
-                return;
-            }
-            _ => {
-                if attempts == 12 {
-                    panic!("failed to query registered namespace");
-                }
-                sleep(wait_time).await
-            }
-        }
-    }
+    let updated = client
+        .update_namespace(
+            UpdateNamespaceOptions::builder()
+                .namespace(register_options.namespace.clone())
+                .description("still alive")
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.namespace_info.unwrap().description, "still alive");
 }
 
 #[tokio::test]
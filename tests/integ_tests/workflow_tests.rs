@@ -241,6 +241,7 @@ async fn fail_wf_task(#[values(true, false)] replay: bool) {
         vec![StartTimer {
             seq: 0,
             start_to_fire_timeout: Some(prost_dur!(from_millis(200))),
+            ..Default::default()
         }
         .into()],
     ))
@@ -365,6 +366,7 @@ async fn signal_workflow_signal_not_handled_on_workflow_completion() {
             vec![StartTimer {
                 seq: 0,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(10))),
+                ..Default::default()
             }
             .into()],
         ))
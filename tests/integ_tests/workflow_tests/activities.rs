@@ -360,6 +360,7 @@ async fn activity_cancellation_try_cancel() {
             StartTimer {
                 seq: 1,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(50))),
+                ..Default::default()
             }
             .into(),
         ]
@@ -417,6 +418,7 @@ async fn activity_cancellation_plus_complete_doesnt_double_resolve() {
             StartTimer {
                 seq: 1,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(50))),
+                ..Default::default()
             }
             .into(),
         ]
@@ -461,6 +463,7 @@ async fn activity_cancellation_plus_complete_doesnt_double_resolve() {
         vec![StartTimer {
             seq: 2,
             start_to_fire_timeout: Some(prost_dur!(from_millis(100))),
+            ..Default::default()
         }
         .into()],
     ))
@@ -559,6 +562,7 @@ async fn activity_cancellation_wait_cancellation_completed() {
             StartTimer {
                 seq: 1,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(50))),
+                ..Default::default()
             }
             .into(),
         ]
@@ -621,6 +625,7 @@ async fn activity_cancellation_abandon() {
             StartTimer {
                 seq: 1,
                 start_to_fire_timeout: Some(prost_dur!(from_millis(50))),
+                ..Default::default()
             }
             .into(),
         ]
@@ -761,6 +766,7 @@ async fn activity_cancelled_after_heartbeat_times_out() {
     core.record_activity_heartbeat(ActivityHeartbeat {
         task_token: task.task_token.clone(),
         details: vec![],
+        throttle_interval: None,
     });
 
     // Verify activity got cancelled
@@ -2,7 +2,7 @@ use assert_matches::assert_matches;
 use std::time::Duration;
 use temporal_client::{WfClientExt, WorkflowExecutionResult, WorkflowOptions};
 use temporal_sdk::{ActContext, ActivityOptions, WfContext, WorkflowResult};
-use temporal_sdk_core_protos::coresdk::AsJsonPayloadExt;
+use temporal_sdk_core_protos::{coresdk::AsJsonPayloadExt, temporal::api::common::v1::Payload};
 use temporal_sdk_core_test_utils::CoreWfStarter;
 
 const TEST_APPDATA_MESSAGE: &str = "custom app data, yay";
@@ -52,9 +52,8 @@ async fn appdata_access_in_activities_and_workflows() {
         .await
         .unwrap();
     worker.run_until_done().await.unwrap();
-    let handle = client.get_untyped_workflow_handle(wf_name, run_id);
-    let res = handle
-        .get_workflow_result(Default::default())
+    let res = client
+        .get_workflow_result::<Vec<Payload>>(wf_name, run_id, Default::default())
         .await
         .unwrap();
     assert_matches!(res, WorkflowExecutionResult::Succeeded(_));
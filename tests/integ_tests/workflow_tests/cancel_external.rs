@@ -4,17 +4,21 @@ use temporal_sdk_core_protos::coresdk::common::NamespacedWorkflowExecution;
 use temporal_sdk_core_test_utils::CoreWfStarter;
 
 const RECEIVER_WFID: &str = "sends-cancel-receiver";
+const CANCEL_REASON: &str = "Sender is done with it";
 
 async fn cancel_sender(ctx: WfContext) -> WorkflowResult<()> {
     let run_id = std::str::from_utf8(&ctx.get_args()[0].data)
         .unwrap()
         .to_owned();
     let sigres = ctx
-        .cancel_external(NamespacedWorkflowExecution {
-            workflow_id: RECEIVER_WFID.to_string(),
-            run_id,
-            namespace: ctx.namespace().to_string(),
-        })
+        .cancel_external_with_reason(
+            NamespacedWorkflowExecution {
+                workflow_id: RECEIVER_WFID.to_string(),
+                run_id,
+                namespace: ctx.namespace().to_string(),
+            },
+            CANCEL_REASON.to_string(),
+        )
         .await;
     if ctx.get_args().get(1).is_some() {
         // We expect failure
@@ -27,6 +31,7 @@ async fn cancel_sender(ctx: WfContext) -> WorkflowResult<()> {
 
 async fn cancel_receiver(ctx: WfContext) -> WorkflowResult<()> {
     ctx.cancelled().await;
+    assert_eq!(ctx.cancelled_reason().as_deref(), Some(CANCEL_REASON));
     Ok(().into())
 }
 
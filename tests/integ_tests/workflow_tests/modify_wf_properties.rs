@@ -12,6 +12,7 @@ async fn memo_upserter(ctx: WfContext) -> WorkflowResult<()> {
         (FIELD_A.to_string(), "enchi".as_json_payload().unwrap()),
         (FIELD_B.to_string(), 9001.as_json_payload().unwrap()),
     ]);
+    assert_eq!(ctx.memo().len(), 2);
     Ok(().into())
 }
 
@@ -1,11 +1,17 @@
 use std::collections::HashMap;
 
 use futures::StreamExt;
-use temporal_client::{SignalWithStartOptions, WorkflowClientTrait, WorkflowOptions};
+use temporal_client::{
+    tonic::Code, SignalWithStartOptions, WfClientExt, WorkflowClientTrait, WorkflowOptions,
+    WorkflowStartOutcome,
+};
 use temporal_sdk::{
     ChildWorkflowOptions, Signal, SignalWorkflowOptions, WfContext, WorkflowResult,
 };
-use temporal_sdk_core_protos::{coresdk::IntoPayloadsExt, temporal::api::common::v1::Payload};
+use temporal_sdk_core_protos::{
+    coresdk::IntoPayloadsExt,
+    temporal::api::{common::v1::Payload, enums::v1::WorkflowIdConflictPolicy},
+};
 use temporal_sdk_core_test_utils::CoreWfStarter;
 use uuid::Uuid;
 
@@ -125,6 +131,94 @@ async fn sends_signal_with_create_wf() {
     worker.run_until_done().await.unwrap();
 }
 
+#[tokio::test]
+async fn signal_with_start_returns_handle_to_signalled_run() {
+    let wf_id = "signal_with_start_returns_handle_to_signalled_run";
+    let mut starter = CoreWfStarter::new(wf_id);
+    starter.no_remote_activities();
+    let mut worker = starter.worker().await;
+    worker.register_wf("receiver_signal", signal_with_create_wf_receiver);
+
+    let client = starter.get_client().await;
+    let mut header: HashMap<String, Payload> = HashMap::new();
+    header.insert("tupac".into(), "shakur".into());
+    let options = SignalWithStartOptions::builder()
+        .task_queue(worker.inner_mut().task_queue())
+        .workflow_id(wf_id)
+        .workflow_type("receiver_signal")
+        .signal_name(SIGNAME)
+        .signal_input(vec![b"tada".into()].into_payloads())
+        .signal_header(header.into())
+        .build()
+        .unwrap();
+    let handle = client
+        .signal_with_start_workflow(options, WorkflowOptions::default())
+        .await
+        .unwrap()
+        .into_handle();
+    assert_eq!(handle.info().workflow_id, wf_id);
+
+    worker.expect_workflow_completion(wf_id, handle.info().run_id.clone());
+    worker.run_until_done().await.unwrap();
+}
+
+#[tokio::test]
+async fn signal_with_start_conflict_policies() {
+    let wf_id = "signal_with_start_conflict_policies";
+    let mut starter = CoreWfStarter::new(wf_id);
+    let client = starter.get_client().await;
+    let options = || {
+        SignalWithStartOptions::builder()
+            .task_queue(starter.get_task_queue())
+            .workflow_id(wf_id)
+            .workflow_type("receiver_signal")
+            .signal_name(SIGNAME)
+            .build()
+            .unwrap()
+    };
+    let with_policy = |id_conflict_policy| WorkflowOptions {
+        id_conflict_policy,
+        ..Default::default()
+    };
+
+    let first = client
+        .signal_with_start_workflow(options(), WorkflowOptions::default())
+        .await
+        .unwrap();
+    assert!(matches!(first, WorkflowStartOutcome::Started(_)));
+    let first = first.into_handle();
+    // The running workflow is signalled rather than started again
+    let signalled = client
+        .signal_with_start_workflow(
+            options(),
+            with_policy(WorkflowIdConflictPolicy::UseExisting),
+        )
+        .await
+        .unwrap();
+    let WorkflowStartOutcome::AttachedToRunning(signalled) = signalled else {
+        panic!("The running workflow should have been signalled");
+    };
+    assert_eq!(signalled.info().run_id, first.info().run_id);
+    let res = client
+        .signal_with_start_workflow(options(), with_policy(WorkflowIdConflictPolicy::Fail))
+        .await;
+    assert!(matches!(res, Err(e) if e.code() == Code::InvalidArgument));
+    let replaced = client
+        .signal_with_start_workflow(
+            options(),
+            with_policy(WorkflowIdConflictPolicy::TerminateExisting),
+        )
+        .await
+        .unwrap()
+        .into_handle();
+    assert_ne!(replaced.info().run_id, first.info().run_id);
+
+    client
+        .terminate_workflow_execution(wf_id.to_string(), None)
+        .await
+        .unwrap();
+}
+
 async fn signals_child(ctx: WfContext) -> WorkflowResult<()> {
     let started_child = ctx
         .child_workflow(ChildWorkflowOptions {
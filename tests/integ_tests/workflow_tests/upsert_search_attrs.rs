@@ -1,7 +1,12 @@
 use std::{collections::HashMap, env};
 use temporal_client::{WorkflowClientTrait, WorkflowOptions};
 use temporal_sdk::{WfContext, WorkflowResult};
-use temporal_sdk_core_protos::coresdk::{AsJsonPayloadExt, FromJsonPayloadExt};
+use temporal_sdk_core_protos::{
+    coresdk::{AsJsonPayloadExt, FromJsonPayloadExt},
+    search_attributes::{
+        SearchAttributeError, SearchAttributeSchema, SearchAttributeValue, TypedSearchAttributes,
+    },
+};
 use temporal_sdk_core_test_utils::{CoreWfStarter, INTEG_TEMPORAL_DEV_SERVER_USED_ENV_VAR};
 use tracing::warn;
 use uuid::Uuid;
@@ -72,3 +77,106 @@ async fn sends_upsert() {
     );
     assert_eq!(98, usize::from_json_payload(int_attr_payload).unwrap());
 }
+
+async fn typed_search_attr_updater(ctx: WfContext) -> WorkflowResult<()> {
+    let mut attrs = TypedSearchAttributes::new();
+    attrs
+        .insert(TXT_ATTR, SearchAttributeValue::Text("goodbye".to_string()))?
+        .insert(INT_ATTR, 98)?;
+    ctx.upsert_typed_search_attributes(&attrs)?;
+    Ok(().into())
+}
+
+#[tokio::test]
+async fn sends_typed_upsert() {
+    let wf_name = "sends_typed_upsert_search_attrs";
+    let wf_id = Uuid::new_v4();
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.no_remote_activities();
+    let mut worker = starter.worker().await;
+    if env::var(INTEG_TEMPORAL_DEV_SERVER_USED_ENV_VAR).is_ok() {
+        warn!("skipping sends_typed_upsert -- does not work on temporal dev server");
+        return;
+    }
+
+    worker.register_wf(wf_name, typed_search_attr_updater);
+    let mut initial = TypedSearchAttributes::new();
+    initial
+        .insert(TXT_ATTR, SearchAttributeValue::Text("hello".to_string()))
+        .unwrap()
+        .insert(INT_ATTR, 1)
+        .unwrap();
+    let run_id = worker
+        .submit_wf(
+            wf_id.to_string(),
+            wf_name,
+            vec![],
+            WorkflowOptions {
+                search_attributes: Some(initial.to_payloads().unwrap()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    worker.run_until_done().await.unwrap();
+
+    let typed = starter
+        .get_client()
+        .await
+        .describe_workflow_execution(wf_id.to_string(), Some(run_id))
+        .await
+        .unwrap()
+        .workflow_execution_info
+        .unwrap()
+        .typed_search_attributes()
+        .unwrap();
+    assert_eq!(
+        typed.get(TXT_ATTR),
+        Some(&SearchAttributeValue::Text("goodbye".to_string()))
+    );
+    assert_eq!(typed.get(INT_ATTR), Some(&SearchAttributeValue::Int(98)));
+}
+
+#[tokio::test]
+async fn validates_upsert_against_server_schema() {
+    let wf_name = "validates_upsert_search_attrs";
+    let wf_id = Uuid::new_v4();
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.no_remote_activities();
+    let mut worker = starter.worker().await;
+    let schema: SearchAttributeSchema = starter
+        .get_client()
+        .await
+        .get_search_attributes()
+        .await
+        .unwrap()
+        .into();
+
+    worker.register_wf(wf_name, move |ctx: WfContext| {
+        let schema = schema.clone();
+        async move {
+            let mut attrs = TypedSearchAttributes::new();
+            attrs
+                .insert(INT_ATTR, "not an int")?
+                .insert("NotRegisteredField", 1)?;
+            let err = ctx
+                .upsert_validated_search_attributes(&attrs, &schema)
+                .unwrap_err();
+            assert_eq!(err.errors.len(), 2);
+            assert!(matches!(
+                err.errors[0],
+                SearchAttributeError::TypeMismatch { .. }
+            ));
+
+            let mut attrs = TypedSearchAttributes::new();
+            attrs.insert(INT_ATTR, 98)?;
+            ctx.upsert_validated_search_attributes(&attrs, &schema)?;
+            Ok(().into())
+        }
+    });
+    worker
+        .submit_wf(wf_id.to_string(), wf_name, vec![], Default::default())
+        .await
+        .unwrap();
+    worker.run_until_done().await.unwrap();
+}